@@ -4,8 +4,24 @@
 //! Extends contributor ranking with multilingual metrics,
 //! cross-language expertise tracking, and language-aware scoring.
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use crate::metrics::MetricSummary;
+use crate::domain_taxonomy::DomainTaxonomy;
+use crate::error::{SerenQaError, SerenQaResult};
+use crate::language_tag::is_valid_bcp47;
+use crate::language_registry::LanguageRegistry;
+use crate::replay::ReplayReport;
+use crate::submission_guard::SubmissionGuard;
+use crate::decay::{DecayModel, TraceActivity};
+use crate::attribution::{CreditSplit, TeamStats};
+use crate::elo::DEFAULT_ELO_RATING;
+use crate::achievements::Badge;
+use crate::season::{LeaderboardSeason, SeasonTrophy};
+use crate::outcome::OutcomeKind;
+use crate::proficiency::{LanguageProficiency, ProficiencyReport, DEFAULT_PROFICIENCY_ALPHA};
+use crate::leaderboard_hooks::LeaderboardObservers;
 
 /// Language-aware contributor statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,9 +44,13 @@ pub struct LanguageAwareContributorStats {
     /// Languages used
     pub languages_used: Vec<String>,
     
-    /// Language proficiency scores
+    /// Language proficiency scores (the proficiency model's current estimates)
     pub language_proficiency: HashMap<String, f64>,
     
+    /// Proficiency model state per language
+    #[serde(default)]
+    pub proficiency_models: BTreeMap<String, LanguageProficiency>,
+    
     /// Cross-language expertise (ability to work across languages)
     pub cross_language_expertise: f64,
     
@@ -48,6 +68,62 @@ pub struct LanguageAwareContributorStats {
     
     /// Expertise domains
     pub expertise_domains: Vec<String>,
+    
+    /// Domain of each discovery (discovery name -> domain)
+    #[serde(default)]
+    pub discovery_domains: HashMap<String, String>,
+    
+    /// When the earliest trace was made
+    #[serde(default)]
+    pub first_active: Option<DateTime<Utc>>,
+    
+    /// When the most recent trace was made
+    #[serde(default)]
+    pub last_active: Option<DateTime<Utc>>,
+    
+    /// Custom metric means across traces (metric name -> summary)
+    #[serde(default)]
+    pub custom_metrics: BTreeMap<String, MetricSummary>,
+    
+    /// Traces checked with the replay engine
+    #[serde(default)]
+    pub replayed_traces: usize,
+    
+    /// Replayed traces that reproduced within tolerance
+    #[serde(default)]
+    pub reproducible_traces: usize,
+    
+    /// Timestamp and scores of every trace, for time-decayed ranking
+    #[serde(default)]
+    pub trace_history: Vec<TraceActivity>,
+    
+    /// Traces credited jointly with other contributors
+    #[serde(default)]
+    pub team_traces: usize,
+    
+    /// Sum of this contributor's credit shares in team traces
+    #[serde(default)]
+    pub team_credit: f64,
+    
+    /// Elo rating from judged discovery comparisons (`None` if never judged)
+    #[serde(default)]
+    pub elo_rating: Option<f64>,
+    
+    /// Achievement badges earned, in award order
+    #[serde(default)]
+    pub badges: Vec<Badge>,
+    
+    /// Season trophies won, in award order
+    #[serde(default)]
+    pub trophies: Vec<SeasonTrophy>,
+    
+    /// Current outcome of each discovery that has one (discovery name -> outcome)
+    #[serde(default)]
+    pub discovery_outcomes: BTreeMap<String, OutcomeKind>,
+    
+    /// Citations of the papers reporting the contributor's discoveries
+    #[serde(default)]
+    pub citations: u64,
 }
 
 impl LanguageAwareContributorStats {
@@ -61,16 +137,33 @@ impl LanguageAwareContributorStats {
             avg_serendipity: 0.0,
             languages_used: Vec::new(),
             language_proficiency: HashMap::new(),
+            proficiency_models: BTreeMap::new(),
             cross_language_expertise: 0.0,
             multilingual_traces: 0,
             avg_alignment_score: 0.0,
             avg_translation_quality: 0.0,
             discoveries: Vec::new(),
             expertise_domains: Vec::new(),
+            discovery_domains: HashMap::new(),
+            first_active: None,
+            last_active: None,
+            custom_metrics: BTreeMap::new(),
+            replayed_traces: 0,
+            reproducible_traces: 0,
+            trace_history: Vec::new(),
+            team_traces: 0,
+            team_credit: 0.0,
+            elo_rating: None,
+            badges: Vec::new(),
+            trophies: Vec::new(),
+            discovery_outcomes: BTreeMap::new(),
+            citations: 0,
         }
     }
 
-    /// Add a trace to statistics
+    /// Add a trace to statistics.
+    /// Scores must lie in [0, 1] and languages must be BCP-47 tags;
+    /// languages are canonicalized so spellings of one language count once.
     pub fn add_trace(
         &mut self,
         depth: usize,
@@ -79,8 +172,37 @@ impl LanguageAwareContributorStats {
         languages: Vec<String>,
         alignment_score: f64,
         translation_quality: f64,
-    ) {
+    ) -> SerenQaResult<()> {
+        self.add_trace_at(Utc::now(), depth, uniqueness, serendipity, languages, alignment_score, translation_quality)
+    }
+
+    /// Add a trace made at `at` (e.g., the trace's creation time) to statistics
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_trace_at(
+        &mut self,
+        at: DateTime<Utc>,
+        depth: usize,
+        uniqueness: f64,
+        serendipity: f64,
+        languages: Vec<String>,
+        alignment_score: f64,
+        translation_quality: f64,
+    ) -> SerenQaResult<()> {
+        SerenQaError::check_unit_range("uniqueness", uniqueness)?;
+        SerenQaError::check_unit_range("serendipity", serendipity)?;
+        SerenQaError::check_unit_range("alignment_score", alignment_score)?;
+        SerenQaError::check_unit_range("translation_quality", translation_quality)?;
+        let mut languages: Vec<String> = languages.iter().map(|l| LanguageRegistry::global().canonical(l)).collect();
+        let mut seen = std::collections::HashSet::new();
+        languages.retain(|l| seen.insert(l.clone()));
+        if let Some(lang) = languages.iter().find(|l| !is_valid_bcp47(l)) {
+            return Err(SerenQaError::UnknownLanguage(lang.clone()));
+        }
+
         // Update basic stats
+        self.first_active = Some(self.first_active.map_or(at, |first| first.min(at)));
+        self.last_active = Some(self.last_active.map_or(at, |last| last.max(at)));
+        self.trace_history.push(TraceActivity { at, uniqueness, serendipity });
         self.total_traces += 1;
         self.avg_trace_depth = (self.avg_trace_depth * (self.total_traces - 1) as f64 + depth as f64)
             / self.total_traces as f64;
@@ -100,9 +222,9 @@ impl LanguageAwareContributorStats {
             }
             
             // Update language proficiency
-            let current_prof = self.language_proficiency.get(lang).unwrap_or(&0.0);
-            let new_prof = (current_prof + uniqueness) / 2.0;
-            self.language_proficiency.insert(lang.clone(), new_prof);
+            let model = self.proficiency_models.entry(lang.clone()).or_default();
+            model.update(alignment_score, translation_quality, DEFAULT_PROFICIENCY_ALPHA);
+            self.language_proficiency.insert(lang.clone(), model.proficiency());
         }
         
         // Update cross-language expertise
@@ -114,6 +236,12 @@ impl LanguageAwareContributorStats {
             / self.total_traces as f64;
         self.avg_translation_quality = (self.avg_translation_quality * (self.total_traces - 1) as f64 + translation_quality)
             / self.total_traces as f64;
+        Ok(())
+    }
+
+    /// Proficiency in every language used, with confidence intervals
+    pub fn proficiency_report(&self) -> ProficiencyReport {
+        ProficiencyReport::new(&self.contributor_id, &self.proficiency_models)
     }
 
     /// Add a discovery
@@ -130,27 +258,222 @@ impl LanguageAwareContributorStats {
         }
     }
 
+    /// Add expertise domain, canonicalized against the taxonomy when known
+    pub fn add_expertise_domain_in(&mut self, domain: &str, taxonomy: &DomainTaxonomy) {
+        let domain = taxonomy.canonicalize(domain).unwrap_or_else(|| domain.to_string());
+        self.add_expertise_domain(&domain);
+    }
+
+    /// Add a discovery classified under a domain
+    pub fn add_discovery_in(&mut self, discovery_name: &str, domain: &str, taxonomy: &DomainTaxonomy) {
+        self.add_discovery(discovery_name);
+        let domain = taxonomy.canonicalize(domain).unwrap_or_else(|| domain.to_string());
+        self.discovery_domains.insert(discovery_name.to_string(), domain);
+    }
+
+    /// Expertise domains rolled up to every ancestor in the taxonomy
+    pub fn expertise_rollup(&self, taxonomy: &DomainTaxonomy) -> BTreeMap<String, usize> {
+        taxonomy.rollup(&self.expertise_domains)
+    }
+
+    /// Discoveries per domain, rolled up to every ancestor in the taxonomy
+    pub fn discovery_rollup(&self, taxonomy: &DomainTaxonomy) -> BTreeMap<String, usize> {
+        taxonomy.rollup(self.discovery_domains.values())
+    }
+
+    /// Check if the contributor was active at some point in [from, to]
+    pub fn active_between(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> bool {
+        let (Some(first), Some(last)) = (self.first_active, self.last_active) else {
+            return from.is_none() && to.is_none();
+        };
+        from.is_none_or(|from| last >= from) && to.is_none_or(|to| first <= to)
+    }
+
+    /// Check if any expertise or discovery domain falls under `domain`
+    pub fn in_domain(&self, domain: &str, taxonomy: &DomainTaxonomy) -> bool {
+        self.expertise_domains
+            .iter()
+            .chain(self.discovery_domains.values())
+            .any(|d| taxonomy.is_within(d, domain) || d.eq_ignore_ascii_case(domain))
+    }
+
+    /// Fold a finalized trace's custom metrics into the per-contributor means
+    pub fn record_custom_metrics(&mut self, metrics: &BTreeMap<String, f64>) {
+        for (name, value) in metrics {
+            self.custom_metrics.entry(name.clone()).or_default().record(*value);
+        }
+    }
+
+    /// Mean of a custom metric across traces that reported it
+    pub fn custom_metric(&self, name: &str) -> Option<f64> {
+        self.custom_metrics.get(name).map(|summary| summary.mean)
+    }
+
+    /// Record the outcome of replaying one of the contributor's traces
+    pub fn record_replay(&mut self, report: &ReplayReport) {
+        self.replayed_traces += 1;
+        if report.is_reproducible() {
+            self.reproducible_traces += 1;
+        }
+    }
+
+    /// Fraction of replayed traces that reproduced (0 if none were replayed)
+    pub fn reproducibility(&self) -> f64 {
+        if self.replayed_traces == 0 {
+            0.0
+        } else {
+            self.reproducible_traces as f64 / self.replayed_traces as f64
+        }
+    }
+
+    /// Fractional trace credit: 1 per solo trace plus the share of each team trace
+    pub fn credit(&self) -> f64 {
+        self.total_traces.saturating_sub(self.team_traces) as f64 + self.team_credit
+    }
+
     /// Calculate overall score
     pub fn overall_score(&self) -> f64 {
-        let depth_score = (self.avg_trace_depth / 50.0).min(1.0);
+        self.score_with(&ScoringConfig::default()).value
+    }
+
+    /// Calculate overall score under a specific scoring config
+    pub fn score_with(&self, config: &ScoringConfig) -> VersionedScore {
+        let depth_score = (self.avg_trace_depth / config.depth_normalizer).min(1.0);
         let uniqueness_score = self.avg_uniqueness;
         let serendipity_score = self.avg_serendipity;
         let language_score = self.cross_language_expertise;
         let quality_score = (self.avg_alignment_score + self.avg_translation_quality) / 2.0;
-        let discovery_score = (self.discoveries.len() as f64 / 10.0).min(1.0);
+        let discovery_score = (self.discoveries.len() as f64 / config.discovery_normalizer).min(1.0);
         
         // Weighted combination
-        0.20 * depth_score +
-        0.25 * uniqueness_score +
-        0.20 * serendipity_score +
-        0.15 * language_score +
-        0.10 * quality_score +
-        0.10 * discovery_score
+        let value = config.depth_weight * depth_score +
+            config.uniqueness_weight * uniqueness_score +
+            config.serendipity_weight * serendipity_score +
+            config.language_weight * language_score +
+            config.quality_weight * quality_score +
+            config.discovery_weight * discovery_score;
+        
+        VersionedScore {
+            value,
+            config_version: config.version.clone(),
+        }
     }
 }
 
+/// Version of the built-in scoring formula
+pub const DEFAULT_SCORING_VERSION: &str = "v1";
+
+/// Tolerance when checking that scoring weights sum to 1
+pub const WEIGHT_SUM_TOLERANCE: f64 = 1e-6;
+
+/// Scoring formula configuration for `overall_score`.
+/// Fields missing from a loaded config take their default values.
+/// The weights are kept here rather than in a separate weights type, so the
+/// `version` stamped on every `VersionedScore` covers them and recalibration
+/// compares one whole config with another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    /// Version tag stored on every score computed with this config
+    pub version: String,
+    /// Trace depth at which the depth component saturates
+    pub depth_normalizer: f64,
+    /// Discovery count at which the discovery component saturates
+    pub discovery_normalizer: f64,
+    /// Weight of average trace depth
+    pub depth_weight: f64,
+    /// Weight of average uniqueness
+    pub uniqueness_weight: f64,
+    /// Weight of average serendipity
+    pub serendipity_weight: f64,
+    /// Weight of cross-language expertise
+    pub language_weight: f64,
+    /// Weight of alignment/translation quality
+    pub quality_weight: f64,
+    /// Weight of discoveries
+    pub discovery_weight: f64,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            version: DEFAULT_SCORING_VERSION.to_string(),
+            depth_normalizer: 50.0,
+            discovery_normalizer: 10.0,
+            depth_weight: 0.20,
+            uniqueness_weight: 0.25,
+            serendipity_weight: 0.20,
+            language_weight: 0.15,
+            quality_weight: 0.10,
+            discovery_weight: 0.10,
+        }
+    }
+}
+
+impl ScoringConfig {
+    /// Component weights in formula order
+    fn weights(&self) -> [(&'static str, f64); 6] {
+        [
+            ("depth_weight", self.depth_weight),
+            ("uniqueness_weight", self.uniqueness_weight),
+            ("serendipity_weight", self.serendipity_weight),
+            ("language_weight", self.language_weight),
+            ("quality_weight", self.quality_weight),
+            ("discovery_weight", self.discovery_weight),
+        ]
+    }
+
+    /// Check weights are non-negative and sum to 1, and normalizers are positive
+    pub fn validate(&self) -> SerenQaResult<()> {
+        for (name, weight) in self.weights() {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(SerenQaError::InvalidScoringConfig(format!("{} {} is negative or not finite", name, weight)));
+            }
+        }
+        let sum: f64 = self.weights().iter().map(|(_, weight)| weight).sum();
+        if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+            return Err(SerenQaError::InvalidScoringConfig(format!("weights sum to {} instead of 1", sum)));
+        }
+        for (name, normalizer) in [("depth_normalizer", self.depth_normalizer), ("discovery_normalizer", self.discovery_normalizer)] {
+            if !(normalizer.is_finite() && normalizer > 0.0) {
+                return Err(SerenQaError::InvalidScoringConfig(format!("{} {} is not positive", name, normalizer)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Load and validate a config from JSON
+    pub fn from_json(json: &str) -> SerenQaResult<Self> {
+        let config: Self = serde_json::from_str(json)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load and validate a config from TOML
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> SerenQaResult<Self> {
+        let config: Self = toml::from_str(toml)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Order scores best first, with NaN after every number
+pub(crate) fn best_first(a: f64, b: f64) -> std::cmp::Ordering {
+    a.is_nan().cmp(&b.is_nan()).then_with(|| b.total_cmp(&a))
+}
+
+/// Score tagged with the scoring-config version that produced it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionedScore {
+    /// Score value
+    pub value: f64,
+    /// Version of the scoring config
+    pub config_version: String,
+}
+
 /// Language-aware ranking criteria
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LanguageAwareRankingCriteria {
     /// Overall combined score
     Overall,
@@ -164,12 +487,224 @@ pub enum LanguageAwareRankingCriteria {
     TranslationQuality,
     /// Language diversity
     LanguageDiversity,
+    /// Fractional trace credit (team traces split among members)
+    Credit,
+    /// Elo rating from pairwise judging (unjudged contributors rank at the default)
+    Elo,
+    /// Impact of discoveries by their outcomes (retractions count against)
+    VerifiedImpact,
+    /// Serendipity scaled by downstream citations
+    Impact,
+    /// Season trophy points (3 per first place, 2 per second, 1 per third)
+    Trophies,
 }
 
-/// Language-aware leaderboard
+/// Criterion maximized by a Pareto-front computation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ParetoCriterion {
+    /// Average serendipity score
+    Serendipity,
+    /// Fraction of replayed traces that reproduced
+    Reproducibility,
+    /// Number of languages used
+    LanguageDiversity,
+    /// Number of discoveries
+    Discoveries,
+}
+
+impl ParetoCriterion {
+    /// Criterion value for a contributor (higher is better)
+    pub fn value(&self, stats: &LanguageAwareContributorStats) -> f64 {
+        match self {
+            ParetoCriterion::Serendipity => stats.avg_serendipity,
+            ParetoCriterion::Reproducibility => stats.reproducibility(),
+            ParetoCriterion::LanguageDiversity => stats.languages_used.len() as f64,
+            ParetoCriterion::Discoveries => stats.discoveries.len() as f64,
+        }
+    }
+}
+
+/// Criteria used by `pareto_front`
+pub const DEFAULT_PARETO_CRITERIA: [ParetoCriterion; 4] = [
+    ParetoCriterion::Serendipity,
+    ParetoCriterion::Reproducibility,
+    ParetoCriterion::LanguageDiversity,
+    ParetoCriterion::Discoveries,
+];
+
+/// Default number of entries per leaderboard page
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Filtered, paginated leaderboard query
 #[derive(Debug, Clone)]
+pub struct LeaderboardQuery {
+    /// Ranking criteria
+    pub criteria: LanguageAwareRankingCriteria,
+    /// Zero-based page index
+    pub page: usize,
+    /// Entries per page
+    pub page_size: usize,
+    /// Minimum number of traces submitted
+    pub min_traces: usize,
+    /// Languages every returned contributor must have used
+    pub languages: Vec<String>,
+    /// Expertise or discovery domain contributors must fall under
+    pub domain: Option<String>,
+    /// Only contributors active on or after this time
+    pub active_from: Option<DateTime<Utc>>,
+    /// Only contributors active on or before this time
+    pub active_to: Option<DateTime<Utc>>,
+    /// Rank by this custom metric instead of `criteria`,
+    /// skipping contributors that never reported it
+    pub metric: Option<String>,
+    /// Rank by time-decayed score as of the given time instead of `criteria`
+    pub decay: Option<(DecayModel, DateTime<Utc>)>,
+}
+
+impl LeaderboardQuery {
+    /// Query the first page ranked by `criteria`, without filters
+    pub fn new(criteria: LanguageAwareRankingCriteria) -> Self {
+        Self {
+            criteria,
+            page: 0,
+            page_size: DEFAULT_PAGE_SIZE,
+            min_traces: 0,
+            languages: Vec::new(),
+            domain: None,
+            active_from: None,
+            active_to: None,
+            metric: None,
+            decay: None,
+        }
+    }
+
+    /// Select a page
+    pub fn page(mut self, page: usize, page_size: usize) -> Self {
+        self.page = page;
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Require a minimum trace count
+    pub fn min_traces(mut self, min_traces: usize) -> Self {
+        self.min_traces = min_traces;
+        self
+    }
+
+    /// Require a language to have been used (any spelling of it)
+    pub fn language(mut self, language: &str) -> Self {
+        self.languages.push(LanguageRegistry::global().canonical(language));
+        self
+    }
+
+    /// Require an expertise or discovery domain
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Rank by a custom metric
+    pub fn metric(mut self, name: &str) -> Self {
+        self.metric = Some(name.to_string());
+        self
+    }
+
+    /// Rank by time-decayed score as of `now`
+    pub fn decayed(mut self, model: DecayModel, now: DateTime<Utc>) -> Self {
+        self.decay = Some((model, now));
+        self
+    }
+
+    /// Require activity within a date range (either bound optional)
+    pub fn active_between(mut self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Self {
+        self.active_from = from;
+        self.active_to = to;
+        self
+    }
+}
+
+/// Lightweight ranked leaderboard entry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LeaderboardEntry {
+    /// One-based rank among matching contributors
+    pub rank: usize,
+    /// Contributor ID
+    pub contributor_id: String,
+    /// Score under the query criteria
+    pub score: f64,
+    /// Total traces submitted
+    pub total_traces: usize,
+    /// Languages used
+    pub languages_used: Vec<String>,
+    /// Number of discoveries
+    pub discoveries: usize,
+}
+
+/// One page of leaderboard query results
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LeaderboardPage {
+    /// Entries on this page
+    pub entries: Vec<LeaderboardEntry>,
+    /// Contributors matching the filters across all pages
+    pub total_matches: usize,
+    /// Zero-based page index
+    pub page: usize,
+    /// Entries per page
+    pub page_size: usize,
+    /// Whether later pages exist
+    pub has_more: bool,
+}
+
+/// Current leaderboard snapshot schema version.
+/// Snapshots without a version are treated as version 1.
+pub const LEADERBOARD_SCHEMA_VERSION: u32 = 1;
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// Language-aware leaderboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LanguageAwareLeaderboard {
-    contributors: HashMap<String, LanguageAwareContributorStats>,
+    #[serde(default)]
+    pub(crate) contributors: HashMap<String, LanguageAwareContributorStats>,
+    /// Duplicate-submission guard applied by `credit_trace`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) guard: Option<SubmissionGuard>,
+    /// How `credit_trace` splits team credit
+    #[serde(default)]
+    pub(crate) credit_split: CreditSplit,
+    /// Team view (team ID -> stats)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) teams: BTreeMap<String, TeamStats>,
+    /// Whether `credit_trace` only counts discoveries of approved traces
+    #[serde(default)]
+    pub(crate) approval_required: bool,
+    /// Scoring formula used for `LanguageAwareRankingCriteria::Overall`
+    #[serde(default, deserialize_with = "validated_scoring")]
+    scoring: ScoringConfig,
+    /// Challenge seasons, in date order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) seasons: Vec<LeaderboardSeason>,
+    /// Notified after crediting and re-ranking (not serialized)
+    #[serde(skip)]
+    pub(crate) observers: LeaderboardObservers,
+}
+
+/// Deserialize a scoring config, rejecting one `set_scoring_config` would refuse
+fn validated_scoring<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ScoringConfig, D::Error> {
+    let config = ScoringConfig::deserialize(deserializer)?;
+    config.validate().map_err(serde::de::Error::custom)?;
+    Ok(config)
+}
+
+/// Versioned envelope for leaderboard snapshots
+#[derive(Serialize, Deserialize)]
+struct LeaderboardSnapshot {
+    #[serde(default = "legacy_schema_version")]
+    schema_version: u32,
+    #[serde(flatten)]
+    leaderboard: LanguageAwareLeaderboard,
 }
 
 impl LanguageAwareLeaderboard {
@@ -177,12 +712,80 @@ impl LanguageAwareLeaderboard {
     pub fn new() -> Self {
         Self {
             contributors: HashMap::new(),
+            guard: None,
+            credit_split: CreditSplit::default(),
+            teams: BTreeMap::new(),
+            approval_required: false,
+            scoring: ScoringConfig::default(),
+            seasons: Vec::new(),
+            observers: LeaderboardObservers::default(),
         }
     }
 
+    /// Export a versioned JSON snapshot
+    pub fn to_json(&self) -> SerenQaResult<String> {
+        let snapshot = LeaderboardSnapshot {
+            schema_version: LEADERBOARD_SCHEMA_VERSION,
+            leaderboard: self.clone(),
+        };
+        Ok(serde_json::to_string_pretty(&snapshot)?)
+    }
+
+    /// Load a JSON snapshot written by this or an older schema version
+    pub fn from_json(json: &str) -> SerenQaResult<Self> {
+        let snapshot: LeaderboardSnapshot = serde_json::from_str(json)?;
+        if snapshot.schema_version > LEADERBOARD_SCHEMA_VERSION {
+            return Err(SerenQaError::UnsupportedSchemaVersion {
+                found: snapshot.schema_version,
+                supported: LEADERBOARD_SCHEMA_VERSION,
+            });
+        }
+        Ok(snapshot.leaderboard)
+    }
+
+    /// Rank `Overall` with a validated scoring config
+    pub fn set_scoring_config(&mut self, config: ScoringConfig) -> SerenQaResult<()> {
+        config.validate()?;
+        self.observed(|leaderboard| leaderboard.scoring = config);
+        Ok(())
+    }
+
+    /// Scoring config used for `Overall` rankings
+    pub fn scoring_config(&self) -> &ScoringConfig {
+        &self.scoring
+    }
+
+    /// Look up a contributor
+    pub fn get(&self, contributor_id: &str) -> Option<&LanguageAwareContributorStats> {
+        self.contributors.get(contributor_id)
+    }
+
+    /// Language proficiency report of a contributor
+    pub fn proficiency_report(&self, contributor_id: &str) -> Option<ProficiencyReport> {
+        self.get(contributor_id).map(LanguageAwareContributorStats::proficiency_report)
+    }
+
+    /// Number of contributors
+    pub fn len(&self) -> usize {
+        self.contributors.len()
+    }
+
+    /// Check if the leaderboard has no contributors
+    pub fn is_empty(&self) -> bool {
+        self.contributors.is_empty()
+    }
+
     /// Add or update contributor
     pub fn add_contributor(&mut self, stats: LanguageAwareContributorStats) {
-        self.contributors.insert(stats.contributor_id.clone(), stats);
+        self.observed(|leaderboard| leaderboard.contributors.insert(stats.contributor_id.clone(), stats));
+    }
+
+    /// Apply an update to every contributor
+    pub fn update_contributors<F>(&mut self, update: F)
+    where
+        F: FnMut(&mut LanguageAwareContributorStats),
+    {
+        self.observed(|leaderboard| leaderboard.contributors.values_mut().for_each(update));
     }
 
     /// Get top N contributors by criteria
@@ -191,57 +794,187 @@ impl LanguageAwareLeaderboard {
         n: usize,
         criteria: LanguageAwareRankingCriteria,
     ) -> Vec<LanguageAwareContributorStats> {
-        let mut contributors: Vec<_> = self.contributors.values().cloned().collect();
+        self.rank(self.contributors.values(), n, criteria)
+    }
+
+    /// Get top N contributors by a custom metric, skipping those without it
+    pub fn get_top_n_by_metric(&self, n: usize, metric: &str) -> Vec<LanguageAwareContributorStats> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let query = LeaderboardQuery::new(LanguageAwareRankingCriteria::Overall)
+            .metric(metric)
+            .page(0, n);
+        self.query(&query)
+            .entries
+            .iter()
+            .filter_map(|entry| self.contributors.get(&entry.contributor_id).cloned())
+            .collect()
+    }
+
+    /// Get top N contributors by criteria within a domain facet
+    pub fn get_top_n_in_domain(
+        &self,
+        n: usize,
+        criteria: LanguageAwareRankingCriteria,
+        domain: &str,
+        taxonomy: &DomainTaxonomy,
+    ) -> Vec<LanguageAwareContributorStats> {
+        let contributors = self.contributors
+            .values()
+            .filter(|stats| stats.in_domain(domain, taxonomy));
+        self.rank(contributors, n, criteria)
+    }
+
+    /// Run a filtered, paginated query.
+    /// Domains match recorded expertise/discovery domains case-insensitively.
+    pub fn query(&self, query: &LeaderboardQuery) -> LeaderboardPage {
+        self.run_query(query, None)
+    }
+
+    /// Run a query whose domain filter includes taxonomy subdomains
+    pub fn query_in(&self, query: &LeaderboardQuery, taxonomy: &DomainTaxonomy) -> LeaderboardPage {
+        self.run_query(query, Some(taxonomy))
+    }
+
+    fn run_query(&self, query: &LeaderboardQuery, taxonomy: Option<&DomainTaxonomy>) -> LeaderboardPage {
+        let in_domain = |stats: &LanguageAwareContributorStats, domain: &str| match taxonomy {
+            Some(taxonomy) => stats.in_domain(domain, taxonomy),
+            None => stats.expertise_domains
+                .iter()
+                .chain(stats.discovery_domains.values())
+                .any(|d| d.eq_ignore_ascii_case(domain)),
+        };
+
+        let mut matches: Vec<(&LanguageAwareContributorStats, f64)> = self.contributors
+            .values()
+            .filter(|stats| stats.total_traces >= query.min_traces)
+            .filter(|stats| query.languages.iter().all(|l| stats.languages_used.contains(l)))
+            .filter(|stats| query.domain.as_deref().is_none_or(|d| in_domain(stats, d)))
+            .filter(|stats| stats.active_between(query.active_from, query.active_to))
+            .filter_map(|stats| match (&query.metric, &query.decay) {
+                (Some(name), _) => stats.custom_metric(name).map(|score| (stats, score)),
+                (None, Some((model, now))) => Some((stats, stats.decayed_score(model, *now))),
+                (None, None) => Some((stats, self.get_score(stats, query.criteria))),
+            })
+            .collect();
+        matches.sort_by(|(a, score_a), (b, score_b)| {
+            best_first(*score_a, *score_b).then_with(|| a.contributor_id.cmp(&b.contributor_id))
+        });
+
+        let page_size = query.page_size.max(1);
+        let start = query.page.saturating_mul(page_size);
+        let entries: Vec<LeaderboardEntry> = matches
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(page_size)
+            .map(|(i, (stats, score))| LeaderboardEntry {
+                rank: i + 1,
+                contributor_id: stats.contributor_id.clone(),
+                score: *score,
+                total_traces: stats.total_traces,
+                languages_used: stats.languages_used.clone(),
+                discoveries: stats.discoveries.len(),
+            })
+            .collect();
+
+        LeaderboardPage {
+            has_more: start + entries.len() < matches.len(),
+            total_matches: matches.len(),
+            page: query.page,
+            page_size,
+            entries,
+        }
+    }
+
+    /// Sort contributors by criteria (ties by ID, NaN scores last) and take the top N
+    fn rank<'a, I>(
+        &self,
+        contributors: I,
+        n: usize,
+        criteria: LanguageAwareRankingCriteria,
+    ) -> Vec<LanguageAwareContributorStats>
+    where
+        I: Iterator<Item = &'a LanguageAwareContributorStats>,
+    {
+        let mut contributors: Vec<_> = contributors.cloned().collect();
         
         contributors.sort_by(|a, b| {
             let score_a = self.get_score(a, criteria);
             let score_b = self.get_score(b, criteria);
-            score_b.partial_cmp(&score_a).unwrap()
+            best_first(score_a, score_b).then_with(|| a.contributor_id.cmp(&b.contributor_id))
         });
         
         contributors.into_iter().take(n).collect()
     }
 
+    /// Contributors not dominated on serendipity, reproducibility, language
+    /// diversity, and discoveries, ordered by contributor ID
+    pub fn pareto_front(&self) -> Vec<LanguageAwareContributorStats> {
+        self.pareto_front_by(&DEFAULT_PARETO_CRITERIA)
+    }
+
+    /// Contributors for whom no other contributor is at least as good on every
+    /// criterion and strictly better on one, ordered by contributor ID
+    pub fn pareto_front_by(&self, criteria: &[ParetoCriterion]) -> Vec<LanguageAwareContributorStats> {
+        let scored: Vec<(&LanguageAwareContributorStats, Vec<f64>)> = self.contributors
+            .values()
+            .map(|stats| (stats, criteria.iter().map(|c| c.value(stats)).collect()))
+            .collect();
+        let dominates = |a: &[f64], b: &[f64]| {
+            a.iter().zip(b).all(|(x, y)| x >= y) && a.iter().zip(b).any(|(x, y)| x > y)
+        };
+
+        let mut front: Vec<LanguageAwareContributorStats> = scored
+            .iter()
+            .filter(|(_, values)| !scored.iter().any(|(_, other)| dominates(other, values)))
+            .map(|(stats, _)| (*stats).clone())
+            .collect();
+        front.sort_by(|a, b| a.contributor_id.cmp(&b.contributor_id));
+        front
+    }
+
+    /// Number of contributors under each domain facet
+    pub fn domain_facets(&self, taxonomy: &DomainTaxonomy) -> BTreeMap<String, usize> {
+        let mut facets = BTreeMap::new();
+        for stats in self.contributors.values() {
+            let mut domains: Vec<String> = stats.expertise_domains
+                .iter()
+                .chain(stats.discovery_domains.values())
+                .cloned()
+                .collect();
+            domains.sort();
+            domains.dedup();
+            // Count each contributor at most once per facet
+            for facet in taxonomy.rollup(&domains).into_keys() {
+                *facets.entry(facet).or_insert(0) += 1;
+            }
+        }
+        facets
+    }
+
     /// Get score based on criteria
-    fn get_score(&self, stats: &LanguageAwareContributorStats, criteria: LanguageAwareRankingCriteria) -> f64 {
+    pub(crate) fn get_score(&self, stats: &LanguageAwareContributorStats, criteria: LanguageAwareRankingCriteria) -> f64 {
         match criteria {
-            LanguageAwareRankingCriteria::Overall => stats.overall_score(),
+            LanguageAwareRankingCriteria::Overall => stats.score_with(&self.scoring).value,
             LanguageAwareRankingCriteria::Serendipity => stats.avg_serendipity,
             LanguageAwareRankingCriteria::CrossLanguageExpertise => stats.cross_language_expertise,
             LanguageAwareRankingCriteria::Discoveries => stats.discoveries.len() as f64,
             LanguageAwareRankingCriteria::TranslationQuality => stats.avg_translation_quality,
             LanguageAwareRankingCriteria::LanguageDiversity => stats.languages_used.len() as f64,
+            LanguageAwareRankingCriteria::Credit => stats.credit(),
+            LanguageAwareRankingCriteria::Elo => stats.elo_rating.unwrap_or(DEFAULT_ELO_RATING),
+            LanguageAwareRankingCriteria::VerifiedImpact => stats.verified_impact(),
+            LanguageAwareRankingCriteria::Impact => stats.impact_score(),
+            LanguageAwareRankingCriteria::Trophies => stats.trophy_points(),
         }
     }
 
-    /// Display leaderboard
-    pub fn display(&self, criteria: LanguageAwareRankingCriteria) {
-        let top_contributors = self.get_top_n(10, criteria);
-        
-        println!("\n╔════════════════════════════════════════════════════════════════╗");
-        println!("║     Language-Aware Serendipity Discovery Leaderboard          ║");
-        println!("║     Ranking by: {:?}                                    ║", criteria);
-        println!("╚════════════════════════════════════════════════════════════════╝\n");
-        
-        for (i, stats) in top_contributors.iter().enumerate() {
-            let medal = match i {
-                0 => "🥇",
-                1 => "🥈",
-                2 => "🥉",
-                _ => "  ",
-            };
-            
-            println!("{} #{} {}", medal, i + 1, stats.contributor_id);
-            println!("   Score: {:.3} | Traces: {} | Languages: {}",
-                self.get_score(stats, criteria),
-                stats.total_traces,
-                stats.languages_used.join(", "));
-            println!("   Serendipity: {:.3} | Cross-Lang: {:.3} | Discoveries: {}",
-                stats.avg_serendipity,
-                stats.cross_language_expertise,
-                stats.discoveries.len());
-            println!();
-        }
+    /// Print the leaderboard to stdout in the CLI format (see `render`),
+    /// returning the write error (e.g. a closed pipe) instead of panicking
+    pub fn display(&self, criteria: LanguageAwareRankingCriteria) -> std::io::Result<()> {
+        self.render(criteria, &mut std::io::stdout().lock())
     }
 }
 
@@ -258,17 +991,24 @@ mod tests {
     #[test]
     fn test_contributor_stats() {
         let mut stats = LanguageAwareContributorStats::new("researcher1");
-        stats.add_trace(10, 0.8, 0.85, vec!["en".to_string(), "id".to_string()], 0.9, 0.88);
+        stats.add_trace(10, 0.8, 0.85, vec!["en".to_string(), "id".to_string()], 0.9, 0.88).unwrap();
         
         assert_eq!(stats.total_traces, 1);
         assert_eq!(stats.multilingual_traces, 1);
         assert_eq!(stats.languages_used.len(), 2);
+        assert!((stats.language_proficiency["en"] - 0.89).abs() < 1e-12);
+        
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        leaderboard.add_contributor(stats);
+        let report = leaderboard.proficiency_report("researcher1").unwrap();
+        assert_eq!(report.language("id").unwrap().samples, 1);
+        assert!(leaderboard.proficiency_report("nobody").is_none());
     }
 
     #[test]
     fn test_overall_score() {
         let mut stats = LanguageAwareContributorStats::new("researcher1");
-        stats.add_trace(20, 0.85, 0.9, vec!["en".to_string(), "id".to_string()], 0.88, 0.9);
+        stats.add_trace(20, 0.85, 0.9, vec!["en".to_string(), "id".to_string()], 0.88, 0.9).unwrap();
         stats.add_discovery("Journavx");
         
         let score = stats.overall_score();
@@ -280,10 +1020,10 @@ mod tests {
         let mut leaderboard = LanguageAwareLeaderboard::new();
         
         let mut stats1 = LanguageAwareContributorStats::new("researcher1");
-        stats1.add_trace(20, 0.85, 0.9, vec!["en".to_string(), "id".to_string()], 0.88, 0.9);
+        stats1.add_trace(20, 0.85, 0.9, vec!["en".to_string(), "id".to_string()], 0.88, 0.9).unwrap();
         
         let mut stats2 = LanguageAwareContributorStats::new("researcher2");
-        stats2.add_trace(15, 0.75, 0.8, vec!["en".to_string()], 0.85, 0.82);
+        stats2.add_trace(15, 0.75, 0.8, vec!["en".to_string()], 0.85, 0.82).unwrap();
         
         leaderboard.add_contributor(stats1);
         leaderboard.add_contributor(stats2);
@@ -291,4 +1031,223 @@ mod tests {
         let top = leaderboard.get_top_n(2, LanguageAwareRankingCriteria::Overall);
         assert_eq!(top.len(), 2);
     }
+
+    #[test]
+    fn test_scoring_config_weights() {
+        let config = ScoringConfig::from_json(r#"{"version": "discovery-only", "depth_weight": 0.0, "uniqueness_weight": 0.0,
+            "serendipity_weight": 0.0, "language_weight": 0.0, "quality_weight": 0.0, "discovery_weight": 1.0}"#).unwrap();
+        assert_eq!(config.depth_normalizer, ScoringConfig::default().depth_normalizer);
+        assert!(matches!(
+            ScoringConfig::from_json(r#"{"depth_weight": 0.5}"#),
+            Err(SerenQaError::InvalidScoringConfig(_))
+        ));
+        let negative = ScoringConfig { depth_weight: -0.1, uniqueness_weight: 0.35, ..ScoringConfig::default() };
+        assert!(negative.validate().is_err());
+
+        let mut deep = LanguageAwareContributorStats::new("deep");
+        deep.add_trace(50, 0.9, 0.9, vec!["en".to_string()], 0.9, 0.9).unwrap();
+        let mut finder = LanguageAwareContributorStats::new("finder");
+        finder.add_trace(5, 0.5, 0.5, vec!["en".to_string()], 0.5, 0.5).unwrap();
+        finder.add_discovery("Journavx");
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        leaderboard.add_contributor(deep);
+        leaderboard.add_contributor(finder);
+        assert_eq!(leaderboard.get_top_n(1, LanguageAwareRankingCriteria::Overall)[0].contributor_id, "deep");
+
+        assert!(leaderboard.set_scoring_config(negative).is_err());
+        leaderboard.set_scoring_config(config.clone()).unwrap();
+        assert_eq!(leaderboard.get_top_n(1, LanguageAwareRankingCriteria::Overall)[0].contributor_id, "finder");
+        let restored = LanguageAwareLeaderboard::from_json(&leaderboard.to_json().unwrap()).unwrap();
+        assert_eq!(restored.scoring_config(), &config);
+
+        // Snapshots carrying an invalid config are refused on load
+        let mut snapshot: serde_json::Value = serde_json::from_str(&leaderboard.to_json().unwrap()).unwrap();
+        snapshot["scoring"]["discovery_weight"] = serde_json::json!(-1.0);
+        let loaded = LanguageAwareLeaderboard::from_json(&snapshot.to_string());
+        assert!(matches!(loaded, Err(SerenQaError::Json(e)) if e.to_string().contains("negative")));
+    }
+
+    #[test]
+    fn test_nan_scores_rank_without_panicking() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for (id, serendipity) in [("steady", 0.7), ("broken", f64::NAN), ("lucky", 0.9)] {
+            let mut stats = LanguageAwareContributorStats::new(id);
+            stats.avg_serendipity = serendipity;
+            leaderboard.add_contributor(stats);
+        }
+        let ranked: Vec<String> = leaderboard
+            .get_top_n(3, LanguageAwareRankingCriteria::Serendipity)
+            .into_iter()
+            .map(|stats| stats.contributor_id)
+            .collect();
+        assert_eq!(ranked, ["lucky", "steady", "broken"]);
+
+        let page = leaderboard.query(&LeaderboardQuery::new(LanguageAwareRankingCriteria::Serendipity));
+        let ids: Vec<&str> = page.entries.iter().map(|entry| entry.contributor_id.as_str()).collect();
+        assert_eq!(ids, ["lucky", "steady", "broken"]);
+        assert_eq!(leaderboard.standings(LanguageAwareRankingCriteria::Serendipity), ["lucky", "steady", "broken"]);
+    }
+
+    #[test]
+    fn test_equal_scores_rank_by_contributor_id() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for id in ["citra", "ayu", "dewi", "budi"] {
+            let mut stats = LanguageAwareContributorStats::new(id);
+            stats.avg_serendipity = 0.5;
+            leaderboard.add_contributor(stats);
+        }
+        let top: Vec<String> = leaderboard
+            .get_top_n(2, LanguageAwareRankingCriteria::Serendipity)
+            .into_iter()
+            .map(|stats| stats.contributor_id)
+            .collect();
+        assert_eq!(top, ["ayu", "budi"]);
+        let page = leaderboard.query(&LeaderboardQuery::new(LanguageAwareRankingCriteria::Serendipity).page(0, 2));
+        let ids: Vec<&str> = page.entries.iter().map(|entry| entry.contributor_id.as_str()).collect();
+        assert_eq!(ids, top);
+    }
+
+    #[test]
+    fn test_leaderboard_query() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for (i, languages) in [vec!["en", "id"], vec!["en"], vec!["id", "jv"], vec!["en", "id"]].into_iter().enumerate() {
+            let mut stats = LanguageAwareContributorStats::new(&format!("researcher{}", i));
+            let languages = languages.into_iter().map(String::from).collect();
+            stats.add_trace(10 + i * 5, 0.8, 0.5 + i as f64 * 0.1, languages, 0.9, 0.9).unwrap();
+            if i == 3 {
+                stats.add_expertise_domain("Quantum Computing");
+                stats.last_active = Some(Utc::now() - chrono::Duration::days(400));
+                stats.first_active = stats.last_active;
+            }
+            leaderboard.add_contributor(stats);
+        }
+
+        let query = LeaderboardQuery::new(LanguageAwareRankingCriteria::Serendipity).language("id");
+        let page = leaderboard.query(&query.clone().page(0, 2));
+        assert_eq!(page.total_matches, 3);
+        assert!(page.has_more);
+        let ids: Vec<&str> = page.entries.iter().map(|e| e.contributor_id.as_str()).collect();
+        assert_eq!(ids, vec!["researcher3", "researcher2"]);
+        assert_eq!(leaderboard.query(&query.clone().page(1, 2)).entries[0].rank, 3);
+
+        let recent = query.clone().active_between(Some(Utc::now() - chrono::Duration::days(30)), None);
+        assert_eq!(leaderboard.query(&recent).total_matches, 2);
+        assert_eq!(leaderboard.query(&query.domain("quantum computing")).entries[0].contributor_id, "researcher3");
+        let none = LeaderboardQuery::new(LanguageAwareRankingCriteria::Overall).min_traces(2);
+        assert!(leaderboard.query(&none).entries.is_empty());
+    }
+
+    #[test]
+    fn test_leaderboard_json_round_trip() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        let mut stats = LanguageAwareContributorStats::new("researcher1");
+        stats.add_trace(20, 0.85, 0.9, vec!["en".to_string(), "id".to_string()], 0.88, 0.9).unwrap();
+        stats.add_discovery("Journavx");
+        leaderboard.add_contributor(stats);
+
+        let json = leaderboard.to_json().unwrap();
+        assert!(json.contains("\"schema_version\": 1"));
+        let restored = LanguageAwareLeaderboard::from_json(&json).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored.get("researcher1").unwrap().discoveries, vec!["Journavx".to_string()]);
+        let criteria = LanguageAwareRankingCriteria::CrossLanguageExpertise;
+        assert_eq!(restored.get_top_n(1, criteria)[0].contributor_id, "researcher1");
+
+        // Unversioned snapshots predating activity timestamps still load
+        let mut legacy: serde_json::Value = serde_json::from_str(&json).unwrap();
+        legacy.as_object_mut().unwrap().remove("schema_version");
+        let stats = legacy["contributors"]["researcher1"].as_object_mut().unwrap();
+        stats.remove("first_active");
+        stats.remove("last_active");
+        let legacy = LanguageAwareLeaderboard::from_json(&legacy.to_string()).unwrap();
+        assert!(legacy.get("researcher1").unwrap().last_active.is_none());
+
+        let future = json.replace("\"schema_version\": 1", "\"schema_version\": 99");
+        assert!(matches!(
+            LanguageAwareLeaderboard::from_json(&future),
+            Err(SerenQaError::UnsupportedSchemaVersion { found: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn test_rank_by_custom_metric() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for (id, values) in [("researcher1", vec![0.2, 0.4]), ("researcher2", vec![0.5]), ("researcher3", vec![])] {
+            let mut stats = LanguageAwareContributorStats::new(id);
+            for value in values {
+                stats.record_custom_metrics(&BTreeMap::from([("novelty".to_string(), value)]));
+            }
+            leaderboard.add_contributor(stats);
+        }
+
+        assert!((leaderboard.get("researcher1").unwrap().custom_metric("novelty").unwrap() - 0.3).abs() < 1e-9);
+        let top = leaderboard.get_top_n_by_metric(10, "novelty");
+        let ids: Vec<&str> = top.iter().map(|s| s.contributor_id.as_str()).collect();
+        assert_eq!(ids, vec!["researcher2", "researcher1"]);
+    }
+
+    #[test]
+    fn test_domain_facets() {
+        let taxonomy = DomainTaxonomy::from_json(include_str!("data/domain_taxonomy.json")).unwrap();
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        
+        let mut stats1 = LanguageAwareContributorStats::new("researcher1");
+        stats1.add_expertise_domain_in("quantum algorithms", &taxonomy);
+        stats1.add_discovery_in("Journavx", "Traditional Wayfinding", &taxonomy);
+        
+        let mut stats2 = LanguageAwareContributorStats::new("researcher2");
+        stats2.add_expertise_domain_in("Linguistics", &taxonomy);
+        
+        assert_eq!(stats1.expertise_domains[0], "Physics > Quantum Computing > Quantum Algorithms");
+        assert_eq!(stats1.discovery_rollup(&taxonomy)["Humanities"], 1);
+        
+        leaderboard.add_contributor(stats1);
+        leaderboard.add_contributor(stats2);
+        
+        let facets = leaderboard.domain_facets(&taxonomy);
+        assert_eq!(facets["Humanities"], 2);
+        assert_eq!(facets["Physics"], 1);
+        
+        let physicists = leaderboard.get_top_n_in_domain(10, LanguageAwareRankingCriteria::Overall, "Physics", &taxonomy);
+        assert_eq!(physicists.len(), 1);
+        assert_eq!(physicists[0].contributor_id, "researcher1");
+    }
+
+    #[test]
+    fn test_pareto_front() {
+        let replay = |reproducible: bool| ReplayReport {
+            trace_id: "t".to_string(),
+            provenance_hash: String::new(),
+            steps: Vec::new(),
+            diverged_events: if reproducible { Vec::new() } else { vec!["event_0".to_string()] },
+            skipped_steps: 0,
+            output_mismatches: 0,
+            max_serendipity_delta: 0.0,
+            max_confidence_delta: 0.0,
+            tolerance: 0.01,
+        };
+        let contributor = |id: &str, serendipity: f64, languages: &[&str], reproducible: bool| {
+            let mut stats = LanguageAwareContributorStats::new(id);
+            let languages = languages.iter().map(|l| l.to_string()).collect();
+            stats.add_trace(5, 0.7, serendipity, languages, 0.8, 0.8).unwrap();
+            stats.record_replay(&replay(reproducible));
+            stats
+        };
+
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        // Best serendipity, but irreproducible
+        leaderboard.add_contributor(contributor("bold", 0.95, &["en"], false));
+        // Reproducible and multilingual
+        leaderboard.add_contributor(contributor("careful", 0.7, &["en", "id", "jv"], true));
+        // Dominated by "careful" on every criterion
+        leaderboard.add_contributor(contributor("behind", 0.6, &["en", "id"], true));
+
+        let ids: Vec<String> = leaderboard.pareto_front().into_iter().map(|s| s.contributor_id).collect();
+        assert_eq!(ids, vec!["bold", "careful"]);
+
+        let by_serendipity = leaderboard.pareto_front_by(&[ParetoCriterion::Serendipity]);
+        assert_eq!(by_serendipity.len(), 1);
+        assert_eq!(by_serendipity[0].contributor_id, "bold");
+        assert_eq!(leaderboard.get("careful").unwrap().reproducibility(), 1.0);
+    }
 }
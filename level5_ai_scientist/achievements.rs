@@ -0,0 +1,206 @@
+// -*- coding: utf-8 -*-
+//! Contributor Achievements and Badges
+//!
+//! An `AchievementSet` holds configurable rules (first multilingual
+//! discovery, several high-serendipity traces, work across language
+//! families, ...) evaluated against contributor statistics. Earned badges
+//! are stored on `LanguageAwareContributorStats` with the time they were
+//! awarded, are never revoked, and appear in the leaderboard display and
+//! CSV export. Rule sets load from JSON so each benchmark can define its own.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use crate::ContributorStats::{LanguageAwareContributorStats, LanguageAwareLeaderboard};
+use crate::language_registry::LanguageRegistry;
+
+/// Condition a contributor must meet to earn an achievement
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum AchievementRule {
+    /// At least one multilingual trace and one discovery
+    MultilingualDiscovery,
+    /// At least `count` traces with serendipity above `min_serendipity`
+    HighSerendipityTraces { count: usize, min_serendipity: f64 },
+    /// Languages used span at least `min` language families
+    LanguageFamilies { min: usize },
+    /// At least `min` discoveries
+    Discoveries { min: usize },
+    /// At least `min` traces
+    Traces { min: usize },
+}
+
+impl AchievementRule {
+    /// Whether the statistics satisfy the rule
+    pub fn is_met(&self, stats: &LanguageAwareContributorStats) -> bool {
+        match self {
+            AchievementRule::MultilingualDiscovery => stats.multilingual_traces > 0 && !stats.discoveries.is_empty(),
+            AchievementRule::HighSerendipityTraces { count, min_serendipity } => {
+                stats.trace_history.iter().filter(|t| t.serendipity > *min_serendipity).count() >= *count
+            }
+            AchievementRule::LanguageFamilies { min } => language_families(stats).len() >= *min,
+            AchievementRule::Discoveries { min } => stats.discoveries.len() >= *min,
+            AchievementRule::Traces { min } => stats.total_traces >= *min,
+        }
+    }
+}
+
+/// Language families of the languages a contributor used (unregistered
+/// languages are skipped)
+pub fn language_families(stats: &LanguageAwareContributorStats) -> BTreeSet<&'static str> {
+    let registry = LanguageRegistry::global();
+    stats.languages_used.iter().filter_map(|l| registry.family(l)).collect()
+}
+
+/// Named achievement with its rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Achievement {
+    /// Stable identifier stored on earned badges
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Rule to meet
+    #[serde(flatten)]
+    pub rule: AchievementRule,
+}
+
+impl Achievement {
+    /// Achievement with the given ID, name, and rule
+    pub fn new(id: &str, name: &str, rule: AchievementRule) -> Self {
+        Self { id: id.to_string(), name: name.to_string(), rule }
+    }
+}
+
+/// Earned achievement
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Badge {
+    /// Achievement ID
+    pub id: String,
+    /// Achievement name when awarded
+    pub name: String,
+    /// When the badge was awarded
+    pub earned_at: DateTime<Utc>,
+}
+
+/// Configurable set of achievements
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AchievementSet {
+    /// Achievements in display order
+    pub achievements: Vec<Achievement>,
+}
+
+impl Default for AchievementSet {
+    fn default() -> Self {
+        Self {
+            achievements: vec![
+                Achievement::new("first_multilingual_discovery", "First Multilingual Discovery", AchievementRule::MultilingualDiscovery),
+                Achievement::new(
+                    "serendipity_streak",
+                    "5 Traces Above 0.9 Serendipity",
+                    AchievementRule::HighSerendipityTraces { count: 5, min_serendipity: 0.9 },
+                ),
+                Achievement::new("polyglot", "Worked in 4+ Language Families", AchievementRule::LanguageFamilies { min: 4 }),
+                Achievement::new("prolific", "10 Discoveries", AchievementRule::Discoveries { min: 10 }),
+            ],
+        }
+    }
+}
+
+impl AchievementSet {
+    /// Load a set from JSON (`{"achievements": [{"id", "name", "rule", ...}]}`)
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Achievements whose rules the statistics meet
+    pub fn evaluate<'a>(&'a self, stats: &LanguageAwareContributorStats) -> Vec<&'a Achievement> {
+        self.achievements.iter().filter(|a| a.rule.is_met(stats)).collect()
+    }
+
+    /// Award newly earned badges; returns their IDs
+    pub fn award(&self, stats: &mut LanguageAwareContributorStats) -> Vec<String> {
+        let now = Utc::now();
+        let earned: Vec<Badge> = self
+            .evaluate(stats)
+            .into_iter()
+            .filter(|a| !stats.has_badge(&a.id))
+            .map(|a| Badge { id: a.id.clone(), name: a.name.clone(), earned_at: now })
+            .collect();
+        let ids = earned.iter().map(|b| b.id.clone()).collect();
+        stats.badges.extend(earned);
+        ids
+    }
+}
+
+impl LanguageAwareContributorStats {
+    /// Whether the contributor holds a badge
+    pub fn has_badge(&self, id: &str) -> bool {
+        self.badges.iter().any(|b| b.id == id)
+    }
+
+    /// Names of earned badges, in award order
+    pub fn badge_names(&self) -> Vec<String> {
+        self.badges.iter().map(|b| b.name.clone()).collect()
+    }
+}
+
+impl LanguageAwareLeaderboard {
+    /// Award newly earned badges to every contributor
+    pub fn award_badges(&mut self, achievements: &AchievementSet) {
+        self.update_contributors(|stats| {
+            achievements.award(stats);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(languages: &[&str], serendipities: &[f64]) -> LanguageAwareContributorStats {
+        let mut stats = LanguageAwareContributorStats::new("sari");
+        for serendipity in serendipities {
+            let languages = languages.iter().map(|l| l.to_string()).collect();
+            stats.add_trace(10, 0.8, *serendipity, languages, 0.9, 0.9).unwrap();
+        }
+        stats
+    }
+
+    #[test]
+    fn test_rules() {
+        let mut multilingual = stats(&["en", "id"], &[0.95; 5]);
+        assert!(!AchievementRule::MultilingualDiscovery.is_met(&multilingual));
+        multilingual.add_discovery("Journavx");
+        assert!(AchievementRule::MultilingualDiscovery.is_met(&multilingual));
+
+        let streak = AchievementRule::HighSerendipityTraces { count: 5, min_serendipity: 0.9 };
+        assert!(streak.is_met(&multilingual));
+        assert!(!streak.is_met(&stats(&["en"], &[0.95, 0.95, 0.95, 0.95, 0.9])));
+
+        // Indo-European, Austronesian, Sino-Tibetan, Afro-Asiatic
+        let families = AchievementRule::LanguageFamilies { min: 4 };
+        assert!(families.is_met(&stats(&["en", "id", "zh", "ar"], &[0.5])));
+        assert!(!families.is_met(&stats(&["en", "fr", "id", "jv"], &[0.5])));
+    }
+
+    #[test]
+    fn test_badges_awarded_once_and_exported() {
+        let set = AchievementSet::from_json(r#"{"achievements": [
+            {"id": "first_multilingual_discovery", "name": "First Multilingual Discovery", "rule": "multilingual_discovery"},
+            {"id": "five_traces", "name": "Five Traces", "rule": "traces", "min": 5}
+        ]}"#).unwrap();
+
+        let mut contributor = stats(&["en", "id"], &[0.7]);
+        contributor.add_discovery("Journavx");
+        assert_eq!(set.award(&mut contributor), vec!["first_multilingual_discovery".to_string()]);
+        assert!(set.award(&mut contributor).is_empty());
+
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        leaderboard.add_contributor(contributor);
+        leaderboard.award_badges(&set);
+        let stats = leaderboard.get("sari").unwrap();
+        assert_eq!(stats.badge_names(), vec!["First Multilingual Discovery".to_string()]);
+        let csv = leaderboard.export_csv(crate::ContributorStats::LanguageAwareRankingCriteria::Overall);
+        assert!(csv.lines().nth(1).unwrap().ends_with(",Journavx,First Multilingual Discovery"));
+    }
+}
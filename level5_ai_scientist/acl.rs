@@ -0,0 +1,357 @@
+// -*- coding: utf-8 -*-
+//! Trace Access Control
+//!
+//! A shared deployment hosts competing labs, so a trace is only readable by
+//! those its visibility allows. Every trace is `Private` (its contributor and
+//! co-contributors), `Team` (also members of one team), or `Public`. On top of
+//! that, principals may hold global roles: a `Reviewer` can read and review
+//! any trace submitted for review, and a `BenchmarkAdmin` can do anything.
+//! Only owners and admins can write or delete a trace, and only a
+//! contributor and admins can read the contributor's full statistics (the
+//! leaderboard summary stays public). `AccessControlledStore`
+//! enforces this in front of any `TraceStore`; the HTTP server uses it when
+//! configured with a `PrincipalDirectory`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use crate::serendipity_trace::SerendipityTrace;
+use crate::store::TraceStore;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Who may read a trace besides its owners
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// Owners only
+    #[default]
+    Private,
+    /// Owners and members of the team
+    Team(String),
+    /// Everyone
+    Public,
+}
+
+impl Visibility {
+    /// Whether this is the default, `Private`
+    pub fn is_private(&self) -> bool {
+        *self == Visibility::Private
+    }
+}
+
+/// Role of a principal with respect to a trace
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Contributor or co-contributor of the trace (derived, not assigned)
+    Owner,
+    /// May read and review traces submitted for review
+    Reviewer,
+    /// May do anything to any trace
+    BenchmarkAdmin,
+}
+
+/// Action on a trace
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Read,
+    Write,
+    Delete,
+    Review,
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Permission::Read => "read",
+            Permission::Write => "write",
+            Permission::Delete => "delete",
+            Permission::Review => "review",
+        })
+    }
+}
+
+/// Authenticated user of a deployment
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Principal {
+    /// Contributor ID the principal acts as
+    pub id: String,
+    /// Global roles (`Owner` is derived per trace and ignored here)
+    #[serde(default)]
+    pub roles: BTreeSet<Role>,
+    /// Teams the principal belongs to
+    #[serde(default)]
+    pub teams: BTreeSet<String>,
+}
+
+impl Principal {
+    /// Principal acting as a contributor, without roles or teams
+    pub fn new(id: &str) -> Self {
+        Self { id: id.to_string(), ..Self::default() }
+    }
+
+    /// Unauthenticated caller: can read public traces only
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    /// Grant a global role
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.roles.insert(role);
+        self
+    }
+
+    /// Add team membership
+    pub fn in_team(mut self, team: &str) -> Self {
+        self.teams.insert(team.to_string());
+        self
+    }
+
+    /// Roles the principal holds on `trace`
+    pub fn roles_on(&self, trace: &SerendipityTrace) -> BTreeSet<Role> {
+        let mut roles: BTreeSet<Role> = self.roles.iter().copied().filter(|role| *role != Role::Owner).collect();
+        let owns = !self.id.is_empty()
+            && (trace.contributor_id == self.id || trace.co_contributors.contains(&self.id));
+        if owns {
+            roles.insert(Role::Owner);
+        }
+        roles
+    }
+
+    /// Whether the principal may perform `permission` on `trace`
+    pub fn can(&self, permission: Permission, trace: &SerendipityTrace) -> bool {
+        let roles = self.roles_on(trace);
+        if roles.contains(&Role::BenchmarkAdmin) {
+            return true;
+        }
+        let in_review = roles.contains(&Role::Reviewer) && trace.review.submitted_at.is_some();
+        match permission {
+            Permission::Read => {
+                roles.contains(&Role::Owner)
+                    || in_review
+                    || match &trace.visibility {
+                        Visibility::Private => false,
+                        Visibility::Team(team) => self.teams.contains(team),
+                        Visibility::Public => true,
+                    }
+            }
+            Permission::Write | Permission::Delete => roles.contains(&Role::Owner),
+            // Owners do not review their own traces
+            Permission::Review => in_review && !roles.contains(&Role::Owner),
+        }
+    }
+
+    /// Whether the principal may read `contributor_id`'s full statistics
+    pub fn can_read_stats(&self, contributor_id: &str) -> bool {
+        self.roles.contains(&Role::BenchmarkAdmin) || (!self.id.is_empty() && self.id == contributor_id)
+    }
+
+    /// Fail with `AccessDenied` unless the principal may perform `permission` on `trace`
+    pub fn check(&self, permission: Permission, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        if self.can(permission, trace) {
+            Ok(())
+        } else {
+            Err(SerenQaError::AccessDenied {
+                principal: self.id.clone(),
+                permission,
+                trace_id: trace.trace_id.clone(),
+            })
+        }
+    }
+}
+
+impl SerendipityTrace {
+    /// Change who may read the trace
+    pub fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+}
+
+/// Principals of a deployment by their API token
+#[derive(Debug, Clone, Default)]
+pub struct PrincipalDirectory {
+    tokens: HashMap<String, Principal>,
+}
+
+impl PrincipalDirectory {
+    /// Empty directory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `principal` under `token`
+    pub fn with_token(mut self, token: &str, principal: Principal) -> Self {
+        self.tokens.insert(token.to_string(), principal);
+        self
+    }
+
+    /// Principal holding `token`
+    pub fn authenticate(&self, token: &str) -> Option<&Principal> {
+        self.tokens.get(token)
+    }
+}
+
+/// `TraceStore` wrapper checking every operation against a principal
+#[derive(Clone)]
+pub struct AccessControlledStore {
+    inner: Arc<dyn TraceStore>,
+}
+
+impl AccessControlledStore {
+    /// Enforce access control in front of `inner`
+    pub fn new(inner: Arc<dyn TraceStore>) -> Self {
+        Self { inner }
+    }
+
+    /// Save a trace the principal may write; replacing a stored trace also
+    /// requires write access to the stored version
+    pub fn save(&self, principal: &Principal, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        self.check_save(principal, trace)?;
+        self.inner.save(trace)
+    }
+
+    /// Check a save without performing it, e.g. before crediting a submission
+    pub fn check_save(&self, principal: &Principal, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        principal.check(Permission::Write, trace)?;
+        if let Some(stored) = self.inner.load(&trace.trace_id)? {
+            principal.check(Permission::Write, &stored)?;
+        }
+        Ok(())
+    }
+
+    /// Load a trace the principal may read
+    pub fn load(&self, principal: &Principal, trace_id: &str) -> SerenQaResult<Option<SerendipityTrace>> {
+        match self.inner.load(trace_id)? {
+            Some(trace) => {
+                principal.check(Permission::Read, &trace)?;
+                Ok(Some(trace))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Delete a trace the principal may delete; returns whether it existed
+    pub fn delete(&self, principal: &Principal, trace_id: &str) -> SerenQaResult<bool> {
+        match self.inner.load(trace_id)? {
+            Some(trace) => {
+                principal.check(Permission::Delete, &trace)?;
+                self.inner.delete(trace_id)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// IDs of the stored traces the principal may read, sorted
+    pub fn list(&self, principal: &Principal) -> SerenQaResult<Vec<String>> {
+        let mut readable = Vec::new();
+        for trace_id in self.inner.list()? {
+            if self.inner.load(&trace_id)?.is_some_and(|trace| principal.can(Permission::Read, &trace)) {
+                readable.push(trace_id);
+            }
+        }
+        Ok(readable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryTraceStore;
+
+    fn trace(contributor_id: &str, visibility: Visibility) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor_id, "backend", "Journavx");
+        trace.trace_id = format!("{}-trace", contributor_id);
+        trace.set_visibility(visibility);
+        trace
+    }
+
+    #[test]
+    fn test_visibility_and_roles() {
+        let private = trace("sari", Visibility::Private);
+        let team = trace("sari", Visibility::Team("lab-a".to_string()));
+        let public = trace("sari", Visibility::Public);
+
+        let rival = Principal::new("budi").in_team("lab-b");
+        let colleague = Principal::new("ayu").in_team("lab-a");
+        assert!(Principal::new("sari").can(Permission::Delete, &private));
+        assert!(!rival.can(Permission::Read, &private) && !rival.can(Permission::Read, &team));
+        assert!(colleague.can(Permission::Read, &team) && !colleague.can(Permission::Write, &team));
+        assert!(Principal::anonymous().can(Permission::Read, &public));
+        assert!(!Principal::anonymous().can(Permission::Write, &public));
+
+        let reviewer = Principal::new("citra").with_role(Role::Reviewer);
+        let mut submitted = private.clone();
+        assert!(!reviewer.can(Permission::Read, &submitted));
+        submitted.submit_for_review();
+        assert!(reviewer.can(Permission::Review, &submitted));
+        assert!(!Principal::new("sari").with_role(Role::Reviewer).can(Permission::Review, &submitted));
+        assert!(Principal::new("admin").with_role(Role::BenchmarkAdmin).can(Permission::Delete, &private));
+        assert!(Principal::new("sari").can_read_stats("sari") && !rival.can_read_stats("sari"));
+        assert!(!Principal::anonymous().can_read_stats(""));
+        assert!(Principal::new("admin").with_role(Role::BenchmarkAdmin).can_read_stats("sari"));
+
+        let mut shared = private.clone();
+        shared.add_co_contributor("budi");
+        assert_eq!(rival.roles_on(&shared), BTreeSet::from([Role::Owner]));
+        // Private is the default and is not written out
+        assert!(!serde_json::to_string(&private).unwrap().contains("visibility"));
+    }
+
+    #[test]
+    fn test_store_enforcement() {
+        let store = AccessControlledStore::new(Arc::new(InMemoryTraceStore::new()));
+        let sari = Principal::new("sari");
+        let budi = Principal::new("budi");
+        store.save(&sari, &trace("sari", Visibility::Private)).unwrap();
+        store.save(&budi, &trace("budi", Visibility::Public)).unwrap();
+
+        assert!(matches!(store.load(&budi, "sari-trace"), Err(SerenQaError::AccessDenied { .. })));
+        assert_eq!(store.list(&budi).unwrap(), vec!["budi-trace".to_string()]);
+        assert_eq!(store.list(&sari).unwrap().len(), 2);
+
+        // Budi cannot overwrite or delete Sari's trace, even by claiming it
+        let mut hijack = trace("budi", Visibility::Public);
+        hijack.trace_id = "sari-trace".to_string();
+        assert!(store.save(&budi, &hijack).is_err());
+        assert!(store.delete(&budi, "sari-trace").is_err());
+        assert!(store.delete(&sari, "sari-trace").unwrap());
+    }
+
+    #[test]
+    fn test_denials_are_reported_and_change_nothing() {
+        let store = AccessControlledStore::new(Arc::new(InMemoryTraceStore::new()));
+        let sari = Principal::new("sari");
+        let colleague = Principal::new("ayu").in_team("lab-a");
+        let mut shared = trace("sari", Visibility::Team("lab-a".to_string()));
+        store.save(&sari, &shared).unwrap();
+
+        // The error names who was denied what on which trace
+        let err = colleague.check(Permission::Write, &shared).unwrap_err();
+        assert!(matches!(
+            &err,
+            SerenQaError::AccessDenied { principal, permission: Permission::Write, trace_id }
+                if principal == "ayu" && trace_id == "sari-trace"
+        ));
+        assert_eq!(err.to_string(), "\"ayu\" may not write trace sari-trace");
+
+        // A team member may read but not replace or delete, and a denied
+        // save leaves the stored trace as it was
+        shared.discovery_name = "Hijacked".to_string();
+        assert!(matches!(store.save(&colleague, &shared), Err(SerenQaError::AccessDenied { .. })));
+        assert!(matches!(store.delete(&colleague, "sari-trace"), Err(SerenQaError::AccessDenied { permission: Permission::Delete, .. })));
+        assert_eq!(store.load(&colleague, "sari-trace").unwrap().unwrap().discovery_name, "Journavx");
+
+        // Anonymous callers can write nothing, and missing traces are not denials
+        let anonymous = Principal::anonymous();
+        assert!(matches!(store.save(&anonymous, &trace("", Visibility::Public)), Err(SerenQaError::AccessDenied { .. })));
+        assert!(matches!(store.load(&anonymous, "sari-trace"), Err(SerenQaError::AccessDenied { permission: Permission::Read, .. })));
+        assert!(store.load(&anonymous, "missing").unwrap().is_none());
+        assert!(!store.delete(&anonymous, "missing").unwrap());
+        assert!(store.list(&anonymous).unwrap().is_empty());
+
+        let directory = PrincipalDirectory::new().with_token("sari-token", sari.clone());
+        assert_eq!(directory.authenticate("sari-token"), Some(&sari));
+        assert!(directory.authenticate("guessed-token").is_none());
+    }
+}
@@ -0,0 +1,302 @@
+// -*- coding: utf-8 -*-
+//! Per-Agent Performance Profiles
+//!
+//! Pipeline designers want to know which agent roles produce the surprising
+//! moments. `AgentProfiles` accumulates, over one or many traces, each
+//! agent's events, mean and peak serendipity, mean confidence, languages,
+//! stages (and the stage it most often works in), key discoveries, and
+//! serendipity peaks. `AgentPerformanceReport` ranks the agents by a chosen
+//! criterion, leaderboard style.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use crate::serendipity_trace::{SerendipityAgent, SerendipityStage, SerendipityTrace};
+
+/// Performance of one agent role across the traces seen
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentProfile {
+    pub agent: SerendipityAgent,
+    /// Events carried out by the agent
+    pub events: usize,
+    /// Traces the agent appears in
+    pub traces: usize,
+    pub mean_serendipity: f64,
+    pub peak_serendipity: f64,
+    pub mean_confidence: f64,
+    /// Events per language
+    pub languages: BTreeMap<String, usize>,
+    /// Events per stage, keyed by stage name
+    pub stages: BTreeMap<String, usize>,
+    /// Stage the agent most often works in (earliest on ties)
+    pub typical_stage: SerendipityStage,
+    /// Events kept as key discoveries by their trace's threshold
+    pub key_discoveries: usize,
+    /// Events flagged as serendipity peaks
+    pub serendipity_peaks: usize,
+}
+
+/// What agents are ranked by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentRankingCriteria {
+    /// Mean serendipity of the agent's events
+    #[default]
+    Serendipity,
+    /// Mean confidence of the agent's events
+    Confidence,
+    /// Key discoveries produced
+    KeyDiscoveries,
+    /// Serendipity peaks produced
+    Peaks,
+    /// Events carried out
+    Events,
+}
+
+impl AgentRankingCriteria {
+    fn value(&self, profile: &AgentProfile) -> f64 {
+        match self {
+            AgentRankingCriteria::Serendipity => profile.mean_serendipity,
+            AgentRankingCriteria::Confidence => profile.mean_confidence,
+            AgentRankingCriteria::KeyDiscoveries => profile.key_discoveries as f64,
+            AgentRankingCriteria::Peaks => profile.serendipity_peaks as f64,
+            AgentRankingCriteria::Events => profile.events as f64,
+        }
+    }
+}
+
+/// Running sums for one agent
+#[derive(Debug, Clone)]
+struct AgentTotals {
+    events: usize,
+    traces: usize,
+    serendipity_sum: f64,
+    peak_serendipity: f64,
+    confidence_sum: f64,
+    languages: BTreeMap<String, usize>,
+    stages: HashMap<SerendipityStage, usize>,
+    key_discoveries: usize,
+    serendipity_peaks: usize,
+}
+
+impl AgentTotals {
+    fn new() -> Self {
+        Self {
+            events: 0,
+            traces: 0,
+            serendipity_sum: 0.0,
+            peak_serendipity: 0.0,
+            confidence_sum: 0.0,
+            languages: BTreeMap::new(),
+            stages: HashMap::new(),
+            key_discoveries: 0,
+            serendipity_peaks: 0,
+        }
+    }
+
+    fn profile(&self, agent: &SerendipityAgent) -> AgentProfile {
+        let typical_stage = self.stages
+            .iter()
+            .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.ordinal().cmp(&a.ordinal())))
+            .map(|(stage, _)| stage.clone())
+            .expect("profiled agents have events");
+        AgentProfile {
+            agent: agent.clone(),
+            events: self.events,
+            traces: self.traces,
+            mean_serendipity: self.serendipity_sum / self.events as f64,
+            peak_serendipity: self.peak_serendipity,
+            mean_confidence: self.confidence_sum / self.events as f64,
+            languages: self.languages.clone(),
+            stages: self.stages.iter().map(|(stage, count)| (stage.name(), *count)).collect(),
+            typical_stage,
+            key_discoveries: self.key_discoveries,
+            serendipity_peaks: self.serendipity_peaks,
+        }
+    }
+}
+
+/// Agent profiles accumulated over traces
+#[derive(Debug, Clone, Default)]
+pub struct AgentProfiles {
+    totals: HashMap<SerendipityAgent, AgentTotals>,
+    traces: usize,
+}
+
+impl AgentProfiles {
+    /// No traces seen yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Profiles over `traces`
+    pub fn from_traces<'a, I: IntoIterator<Item = &'a SerendipityTrace>>(traces: I) -> Self {
+        let mut profiles = Self::new();
+        for trace in traces {
+            profiles.add_trace(trace);
+        }
+        profiles
+    }
+
+    /// Add a trace's events to the profiles
+    pub fn add_trace(&mut self, trace: &SerendipityTrace) {
+        let threshold = trace.key_discovery_threshold();
+        let peaks: HashSet<String> = trace.serendipity_peaks().into_iter().map(|peak| peak.event_id).collect();
+        let mut agents_seen = HashSet::new();
+        for event in &trace.events {
+            let totals = self.totals.entry(event.agent.clone()).or_insert_with(AgentTotals::new);
+            if agents_seen.insert(&event.agent) {
+                totals.traces += 1;
+            }
+            totals.events += 1;
+            totals.serendipity_sum += event.serendipity_score;
+            totals.peak_serendipity = totals.peak_serendipity.max(event.serendipity_score);
+            totals.confidence_sum += event.confidence;
+            *totals.languages.entry(event.language.clone()).or_insert(0) += 1;
+            *totals.stages.entry(event.stage.clone()).or_insert(0) += 1;
+            if event.serendipity_score > threshold {
+                totals.key_discoveries += 1;
+            }
+            if peaks.contains(&event.event_id) {
+                totals.serendipity_peaks += 1;
+            }
+        }
+        self.traces += 1;
+    }
+
+    /// Profile of one agent, if it carried out any event
+    pub fn profile(&self, agent: &SerendipityAgent) -> Option<AgentProfile> {
+        self.totals.get(agent).map(|totals| totals.profile(agent))
+    }
+
+    /// Profiles of every agent seen: built-in roles, then custom roles, each by name
+    pub fn profiles(&self) -> Vec<AgentProfile> {
+        let mut profiles: Vec<AgentProfile> = self.totals.iter().map(|(agent, totals)| totals.profile(agent)).collect();
+        profiles.sort_by_cached_key(|profile| (profile.agent.is_custom(), profile.agent.name()));
+        profiles
+    }
+
+    /// Agents ranked by `criteria`
+    pub fn report(&self, criteria: AgentRankingCriteria) -> AgentPerformanceReport {
+        let mut profiles = self.profiles();
+        // Sorting is stable, so ties keep the name order
+        profiles.sort_by(|a, b| criteria.value(b).total_cmp(&criteria.value(a)));
+        AgentPerformanceReport {
+            criteria,
+            traces: self.traces,
+            rankings: profiles
+                .into_iter()
+                .enumerate()
+                .map(|(i, profile)| AgentRanking { rank: i + 1, score: criteria.value(&profile), profile })
+                .collect(),
+        }
+    }
+}
+
+/// Agent's place in a performance report
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentRanking {
+    /// 1-based rank
+    pub rank: usize,
+    /// Value of the ranking criterion
+    pub score: f64,
+    pub profile: AgentProfile,
+}
+
+/// Agents ranked by one criterion
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentPerformanceReport {
+    pub criteria: AgentRankingCriteria,
+    /// Traces the profiles cover
+    pub traces: usize,
+    pub rankings: Vec<AgentRanking>,
+}
+
+impl AgentPerformanceReport {
+    /// Write the ranking to `out`
+    pub fn render<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "Agent performance by {:?} ({} traces)", self.criteria, self.traces)?;
+        for ranking in &self.rankings {
+            let profile = &ranking.profile;
+            writeln!(
+                out,
+                "  {:>2}. {:<20} {:>8.3}  events {:>4}  serendipity {:.3}  confidence {:.3}  peaks {}  typically {}",
+                ranking.rank,
+                profile.agent.name(),
+                ranking.score,
+                profile.events,
+                profile.mean_serendipity,
+                profile.mean_confidence,
+                profile.serendipity_peaks,
+                profile.typical_stage.name(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl SerendipityTrace {
+    /// Agent profiles of this trace alone
+    pub fn agent_profiles(&self) -> AgentProfiles {
+        AgentProfiles::from_traces([self])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(events: &[(SerendipityStage, SerendipityAgent, &str, f64)]) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        for (stage, agent, language, serendipity) in events {
+            trace.log_event(stage.clone(), agent.clone(), "Survey", "note", language, *serendipity, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_agent_profile() {
+        use SerendipityAgent::*;
+        use SerendipityStage::*;
+        let trace = trace(&[
+            (Exploration, Explorer, "en", 0.3),
+            (UnexpectedConnection, PatternRecognizer, "id", 0.9),
+            (Exploration, Explorer, "id", 0.5),
+            (Validation, Explorer, "en", 0.4),
+        ]);
+        let explorer = trace.agent_profiles().profile(&Explorer).unwrap();
+        assert_eq!((explorer.events, explorer.traces), (3, 1));
+        assert!((explorer.mean_serendipity - 0.4).abs() < 1e-12);
+        assert_eq!(explorer.peak_serendipity, 0.5);
+        assert_eq!(explorer.languages, BTreeMap::from([("en".to_string(), 2), ("id".to_string(), 1)]));
+        assert_eq!(explorer.typical_stage, Exploration);
+        assert_eq!(explorer.key_discoveries, 0);
+        assert!(trace.agent_profiles().profile(&Translator).is_none());
+    }
+
+    #[test]
+    fn test_report_ranks_agents_across_traces() {
+        use SerendipityAgent::*;
+        use SerendipityStage::*;
+        let first = trace(&[(Exploration, Explorer, "en", 0.3), (UnexpectedConnection, PatternRecognizer, "en", 0.9)]);
+        let second = trace(&[(Exploration, Explorer, "jv", 0.4), (Integration, Synthesizer, "en", 0.6)]);
+        let profiles = AgentProfiles::from_traces([&first, &second]);
+
+        let report = profiles.report(AgentRankingCriteria::Serendipity);
+        let order: Vec<&SerendipityAgent> = report.rankings.iter().map(|r| &r.profile.agent).collect();
+        assert_eq!(order, vec![&PatternRecognizer, &Synthesizer, &Explorer]);
+        assert_eq!(report.traces, 2);
+        assert_eq!(report.rankings[2].profile.traces, 2);
+
+        let by_events = profiles.report(AgentRankingCriteria::Events);
+        assert_eq!((by_events.rankings[0].rank, &by_events.rankings[0].profile.agent), (1, &Explorer));
+        assert_eq!(profiles.report(AgentRankingCriteria::KeyDiscoveries).rankings[0].score, 1.0);
+
+        let mut out = Vec::new();
+        report.render(&mut out).unwrap();
+        let lines: Vec<String> = String::from_utf8(out).unwrap().lines().map(str::to_string).collect();
+        assert_eq!(lines[0], "Agent performance by Serendipity (2 traces)");
+        assert!(lines[1].starts_with("   1. PatternRecognizer"), "{}", lines[1]);
+        assert_eq!(lines.len(), 4);
+    }
+}
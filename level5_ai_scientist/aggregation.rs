@@ -0,0 +1,234 @@
+// -*- coding: utf-8 -*-
+//! Serendipity Aggregation Strategies
+//!
+//! A plain mean washes out a single breakthrough in a long trace. Each trace
+//! carries an `AggregationStrategy` so different benchmarks can reward
+//! different serendipity profiles (steady exploration vs. rare peaks).
+//! `RunningAggregate` computes the same score one event at a time for
+//! streaming traces, keeping only what the strategy needs.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::serendipity_trace::SerendipityEvent;
+
+/// How event serendipity scores are combined into the overall score
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum AggregationStrategy {
+    /// Arithmetic mean of all events
+    #[default]
+    Mean,
+    /// Highest single event
+    Max,
+    /// Mean weighted by recency: an event `half_life_secs` older than the
+    /// latest event counts half as much
+    TimeDecayedMean { half_life_secs: f64 },
+    /// Mean of the `k` highest-scoring events
+    TopKMean { k: usize },
+    /// Blend of peak and mean: `peak_weight * max + (1 - peak_weight) * mean`
+    PeakWeighted { peak_weight: f64 },
+}
+
+impl AggregationStrategy {
+    /// Aggregate credited event serendipity scores (0.0 for an empty trace).
+    /// Events with an unverified benchmark count at most `UNVERIFIED_SERENDIPITY_CAP`.
+    pub fn aggregate(&self, events: &[SerendipityEvent]) -> f64 {
+        if events.is_empty() {
+            return 0.0;
+        }
+
+        let mean = || events.iter().map(|e| e.credited_serendipity()).sum::<f64>() / events.len() as f64;
+        let max = || events.iter().map(|e| e.credited_serendipity()).fold(f64::MIN, f64::max);
+
+        match *self {
+            AggregationStrategy::Mean => mean(),
+            AggregationStrategy::Max => max(),
+            AggregationStrategy::TimeDecayedMean { half_life_secs } => {
+                if half_life_secs <= 0.0 {
+                    return mean();
+                }
+                let latest = events.iter().map(|e| e.timestamp).max().unwrap();
+                let (weighted, total) = events.iter().fold((0.0, 0.0), |(weighted, total), e| {
+                    let age_secs = (latest - e.timestamp).num_milliseconds() as f64 / 1000.0;
+                    let weight = 0.5f64.powf(age_secs / half_life_secs);
+                    (weighted + weight * e.credited_serendipity(), total + weight)
+                });
+                weighted / total
+            }
+            AggregationStrategy::TopKMean { k } => {
+                let mut scores: Vec<f64> = events.iter().map(|e| e.credited_serendipity()).collect();
+                scores.sort_by(|a, b| b.total_cmp(a));
+                let k = k.clamp(1, scores.len());
+                scores[..k].iter().sum::<f64>() / k as f64
+            }
+            AggregationStrategy::PeakWeighted { peak_weight } => {
+                let peak_weight = peak_weight.clamp(0.0, 1.0);
+                peak_weight * max() + (1.0 - peak_weight) * mean()
+            }
+        }
+    }
+}
+
+/// Aggregate kept up to date as events arrive, in memory bounded by the
+/// strategy (the `k` highest scores for `TopKMean`). Equal to `aggregate`
+/// over the same events, up to rounding for `TimeDecayedMean`.
+#[derive(Debug, Clone)]
+pub struct RunningAggregate {
+    strategy: AggregationStrategy,
+    count: usize,
+    sum: f64,
+    max: f64,
+    /// Highest scores, in descending order (`TopKMean` only)
+    top: Vec<f64>,
+    /// Latest timestamp, then decayed weighted score and weight sums
+    /// relative to it (`TimeDecayedMean` only)
+    decayed: Option<(DateTime<Utc>, f64, f64)>,
+}
+
+impl RunningAggregate {
+    /// Empty aggregate for `strategy`
+    pub fn new(strategy: AggregationStrategy) -> Self {
+        Self { strategy, count: 0, sum: 0.0, max: f64::MIN, top: Vec::new(), decayed: None }
+    }
+
+    /// Strategy being computed
+    pub fn strategy(&self) -> AggregationStrategy {
+        self.strategy
+    }
+
+    /// Add an event's credited score
+    pub fn push(&mut self, event: &SerendipityEvent) {
+        let score = event.credited_serendipity();
+        self.count += 1;
+        self.sum += score;
+        self.max = self.max.max(score);
+        match self.strategy {
+            AggregationStrategy::TopKMean { k } => {
+                let at = self.top.partition_point(|kept| kept.total_cmp(&score).is_ge());
+                self.top.insert(at, score);
+                self.top.truncate(k.max(1));
+            }
+            AggregationStrategy::TimeDecayedMean { half_life_secs } if half_life_secs > 0.0 => {
+                let decay = |from: DateTime<Utc>, to: DateTime<Utc>| {
+                    0.5f64.powf(((to - from).num_milliseconds() as f64 / 1000.0) / half_life_secs)
+                };
+                let (latest, weighted, total) = match self.decayed {
+                    // A newer event ages everything seen so far
+                    Some((latest, weighted, total)) if event.timestamp > latest => {
+                        let factor = decay(latest, event.timestamp);
+                        (event.timestamp, weighted * factor, total * factor)
+                    }
+                    Some(running) => running,
+                    None => (event.timestamp, 0.0, 0.0),
+                };
+                let weight = decay(event.timestamp, latest);
+                self.decayed = Some((latest, weighted + weight * score, total + weight));
+            }
+            _ => {}
+        }
+    }
+
+    /// Aggregate of the events pushed so far (0.0 before the first)
+    pub fn value(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let mean = self.sum / self.count as f64;
+        match self.strategy {
+            AggregationStrategy::Mean => mean,
+            AggregationStrategy::Max => self.max,
+            AggregationStrategy::TimeDecayedMean { .. } => match self.decayed {
+                Some((_, weighted, total)) => weighted / total,
+                None => mean,
+            },
+            AggregationStrategy::TopKMean { .. } => self.top.iter().sum::<f64>() / self.top.len() as f64,
+            AggregationStrategy::PeakWeighted { peak_weight } => {
+                let peak_weight = peak_weight.clamp(0.0, 1.0);
+                peak_weight * self.max + (1.0 - peak_weight) * mean
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, SerendipityAgent};
+
+    fn breakthrough_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        for score in [0.2, 0.2, 0.2, 1.0] {
+            trace.log_event(
+                SerendipityStage::Exploration,
+                SerendipityAgent::Explorer,
+                "input",
+                "output",
+                "en",
+                score,
+                0.9,
+            ).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_strategies() {
+        let events = breakthrough_trace().events;
+        assert!((AggregationStrategy::Mean.aggregate(&events) - 0.4).abs() < 1e-9);
+        assert_eq!(AggregationStrategy::Max.aggregate(&events), 1.0);
+        assert!((AggregationStrategy::TopKMean { k: 2 }.aggregate(&events) - 0.6).abs() < 1e-9);
+        assert!((AggregationStrategy::PeakWeighted { peak_weight: 0.5 }.aggregate(&events) - 0.7).abs() < 1e-9);
+        assert_eq!(AggregationStrategy::Max.aggregate(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_time_decayed_mean() {
+        let mut events = breakthrough_trace().events;
+        let latest = events[3].timestamp;
+        for (i, event) in events.iter_mut().enumerate() {
+            event.timestamp = latest - Duration::seconds(10 * (3 - i as i64));
+        }
+        let decayed = AggregationStrategy::TimeDecayedMean { half_life_secs: 10.0 }.aggregate(&events);
+        // Weights 1/8, 1/4, 1/2, 1
+        let expected = (0.2 * (0.125 + 0.25 + 0.5) + 1.0) / 1.875;
+        assert!((decayed - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_running_aggregate_matches_batch() {
+        let mut events = breakthrough_trace().events;
+        let latest = events[3].timestamp;
+        for (i, event) in events.iter_mut().enumerate() {
+            event.timestamp = latest - Duration::seconds(7 * ((i as i64 + 2) % 4));
+        }
+        for strategy in [
+            AggregationStrategy::Mean,
+            AggregationStrategy::Max,
+            AggregationStrategy::TimeDecayedMean { half_life_secs: 10.0 },
+            AggregationStrategy::TimeDecayedMean { half_life_secs: 0.0 },
+            AggregationStrategy::TopKMean { k: 2 },
+            AggregationStrategy::TopKMean { k: 0 },
+            AggregationStrategy::PeakWeighted { peak_weight: 0.5 },
+        ] {
+            let mut running = RunningAggregate::new(strategy);
+            assert_eq!(running.value(), 0.0);
+            for (i, event) in events.iter().enumerate() {
+                running.push(event);
+                let batch = strategy.aggregate(&events[..=i]);
+                assert!((running.value() - batch).abs() < 1e-12, "{:?}: {} vs {}", strategy, running.value(), batch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trace_strategy() {
+        let mut trace = breakthrough_trace();
+        trace.set_aggregation(AggregationStrategy::Max);
+        assert_eq!(trace.overall_serendipity, 1.0);
+
+        let json = serde_json::to_string(&trace).unwrap();
+        let restored: SerendipityTrace = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.aggregation, AggregationStrategy::Max);
+    }
+}
@@ -0,0 +1,175 @@
+// -*- coding: utf-8 -*-
+//! Multilingual Alignment
+//!
+//! Scores how well a text lines up with its translation. The semantic score
+//! is a character-bigram overlap (a stand-in for embeddings: names, numbers,
+//! and loanwords carry across languages), the structural score compares
+//! length and punctuation, and the cultural score compares the languages'
+//! family and script from the `LanguageRegistry`. Overall scores are kept per
+//! language pair for averaging.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::language_registry::LanguageRegistry;
+
+/// Weights of the semantic, structural, and cultural scores in the overall score
+const SCORE_WEIGHTS: (f64, f64, f64) = (0.5, 0.3, 0.2);
+
+/// Alignment of a text with its translation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlignmentResult {
+    /// Weighted combination of the three scores (0.0-1.0)
+    pub overall_score: f64,
+    /// Shared character bigrams, Dice coefficient (0.0-1.0)
+    pub semantic_score: f64,
+    /// Length and punctuation agreement (0.0-1.0)
+    pub structural_score: f64,
+    /// Language family and script agreement (0.0-1.0)
+    pub cultural_score: f64,
+}
+
+/// Aligner keeping the overall scores of every language pair it aligned
+#[derive(Debug, Clone, Default)]
+pub struct MultilingualAligner {
+    /// "source-target" -> overall scores, in alignment order
+    history: HashMap<String, Vec<f64>>,
+}
+
+impl MultilingualAligner {
+    /// Create an aligner with no history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Align `target_text` (in `target_lang`) with `source_text` (in `source_lang`)
+    pub fn align(&mut self, source_text: &str, target_text: &str, source_lang: &str, target_lang: &str) -> AlignmentResult {
+        let semantic_score = bigram_overlap(source_text, target_text);
+        let structural_score = structural_agreement(source_text, target_text);
+        let cultural_score = cultural_agreement(source_lang, target_lang);
+        let (semantic, structural, cultural) = SCORE_WEIGHTS;
+        let result = AlignmentResult {
+            overall_score: semantic * semantic_score + structural * structural_score + cultural * cultural_score,
+            semantic_score,
+            structural_score,
+            cultural_score,
+        };
+        self.history.entry(pair_key(source_lang, target_lang)).or_default().push(result.overall_score);
+        result
+    }
+
+    /// Mean overall score of the pair's alignments, if any
+    pub fn get_average_alignment(&self, source_lang: &str, target_lang: &str) -> Option<f64> {
+        let scores = self.history.get(&pair_key(source_lang, target_lang))?;
+        Some(scores.iter().sum::<f64>() / scores.len() as f64)
+    }
+
+    /// Number of alignments of the pair
+    pub fn alignment_count(&self, source_lang: &str, target_lang: &str) -> usize {
+        self.history.get(&pair_key(source_lang, target_lang)).map_or(0, |scores| scores.len())
+    }
+
+    /// Language pairs aligned so far ("source-target"), sorted
+    pub fn language_pairs(&self) -> Vec<String> {
+        let mut pairs: Vec<String> = self.history.keys().cloned().collect();
+        pairs.sort();
+        pairs
+    }
+}
+
+fn pair_key(source_lang: &str, target_lang: &str) -> String {
+    let registry = LanguageRegistry::global();
+    format!("{}-{}", registry.canonical(source_lang), registry.canonical(target_lang))
+}
+
+/// Lowercased letter and digit bigrams within words
+fn bigrams(text: &str) -> HashSet<(char, char)> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .flat_map(|word| {
+            let chars: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+            chars.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn bigram_overlap(source: &str, target: &str) -> f64 {
+    let (source, target) = (bigrams(source), bigrams(target));
+    if source.is_empty() && target.is_empty() {
+        return 1.0;
+    }
+    2.0 * source.intersection(&target).count() as f64 / (source.len() + target.len()) as f64
+}
+
+/// Ratio of the smaller count to the larger (1.0 when both are zero)
+fn count_ratio(a: usize, b: usize) -> f64 {
+    if a.max(b) == 0 {
+        1.0
+    } else {
+        a.min(b) as f64 / a.max(b) as f64
+    }
+}
+
+fn structural_agreement(source: &str, target: &str) -> f64 {
+    let length = count_ratio(source.chars().count(), target.chars().count());
+    let punctuation = |text: &str| text.chars().filter(|c| c.is_ascii_punctuation()).count();
+    0.7 * length + 0.3 * count_ratio(punctuation(source), punctuation(target))
+}
+
+fn cultural_agreement(source_lang: &str, target_lang: &str) -> f64 {
+    let registry = LanguageRegistry::global();
+    let primary = |tag: &str| registry.canonical(tag).split('-').next().unwrap_or_default().to_string();
+    if primary(source_lang) == primary(target_lang) {
+        return 1.0;
+    }
+    let same = |a: Option<&str>, b: Option<&str>| a.is_some() && a == b;
+    let mut score = 0.2;
+    if same(registry.family(source_lang), registry.family(target_lang)) {
+        score += 0.4;
+    }
+    if same(registry.script(source_lang), registry.script(target_lang)) {
+        score += 0.4;
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_stay_in_range() {
+        let mut aligner = MultilingualAligner::new();
+        let same = aligner.align("Batik patterns, 1998.", "Batik patterns, 1998.", "en", "en");
+        assert_eq!(same.overall_score, 1.0);
+
+        let translated = aligner.align("Hello world", "Halo dunia", "en", "id");
+        assert!(translated.semantic_score > 0.0 && translated.semantic_score < 1.0);
+        assert!(translated.overall_score > 0.0 && translated.overall_score < same.overall_score);
+        assert_eq!(aligner.align("", "", "en", "id").semantic_score, 1.0);
+        assert_eq!(aligner.align("abc", "", "en", "id").semantic_score, 0.0);
+    }
+
+    #[test]
+    fn test_cultural_score_follows_family_and_script() {
+        let mut aligner = MultilingualAligner::new();
+        let javanese = aligner.align("a", "a", "id", "jv").cultural_score;
+        let english = aligner.align("a", "a", "id", "en").cultural_score;
+        let russian = aligner.align("a", "a", "en", "ru").cultural_score;
+        let unknown = aligner.align("a", "a", "en", "xx").cultural_score;
+        assert!(javanese > english);
+        assert!(russian < javanese);
+        assert_eq!(unknown, 0.2);
+    }
+
+    #[test]
+    fn test_history_per_language_pair() {
+        let mut aligner = MultilingualAligner::new();
+        assert!(aligner.get_average_alignment("en", "id").is_none());
+        let first = aligner.align("Hello world", "Halo dunia", "en", "id").overall_score;
+        let second = aligner.align("Batik", "Batik", "EN", "id").overall_score;
+        aligner.align("Batik", "Batik", "id", "en");
+
+        assert_eq!(aligner.alignment_count("en", "id"), 2);
+        assert!((aligner.get_average_alignment("en", "id").unwrap() - (first + second) / 2.0).abs() < 1e-12);
+        assert_eq!(aligner.language_pairs(), vec!["en-id", "id-en"]);
+    }
+}
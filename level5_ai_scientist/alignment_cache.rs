@@ -0,0 +1,192 @@
+// -*- coding: utf-8 -*-
+//! Alignment Result Caching
+//!
+//! Translation summaries align the same (source, target, language, language)
+//! tuples every time they are rebuilt. `CachedAligner` wraps a
+//! `MultilingualAligner` with a least-recently-used cache of results keyed by
+//! that tuple, of configurable capacity (0 disables caching), and counts hits
+//! and misses. Cache hits do not reach the wrapped aligner.
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::alignment::{AlignmentResult, MultilingualAligner};
+
+/// Alignment results kept by default
+pub const DEFAULT_ALIGNMENT_CACHE_CAPACITY: usize = 1024;
+
+/// Text pair and languages an alignment was computed for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct AlignmentKey {
+    source_text: String,
+    target_text: String,
+    source_lang: String,
+    target_lang: String,
+}
+
+/// Hit/miss counters of an alignment cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlignmentCacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that ran the aligner
+    pub misses: u64,
+    /// Results dropped to stay within capacity
+    pub evictions: u64,
+    /// Results currently cached
+    pub entries: usize,
+    /// Maximum results kept
+    pub capacity: usize,
+}
+
+impl AlignmentCacheStats {
+    /// Fraction of lookups answered from the cache (0.0 before any lookup)
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// `MultilingualAligner` with an LRU cache of alignment results
+pub struct CachedAligner {
+    aligner: MultilingualAligner,
+    capacity: usize,
+    /// Cached result and last-use tick per key
+    entries: HashMap<AlignmentKey, (AlignmentResult, u64)>,
+    /// Keys by last-use tick, least recent first
+    recency: BTreeMap<u64, AlignmentKey>,
+    tick: u64,
+    stats: AlignmentCacheStats,
+}
+
+impl CachedAligner {
+    /// Cache of `DEFAULT_ALIGNMENT_CACHE_CAPACITY` results over a new aligner
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_ALIGNMENT_CACHE_CAPACITY)
+    }
+
+    /// Cache of `capacity` results over a new aligner
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::wrap(MultilingualAligner::new(), capacity)
+    }
+
+    /// Cache of `capacity` results over an existing aligner
+    pub fn wrap(aligner: MultilingualAligner, capacity: usize) -> Self {
+        Self {
+            aligner,
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            stats: AlignmentCacheStats { capacity, ..AlignmentCacheStats::default() },
+        }
+    }
+
+    /// Align `source_text` with `target_text`, reusing a cached result for
+    /// the same texts and languages
+    pub fn align(
+        &mut self,
+        source_text: &str,
+        target_text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> AlignmentResult {
+        let key = AlignmentKey {
+            source_text: source_text.to_string(),
+            target_text: target_text.to_string(),
+            source_lang: source_lang.to_string(),
+            target_lang: target_lang.to_string(),
+        };
+        self.tick += 1;
+
+        if let Some((result, last_used)) = self.entries.get_mut(&key) {
+            self.stats.hits += 1;
+            #[cfg(feature = "metrics")]
+            crate::prometheus::RecorderMetrics::global().record_alignment_lookup(true);
+            let key = self.recency.remove(last_used).expect("cached key has a recency entry");
+            *last_used = self.tick;
+            self.recency.insert(self.tick, key);
+            return result.clone();
+        }
+
+        self.stats.misses += 1;
+        #[cfg(feature = "metrics")]
+        crate::prometheus::RecorderMetrics::global().record_alignment_lookup(false);
+        let result = self.aligner.align(source_text, target_text, source_lang, target_lang);
+        if self.capacity > 0 {
+            if self.entries.len() == self.capacity {
+                if let Some((_, oldest)) = self.recency.pop_first() {
+                    self.entries.remove(&oldest);
+                    self.stats.evictions += 1;
+                }
+            }
+            self.recency.insert(self.tick, key.clone());
+            self.entries.insert(key, (result.clone(), self.tick));
+        }
+        result
+    }
+
+    /// Hit/miss counters and current size
+    pub fn cache_stats(&self) -> AlignmentCacheStats {
+        AlignmentCacheStats { entries: self.entries.len(), ..self.stats }
+    }
+
+    /// Drop all cached results, keeping the counters
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    /// The wrapped aligner
+    pub fn aligner(&self) -> &MultilingualAligner {
+        &self.aligner
+    }
+}
+
+impl Default for CachedAligner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_pairs_hit_the_cache() {
+        let mut aligner = CachedAligner::new();
+        let first = aligner.align("Batik patterns", "Pola batik", "en", "id");
+        let again = aligner.align("Batik patterns", "Pola batik", "en", "id");
+        assert_eq!(again.overall_score, first.overall_score);
+        aligner.align("Batik patterns", "Pola batik", "en", "jv");
+
+        let stats = aligner.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 2, 2));
+        assert!((stats.hit_rate() - 1.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let mut aligner = CachedAligner::with_capacity(2);
+        aligner.align("a", "b", "en", "id");
+        aligner.align("c", "d", "en", "id");
+        aligner.align("a", "b", "en", "id");
+        // Evicts ("c", "d"), the least recently used
+        aligner.align("e", "f", "en", "id");
+        aligner.align("a", "b", "en", "id");
+        aligner.align("c", "d", "en", "id");
+
+        let stats = aligner.cache_stats();
+        assert_eq!((stats.hits, stats.misses), (2, 4));
+        assert_eq!((stats.entries, stats.evictions), (2, 2));
+
+        let mut uncached = CachedAligner::with_capacity(0);
+        uncached.align("a", "b", "en", "id");
+        uncached.align("a", "b", "en", "id");
+        assert_eq!((uncached.cache_stats().hits, uncached.cache_stats().entries), (0, 0));
+    }
+}
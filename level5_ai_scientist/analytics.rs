@@ -0,0 +1,178 @@
+// -*- coding: utf-8 -*-
+//! Sliding-Window Serendipity Analytics
+//!
+//! The overall score says how serendipitous a session was, not when the
+//! breakthrough happened. `serendipity_timeline` smooths event scores with a
+//! trailing window and reports the rate of change at every event, bursts
+//! where the smoothed score jumps well above its typical change, and
+//! inflection points where it turns from rising to falling or back.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::serendipity_trace::SerendipityTrace;
+
+/// Rate-of-change z-score above which a change counts as a burst
+pub const DEFAULT_BURST_Z_SCORE: f64 = 1.5;
+
+/// Smoothed serendipity at one event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelinePoint {
+    /// Event index
+    pub index: usize,
+    /// Event ID
+    pub event_id: String,
+    /// Event time
+    pub timestamp: DateTime<Utc>,
+    /// Event serendipity score
+    pub serendipity: f64,
+    /// Mean score over the trailing window ending at this event
+    pub rolling_mean: f64,
+    /// Change in the rolling mean since the previous event (0 for the first)
+    pub rate_of_change: f64,
+}
+
+/// Direction change of the rolling mean
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InflectionKind {
+    /// Rising, then falling
+    Peak,
+    /// Falling, then rising
+    Trough,
+}
+
+/// Event where the rolling mean changes direction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InflectionPoint {
+    /// Event index
+    pub index: usize,
+    /// Kind of turn
+    pub kind: InflectionKind,
+}
+
+/// Rolling serendipity over a trace with bursts and inflection points
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SerendipityTimeline {
+    /// Trailing window size, in events
+    pub window: usize,
+    /// One point per event
+    pub points: Vec<TimelinePoint>,
+    /// Indices of events whose rate of change is a burst
+    pub bursts: Vec<usize>,
+    /// Direction changes of the rolling mean
+    pub inflections: Vec<InflectionPoint>,
+}
+
+impl SerendipityTimeline {
+    /// Indices of events whose rate of change lies more than `z_score`
+    /// standard deviations above the mean rate of change
+    pub fn bursts_above(&self, z_score: f64) -> Vec<usize> {
+        let rates: Vec<f64> = self.points.iter().skip(1).map(|p| p.rate_of_change).collect();
+        if rates.is_empty() {
+            return Vec::new();
+        }
+        let mean = rates.iter().sum::<f64>() / rates.len() as f64;
+        let std = (rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / rates.len() as f64).sqrt();
+        if std == 0.0 {
+            return Vec::new();
+        }
+        self.points
+            .iter()
+            .skip(1)
+            .filter(|p| (p.rate_of_change - mean) / std > z_score)
+            .map(|p| p.index)
+            .collect()
+    }
+
+    /// Point with the steepest rise: when the breakthrough happened
+    pub fn breakthrough(&self) -> Option<&TimelinePoint> {
+        self.points
+            .iter()
+            .skip(1)
+            .filter(|p| p.rate_of_change > 0.0)
+            .max_by(|a, b| a.rate_of_change.total_cmp(&b.rate_of_change))
+    }
+}
+
+impl SerendipityTrace {
+    /// Rolling serendipity over a trailing window of `window` events
+    /// (at least 1)
+    pub fn serendipity_timeline(&self, window: usize) -> SerendipityTimeline {
+        let window = window.max(1);
+        let mut points: Vec<TimelinePoint> = Vec::with_capacity(self.events.len());
+        for (index, event) in self.events.iter().enumerate() {
+            let start = (index + 1).saturating_sub(window);
+            let scores = &self.events[start..=index];
+            let rolling_mean = scores.iter().map(|e| e.serendipity_score).sum::<f64>() / scores.len() as f64;
+            let rate_of_change = points.last().map_or(0.0, |p| rolling_mean - p.rolling_mean);
+            points.push(TimelinePoint {
+                index,
+                event_id: event.event_id.clone(),
+                timestamp: event.timestamp,
+                serendipity: event.serendipity_score,
+                rolling_mean,
+                rate_of_change,
+            });
+        }
+
+        // Compare each non-flat change with the previous non-flat one
+        let mut inflections = Vec::new();
+        let mut previous: Option<f64> = None;
+        for point in points.iter().skip(1).filter(|p| p.rate_of_change != 0.0) {
+            if let Some(previous) = previous {
+                if previous.signum() != point.rate_of_change.signum() {
+                    let kind = if previous > 0.0 { InflectionKind::Peak } else { InflectionKind::Trough };
+                    inflections.push(InflectionPoint { index: point.index - 1, kind });
+                }
+            }
+            previous = Some(point.rate_of_change);
+        }
+
+        let mut timeline = SerendipityTimeline { window, points, bursts: Vec::new(), inflections };
+        timeline.bursts = timeline.bursts_above(DEFAULT_BURST_Z_SCORE);
+        timeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn trace(scores: &[f64]) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        for (i, score) in scores.iter().enumerate() {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", &format!("out {}", i), "en", *score, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_rolling_mean_and_breakthrough() {
+        let trace = trace(&[0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.9, 0.7, 0.3]);
+        let timeline = trace.serendipity_timeline(2);
+        assert_eq!(timeline.points.len(), 11);
+        assert!((timeline.points[8].rolling_mean - 0.55).abs() < 1e-9);
+        assert_eq!(timeline.points[0].rate_of_change, 0.0);
+        assert_eq!(timeline.breakthrough().unwrap().index, 8);
+        assert_eq!(timeline.bursts, vec![8]);
+        assert_eq!(
+            timeline.inflections,
+            vec![InflectionPoint { index: 9, kind: InflectionKind::Peak }]
+        );
+    }
+
+    #[test]
+    fn test_flat_and_empty_traces() {
+        let flat = trace(&[0.5; 4]).serendipity_timeline(3);
+        assert!(flat.bursts.is_empty() && flat.inflections.is_empty() && flat.breakthrough().is_none());
+
+        let wave = trace(&[0.1, 0.8, 0.1, 0.8]).serendipity_timeline(0);
+        assert_eq!(wave.window, 1);
+        let kinds: Vec<InflectionKind> = wave.inflections.iter().map(|i| i.kind).collect();
+        assert_eq!(kinds, vec![InflectionKind::Peak, InflectionKind::Trough]);
+
+        let empty = SerendipityTrace::new("sari", "backend", "Journavx").serendipity_timeline(3);
+        assert!(empty.points.is_empty() && empty.bursts.is_empty());
+    }
+}
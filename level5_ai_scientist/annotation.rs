@@ -0,0 +1,36 @@
+// -*- coding: utf-8 -*-
+//! Trace Annotations
+//!
+//! Audit notes attached to a trace for later review: stage-policy violations
+//! logged under a lenient policy, language tags that disagree with the
+//! detected language of the event text, fitted event topics, and text
+//! stripped by redaction rules.
+
+use serde::{Deserialize, Serialize};
+use crate::stage_policy::StageViolation;
+use crate::language_detection::LanguageMismatch;
+use crate::topics::EventTopic;
+use crate::redaction_rules::RuleRedaction;
+
+/// What an annotation records
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    /// Event broke a lenient stage policy
+    StagePolicy(StageViolation),
+    /// Declared language tag disagrees with the detected language
+    LanguageMismatch(LanguageMismatch),
+    /// Topic the event's output was clustered into
+    Topic(EventTopic),
+    /// Redaction rules replaced parts of the event text
+    Redacted(RuleRedaction),
+}
+
+/// Audit note attached to a trace
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceAnnotation {
+    /// Event the note refers to
+    pub event_id: String,
+    /// What was recorded
+    pub kind: AnnotationKind,
+}
@@ -0,0 +1,178 @@
+// -*- coding: utf-8 -*-
+//! Serendipity Anomaly Detection
+//!
+//! A high serendipity score is not by itself an unexpected connection: noisy
+//! scorers jitter from event to event. The detector looks at the jumps in
+//! serendipity between consecutive events and flags those that are outliers
+//! of the trace's own jump distribution by modified z-score
+//! (0.6745 · (x − median) / MAD, Iglewicz and Hoaglin), which a few large
+//! jumps cannot mask the way they inflate a standard deviation. Rises
+//! scoring at least the threshold (3.5 by default) are reported in the folded
+//! trace as `serendipity_peaks`; traces with fewer than five events have too
+//! few jumps to judge and report none.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::{SerendipityEvent, SerendipityStage, SerendipityTrace};
+
+/// Modified z-score at or above which a jump is anomalous
+pub const DEFAULT_PEAK_Z_SCORE: f64 = 3.5;
+
+/// Fewest events whose jumps are judged
+pub const DEFAULT_MIN_PEAK_EVENTS: usize = 5;
+
+/// Scale making the MAD consistent with the standard deviation of normal data
+const MAD_SCALE: f64 = 0.6745;
+
+/// Scale of the mean absolute deviation, used when the MAD is zero
+const MEAN_AD_SCALE: f64 = 1.253314;
+
+/// Spread below which deviations are floating-point rounding
+const MIN_SPREAD: f64 = 1e-9;
+
+/// Event whose rise in serendipity is anomalous for its trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerendipityPeak {
+    pub event_id: String,
+    pub stage: SerendipityStage,
+    /// Serendipity of the event
+    pub serendipity: f64,
+    /// Rise over the previous event's serendipity
+    pub jump: f64,
+    /// Modified z-score of the jump
+    pub z_score: f64,
+}
+
+/// Modified z-score outlier test over serendipity jumps
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyDetector {
+    /// Modified z-score at or above which a rise is a peak
+    pub z_threshold: f64,
+    /// Fewest events for which peaks are reported
+    pub min_events: usize,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self { z_threshold: DEFAULT_PEAK_Z_SCORE, min_events: DEFAULT_MIN_PEAK_EVENTS }
+    }
+}
+
+/// Median of `values`, reordering them
+fn median(values: &mut [f64]) -> f64 {
+    let even = values.len().is_multiple_of(2);
+    let (below, upper, _) = values.select_nth_unstable_by(values.len() / 2, f64::total_cmp);
+    let upper = *upper;
+    if even {
+        let lower = below.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        (lower + upper) / 2.0
+    } else {
+        upper
+    }
+}
+
+/// Modified z-scores of `values`, falling back to the mean absolute
+/// deviation when more than half the values are equal; `None` when all are
+/// (to rounding)
+pub fn modified_z_scores(values: &[f64]) -> Option<Vec<f64>> {
+    if values.is_empty() {
+        return None;
+    }
+    let center = median(&mut values.to_vec());
+    let mut deviations: Vec<f64> = values.iter().map(|v| (v - center).abs()).collect();
+    let mean_deviation = deviations.iter().sum::<f64>() / deviations.len() as f64;
+    let mad = median(&mut deviations);
+    let scale = if mad > MIN_SPREAD {
+        mad / MAD_SCALE
+    } else if mean_deviation > MIN_SPREAD {
+        MEAN_AD_SCALE * mean_deviation
+    } else {
+        return None;
+    };
+    Some(values.iter().map(|v| (v - center) / scale).collect())
+}
+
+/// Change in serendipity from each event to the next
+pub(crate) fn serendipity_jumps(events: &[SerendipityEvent]) -> Vec<f64> {
+    events.windows(2).map(|pair| pair[1].serendipity_score - pair[0].serendipity_score).collect()
+}
+
+impl AnomalyDetector {
+    /// Peaks among `events`, given their jumps (`serendipity_jumps`)
+    pub(crate) fn peaks_from_jumps(&self, events: &[SerendipityEvent], jumps: &[f64]) -> Vec<SerendipityPeak> {
+        if events.len() < self.min_events.max(2) {
+            return Vec::new();
+        }
+        let Some(z_scores) = modified_z_scores(jumps) else {
+            return Vec::new();
+        };
+        jumps
+            .iter()
+            .zip(z_scores)
+            .enumerate()
+            .filter(|&(_, (&jump, z_score))| jump > 0.0 && z_score >= self.z_threshold)
+            .map(|(i, (&jump, z_score))| {
+                let event = &events[i + 1];
+                SerendipityPeak {
+                    event_id: event.event_id.clone(),
+                    stage: event.stage.clone(),
+                    serendipity: event.serendipity_score,
+                    jump,
+                    z_score,
+                }
+            })
+            .collect()
+    }
+
+    /// Events whose rise in serendipity is anomalous among `events`
+    pub fn peaks(&self, events: &[SerendipityEvent]) -> Vec<SerendipityPeak> {
+        self.peaks_from_jumps(events, &serendipity_jumps(events))
+    }
+}
+
+impl SerendipityTrace {
+    /// Serendipity peaks under the default detector, as recorded by `fold_memory`
+    pub fn serendipity_peaks(&self) -> Vec<SerendipityPeak> {
+        AnomalyDetector::default().peaks(&self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityAgent;
+
+    fn trace(scores: &[f64]) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        for (i, &score) in scores.iter().enumerate() {
+            let stage = if score > 0.8 { SerendipityStage::UnexpectedConnection } else { SerendipityStage::Exploration };
+            trace.log_event(stage, SerendipityAgent::Explorer, "Survey", &format!("note {}", i), "en", score, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_modified_z_scores() {
+        let z = modified_z_scores(&[1.0, 2.0, 3.0, 4.0, 100.0]).unwrap();
+        assert_eq!(z[2], 0.0);
+        assert!((z[4] - MAD_SCALE * 97.0).abs() < 1e-9);
+        // MAD is zero: falls back to the mean absolute deviation
+        let z = modified_z_scores(&[0.0, 0.0, 0.0, 1.0]).unwrap();
+        assert!((z[3] - 1.0 / (MEAN_AD_SCALE * 0.25)).abs() < 1e-9);
+        assert_eq!(modified_z_scores(&[0.5, 0.5]), None);
+    }
+
+    #[test]
+    fn test_peaks_separate_breakthroughs_from_noise() {
+        let noisy = trace(&[0.30, 0.34, 0.31, 0.35, 0.32, 0.95, 0.36, 0.33, 0.37]);
+        let peaks = noisy.serendipity_peaks();
+        assert_eq!(peaks.len(), 1);
+        assert_eq!(peaks[0].event_id, noisy.events[5].event_id);
+        assert_eq!(peaks[0].stage, SerendipityStage::UnexpectedConnection);
+        assert!((peaks[0].jump - 0.63).abs() < 1e-9);
+        assert_eq!(noisy.fold_memory().unwrap().serendipity_peaks, peaks);
+
+        // A steady climb has no outlying jump; short traces are not judged
+        assert!(trace(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]).serendipity_peaks().is_empty());
+        assert!(trace(&[0.1, 0.1, 0.9]).serendipity_peaks().is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+// -*- coding: utf-8 -*-
+//! Trace Anonymization for Public Benchmarks
+//!
+//! `anonymize` produces a publishable copy of a trace: the contributor ID (and
+//! the trace ID, which embeds it) is replaced by a salted pseudonym, event text
+//! longer than the policy's threshold is replaced by a salted hash or a
+//! redaction marker, and free-form metadata is dropped unless kept explicitly.
+//!
+//! Pseudonyms are deterministic for a given salt, so one contributor's traces
+//! stay linkable and re-anonymizing gives the same result. Replaced text gets
+//! a redaction tombstone whose content hash covers the anonymized text, so
+//! anyone can recompute the anonymized trace's provenance hash from the
+//! published form, while the salt holder can still prove the original text.
+
+use chrono::Utc;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEvent};
+use crate::redaction::{RedactionTombstone, salted_hash};
+
+/// Text longer than this (in bytes) is anonymized by default
+pub const DEFAULT_MAX_TEXT_LEN: usize = 256;
+
+/// Hex digits of the salted hash kept in pseudonyms
+const PSEUDONYM_LEN: usize = 16;
+
+/// How over-long event text is replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextTreatment {
+    /// Replace with `[sha256:<salted hash>]`
+    #[default]
+    Hash,
+    /// Replace with `[REDACTED]`
+    Redact,
+}
+
+/// What `anonymize` removes from a trace
+#[derive(Debug, Clone)]
+pub struct AnonymizationPolicy {
+    salt: Vec<u8>,
+    /// Input/output text longer than this is replaced
+    pub max_text_len: usize,
+    /// Replacement for over-long text
+    pub text: TextTreatment,
+    /// Keep event metadata (dropped by default)
+    pub keep_metadata: bool,
+}
+
+impl AnonymizationPolicy {
+    /// Policy with the given pseudonymization salt and default settings
+    pub fn new(salt: &[u8]) -> Self {
+        Self {
+            salt: salt.to_vec(),
+            max_text_len: DEFAULT_MAX_TEXT_LEN,
+            text: TextTreatment::default(),
+            keep_metadata: false,
+        }
+    }
+
+    /// Replace text longer than `len` bytes (0 replaces all non-empty text)
+    pub fn max_text_len(mut self, len: usize) -> Self {
+        self.max_text_len = len;
+        self
+    }
+
+    /// Redact over-long text instead of hashing it
+    pub fn redact_text(mut self) -> Self {
+        self.text = TextTreatment::Redact;
+        self
+    }
+
+    /// Keep event metadata
+    pub fn keep_metadata(mut self) -> Self {
+        self.keep_metadata = true;
+        self
+    }
+
+    /// Pseudonym for an identifier under this policy's salt
+    pub fn pseudonym(&self, id: &str) -> String {
+        format!("anon_{}", &salted_hash(&self.salt, id)[..PSEUDONYM_LEN])
+    }
+
+    /// Anonymized text, or `None` if the text is short enough to keep
+    fn replace_text(&self, text: &str) -> Option<String> {
+        if text.len() <= self.max_text_len {
+            return None;
+        }
+        Some(match self.text {
+            TextTreatment::Hash => format!("[sha256:{}]", salted_hash(&self.salt, text)),
+            TextTreatment::Redact => "[REDACTED]".to_string(),
+        })
+    }
+
+    fn anonymize_event(&self, event: &mut SerendipityEvent) {
+        if !self.keep_metadata {
+            event.metadata.clear();
+        }
+        if event.is_redacted() {
+            return;
+        }
+        let input = self.replace_text(&event.input);
+        let output = self.replace_text(&event.output);
+        if input.is_none() && output.is_none() {
+            return;
+        }
+
+        let salted_input_hash = salted_hash(&self.salt, &event.input);
+        let salted_output_hash = salted_hash(&self.salt, &event.output);
+        if let Some(input) = input {
+            event.input = input;
+        }
+        if let Some(output) = output {
+            event.output = output;
+        }
+        event.redaction = Some(RedactionTombstone {
+            requested_by: "anonymization".to_string(),
+            reason: "anonymization".to_string(),
+            redacted_at: Utc::now(),
+            // Hash of the published text, so the provenance hash is recomputable from it
+            content_hash: event.content_hash(),
+            salted_input_hash,
+            salted_output_hash,
+        });
+    }
+}
+
+impl SerendipityTrace {
+    /// Publishable copy of the trace with identities and long text removed.
+    /// The contributor signature is dropped, since it covers the original hash.
+    pub fn anonymize(&self, policy: &AnonymizationPolicy) -> SerendipityTrace {
+        let mut anonymized = self.clone();
+        anonymized.trace_id = policy.pseudonym(&self.trace_id);
+        anonymized.contributor_id = policy.pseudonym(&self.contributor_id);
+        anonymized.co_contributors = self.co_contributors.iter().map(|id| policy.pseudonym(id)).collect();
+        anonymized.signature = None;
+        anonymized.invalidate_provenance();
+        for event in &mut anonymized.events {
+            policy.anonymize_event(event);
+            event.contributor = event.contributor.as_deref().map(|id| policy.pseudonym(id));
+        }
+        for verdict in &mut anonymized.review.verdicts {
+            verdict.reviewer = policy.pseudonym(&verdict.reviewer);
+        }
+        anonymized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redaction::verify_redacted_content;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent, SerendipityEventBuilder};
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari@example.org", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "survey", "short note", "en", 0.5, 0.8).unwrap();
+        trace.log(SerendipityEventBuilder::new(
+            SerendipityStage::Validation,
+            SerendipityAgent::Validator,
+            &"confidential prompt ".repeat(20),
+            "confirmed",
+            "id",
+        ).serendipity(0.9).confidence(0.9).metadata("lab", "Yogyakarta")).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_anonymize_pseudonymizes_and_hashes_long_text() {
+        let trace = sample_trace();
+        let policy = AnonymizationPolicy::new(b"benchmark-salt");
+        let anonymized = trace.anonymize(&policy);
+
+        assert_eq!(anonymized.contributor_id, policy.pseudonym("sari@example.org"));
+        assert!(!anonymized.trace_id.contains("sari"));
+        assert_eq!(anonymized.events[0].input, "survey");
+        assert!(anonymized.events[1].input.starts_with("[sha256:"));
+        assert_eq!(anonymized.events[1].output, "confirmed");
+        assert!(anonymized.events[1].metadata.is_empty());
+        assert!(verify_redacted_content(&anonymized.events[1], &trace.events[1].input, "confirmed", b"benchmark-salt"));
+        assert_eq!(anonymized.fold_memory().unwrap().redacted_events, 1);
+    }
+
+    #[test]
+    fn test_anonymized_provenance_is_stable() {
+        let trace = sample_trace();
+        let policy = AnonymizationPolicy::new(b"benchmark-salt").redact_text().keep_metadata();
+        let first = trace.anonymize(&policy);
+        let second = trace.anonymize(&policy);
+        assert_eq!(first.compute_provenance_hash(), second.compute_provenance_hash());
+        assert_ne!(first.compute_provenance_hash(), trace.compute_provenance_hash());
+        assert_eq!(first.events[1].input, "[REDACTED]");
+        assert_eq!(first.events[1].metadata.len(), 1);
+
+        // The published form alone reproduces the hash
+        let published: SerendipityTrace = serde_json::from_str(&serde_json::to_string(&first).unwrap()).unwrap();
+        assert_eq!(published.compute_provenance_hash(), first.compute_provenance_hash());
+        let other_salt = trace.anonymize(&AnonymizationPolicy::new(b"other"));
+        assert_ne!(other_salt.contributor_id, first.contributor_id);
+    }
+}
@@ -0,0 +1,266 @@
+// -*- coding: utf-8 -*-
+//! Arrow / Parquet Export for Analytics
+//!
+//! Converts events, transitions, and folded traces into Arrow `RecordBatch`es
+//! with a stable schema, and writes them as Parquet files, so analysts can
+//! load thousands of traces into pandas or Polars. Requires the `arrow`
+//! feature (`arrow` and `parquet` crates).
+#![cfg(feature = "arrow")]
+
+use std::path::Path;
+use std::sync::Arc;
+use arrow::array::{
+    ArrayRef, BooleanArray, Float64Array, ListBuilder, StringArray, StringBuilder,
+    TimestampMillisecondArray, UInt64Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use crate::serendipity_trace::{SerendipityTrace, FoldedSerendipityTrace};
+use crate::error::SerenQaResult;
+
+/// Events table written by `export_parquet`
+pub const EVENTS_FILE: &str = "events.parquet";
+
+/// Transitions table written by `export_parquet`
+pub const TRANSITIONS_FILE: &str = "transitions.parquet";
+
+/// Folds table written by `export_parquet`
+pub const FOLDS_FILE: &str = "folds.parquet";
+
+fn utc_millis() -> DataType {
+    DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+}
+
+fn string_list() -> DataType {
+    DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)))
+}
+
+/// Schema of the events table (one row per event)
+pub fn events_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("trace_id", DataType::Utf8, false),
+        Field::new("event_id", DataType::Utf8, false),
+        Field::new("timestamp", utc_millis(), false),
+        Field::new("stage", DataType::Utf8, false),
+        Field::new("agent", DataType::Utf8, false),
+        Field::new("language", DataType::Utf8, false),
+        Field::new("serendipity", DataType::Float64, false),
+        Field::new("confidence", DataType::Float64, false),
+        Field::new("redacted", DataType::Boolean, false),
+    ]))
+}
+
+/// Schema of the transitions table (one row per transition)
+pub fn transitions_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("trace_id", DataType::Utf8, false),
+        Field::new("from_event", DataType::Utf8, false),
+        Field::new("to_event", DataType::Utf8, false),
+        Field::new("from_agent", DataType::Utf8, false),
+        Field::new("to_agent", DataType::Utf8, false),
+        Field::new("transition_score", DataType::Float64, false),
+        Field::new("from_language", DataType::Utf8, true),
+        Field::new("to_language", DataType::Utf8, true),
+    ]))
+}
+
+/// Schema of the folds table (one row per folded trace)
+pub fn folds_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("trace_id", DataType::Utf8, false),
+        Field::new("discovery_name", DataType::Utf8, false),
+        Field::new("total_events", DataType::UInt64, false),
+        Field::new("overall_serendipity", DataType::Float64, false),
+        Field::new("compression_ratio", DataType::Float64, false),
+        Field::new("uniqueness", DataType::Float64, false),
+        Field::new("languages", string_list(), false),
+        Field::new("key_discoveries", string_list(), false),
+    ]))
+}
+
+fn strings<'a>(values: impl Iterator<Item = &'a str>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn floats(values: impl Iterator<Item = f64>) -> ArrayRef {
+    Arc::new(Float64Array::from_iter_values(values))
+}
+
+fn string_lists<'a>(rows: impl Iterator<Item = &'a [String]>) -> ArrayRef {
+    let mut builder = ListBuilder::new(StringBuilder::new());
+    for row in rows {
+        for value in row {
+            builder.values().append_value(value);
+        }
+        builder.append(true);
+    }
+    Arc::new(builder.finish())
+}
+
+/// Events of all traces as one batch
+pub fn events_batch(traces: &[SerendipityTrace]) -> SerenQaResult<RecordBatch> {
+    let rows: Vec<_> = traces
+        .iter()
+        .flat_map(|t| t.events.iter().map(move |e| (t.trace_id.as_str(), e)))
+        .collect();
+    let stages: Vec<String> = rows.iter().map(|(_, e)| e.stage.name()).collect();
+    let agents: Vec<String> = rows.iter().map(|(_, e)| e.agent.name()).collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        strings(rows.iter().map(|(id, _)| *id)),
+        strings(rows.iter().map(|(_, e)| e.event_id.as_str())),
+        Arc::new(
+            TimestampMillisecondArray::from_iter_values(rows.iter().map(|(_, e)| e.timestamp.timestamp_millis()))
+                .with_timezone("UTC"),
+        ),
+        strings(stages.iter().map(String::as_str)),
+        strings(agents.iter().map(String::as_str)),
+        strings(rows.iter().map(|(_, e)| e.language.as_str())),
+        floats(rows.iter().map(|(_, e)| e.serendipity_score)),
+        floats(rows.iter().map(|(_, e)| e.confidence)),
+        Arc::new(BooleanArray::from(rows.iter().map(|(_, e)| e.is_redacted()).collect::<Vec<_>>())),
+    ];
+    Ok(RecordBatch::try_new(events_schema(), columns)?)
+}
+
+/// Transitions of all traces as one batch
+pub fn transitions_batch(traces: &[SerendipityTrace]) -> SerenQaResult<RecordBatch> {
+    let rows: Vec<_> = traces
+        .iter()
+        .flat_map(|t| t.transitions.iter().map(move |tr| (t.trace_id.as_str(), tr)))
+        .collect();
+    let from_agents: Vec<String> = rows.iter().map(|(_, tr)| tr.from_agent.name()).collect();
+    let to_agents: Vec<String> = rows.iter().map(|(_, tr)| tr.to_agent.name()).collect();
+    let shift = |pick: fn(&(String, String)) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from(
+            rows.iter()
+                .map(|(_, tr)| tr.language_shift.as_ref().map(pick))
+                .collect::<Vec<Option<&str>>>(),
+        ))
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        strings(rows.iter().map(|(id, _)| *id)),
+        strings(rows.iter().map(|(_, tr)| tr.from_event.as_str())),
+        strings(rows.iter().map(|(_, tr)| tr.to_event.as_str())),
+        strings(from_agents.iter().map(String::as_str)),
+        strings(to_agents.iter().map(String::as_str)),
+        floats(rows.iter().map(|(_, tr)| tr.transition_score)),
+        shift(|(from, _)| from.as_str()),
+        shift(|(_, to)| to.as_str()),
+    ];
+    Ok(RecordBatch::try_new(transitions_schema(), columns)?)
+}
+
+/// Folded traces as one batch
+pub fn folds_batch(folds: &[FoldedSerendipityTrace]) -> SerenQaResult<RecordBatch> {
+    let columns: Vec<ArrayRef> = vec![
+        strings(folds.iter().map(|f| f.trace_id.as_str())),
+        strings(folds.iter().map(|f| f.discovery_name.as_str())),
+        Arc::new(UInt64Array::from_iter_values(folds.iter().map(|f| f.total_events as u64))),
+        floats(folds.iter().map(|f| f.overall_serendipity)),
+        floats(folds.iter().map(|f| f.compression_ratio)),
+        floats(folds.iter().map(|f| f.uniqueness.score)),
+        string_lists(folds.iter().map(|f| f.languages.as_slice())),
+        string_lists(folds.iter().map(|f| f.key_discoveries.as_slice())),
+    ];
+    Ok(RecordBatch::try_new(folds_schema(), columns)?)
+}
+
+/// Write a batch to a Parquet file
+pub fn write_parquet(path: &Path, batch: &RecordBatch) -> SerenQaResult<()> {
+    let file = std::fs::File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Write events, transitions, and folds of `traces` as Parquet files in `dir`.
+/// Empty traces are skipped in the folds table.
+pub fn export_parquet(traces: &[SerendipityTrace], dir: &Path) -> SerenQaResult<()> {
+    std::fs::create_dir_all(dir)?;
+    let folds = traces
+        .iter()
+        .filter(|t| !t.events.is_empty())
+        .map(|t| t.fold_memory())
+        .collect::<SerenQaResult<Vec<_>>>()?;
+
+    write_parquet(&dir.join(EVENTS_FILE), &events_batch(traces)?)?;
+    write_parquet(&dir.join(TRANSITIONS_FILE), &transitions_batch(traces)?)?;
+    write_parquet(&dir.join(FOLDS_FILE), &folds_batch(&folds)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        for (language, score) in [("en", 0.4), ("id", 0.9)] {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", language, score, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_batches_follow_schema() {
+        let traces = vec![sample_trace(), sample_trace()];
+        let events = events_batch(&traces).unwrap();
+        assert_eq!(events.num_rows(), 4);
+        assert_eq!(events.schema(), events_schema());
+
+        let transitions = transitions_batch(&traces).unwrap();
+        assert_eq!(transitions.num_rows(), 2);
+        let to_language = transitions.column(7).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(to_language.value(0), "id");
+
+        let folds = folds_batch(&[traces[0].fold_memory().unwrap()]).unwrap();
+        assert_eq!(folds.num_rows(), 1);
+    }
+
+    #[test]
+    fn test_export_parquet() {
+        let dir = std::env::temp_dir().join(format!("seren_parquet_{}", std::process::id()));
+        export_parquet(&[sample_trace()], &dir).unwrap();
+        for file in [EVENTS_FILE, TRANSITIONS_FILE, FOLDS_FILE] {
+            assert!(std::fs::metadata(dir.join(file)).unwrap().len() > 0);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_empty_trace_non_ascii_text_and_null_language_shift() {
+        use arrow::array::{Array, ListArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let empty = SerendipityTrace::new("researcher1", "backend", "Kosong");
+        let events = events_batch(std::slice::from_ref(&empty)).unwrap();
+        assert_eq!((events.num_rows(), events.schema()), (0, events_schema()));
+        assert_eq!(transitions_batch(std::slice::from_ref(&empty)).unwrap().num_rows(), 0);
+
+        let mut trace = SerendipityTrace::new("peneliti", "backend", "Batik — 蝋纈染め");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "motif parang", "id", 0.5, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "フラクタル次元 ≈ 1.58", "id", 0.95, 0.9).unwrap();
+
+        // Same-language transitions have no shift: both language columns are null
+        let transitions = transitions_batch(std::slice::from_ref(&trace)).unwrap();
+        assert!(transitions.column(6).is_null(0) && transitions.column(7).is_null(0));
+
+        // The empty trace is skipped in the folds table; text survives the round trip
+        let dir = std::env::temp_dir().join(format!("seren_parquet_edge_{}", std::process::id()));
+        export_parquet(&[empty, trace], &dir).unwrap();
+        let file = std::fs::File::open(dir.join(FOLDS_FILE)).unwrap();
+        let folds: Vec<RecordBatch> = ParquetRecordBatchReaderBuilder::try_new(file).unwrap().build().unwrap().map(Result::unwrap).collect();
+        assert_eq!(folds.iter().map(RecordBatch::num_rows).sum::<usize>(), 1);
+        let names = folds[0].column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(names.value(0), "Batik — 蝋纈染め");
+        let discoveries = folds[0].column(7).as_any().downcast_ref::<ListArray>().unwrap().value(0);
+        let discoveries = discoveries.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(discoveries.value(0), "UnexpectedConnection: フラクタル次元 ≈ 1.58");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+// -*- coding: utf-8 -*-
+//! Content-Addressed Artifact Attachments
+//!
+//! Events often rest on artifacts too large to inline: plots, datasets,
+//! notebooks. An `Attachment` references one by the SHA-256 hash of its bytes,
+//! with a media type and optionally a URI where it can be fetched. The hash
+//! and media type enter the provenance hash; the URI is only a location and
+//! can change without affecting it. An `AttachmentResolver` (a bundle, an
+//! in-memory map, or a content-addressed directory) fetches the bytes on
+//! demand; `fetch_verified` rejects bytes that do not match the hash.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use crate::serendipity_trace::{EventValidationError, SerendipityTrace};
+use crate::circuit::EventAttachment;
+use crate::provenance::{hex_decode, to_hex};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// SHA-256 of `bytes` (hex), the content address of an attachment
+pub fn content_address(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+/// Reference to an external artifact by content hash
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Attachment {
+    /// Display name, e.g. a file name
+    pub name: String,
+    /// SHA-256 of the artifact's bytes (hex)
+    pub content_hash: String,
+    /// Media type, e.g. `image/png`
+    pub media_type: String,
+    /// Where the artifact can be fetched (not part of the provenance hash)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    /// Size in bytes, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+impl Attachment {
+    /// Reference to an artifact known only by its hash
+    pub fn new(name: &str, media_type: &str, content_hash: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            content_hash: content_hash.to_ascii_lowercase(),
+            media_type: media_type.to_string(),
+            uri: None,
+            size: None,
+        }
+    }
+
+    /// Reference to `bytes`, hashing them
+    pub fn from_bytes(name: &str, media_type: &str, bytes: &[u8]) -> Self {
+        Self { size: Some(bytes.len() as u64), ..Self::new(name, media_type, &content_address(bytes)) }
+    }
+
+    /// Record where the artifact can be fetched
+    pub fn with_uri(mut self, uri: &str) -> Self {
+        self.uri = Some(uri.to_string());
+        self
+    }
+
+    /// Whether `bytes` are the referenced artifact
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        content_address(bytes) == self.content_hash && self.size.is_none_or(|size| size == bytes.len() as u64)
+    }
+
+    /// Why the reference is malformed, if it is: the hash must be 64 hex
+    /// digits and the media type `type/subtype`
+    pub fn problem(&self) -> Option<String> {
+        if self.content_hash.len() != 64 || hex_decode(&self.content_hash).is_none() {
+            return Some(format!("attachment {}: content hash is not SHA-256 hex", self.name));
+        }
+        if self.media_type.split_once('/').is_none_or(|(kind, subtype)| kind.is_empty() || subtype.is_empty()) {
+            return Some(format!("attachment {}: malformed media type {:?}", self.name, self.media_type));
+        }
+        None
+    }
+}
+
+/// Source of attachment bytes by content hash
+pub trait AttachmentResolver {
+    /// Bytes stored under `content_hash`, if any
+    fn fetch(&self, content_hash: &str) -> SerenQaResult<Option<Vec<u8>>>;
+
+    /// Bytes of `attachment`, failing if they are missing or do not match it
+    fn fetch_verified(&self, attachment: &Attachment) -> SerenQaResult<Vec<u8>> {
+        let bytes = self
+            .fetch(&attachment.content_hash)?
+            .ok_or_else(|| SerenQaError::InvalidAttachment(format!("{} ({}) not found", attachment.name, attachment.content_hash)))?;
+        if !attachment.matches(&bytes) {
+            return Err(SerenQaError::InvalidAttachment(format!("fetched bytes of {} do not match", attachment.name)));
+        }
+        Ok(bytes)
+    }
+}
+
+/// Attachment bytes held in memory
+#[derive(Debug, Default)]
+pub struct InMemoryAttachmentStore {
+    blobs: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl InMemoryAttachmentStore {
+    /// Empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `bytes`, returning their content hash
+    pub fn put(&self, bytes: Vec<u8>) -> String {
+        let hash = content_address(&bytes);
+        self.blobs.write().unwrap_or_else(|e| e.into_inner()).insert(hash.clone(), bytes);
+        hash
+    }
+}
+
+impl AttachmentResolver for InMemoryAttachmentStore {
+    fn fetch(&self, content_hash: &str) -> SerenQaResult<Option<Vec<u8>>> {
+        Ok(self.blobs.read().unwrap_or_else(|e| e.into_inner()).get(content_hash).cloned())
+    }
+}
+
+/// Directory keeping each attachment in a file named by its content hash
+#[derive(Debug, Clone)]
+pub struct FsAttachmentStore {
+    dir: PathBuf,
+}
+
+impl FsAttachmentStore {
+    /// Open (and create if needed) an attachment directory
+    pub fn open(dir: &Path) -> SerenQaResult<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    /// Directory backing the store
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Store `bytes`, returning their content hash
+    pub fn put(&self, bytes: &[u8]) -> SerenQaResult<String> {
+        let hash = content_address(bytes);
+        let path = self.dir.join(&hash);
+        if !path.exists() {
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, bytes)?;
+            std::fs::rename(&tmp, &path)?;
+        }
+        Ok(hash)
+    }
+}
+
+impl AttachmentResolver for FsAttachmentStore {
+    fn fetch(&self, content_hash: &str) -> SerenQaResult<Option<Vec<u8>>> {
+        // Only well-formed hashes name files, so a hash cannot escape the directory
+        if hex_decode(content_hash).is_none() {
+            return Ok(None);
+        }
+        match std::fs::read(self.dir.join(content_hash)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl SerendipityTrace {
+    /// Attach an artifact reference to a logged event
+    pub fn attach(&mut self, event_id: &str, attachment: Attachment) -> SerenQaResult<()> {
+        if let Some(problem) = attachment.problem() {
+            return Err(SerenQaError::InvalidEvent(EventValidationError::InvalidAttachment(problem)));
+        }
+        let event = self.events
+            .iter_mut()
+            .find(|e| e.event_id == event_id)
+            .ok_or_else(|| SerenQaError::UnknownEvent(event_id.to_string()))?;
+        event.attachments.push(EventAttachment::Artifact(attachment));
+        self.invalidate_provenance();
+        Ok(())
+    }
+
+    /// Artifact references of every event, with the event's ID
+    pub fn artifact_attachments(&self) -> impl Iterator<Item = (&str, &Attachment)> {
+        self.events.iter().flat_map(|event| {
+            event.attachments.iter().filter_map(move |attachment| match attachment {
+                EventAttachment::Artifact(artifact) => Some((event.event_id.as_str(), artifact)),
+                _ => None,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+
+    fn trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "fit", "peak at 3.2 eV", "en", 0.7, 0.9).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_attachments_enter_provenance() {
+        let mut trace = trace();
+        let event_id = trace.events[0].event_id.clone();
+        let before = trace.compute_provenance_hash();
+        let plot = Attachment::from_bytes("spectrum.png", "image/png", b"\x89PNG...");
+        trace.attach(&event_id, plot.clone()).unwrap();
+        let attached = trace.compute_provenance_hash();
+        assert_ne!(attached, before);
+        assert_eq!(trace.artifact_attachments().collect::<Vec<_>>(), vec![(event_id.as_str(), &plot)]);
+
+        // Moving the artifact keeps the hash; changing its content or type does not
+        let mut moved = trace.clone();
+        let EventAttachment::Artifact(artifact) = &mut moved.events[0].attachments[0] else { unreachable!() };
+        artifact.uri = Some("https://data.example.org/spectrum.png".to_string());
+        assert_eq!(moved.compute_provenance_hash(), attached);
+        let mut retyped = trace.clone();
+        let EventAttachment::Artifact(artifact) = &mut retyped.events[0].attachments[0] else { unreachable!() };
+        artifact.media_type = "image/jpeg".to_string();
+        assert_ne!(retyped.compute_provenance_hash(), attached);
+
+        let json = serde_json::to_string(&trace).unwrap();
+        assert!(json.contains(r#""kind":"artifact""#));
+        assert!(trace.attach(&event_id, Attachment::new("bad", "image/png", "not-a-hash")).is_err());
+        assert!(trace.attach("missing", plot).is_err());
+    }
+
+    #[test]
+    fn test_resolvers_verify_content() {
+        let memory = InMemoryAttachmentStore::new();
+        let hash = memory.put(b"x,y\n1,2\n".to_vec());
+        let dataset = Attachment::from_bytes("data.csv", "text/csv", b"x,y\n1,2\n");
+        assert_eq!(dataset.content_hash, hash);
+        assert_eq!(memory.fetch_verified(&dataset).unwrap(), b"x,y\n1,2\n");
+        assert!(memory.fetch_verified(&Attachment::from_bytes("other.csv", "text/csv", b"z")).is_err());
+
+        let dir = std::env::temp_dir().join(format!("seren_attachments_{}", std::process::id()));
+        let store = FsAttachmentStore::open(&dir).unwrap();
+        store.put(b"x,y\n1,2\n").unwrap();
+        assert_eq!(store.fetch_verified(&dataset).unwrap(), b"x,y\n1,2\n");
+        assert_eq!(store.fetch("../etc/passwd").unwrap(), None);
+        // A corrupted file is caught on fetch
+        std::fs::write(dir.join(&hash), b"x,y\n9,9\n").unwrap();
+        assert!(store.fetch_verified(&dataset).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
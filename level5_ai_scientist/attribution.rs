@@ -0,0 +1,254 @@
+// -*- coding: utf-8 -*-
+//! Team Attribution and Credit Splitting
+//!
+//! Discoveries are often collaborative, but a trace names a single
+//! `contributor_id`. Traces can list co-contributors and attribute individual
+//! events to whoever carried them out (unattributed events belong to the
+//! trace's contributor). When the leaderboard credits a team trace, each
+//! member records the trace and receives a fractional share of credit: equal,
+//! by event count, by serendipity contributed, or by causal contribution to
+//! the final event. Team traces also feed a
+//! team leaderboard kept alongside the individual one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEvent};
+use crate::ContributorStats::LanguageAwareLeaderboard;
+
+/// How credit for a team trace is divided among its contributors
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CreditSplit {
+    /// Every contributor gets the same share
+    #[default]
+    Equal,
+    /// Shares proportional to the number of events attributed
+    ByEventCount,
+    /// Shares proportional to the serendipity of attributed events
+    BySerendipity,
+    /// Shares proportional to the causal contribution of attributed events
+    /// to the final event (`event_contributions`)
+    ByContribution,
+}
+
+impl SerendipityTrace {
+    /// Add a contributor sharing credit for the trace
+    pub fn add_co_contributor(&mut self, contributor_id: &str) {
+        if contributor_id != self.contributor_id && !self.co_contributors.iter().any(|c| c == contributor_id) {
+            self.co_contributors.push(contributor_id.to_string());
+        }
+    }
+
+    /// Contributor who carried out an event
+    pub fn event_contributor<'a>(&'a self, event: &'a SerendipityEvent) -> &'a str {
+        event.contributor.as_deref().unwrap_or(&self.contributor_id)
+    }
+
+    /// Everyone credited: the trace's contributor, then co-contributors,
+    /// then anyone else an event is attributed to
+    pub fn contributors(&self) -> Vec<&str> {
+        let mut contributors = vec![self.contributor_id.as_str()];
+        let others = self.co_contributors
+            .iter()
+            .map(String::as_str)
+            .chain(self.events.iter().filter_map(|e| e.contributor.as_deref()));
+        for contributor in others {
+            if !contributors.contains(&contributor) {
+                contributors.push(contributor);
+            }
+        }
+        contributors
+    }
+
+    /// Whether more than one contributor is credited
+    pub fn is_team_trace(&self) -> bool {
+        self.contributors().len() > 1
+    }
+
+    /// Each contributor's share of credit (shares sum to 1). Proportional
+    /// splits fall back to equal shares when there is nothing to divide.
+    pub fn credit_shares(&self, split: CreditSplit) -> BTreeMap<String, f64> {
+        let contributors = self.contributors();
+        let mut weights: BTreeMap<String, f64> = contributors.iter().map(|c| (c.to_string(), 0.0)).collect();
+        let contributions = match split {
+            CreditSplit::ByContribution => self.event_contributions(),
+            _ => Vec::new(),
+        };
+        for (index, event) in self.events.iter().enumerate() {
+            let weight = match split {
+                CreditSplit::Equal => 0.0,
+                CreditSplit::ByEventCount => 1.0,
+                CreditSplit::BySerendipity => event.serendipity_score,
+                CreditSplit::ByContribution => contributions[index].contribution,
+            };
+            *weights.get_mut(self.event_contributor(event)).expect("event contributors are listed") += weight;
+        }
+
+        let total: f64 = weights.values().sum();
+        for weight in weights.values_mut() {
+            *weight = if total > 0.0 { *weight / total } else { 1.0 / contributors.len() as f64 };
+        }
+        weights
+    }
+}
+
+/// Identifier of a team: its sorted member IDs joined with "+"
+pub fn team_id<S: AsRef<str>>(members: &[S]) -> String {
+    let mut members: Vec<&str> = members.iter().map(AsRef::as_ref).collect();
+    members.sort_unstable();
+    members.dedup();
+    members.join("+")
+}
+
+/// Statistics of a team across the traces it was credited with together
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TeamStats {
+    /// Team identifier (see `team_id`)
+    pub team_id: String,
+    /// Member contributor IDs, sorted
+    pub members: Vec<String>,
+    /// Team traces credited
+    pub total_traces: usize,
+    /// Average overall serendipity of the team's traces
+    pub avg_serendipity: f64,
+    /// Discoveries the team made
+    pub discoveries: Vec<String>,
+}
+
+impl TeamStats {
+    /// Empty statistics for a team
+    pub fn new<S: AsRef<str>>(members: &[S]) -> Self {
+        let mut sorted: Vec<String> = members.iter().map(|m| m.as_ref().to_string()).collect();
+        sorted.sort_unstable();
+        sorted.dedup();
+        Self {
+            team_id: team_id(&sorted),
+            members: sorted,
+            total_traces: 0,
+            avg_serendipity: 0.0,
+            discoveries: Vec::new(),
+        }
+    }
+
+    /// Add a team trace
+    pub fn add_trace(&mut self, trace: &SerendipityTrace) {
+        self.total_traces += 1;
+        self.avg_serendipity = (self.avg_serendipity * (self.total_traces - 1) as f64 + trace.overall_serendipity)
+            / self.total_traces as f64;
+        if !self.discoveries.contains(&trace.discovery_name) {
+            self.discoveries.push(trace.discovery_name.clone());
+        }
+    }
+}
+
+impl LanguageAwareLeaderboard {
+    /// Split credit for traces credited from now on (equal by default)
+    pub fn set_credit_split(&mut self, split: CreditSplit) {
+        self.credit_split = split;
+    }
+
+    /// Look up a team by its members, in any order
+    pub fn team<S: AsRef<str>>(&self, members: &[S]) -> Option<&TeamStats> {
+        self.teams.get(&team_id(members))
+    }
+
+    /// Record a team trace in the team view
+    pub(crate) fn record_team_trace(&mut self, trace: &SerendipityTrace) {
+        let members = trace.contributors();
+        self.teams
+            .entry(team_id(&members))
+            .or_insert_with(|| TeamStats::new(&members))
+            .add_trace(trace);
+    }
+
+    /// Get top N teams by average serendipity (ties broken by trace count)
+    pub fn get_top_teams(&self, n: usize) -> Vec<TeamStats> {
+        let mut teams: Vec<TeamStats> = self.teams.values().cloned().collect();
+        teams.sort_by(|a, b| {
+            b.avg_serendipity
+                .total_cmp(&a.avg_serendipity)
+                .then_with(|| b.total_traces.cmp(&a.total_traces))
+                .then_with(|| a.team_id.cmp(&b.team_id))
+        });
+        teams.into_iter().take(n).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent, SerendipityEventBuilder};
+    use crate::ContributorStats::LanguageAwareRankingCriteria;
+
+    fn team_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.add_co_contributor("budi");
+        trace.add_co_contributor("sari");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.2, 0.8).unwrap();
+        for (contributor, score) in [("budi", 0.3), ("dewi", 0.5)] {
+            let event = SerendipityEventBuilder::new(SerendipityStage::Validation, SerendipityAgent::Validator, "in", "out", "id")
+                .serendipity(score)
+                .confidence(0.8)
+                .contributor(contributor);
+            trace.log(event).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_credit_shares() {
+        let trace = team_trace();
+        assert_eq!(trace.contributors(), vec!["sari", "budi", "dewi"]);
+        assert_eq!(trace.event_contributor(&trace.events[0]), "sari");
+
+        let equal = trace.credit_shares(CreditSplit::Equal);
+        assert!(equal.values().all(|share| (share - 1.0 / 3.0).abs() < 1e-9));
+        let by_count = trace.credit_shares(CreditSplit::ByEventCount);
+        assert!((by_count["sari"] - 1.0 / 3.0).abs() < 1e-9);
+        let by_serendipity = trace.credit_shares(CreditSplit::BySerendipity);
+        assert!((by_serendipity["dewi"] - 0.5).abs() < 1e-9);
+        assert!((by_serendipity.values().sum::<f64>() - 1.0).abs() < 1e-9);
+        let by_contribution = trace.credit_shares(CreditSplit::ByContribution);
+        assert!((by_contribution.values().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // Reassigning credit changes the provenance hash, incremental or not
+        let hash = trace.compute_provenance_hash();
+        let mut reassigned = trace.clone();
+        reassigned.events[2].contributor = Some("budi".to_string());
+        assert!(!reassigned.verify_provenance_hash(&hash));
+        let mut widened = trace.clone();
+        widened.add_co_contributor("dewi");
+        assert_ne!(widened.compute_provenance_hash(), hash);
+        assert_eq!(widened.provenance_hash(), widened.compute_provenance_hash());
+
+        let solo = SerendipityTrace::new("sari", "backend", "Journavx");
+        assert!(!solo.is_team_trace());
+        assert_eq!(solo.credit_shares(CreditSplit::BySerendipity)["sari"], 1.0);
+    }
+
+    #[test]
+    fn test_team_and_individual_views() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        leaderboard.set_credit_split(CreditSplit::BySerendipity);
+        let verdict = leaderboard.credit_trace(&team_trace(), 0.8, 0.8).unwrap();
+        assert_eq!(verdict, crate::submission_guard::SubmissionVerdict::Accepted);
+        let mut solo = SerendipityTrace::new("budi", "backend", "Star Compass");
+        solo.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.9, 0.8).unwrap();
+        leaderboard.credit_trace(&solo, 0.8, 0.8).unwrap();
+
+        let budi = leaderboard.get("budi").unwrap();
+        assert_eq!(budi.total_traces, 2);
+        assert!((budi.credit() - 1.3).abs() < 1e-9);
+        assert!((leaderboard.get("dewi").unwrap().credit() - 0.5).abs() < 1e-9);
+        let top = leaderboard.get_top_n(1, LanguageAwareRankingCriteria::Credit);
+        assert_eq!(top[0].contributor_id, "budi");
+
+        let team = leaderboard.team(&["sari", "dewi", "budi"]).unwrap();
+        assert_eq!(team.team_id, "budi+dewi+sari");
+        assert_eq!(team.total_traces, 1);
+        assert_eq!(leaderboard.get_top_teams(10).len(), 1);
+
+        let restored = LanguageAwareLeaderboard::from_json(&leaderboard.to_json().unwrap()).unwrap();
+        assert_eq!(restored.team(&["budi", "dewi", "sari"]), Some(team));
+    }
+}
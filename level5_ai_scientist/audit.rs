@@ -0,0 +1,276 @@
+// -*- coding: utf-8 -*-
+//! Audit Log of Trace and Leaderboard Mutations
+//!
+//! Benchmark governance needs to answer "who changed this, and when" long
+//! after the fact. An `AuditLog` records each mutation of a trace or
+//! leaderboard entry: the actor, the target, the action, and the time. Each
+//! entry hashes its own content together with the previous entry's hash, so
+//! editing, reordering, or dropping an entry breaks every later link, and the
+//! head hash commits to the whole history. The log has no way to change or
+//! remove entries. `AuditLog::open` backs it with a JSONL file that each
+//! record is appended to, and reopening the file verifies the chain.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use chrono::{DateTime, Utc};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::provenance::to_hex;
+use crate::clock::{Clock, TraceClock};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// What an audit entry is about
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditTarget {
+    /// Trace by ID
+    Trace(String),
+    /// Leaderboard entry by contributor ID
+    LeaderboardEntry(String),
+}
+
+/// Mutation recorded by an audit entry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AuditAction {
+    /// Event appended to the trace
+    EventAppended { event_id: String },
+    /// Trace stored, with its provenance hash at that point
+    TraceSaved { provenance_hash: String },
+    /// Trace removed from storage
+    TraceDeleted,
+    /// Anonymized copy of the trace published under a pseudonymous ID
+    TraceAnonymized { published_trace_id: String },
+    /// Contributor statistics recomputed, e.g. after crediting a trace
+    StatsRecomputed { trace_id: Option<String> },
+    /// Anything else, described in free text
+    Other { description: String },
+}
+
+/// One link of the audit chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 0
+    pub sequence: u64,
+    pub at: DateTime<Utc>,
+    /// Principal or contributor ID that made the change
+    pub actor: String,
+    pub target: AuditTarget,
+    #[serde(flatten)]
+    pub action: AuditAction,
+    /// Hash of the entry before this one (`None` for the first)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>,
+    /// Hash of this entry's content and `prev_hash`
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// Hash of the entry's content, excluding `hash` itself
+    pub fn compute_hash(&self) -> String {
+        let content = serde_json::json!({
+            "sequence": self.sequence,
+            "at": self.at,
+            "actor": self.actor,
+            "target": self.target,
+            "action": self.action,
+            "prev_hash": self.prev_hash,
+        });
+        to_hex(&Sha256::digest(content.to_string().as_bytes()))
+    }
+}
+
+/// Append-only, hash-chained audit trail
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+    /// JSONL file each entry is appended to
+    path: Option<PathBuf>,
+    clock: TraceClock,
+}
+
+impl AuditLog {
+    /// Empty in-memory log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Log backed by the JSONL file at `path`, loading and verifying the
+    /// entries it already holds (the file is created on the first record)
+    pub fn open(path: impl AsRef<Path>) -> SerenQaResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut log = match File::open(&path) {
+            Ok(file) => Self::from_jsonl(BufReader::new(file))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Self::new(),
+            Err(err) => return Err(err.into()),
+        };
+        log.path = Some(path);
+        Ok(log)
+    }
+
+    /// Read entries from JSON lines and verify their chain
+    pub fn from_jsonl<R: BufRead>(reader: R) -> SerenQaResult<Self> {
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                entries.push(serde_json::from_str(&line)?);
+            }
+        }
+        let log = Self { entries, ..Self::default() };
+        log.verify()?;
+        Ok(log)
+    }
+
+    /// Write every entry as one JSON line
+    pub fn to_jsonl<W: Write>(&self, mut writer: W) -> SerenQaResult<()> {
+        for entry in &self.entries {
+            writeln!(writer, "{}", serde_json::to_string(entry)?)?;
+        }
+        Ok(())
+    }
+
+    /// Read entry times from `clock`
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = TraceClock::new(clock);
+        self
+    }
+
+    /// Append an entry for `actor` changing `target`, persisting it first if
+    /// the log is file-backed
+    pub fn record(&mut self, actor: &str, target: AuditTarget, action: AuditAction) -> SerenQaResult<&AuditEntry> {
+        let mut entry = AuditEntry {
+            sequence: self.entries.len() as u64,
+            at: self.clock.now(),
+            actor: actor.to_string(),
+            target,
+            action,
+            prev_hash: self.head_hash().map(str::to_string),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        }
+        self.entries.push(entry);
+        Ok(self.entries.last().expect("entry was just pushed"))
+    }
+
+    /// Record one `EventAppended` per event of `trace` past the first `previous_len`
+    pub fn record_appended_events(&mut self, actor: &str, trace: &SerendipityTrace, previous_len: usize) -> SerenQaResult<usize> {
+        let appended = trace.events.get(previous_len..).unwrap_or_default();
+        for event in appended {
+            self.record(
+                actor,
+                AuditTarget::Trace(trace.trace_id.clone()),
+                AuditAction::EventAppended { event_id: event.event_id.clone() },
+            )?;
+        }
+        Ok(appended.len())
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Entries about `target`, oldest first
+    pub fn history<'a>(&'a self, target: &'a AuditTarget) -> impl Iterator<Item = &'a AuditEntry> {
+        self.entries.iter().filter(move |entry| entry.target == *target)
+    }
+
+    /// Entries made by `actor`, oldest first
+    pub fn by_actor<'a>(&'a self, actor: &'a str) -> impl Iterator<Item = &'a AuditEntry> {
+        self.entries.iter().filter(move |entry| entry.actor == actor)
+    }
+
+    /// Hash of the newest entry, committing to the whole log
+    pub fn head_hash(&self) -> Option<&str> {
+        self.entries.last().map(|entry| entry.hash.as_str())
+    }
+
+    /// Check every entry's position, hash, and link to the one before it;
+    /// fails at the first entry that does not match
+    pub fn verify(&self) -> SerenQaResult<()> {
+        let mut prev_hash: Option<&str> = None;
+        for (index, entry) in self.entries.iter().enumerate() {
+            let intact = entry.sequence == index as u64
+                && entry.prev_hash.as_deref() == prev_hash
+                && entry.hash == entry.compute_hash();
+            if !intact {
+                return Err(SerenQaError::BrokenAuditLog { sequence: index as u64 });
+            }
+            prev_hash = Some(&entry.hash);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+    use chrono::TimeZone;
+
+    fn log() -> AuditLog {
+        let mut log = AuditLog::new().with_clock(FixedClock(Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap()));
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.trace_id = "trace_1".to_string();
+        for _ in 0..2 {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+        }
+        assert_eq!(log.record_appended_events("sari", &trace, 0).unwrap(), 2);
+        let target = AuditTarget::Trace(trace.trace_id.clone());
+        log.record("sari", target, AuditAction::TraceSaved { provenance_hash: trace.compute_provenance_hash() }).unwrap();
+        log.record(
+            "server",
+            AuditTarget::LeaderboardEntry("sari".to_string()),
+            AuditAction::StatsRecomputed { trace_id: Some(trace.trace_id) },
+        )
+        .unwrap();
+        log
+    }
+
+    #[test]
+    fn test_records_and_queries_history() {
+        let log = log();
+        assert_eq!(log.entries().len(), 4);
+        assert!(log.verify().is_ok());
+        assert_eq!(log.history(&AuditTarget::Trace("trace_1".to_string())).count(), 3);
+        assert_eq!(log.by_actor("server").count(), 1);
+        assert_eq!(log.entries()[1].prev_hash.as_deref(), Some(log.entries()[0].hash.as_str()));
+
+        let mut jsonl = Vec::new();
+        log.to_jsonl(&mut jsonl).unwrap();
+        assert!(String::from_utf8_lossy(&jsonl).contains(r#""action":"event_appended""#));
+        let restored = AuditLog::from_jsonl(jsonl.as_slice()).unwrap();
+        assert_eq!(restored.head_hash(), log.head_hash());
+    }
+
+    #[test]
+    fn test_detects_tampering() {
+        let log = log();
+        let mut jsonl = Vec::new();
+        log.to_jsonl(&mut jsonl).unwrap();
+        let text = String::from_utf8(jsonl).unwrap();
+
+        let edited = text.replacen(r#""actor":"server""#, r#""actor":"sari""#, 1);
+        assert!(matches!(AuditLog::from_jsonl(edited.as_bytes()), Err(SerenQaError::BrokenAuditLog { sequence: 3 })));
+        let dropped: String = text.lines().skip(1).map(|line| format!("{}\n", line)).collect();
+        assert!(matches!(AuditLog::from_jsonl(dropped.as_bytes()), Err(SerenQaError::BrokenAuditLog { sequence: 0 })));
+
+        // A file-backed log appends and re-verifies on open
+        let path = std::env::temp_dir().join(format!("seren_audit_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut file_log = AuditLog::open(&path).unwrap();
+        file_log.record("sari", AuditTarget::Trace("trace_1".to_string()), AuditAction::TraceDeleted).unwrap();
+        file_log.record("sari", AuditTarget::Trace("trace_2".to_string()), AuditAction::TraceDeleted).unwrap();
+        let reopened = AuditLog::open(&path).unwrap();
+        assert_eq!(reopened.head_hash(), file_log.head_hash());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+// -*- coding: utf-8 -*-
+//! SerenQA Benchmarks
+//!
+//! Criterion benchmarks for the hot paths: event logging, memory folding,
+//! provenance hashing, leaderboard ranking, and alignment (with and without
+//! the result cache). Run `cargo bench --bench serenqa_benches` and compare
+//! against a saved baseline (`-- --save-baseline before`, then
+//! `-- --baseline before`) when a change is meant to make one of them faster.
+
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use level5_ai_scientist::serendipity_trace::{SerendipityAgent, SerendipityStage, SerendipityTrace};
+use level5_ai_scientist::ContributorStats::{
+    LanguageAwareContributorStats, LanguageAwareLeaderboard, LanguageAwareRankingCriteria, LeaderboardQuery,
+};
+use level5_ai_scientist::alignment::MultilingualAligner;
+use level5_ai_scientist::alignment_cache::CachedAligner;
+
+const STAGES: [SerendipityStage; 4] = [
+    SerendipityStage::Exploration,
+    SerendipityStage::UnexpectedConnection,
+    SerendipityStage::Validation,
+    SerendipityStage::Integration,
+];
+
+const AGENTS: [SerendipityAgent; 4] = [
+    SerendipityAgent::Explorer,
+    SerendipityAgent::PatternRecognizer,
+    SerendipityAgent::Validator,
+    SerendipityAgent::Synthesizer,
+];
+
+const LANGUAGES: [&str; 3] = ["en", "id", "zh"];
+
+/// Log the `i`-th synthetic event; scores vary so folding finds breakthroughs
+fn log(trace: &mut SerendipityTrace, i: usize) {
+    let serendipity = (i * 37 % 100) as f64 / 100.0;
+    trace.log_event(
+        STAGES[i % STAGES.len()].clone(),
+        AGENTS[i % AGENTS.len()].clone(),
+        "Survey wayfinding texts",
+        &format!("Observation {}", i),
+        LANGUAGES[i % LANGUAGES.len()],
+        serendipity,
+        0.8,
+    ).unwrap();
+}
+
+fn trace_of(events: usize) -> SerendipityTrace {
+    let mut trace = SerendipityTrace::new("bench", "simulator", "Journavx");
+    for i in 0..events {
+        log(&mut trace, i);
+    }
+    trace
+}
+
+fn bench_log_event(c: &mut Criterion) {
+    const EVENTS: usize = 1_000;
+    let mut group = c.benchmark_group("log_event");
+    group.throughput(Throughput::Elements(EVENTS as u64));
+    group.bench_function(BenchmarkId::from_parameter(EVENTS), |b| {
+        b.iter_batched(
+            || SerendipityTrace::new("bench", "simulator", "Journavx"),
+            |mut trace| {
+                for i in 0..EVENTS {
+                    log(&mut trace, i);
+                }
+                trace
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_fold_memory(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fold_memory");
+    group.sample_size(10);
+    for events in [1_000, 10_000, 100_000] {
+        let trace = trace_of(events);
+        group.throughput(Throughput::Elements(events as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(events), &trace, |b, trace| {
+            b.iter(|| trace.fold_memory().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_provenance_hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("provenance_hash");
+    for events in [1_000, 10_000] {
+        let trace = trace_of(events);
+        group.throughput(Throughput::Elements(events as u64));
+        group.bench_with_input(BenchmarkId::new("full", events), &trace, |b, trace| {
+            b.iter(|| trace.compute_provenance_hash())
+        });
+        group.bench_with_input(BenchmarkId::new("incremental", events), &trace, |b, trace| {
+            b.iter(|| trace.provenance_hash())
+        });
+    }
+    group.finish();
+}
+
+fn bench_leaderboard(c: &mut Criterion) {
+    const CONTRIBUTORS: usize = 10_000;
+    let mut leaderboard = LanguageAwareLeaderboard::new();
+    for i in 0..CONTRIBUTORS {
+        let mut stats = LanguageAwareContributorStats::new(&format!("contributor-{}", i));
+        let score = (i * 7919 % CONTRIBUTORS) as f64 / CONTRIBUTORS as f64;
+        let languages = vec!["en".to_string(), LANGUAGES[i % LANGUAGES.len()].to_string()];
+        stats.add_trace(1 + i % 9, score, score, languages, 0.8, 0.7).unwrap();
+        leaderboard.add_contributor(stats);
+    }
+
+    let mut group = c.benchmark_group("leaderboard");
+    group.bench_function(BenchmarkId::new("top_10", CONTRIBUTORS), |b| {
+        b.iter(|| leaderboard.get_top_n(10, black_box(LanguageAwareRankingCriteria::Overall)))
+    });
+    let query = LeaderboardQuery::new(LanguageAwareRankingCriteria::Serendipity).page(5, 50).language("zh");
+    group.bench_function(BenchmarkId::new("query_page", CONTRIBUTORS), |b| {
+        b.iter(|| leaderboard.query(black_box(&query)))
+    });
+    group.finish();
+}
+
+fn bench_alignment(c: &mut Criterion) {
+    let pairs = [
+        ("short", "Batik patterns encode routes", "Pola batik menyandikan rute"),
+        (
+            "paragraph",
+            "The parang motif repeats diagonally across the cloth, and its spacing matches the \
+             step counts recorded by coastal navigators who memorized routes as textile patterns.",
+            "Motif parang berulang secara diagonal di sepanjang kain, dan jaraknya cocok dengan \
+             hitungan langkah yang dicatat para navigator pesisir yang menghafal rute sebagai pola kain.",
+        ),
+    ];
+
+    let mut group = c.benchmark_group("alignment");
+    for (name, source, target) in pairs {
+        group.bench_function(name, |b| {
+            let mut aligner = MultilingualAligner::new();
+            b.iter(|| aligner.align(black_box(source), black_box(target), "en", "id"))
+        });
+        group.bench_function(BenchmarkId::new("cached", name), |b| {
+            let mut aligner = CachedAligner::new();
+            b.iter(|| aligner.align(black_box(source), black_box(target), "en", "id"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_log_event,
+    bench_fold_memory,
+    bench_provenance_hash,
+    bench_leaderboard,
+    bench_alignment
+);
+criterion_main!(benches);
@@ -0,0 +1,325 @@
+// -*- coding: utf-8 -*-
+//! Structured Benchmark Results for Validation Events
+//!
+//! Validation events can carry a `BenchmarkResult` (metric, value, baseline,
+//! claimed improvement, units, runs, variance) instead of a free-text result.
+//! A Welch t-test checks whether the improvement is statistically meaningful;
+//! events whose benchmark is not significant have their serendipity credit
+//! capped at `UNVERIFIED_SERENDIPITY_CAP` during aggregation.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::SerendipityEvent;
+
+/// Serendipity credit allowed for an event whose benchmark is not significant
+pub const UNVERIFIED_SERENDIPITY_CAP: f64 = 0.5;
+
+/// Largest gap (percentage points) between claimed and measured improvement
+pub const IMPROVEMENT_TOLERANCE_PCT: f64 = 0.5;
+
+/// Two-sided 95% critical values of Student's t for 1..=30 degrees of freedom
+const T_CRITICAL_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+/// Normal approximation used above 30 degrees of freedom
+const Z_CRITICAL_95: f64 = 1.960;
+
+fn default_true() -> bool {
+    true
+}
+
+/// Two-sided 95% critical t value for `df` degrees of freedom
+fn t_critical(df: f64) -> f64 {
+    if df >= T_CRITICAL_95.len() as f64 {
+        return Z_CRITICAL_95;
+    }
+    // Round down so fractional Welch degrees of freedom stay conservative
+    T_CRITICAL_95[(df.floor() as usize).max(1) - 1]
+}
+
+/// Measured benchmark outcome against a baseline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BenchmarkResult {
+    /// Metric name (e.g., "energy_error")
+    pub metric: String,
+    /// Mean measured value
+    pub value: f64,
+    /// Mean baseline value
+    pub baseline: f64,
+    /// Claimed improvement over the baseline, in percent
+    pub improvement_pct: f64,
+    /// Units of `value` and `baseline`
+    pub units: String,
+    /// Number of runs behind `value`
+    pub runs: usize,
+    /// Sample variance of `value` across runs
+    pub variance: f64,
+    /// Number of baseline runs (`None` treats the baseline as exact)
+    #[serde(default)]
+    pub baseline_runs: Option<usize>,
+    /// Sample variance of the baseline across runs
+    #[serde(default)]
+    pub baseline_variance: Option<f64>,
+    /// Whether larger values are better
+    #[serde(default = "default_true")]
+    pub higher_is_better: bool,
+}
+
+/// Outcome of the significance check
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchmarkVerdict {
+    /// Improvement recomputed from value and baseline, in percent
+    pub measured_improvement_pct: f64,
+    /// Welch t statistic, signed so positive means improvement
+    pub t_statistic: f64,
+    /// Welch–Satterthwaite degrees of freedom
+    pub degrees_of_freedom: f64,
+    /// Improvement is positive and significant at the 95% level
+    pub significant: bool,
+}
+
+impl BenchmarkResult {
+    /// Create a single-run result; the claimed improvement is computed
+    /// from `value` and `baseline`
+    pub fn new(metric: &str, value: f64, baseline: f64, units: &str) -> Self {
+        let mut result = Self {
+            metric: metric.to_string(),
+            value,
+            baseline,
+            improvement_pct: 0.0,
+            units: units.to_string(),
+            runs: 1,
+            variance: 0.0,
+            baseline_runs: None,
+            baseline_variance: None,
+            higher_is_better: true,
+        };
+        result.improvement_pct = result.measured_improvement_pct();
+        result
+    }
+
+    /// Set the number of runs and their sample variance
+    pub fn runs(mut self, runs: usize, variance: f64) -> Self {
+        self.runs = runs;
+        self.variance = variance;
+        self
+    }
+
+    /// Set the number of baseline runs and their sample variance
+    pub fn baseline_runs(mut self, runs: usize, variance: f64) -> Self {
+        self.baseline_runs = Some(runs);
+        self.baseline_variance = Some(variance);
+        self
+    }
+
+    /// Mark the metric as lower-is-better (error, latency, energy)
+    pub fn lower_is_better(mut self) -> Self {
+        self.higher_is_better = false;
+        self.improvement_pct = self.measured_improvement_pct();
+        self
+    }
+
+    /// Improvement implied by value and baseline, in percent
+    /// (0.0 for a zero baseline)
+    pub fn measured_improvement_pct(&self) -> f64 {
+        if self.baseline == 0.0 {
+            return 0.0;
+        }
+        let delta = if self.higher_is_better {
+            self.value - self.baseline
+        } else {
+            self.baseline - self.value
+        };
+        delta / self.baseline.abs() * 100.0
+    }
+
+    /// Describe why the result is malformed, if it is
+    pub fn problem(&self) -> Option<String> {
+        if self.metric.trim().is_empty() {
+            return Some("benchmark metric name is empty".to_string());
+        }
+        let baseline_variance = self.baseline_variance.unwrap_or(0.0);
+        let numbers = [self.value, self.baseline, self.improvement_pct, self.variance, baseline_variance];
+        if numbers.iter().any(|v| !v.is_finite()) {
+            return Some(format!("benchmark {:?} has non-finite values", self.metric));
+        }
+        if self.runs == 0 || self.baseline_runs == Some(0) {
+            return Some(format!("benchmark {:?} has zero runs", self.metric));
+        }
+        if self.variance < 0.0 || baseline_variance < 0.0 {
+            return Some(format!("benchmark {:?} has a negative variance", self.metric));
+        }
+        if (self.improvement_pct - self.measured_improvement_pct()).abs() > IMPROVEMENT_TOLERANCE_PCT {
+            return Some(format!(
+                "benchmark {:?} claims {:.2}% improvement but value and baseline give {:.2}%",
+                self.metric,
+                self.improvement_pct,
+                self.measured_improvement_pct()
+            ));
+        }
+        None
+    }
+
+    /// Welch t-test of the improvement over the baseline. Single-run results
+    /// without baseline runs have no variance estimate and are never significant.
+    pub fn verdict(&self) -> BenchmarkVerdict {
+        let delta = if self.higher_is_better {
+            self.value - self.baseline
+        } else {
+            self.baseline - self.value
+        };
+        let value_term = self.variance / self.runs.max(1) as f64;
+        let baseline_term = match (self.baseline_runs, self.baseline_variance) {
+            (Some(runs), Some(variance)) => variance / runs.max(1) as f64,
+            _ => 0.0,
+        };
+        let standard_error = (value_term + baseline_term).sqrt();
+
+        let df_part = |term: f64, runs: usize| {
+            if runs > 1 { term * term / (runs - 1) as f64 } else { 0.0 }
+        };
+        let df_denominator = df_part(value_term, self.runs)
+            + df_part(baseline_term, self.baseline_runs.unwrap_or(0));
+        let degrees_of_freedom = if df_denominator > 0.0 {
+            (value_term + baseline_term).powi(2) / df_denominator
+        } else {
+            // Zero variance: fall back to the pooled run count
+            (self.runs + self.baseline_runs.unwrap_or(1)).saturating_sub(2) as f64
+        };
+
+        let t_statistic = if standard_error > 0.0 {
+            delta / standard_error
+        } else if delta > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        };
+        let enough_runs = self.runs > 1 || self.baseline_runs.is_some_and(|runs| runs > 1);
+        let significant = enough_runs
+            && degrees_of_freedom >= 1.0
+            && delta > 0.0
+            && t_statistic > t_critical(degrees_of_freedom);
+
+        BenchmarkVerdict {
+            measured_improvement_pct: self.measured_improvement_pct(),
+            t_statistic,
+            degrees_of_freedom,
+            significant,
+        }
+    }
+
+    /// Check the claimed improvement is consistent and significant
+    pub fn is_verified(&self) -> bool {
+        self.problem().is_none() && self.verdict().significant
+    }
+
+    /// Bytes fed to the provenance hash
+    pub(crate) fn hash_bytes(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}|{:?}|{:?}|{}",
+            self.metric,
+            self.value,
+            self.baseline,
+            self.improvement_pct,
+            self.units,
+            self.runs,
+            self.variance,
+            self.baseline_runs,
+            self.baseline_variance,
+            self.higher_is_better
+        )
+    }
+}
+
+impl SerendipityEvent {
+    /// Serendipity score used for aggregation: capped at
+    /// `UNVERIFIED_SERENDIPITY_CAP` when the event's benchmark is not verified
+    pub fn credited_serendipity(&self) -> f64 {
+        match &self.benchmark {
+            Some(benchmark) if !benchmark.is_verified() => {
+                self.serendipity_score.min(UNVERIFIED_SERENDIPITY_CAP)
+            }
+            _ => self.serendipity_score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{
+        SerendipityTrace, SerendipityEventBuilder, SerendipityStage, SerendipityAgent,
+    };
+
+    fn energy_error() -> BenchmarkResult {
+        BenchmarkResult::new("energy_error", 0.8, 1.0, "mHa")
+            .lower_is_better()
+            .runs(10, 0.01)
+            .baseline_runs(10, 0.01)
+    }
+
+    #[test]
+    fn test_improvement_and_significance() {
+        let result = energy_error();
+        assert!((result.improvement_pct - 20.0).abs() < 1e-9);
+        let verdict = result.verdict();
+        assert!((verdict.degrees_of_freedom - 18.0).abs() < 1e-9);
+        assert!(verdict.t_statistic > 4.0);
+        assert!(result.is_verified());
+
+        let noisy = BenchmarkResult::new("energy_error", 0.8, 1.0, "mHa")
+            .lower_is_better()
+            .runs(3, 0.2)
+            .baseline_runs(3, 0.2);
+        assert!(!noisy.verdict().significant);
+        assert!(!BenchmarkResult::new("energy_error", 0.8, 1.0, "mHa").lower_is_better().is_verified());
+    }
+
+    #[test]
+    fn test_inconsistent_claim_is_rejected() {
+        let mut inflated = energy_error();
+        inflated.improvement_pct = 45.0;
+        assert!(inflated.problem().unwrap().contains("claims 45.00%"));
+
+        let builder = SerendipityEventBuilder::new(
+            SerendipityStage::Validation,
+            SerendipityAgent::Validator,
+            "input",
+            "output",
+            "en",
+        );
+        assert!(builder.benchmark(inflated).build().is_err());
+
+        let builder = SerendipityEventBuilder::new(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input",
+            "output",
+            "en",
+        );
+        assert!(builder.benchmark(energy_error()).build().is_err());
+    }
+
+    #[test]
+    fn test_unverified_benchmark_caps_credit() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        let validated = |benchmark: BenchmarkResult| {
+            SerendipityEventBuilder::new(SerendipityStage::Validation, SerendipityAgent::Validator, "in", "out", "en")
+                .serendipity(0.9)
+                .confidence(0.9)
+                .benchmark(benchmark)
+        };
+        trace.log(validated(energy_error())).unwrap();
+        trace.log(validated(BenchmarkResult::new("energy_error", 0.8, 1.0, "mHa").lower_is_better())).unwrap();
+
+        assert_eq!(trace.events[0].credited_serendipity(), 0.9);
+        assert_eq!(trace.events[1].credited_serendipity(), UNVERIFIED_SERENDIPITY_CAP);
+        assert!((trace.overall_serendipity - 0.7).abs() < 1e-9);
+
+        let hash = trace.compute_provenance_hash();
+        trace.events[0].benchmark = None;
+        assert!(!trace.verify_provenance_hash(&hash));
+    }
+}
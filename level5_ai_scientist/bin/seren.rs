@@ -0,0 +1,154 @@
+// -*- coding: utf-8 -*-
+//! SerenQA Command-Line Interface
+//!
+//! Bulk operations over directories of trace files, and golden-file
+//! regression checks for scoring outputs:
+//!
+//! ```text
+//! seren corpus fold   --in traces/ --out folds/ [--threads N]
+//! seren corpus stats  --in traces/ [--threads N]
+//! seren corpus verify --in traces/ [--threads N]
+//! seren corpus quota  --in traces/ --policy quota.json [--threads N]
+//! seren golden [--dir tests/golden] [--bless]
+//! ```
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use level5_ai_scientist::corpus::{
+    fold_corpus, corpus_stats, verify_corpus, check_corpus_quota, CorpusOptions, CorpusProgress,
+};
+use level5_ai_scientist::quota::QuotaPolicy;
+use level5_ai_scientist::submission::SubmissionPipeline;
+use level5_ai_scientist::golden::{check_golden, GoldenOutcome};
+
+const USAGE: &str = "usage: seren corpus <fold|stats|verify|quota> --in <dir> [--out <dir>] [--policy <file>] [--threads <n>] [--quiet]
+       seren golden [--dir <dir>] [--bless]";
+
+/// Default location of golden files, relative to the crate root
+const DEFAULT_GOLDEN_DIR: &str = "tests/golden";
+
+/// Parsed command-line arguments
+struct Args {
+    command: String,
+    input: PathBuf,
+    output: Option<PathBuf>,
+    policy: Option<PathBuf>,
+    threads: Option<usize>,
+    quiet: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let command = args.next().ok_or_else(|| USAGE.to_string())?;
+
+    let mut input = None;
+    let mut output = None;
+    let mut policy = None;
+    let mut threads = None;
+    let mut quiet = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--in" => input = args.next().map(PathBuf::from),
+            "--out" => output = args.next().map(PathBuf::from),
+            "--policy" => policy = args.next().map(PathBuf::from),
+            "--threads" => {
+                let value = args.next().ok_or("--threads needs a value")?;
+                threads = Some(value.parse().map_err(|_| format!("invalid thread count: {}", value))?);
+            }
+            "--quiet" => quiet = true,
+            other => return Err(format!("unknown argument: {}\n{}", other, USAGE)),
+        }
+    }
+
+    Ok(Args {
+        command,
+        input: input.ok_or_else(|| format!("--in is required\n{}", USAGE))?,
+        output,
+        policy,
+        threads,
+        quiet,
+    })
+}
+
+fn print_progress(progress: &CorpusProgress<'_>) {
+    eprintln!(
+        "[{}/{}] {} {}",
+        progress.completed,
+        progress.total,
+        if progress.ok { "ok  " } else { "FAIL" },
+        progress.path.display()
+    );
+}
+
+fn run(args: Args) -> Result<i32, String> {
+    let mut options = CorpusOptions::default();
+    if let Some(threads) = args.threads {
+        options.threads = threads;
+    }
+    if !args.quiet {
+        options.progress = Some(&print_progress);
+    }
+
+    let report = match args.command.as_str() {
+        "fold" => {
+            let output = args.output.ok_or_else(|| format!("--out is required for fold\n{}", USAGE))?;
+            fold_corpus(&args.input, &output, &options).map_err(|e| e.to_string())?
+        }
+        "stats" => {
+            let (report, stats) = corpus_stats(&args.input, &options).map_err(|e| e.to_string())?;
+            println!("\n=== Corpus Statistics ===");
+            stats.render(&mut std::io::stdout().lock()).map_err(|e| e.to_string())?;
+            report
+        }
+        "verify" => verify_corpus(&args.input, &SubmissionPipeline::new(), &options).map_err(|e| e.to_string())?,
+        "quota" => {
+            let path = args.policy.ok_or_else(|| format!("--policy is required for quota\n{}", USAGE))?;
+            let json = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let policy = QuotaPolicy::from_json(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+            check_corpus_quota(&args.input, &policy, &options).map_err(|e| e.to_string())?
+        }
+        other => return Err(format!("unknown corpus command: {}\n{}", other, USAGE)),
+    };
+
+    report.render(&args.command, &mut std::io::stdout().lock()).map_err(|e| e.to_string())?;
+    Ok(report.exit_code())
+}
+
+fn run_golden(mut args: impl Iterator<Item = String>) -> Result<i32, String> {
+    let mut dir = PathBuf::from(DEFAULT_GOLDEN_DIR);
+    let mut bless = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--dir" => dir = args.next().map(PathBuf::from).ok_or("--dir needs a value")?,
+            "--bless" => bless = true,
+            other => return Err(format!("unknown argument: {}\n{}", other, USAGE)),
+        }
+    }
+
+    let report = check_golden(&dir, bless).map_err(|e| e.to_string())?;
+    for (name, outcome) in &report.outcomes {
+        let status = match outcome {
+            GoldenOutcome::Matched => "ok".to_string(),
+            GoldenOutcome::Blessed => "blessed".to_string(),
+            GoldenOutcome::Missing => "MISSING".to_string(),
+            GoldenOutcome::Mismatch(fields) => format!("CHANGED ({})", fields.join(", ")),
+        };
+        println!("{:<24} {}", name, status);
+    }
+    Ok(if report.is_ok() { 0 } else { 1 })
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("corpus") => parse_args(args).and_then(run),
+        Some("golden") => run_golden(args),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => ExitCode::from(code as u8),
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
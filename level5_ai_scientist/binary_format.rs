@@ -0,0 +1,131 @@
+// -*- coding: utf-8 -*-
+//! Compact Binary Trace Format
+//!
+//! JSON traces of long runs are large and slow to parse. `to_bytes` writes a
+//! small header (magic, format version, and the provenance hash) followed by
+//! zstd-compressed MessagePack. MessagePack is self-describing, so untagged
+//! metadata values and older layouts still decode; payloads go through the
+//! schema migrations like JSON traces do. `from_bytes` recomputes the
+//! provenance hash and rejects files whose content no longer matches the
+//! header. Requires the `binary` feature (`rmp-serde` and `zstd` crates).
+#![cfg(feature = "binary")]
+
+use serde_json::Value;
+use crate::serendipity_trace::SerendipityTrace;
+use crate::migrations::migrate;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// First bytes of every binary trace
+pub const BINARY_MAGIC: &[u8; 4] = b"SRNB";
+
+/// Layout version of the binary container
+pub const BINARY_FORMAT_VERSION: u8 = 1;
+
+/// File extension of binary traces
+pub const BINARY_EXTENSION: &str = "seren";
+
+/// zstd level: fast, and already well past 10x on typical traces
+const ZSTD_LEVEL: i32 = 3;
+
+fn invalid(message: impl Into<String>) -> SerenQaError {
+    SerenQaError::InvalidBinaryTrace(message.into())
+}
+
+/// Split a binary trace into its provenance hash and compressed payload
+fn split(bytes: &[u8]) -> SerenQaResult<(&str, &[u8])> {
+    let rest = bytes.strip_prefix(BINARY_MAGIC.as_slice()).ok_or_else(|| invalid("missing magic header"))?;
+    let (&version, rest) = rest.split_first().ok_or_else(|| invalid("truncated header"))?;
+    if version != BINARY_FORMAT_VERSION {
+        return Err(invalid(format!("unsupported format version {}", version)));
+    }
+    if rest.len() < 2 {
+        return Err(invalid("truncated header"));
+    }
+    let hash_len = u16::from_le_bytes([rest[0], rest[1]]) as usize;
+    let rest = &rest[2..];
+    if rest.len() < hash_len {
+        return Err(invalid("truncated header"));
+    }
+    let hash = std::str::from_utf8(&rest[..hash_len]).map_err(|_| invalid("provenance hash is not UTF-8"))?;
+    Ok((hash, &rest[hash_len..]))
+}
+
+/// Provenance hash stored in a binary trace's header, without decoding it
+pub fn embedded_hash(bytes: &[u8]) -> SerenQaResult<String> {
+    Ok(split(bytes)?.0.to_string())
+}
+
+impl SerendipityTrace {
+    /// Encode as a compressed binary trace with the provenance hash embedded
+    pub fn to_bytes(&self) -> SerenQaResult<Vec<u8>> {
+        let hash = self.compute_provenance_hash();
+        let payload = rmp_serde::to_vec_named(self).map_err(|e| invalid(e.to_string()))?;
+        let compressed = zstd::encode_all(payload.as_slice(), ZSTD_LEVEL)?;
+
+        let mut bytes = Vec::with_capacity(BINARY_MAGIC.len() + 3 + hash.len() + compressed.len());
+        bytes.extend_from_slice(BINARY_MAGIC);
+        bytes.push(BINARY_FORMAT_VERSION);
+        bytes.extend_from_slice(&(hash.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(hash.as_bytes());
+        bytes.extend_from_slice(&compressed);
+        Ok(bytes)
+    }
+
+    /// Decode a binary trace, checking its content against the embedded hash
+    pub fn from_bytes(bytes: &[u8]) -> SerenQaResult<Self> {
+        let (hash, compressed) = split(bytes)?;
+        let payload = zstd::decode_all(compressed)?;
+        let value: Value = rmp_serde::from_slice(&payload).map_err(|e| invalid(e.to_string()))?;
+        let trace: SerendipityTrace = serde_json::from_value(migrate(value)?)?;
+        if !trace.verify_provenance_hash(hash) {
+            return Err(invalid(format!("trace {} does not match its embedded provenance hash", trace.trace_id)));
+        }
+        Ok(trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn long_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        for i in 0..500 {
+            let language = if i % 3 == 0 { "id" } else { "en" };
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "Survey wayfinding texts", &format!("Note {}", i), language, 0.4, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_round_trip_is_much_smaller() {
+        let trace = long_trace();
+        let bytes = trace.to_bytes().unwrap();
+        assert!(bytes.starts_with(BINARY_MAGIC));
+        assert_eq!(embedded_hash(&bytes).unwrap(), trace.compute_provenance_hash());
+        assert!(bytes.len() * 10 < trace.to_json().unwrap().len());
+
+        let loaded = SerendipityTrace::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.events.len(), 500);
+        assert_eq!(loaded.compute_provenance_hash(), trace.compute_provenance_hash());
+    }
+
+    #[test]
+    fn test_rejects_corrupt_files() {
+        let mut trace = long_trace();
+        assert!(matches!(SerendipityTrace::from_bytes(b"{\"trace_id\": 1}"), Err(SerenQaError::InvalidBinaryTrace(_))));
+
+        // Header hash of one trace, payload of an edited copy
+        let bytes = trace.to_bytes().unwrap();
+        let (hash, _) = split(&bytes).unwrap();
+        let header_len = bytes.len() - split(&bytes).unwrap().1.len();
+        let hash = hash.to_string();
+        trace.events[0].serendipity_score = 0.9;
+        let edited = trace.to_bytes().unwrap();
+        let mut spliced = bytes[..header_len].to_vec();
+        spliced.extend_from_slice(split(&edited).unwrap().1);
+        assert_eq!(embedded_hash(&spliced).unwrap(), hash);
+        assert!(matches!(SerendipityTrace::from_bytes(&spliced), Err(SerenQaError::InvalidBinaryTrace(_))));
+    }
+}
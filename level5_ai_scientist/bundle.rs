@@ -0,0 +1,391 @@
+// -*- coding: utf-8 -*-
+//! Discovery Bundles
+//!
+//! A `.serenqa` bundle is the unit of submission to the benchmark: one file
+//! holding the trace, its multilingual memory fold, a provenance proof (hash,
+//! Merkle root, and signature), the contributor stats delta crediting it would
+//! cause, and attachments such as figures or datasets, each addressed by its
+//! SHA-256 hash, so the trace's artifact references resolve against the
+//! bundle. The file is a short header (magic and format version)
+//! followed by a zstd frame holding a length-prefixed JSON manifest and then
+//! the attachment bytes in manifest order. `Bundle::open` and `from_bytes`
+//! verify every part before returning, so a bundle in hand is consistent.
+//! Requires the `bundle` feature (`zstd` crate).
+#![cfg(feature = "bundle")]
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use crate::serendipity_trace::SerendipityTrace;
+use crate::fold_multilingual_memory::MultilingualMemoryFold;
+use crate::provenance::TraceSignature;
+use crate::attachment::{content_address, AttachmentResolver};
+use crate::ContributorStats::{LanguageAwareContributorStats, LanguageAwareLeaderboard};
+use crate::migrations::migrate;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// First bytes of every bundle
+pub const BUNDLE_MAGIC: &[u8; 4] = b"SRNQ";
+
+/// Layout version of the bundle container
+pub const BUNDLE_FORMAT_VERSION: u8 = 1;
+
+/// File extension of bundles
+pub const BUNDLE_EXTENSION: &str = "serenqa";
+
+/// zstd level: attachments dominate, and higher levels gain little on them
+const ZSTD_LEVEL: i32 = 3;
+
+fn invalid(message: impl Into<String>) -> SerenQaError {
+    SerenQaError::InvalidBundle(message.into())
+}
+
+/// What the bundle claims about its trace
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProvenanceProof {
+    pub provenance_hash: String,
+    /// Merkle root over the trace's events
+    pub merkle_root: String,
+    pub event_count: usize,
+    /// The trace's signature, if it is signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<TraceSignature>,
+}
+
+impl ProvenanceProof {
+    /// Proof of the trace in its current state
+    pub fn of(trace: &SerendipityTrace) -> Self {
+        Self {
+            provenance_hash: trace.compute_provenance_hash(),
+            merkle_root: trace.merkle_root(),
+            event_count: trace.events.len(),
+            signature: trace.signature.clone(),
+        }
+    }
+}
+
+/// Change to the contributor's statistics that crediting the trace causes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatsDelta {
+    pub contributor_id: String,
+    pub traces_before: usize,
+    pub traces_after: usize,
+    pub overall_score_before: f64,
+    pub overall_score_after: f64,
+    /// Discoveries the contributor did not have before
+    #[serde(default)]
+    pub new_discoveries: Vec<String>,
+    /// Languages the contributor had not used before
+    #[serde(default)]
+    pub new_languages: Vec<String>,
+}
+
+impl StatsDelta {
+    /// Difference between a contributor's statistics before (`None` if they
+    /// had none) and after an update
+    pub fn between(before: Option<&LanguageAwareContributorStats>, after: &LanguageAwareContributorStats) -> Self {
+        let before_discoveries = before.map_or(&[][..], |stats| stats.discoveries.as_slice());
+        let before_languages = before.map_or(&[][..], |stats| stats.languages_used.as_slice());
+        Self {
+            contributor_id: after.contributor_id.clone(),
+            traces_before: before.map_or(0, |stats| stats.total_traces),
+            traces_after: after.total_traces,
+            overall_score_before: before.map_or(0.0, |stats| stats.overall_score()),
+            overall_score_after: after.overall_score(),
+            new_discoveries: after.discoveries.iter().filter(|d| !before_discoveries.contains(d)).cloned().collect(),
+            new_languages: after.languages_used.iter().filter(|l| !before_languages.contains(l)).cloned().collect(),
+        }
+    }
+
+    /// Delta crediting `trace` with `fold`'s scores would cause on
+    /// `leaderboard`, without changing it
+    pub fn preview(leaderboard: &LanguageAwareLeaderboard, trace: &SerendipityTrace, fold: &MultilingualMemoryFold) -> SerenQaResult<Self> {
+        let mut credited = leaderboard.detached();
+        credited.credit_trace(trace, fold.overall_alignment, fold.translation_summary.average_quality)?;
+        let after = credited.get(&trace.contributor_id).expect("credited contributor has statistics");
+        Ok(Self::between(leaderboard.get(&trace.contributor_id), after))
+    }
+}
+
+/// File carried in a bundle, addressed by its SHA-256 hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleAttachment {
+    pub name: String,
+    /// SHA-256 of `bytes` (hex)
+    pub sha256: String,
+    pub bytes: Vec<u8>,
+}
+
+impl BundleAttachment {
+    /// Attachment named `name` holding `bytes`
+    pub fn new(name: &str, bytes: Vec<u8>) -> Self {
+        Self { name: name.to_string(), sha256: content_address(&bytes), bytes }
+    }
+}
+
+/// Attachment as listed in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AttachmentEntry {
+    name: String,
+    sha256: String,
+    size: usize,
+}
+
+/// JSON part of a bundle; the trace stays untyped until migrated
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    trace: Value,
+    #[serde(default)]
+    fold: Option<MultilingualMemoryFold>,
+    proof: ProvenanceProof,
+    #[serde(default)]
+    stats_delta: Option<StatsDelta>,
+    #[serde(default)]
+    attachments: Vec<AttachmentEntry>,
+}
+
+/// Trace with everything submitted alongside it
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub trace: SerendipityTrace,
+    pub fold: Option<MultilingualMemoryFold>,
+    pub proof: ProvenanceProof,
+    pub stats_delta: Option<StatsDelta>,
+    pub attachments: Vec<BundleAttachment>,
+}
+
+impl Bundle {
+    /// Bundle of `trace` with the proof of its current state
+    pub fn new(trace: SerendipityTrace) -> Self {
+        let proof = ProvenanceProof::of(&trace);
+        Self { trace, fold: None, proof, stats_delta: None, attachments: Vec::new() }
+    }
+
+    /// Include the trace's fold
+    pub fn with_fold(mut self, fold: MultilingualMemoryFold) -> Self {
+        self.fold = Some(fold);
+        self
+    }
+
+    /// Include the stats delta crediting the trace causes
+    pub fn with_stats_delta(mut self, delta: StatsDelta) -> Self {
+        self.stats_delta = Some(delta);
+        self
+    }
+
+    /// Include an attachment; one with the same content is stored once
+    pub fn with_attachment(mut self, attachment: BundleAttachment) -> Self {
+        if self.attachment(&attachment.sha256).is_none() {
+            self.attachments.push(attachment);
+        }
+        self
+    }
+
+    /// Carry the bytes of every artifact the trace references, fetched from
+    /// `resolver` and checked against their references
+    pub fn with_referenced_attachments(mut self, resolver: &dyn AttachmentResolver) -> SerenQaResult<Self> {
+        let references: Vec<_> = self.trace.artifact_attachments().map(|(_, artifact)| artifact.clone()).collect();
+        for artifact in references {
+            let bytes = resolver.fetch_verified(&artifact)?;
+            self = self.with_attachment(BundleAttachment::new(&artifact.name, bytes));
+        }
+        Ok(self)
+    }
+
+    /// Attachment whose content hashes to `sha256`
+    pub fn attachment(&self, sha256: &str) -> Option<&BundleAttachment> {
+        self.attachments.iter().find(|attachment| attachment.sha256 == sha256)
+    }
+
+    /// Check the proof matches the trace, and the fold, stats delta, and
+    /// attachments match what they claim to belong to
+    pub fn verify(&self) -> SerenQaResult<()> {
+        if !self.trace.verify_provenance_hash(&self.proof.provenance_hash) {
+            return Err(invalid("trace does not match the bundled provenance hash"));
+        }
+        if self.proof.merkle_root != self.trace.merkle_root() || self.proof.event_count != self.trace.events.len() {
+            return Err(invalid("trace does not match the bundled Merkle root"));
+        }
+        if self.proof.signature != self.trace.signature {
+            return Err(invalid("bundled signature differs from the trace's"));
+        }
+        if let Some(fold) = &self.fold {
+            if fold.trace_id != self.trace.trace_id {
+                return Err(invalid(format!("fold is of trace {}, not {}", fold.trace_id, self.trace.trace_id)));
+            }
+        }
+        if let Some(delta) = &self.stats_delta {
+            let credited = delta.contributor_id == self.trace.contributor_id
+                || self.trace.co_contributors.contains(&delta.contributor_id);
+            if !credited {
+                return Err(invalid(format!("stats delta is for {}, who is not credited on the trace", delta.contributor_id)));
+            }
+        }
+        for attachment in &self.attachments {
+            if content_address(&attachment.bytes) != attachment.sha256 {
+                return Err(invalid(format!("attachment {} does not match its hash", attachment.name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode as a `.serenqa` archive
+    pub fn to_bytes(&self) -> SerenQaResult<Vec<u8>> {
+        let manifest = Manifest {
+            trace: serde_json::to_value(&self.trace)?,
+            fold: self.fold.clone(),
+            proof: self.proof.clone(),
+            stats_delta: self.stats_delta.clone(),
+            attachments: self
+                .attachments
+                .iter()
+                .map(|a| AttachmentEntry { name: a.name.clone(), sha256: a.sha256.clone(), size: a.bytes.len() })
+                .collect(),
+        };
+        let manifest = serde_json::to_vec(&manifest)?;
+        let manifest_len = u32::try_from(manifest.len()).map_err(|_| invalid("manifest exceeds 4 GiB"))?;
+
+        let mut payload = Vec::with_capacity(4 + manifest.len() + self.attachments.iter().map(|a| a.bytes.len()).sum::<usize>());
+        payload.extend_from_slice(&manifest_len.to_le_bytes());
+        payload.extend_from_slice(&manifest);
+        for attachment in &self.attachments {
+            payload.extend_from_slice(&attachment.bytes);
+        }
+
+        let mut bytes = BUNDLE_MAGIC.to_vec();
+        bytes.push(BUNDLE_FORMAT_VERSION);
+        bytes.extend_from_slice(&zstd::encode_all(payload.as_slice(), ZSTD_LEVEL)?);
+        Ok(bytes)
+    }
+
+    /// Decode a `.serenqa` archive and verify it
+    pub fn from_bytes(bytes: &[u8]) -> SerenQaResult<Self> {
+        let rest = bytes.strip_prefix(BUNDLE_MAGIC.as_slice()).ok_or_else(|| invalid("missing magic header"))?;
+        let (&version, compressed) = rest.split_first().ok_or_else(|| invalid("truncated header"))?;
+        if version != BUNDLE_FORMAT_VERSION {
+            return Err(invalid(format!("unsupported format version {}", version)));
+        }
+        let payload = zstd::decode_all(compressed)?;
+        let (len, rest) = payload.split_first_chunk::<4>().ok_or_else(|| invalid("truncated manifest"))?;
+        let len = u32::from_le_bytes(*len) as usize;
+        if rest.len() < len {
+            return Err(invalid("truncated manifest"));
+        }
+        let (manifest, mut data) = rest.split_at(len);
+        let manifest: Manifest = serde_json::from_slice(manifest)?;
+
+        let mut attachments = Vec::with_capacity(manifest.attachments.len());
+        for entry in manifest.attachments {
+            if data.len() < entry.size {
+                return Err(invalid(format!("attachment {} is truncated", entry.name)));
+            }
+            let (content, remaining) = data.split_at(entry.size);
+            attachments.push(BundleAttachment { name: entry.name, sha256: entry.sha256, bytes: content.to_vec() });
+            data = remaining;
+        }
+        if !data.is_empty() {
+            return Err(invalid("trailing bytes after the last attachment"));
+        }
+
+        let bundle = Self {
+            trace: serde_json::from_value(migrate(manifest.trace)?)?,
+            fold: manifest.fold,
+            proof: manifest.proof,
+            stats_delta: manifest.stats_delta,
+            attachments,
+        };
+        bundle.verify()?;
+        Ok(bundle)
+    }
+
+    /// Write the archive to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> SerenQaResult<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Read and verify the archive at `path`
+    pub fn open(path: impl AsRef<Path>) -> SerenQaResult<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+impl AttachmentResolver for Bundle {
+    fn fetch(&self, content_hash: &str) -> SerenQaResult<Option<Vec<u8>>> {
+        Ok(self.attachment(content_hash).map(|attachment| attachment.bytes.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fold_multilingual_memory::MultilingualMemoryFolder;
+    use crate::pipeline::language_events;
+    use crate::attachment::{Attachment, InMemoryAttachmentStore};
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+
+    fn bundle() -> Bundle {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.4, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "batik", "id", 0.9, 0.8).unwrap();
+        let fold = MultilingualMemoryFolder::new().fold_memory(&trace.trace_id, &language_events(&trace)).unwrap();
+        let delta = StatsDelta::preview(&LanguageAwareLeaderboard::new(), &trace, &fold).unwrap();
+        Bundle::new(trace)
+            .with_fold(fold)
+            .with_stats_delta(delta)
+            .with_attachment(BundleAttachment::new("figure.svg", b"<svg/>".to_vec()))
+            .with_attachment(BundleAttachment::new("data.csv", b"x,y\n1,2\n".to_vec()))
+    }
+
+    #[test]
+    fn test_round_trip_and_stats_delta() {
+        let bundle = bundle();
+        let delta = bundle.stats_delta.as_ref().unwrap();
+        assert_eq!((delta.traces_before, delta.traces_after), (0, 1));
+        assert!(delta.new_languages.len() == 2 && delta.overall_score_after > 0.0);
+
+        let path = std::env::temp_dir().join(format!("seren_bundle_{}.{}", std::process::id(), BUNDLE_EXTENSION));
+        bundle.save(&path).unwrap();
+        let opened = Bundle::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(opened.proof, bundle.proof);
+        assert_eq!(opened.stats_delta, bundle.stats_delta);
+        assert_eq!(opened.attachments, bundle.attachments);
+        let csv = BundleAttachment::new("copy.csv", b"x,y\n1,2\n".to_vec());
+        assert_eq!(opened.attachment(&csv.sha256).unwrap().name, "data.csv");
+        // Identical content is stored once
+        assert_eq!(opened.clone().with_attachment(csv).attachments.len(), 2);
+
+        // Artifact references on events resolve against the bundle
+        let dataset = Attachment::from_bytes("data.csv", "text/csv", b"x,y\n1,2\n");
+        assert_eq!(opened.fetch_verified(&dataset).unwrap(), b"x,y\n1,2\n");
+        let mut trace = opened.trace.clone();
+        let event_id = trace.events[0].event_id.clone();
+        trace.attach(&event_id, Attachment::from_bytes("notes.txt", "text/plain", b"lab notes")).unwrap();
+        let store = InMemoryAttachmentStore::new();
+        assert!(Bundle::new(trace.clone()).with_referenced_attachments(&store).is_err());
+        store.put(b"lab notes".to_vec());
+        let carried = Bundle::new(trace).with_referenced_attachments(&store).unwrap();
+        assert_eq!(carried.attachments[0].name, "notes.txt");
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_bundles() {
+        let mut tampered = bundle();
+        tampered.trace.events[1].serendipity_score = 0.1;
+        assert!(matches!(tampered.verify(), Err(SerenQaError::InvalidBundle(_))));
+        assert!(Bundle::from_bytes(&tampered.to_bytes().unwrap()).is_err());
+
+        let mut swapped = bundle();
+        swapped.attachments[0].bytes = b"<svg>forged</svg>".to_vec();
+        assert!(swapped.verify().unwrap_err().to_string().contains("figure.svg"));
+
+        let mut foreign = bundle();
+        foreign.stats_delta.as_mut().unwrap().contributor_id = "budi".to_string();
+        assert!(foreign.verify().is_err());
+
+        let bytes = bundle().to_bytes().unwrap();
+        assert!(Bundle::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Bundle::from_bytes(b"SRNB\x01").is_err());
+    }
+}
@@ -0,0 +1,211 @@
+// -*- coding: utf-8 -*-
+//! Score Recalibration Across Benchmark Versions
+//!
+//! When the scoring formula changes, historical overall scores become
+//! incomparable. Recalibration re-scores a corpus of contributor statistics
+//! under both configs, reports how scores and rankings moved, and fits a
+//! linear map so archived scores that only exist as numbers can be translated.
+
+use serde::{Deserialize, Serialize};
+use crate::ContributorStats::{LanguageAwareContributorStats, ScoringConfig, VersionedScore};
+
+/// Score of a single contributor under the old and new config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecalibratedScore {
+    /// Contributor ID
+    pub contributor_id: String,
+    /// Score under the old config
+    pub old_score: VersionedScore,
+    /// Score under the new config
+    pub new_score: VersionedScore,
+    /// Rank under the old config (1-based)
+    pub old_rank: usize,
+    /// Rank under the new config (1-based)
+    pub new_rank: usize,
+}
+
+impl RecalibratedScore {
+    /// Change in score value
+    pub fn delta(&self) -> f64 {
+        self.new_score.value - self.old_score.value
+    }
+}
+
+/// Calibration report between two scoring configs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationReport {
+    /// Old scoring-config version
+    pub old_version: String,
+    /// New scoring-config version
+    pub new_version: String,
+    /// Per-contributor mapped scores
+    pub scores: Vec<RecalibratedScore>,
+    /// Mean change in score
+    pub mean_delta: f64,
+    /// Largest absolute change in score
+    pub max_abs_delta: f64,
+    /// Spearman rank correlation between old and new rankings
+    pub rank_correlation: f64,
+    /// Number of contributors whose rank changed
+    pub rank_changes: usize,
+    /// Slope of the least-squares map old -> new
+    pub slope: f64,
+    /// Intercept of the least-squares map old -> new
+    pub intercept: f64,
+}
+
+impl CalibrationReport {
+    /// Map a historical score computed under the old config onto the new scale.
+    /// Returns `None` if the score was produced by a different config version.
+    pub fn map_score(&self, score: &VersionedScore) -> Option<VersionedScore> {
+        if score.config_version != self.old_version {
+            return None;
+        }
+        Some(VersionedScore {
+            value: self.slope * score.value + self.intercept,
+            config_version: self.new_version.clone(),
+        })
+    }
+}
+
+/// Ranks (1-based) of values sorted descending
+fn ranks(values: &[f64]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[b].partial_cmp(&values[a]).unwrap());
+    let mut ranks = vec![0; values.len()];
+    for (rank, index) in order.into_iter().enumerate() {
+        ranks[index] = rank + 1;
+    }
+    ranks
+}
+
+/// Re-score a corpus under two configs and produce a calibration report
+pub fn recalibrate(
+    corpus: &[LanguageAwareContributorStats],
+    old_config: &ScoringConfig,
+    new_config: &ScoringConfig,
+) -> CalibrationReport {
+    let old_scores: Vec<VersionedScore> = corpus.iter().map(|s| s.score_with(old_config)).collect();
+    let new_scores: Vec<VersionedScore> = corpus.iter().map(|s| s.score_with(new_config)).collect();
+
+    let old_values: Vec<f64> = old_scores.iter().map(|s| s.value).collect();
+    let new_values: Vec<f64> = new_scores.iter().map(|s| s.value).collect();
+    let old_ranks = ranks(&old_values);
+    let new_ranks = ranks(&new_values);
+
+    let scores: Vec<RecalibratedScore> = corpus
+        .iter()
+        .zip(old_scores.into_iter().zip(new_scores))
+        .enumerate()
+        .map(|(i, (stats, (old_score, new_score)))| RecalibratedScore {
+            contributor_id: stats.contributor_id.clone(),
+            old_score,
+            new_score,
+            old_rank: old_ranks[i],
+            new_rank: new_ranks[i],
+        })
+        .collect();
+
+    let n = scores.len() as f64;
+    let mean_delta = if scores.is_empty() {
+        0.0
+    } else {
+        scores.iter().map(|s| s.delta()).sum::<f64>() / n
+    };
+    let max_abs_delta = scores.iter().map(|s| s.delta().abs()).fold(0.0, f64::max);
+    let rank_changes = scores.iter().filter(|s| s.old_rank != s.new_rank).count();
+
+    let rank_correlation = if scores.len() < 2 {
+        1.0
+    } else {
+        let d_squared: f64 = scores
+            .iter()
+            .map(|s| (s.old_rank as f64 - s.new_rank as f64).powi(2))
+            .sum();
+        1.0 - 6.0 * d_squared / (n * (n * n - 1.0))
+    };
+
+    // Least-squares fit new = slope * old + intercept
+    let (slope, intercept) = if scores.is_empty() {
+        (1.0, 0.0)
+    } else {
+        let mean_old = old_values.iter().sum::<f64>() / n;
+        let mean_new = new_values.iter().sum::<f64>() / n;
+        let covariance: f64 = old_values
+            .iter()
+            .zip(&new_values)
+            .map(|(o, v)| (o - mean_old) * (v - mean_new))
+            .sum();
+        let variance: f64 = old_values.iter().map(|o| (o - mean_old).powi(2)).sum();
+        if variance > f64::EPSILON {
+            let slope = covariance / variance;
+            (slope, mean_new - slope * mean_old)
+        } else {
+            (1.0, mean_new - mean_old)
+        }
+    };
+
+    CalibrationReport {
+        old_version: old_config.version.clone(),
+        new_version: new_config.version.clone(),
+        scores,
+        mean_delta,
+        max_abs_delta,
+        rank_correlation,
+        rank_changes,
+        slope,
+        intercept,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus() -> Vec<LanguageAwareContributorStats> {
+        let mut stats1 = LanguageAwareContributorStats::new("researcher1");
+        stats1.add_trace(20, 0.85, 0.9, vec!["en".to_string(), "id".to_string()], 0.88, 0.9).unwrap();
+        stats1.add_discovery("Journavx");
+
+        let mut stats2 = LanguageAwareContributorStats::new("researcher2");
+        stats2.add_trace(45, 0.6, 0.7, vec!["en".to_string()], 0.85, 0.82).unwrap();
+
+        let mut stats3 = LanguageAwareContributorStats::new("researcher3");
+        stats3.add_trace(5, 0.4, 0.5, vec!["en".to_string()], 0.7, 0.7).unwrap();
+
+        vec![stats1, stats2, stats3]
+    }
+
+    #[test]
+    fn test_same_config_is_identity() {
+        let config = ScoringConfig::default();
+        let report = recalibrate(&corpus(), &config, &config);
+        assert_eq!(report.rank_changes, 0);
+        assert!((report.rank_correlation - 1.0).abs() < 1e-12);
+        assert!(report.max_abs_delta < 1e-12);
+        assert!((report.slope - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_recalibration_report() {
+        let old_config = ScoringConfig::default();
+        let new_config = ScoringConfig {
+            version: "v2".to_string(),
+            depth_weight: 0.40,
+            uniqueness_weight: 0.15,
+            serendipity_weight: 0.10,
+            ..ScoringConfig::default()
+        };
+        let report = recalibrate(&corpus(), &old_config, &new_config);
+
+        assert_eq!(report.old_version, "v1");
+        assert_eq!(report.new_version, "v2");
+        assert_eq!(report.scores.len(), 3);
+        assert!(report.scores.iter().all(|s| s.new_score.config_version == "v2"));
+
+        let historical = VersionedScore { value: 0.5, config_version: "v1".to_string() };
+        let mapped = report.map_score(&historical).unwrap();
+        assert_eq!(mapped.config_version, "v2");
+        assert!(report.map_score(&mapped).is_none());
+    }
+}
@@ -0,0 +1,346 @@
+// -*- coding: utf-8 -*-
+//! Canonical Encoding for Provenance Hashes
+//!
+//! The original provenance hash fed `format!("{}", f64)` text and unframed
+//! strings to SHA-256, so it depended on float formatting and field
+//! boundaries were ambiguous. Version 1 hashes a canonical binary encoding
+//! instead: fixed-width little-endian floats, length-prefixed strings, counted
+//! lists, and metadata sorted by key. It covers every event field that carries
+//! meaning, stage, agent, contributor, timestamp, and confidence included, so
+//! none can change under a signature. Hashes carry a `v1:` prefix; unprefixed
+//! hashes are legacy and are still recomputed the old way for verification.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use crate::serendipity_trace::{SerendipityEvent, SerendipityTransition};
+use crate::circuit::EventAttachment;
+use crate::knowledge_source::KnowledgeSource;
+use crate::benchmark::BenchmarkResult;
+use crate::metadata::MetadataValue;
+use crate::review::ReviewVerdict;
+
+/// Provenance hash encoding
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HashVersion {
+    /// Unprefixed hash over formatted text (kept for verifying old hashes)
+    Legacy,
+    /// Canonical binary encoding, hashes prefixed `v1:`
+    V1,
+}
+
+impl HashVersion {
+    /// Encoding used for new hashes
+    pub const CURRENT: HashVersion = HashVersion::V1;
+
+    /// Prefix written ahead of the hex digest
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            HashVersion::Legacy => "",
+            HashVersion::V1 => "v1:",
+        }
+    }
+
+    /// Encoding a hash was computed with, from its prefix
+    pub fn of(hash: &str) -> Self {
+        if hash.starts_with(HashVersion::V1.prefix()) {
+            HashVersion::V1
+        } else {
+            HashVersion::Legacy
+        }
+    }
+}
+
+/// SHA-256 hasher writing trace parts in a given encoding.
+/// `SerendipityTrace` and `StreamingSerendipityTrace` feed it the same parts
+/// in the same order, so both produce the same hash.
+#[derive(Debug, Clone)]
+pub struct ProvenanceHasher {
+    hasher: Sha256,
+    version: HashVersion,
+}
+
+impl ProvenanceHasher {
+    /// Empty hasher for an encoding
+    pub fn new(version: HashVersion) -> Self {
+        Self { hasher: Sha256::new(), version }
+    }
+
+    /// Encoding in use
+    pub fn version(&self) -> HashVersion {
+        self.version
+    }
+
+    fn write_str(&mut self, value: &str) {
+        if self.version == HashVersion::V1 {
+            self.hasher.update((value.len() as u64).to_le_bytes());
+        }
+        self.hasher.update(value.as_bytes());
+    }
+
+    fn write_f64(&mut self, value: f64) {
+        match self.version {
+            HashVersion::Legacy => self.hasher.update(format!("{}", value).as_bytes()),
+            HashVersion::V1 => self.hasher.update(value.to_le_bytes()),
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.hasher.update(value.to_le_bytes());
+    }
+
+    fn write_count(&mut self, count: usize) {
+        self.write_u64(count as u64);
+    }
+
+    fn write_opt_str(&mut self, value: Option<&str>) {
+        match value {
+            Some(value) => {
+                self.hasher.update([1]);
+                self.write_str(value);
+            }
+            None => self.hasher.update([0]),
+        }
+    }
+
+    /// Stage or agent name, tagged so a custom name cannot pose as a built-in one
+    fn write_tagged_name(&mut self, custom: bool, name: &str) {
+        self.hasher.update([custom as u8]);
+        self.write_str(name);
+    }
+
+    /// Hash the trace identity
+    pub fn header(&mut self, trace_id: &str, contributor_id: &str, backend: &str, discovery_name: &str) {
+        for field in [trace_id, contributor_id, backend, discovery_name] {
+            self.write_str(field);
+        }
+    }
+
+    /// Hash the link to the previous trace in a hash chain
+    pub fn chain_link(&mut self, prev_trace_hash: &str) {
+        self.write_str(prev_trace_hash);
+    }
+
+    /// Hash an event. Text is hashed via its content hash so redaction keeps
+    /// the hash stable. V1 writes every field that carries meaning, in a
+    /// fixed order: ID, timestamp, stage, agent, contributor, content,
+    /// language, serendipity, confidence, then metadata, sources,
+    /// attachments, and benchmark.
+    pub fn event(&mut self, event: &SerendipityEvent) {
+        match self.version {
+            // Sources, attachments, and benchmarks are only hashed when
+            // present, keeping older hashes stable
+            HashVersion::Legacy => {
+                self.write_str(&event.event_id);
+                self.write_str(&event.content_hash());
+                self.write_str(&event.language);
+                self.write_f64(event.serendipity_score);
+                for source in &event.knowledge_sources {
+                    self.hasher.update(source.hash_bytes().as_bytes());
+                }
+                for attachment in &event.attachments {
+                    self.hasher.update(attachment.content_hash().as_bytes());
+                }
+                if let Some(benchmark) = &event.benchmark {
+                    self.hasher.update(benchmark.hash_bytes().as_bytes());
+                }
+            }
+            HashVersion::V1 => {
+                self.write_str(&event.event_id);
+                self.write_str(&event.timestamp.to_rfc3339());
+                self.write_tagged_name(event.stage.is_custom(), &event.stage.name());
+                self.write_tagged_name(event.agent.is_custom(), &event.agent.name());
+                self.write_opt_str(event.contributor.as_deref());
+                self.write_str(&event.content_hash());
+                self.write_str(&event.language);
+                self.write_f64(event.serendipity_score);
+                self.write_f64(event.confidence);
+                let mut metadata: Vec<(&String, &MetadataValue)> = event.metadata.iter().collect();
+                metadata.sort_by(|a, b| a.0.cmp(b.0));
+                self.write_count(metadata.len());
+                for (key, value) in metadata {
+                    self.write_str(key);
+                    self.metadata_value(value);
+                }
+                self.write_count(event.knowledge_sources.len());
+                for source in &event.knowledge_sources {
+                    self.knowledge_source(source);
+                }
+                self.write_count(event.attachments.len());
+                for attachment in &event.attachments {
+                    self.write_str(attachment.content_hash());
+                    // The URI is only a location and stays out of the hash
+                    if let EventAttachment::Artifact(artifact) = attachment {
+                        self.write_str(&artifact.media_type);
+                    }
+                }
+                match &event.benchmark {
+                    Some(benchmark) => {
+                        self.hasher.update([1]);
+                        self.benchmark(benchmark);
+                    }
+                    None => self.hasher.update([0]),
+                }
+            }
+        }
+    }
+
+    /// Hash a transition between events
+    pub fn transition(&mut self, transition: &SerendipityTransition) {
+        self.write_str(&transition.from_event);
+        self.write_str(&transition.to_event);
+        self.write_f64(transition.transition_score);
+    }
+
+    /// Hash the contributors sharing credit for the trace (V1 only), so credit
+    /// cannot be reassigned under a signature
+    pub fn co_contributors(&mut self, contributor_ids: &[String]) {
+        if self.version == HashVersion::V1 {
+            self.write_count(contributor_ids.len());
+            for contributor_id in contributor_ids {
+                self.write_str(contributor_id);
+            }
+        }
+    }
+
+    /// Hash a child trace by its own provenance hash
+    pub fn subtrace(&mut self, parent_event_id: &str, hash: &str) {
+        self.write_str(parent_event_id);
+        self.write_str(hash);
+    }
+
+    /// Hash a review verdict
+    pub fn verdict(&mut self, verdict: &ReviewVerdict) {
+        match self.version {
+            HashVersion::Legacy => self.hasher.update(verdict.hash_bytes().as_bytes()),
+            HashVersion::V1 => {
+                self.write_str(&verdict.reviewer);
+                self.write_str(&format!("{:?}", verdict.decision));
+                self.write_str(verdict.event_id.as_deref().unwrap_or(""));
+                self.write_str(&verdict.comment);
+                self.write_str(&verdict.at.to_rfc3339());
+            }
+        }
+    }
+
+    fn metadata_value(&mut self, value: &MetadataValue) {
+        match value {
+            MetadataValue::Bool(flag) => self.hasher.update([0, *flag as u8]),
+            MetadataValue::Number(number) => {
+                self.hasher.update([1]);
+                self.write_f64(*number);
+            }
+            MetadataValue::String(text) => {
+                self.hasher.update([2]);
+                self.write_str(text);
+            }
+            MetadataValue::Json(json) => {
+                self.hasher.update([3]);
+                self.write_str(&json.to_string());
+            }
+        }
+    }
+
+    fn knowledge_source(&mut self, source: &KnowledgeSource) {
+        self.write_str(&format!("{:?}", source.kind));
+        self.write_str(&source.title);
+        self.write_str(&source.language);
+        self.write_str(source.cultural_origin.as_deref().unwrap_or_default());
+        self.write_str(source.reference.as_deref().unwrap_or_default());
+    }
+
+    fn benchmark(&mut self, benchmark: &BenchmarkResult) {
+        self.write_str(&benchmark.metric);
+        self.write_f64(benchmark.value);
+        self.write_f64(benchmark.baseline);
+        self.write_f64(benchmark.improvement_pct);
+        self.write_str(&benchmark.units);
+        self.write_u64(benchmark.runs as u64);
+        self.write_f64(benchmark.variance);
+        self.write_u64(benchmark.baseline_runs.map_or(u64::MAX, |runs| runs as u64));
+        self.write_f64(benchmark.baseline_variance.unwrap_or(f64::NAN));
+        self.hasher.update([benchmark.higher_is_better as u8]);
+    }
+
+    /// Prefixed hex digest
+    pub fn finish(&self) -> String {
+        format!("{}{:x}", self.version.prefix(), self.hasher.clone().finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, SerendipityAgent};
+
+    fn trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.1, 0.8).unwrap();
+        trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "in2", "out2", "id", 0.7, 0.9).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_versioned_hashes() {
+        let trace = trace();
+        let current = trace.compute_provenance_hash();
+        assert!(current.starts_with("v1:") && current.len() == 67);
+        assert_eq!(HashVersion::of(&current), HashVersion::V1);
+
+        let legacy = trace.compute_provenance_hash_with(HashVersion::Legacy);
+        assert_eq!(legacy.len(), 64);
+        assert_eq!(HashVersion::of(&legacy), HashVersion::Legacy);
+        assert!(trace.verify_provenance_hash(&current));
+        assert!(trace.verify_provenance_hash(&legacy));
+        assert!(!trace.verify_provenance_hash("v1:00"));
+    }
+
+    #[test]
+    fn test_canonical_encoding_frames_fields() {
+        // Legacy concatenation cannot tell where one field ends and the next begins
+        let mut a = ProvenanceHasher::new(HashVersion::Legacy);
+        a.header("ab", "c", "", "");
+        let mut b = ProvenanceHasher::new(HashVersion::Legacy);
+        b.header("a", "bc", "", "");
+        assert_eq!(a.finish(), b.finish());
+
+        let mut a = ProvenanceHasher::new(HashVersion::V1);
+        a.header("ab", "c", "", "");
+        let mut b = ProvenanceHasher::new(HashVersion::V1);
+        b.header("a", "bc", "", "");
+        assert_ne!(a.finish(), b.finish());
+
+        // Metadata is hashed in key order, whatever the map's iteration order
+        let mut first = trace();
+        let mut second = first.clone();
+        first.events[0].metadata.insert("alpha".to_string(), MetadataValue::Number(1.0));
+        first.events[0].metadata.insert("beta".to_string(), MetadataValue::Bool(true));
+        second.events[0].metadata.insert("beta".to_string(), MetadataValue::Bool(true));
+        second.events[0].metadata.insert("alpha".to_string(), MetadataValue::Number(1.0));
+        assert_eq!(first.compute_provenance_hash_with(HashVersion::V1), second.compute_provenance_hash_with(HashVersion::V1));
+        let mut plain = first.clone();
+        plain.events[0].metadata.clear();
+        assert_ne!(first.compute_provenance_hash_with(HashVersion::V1), plain.compute_provenance_hash_with(HashVersion::V1));
+        assert_eq!(first.compute_provenance_hash_with(HashVersion::Legacy), plain.compute_provenance_hash_with(HashVersion::Legacy));
+    }
+
+    #[test]
+    fn test_v1_hashes_every_event_field() {
+        let trace = trace();
+        let hash = trace.compute_provenance_hash();
+        type Edit = fn(&mut SerendipityEvent);
+        let edits: [(&str, Edit); 6] = [
+            ("stage", |e| e.stage = SerendipityStage::Publication),
+            ("custom stage", |e| e.stage = SerendipityStage::custom("Exploration")),
+            ("agent", |e| e.agent = SerendipityAgent::Synthesizer),
+            ("confidence", |e| e.confidence = 0.1),
+            ("timestamp", |e| e.timestamp += chrono::Duration::seconds(1)),
+            ("contributor", |e| e.contributor = Some("budi".to_string())),
+        ];
+        for (field, edit) in edits {
+            let mut edited = trace.clone();
+            edit(&mut edited.events[0]);
+            assert_ne!(edited.compute_provenance_hash_with(HashVersion::V1), hash, "{} is not hashed", field);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// -*- coding: utf-8 -*-
+//! Causal Contribution of Events
+//!
+//! A transition score only averages the confidences of its two events; it
+//! says nothing about how much an early observation mattered to where the
+//! trace ended up. Here the transitions form a graph whose edges carry their
+//! scores, and an event's influence on a target event is the sum, over every
+//! path from the event to the target, of the product of the edge scores
+//! along the path (the target influences itself fully). An event's
+//! contribution is its serendipity weighted by that influence: a surprising
+//! observation early in a weak chain counts for less than one feeding the
+//! discovery through confident steps. Contributions are also given as shares
+//! summing to one, which `CreditSplit::ByContribution` uses to divide credit.
+//!
+//! Transitions pointing back to an earlier event (possible in hand-built or
+//! merged traces) are ignored, so influence is always finite.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::{SerendipityAgent, SerendipityStage, SerendipityTrace};
+
+/// Contribution of one event to a target event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventContribution {
+    pub event_id: String,
+    pub agent: SerendipityAgent,
+    pub stage: SerendipityStage,
+    /// Path-weighted reach of the event to the target (1 for the target)
+    pub influence: f64,
+    /// Serendipity times influence
+    pub contribution: f64,
+    /// Fraction of all events' contributions (0 when there are none)
+    pub share: f64,
+}
+
+impl SerendipityTrace {
+    /// Each event's contribution to the final event, in event order
+    pub fn event_contributions(&self) -> Vec<EventContribution> {
+        match self.events.last() {
+            Some(last) => self.event_contributions_to(&last.event_id).unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Each event's contribution to the event `target_id`, in event order
+    /// (`None` if the trace has no such event)
+    pub fn event_contributions_to(&self, target_id: &str) -> Option<Vec<EventContribution>> {
+        let position: HashMap<&str, usize> =
+            self.events.iter().enumerate().map(|(i, e)| (e.event_id.as_str(), i)).collect();
+        let target = *position.get(target_id)?;
+
+        // Forward edges by source event
+        let mut edges: Vec<Vec<(usize, f64)>> = vec![Vec::new(); self.events.len()];
+        for transition in &self.transitions {
+            let from = position.get(transition.from_event.as_str());
+            let to = position.get(transition.to_event.as_str());
+            if let (Some(&from), Some(&to)) = (from, to) {
+                if from < to {
+                    edges[from].push((to, transition.transition_score));
+                }
+            }
+        }
+
+        // Influence on the target, from the target back to the first event
+        let mut influence = vec![0.0; self.events.len()];
+        influence[target] = 1.0;
+        for from in (0..target).rev() {
+            influence[from] = edges[from].iter().map(|&(to, score)| score * influence[to]).sum();
+        }
+
+        let contributions: Vec<f64> = self.events
+            .iter()
+            .zip(&influence)
+            .map(|(event, influence)| event.serendipity_score * influence)
+            .collect();
+        let total: f64 = contributions.iter().sum();
+        Some(
+            self.events
+                .iter()
+                .zip(influence)
+                .zip(contributions)
+                .map(|((event, influence), contribution)| EventContribution {
+                    event_id: event.event_id.clone(),
+                    agent: event.agent.clone(),
+                    stage: event.stage.clone(),
+                    influence,
+                    contribution,
+                    share: if total > 0.0 { contribution / total } else { 0.0 },
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityTransition;
+
+    fn trace(events: &[(f64, f64)]) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        for (i, &(serendipity, confidence)) in events.iter().enumerate() {
+            trace.log_event(
+                SerendipityStage::Exploration,
+                SerendipityAgent::Explorer,
+                "Survey",
+                &format!("note {}", i),
+                "en",
+                serendipity,
+                confidence,
+            ).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_influence_decays_along_the_chain() {
+        let trace = trace(&[(0.8, 0.5), (0.4, 0.9), (0.6, 0.7)]);
+        let contributions = trace.event_contributions();
+        let influence: Vec<f64> = contributions.iter().map(|c| c.influence).collect();
+        // Transition scores: (0.5 + 0.9) / 2 = 0.7 and (0.9 + 0.7) / 2 = 0.8
+        assert!((influence[0] - 0.56).abs() < 1e-12);
+        assert!((influence[1] - 0.8).abs() < 1e-12);
+        assert_eq!(influence[2], 1.0);
+        assert!((contributions[0].contribution - 0.448).abs() < 1e-12);
+        let shares: f64 = contributions.iter().map(|c| c.share).sum();
+        assert!((shares - 1.0).abs() < 1e-12);
+
+        // Events after the target do not contribute to it
+        let to_middle = trace.event_contributions_to(&trace.events[1].event_id).unwrap();
+        assert_eq!(to_middle[2].influence, 0.0);
+        assert!(trace.event_contributions_to("missing").is_none());
+    }
+
+    #[test]
+    fn test_parallel_paths_add_up() {
+        let mut trace = trace(&[(0.5, 1.0), (0.5, 1.0), (0.5, 1.0)]);
+        // Shortcut from the first event straight to the last, plus a back edge
+        let mut shortcut = SerendipityTransition::between(&trace.events[0], &trace.events[2]);
+        shortcut.transition_score = 0.5;
+        let back = SerendipityTransition::between(&trace.events[2], &trace.events[0]);
+        trace.transitions.extend([shortcut, back]);
+
+        let contributions = trace.event_contributions();
+        assert!((contributions[0].influence - 1.5).abs() < 1e-12);
+        assert!((contributions[0].share - 0.75 / 1.75).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,538 @@
+// -*- coding: utf-8 -*-
+//! Quantum Circuit Artifacts
+//!
+//! Typed attachment for OpenQASM 3 circuits used by quantum backends. Circuits
+//! are validated on attachment (version header, declared registers, qubit
+//! indices), summarized as gate-count statistics for the backend report, and
+//! their content hash enters the provenance hash, so algorithmic claims are
+//! tied to the exact circuit that produced them.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use crate::serendipity_trace::SerendipityTrace;
+use crate::attachment::Attachment;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Statements that are accepted but do not touch qubits
+const CLASSICAL_KEYWORDS: [&str; 14] = [
+    "include", "bit", "creg", "int", "uint", "float", "angle", "bool", "const",
+    "input", "output", "let", "duration", "stretch",
+];
+
+/// Gate modifiers that may prefix a gate application (e.g. `ctrl @ x`)
+const GATE_MODIFIERS: [&str; 4] = ["ctrl", "negctrl", "inv", "pow"];
+
+/// Reason a circuit failed to parse
+#[derive(Debug, Clone, PartialEq)]
+pub enum CircuitParseError {
+    /// First statement is not an `OPENQASM` version header
+    MissingVersion,
+    /// Header names a version other than 3.x
+    UnsupportedVersion(String),
+    /// Text ends inside a statement or block
+    UnterminatedStatement { line: usize },
+    /// Operand names an undeclared qubit register
+    UnknownRegister { line: usize, name: String },
+    /// Operand index exceeds the register size
+    IndexOutOfRange { line: usize, register: String, index: usize, size: usize },
+    /// Statement could not be understood
+    Malformed { line: usize, statement: String },
+}
+
+impl std::fmt::Display for CircuitParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitParseError::MissingVersion => write!(f, "circuit does not start with an OPENQASM header"),
+            CircuitParseError::UnsupportedVersion(v) => write!(f, "unsupported OpenQASM version {} (expected 3)", v),
+            CircuitParseError::UnterminatedStatement { line } => write!(f, "line {}: unterminated statement", line),
+            CircuitParseError::UnknownRegister { line, name } => {
+                write!(f, "line {}: unknown qubit register {:?}", line, name)
+            }
+            CircuitParseError::IndexOutOfRange { line, register, index, size } => {
+                write!(f, "line {}: {}[{}] is out of range for size {}", line, register, index, size)
+            }
+            CircuitParseError::Malformed { line, statement } => {
+                write!(f, "line {}: cannot parse {:?}", line, statement)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CircuitParseError {}
+
+/// Gate-count statistics of a circuit
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GateStats {
+    /// Declared qubits across all registers
+    pub qubits: usize,
+    /// Gate applications (register broadcasts count once per qubit)
+    pub gate_count: usize,
+    /// Gates acting on two or more qubits
+    pub multi_qubit_gates: usize,
+    /// Measurements
+    pub measurements: usize,
+    /// Circuit depth over gates and measurements
+    pub depth: usize,
+    /// Count per gate name
+    pub gates: BTreeMap<String, usize>,
+}
+
+/// Validated OpenQASM 3 circuit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CircuitArtifact {
+    /// Circuit name
+    pub name: String,
+    /// OpenQASM 3 source
+    pub qasm: String,
+    /// SHA-256 of the source (hex)
+    pub content_hash: String,
+    /// Gate-count statistics
+    pub stats: GateStats,
+}
+
+impl CircuitArtifact {
+    /// Parse and validate OpenQASM 3 source
+    pub fn from_qasm3(name: &str, qasm: &str) -> Result<Self, CircuitParseError> {
+        let stats = parse_qasm3(qasm)?;
+        Ok(Self {
+            name: name.to_string(),
+            qasm: qasm.to_string(),
+            content_hash: format!("{:x}", Sha256::digest(qasm.as_bytes())),
+            stats,
+        })
+    }
+
+    /// Check the stored hash still matches the source
+    pub fn verify_hash(&self) -> bool {
+        format!("{:x}", Sha256::digest(self.qasm.as_bytes())) == self.content_hash
+    }
+}
+
+/// Typed artifact attached to an event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventAttachment {
+    /// Quantum circuit
+    Circuit(CircuitArtifact),
+    /// External artifact referenced by content hash
+    Artifact(Attachment),
+}
+
+impl EventAttachment {
+    /// Content hash fed to the provenance hash
+    pub fn content_hash(&self) -> &str {
+        match self {
+            EventAttachment::Circuit(circuit) => &circuit.content_hash,
+            EventAttachment::Artifact(artifact) => &artifact.content_hash,
+        }
+    }
+}
+
+/// Remove `//` and `/* */` comments, keeping line breaks
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Split into (line, statement) pairs; `{ ... }` blocks end a statement
+fn statements(text: &str) -> Result<Vec<(usize, String)>, CircuitParseError> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut line = 1;
+    let mut start_line = 1;
+    let mut depth = 0usize;
+    for c in text.chars() {
+        if current.trim().is_empty() {
+            start_line = line;
+        }
+        if c == '\n' {
+            line += 1;
+        }
+        match c {
+            '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                current.push(c);
+                if depth == 0 {
+                    result.push((start_line, current.trim().to_string()));
+                    current.clear();
+                }
+            }
+            ';' if depth == 0 => {
+                result.push((start_line, current.trim().to_string()));
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() || depth > 0 {
+        return Err(CircuitParseError::UnterminatedStatement { line: start_line });
+    }
+    Ok(result)
+}
+
+/// Qubit registers: name -> (global offset, size)
+struct Registers {
+    map: HashMap<String, (usize, usize)>,
+    total: usize,
+}
+
+impl Registers {
+    /// Global qubit indices named by an operand (`q` or `q[i]`)
+    fn resolve(&self, line: usize, operand: &str) -> Result<Vec<usize>, CircuitParseError> {
+        let operand = operand.trim();
+        let (name, index) = match operand.split_once('[') {
+            Some((name, rest)) => {
+                let index = rest
+                    .strip_suffix(']')
+                    .and_then(|i| i.trim().parse::<usize>().ok())
+                    .ok_or_else(|| CircuitParseError::Malformed { line, statement: operand.to_string() })?;
+                (name.trim(), Some(index))
+            }
+            None => (operand, None),
+        };
+        let &(offset, size) = self.map.get(name).ok_or_else(|| CircuitParseError::UnknownRegister {
+            line,
+            name: name.to_string(),
+        })?;
+        match index {
+            Some(index) if index >= size => Err(CircuitParseError::IndexOutOfRange {
+                line,
+                register: name.to_string(),
+                index,
+                size,
+            }),
+            Some(index) => Ok(vec![offset + index]),
+            None => Ok((offset..offset + size).collect()),
+        }
+    }
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse `qubit[n] name`, `qubit name`, or `qreg name[n]`
+fn declare(registers: &mut Registers, line: usize, statement: &str) -> Result<(), CircuitParseError> {
+    let malformed = || CircuitParseError::Malformed { line, statement: statement.to_string() };
+    let (name, size) = if let Some(rest) = statement.strip_prefix("qreg") {
+        let (name, size) = rest.trim().split_once('[').ok_or_else(malformed)?;
+        (name.trim(), size.trim_end_matches(']').trim().parse::<usize>().map_err(|_| malformed())?)
+    } else {
+        let rest = statement.strip_prefix("qubit").ok_or_else(malformed)?.trim();
+        match rest.strip_prefix('[') {
+            Some(sized) => {
+                let (size, name) = sized.split_once(']').ok_or_else(malformed)?;
+                (name.trim(), size.trim().parse::<usize>().map_err(|_| malformed())?)
+            }
+            None => (rest, 1),
+        }
+    };
+    if !is_identifier(name) {
+        return Err(malformed());
+    }
+    registers.map.insert(name.to_string(), (registers.total, size));
+    registers.total += size;
+    Ok(())
+}
+
+/// Validate OpenQASM 3 source and compute gate statistics
+pub fn parse_qasm3(qasm: &str) -> Result<GateStats, CircuitParseError> {
+    let statements = statements(&strip_comments(qasm))?;
+    let mut iter = statements.into_iter().filter(|(_, s)| !s.is_empty());
+
+    let (_, header) = iter.next().ok_or(CircuitParseError::MissingVersion)?;
+    let version = header.strip_prefix("OPENQASM").ok_or(CircuitParseError::MissingVersion)?.trim();
+    if version != "3" && !version.starts_with("3.") {
+        return Err(CircuitParseError::UnsupportedVersion(version.to_string()));
+    }
+
+    let mut registers = Registers { map: HashMap::new(), total: 0 };
+    let mut stats = GateStats::default();
+    let mut layers: Vec<usize> = Vec::new();
+
+    for (line, statement) in iter {
+        let keyword = statement
+            .split(|c: char| c.is_whitespace() || c == '[' || c == '(')
+            .next()
+            .unwrap_or_default();
+
+        if keyword == "qubit" || keyword == "qreg" {
+            declare(&mut registers, line, &statement)?;
+        } else if CLASSICAL_KEYWORDS.contains(&keyword) || keyword == "gate" || keyword == "def" {
+            continue;
+        } else if keyword == "measure" || statement.contains("= measure") {
+            let target = match statement.split_once("measure") {
+                Some((_, rest)) => rest.split("->").next().unwrap_or_default(),
+                None => "",
+            };
+            for qubit in registers.resolve(line, target)? {
+                stats.measurements += 1;
+                schedule(&mut stats, &mut layers, &[qubit]);
+            }
+        } else if keyword == "reset" || keyword == "barrier" {
+            let operands = statement[keyword.len()..].trim();
+            for operand in operands.split(',').filter(|o| !o.trim().is_empty()) {
+                registers.resolve(line, operand)?;
+            }
+        } else {
+            // Gate application with optional modifiers: [mod @]* name[(params)] operands
+            let mut body = statement.as_str();
+            while let Some((prefix, rest)) = body.split_once('@') {
+                let modifier = prefix.trim().split('(').next().unwrap_or_default().trim();
+                if !GATE_MODIFIERS.contains(&modifier) {
+                    break;
+                }
+                body = rest.trim();
+            }
+            let name_end = body.find(|c: char| c.is_whitespace() || c == '(').unwrap_or(body.len());
+            let name = &body[..name_end];
+            let mut rest = body[name_end..].trim_start();
+            if rest.starts_with('(') {
+                let close = rest.find(')').ok_or_else(|| CircuitParseError::Malformed {
+                    line,
+                    statement: statement.clone(),
+                })?;
+                rest = &rest[close + 1..];
+            }
+            if !is_identifier(name) || rest.trim().is_empty() {
+                return Err(CircuitParseError::Malformed { line, statement: statement.clone() });
+            }
+
+            let operands = rest
+                .split(',')
+                .map(|operand| registers.resolve(line, operand))
+                .collect::<Result<Vec<_>, _>>()?;
+            let applications: Vec<Vec<usize>> = if operands.len() == 1 {
+                operands[0].iter().map(|&q| vec![q]).collect()
+            } else {
+                vec![operands.concat()]
+            };
+            for qubits in applications {
+                stats.gate_count += 1;
+                if qubits.len() > 1 {
+                    stats.multi_qubit_gates += 1;
+                }
+                *stats.gates.entry(name.to_string()).or_insert(0) += 1;
+                schedule(&mut stats, &mut layers, &qubits);
+            }
+        }
+    }
+
+    stats.qubits = registers.total;
+    Ok(stats)
+}
+
+/// Place an operation on the earliest layer after its qubits' last use
+fn schedule(stats: &mut GateStats, layers: &mut Vec<usize>, qubits: &[usize]) {
+    if let Some(&highest) = qubits.iter().max() {
+        if layers.len() <= highest {
+            layers.resize(highest + 1, 0);
+        }
+    }
+    let layer = qubits.iter().map(|&q| layers[q]).max().unwrap_or(0) + 1;
+    for &q in qubits {
+        layers[q] = layer;
+    }
+    stats.depth = stats.depth.max(layer);
+}
+
+/// Circuit reference in a backend report
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CircuitSummary {
+    /// Event the circuit is attached to
+    pub event_id: String,
+    /// Circuit name
+    pub name: String,
+    /// Content hash
+    pub content_hash: String,
+    /// Gate statistics
+    pub stats: GateStats,
+}
+
+/// Backend usage report for a trace
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackendReport {
+    /// Backend name
+    pub backend: String,
+    /// Attached circuits in event order
+    pub circuits: Vec<CircuitSummary>,
+    /// Gates across all circuits
+    pub total_gates: usize,
+    /// Widest circuit (qubits)
+    pub max_qubits: usize,
+    /// Deepest circuit
+    pub max_depth: usize,
+}
+
+impl BackendReport {
+    /// Write the report to `out`
+    pub fn render<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "Backend: {}", self.backend)?;
+        writeln!(out, "Circuits: {} ({} gates, up to {} qubits, depth {})",
+            self.circuits.len(), self.total_gates, self.max_qubits, self.max_depth)?;
+        for circuit in &self.circuits {
+            let gates: Vec<String> = circuit.stats.gates.iter().map(|(g, n)| format!("{}×{}", g, n)).collect();
+            writeln!(out, "  • {} [{}] {} qubits, depth {}: {}",
+                circuit.name, &circuit.content_hash[..12.min(circuit.content_hash.len())],
+                circuit.stats.qubits, circuit.stats.depth, gates.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl SerendipityTrace {
+    /// Attach a validated circuit to a logged event
+    pub fn attach_circuit(&mut self, event_id: &str, circuit: CircuitArtifact) -> SerenQaResult<()> {
+        let event = self.events
+            .iter_mut()
+            .find(|e| e.event_id == event_id)
+            .ok_or_else(|| SerenQaError::UnknownEvent(event_id.to_string()))?;
+        event.attachments.push(EventAttachment::Circuit(circuit));
+        self.invalidate_provenance();
+        Ok(())
+    }
+
+    /// Circuits attached to the trace's events, with gate statistics
+    pub fn backend_report(&self) -> BackendReport {
+        let circuits: Vec<CircuitSummary> = self.events
+            .iter()
+            .flat_map(|event| event.attachments.iter().map(move |a| (event, a)))
+            .filter_map(|(event, attachment)| match attachment {
+                EventAttachment::Circuit(circuit) => Some(CircuitSummary {
+                    event_id: event.event_id.clone(),
+                    name: circuit.name.clone(),
+                    content_hash: circuit.content_hash.clone(),
+                    stats: circuit.stats.clone(),
+                }),
+                EventAttachment::Artifact(_) => None,
+            })
+            .collect();
+
+        BackendReport {
+            backend: self.backend.clone(),
+            total_gates: circuits.iter().map(|c| c.stats.gate_count).sum(),
+            max_qubits: circuits.iter().map(|c| c.stats.qubits).max().unwrap_or(0),
+            max_depth: circuits.iter().map(|c| c.stats.depth).max().unwrap_or(0),
+            circuits,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    const QUANTUM_WALK: &str = "OPENQASM 3.0;
+include \"stdgates.inc\";
+// two-qubit walk step
+qubit[2] q;
+bit[2] c;
+gate coin a { h a; }
+h q;
+cx q[0], q[1];
+rz(pi / 4) q[1];
+ctrl @ x q[1], q[0];
+/* block
+   comment */
+c = measure q;
+";
+
+    #[test]
+    fn test_gate_statistics() {
+        let circuit = CircuitArtifact::from_qasm3("quantum_walk", QUANTUM_WALK).unwrap();
+        let stats = &circuit.stats;
+        assert_eq!(stats.qubits, 2);
+        assert_eq!(stats.gate_count, 5);
+        assert_eq!(stats.multi_qubit_gates, 2);
+        assert_eq!(stats.measurements, 2);
+        assert_eq!(stats.gates["h"], 2);
+        assert_eq!(stats.gates["x"], 1);
+        assert_eq!(stats.depth, 5);
+        assert!(circuit.verify_hash());
+    }
+
+    #[test]
+    fn test_invalid_circuits() {
+        assert_eq!(parse_qasm3("qubit q;"), Err(CircuitParseError::MissingVersion));
+        assert_eq!(
+            parse_qasm3("OPENQASM 2.0; qreg q[1];"),
+            Err(CircuitParseError::UnsupportedVersion("2.0".to_string()))
+        );
+        assert!(matches!(
+            parse_qasm3("OPENQASM 3;\nqubit[2] q;\ncx q[0], q[2];"),
+            Err(CircuitParseError::IndexOutOfRange { line: 3, index: 2, size: 2, .. })
+        ));
+        assert!(matches!(
+            parse_qasm3("OPENQASM 3; h r[0];"),
+            Err(CircuitParseError::UnknownRegister { .. })
+        ));
+        assert!(matches!(parse_qasm3("OPENQASM 3; qubit q; h q"), Err(CircuitParseError::UnterminatedStatement { .. })));
+    }
+
+    #[test]
+    fn test_attachment_in_report_and_provenance() {
+        let mut trace = SerendipityTrace::new("researcher1", "ibm_quantum", "Journavx");
+        let event_id = trace.log_event(
+            SerendipityStage::Validation,
+            SerendipityAgent::Validator,
+            "run benchmark",
+            "23% improvement",
+            "en",
+            0.7,
+            0.9,
+        ).unwrap().event_id.clone();
+        let before = trace.compute_provenance_hash();
+
+        trace.attach_circuit(&event_id, CircuitArtifact::from_qasm3("walk", QUANTUM_WALK).unwrap()).unwrap();
+        assert_ne!(trace.compute_provenance_hash(), before);
+        assert!(trace.attach_circuit("missing", CircuitArtifact::from_qasm3("walk", QUANTUM_WALK).unwrap()).is_err());
+
+        let report = trace.backend_report();
+        assert_eq!(report.circuits.len(), 1);
+        assert_eq!(report.circuits[0].event_id, event_id);
+        assert_eq!(report.total_gates, 5);
+        assert_eq!(report.max_qubits, 2);
+
+        let mut out = Vec::new();
+        report.render(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("Backend: ibm_quantum\nCircuits: 1 (5 gates, up to 2 qubits"));
+        assert!(text.contains("  • walk ["));
+    }
+}
@@ -0,0 +1,281 @@
+// -*- coding: utf-8 -*-
+//! Citation and Impact Tracking
+//!
+//! A discovery's influence shows up later as citations of the papers that
+//! report it. A `CitationIndex` links discovery names to external
+//! identifiers (DOIs and arXiv IDs). It keeps the latest citation count of
+//! each identifier, taken from `CitationImporter`s such as a Crossref or
+//! Semantic Scholar client run on a schedule. Counts older than the one on
+//! record are ignored, so importers may run overlapping or out of order.
+//! `apply_to_leaderboard` totals each contributor's citations for
+//! `LanguageAwareRankingCriteria::Impact`, which scales average serendipity
+//! by the logarithm of citations.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use crate::discovery_registry::normalize_discovery_name;
+use crate::ContributorStats::{LanguageAwareContributorStats, LanguageAwareLeaderboard};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// External identifier of a paper reporting a discovery
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ExternalId {
+    /// Digital Object Identifier, lowercased, e.g. `10.1103/physrevlett.1`
+    Doi(String),
+    /// arXiv identifier without version, e.g. `2401.01234`
+    Arxiv(String),
+}
+
+fn is_doi(doi: &str) -> bool {
+    let Some((registrant, suffix)) = doi.split_once('/') else { return false };
+    registrant
+        .strip_prefix("10.")
+        .is_some_and(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        && !suffix.is_empty()
+        && !suffix.chars().any(char::is_whitespace)
+}
+
+fn is_arxiv(id: &str) -> bool {
+    let digits = |s: &str, lengths: &[usize]| lengths.contains(&s.len()) && s.chars().all(|c| c.is_ascii_digit());
+    if let Some((archive, number)) = id.split_once('/') {
+        // Old style: `hep-th/9901001`
+        return !archive.is_empty()
+            && archive.chars().all(|c| c.is_ascii_lowercase() || c == '-' || c == '.')
+            && digits(number, &[7]);
+    }
+    // New style: `2401.01234`
+    id.split_once('.').is_some_and(|(month, number)| digits(month, &[4]) && digits(number, &[4, 5]))
+}
+
+impl ExternalId {
+    /// Parse a DOI (`10.…`, `doi:10.…`, or a `doi.org` URL) or an arXiv ID
+    /// (`2401.01234v2`, `arXiv:hep-th/9901001`, or an `arxiv.org/abs` URL).
+    /// DOIs are case-insensitive and arXiv versions are dropped, so every
+    /// spelling of a paper parses to the same ID.
+    pub fn parse(text: &str) -> SerenQaResult<Self> {
+        let text = text.trim().to_ascii_lowercase();
+        let invalid = || SerenQaError::InvalidIdentifier(text.clone());
+
+        let doi = ["https://doi.org/", "http://doi.org/", "https://dx.doi.org/", "doi:"]
+            .iter()
+            .find_map(|prefix| text.strip_prefix(prefix))
+            .or_else(|| text.starts_with("10.").then_some(text.as_str()));
+        if let Some(doi) = doi {
+            return if is_doi(doi) { Ok(ExternalId::Doi(doi.to_string())) } else { Err(invalid()) };
+        }
+
+        let id = ["https://arxiv.org/abs/", "http://arxiv.org/abs/", "arxiv:"]
+            .iter()
+            .find_map(|prefix| text.strip_prefix(prefix))
+            .unwrap_or(&text);
+        let unversioned = match id.rsplit_once('v') {
+            Some((base, version)) if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) => base,
+            _ => id,
+        };
+        if is_arxiv(unversioned) {
+            Ok(ExternalId::Arxiv(unversioned.to_string()))
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl fmt::Display for ExternalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternalId::Doi(doi) => write!(f, "doi:{}", doi),
+            ExternalId::Arxiv(id) => write!(f, "arXiv:{}", id),
+        }
+    }
+}
+
+impl TryFrom<String> for ExternalId {
+    type Error = SerenQaError;
+
+    fn try_from(text: String) -> SerenQaResult<Self> {
+        Self::parse(&text)
+    }
+}
+
+impl From<ExternalId> for String {
+    fn from(id: ExternalId) -> Self {
+        id.to_string()
+    }
+}
+
+/// Citation count of a paper as of a time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CitationUpdate {
+    pub id: ExternalId,
+    pub count: u64,
+    /// When the source reported the count
+    pub at: DateTime<Utc>,
+}
+
+/// Source of citation counts, e.g. a bibliographic database client
+pub trait CitationImporter: Send + Sync {
+    /// Name of the source, e.g. `crossref`
+    fn name(&self) -> &str;
+
+    /// Current counts of `ids`; IDs the source does not know are left out
+    fn fetch(&self, ids: &[ExternalId]) -> SerenQaResult<Vec<CitationUpdate>>;
+}
+
+/// Latest citation count on record for a paper
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CitationRecord {
+    pub count: u64,
+    pub updated_at: DateTime<Utc>,
+    /// Importer that reported the count
+    pub source: String,
+}
+
+/// Discovery-to-paper links and the latest citation counts of those papers
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CitationIndex {
+    /// Normalized discovery name -> identifiers
+    #[serde(default)]
+    links: BTreeMap<String, BTreeSet<ExternalId>>,
+    #[serde(default)]
+    counts: BTreeMap<ExternalId, CitationRecord>,
+}
+
+impl CitationIndex {
+    /// Empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Associate a paper with a discovery (any spelling of its name)
+    pub fn link(&mut self, discovery_name: &str, id: ExternalId) {
+        self.links.entry(normalize_discovery_name(discovery_name)).or_default().insert(id);
+    }
+
+    /// Papers associated with a discovery
+    pub fn identifiers(&self, discovery_name: &str) -> impl Iterator<Item = &ExternalId> {
+        self.links.get(&normalize_discovery_name(discovery_name)).into_iter().flatten()
+    }
+
+    /// Latest count on record for a paper
+    pub fn record(&self, id: &ExternalId) -> Option<&CitationRecord> {
+        self.counts.get(id)
+    }
+
+    /// Record `update` from `source` unless a newer count is already on
+    /// record; returns whether it was recorded
+    pub fn apply(&mut self, source: &str, update: CitationUpdate) -> bool {
+        if self.counts.get(&update.id).is_some_and(|record| record.updated_at > update.at) {
+            return false;
+        }
+        let record = CitationRecord { count: update.count, updated_at: update.at, source: source.to_string() };
+        self.counts.insert(update.id, record);
+        true
+    }
+
+    /// Fetch counts of every linked paper from `importer`; returns how many
+    /// were recorded
+    pub fn import(&mut self, importer: &dyn CitationImporter) -> SerenQaResult<usize> {
+        let ids: Vec<ExternalId> = self.links.values().flatten().cloned().collect::<BTreeSet<_>>().into_iter().collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let updates = importer.fetch(&ids)?;
+        Ok(updates.into_iter().filter(|update| self.apply(importer.name(), update.clone())).count())
+    }
+
+    /// Citations of a discovery: the sum over its papers, so a preprint and
+    /// its journal version both count
+    pub fn citations(&self, discovery_name: &str) -> u64 {
+        self.identifiers(discovery_name)
+            .filter_map(|id| self.counts.get(id))
+            .map(|record| record.count)
+            .sum()
+    }
+
+    /// Set every contributor's citation total over their discoveries
+    pub fn apply_to_leaderboard(&self, leaderboard: &mut LanguageAwareLeaderboard) {
+        leaderboard.update_contributors(|stats| {
+            stats.citations = stats.discoveries.iter().map(|discovery| self.citations(discovery)).sum();
+        });
+    }
+}
+
+impl LanguageAwareContributorStats {
+    /// Average serendipity scaled by `1 + ln(1 + citations)`: uncited work
+    /// keeps its serendipity, and each tenfold rise in citations adds about
+    /// 2.3 times it again
+    pub fn impact_score(&self) -> f64 {
+        self.avg_serendipity * (1.0 + (self.citations as f64).ln_1p())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContributorStats::LanguageAwareRankingCriteria;
+    use chrono::TimeZone;
+
+    struct FixedCounts(Vec<(&'static str, u64)>, DateTime<Utc>);
+
+    impl CitationImporter for FixedCounts {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn fetch(&self, ids: &[ExternalId]) -> SerenQaResult<Vec<CitationUpdate>> {
+            Ok(self.0
+                .iter()
+                .map(|(id, count)| CitationUpdate { id: ExternalId::parse(id).unwrap(), count: *count, at: self.1 })
+                .filter(|update| ids.contains(&update.id))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_identifiers_normalize() {
+        let doi = ExternalId::Doi("10.1103/physrevlett.130.1".to_string());
+        assert_eq!(ExternalId::parse("https://doi.org/10.1103/PhysRevLett.130.1").unwrap(), doi);
+        assert_eq!(ExternalId::parse("doi:10.1103/physrevlett.130.1").unwrap(), doi);
+        let arxiv = ExternalId::Arxiv("2401.01234".to_string());
+        assert_eq!(ExternalId::parse("arXiv:2401.01234v3").unwrap(), arxiv);
+        assert_eq!(ExternalId::parse("https://arxiv.org/abs/2401.01234").unwrap(), arxiv);
+        assert!(ExternalId::parse("hep-th/9901001").is_ok());
+        assert!(ExternalId::parse("10.1103").is_err());
+        assert!(ExternalId::parse("journavx").is_err());
+        assert_eq!(serde_json::to_string(&arxiv).unwrap(), r#""arXiv:2401.01234""#);
+    }
+
+    #[test]
+    fn test_import_and_rank_by_impact() {
+        let mut index = CitationIndex::new();
+        index.link("Journavx", ExternalId::parse("arXiv:2401.01234").unwrap());
+        index.link("journavx", ExternalId::parse("10.1103/prl.1").unwrap());
+        let march = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let april = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+        let importer = FixedCounts(vec![("2401.01234", 40), ("10.1103/prl.1", 60), ("9999.00001", 5)], april);
+        assert_eq!(index.import(&importer).unwrap(), 2);
+        assert_eq!(index.citations("JOURNAVX"), 100);
+        // A stale count from an earlier run does not overwrite a newer one
+        assert!(!index.apply("late", CitationUpdate { id: ExternalId::parse("10.1103/prl.1").unwrap(), count: 1, at: march }));
+
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        let mut cited = LanguageAwareContributorStats::new("sari");
+        cited.add_trace(10, 0.6, 0.6, vec!["en".to_string()], 0.8, 0.8).unwrap();
+        cited.add_discovery("Journavx");
+        let mut uncited = LanguageAwareContributorStats::new("budi");
+        uncited.add_trace(10, 0.9, 0.9, vec!["en".to_string()], 0.8, 0.8).unwrap();
+        uncited.add_discovery("Star Compass");
+        leaderboard.add_contributor(cited);
+        leaderboard.add_contributor(uncited);
+        index.apply_to_leaderboard(&mut leaderboard);
+
+        assert_eq!(leaderboard.get_top_n(1, LanguageAwareRankingCriteria::Serendipity)[0].contributor_id, "budi");
+        let top = leaderboard.get_top_n(2, LanguageAwareRankingCriteria::Impact);
+        assert_eq!(top[0].contributor_id, "sari");
+        assert_eq!(top[0].citations, 100);
+        assert_eq!(top[1].impact_score(), 0.9);
+    }
+}
@@ -0,0 +1,180 @@
+// -*- coding: utf-8 -*-
+//! Injectable Clocks for Traces
+//!
+//! Traces read the time for their ID, creation time, and event timestamps
+//! from a `Clock` instead of calling `Utc::now()` directly. The system clock
+//! is the default; `FixedClock` and `MockClock` make tests deterministic, and
+//! `log_event_at` records events at explicit times when importing past
+//! research logs.
+
+use chrono::{DateTime, Duration, Utc};
+use std::sync::{Arc, Mutex};
+use crate::serendipity_trace::{SerendipityAgent, SerendipityEvent, SerendipityEventBuilder, SerendipityStage, SerendipityTrace};
+use crate::error::SerenQaResult;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock stopped at one instant
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Clock that tests move forward by hand; clones share the same time
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    /// Clock starting at `start`
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self { now: Arc::new(Mutex::new(start)) }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) += by;
+    }
+
+    /// Jump to a specific time
+    pub fn set(&self, to: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = to;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Clock attached to a trace (not serialized; the system clock by default)
+#[derive(Clone)]
+pub struct TraceClock(Arc<dyn Clock>);
+
+impl TraceClock {
+    /// Wrap a clock
+    pub fn new<C: Clock + 'static>(clock: C) -> Self {
+        Self(Arc::new(clock))
+    }
+
+    /// Current time
+    pub fn now(&self) -> DateTime<Utc> {
+        self.0.now()
+    }
+}
+
+impl Default for TraceClock {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl std::fmt::Debug for TraceClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TraceClock").field(&self.now()).finish()
+    }
+}
+
+impl SerendipityTrace {
+    /// Create a trace whose ID, creation time, and event timestamps come from `clock`
+    pub fn with_clock<C: Clock + 'static>(contributor_id: &str, backend: &str, discovery_name: &str, clock: C) -> Self {
+        let clock = TraceClock::new(clock);
+        let now = clock.now();
+        let mut trace = Self::new(contributor_id, backend, discovery_name);
+        trace.trace_id = trace.ids.trace_id(contributor_id, now);
+        trace.created_at = now;
+        trace.clock = clock;
+        trace
+    }
+
+    /// Read event timestamps from `clock` from now on
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.clock = TraceClock::new(clock);
+    }
+
+    /// Log an event that happened at `timestamp`, e.g. when importing past
+    /// research logs
+    #[allow(clippy::too_many_arguments)]
+    pub fn log_event_at(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        stage: SerendipityStage,
+        agent: SerendipityAgent,
+        input: &str,
+        output: &str,
+        language: &str,
+        serendipity_score: f64,
+        confidence: f64,
+    ) -> SerenQaResult<&SerendipityEvent> {
+        let event = SerendipityEventBuilder::new(stage, agent, input, output, language)
+            .serendipity(serendipity_score)
+            .confidence(confidence)
+            .timestamp(timestamp);
+        self.log(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::ids::UuidV7Ids;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_mock_clock_makes_traces_deterministic() {
+        let build = || {
+            let clock = MockClock::new(start());
+            let mut trace = SerendipityTrace::with_clock("sari", "backend", "Journavx", clock.clone()).with_ids(UuidV7Ids::seeded(1));
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+            clock.advance(Duration::minutes(5));
+            trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "in", "out", "id", 0.9, 0.9).unwrap();
+            trace
+        };
+        let (first, second) = (build(), build());
+        assert_eq!(first.trace_id, second.trace_id);
+        assert_eq!(first.created_at, start());
+        assert_eq!(first.events[1].timestamp, start() + Duration::minutes(5));
+        assert_eq!(first.events[1].event_id, second.events[1].event_id);
+        assert_eq!(first.compute_provenance_hash(), second.compute_provenance_hash());
+    }
+
+    #[test]
+    fn test_log_event_at_backfills_history() {
+        let mut trace = SerendipityTrace::with_clock("sari", "backend", "Journavx", FixedClock(start()));
+        let past = start() - Duration::days(365);
+        trace.log_event_at(past, SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+        trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "in", "out", "en", 0.9, 0.9).unwrap();
+        assert_eq!(trace.events[0].timestamp, past);
+        assert_eq!(trace.events[1].timestamp, start());
+
+        let built = SerendipityEventBuilder::new(SerendipityStage::Integration, SerendipityAgent::Synthesizer, "in", "out", "en")
+            .timestamp(past)
+            .build()
+            .unwrap();
+        assert_eq!(built.timestamp, past);
+    }
+}
@@ -0,0 +1,549 @@
+// -*- coding: utf-8 -*-
+//! Corpus Bulk Operations
+//!
+//! Offline batch analysis of benchmark submissions: fold, summarize, verify,
+//! and quota-check every trace file in a directory, in parallel, with progress
+//! reporting and a summary report. Backs the `seren corpus` CLI.
+//!
+//! `CorpusStats` is built one trace at a time, so `summarize_store` can stream
+//! a corpus of any size out of a `TraceStore`. Besides means and counts, it
+//! keeps stage, agent, and language distributions over events, a histogram of
+//! event serendipity scores, a monthly timeline with each discovery's first
+//! appearance, and a reservoir sample of representative trace IDs.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use crate::serendipity_trace::{SerendipityTrace, FoldedSerendipityTrace};
+use crate::submission::SubmissionPipeline;
+use crate::quota::{QuotaManager, QuotaPolicy};
+use crate::store::TraceStore;
+use crate::error::SerenQaResult;
+
+/// File extension of trace files in a corpus
+pub const TRACE_EXTENSION: &str = "json";
+
+/// Suffix of folded trace files written by `fold_corpus`
+pub const FOLD_SUFFIX: &str = ".fold.json";
+
+/// Bins of the serendipity histogram, each 0.1 wide
+pub const HISTOGRAM_BINS: usize = 10;
+
+/// Example traces `corpus_stats` samples
+pub const DEFAULT_SAMPLE_SIZE: usize = 5;
+
+/// Progress notification for one processed file
+#[derive(Debug, Clone)]
+pub struct CorpusProgress<'a> {
+    /// Number of files finished so far
+    pub completed: usize,
+    /// Total number of files
+    pub total: usize,
+    /// File just finished
+    pub path: &'a Path,
+    /// Whether processing succeeded
+    pub ok: bool,
+}
+
+/// Options shared by corpus operations
+pub struct CorpusOptions<'a> {
+    /// Worker threads (at least one)
+    pub threads: usize,
+    /// Called after every file
+    pub progress: Option<&'a (dyn Fn(&CorpusProgress<'_>) + Sync)>,
+}
+
+impl Default for CorpusOptions<'_> {
+    fn default() -> Self {
+        Self {
+            threads: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            progress: None,
+        }
+    }
+}
+
+/// File that failed to process
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CorpusFailure {
+    /// File path
+    pub path: String,
+    /// Error description
+    pub error: String,
+}
+
+/// Summary of a corpus operation
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CorpusReport {
+    /// Files processed
+    pub processed: usize,
+    /// Files processed successfully
+    pub succeeded: usize,
+    /// Files that failed, sorted by path
+    pub failures: Vec<CorpusFailure>,
+}
+
+impl CorpusReport {
+    /// Process exit code: 0 if every file succeeded, 1 otherwise
+    pub fn exit_code(&self) -> i32 {
+        if self.failures.is_empty() { 0 } else { 1 }
+    }
+
+    /// Write a summary of the run to `out`
+    pub fn render<W: Write>(&self, operation: &str, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "\n=== seren corpus {} ===", operation)?;
+        writeln!(out, "Processed: {}", self.processed)?;
+        writeln!(out, "Succeeded: {}", self.succeeded)?;
+        writeln!(out, "Failed:    {}", self.failures.len())?;
+        for failure in &self.failures {
+            writeln!(out, "  {}: {}", failure.path, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Aggregate statistics over a corpus
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CorpusStats {
+    /// Traces loaded
+    pub traces: usize,
+    /// Total events across traces
+    pub total_events: usize,
+    /// Mean trace depth
+    pub mean_depth: f64,
+    /// Mean overall serendipity
+    pub mean_serendipity: f64,
+    /// Mean uniqueness score
+    pub mean_uniqueness: f64,
+    /// Traces per contributor
+    pub contributors: BTreeMap<String, usize>,
+    /// Traces per discovery
+    pub discoveries: BTreeMap<String, usize>,
+    /// Traces involving each language
+    pub languages: BTreeMap<String, usize>,
+    /// Events per stage
+    #[serde(default)]
+    pub stages: BTreeMap<String, usize>,
+    /// Events per agent
+    #[serde(default)]
+    pub agents: BTreeMap<String, usize>,
+    /// Events per language
+    #[serde(default)]
+    pub event_languages: BTreeMap<String, usize>,
+    /// Event serendipity scores in `HISTOGRAM_BINS` bins over [0, 1]
+    #[serde(default)]
+    pub serendipity_histogram: Vec<usize>,
+    /// Traces created per month (`YYYY-MM`)
+    #[serde(default)]
+    pub timeline: BTreeMap<String, usize>,
+    /// Creation time of each discovery's earliest trace
+    #[serde(default)]
+    pub discovery_first_seen: BTreeMap<String, DateTime<Utc>>,
+    /// IDs of a uniform random sample of traces
+    #[serde(default)]
+    pub samples: Vec<String>,
+}
+
+/// Histogram bin of a score in [0, 1]; 1.0 falls in the last bin
+fn histogram_bin(score: f64) -> usize {
+    ((score.clamp(0.0, 1.0) * HISTOGRAM_BINS as f64) as usize).min(HISTOGRAM_BINS - 1)
+}
+
+impl CorpusStats {
+    /// Add one trace
+    pub fn add(&mut self, trace: &SerendipityTrace) {
+        let n = self.traces as f64;
+        self.traces += 1;
+        self.total_events += trace.depth();
+        self.mean_depth = (self.mean_depth * n + trace.depth() as f64) / self.traces as f64;
+        self.mean_serendipity = (self.mean_serendipity * n + trace.overall_serendipity) / self.traces as f64;
+        self.mean_uniqueness = (self.mean_uniqueness * n + trace.uniqueness_score()) / self.traces as f64;
+        *self.contributors.entry(trace.contributor_id.clone()).or_insert(0) += 1;
+        *self.discoveries.entry(trace.discovery_name.clone()).or_insert(0) += 1;
+        for language in &trace.languages {
+            *self.languages.entry(language.clone()).or_insert(0) += 1;
+        }
+
+        self.serendipity_histogram.resize(HISTOGRAM_BINS, 0);
+        for event in &trace.events {
+            *self.stages.entry(event.stage.name()).or_insert(0) += 1;
+            *self.agents.entry(event.agent.name()).or_insert(0) += 1;
+            *self.event_languages.entry(event.language.clone()).or_insert(0) += 1;
+            self.serendipity_histogram[histogram_bin(event.serendipity_score)] += 1;
+        }
+        *self.timeline.entry(trace.created_at.format("%Y-%m").to_string()).or_insert(0) += 1;
+        self.discovery_first_seen
+            .entry(trace.discovery_name.clone())
+            .and_modify(|first| *first = (*first).min(trace.created_at))
+            .or_insert(trace.created_at);
+    }
+
+    /// Write the statistics to `out`
+    pub fn render<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "Traces:            {}", self.traces)?;
+        writeln!(out, "Total events:      {}", self.total_events)?;
+        writeln!(out, "Mean depth:        {:.2}", self.mean_depth)?;
+        writeln!(out, "Mean serendipity:  {:.3}", self.mean_serendipity)?;
+        writeln!(out, "Mean uniqueness:   {:.3}", self.mean_uniqueness)?;
+        writeln!(out, "Contributors:      {}", self.contributors.len())?;
+        writeln!(out, "Discoveries:       {}", self.discoveries.len())?;
+        for (language, count) in &self.languages {
+            writeln!(out, "  {}: {} traces", language, count)?;
+        }
+        writeln!(out, "Events per stage:")?;
+        for (stage, count) in &self.stages {
+            writeln!(out, "  {}: {}", stage, count)?;
+        }
+        writeln!(out, "Events per agent:")?;
+        for (agent, count) in &self.agents {
+            writeln!(out, "  {}: {}", agent, count)?;
+        }
+        writeln!(out, "Serendipity histogram:")?;
+        let widest = self.serendipity_histogram.iter().copied().max().unwrap_or(0).max(1);
+        for (bin, count) in self.serendipity_histogram.iter().enumerate() {
+            let bar = "#".repeat(count * 40 / widest);
+            writeln!(out, "  {:.1}-{:.1} {:>7} {}", bin as f64 / HISTOGRAM_BINS as f64, (bin + 1) as f64 / HISTOGRAM_BINS as f64, count, bar)?;
+        }
+        writeln!(out, "Traces per month:")?;
+        for (month, count) in &self.timeline {
+            writeln!(out, "  {}: {}", month, count)?;
+        }
+        if !self.samples.is_empty() {
+            writeln!(out, "Sample traces:     {}", self.samples.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Uniform random sample of at most `capacity` items from a stream of
+/// unknown length (reservoir sampling), reproducible for a given seed
+#[derive(Debug, Clone)]
+pub struct ReservoirSampler<T> {
+    capacity: usize,
+    seen: u64,
+    state: u64,
+    items: Vec<T>,
+}
+
+impl<T> ReservoirSampler<T> {
+    /// Sampler keeping `capacity` items, drawing from a generator seeded with `seed`
+    pub fn new(capacity: usize, seed: u64) -> Self {
+        Self { capacity, seen: 0, state: seed, items: Vec::with_capacity(capacity) }
+    }
+
+    /// SplitMix64 step
+    fn next_random(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut x = self.state;
+        x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        x ^ (x >> 31)
+    }
+
+    /// Offer the next item of the stream
+    pub fn offer(&mut self, item: T) {
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item);
+            return;
+        }
+        // Keep the n-th item with probability capacity / n
+        let slot = (self.next_random() % self.seen) as usize;
+        if slot < self.capacity {
+            self.items[slot] = item;
+        }
+    }
+
+    /// Items offered so far
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    /// Sampled items
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+/// Statistics over every trace in `store`, loading one trace at a time, with
+/// `sample_size` trace IDs sampled using `seed`
+pub fn summarize_store(store: &dyn TraceStore, sample_size: usize, seed: u64) -> SerenQaResult<CorpusStats> {
+    let mut stats = CorpusStats::default();
+    let mut sampler = ReservoirSampler::new(sample_size, seed);
+    for trace_id in store.list()? {
+        // A trace deleted since listing is skipped
+        if let Some(trace) = store.load(&trace_id)? {
+            stats.add(&trace);
+            sampler.offer(trace.trace_id);
+        }
+    }
+    stats.samples = sampler.into_items();
+    Ok(stats)
+}
+
+/// List trace files in a directory (sorted, excluding folded outputs)
+pub fn discover_trace_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_trace = path.is_file()
+            && path.extension().is_some_and(|ext| ext == TRACE_EXTENSION)
+            && !path.to_string_lossy().ends_with(FOLD_SUFFIX);
+        if is_trace {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Load a trace from a JSON file written by this or an older schema version
+pub fn load_trace(path: &Path) -> SerenQaResult<SerendipityTrace> {
+    let json = std::fs::read_to_string(path)?;
+    SerendipityTrace::from_json_any_version(&json)
+}
+
+/// Run `job` over every file on a pool of worker threads
+fn process_files<T, F>(files: &[PathBuf], options: &CorpusOptions<'_>, job: F) -> (CorpusReport, Vec<T>)
+where
+    T: Send,
+    F: Fn(&Path) -> Result<T, String> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let completed = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, Result<T, String>)>> = Mutex::new(Vec::with_capacity(files.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..options.threads.clamp(1, files.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(path) = files.get(index) else { break };
+                let result = job(path);
+                let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                if let Some(progress) = options.progress {
+                    progress(&CorpusProgress { completed: done, total: files.len(), path, ok: result.is_ok() });
+                }
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut report = CorpusReport { processed: files.len(), ..Default::default() };
+    let mut outputs = Vec::new();
+    for (index, result) in results {
+        match result {
+            Ok(output) => {
+                report.succeeded += 1;
+                outputs.push(output);
+            }
+            Err(error) => report.failures.push(CorpusFailure {
+                path: files[index].display().to_string(),
+                error,
+            }),
+        }
+    }
+    (report, outputs)
+}
+
+/// Fold every trace in `input` and write `<name>.fold.json` files to `output`
+pub fn fold_corpus(input: &Path, output: &Path, options: &CorpusOptions<'_>) -> std::io::Result<CorpusReport> {
+    let files = discover_trace_files(input)?;
+    std::fs::create_dir_all(output)?;
+
+    let (report, _) = process_files(&files, options, |path| {
+        let write_fold = || -> SerenQaResult<()> {
+            let folded: FoldedSerendipityTrace = load_trace(path)?.fold_memory()?;
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let target = output.join(format!("{}{}", stem, FOLD_SUFFIX));
+            std::fs::write(&target, serde_json::to_string_pretty(&folded)?)?;
+            Ok(())
+        };
+        write_fold().map_err(|e| e.to_string())
+    });
+    Ok(report)
+}
+
+/// Compute aggregate statistics over every trace in `input`
+pub fn corpus_stats(input: &Path, options: &CorpusOptions<'_>) -> std::io::Result<(CorpusReport, CorpusStats)> {
+    let files = discover_trace_files(input)?;
+    let (report, traces) = process_files(&files, options, |path| load_trace(path).map_err(|e| e.to_string()));
+
+    let mut stats = CorpusStats::default();
+    let mut sampler = ReservoirSampler::new(DEFAULT_SAMPLE_SIZE, 0);
+    for trace in &traces {
+        stats.add(trace);
+        sampler.offer(trace.trace_id.clone());
+    }
+    stats.samples = sampler.into_items();
+    Ok((report, stats))
+}
+
+/// Verify integrity of every trace in `input` with the submission checks
+pub fn verify_corpus(
+    input: &Path,
+    pipeline: &SubmissionPipeline,
+    options: &CorpusOptions<'_>,
+) -> std::io::Result<CorpusReport> {
+    let files = discover_trace_files(input)?;
+    let (report, _) = process_files(&files, options, |path| {
+        let trace = load_trace(path).map_err(|e| e.to_string())?;
+        let rejection = pipeline.check(&trace);
+        if rejection.is_empty() {
+            Ok(())
+        } else {
+            let codes: Vec<String> = rejection.codes().iter().map(|c| format!("{:?}", c)).collect();
+            Err(codes.join(", "))
+        }
+    });
+    Ok(report)
+}
+
+/// Replay every trace in `input` through `policy` in creation order; traces
+/// the quota would have refused are failures
+pub fn check_corpus_quota(
+    input: &Path,
+    policy: &QuotaPolicy,
+    options: &CorpusOptions<'_>,
+) -> std::io::Result<CorpusReport> {
+    let files = discover_trace_files(input)?;
+    let (mut report, mut traces) = process_files(&files, options, |path| {
+        load_trace(path).map(|trace| (path.to_path_buf(), trace)).map_err(|e| e.to_string())
+    });
+
+    traces.sort_by_key(|(_, trace)| trace.created_at);
+    let mut quota = QuotaManager::new(policy.clone());
+    for (path, trace) in &traces {
+        match quota.check_at(trace, trace.created_at) {
+            Ok(()) => quota.record_at(&trace.contributor_id, trace.created_at),
+            Err(exceeded) => {
+                report.succeeded -= 1;
+                report.failures.push(CorpusFailure { path: path.display().to_string(), error: exceeded.to_string() });
+            }
+        }
+    }
+    report.failures.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn corpus_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("seren_corpus_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (i, language) in ["en", "id", "en"].iter().enumerate() {
+            let mut trace = SerendipityTrace::new(&format!("researcher{}", i % 2), "backend", "Discovery");
+            trace.log_event(
+                SerendipityStage::Exploration,
+                SerendipityAgent::Explorer,
+                "input",
+                "output",
+                language,
+                0.8,
+                0.9,
+            ).unwrap();
+            std::fs::write(dir.join(format!("trace{}.json", i)), serde_json::to_string(&trace).unwrap()).unwrap();
+        }
+        std::fs::write(dir.join("broken.json"), "{ not a trace").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_fold_corpus_with_progress() {
+        let dir = corpus_dir("fold");
+        let out = dir.join("folds");
+        let seen = AtomicUsize::new(0);
+        let progress = |_: &CorpusProgress<'_>| {
+            seen.fetch_add(1, Ordering::SeqCst);
+        };
+        let options = CorpusOptions { threads: 2, progress: Some(&progress) };
+
+        let report = fold_corpus(&dir, &out, &options).unwrap();
+        assert_eq!(report.processed, 4);
+        assert_eq!(report.succeeded, 3);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.exit_code(), 1);
+        assert_eq!(seen.load(Ordering::SeqCst), 4);
+        assert!(out.join("trace0.fold.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stats_and_verify() {
+        let dir = corpus_dir("stats");
+        std::fs::remove_file(dir.join("broken.json")).unwrap();
+
+        let (report, stats) = corpus_stats(&dir, &CorpusOptions::default()).unwrap();
+        assert_eq!(report.exit_code(), 0);
+        assert_eq!(stats.traces, 3);
+        assert_eq!(stats.contributors.len(), 2);
+        assert_eq!(stats.languages.get("en"), Some(&2));
+        assert_eq!(stats.stages.get("Exploration"), Some(&3));
+        assert_eq!(stats.serendipity_histogram[8], 3);
+        assert_eq!(stats.timeline.values().sum::<usize>(), 3);
+        assert_eq!(stats.samples.len(), 3);
+
+        let mut out = Vec::new();
+        stats.render(&mut out).unwrap();
+        report.render("stats", &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("Traces:            3\n"));
+        assert!(text.contains("  en: 2 traces\n"));
+        assert!(text.ends_with("=== seren corpus stats ===\nProcessed: 3\nSucceeded: 3\nFailed:    0\n"));
+        // A closed pipe surfaces as an error rather than a panic
+        assert_eq!(stats.render(&mut ClosedPipe).unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+
+        let report = verify_corpus(&dir, &SubmissionPipeline::new(), &CorpusOptions::default()).unwrap();
+        assert_eq!(report.succeeded, 3);
+
+        // researcher0 made two traces within a day
+        let report = check_corpus_quota(&dir, &QuotaPolicy::unlimited().with_traces_per_day(1), &CorpusOptions::default()).unwrap();
+        assert_eq!(report.succeeded, 2);
+        assert!(report.failures[0].error.contains("traces per day"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_summarize_store_with_reservoir_sample() {
+        let store = crate::store::InMemoryTraceStore::new();
+        for i in 0..200 {
+            let mut trace = SerendipityTrace::new("sari", "backend", &format!("discovery{}", i % 4));
+            trace.trace_id = format!("trace{:03}", i);
+            let score = if i % 2 == 0 { 1.0 } else { 0.05 };
+            trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "out", "id", score, 0.9).unwrap();
+            store.save(&trace).unwrap();
+        }
+
+        let stats = summarize_store(&store, 10, 7).unwrap();
+        assert_eq!(stats.traces, 200);
+        assert_eq!((stats.serendipity_histogram[0], stats.serendipity_histogram[HISTOGRAM_BINS - 1]), (100, 100));
+        assert_eq!(stats.agents.get("PatternRecognizer"), Some(&200));
+        assert_eq!(stats.discovery_first_seen.len(), 4);
+        assert_eq!(stats.samples.len(), 10);
+        // Reproducible per seed, and not just the first traces listed
+        assert_eq!(summarize_store(&store, 10, 7).unwrap().samples, stats.samples);
+        assert!(stats.samples.iter().any(|id| id.as_str() >= "trace010"));
+    }
+}
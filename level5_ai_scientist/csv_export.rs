@@ -0,0 +1,130 @@
+// -*- coding: utf-8 -*-
+//! CSV Export of Folded Traces and Leaderboards
+//!
+//! RFC 4180 rows for folded traces and ranked contributors, so leaderboard
+//! results can go straight into spreadsheets. List fields are joined with
+//! `"; "` inside a single cell.
+
+use crate::serendipity_trace::FoldedSerendipityTrace;
+use crate::ContributorStats::{LanguageAwareLeaderboard, LanguageAwareRankingCriteria, LeaderboardQuery};
+
+/// Header matching `FoldedSerendipityTrace::to_csv_row`
+pub const FOLDED_CSV_HEADER: &str = "trace_id,discovery_name,total_events,overall_serendipity,\
+compression_ratio,uniqueness,languages,key_discoveries,language_transitions,redacted_events";
+
+/// Header of `LanguageAwareLeaderboard::export_csv`
+pub const LEADERBOARD_CSV_HEADER: &str = "rank,contributor_id,score,overall_score,total_traces,\
+avg_serendipity,avg_uniqueness,cross_language_expertise,languages,discoveries,badges";
+
+/// Separator for list values within one cell
+const LIST_SEPARATOR: &str = "; ";
+
+/// Quote a field if it contains a comma, quote, or line break
+pub fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_list(values: &[String]) -> String {
+    csv_escape(&values.join(LIST_SEPARATOR))
+}
+
+impl FoldedSerendipityTrace {
+    /// One CSV row (no trailing newline) in `FOLDED_CSV_HEADER` column order
+    pub fn to_csv_row(&self) -> String {
+        [
+            csv_escape(&self.trace_id),
+            csv_escape(&self.discovery_name),
+            self.total_events.to_string(),
+            format!("{:.4}", self.overall_serendipity),
+            format!("{:.4}", self.compression_ratio),
+            format!("{:.4}", self.uniqueness.score),
+            csv_list(&self.languages),
+            csv_list(&self.key_discoveries),
+            csv_list(&self.language_transitions),
+            self.redacted_events.to_string(),
+        ]
+        .join(",")
+    }
+}
+
+/// CSV document (header plus one row per fold)
+pub fn folds_to_csv(folds: &[FoldedSerendipityTrace]) -> String {
+    let mut csv = format!("{}\n", FOLDED_CSV_HEADER);
+    for fold in folds {
+        csv.push_str(&fold.to_csv_row());
+        csv.push('\n');
+    }
+    csv
+}
+
+impl LanguageAwareLeaderboard {
+    /// All contributors ranked by `criteria` as a CSV document
+    pub fn export_csv(&self, criteria: LanguageAwareRankingCriteria) -> String {
+        let page = self.query(&LeaderboardQuery::new(criteria).page(0, self.len()));
+        let mut csv = format!("{}\n", LEADERBOARD_CSV_HEADER);
+        for entry in &page.entries {
+            let Some(stats) = self.get(&entry.contributor_id) else {
+                continue;
+            };
+            let row = [
+                entry.rank.to_string(),
+                csv_escape(&entry.contributor_id),
+                format!("{:.4}", entry.score),
+                format!("{:.4}", stats.overall_score()),
+                stats.total_traces.to_string(),
+                format!("{:.4}", stats.avg_serendipity),
+                format!("{:.4}", stats.avg_uniqueness),
+                format!("{:.4}", stats.cross_language_expertise),
+                csv_list(&stats.languages_used),
+                csv_list(&stats.discoveries),
+                csv_list(&stats.badge_names()),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, SerendipityAgent};
+    use crate::ContributorStats::LanguageAwareContributorStats;
+
+    #[test]
+    fn test_folded_csv_row() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx, \"v2\"");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "found it", "en", 0.9, 0.8).unwrap();
+        trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "in", "ok", "id", 0.5, 0.8).unwrap();
+        let folded = trace.fold_memory().unwrap();
+
+        let row = folded.to_csv_row();
+        assert!(row.contains(",\"Journavx, \"\"v2\"\"\",2,0.7000,"));
+        assert!(row.ends_with(",en; id,Exploration: found it,en -> id,0"));
+        let columns = FOLDED_CSV_HEADER.split(',').count();
+        assert_eq!(folds_to_csv(&[folded]).lines().next().unwrap().split(',').count(), columns);
+    }
+
+    #[test]
+    fn test_leaderboard_csv() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for (id, serendipity) in [("researcher1", 0.6), ("researcher2", 0.9)] {
+            let mut stats = LanguageAwareContributorStats::new(id);
+            stats.add_trace(10, 0.8, serendipity, vec!["en".to_string(), "id".to_string()], 0.9, 0.9).unwrap();
+            stats.add_discovery("Journavx");
+            leaderboard.add_contributor(stats);
+        }
+
+        let csv = leaderboard.export_csv(LanguageAwareRankingCriteria::Serendipity);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], LEADERBOARD_CSV_HEADER);
+        assert!(lines[1].starts_with("1,researcher2,0.9000,"));
+        assert!(lines[2].ends_with(",en; id,Journavx,"));
+    }
+}
@@ -0,0 +1,164 @@
+// -*- coding: utf-8 -*-
+//! Time-Decayed Contributor Scoring
+//!
+//! `overall_score` weighs a trace from two years ago the same as one from
+//! today. Contributor statistics keep a timestamped history of their traces,
+//! and a `DecayModel` with a configurable half-life weights each trace by its
+//! age. The decayed score sums the weighted trace scores, so it rewards both
+//! recent and sustained activity; lifetime statistics are left untouched.
+//! Leaderboards rank by it through `LeaderboardQuery::decayed`.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use crate::ContributorStats::{
+    LanguageAwareContributorStats, LanguageAwareLeaderboard, LanguageAwareRankingCriteria, LeaderboardQuery,
+};
+
+/// Half-life used by `DecayModel::default`
+pub const DEFAULT_HALF_LIFE_DAYS: f64 = 180.0;
+
+/// When a trace was made and how it scored
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceActivity {
+    /// Time the trace was made
+    pub at: DateTime<Utc>,
+    /// Uniqueness score of the trace
+    pub uniqueness: f64,
+    /// Serendipity score of the trace
+    pub serendipity: f64,
+}
+
+impl TraceActivity {
+    /// Score of the trace: mean of its uniqueness and serendipity
+    pub fn score(&self) -> f64 {
+        (self.uniqueness + self.serendipity) / 2.0
+    }
+}
+
+/// Exponential decay of trace weight with age
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct DecayModel {
+    /// Age, in days, at which a trace counts half
+    pub half_life_days: f64,
+}
+
+impl Default for DecayModel {
+    fn default() -> Self {
+        Self { half_life_days: DEFAULT_HALF_LIFE_DAYS }
+    }
+}
+
+impl DecayModel {
+    /// Decay with the given half-life
+    pub fn new(half_life: Duration) -> Self {
+        Self { half_life_days: half_life.num_seconds() as f64 / 86_400.0 }
+    }
+
+    /// Weight of a trace made at `at`, as of `now`: 1 for traces from `now`
+    /// (or the future), halving every half-life
+    pub fn weight(&self, at: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+        if self.half_life_days <= 0.0 {
+            return if at >= now { 1.0 } else { 0.0 };
+        }
+        let age_days = (now - at).num_seconds().max(0) as f64 / 86_400.0;
+        0.5f64.powf(age_days / self.half_life_days)
+    }
+}
+
+impl LanguageAwareContributorStats {
+    /// Traces as timestamped activity. Statistics saved before histories were
+    /// kept count every trace at `last_active` with the lifetime averages.
+    fn activity(&self) -> Vec<TraceActivity> {
+        if !self.trace_history.is_empty() {
+            return self.trace_history.clone();
+        }
+        let Some(at) = self.last_active else {
+            return Vec::new();
+        };
+        let legacy = TraceActivity { at, uniqueness: self.avg_uniqueness, serendipity: self.avg_serendipity };
+        vec![legacy; self.total_traces]
+    }
+
+    /// Decay-weighted number of traces as of `now`
+    pub fn recent_traces(&self, model: &DecayModel, now: DateTime<Utc>) -> f64 {
+        self.activity().iter().map(|trace| model.weight(trace.at, now)).sum()
+    }
+
+    /// Sum of trace scores weighted by age as of `now`
+    pub fn decayed_score(&self, model: &DecayModel, now: DateTime<Utc>) -> f64 {
+        self.activity()
+            .iter()
+            .map(|trace| model.weight(trace.at, now) * trace.score())
+            .sum()
+    }
+}
+
+impl LanguageAwareLeaderboard {
+    /// Get top N contributors by time-decayed score as of `now`
+    pub fn get_top_n_decayed(&self, n: usize, model: DecayModel, now: DateTime<Utc>) -> Vec<LanguageAwareContributorStats> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let query = LeaderboardQuery::new(LanguageAwareRankingCriteria::Overall)
+            .decayed(model, now)
+            .page(0, n);
+        self.query(&query)
+            .entries
+            .iter()
+            .filter_map(|entry| self.get(&entry.contributor_id).cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_at(contributor: &str, ages_days: &[i64], now: DateTime<Utc>) -> LanguageAwareContributorStats {
+        let mut stats = LanguageAwareContributorStats::new(contributor);
+        for age in ages_days {
+            let at = now - Duration::days(*age);
+            stats.add_trace_at(at, 10, 0.8, 0.8, vec!["en".to_string()], 0.9, 0.9).unwrap();
+        }
+        stats
+    }
+
+    #[test]
+    fn test_weights_halve_every_half_life() {
+        let now = Utc::now();
+        let model = DecayModel::new(Duration::days(30));
+        assert_eq!(model.weight(now, now), 1.0);
+        assert!((model.weight(now - Duration::days(30), now) - 0.5).abs() < 1e-9);
+        assert!((model.weight(now - Duration::days(90), now) - 0.125).abs() < 1e-9);
+        assert_eq!(model.weight(now + Duration::days(1), now), 1.0);
+
+        let stats = stats_at("researcher1", &[0, 30, 720], now);
+        assert_eq!(stats.first_active, Some(now - Duration::days(720)));
+        assert_eq!(stats.last_active, Some(now));
+        assert!((stats.recent_traces(&model, now) - 1.5).abs() < 1e-6);
+        assert!((stats.decayed_score(&model, now) - 1.2).abs() < 1e-6);
+        assert_eq!(stats.total_traces, 3);
+    }
+
+    #[test]
+    fn test_leaderboard_ranks_recent_activity() {
+        let now = Utc::now();
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        // Veteran: many traces, all two years old; newcomer: two this month
+        leaderboard.add_contributor(stats_at("veteran", &[720, 725, 730, 735, 740], now));
+        leaderboard.add_contributor(stats_at("newcomer", &[3, 10], now));
+
+        let lifetime = leaderboard.get_top_n(2, LanguageAwareRankingCriteria::Overall);
+        assert_eq!(lifetime[0].overall_score(), lifetime[1].overall_score());
+
+        let recent = leaderboard.get_top_n_decayed(2, DecayModel::default(), now);
+        assert_eq!(recent[0].contributor_id, "newcomer");
+        let page = leaderboard.query(&LeaderboardQuery::new(LanguageAwareRankingCriteria::Overall).decayed(DecayModel::default(), now));
+        assert!(page.entries[0].score > page.entries[1].score);
+
+        // Legacy stats without a history fall back to last_active
+        let mut legacy = stats_at("legacy", &[0, 0], now);
+        legacy.trace_history.clear();
+        assert!((legacy.recent_traces(&DecayModel::default(), now) - 2.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,226 @@
+// -*- coding: utf-8 -*-
+//! Event Deduplication and Idempotent Logging
+//!
+//! Agents that retry a step log the same event twice, which skews serendipity
+//! averages. Events get a content fingerprint (stage, agent, language, and
+//! input/output text, ignoring scores and timestamps); `log_event_idempotent`
+//! merges a retried event into the one already logged, keeping the higher
+//! confidence. Folds report merged retries and any duplicates still present.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use std::collections::HashMap;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEvent, SerendipityEventBuilder, SerendipityTransition};
+use crate::error::SerenQaResult;
+
+impl SerendipityEvent {
+    /// Content fingerprint identifying retries of the same event
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{:?}|{:?}|", self.stage, self.agent).as_bytes());
+        hasher.update(self.language.as_bytes());
+        hasher.update(b"|");
+        hasher.update(self.content_hash().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Whether an idempotent log appended a new event or merged a retry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotentLog {
+    /// New event appended with this ID
+    Appended(String),
+    /// Retry merged into the existing event with this ID
+    Merged(String),
+}
+
+impl IdempotentLog {
+    /// ID of the event that now holds the content
+    pub fn event_id(&self) -> &str {
+        match self {
+            IdempotentLog::Appended(id) | IdempotentLog::Merged(id) => id,
+        }
+    }
+}
+
+/// Retries merged into one event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MergedRetries {
+    /// Event the retries were merged into
+    pub event_id: String,
+    /// Number of retries merged
+    pub retries: usize,
+}
+
+/// Deduplication summary included in folds
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DedupReport {
+    /// Retries merged by idempotent logging, in trace order
+    pub merged: Vec<MergedRetries>,
+    /// Events sharing a fingerprint that were logged separately
+    /// (each group in trace order)
+    pub duplicate_groups: Vec<Vec<String>>,
+}
+
+impl DedupReport {
+    /// Total retries merged
+    pub fn merged_retries(&self) -> usize {
+        self.merged.iter().map(|m| m.retries).sum()
+    }
+
+    /// Check if no duplicates were merged or found
+    pub fn is_empty(&self) -> bool {
+        self.merged.is_empty() && self.duplicate_groups.is_empty()
+    }
+}
+
+impl SerendipityTrace {
+    /// Log an event unless an event with the same fingerprint is already in
+    /// the trace; a retry is merged into it instead, keeping the higher
+    /// confidence (and refreshing the adjacent transition scores). Retries go
+    /// through middleware and the event checks like any new event, and are
+    /// fingerprinted as they would be stored
+    pub fn log_event_idempotent(&mut self, mut builder: SerendipityEventBuilder) -> SerenQaResult<IdempotentLog> {
+        builder.timestamp.get_or_insert_with(|| self.clock.now());
+        let (event, notes) = self.check_event(builder.build()?)?;
+        let fingerprint = event.fingerprint();
+        let Some(index) = self.events.iter().position(|e| e.fingerprint() == fingerprint) else {
+            return Ok(IdempotentLog::Appended(self.append_checked(event, notes)?.event_id.clone()));
+        };
+
+        let mut merged = self.events[index].clone();
+        merged.confidence = merged.confidence.max(event.confidence);
+        merged.validate()?;
+        let event_id = merged.event_id.clone();
+        self.events[index] = merged;
+        *self.merged_retries.entry(event_id.clone()).or_insert(0) += 1;
+        self.invalidate_provenance();
+        self.invalidate_fold_cache();
+
+        for (t, transition) in self.transitions.iter_mut().enumerate() {
+            if transition.from_event == event_id || transition.to_event == event_id {
+                *transition = SerendipityTransition::between(&self.events[t], &self.events[t + 1]);
+            }
+        }
+        Ok(IdempotentLog::Merged(event_id))
+    }
+
+    /// Merged retries and remaining duplicate groups
+    pub fn dedup_report(&self) -> DedupReport {
+        let merged = self.events
+            .iter()
+            .filter_map(|e| {
+                self.merged_retries.get(&e.event_id).map(|&retries| MergedRetries {
+                    event_id: e.event_id.clone(),
+                    retries,
+                })
+            })
+            .collect();
+
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut group_of: HashMap<String, usize> = HashMap::new();
+        for event in &self.events {
+            let slot = *group_of.entry(event.fingerprint()).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[slot].push(event.event_id.clone());
+        }
+        groups.retain(|group| group.len() > 1);
+
+        DedupReport { merged, duplicate_groups: groups }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+    use crate::middleware::{TraceMiddleware, MiddlewareDecision};
+    use crate::redaction_rules::RedactionRules;
+    use crate::error::SerenQaError;
+
+    fn attempt(output: &str, confidence: f64) -> SerendipityEventBuilder {
+        SerendipityEventBuilder::new(SerendipityStage::Validation, SerendipityAgent::Validator, "check", output, "id")
+            .serendipity(0.8)
+            .confidence(confidence)
+    }
+
+    #[test]
+    fn test_retry_is_merged_with_max_confidence() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.log(SerendipityEventBuilder::new(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en")
+            .serendipity(0.4)
+            .confidence(0.6)).unwrap();
+        let first = trace.log_event_idempotent(attempt("confirmed", 0.7)).unwrap();
+        let retry = trace.log_event_idempotent(attempt("confirmed", 0.9)).unwrap();
+        let lower = trace.log_event_idempotent(attempt("confirmed", 0.5)).unwrap();
+
+        assert!(matches!(first, IdempotentLog::Appended(_)));
+        assert_eq!(retry, IdempotentLog::Merged(first.event_id().to_string()));
+        assert_eq!(lower.event_id(), first.event_id());
+        assert_eq!(trace.events.len(), 2);
+        assert_eq!(trace.events[1].confidence, 0.9);
+        assert!((trace.transitions[0].transition_score - 0.75).abs() < 1e-9);
+
+        let report = trace.fold_memory().unwrap().dedup;
+        assert_eq!(report.merged_retries(), 2);
+        assert!(report.duplicate_groups.is_empty());
+    }
+
+    #[test]
+    fn test_fold_reports_unmerged_duplicates() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.log(attempt("confirmed", 0.7)).unwrap();
+        trace.log(attempt("something else", 0.7)).unwrap();
+        trace.log(attempt("confirmed", 0.8)).unwrap();
+
+        assert_eq!(trace.events[0].fingerprint(), trace.events[2].fingerprint());
+        assert_ne!(trace.events[0].fingerprint(), trace.events[1].fingerprint());
+        let report = trace.fold_memory().unwrap().dedup;
+        assert_eq!(
+            report.duplicate_groups,
+            vec![vec![trace.events[0].event_id.clone(), trace.events[2].event_id.clone()]]
+        );
+        assert!(report.merged.is_empty());
+    }
+
+    struct CapConfidence;
+
+    impl TraceMiddleware for CapConfidence {
+        fn name(&self) -> &str {
+            "cap_confidence"
+        }
+
+        fn before_log(&self, event: &mut SerendipityEvent, _trace: &SerendipityTrace) -> MiddlewareDecision {
+            if event.confidence > 0.9 {
+                MiddlewareDecision::Veto("overconfident".to_string())
+            } else {
+                MiddlewareDecision::Continue
+            }
+        }
+    }
+
+    #[test]
+    fn test_retry_is_checked_and_fingerprinted_as_stored() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.set_redaction_rules(RedactionRules::from_json(
+            r#"[{"name": "codename", "fields": ["output"], "matcher": {"keywords": ["falcon"]}}]"#,
+        ).unwrap());
+        trace.add_middleware(CapConfidence);
+        let first = trace.log_event_idempotent(attempt("falcon confirmed", 0.7)).unwrap();
+
+        // The stored output is redacted; the retry is redacted before matching
+        let retry = trace.log_event_idempotent(attempt("falcon confirmed", 0.8)).unwrap();
+        assert_eq!(retry, IdempotentLog::Merged(first.event_id().to_string()));
+        assert_eq!(trace.events.len(), 1);
+        assert_eq!(trace.events[0].confidence, 0.8);
+        assert_eq!(trace.rule_redactions().count(), 1);
+
+        // A retry the middleware vetoes leaves the logged event unchanged
+        let vetoed = trace.log_event_idempotent(attempt("falcon confirmed", 0.95));
+        assert!(matches!(vetoed, Err(SerenQaError::Vetoed { .. })));
+        assert_eq!(trace.events[0].confidence, 0.8);
+        assert_eq!(trace.dedup_report().merged_retries(), 1);
+    }
+}
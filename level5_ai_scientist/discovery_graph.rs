@@ -0,0 +1,371 @@
+// -*- coding: utf-8 -*-
+//! Cross-Trace Discovery Graph
+//!
+//! Each trace describes one discovery in isolation. `DiscoveryGraph` ingests
+//! many traces and links every discovery to its contributors, the languages
+//! it used, and the concepts mentioned in its event outputs, so discoveries
+//! sharing a concept, contributor, or language become connected. The graph
+//! answers queries such as "which discoveries share an Indonesian validation
+//! step" and ranks discoveries by degree and PageRank centrality. Discovery
+//! names are matched after normalization, so "Journavx" and "journavx" merge.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::serendipity_trace::{SerendipityTrace, SerendipityStage};
+use crate::discovery_registry::normalize_discovery_name;
+use crate::language_registry::LanguageRegistry;
+
+/// Words shorter than this (in characters) are not extracted as concepts by default
+pub const DEFAULT_MIN_CONCEPT_LEN: usize = 5;
+
+/// Common words never extracted as concepts
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "among", "based", "being", "between", "could", "every",
+    "found", "from", "other", "should", "their", "there", "these", "those", "through",
+    "under", "using", "where", "which", "while", "with", "would",
+    "adalah", "dalam", "dengan", "untuk", "yang",
+];
+
+/// PageRank damping factor
+const DAMPING: f64 = 0.85;
+
+/// PageRank power iterations
+const PAGERANK_ITERATIONS: usize = 50;
+
+/// Node in the discovery graph
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum GraphNode {
+    /// Discovery, by the name it was first ingested under
+    Discovery(String),
+    /// Contributor ID
+    Contributor(String),
+    /// Canonical language tag
+    Language(String),
+    /// Lowercased keyword from event outputs
+    Concept(String),
+}
+
+/// Stage of a discovery's trace and the language it was carried out in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveryStep {
+    /// Trace the step comes from
+    pub trace_id: String,
+    /// Stage of the event
+    pub stage: SerendipityStage,
+    /// Canonical language tag of the event
+    pub language: String,
+}
+
+/// Centrality of a discovery node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveryCentrality {
+    /// Discovery name
+    pub discovery: String,
+    /// Neighbors over the number of other nodes
+    pub degree: f64,
+    /// PageRank over the whole graph (all nodes sum to 1)
+    pub pagerank: f64,
+}
+
+/// Graph linking discoveries, contributors, languages, and concepts across traces
+#[derive(Debug, Clone)]
+pub struct DiscoveryGraph {
+    /// Undirected adjacency
+    adjacency: BTreeMap<GraphNode, BTreeSet<GraphNode>>,
+    /// Discovery name -> steps from all its traces
+    steps: BTreeMap<String, Vec<DiscoveryStep>>,
+    /// Normalized discovery name -> name used in the graph
+    names: HashMap<String, String>,
+    /// Minimum concept length in characters
+    min_concept_len: usize,
+}
+
+impl Default for DiscoveryGraph {
+    fn default() -> Self {
+        Self {
+            adjacency: BTreeMap::new(),
+            steps: BTreeMap::new(),
+            names: HashMap::new(),
+            min_concept_len: DEFAULT_MIN_CONCEPT_LEN,
+        }
+    }
+}
+
+/// Whether a step language matches a query tag ("id" matches "id-ID")
+fn language_matches(language: &str, query: &str) -> bool {
+    language == query || language.strip_prefix(query).is_some_and(|rest| rest.starts_with('-'))
+}
+
+impl DiscoveryGraph {
+    /// Empty graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only extract concepts of at least `chars` characters
+    pub fn min_concept_len(mut self, chars: usize) -> Self {
+        self.min_concept_len = chars;
+        self
+    }
+
+    /// Graph built from several traces
+    pub fn from_traces<'a, I: IntoIterator<Item = &'a SerendipityTrace>>(traces: I) -> Self {
+        let mut graph = Self::new();
+        for trace in traces {
+            graph.ingest(trace);
+        }
+        graph
+    }
+
+    /// Concepts mentioned in a piece of text
+    fn concepts(&self, text: &str) -> BTreeSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.chars().count() >= self.min_concept_len)
+            .map(str::to_lowercase)
+            .filter(|word| !STOPWORDS.contains(&word.as_str()) && !word.chars().all(|c| c.is_numeric()))
+            .collect()
+    }
+
+    fn link(&mut self, a: GraphNode, b: GraphNode) {
+        self.adjacency.entry(a.clone()).or_default().insert(b.clone());
+        self.adjacency.entry(b).or_default().insert(a);
+    }
+
+    /// Add a trace's discovery, contributor, languages, concepts, and steps
+    pub fn ingest(&mut self, trace: &SerendipityTrace) {
+        let name = self.names
+            .entry(normalize_discovery_name(&trace.discovery_name))
+            .or_insert_with(|| trace.discovery_name.clone())
+            .clone();
+        let discovery = GraphNode::Discovery(name.clone());
+        self.adjacency.entry(discovery.clone()).or_default();
+        self.link(discovery.clone(), GraphNode::Contributor(trace.contributor_id.clone()));
+
+        let registry = LanguageRegistry::global();
+        for event in &trace.events {
+            let language = registry.canonical(&event.language);
+            self.link(discovery.clone(), GraphNode::Language(language.clone()));
+            for concept in self.concepts(&event.output) {
+                self.link(discovery.clone(), GraphNode::Concept(concept));
+            }
+            self.steps.entry(name.clone()).or_default().push(DiscoveryStep {
+                trace_id: trace.trace_id.clone(),
+                stage: event.stage.clone(),
+                language,
+            });
+        }
+    }
+
+    /// Discovery names, ordered
+    pub fn discoveries(&self) -> Vec<&str> {
+        self.adjacency
+            .keys()
+            .filter_map(|node| match node {
+                GraphNode::Discovery(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Number of nodes
+    pub fn node_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Number of undirected edges
+    pub fn edge_count(&self) -> usize {
+        self.adjacency.values().map(BTreeSet::len).sum::<usize>() / 2
+    }
+
+    /// Nodes linked to `node`
+    pub fn neighbors(&self, node: &GraphNode) -> impl Iterator<Item = &GraphNode> {
+        self.adjacency.get(node).into_iter().flatten()
+    }
+
+    /// Graph node for a discovery name, in any spelling that normalizes the same
+    fn discovery_node(&self, name: &str) -> Option<GraphNode> {
+        self.names
+            .get(&normalize_discovery_name(name))
+            .map(|name| GraphNode::Discovery(name.clone()))
+    }
+
+    /// Steps recorded for a discovery across all its traces
+    pub fn steps(&self, discovery: &str) -> &[DiscoveryStep] {
+        self.names
+            .get(&normalize_discovery_name(discovery))
+            .and_then(|name| self.steps.get(name))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Discoveries with a `stage` event in `language` (any spelling the language
+    /// registry resolves; a bare language also matches regional tags)
+    pub fn discoveries_with_step(&self, stage: &SerendipityStage, language: &str) -> Vec<&str> {
+        let language = LanguageRegistry::global().canonical(language);
+        self.steps
+            .iter()
+            .filter(|(_, steps)| {
+                steps.iter().any(|step| &step.stage == stage && language_matches(&step.language, &language))
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Discoveries linked to a concept
+    pub fn discoveries_with_concept(&self, concept: &str) -> Vec<&str> {
+        self.neighbors(&GraphNode::Concept(concept.to_lowercase()))
+            .filter_map(|node| match node {
+                GraphNode::Discovery(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Concepts mentioned by both discoveries
+    pub fn shared_concepts(&self, a: &str, b: &str) -> Vec<&str> {
+        let (Some(a), Some(b)) = (self.discovery_node(a), self.discovery_node(b)) else {
+            return Vec::new();
+        };
+        let b: BTreeSet<&GraphNode> = self.neighbors(&b).collect();
+        self.neighbors(&a)
+            .filter(|node| b.contains(node))
+            .filter_map(|node| match node {
+                GraphNode::Concept(concept) => Some(concept.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Other discoveries sharing a contributor, language, or concept with
+    /// `discovery`, with the number of shared nodes, most related first
+    pub fn related_discoveries(&self, discovery: &str) -> Vec<(&str, usize)> {
+        let Some(node) = self.discovery_node(discovery) else {
+            return Vec::new();
+        };
+        let mut shared: BTreeMap<&str, usize> = BTreeMap::new();
+        for neighbor in self.neighbors(&node) {
+            for other in self.neighbors(neighbor) {
+                if let GraphNode::Discovery(name) = other {
+                    if other != &node {
+                        *shared.entry(name.as_str()).or_default() += 1;
+                    }
+                }
+            }
+        }
+        let mut related: Vec<_> = shared.into_iter().collect();
+        related.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        related
+    }
+
+    /// PageRank of every node (dangling mass is spread uniformly)
+    fn pagerank(&self) -> HashMap<&GraphNode, f64> {
+        let nodes: Vec<&GraphNode> = self.adjacency.keys().collect();
+        let n = nodes.len();
+        if n == 0 {
+            return HashMap::new();
+        }
+        let index: HashMap<&GraphNode, usize> = nodes.iter().enumerate().map(|(i, node)| (*node, i)).collect();
+        let neighbors: Vec<Vec<usize>> = nodes
+            .iter()
+            .map(|node| self.adjacency[*node].iter().map(|other| index[other]).collect())
+            .collect();
+
+        let mut rank = vec![1.0 / n as f64; n];
+        for _ in 0..PAGERANK_ITERATIONS {
+            let dangling: f64 = (0..n).filter(|&i| neighbors[i].is_empty()).map(|i| rank[i]).sum();
+            let base = (1.0 - DAMPING + DAMPING * dangling) / n as f64;
+            let mut next = vec![base; n];
+            for (i, links) in neighbors.iter().enumerate() {
+                for &j in links {
+                    next[j] += DAMPING * rank[i] / links.len() as f64;
+                }
+            }
+            rank = next;
+        }
+        nodes.into_iter().zip(rank).collect()
+    }
+
+    /// Centrality of every discovery, highest PageRank first
+    pub fn centrality(&self) -> Vec<DiscoveryCentrality> {
+        let pagerank = self.pagerank();
+        let others = self.node_count().saturating_sub(1).max(1) as f64;
+        let mut centrality: Vec<DiscoveryCentrality> = self.adjacency
+            .iter()
+            .filter_map(|(node, links)| match node {
+                GraphNode::Discovery(name) => Some(DiscoveryCentrality {
+                    discovery: name.clone(),
+                    degree: links.len() as f64 / others,
+                    pagerank: pagerank[node],
+                }),
+                _ => None,
+            })
+            .collect();
+        centrality.sort_by(|a, b| {
+            b.pagerank.total_cmp(&a.pagerank).then_with(|| a.discovery.cmp(&b.discovery))
+        });
+        centrality
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityAgent;
+
+    fn trace(contributor: &str, discovery: &str, steps: &[(SerendipityStage, &str, &str)]) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor, "backend", discovery);
+        for (stage, output, language) in steps {
+            trace.log_event(stage.clone(), SerendipityAgent::Explorer, "in", output, language, 0.5, 0.8).unwrap();
+        }
+        trace
+    }
+
+    fn sample() -> DiscoveryGraph {
+        use SerendipityStage::*;
+        let traces = [
+            trace("researcher1", "Journavx", &[
+                (Exploration, "Quantum walk navigation", "en"),
+                (Validation, "Navigasi bintang divalidasi dengan nelayan", "id-ID"),
+            ]),
+            trace("researcher2", "Star Compass", &[
+                (Exploration, "Javanese navigation by stars", "jv"),
+                (Validation, "Kompas bintang dikonfirmasi", "Indonesian"),
+            ]),
+            trace("researcher1", "journavx", &[(Publication, "Quantum navigation paper", "en")]),
+            trace("researcher3", "Tidal Model", &[(Validation, "Tidal model validated", "en")]),
+        ];
+        DiscoveryGraph::from_traces(&traces)
+    }
+
+    #[test]
+    fn test_step_and_concept_queries() {
+        let graph = sample();
+        assert_eq!(graph.discoveries(), vec!["Journavx", "Star Compass", "Tidal Model"]);
+        assert_eq!(graph.steps("JOURNAVX").len(), 3);
+
+        assert_eq!(graph.discoveries_with_step(&SerendipityStage::Validation, "id"), vec!["Journavx", "Star Compass"]);
+        assert_eq!(graph.discoveries_with_step(&SerendipityStage::Validation, "english"), vec!["Tidal Model"]);
+        assert!(graph.discoveries_with_step(&SerendipityStage::Publication, "id").is_empty());
+
+        assert_eq!(graph.shared_concepts("Journavx", "Star Compass"), vec!["bintang", "navigation"]);
+        assert_eq!(graph.discoveries_with_concept("Quantum"), vec!["Journavx"]);
+        assert!(graph.discoveries_with_concept("dengan").is_empty());
+    }
+
+    #[test]
+    fn test_related_discoveries_and_centrality() {
+        let graph = sample();
+        // "bintang" and "navigation"; the tags "id-ID" and "id" are distinct language nodes
+        assert_eq!(graph.related_discoveries("Star Compass"), vec![("Journavx", 2)]);
+        assert_eq!(graph.related_discoveries("Tidal Model"), vec![("Journavx", 1)]);
+        assert!(graph.related_discoveries("Unknown").is_empty());
+
+        let centrality = graph.centrality();
+        assert_eq!(centrality.len(), 3);
+        assert_eq!(centrality[0].discovery, "Journavx");
+        assert!(centrality[0].degree > centrality[2].degree);
+        let total: f64 = graph.pagerank().values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert_eq!(DiscoveryGraph::new().centrality(), Vec::new());
+    }
+}
@@ -0,0 +1,324 @@
+// -*- coding: utf-8 -*-
+//! Discovery Registry with Aliasing
+//!
+//! Different contributors may name the same discovery differently
+//! ("Journavx", "JournaVX", "Java Quantum Navigation"). The registry assigns
+//! canonical ids, tracks aliases, and offers fuzzy-match suggestions at
+//! submission time so credit consolidates on a single discovery.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::ContributorStats::{LanguageAwareContributorStats, LanguageAwareLeaderboard};
+
+/// Minimum similarity for a fuzzy-match suggestion
+pub const DEFAULT_SUGGESTION_THRESHOLD: f64 = 0.75;
+
+/// Registered discovery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryRecord {
+    /// Canonical identifier (normalized slug)
+    pub canonical_id: String,
+    /// Canonical display name
+    pub name: String,
+    /// Alternative names resolving to this discovery
+    pub aliases: Vec<String>,
+    /// Contributors credited with the discovery
+    pub contributors: Vec<String>,
+}
+
+/// Fuzzy-match suggestion for a submitted name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoverySuggestion {
+    /// Canonical id of the suggested discovery
+    pub canonical_id: String,
+    /// Name or alias that matched
+    pub matched_name: String,
+    /// Similarity (0.0-1.0)
+    pub similarity: f64,
+}
+
+/// Result of submitting a discovery name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoverySubmission {
+    /// Canonical id the submission was credited to
+    pub canonical_id: String,
+    /// Whether a new discovery was registered
+    pub is_new: bool,
+    /// Similar existing discoveries the submitter may want to alias to
+    pub suggestions: Vec<DiscoverySuggestion>,
+}
+
+/// Registry of discoveries and their aliases
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiscoveryRegistry {
+    /// Canonical id -> record
+    records: HashMap<String, DiscoveryRecord>,
+    /// Normalized name or alias -> canonical id
+    alias_index: HashMap<String, String>,
+    /// Minimum similarity for suggestions
+    suggestion_threshold: f64,
+}
+
+/// Normalize a discovery name for matching (lowercase alphanumerics)
+pub fn normalize_discovery_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Levenshtein edit distance over characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Normalized similarity between two names (0.0-1.0)
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let a = normalize_discovery_name(a);
+    let b = normalize_discovery_name(b);
+    let max_len = a.chars().count().max(b.chars().count());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / max_len as f64
+}
+
+impl DiscoveryRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            records: HashMap::new(),
+            alias_index: HashMap::new(),
+            suggestion_threshold: DEFAULT_SUGGESTION_THRESHOLD,
+        }
+    }
+
+    /// Set the minimum similarity for suggestions
+    pub fn with_suggestion_threshold(mut self, threshold: f64) -> Self {
+        self.suggestion_threshold = threshold;
+        self
+    }
+
+    /// Register a new discovery, returning its canonical id.
+    /// Returns the existing id if the name is already known.
+    pub fn register(&mut self, name: &str) -> String {
+        if let Some(id) = self.resolve_id(name) {
+            return id;
+        }
+
+        let canonical_id = normalize_discovery_name(name);
+        self.alias_index.insert(canonical_id.clone(), canonical_id.clone());
+        self.records.insert(canonical_id.clone(), DiscoveryRecord {
+            canonical_id: canonical_id.clone(),
+            name: name.to_string(),
+            aliases: Vec::new(),
+            contributors: Vec::new(),
+        });
+        canonical_id
+    }
+
+    /// Add an alias for a registered discovery.
+    /// Returns false if the discovery is unknown or the alias belongs to another discovery.
+    pub fn add_alias(&mut self, canonical_id: &str, alias: &str) -> bool {
+        let key = normalize_discovery_name(alias);
+        match self.alias_index.get(&key) {
+            Some(existing) => return existing == canonical_id,
+            None if !self.records.contains_key(canonical_id) => return false,
+            None => {}
+        }
+
+        self.alias_index.insert(key, canonical_id.to_string());
+        if let Some(record) = self.records.get_mut(canonical_id) {
+            record.aliases.push(alias.to_string());
+        }
+        true
+    }
+
+    /// Resolve a name or alias to its canonical id
+    pub fn resolve_id(&self, name: &str) -> Option<String> {
+        self.alias_index.get(&normalize_discovery_name(name)).cloned()
+    }
+
+    /// Resolve a name or alias to its record
+    pub fn resolve(&self, name: &str) -> Option<&DiscoveryRecord> {
+        self.resolve_id(name).and_then(|id| self.records.get(&id))
+    }
+
+    /// Get a record by canonical id
+    pub fn get(&self, canonical_id: &str) -> Option<&DiscoveryRecord> {
+        self.records.get(canonical_id)
+    }
+
+    /// Fuzzy-match suggestions for a name, best first
+    pub fn suggest(&self, name: &str, max: usize) -> Vec<DiscoverySuggestion> {
+        let mut best: HashMap<String, DiscoverySuggestion> = HashMap::new();
+
+        for record in self.records.values() {
+            for candidate in std::iter::once(&record.name).chain(record.aliases.iter()) {
+                let similarity = name_similarity(name, candidate);
+                if similarity < self.suggestion_threshold {
+                    continue;
+                }
+                let better = best
+                    .get(&record.canonical_id)
+                    .map(|s| similarity > s.similarity)
+                    .unwrap_or(true);
+                if better {
+                    best.insert(record.canonical_id.clone(), DiscoverySuggestion {
+                        canonical_id: record.canonical_id.clone(),
+                        matched_name: candidate.clone(),
+                        similarity,
+                    });
+                }
+            }
+        }
+
+        let mut suggestions: Vec<_> = best.into_values().collect();
+        suggestions.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
+        suggestions.truncate(max);
+        suggestions
+    }
+
+    /// Submit a discovery name for a contributor.
+    /// Exact name/alias matches are credited to the existing discovery; otherwise
+    /// a new discovery is registered and near matches are returned as suggestions.
+    pub fn submit(&mut self, name: &str, contributor_id: &str) -> DiscoverySubmission {
+        let (canonical_id, is_new, suggestions) = match self.resolve_id(name) {
+            Some(id) => (id, false, Vec::new()),
+            None => {
+                let suggestions = self.suggest(name, 5);
+                (self.register(name), true, suggestions)
+            }
+        };
+
+        if let Some(record) = self.records.get_mut(&canonical_id) {
+            if !record.contributors.iter().any(|c| c == contributor_id) {
+                record.contributors.push(contributor_id.to_string());
+            }
+        }
+
+        DiscoverySubmission { canonical_id, is_new, suggestions }
+    }
+
+    /// Merge `duplicate_id` into `canonical_id`, moving its names and credit
+    pub fn merge(&mut self, canonical_id: &str, duplicate_id: &str) -> bool {
+        if canonical_id == duplicate_id || !self.records.contains_key(canonical_id) {
+            return false;
+        }
+        let duplicate = match self.records.remove(duplicate_id) {
+            Some(duplicate) => duplicate,
+            None => return false,
+        };
+
+        for target in self.alias_index.values_mut() {
+            if target == duplicate_id {
+                *target = canonical_id.to_string();
+            }
+        }
+
+        let record = self.records.get_mut(canonical_id).unwrap();
+        record.aliases.push(duplicate.name);
+        record.aliases.extend(duplicate.aliases);
+        for contributor in duplicate.contributors {
+            if !record.contributors.contains(&contributor) {
+                record.contributors.push(contributor);
+            }
+        }
+        true
+    }
+
+    /// Rewrite a contributor's discoveries to canonical names, merging duplicates
+    pub fn canonicalize_stats(&self, stats: &mut LanguageAwareContributorStats) {
+        let mut canonical: Vec<String> = Vec::new();
+        for discovery in &stats.discoveries {
+            let name = self
+                .resolve(discovery)
+                .map(|r| r.name.clone())
+                .unwrap_or_else(|| discovery.clone());
+            if let Some(domain) = stats.discovery_domains.get(discovery) {
+                if name != *discovery {
+                    let domain = domain.clone();
+                    stats.discovery_domains.entry(name.clone()).or_insert(domain);
+                }
+            }
+            if !canonical.contains(&name) {
+                canonical.push(name);
+            }
+        }
+        stats.discovery_domains.retain(|name, _| canonical.contains(name));
+        stats.discoveries = canonical;
+    }
+
+    /// Canonicalize discoveries of every contributor on a leaderboard
+    pub fn consolidate_leaderboard(&self, leaderboard: &mut LanguageAwareLeaderboard) {
+        leaderboard.update_contributors(|stats| self.canonicalize_stats(stats));
+    }
+
+    /// Number of registered discoveries
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Check if no discoveries are registered
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_alias() {
+        let mut registry = DiscoveryRegistry::new();
+        let id = registry.register("Journavx");
+        assert_eq!(id, "journavx");
+        assert!(registry.add_alias(&id, "Java Quantum Navigation"));
+        assert_eq!(registry.resolve_id("JOURNAVX"), Some(id.clone()));
+        assert_eq!(registry.resolve_id("java quantum-navigation"), Some(id));
+    }
+
+    #[test]
+    fn test_submission_suggestions() {
+        let mut registry = DiscoveryRegistry::new();
+        registry.submit("Journavx", "dr_sari_wijaya");
+
+        let exact = registry.submit("JournaVX", "researcher2");
+        assert!(!exact.is_new);
+        assert_eq!(registry.get("journavx").unwrap().contributors.len(), 2);
+
+        let fuzzy = registry.submit("Journavix", "researcher3");
+        assert!(fuzzy.is_new);
+        assert_eq!(fuzzy.suggestions[0].canonical_id, "journavx");
+    }
+
+    #[test]
+    fn test_merge_and_canonicalize_stats() {
+        let mut registry = DiscoveryRegistry::new();
+        registry.submit("Journavx", "dr_sari_wijaya");
+        registry.submit("Journavix", "researcher3");
+        assert!(registry.merge("journavx", "journavix"));
+        assert_eq!(registry.len(), 1);
+
+        let mut stats = LanguageAwareContributorStats::new("researcher3");
+        stats.add_discovery("Journavix");
+        stats.add_discovery("Journavx");
+        registry.canonicalize_stats(&mut stats);
+        assert_eq!(stats.discoveries, vec!["Journavx".to_string()]);
+    }
+}
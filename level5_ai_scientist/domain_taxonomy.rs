@@ -0,0 +1,213 @@
+// -*- coding: utf-8 -*-
+//! Hierarchical Domain Taxonomy
+//!
+//! Optional taxonomy of research domains (e.g., Physics > Quantum Computing >
+//! Quantum Algorithms) loaded from a data file. Used to canonicalize free-text
+//! expertise/discovery domains, roll statistics up to parent domains, and
+//! filter the leaderboard by domain facet.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
+
+/// Separator used for full domain paths
+pub const DOMAIN_PATH_SEPARATOR: &str = " > ";
+
+/// Node of the taxonomy as stored in the data file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainNode {
+    /// Display name of the domain
+    pub name: String,
+    /// Alternative spellings resolving to this domain
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Sub-domains
+    #[serde(default)]
+    pub children: Vec<DomainNode>,
+}
+
+/// Hierarchical domain taxonomy
+#[derive(Debug, Clone, Default)]
+pub struct DomainTaxonomy {
+    /// Root domains
+    roots: Vec<DomainNode>,
+    /// Full path -> parent full path
+    parents: HashMap<String, Option<String>>,
+    /// Lowercased name, alias, or full path -> full path
+    lookup: HashMap<String, String>,
+}
+
+impl DomainTaxonomy {
+    /// Create a taxonomy from root nodes
+    pub fn new(roots: Vec<DomainNode>) -> Self {
+        let mut taxonomy = Self::default();
+        // Breadth-first, so a name shared by several nodes resolves to the shallowest
+        let mut queue: VecDeque<(&DomainNode, Option<String>)> = roots.iter().map(|root| (root, None)).collect();
+        while let Some((node, parent)) = queue.pop_front() {
+            let path = taxonomy.index_node(node, parent.as_deref());
+            queue.extend(node.children.iter().map(|child| (child, Some(path.clone()))));
+        }
+        taxonomy.roots = roots;
+        taxonomy
+    }
+
+    /// Load taxonomy from a JSON array of root nodes
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let roots: Vec<DomainNode> = serde_json::from_str(json)?;
+        Ok(Self::new(roots))
+    }
+
+    /// Built-in taxonomy shipped in `data/domain_taxonomy.json`
+    pub fn builtin() -> Self {
+        Self::from_json(include_str!("data/domain_taxonomy.json")).expect("built-in domain taxonomy is valid JSON")
+    }
+
+    /// Load taxonomy from a JSON data file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Index a node (not its children), returning its full path
+    fn index_node(&mut self, node: &DomainNode, parent: Option<&str>) -> String {
+        let path = match parent {
+            Some(parent) => format!("{}{}{}", parent, DOMAIN_PATH_SEPARATOR, node.name),
+            None => node.name.clone(),
+        };
+
+        self.parents.insert(path.clone(), parent.map(|p| p.to_string()));
+        self.lookup.insert(path.to_lowercase(), path.clone());
+        // Names and aliases resolve to the first node indexed, the shallowest
+        self.lookup.entry(node.name.to_lowercase()).or_insert_with(|| path.clone());
+        for alias in &node.aliases {
+            self.lookup.entry(alias.to_lowercase()).or_insert_with(|| path.clone());
+        }
+        path
+    }
+
+    /// Root domains
+    pub fn roots(&self) -> &[DomainNode] {
+        &self.roots
+    }
+
+    /// Number of domains in the taxonomy
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Check if the taxonomy has no domains
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Resolve a name, alias, or full path to its canonical full path
+    pub fn canonicalize(&self, domain: &str) -> Option<String> {
+        let key = domain
+            .split('>')
+            .map(|part| part.trim())
+            .collect::<Vec<_>>()
+            .join(DOMAIN_PATH_SEPARATOR)
+            .to_lowercase();
+        self.lookup.get(&key).cloned()
+    }
+
+    /// Parent of a canonical path
+    pub fn parent(&self, path: &str) -> Option<&str> {
+        self.parents.get(path).and_then(|p| p.as_deref())
+    }
+
+    /// Canonical path and all its ancestors, from the domain up to the root
+    pub fn ancestors(&self, domain: &str) -> Vec<String> {
+        let mut chain = Vec::new();
+        let mut current = self.canonicalize(domain);
+        while let Some(path) = current {
+            current = self.parent(&path).map(|p| p.to_string());
+            chain.push(path);
+        }
+        chain
+    }
+
+    /// Check if `domain` is `ancestor` or one of its descendants
+    pub fn is_within(&self, domain: &str, ancestor: &str) -> bool {
+        match self.canonicalize(ancestor) {
+            Some(ancestor) => self.ancestors(domain).contains(&ancestor),
+            None => false,
+        }
+    }
+
+    /// Roll up counts of domains to every ancestor in the taxonomy.
+    /// Domains not present in the taxonomy are counted under their free-text name.
+    pub fn rollup<'a, I>(&self, domains: I) -> BTreeMap<String, usize>
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        let mut counts = BTreeMap::new();
+        for domain in domains {
+            let chain = self.ancestors(domain);
+            if chain.is_empty() {
+                *counts.entry(domain.clone()).or_insert(0) += 1;
+            } else {
+                for path in chain {
+                    *counts.entry(path).or_insert(0) += 1;
+                }
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_taxonomy() -> DomainTaxonomy {
+        DomainTaxonomy::from_json(include_str!("data/domain_taxonomy.json")).unwrap()
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let taxonomy = sample_taxonomy();
+        assert_eq!(
+            taxonomy.canonicalize("quantum algorithms"),
+            Some("Physics > Quantum Computing > Quantum Algorithms".to_string())
+        );
+        assert_eq!(
+            taxonomy.canonicalize("Physics>Quantum Computing"),
+            Some("Physics > Quantum Computing".to_string())
+        );
+        assert!(taxonomy.canonicalize("Basket Weaving").is_none());
+
+        // A shared name resolves to the shallowest node, even if a deeper one comes first
+        let shared = DomainTaxonomy::from_json(r#"[
+            {"name": "Physics", "children": [{"name": "Photonics", "children": [{"name": "Optics"}]}]},
+            {"name": "Engineering", "children": [{"name": "Optics"}]}
+        ]"#).unwrap();
+        assert_eq!(shared.canonicalize("optics"), Some("Engineering > Optics".to_string()));
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let taxonomy = sample_taxonomy();
+        let chain = taxonomy.ancestors("Quantum Algorithms");
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[2], "Physics");
+        assert!(taxonomy.is_within("Quantum Algorithms", "Physics"));
+        assert!(!taxonomy.is_within("Physics", "Quantum Algorithms"));
+    }
+
+    #[test]
+    fn test_rollup() {
+        let taxonomy = sample_taxonomy();
+        let domains = vec![
+            "Quantum Algorithms".to_string(),
+            "Quantum Computing".to_string(),
+            "Basket Weaving".to_string(),
+        ];
+        let counts = taxonomy.rollup(&domains);
+        assert_eq!(counts["Physics"], 2);
+        assert_eq!(counts["Physics > Quantum Computing"], 2);
+        assert_eq!(counts["Physics > Quantum Computing > Quantum Algorithms"], 1);
+        assert_eq!(counts["Basket Weaving"], 1);
+    }
+}
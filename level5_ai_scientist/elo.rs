@@ -0,0 +1,229 @@
+// -*- coding: utf-8 -*-
+//! Pairwise Discovery Ranking (Elo)
+//!
+//! Absolute scores are hard for judges to calibrate; choosing the better of
+//! two discoveries is easier. `DiscoveryElo` records head-to-head judgements
+//! and updates Elo ratings for both discoveries and the contributors behind
+//! them. For team traces the side's rating is the mean of its members'
+//! ratings and every member moves by the same amount. Ratings are copied onto
+//! leaderboard statistics for `LanguageAwareRankingCriteria::Elo`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::serendipity_trace::SerendipityTrace;
+use crate::discovery_registry::normalize_discovery_name;
+use crate::ContributorStats::LanguageAwareLeaderboard;
+
+/// Rating of a discovery or contributor with no judged matches
+pub const DEFAULT_ELO_RATING: f64 = 1500.0;
+
+/// Maximum rating change per match
+pub const DEFAULT_K_FACTOR: f64 = 32.0;
+
+/// Judge's verdict on a head-to-head comparison
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchOutcome {
+    /// The first discovery is better
+    FirstWins,
+    /// The second discovery is better
+    SecondWins,
+    /// Neither is better
+    Draw,
+}
+
+impl MatchOutcome {
+    /// Score of the first discovery (1 win, 0.5 draw, 0 loss)
+    pub fn first_score(&self) -> f64 {
+        match self {
+            MatchOutcome::FirstWins => 1.0,
+            MatchOutcome::SecondWins => 0.0,
+            MatchOutcome::Draw => 0.5,
+        }
+    }
+}
+
+/// Elo rating with the number of matches behind it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct EloRating {
+    /// Current rating
+    pub rating: f64,
+    /// Judged matches played
+    pub matches: usize,
+}
+
+impl Default for EloRating {
+    fn default() -> Self {
+        Self { rating: DEFAULT_ELO_RATING, matches: 0 }
+    }
+}
+
+/// Expected score of a side rated `rating` against one rated `opponent`
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Rating changes of the two discoveries after a match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchResult {
+    /// Rating change of the first discovery
+    pub first_delta: f64,
+    /// Rating change of the second discovery
+    pub second_delta: f64,
+}
+
+/// Elo ratings of discoveries and contributors from judged comparisons
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiscoveryElo {
+    /// Maximum rating change per match
+    pub k_factor: f64,
+    /// Discovery name -> rating (names as first judged)
+    #[serde(default)]
+    discoveries: BTreeMap<String, EloRating>,
+    /// Contributor ID -> rating
+    #[serde(default)]
+    contributors: BTreeMap<String, EloRating>,
+}
+
+impl Default for DiscoveryElo {
+    fn default() -> Self {
+        Self::new(DEFAULT_K_FACTOR)
+    }
+}
+
+impl DiscoveryElo {
+    /// Empty ratings with the given K-factor
+    pub fn new(k_factor: f64) -> Self {
+        Self { k_factor, discoveries: BTreeMap::new(), contributors: BTreeMap::new() }
+    }
+
+    /// Rating key of a discovery, merging spellings that normalize the same
+    fn discovery_key(&self, name: &str) -> String {
+        let normalized = normalize_discovery_name(name);
+        self.discoveries
+            .keys()
+            .find(|known| normalize_discovery_name(known) == normalized)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Rating of a discovery (default if never judged)
+    pub fn discovery_rating(&self, name: &str) -> EloRating {
+        self.discoveries.get(&self.discovery_key(name)).copied().unwrap_or_default()
+    }
+
+    /// Rating of a contributor (default if never judged)
+    pub fn contributor_rating(&self, contributor_id: &str) -> EloRating {
+        self.contributors.get(contributor_id).copied().unwrap_or_default()
+    }
+
+    /// Mean rating of a team
+    fn team_rating(&self, members: &[&str]) -> f64 {
+        members.iter().map(|m| self.contributor_rating(m).rating).sum::<f64>() / members.len().max(1) as f64
+    }
+
+    fn adjust(ratings: &mut BTreeMap<String, EloRating>, key: &str, delta: f64) {
+        let rating = ratings.entry(key.to_string()).or_default();
+        rating.rating += delta;
+        rating.matches += 1;
+    }
+
+    /// Record a judge's comparison of the discoveries in two traces and
+    /// update both discoveries and their contributors. Contributors on both
+    /// sides are left unchanged.
+    pub fn record_match(&mut self, first: &SerendipityTrace, second: &SerendipityTrace, outcome: MatchOutcome) -> MatchResult {
+        let score = outcome.first_score();
+
+        let (a, b) = (self.discovery_key(&first.discovery_name), self.discovery_key(&second.discovery_name));
+        let expected = expected_score(self.discovery_rating(&a).rating, self.discovery_rating(&b).rating);
+        let first_delta = self.k_factor * (score - expected);
+        Self::adjust(&mut self.discoveries, &a, first_delta);
+        Self::adjust(&mut self.discoveries, &b, -first_delta);
+
+        let first_team = first.contributors();
+        let second_team = second.contributors();
+        let first_only: Vec<&str> = first_team.iter().copied().filter(|c| !second_team.contains(c)).collect();
+        let second_only: Vec<&str> = second_team.iter().copied().filter(|c| !first_team.contains(c)).collect();
+        if !first_only.is_empty() && !second_only.is_empty() {
+            let expected = expected_score(self.team_rating(&first_only), self.team_rating(&second_only));
+            let delta = self.k_factor * (score - expected);
+            for member in first_only {
+                Self::adjust(&mut self.contributors, member, delta);
+            }
+            for member in second_only {
+                Self::adjust(&mut self.contributors, member, -delta);
+            }
+        }
+
+        MatchResult { first_delta, second_delta: -first_delta }
+    }
+
+    /// Discoveries ordered by rating, highest first
+    pub fn discovery_rankings(&self) -> Vec<(&str, EloRating)> {
+        let mut rankings: Vec<(&str, EloRating)> = self.discoveries.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        rankings.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating).then_with(|| a.0.cmp(b.0)));
+        rankings
+    }
+
+    /// Copy contributor ratings onto a leaderboard for `LanguageAwareRankingCriteria::Elo`
+    pub fn apply_to_leaderboard(&self, leaderboard: &mut LanguageAwareLeaderboard) {
+        leaderboard.update_contributors(|stats| {
+            stats.elo_rating = self.contributors.get(&stats.contributor_id).map(|r| r.rating);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContributorStats::{LanguageAwareContributorStats, LanguageAwareRankingCriteria};
+
+    fn trace(contributor: &str, discovery: &str) -> SerendipityTrace {
+        SerendipityTrace::new(contributor, "backend", discovery)
+    }
+
+    #[test]
+    fn test_ratings_update_for_discoveries_and_contributors() {
+        let mut elo = DiscoveryElo::default();
+        let journavx = trace("sari", "Journavx");
+        let compass = trace("budi", "Star Compass");
+
+        let result = elo.record_match(&journavx, &compass, MatchOutcome::FirstWins);
+        assert_eq!(result.first_delta, 16.0);
+        assert_eq!(elo.discovery_rating("journavx").rating, 1516.0);
+        assert_eq!(elo.contributor_rating("budi").rating, 1484.0);
+
+        // An upset moves ratings further than an expected result
+        let upset = elo.record_match(&journavx, &compass, MatchOutcome::SecondWins);
+        assert!(upset.second_delta > 16.0);
+        assert_eq!(elo.discovery_rating("Journavx").matches, 2);
+        let draw = elo.record_match(&journavx, &journavx, MatchOutcome::Draw);
+        assert_eq!(draw.first_delta, 0.0);
+        assert_eq!(elo.contributor_rating("sari").matches, 2);
+
+        assert_eq!(elo.discovery_rankings()[0].0, "Star Compass");
+        let restored: DiscoveryElo = serde_json::from_str(&serde_json::to_string(&elo).unwrap()).unwrap();
+        assert_eq!(restored, elo);
+    }
+
+    #[test]
+    fn test_leaderboard_elo_criteria() {
+        let mut elo = DiscoveryElo::default();
+        for _ in 0..3 {
+            elo.record_match(&trace("budi", "Star Compass"), &trace("sari", "Journavx"), MatchOutcome::FirstWins);
+        }
+
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for contributor in ["sari", "budi", "dewi"] {
+            leaderboard.add_contributor(LanguageAwareContributorStats::new(contributor));
+        }
+        elo.apply_to_leaderboard(&mut leaderboard);
+        let top: Vec<String> = leaderboard
+            .get_top_n(3, LanguageAwareRankingCriteria::Elo)
+            .into_iter()
+            .map(|s| s.contributor_id)
+            .collect();
+        assert_eq!(top, vec!["budi", "dewi", "sari"]);
+        assert_eq!(leaderboard.get("dewi").unwrap().elo_rating, None);
+    }
+}
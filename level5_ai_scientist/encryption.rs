@@ -0,0 +1,422 @@
+// -*- coding: utf-8 -*-
+//! Encryption at Rest for Stored Traces
+//!
+//! Institutions keep confidential pre-publication traces on shared storage.
+//! `EncryptedFsTraceStore` writes each trace as `<trace_id>.serenc`: a small
+//! cleartext header (trace and contributor IDs, key version, nonce, and the
+//! provenance hash of the plaintext) followed by the AES-256-GCM encryption of
+//! the trace JSON. The header is authenticated along with the ciphertext, so
+//! neither can be changed unnoticed. Each contributor has their own keys in a
+//! `Keyring`; adding a key rotates to it, and `reencrypt` moves older files
+//! onto the current keys so retired keys can be dropped. Loading decrypts and
+//! checks the trace against the header's provenance hash. Requires the
+//! `encryption` feature (`aes-gcm` crate).
+#![cfg(feature = "encryption")]
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::store::{file_stem, TraceStore};
+use crate::provenance::{hex_decode, to_hex};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// First bytes of every encrypted trace
+pub const ENCRYPTED_MAGIC: &[u8; 4] = b"SRNE";
+
+/// Layout version of the encrypted container
+pub const ENCRYPTED_FORMAT_VERSION: u8 = 1;
+
+/// File extension of encrypted traces
+pub const ENCRYPTED_EXTENSION: &str = "serenc";
+
+fn failed(message: impl Into<String>) -> SerenQaError {
+    SerenQaError::Encryption(message.into())
+}
+
+/// AES-256 key of one contributor
+#[derive(Clone, PartialEq, Eq)]
+pub struct ContributorKey([u8; 32]);
+
+impl ContributorKey {
+    /// Fresh random key
+    pub fn generate() -> Self {
+        Self(Aes256Gcm::generate_key(OsRng).into())
+    }
+
+    /// Key from its raw bytes (e.g. loaded from a secrets manager)
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Raw key bytes, for storing in a secrets manager
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+/// Keys are never printed
+impl std::fmt::Debug for ContributorKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ContributorKey(..)")
+    }
+}
+
+/// Versioned keys of every contributor; the newest version is current
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    keys: HashMap<String, BTreeMap<u32, ContributorKey>>,
+}
+
+impl Keyring {
+    /// Empty keyring
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `key` the current key of `contributor_id`; returns its version
+    /// (1 for a contributor's first key)
+    pub fn add_key(&mut self, contributor_id: &str, key: ContributorKey) -> u32 {
+        let versions = self.keys.entry(contributor_id.to_string()).or_default();
+        let version = versions.keys().next_back().map_or(1, |latest| latest + 1);
+        versions.insert(version, key);
+        version
+    }
+
+    /// Current key of `contributor_id` with its version
+    pub fn current(&self, contributor_id: &str) -> Option<(u32, &ContributorKey)> {
+        self.keys.get(contributor_id)?.iter().next_back().map(|(version, key)| (*version, key))
+    }
+
+    /// Key `version` of `contributor_id`
+    pub fn key(&self, contributor_id: &str, version: u32) -> Option<&ContributorKey> {
+        self.keys.get(contributor_id)?.get(&version)
+    }
+
+    /// Drop an old key; the current key cannot be retired. Returns whether
+    /// the key was dropped.
+    pub fn retire(&mut self, contributor_id: &str, version: u32) -> bool {
+        let Some(versions) = self.keys.get_mut(contributor_id) else { return false };
+        if versions.keys().next_back() == Some(&version) {
+            return false;
+        }
+        versions.remove(&version).is_some()
+    }
+}
+
+/// Cleartext header of an encrypted trace, authenticated with the ciphertext
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EncryptedHeader {
+    pub trace_id: String,
+    pub contributor_id: String,
+    /// Version of the contributor key the trace is encrypted under
+    pub key_version: u32,
+    /// AES-GCM nonce, hex
+    pub nonce: String,
+    /// Provenance hash of the plaintext trace
+    pub provenance_hash: String,
+}
+
+/// Encrypt a trace under the contributor's current key
+pub fn encrypt_trace(trace: &SerendipityTrace, keyring: &Keyring) -> SerenQaResult<Vec<u8>> {
+    let (key_version, key) = keyring
+        .current(&trace.contributor_id)
+        .ok_or_else(|| failed(format!("no key for contributor {}", trace.contributor_id)))?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let header = EncryptedHeader {
+        trace_id: trace.trace_id.clone(),
+        contributor_id: trace.contributor_id.clone(),
+        key_version,
+        nonce: to_hex(&nonce),
+        provenance_hash: trace.compute_provenance_hash(),
+    };
+    let header_json = serde_json::to_vec(&header)?;
+    let plaintext = trace.to_json()?;
+    let ciphertext = key
+        .cipher()
+        .encrypt(&nonce, Payload { msg: plaintext.as_bytes(), aad: &header_json })
+        .map_err(|_| failed("encryption failed"))?;
+
+    let mut bytes = Vec::with_capacity(ENCRYPTED_MAGIC.len() + 5 + header_json.len() + ciphertext.len());
+    bytes.extend_from_slice(ENCRYPTED_MAGIC);
+    bytes.push(ENCRYPTED_FORMAT_VERSION);
+    bytes.extend_from_slice(&(header_json.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&header_json);
+    bytes.extend_from_slice(&ciphertext);
+    Ok(bytes)
+}
+
+/// Split an encrypted trace into its header, the header's bytes, and the ciphertext
+fn split(bytes: &[u8]) -> SerenQaResult<(EncryptedHeader, &[u8], &[u8])> {
+    let rest = bytes.strip_prefix(ENCRYPTED_MAGIC.as_slice()).ok_or_else(|| failed("missing magic header"))?;
+    let (&version, rest) = rest.split_first().ok_or_else(|| failed("truncated header"))?;
+    if version != ENCRYPTED_FORMAT_VERSION {
+        return Err(failed(format!("unsupported format version {}", version)));
+    }
+    let (length, rest) = rest.split_first_chunk::<4>().ok_or_else(|| failed("truncated header"))?;
+    let length = u32::from_le_bytes(*length) as usize;
+    if rest.len() < length {
+        return Err(failed("truncated header"));
+    }
+    let (header_json, ciphertext) = rest.split_at(length);
+    Ok((serde_json::from_slice(header_json)?, header_json, ciphertext))
+}
+
+/// Header of an encrypted trace, without decrypting it
+pub fn read_header(bytes: &[u8]) -> SerenQaResult<EncryptedHeader> {
+    Ok(split(bytes)?.0)
+}
+
+/// Decrypt a trace and check it against the header's provenance hash
+pub fn decrypt_trace(bytes: &[u8], keyring: &Keyring) -> SerenQaResult<SerendipityTrace> {
+    let (header, header_json, ciphertext) = split(bytes)?;
+    let key = keyring.key(&header.contributor_id, header.key_version).ok_or_else(|| {
+        failed(format!("no key version {} for contributor {}", header.key_version, header.contributor_id))
+    })?;
+    let nonce = hex_decode(&header.nonce)
+        .filter(|nonce| nonce.len() == 12)
+        .ok_or_else(|| failed("malformed nonce"))?;
+    let plaintext = key
+        .cipher()
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: ciphertext, aad: header_json })
+        .map_err(|_| failed(format!("trace {} failed authentication", header.trace_id)))?;
+    let trace = SerendipityTrace::from_json_any_version(std::str::from_utf8(&plaintext).map_err(|_| failed("plaintext is not UTF-8"))?)?;
+    if trace.trace_id != header.trace_id || !trace.verify_provenance_hash(&header.provenance_hash) {
+        return Err(failed(format!("trace {} does not match its provenance hash", header.trace_id)));
+    }
+    Ok(trace)
+}
+
+/// Store keeping one encrypted `<trace_id>.serenc` file per trace in a directory
+#[derive(Debug)]
+pub struct EncryptedFsTraceStore {
+    dir: PathBuf,
+    keyring: RwLock<Keyring>,
+}
+
+impl EncryptedFsTraceStore {
+    /// Open (and create if needed) a store directory encrypted with `keyring`
+    pub fn open(dir: &Path, keyring: Keyring) -> SerenQaResult<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self { dir: dir.to_path_buf(), keyring: RwLock::new(keyring) })
+    }
+
+    /// Directory backing the store
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Rotate `contributor_id` to a new key; returns its version. Existing
+    /// files stay readable with the old key until `reencrypt`.
+    pub fn add_key(&self, contributor_id: &str, key: ContributorKey) -> u32 {
+        self.keyring.write().unwrap_or_else(|e| e.into_inner()).add_key(contributor_id, key)
+    }
+
+    /// Drop an old key of `contributor_id` (see `Keyring::retire`)
+    pub fn retire_key(&self, contributor_id: &str, version: u32) -> bool {
+        self.keyring.write().unwrap_or_else(|e| e.into_inner()).retire(contributor_id, version)
+    }
+
+    fn path_for(&self, trace_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", file_stem(trace_id), ENCRYPTED_EXTENSION))
+    }
+
+    /// Encrypted files in the directory
+    fn files(&self) -> SerenQaResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some(ENCRYPTED_EXTENSION) {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn write(&self, path: &Path, bytes: &[u8]) -> SerenQaResult<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Header of a stored trace, without decrypting it
+    pub fn header(&self, trace_id: &str) -> SerenQaResult<Option<EncryptedHeader>> {
+        let path = self.path_for(trace_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(read_header(&std::fs::read(path)?)?))
+    }
+
+    /// Re-encrypt every trace not under its contributor's current key;
+    /// returns the number rewritten
+    pub fn reencrypt(&self) -> SerenQaResult<usize> {
+        let keyring = self.keyring.read().unwrap_or_else(|e| e.into_inner());
+        let mut rewritten = 0;
+        for path in self.files()? {
+            let bytes = std::fs::read(&path)?;
+            let header = read_header(&bytes)?;
+            let current = keyring.current(&header.contributor_id).map(|(version, _)| version);
+            if current != Some(header.key_version) {
+                let trace = decrypt_trace(&bytes, &keyring)?;
+                self.write(&path, &encrypt_trace(&trace, &keyring)?)?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+}
+
+impl TraceStore for EncryptedFsTraceStore {
+    fn save(&self, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        let bytes = encrypt_trace(trace, &self.keyring.read().unwrap_or_else(|e| e.into_inner()))?;
+        self.write(&self.path_for(&trace.trace_id), &bytes)
+    }
+
+    fn load(&self, trace_id: &str) -> SerenQaResult<Option<SerendipityTrace>> {
+        let path = self.path_for(trace_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = std::fs::read(path)?;
+        Ok(Some(decrypt_trace(&bytes, &self.keyring.read().unwrap_or_else(|e| e.into_inner()))?))
+    }
+
+    fn delete(&self, trace_id: &str) -> SerenQaResult<bool> {
+        match std::fs::remove_file(self.path_for(trace_id)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Listing reads the cleartext headers only
+    fn list(&self) -> SerenQaResult<Vec<String>> {
+        let mut ids = Vec::new();
+        for path in self.files()? {
+            ids.push(read_header(&std::fs::read(path)?)?.trace_id);
+        }
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+
+    fn trace(contributor_id: &str) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor_id, "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "unpublished", "batik route", "id", 0.9, 0.8).unwrap();
+        trace
+    }
+
+    fn store(name: &str) -> EncryptedFsTraceStore {
+        let dir = std::env::temp_dir().join(format!("seren_encrypted_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut keyring = Keyring::new();
+        keyring.add_key("sari", ContributorKey::generate());
+        EncryptedFsTraceStore::open(&dir, keyring).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_keeps_plaintext_off_disk() {
+        let store = store("round_trip");
+        let trace = trace("sari");
+        store.save(&trace).unwrap();
+
+        let bytes = std::fs::read(store.path_for(&trace.trace_id)).unwrap();
+        assert!(!bytes.windows(b"batik route".len()).any(|w| w == b"batik route"));
+        let header = store.header(&trace.trace_id).unwrap().unwrap();
+        assert_eq!(header.provenance_hash, trace.compute_provenance_hash());
+        assert_eq!(store.list().unwrap(), vec![trace.trace_id.clone()]);
+        assert_eq!(store.load(&trace.trace_id).unwrap().unwrap().compute_provenance_hash(), header.provenance_hash);
+
+        // No key, no storage; a flipped byte fails authentication
+        assert!(matches!(store.save(&self::trace("budi")), Err(SerenQaError::Encryption(_))));
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt_trace(&tampered, &store.keyring.read().unwrap()).is_err());
+        std::fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    /// Re-frame `bytes` with an edited header, keeping the ciphertext
+    fn with_header(bytes: &[u8], edit: impl FnOnce(&mut EncryptedHeader)) -> Vec<u8> {
+        let (mut header, _, ciphertext) = split(bytes).unwrap();
+        edit(&mut header);
+        let header_json = serde_json::to_vec(&header).unwrap();
+        let mut framed = ENCRYPTED_MAGIC.to_vec();
+        framed.push(ENCRYPTED_FORMAT_VERSION);
+        framed.extend_from_slice(&(header_json.len() as u32).to_le_bytes());
+        framed.extend_from_slice(&header_json);
+        framed.extend_from_slice(ciphertext);
+        framed
+    }
+
+    fn encryption_error(result: SerenQaResult<SerendipityTrace>) -> String {
+        match result {
+            Err(SerenQaError::Encryption(message)) => message,
+            other => panic!("expected an encryption error, got {:?}", other.map(|t| t.trace_id)),
+        }
+    }
+
+    #[test]
+    fn test_decryption_errors() {
+        let trace = trace("sari");
+        let mut keyring = Keyring::new();
+        keyring.add_key("sari", ContributorKey::generate());
+        let bytes = encrypt_trace(&trace, &keyring).unwrap();
+        assert_eq!(decrypt_trace(&bytes, &keyring).unwrap().trace_id, trace.trace_id);
+
+        // Another key under the same version fails authentication
+        let mut wrong = Keyring::new();
+        wrong.add_key("sari", ContributorKey::generate());
+        assert!(encryption_error(decrypt_trace(&bytes, &wrong)).ends_with("failed authentication"));
+        assert!(encryption_error(decrypt_trace(&bytes, &Keyring::new())).starts_with("no key version 1 for contributor sari"));
+
+        // The nonce is authenticated with the header, so changing it fails too
+        let tampered = with_header(&bytes, |header| header.nonce = to_hex(&[0u8; 12]));
+        assert!(encryption_error(decrypt_trace(&tampered, &keyring)).ends_with("failed authentication"));
+        let short = with_header(&bytes, |header| header.nonce.truncate(8));
+        assert_eq!(encryption_error(decrypt_trace(&short, &keyring)), "malformed nonce");
+        let relabeled = with_header(&bytes, |header| header.provenance_hash = "sha256:0".to_string());
+        assert!(encryption_error(decrypt_trace(&relabeled, &keyring)).ends_with("failed authentication"));
+
+        // Damaged framing is reported before any key is looked up
+        assert_eq!(encryption_error(decrypt_trace(b"not encrypted", &keyring)), "missing magic header");
+        assert_eq!(encryption_error(decrypt_trace(&bytes[..ENCRYPTED_MAGIC.len() + 3], &keyring)), "truncated header");
+        let mut future = bytes.clone();
+        future[ENCRYPTED_MAGIC.len()] = ENCRYPTED_FORMAT_VERSION + 1;
+        assert!(encryption_error(decrypt_trace(&future, &keyring)).starts_with("unsupported format version"));
+        assert!(matches!(read_header(&bytes[..ENCRYPTED_MAGIC.len() + 15]), Err(SerenQaError::Encryption(ref m)) if m == "truncated header"));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let store = store("rotation");
+        let trace = trace("sari");
+        store.save(&trace).unwrap();
+
+        assert_eq!(store.add_key("sari", ContributorKey::generate()), 2);
+        assert!(!store.retire_key("sari", 2));
+        // Still readable with the old key until re-encrypted
+        assert!(store.load(&trace.trace_id).unwrap().is_some());
+        assert_eq!(store.reencrypt().unwrap(), 1);
+        assert_eq!(store.header(&trace.trace_id).unwrap().unwrap().key_version, 2);
+        assert_eq!(store.reencrypt().unwrap(), 0);
+
+        assert!(store.retire_key("sari", 1));
+        assert_eq!(store.load(&trace.trace_id).unwrap().unwrap().trace_id, trace.trace_id);
+        std::fs::remove_dir_all(store.dir()).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+// -*- coding: utf-8 -*-
+//! Crate-Wide Error Type
+//!
+//! Out-of-range scores, empty traces, unknown languages, and empty folds are
+//! reported as `SerenQaError` instead of panicking or silently producing
+//! nonsense scores.
+
+use thiserror::Error;
+use crate::serendipity_trace::EventValidationError;
+use crate::redaction::RedactionError;
+use crate::trace_merge::MergeConflict;
+use crate::circuit::CircuitParseError;
+use crate::stage_policy::StageViolation;
+use crate::quota::QuotaExceeded;
+use crate::acl::Permission;
+
+/// Result alias used across the crate
+pub type SerenQaResult<T> = Result<T, SerenQaError>;
+
+/// Errors raised by SerenQA APIs
+#[derive(Debug, Error)]
+pub enum SerenQaError {
+    /// A score field outside [0, 1]
+    #[error("{field} {value} is outside [0, 1]")]
+    ScoreOutOfRange { field: &'static str, value: f64 },
+
+    /// Language tag that is not well-formed BCP-47
+    #[error("unknown language tag: {0:?}")]
+    UnknownLanguage(String),
+
+    /// Operation requires at least one event
+    #[error("trace {0} has no events")]
+    EmptyTrace(String),
+
+    /// No event with this ID in the trace
+    #[error("unknown event: {0}")]
+    UnknownEvent(String),
+
+    /// Event rejected for another reason (e.g., empty metadata key)
+    #[error("invalid event: {0}")]
+    InvalidEvent(EventValidationError),
+
+    /// Event vetoed by a middleware interceptor
+    #[error("event vetoed by {middleware}: {reason}")]
+    Vetoed { middleware: String, reason: String },
+
+    /// Event stage not listed in the trace's stage taxonomy
+    #[error("stage {0:?} is not in the trace's stage taxonomy")]
+    UnknownStage(String),
+
+    /// Stage taxonomy is empty or lists a stage twice
+    #[error("invalid stage taxonomy: {0}")]
+    InvalidStageTaxonomy(String),
+
+    /// Redaction rule without a name, fields, or keywords, or with a bad pattern
+    #[error("invalid redaction rule: {0}")]
+    InvalidRedactionRule(String),
+
+    /// Event stage breaks the trace's strict stage policy
+    #[error(transparent)]
+    StagePolicy(#[from] StageViolation),
+
+    /// Redaction failed
+    #[error(transparent)]
+    Redaction(#[from] RedactionError),
+
+    /// Quantum circuit failed validation
+    #[error(transparent)]
+    Circuit(#[from] CircuitParseError),
+
+    /// Contributor has not opted in to a public profile
+    #[error("contributor {0} has not consented to a public profile")]
+    ConsentRequired(String),
+
+    /// Shared recorder's writer thread is no longer running
+    #[error("trace recorder has stopped")]
+    RecorderStopped,
+
+    /// Trace resembles an earlier leaderboard submission
+    #[error("trace {trace_id} duplicates submission {matched_trace_id} (similarity {similarity:.2})")]
+    DuplicateSubmission { trace_id: String, matched_trace_id: String, similarity: f64 },
+
+    /// Scoring weights or normalizers rejected by validation
+    #[error("invalid scoring config: {0}")]
+    InvalidScoringConfig(String),
+
+    /// Submission exceeds the contributor's quota
+    #[error(transparent)]
+    Quota(#[from] QuotaExceeded),
+
+    /// Principal lacks the permission on the trace
+    #[error("{principal:?} may not {permission} trace {trace_id}")]
+    AccessDenied { principal: String, permission: Permission, trace_id: String },
+
+    /// Review verdict given before the trace was submitted for review
+    #[error("trace {0} has not been submitted for review")]
+    NotSubmittedForReview(String),
+
+    /// No leaderboard season with this name
+    #[error("unknown season: {0}")]
+    UnknownSeason(String),
+
+    /// Season overlaps another, is empty, or is already final
+    #[error("invalid season: {0}")]
+    InvalidSeason(String),
+
+    /// Trace does not link to the one before it in a hash chain
+    #[error("hash chain broken at trace {trace_id} (position {index})")]
+    BrokenChain { index: usize, trace_id: String },
+
+    /// Outcome predates its trace or has a blank evidence link
+    #[error("invalid outcome: {0}")]
+    InvalidOutcome(String),
+
+    /// Text is neither a DOI nor an arXiv identifier
+    #[error("invalid external identifier: {0:?}")]
+    InvalidIdentifier(String),
+
+    /// Audit log entry was edited, reordered, or dropped
+    #[error("audit log broken at entry {sequence}")]
+    BrokenAuditLog { sequence: u64 },
+
+    /// Traces could not be merged
+    #[error(transparent)]
+    Merge(#[from] MergeConflict),
+
+    /// Reading or writing trace data failed
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// Snapshot written by a newer schema than this build understands
+    #[error("schema version {found} is newer than supported version {supported}")]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+
+    /// Binary trace is malformed or does not match its embedded provenance hash
+    #[error("invalid binary trace: {0}")]
+    InvalidBinaryTrace(String),
+
+    /// Attachment reference is malformed, or its bytes are missing or do not match it
+    #[error("invalid attachment: {0}")]
+    InvalidAttachment(String),
+
+    /// Bundle is malformed or its parts do not match each other
+    #[error("invalid bundle: {0}")]
+    InvalidBundle(String),
+
+    /// Encrypted trace could not be encrypted, authenticated, or decrypted
+    #[error("encryption failed: {0}")]
+    Encryption(String),
+
+    /// Setting a streaming trace cannot honour without keeping every event
+    #[error("not supported by streaming traces: {0}")]
+    StreamingUnsupported(String),
+
+    /// Translation backend failed or returned an unusable response
+    #[error("translation backend failed: {0}")]
+    Translation(String),
+
+    /// Trace data could not be (de)serialized
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// Arrow record batch could not be built
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    Arrow(#[from] arrow::error::ArrowError),
+
+    /// Parquet file could not be written
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// TOML configuration could not be parsed
+    #[cfg(feature = "toml")]
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+
+    /// OpenTelemetry spans could not be exported
+    #[cfg(feature = "otel")]
+    #[error(transparent)]
+    Otel(#[from] opentelemetry::trace::TraceError),
+
+    /// Full-text index could not be opened, written, or searched
+    #[cfg(feature = "search")]
+    #[error(transparent)]
+    Search(#[from] tantivy::TantivyError),
+
+    /// Full-text search query could not be parsed
+    #[cfg(feature = "search")]
+    #[error(transparent)]
+    SearchQuery(#[from] tantivy::query::QueryParserError),
+}
+
+impl SerenQaError {
+    /// Check a score lies in [0, 1]
+    pub fn check_unit_range(field: &'static str, value: f64) -> SerenQaResult<f64> {
+        if (0.0..=1.0).contains(&value) {
+            Ok(value)
+        } else {
+            Err(SerenQaError::ScoreOutOfRange { field, value })
+        }
+    }
+}
+
+impl From<EventValidationError> for SerenQaError {
+    fn from(error: EventValidationError) -> Self {
+        match error {
+            EventValidationError::SerendipityOutOfRange(value) => {
+                SerenQaError::ScoreOutOfRange { field: "serendipity_score", value }
+            }
+            EventValidationError::ConfidenceOutOfRange(value) => {
+                SerenQaError::ScoreOutOfRange { field: "confidence", value }
+            }
+            EventValidationError::InvalidLanguageTag(tag) => SerenQaError::UnknownLanguage(tag),
+            EventValidationError::EmptyMetadataKey
+            | EventValidationError::InvalidKnowledgeSource(_)
+            | EventValidationError::InvalidAttachment(_)
+            | EventValidationError::InvalidBenchmark(_) => {
+                SerenQaError::InvalidEvent(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_errors_map_to_variants() {
+        let error: SerenQaError = EventValidationError::ConfidenceOutOfRange(1.2).into();
+        assert!(matches!(error, SerenQaError::ScoreOutOfRange { field: "confidence", .. }));
+        assert_eq!(error.to_string(), "confidence 1.2 is outside [0, 1]");
+
+        let error: SerenQaError = EventValidationError::InvalidLanguageTag("english!".to_string()).into();
+        assert!(matches!(error, SerenQaError::UnknownLanguage(_)));
+    }
+
+    #[test]
+    fn test_check_unit_range() {
+        assert_eq!(SerenQaError::check_unit_range("alignment_score", 0.5).unwrap(), 0.5);
+        assert!(SerenQaError::check_unit_range("alignment_score", f64::NAN).is_err());
+    }
+}
@@ -5,8 +5,9 @@
 //! discovery of Journavx through multilingual reasoning.
 
 use level5_ai_scientist::Journavx_Discovery::demo_journavx_complete_analysis;
+use level5_ai_scientist::error::SerenQaError;
 
-fn main() {
+fn main() -> Result<(), SerenQaError> {
     println!("\n");
     println!("╔══════════════════════════════════════════════════════════════════╗");
     println!("║                                                                  ║");
@@ -19,7 +20,7 @@ fn main() {
     println!("\n");
     
     // Run complete Journavx discovery analysis
-    demo_journavx_complete_analysis();
+    demo_journavx_complete_analysis(&mut std::io::stdout().lock())?;
     
     println!("\n");
     println!("╔══════════════════════════════════════════════════════════════════╗");
@@ -36,4 +37,5 @@ fn main() {
     println!("  ✓ Contributor statistics and leaderboard");
     println!("  ✓ Cultural context preservation");
     println!("\n");
+    Ok(())
 }
@@ -0,0 +1,134 @@
+// -*- coding: utf-8 -*-
+//! Slide Deck Export (reveal.js)
+//!
+//! Renders a trace as a self-contained reveal.js HTML deck for presenting a
+//! discovery journey at lab meetings: a title slide, one slide per stage
+//! (in order of first appearance) listing its events with scores and
+//! language annotations, and a closing summary of key discoveries.
+
+use std::fmt::Write;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, key_discovery};
+use crate::graph_export::{stage_color, escape_xml};
+
+/// reveal.js distribution loaded by the generated deck
+pub const REVEAL_JS_CDN: &str = "https://cdn.jsdelivr.net/npm/reveal.js@5";
+
+/// Stages of `trace` in order of first appearance
+fn stages_in_order(trace: &SerendipityTrace) -> Vec<SerendipityStage> {
+    let mut stages: Vec<SerendipityStage> = Vec::new();
+    for event in &trace.events {
+        if !stages.contains(&event.stage) {
+            stages.push(event.stage.clone());
+        }
+    }
+    stages
+}
+
+/// Render `trace` as a reveal.js slide deck (complete HTML document)
+pub fn to_reveal_js(trace: &SerendipityTrace) -> String {
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, "<html>\n<head>\n<meta charset=\"utf-8\">");
+    let _ = writeln!(html, "<title>{}</title>", escape_xml(&trace.discovery_name));
+    let _ = writeln!(html, "<link rel=\"stylesheet\" href=\"{}/dist/reveal.css\">", REVEAL_JS_CDN);
+    let _ = writeln!(html, "<link rel=\"stylesheet\" href=\"{}/dist/theme/white.css\">", REVEAL_JS_CDN);
+    let _ = writeln!(html, "<style>.lang {{ font-family: monospace; padding: 0 0.3em; border: 1px solid #999; border-radius: 4px; }}</style>");
+    let _ = writeln!(html, "</head>\n<body>\n<div class=\"reveal\">\n<div class=\"slides\">");
+
+    // Title slide
+    let _ = writeln!(html, "<section>");
+    let _ = writeln!(html, "<h1>{}</h1>", escape_xml(&trace.discovery_name));
+    let _ = writeln!(
+        html,
+        "<p>{} &middot; {}</p>",
+        escape_xml(&trace.contributor_id),
+        escape_xml(&trace.backend)
+    );
+    let _ = writeln!(
+        html,
+        "<p>Overall serendipity {:.3} &middot; {} events &middot; languages: {}</p>",
+        trace.overall_serendipity,
+        trace.events.len(),
+        escape_xml(&trace.languages.join(", "))
+    );
+    let _ = writeln!(html, "</section>");
+
+    // One slide per stage
+    let threshold = trace.key_discovery_threshold();
+    for stage in stages_in_order(trace) {
+        let _ = writeln!(html, "<section data-background-color=\"{}\">", stage_color(&stage));
+        let _ = writeln!(html, "<h2>{}</h2>\n<ul>", stage.name());
+        for event in trace.events.iter().filter(|e| e.stage == stage) {
+            let fragment = if key_discovery(event, threshold).is_some() { " class=\"fragment highlight-red\"" } else { "" };
+            let _ = writeln!(
+                html,
+                "<li{}><span class=\"lang\">{}</span> <strong>{:?}</strong>: {} <small>(serendipity {:.3}, confidence {:.3})</small></li>",
+                fragment,
+                escape_xml(&event.language),
+                event.agent,
+                escape_xml(&event.output),
+                event.serendipity_score,
+                event.confidence
+            );
+        }
+        let _ = writeln!(html, "</ul>");
+
+        let shifts: Vec<String> = trace.transitions
+            .iter()
+            .filter(|tr| trace.events.iter().any(|e| e.event_id == tr.to_event && e.stage == stage))
+            .filter_map(|tr| tr.language_shift.as_ref())
+            .map(|(from, to)| format!("{} &rarr; {}", escape_xml(from), escape_xml(to)))
+            .collect();
+        if !shifts.is_empty() {
+            let _ = writeln!(html, "<p><small>Language shifts: {}</small></p>", shifts.join(", "));
+        }
+        let _ = writeln!(html, "</section>");
+    }
+
+    // Summary slide
+    let key_discoveries: Vec<String> = trace.events.iter().filter_map(|e| key_discovery(e, threshold)).collect();
+    if !key_discoveries.is_empty() {
+        let _ = writeln!(html, "<section>\n<h2>Key Discoveries</h2>\n<ul>");
+        for discovery in &key_discoveries {
+            let _ = writeln!(html, "<li>{}</li>", escape_xml(discovery));
+        }
+        let _ = writeln!(html, "</ul>\n</section>");
+    }
+
+    let _ = writeln!(html, "</div>\n</div>");
+    let _ = writeln!(html, "<script src=\"{}/dist/reveal.js\"></script>", REVEAL_JS_CDN);
+    let _ = writeln!(html, "<script>Reveal.initialize({{ hash: true }});</script>");
+    let _ = writeln!(html, "</body>\n</html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityAgent;
+
+    #[test]
+    fn test_one_slide_per_stage() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx <v2>");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "routes", "en", 0.4, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "wayfinding & stars", "id", 0.9, 0.8).unwrap();
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "more routes", "en", 0.3, 0.8).unwrap();
+
+        let deck = to_reveal_js(&trace);
+        // title + 2 stages + key discoveries
+        assert_eq!(deck.matches("<section").count(), 4);
+        assert!(deck.contains("<title>Journavx &lt;v2&gt;</title>"));
+        assert!(deck.contains("wayfinding &amp; stars"));
+        assert!(deck.contains("Language shifts: en &rarr; id"));
+        assert!(deck.contains("class=\"fragment highlight-red\""));
+        assert!(deck.find("<h2>Exploration</h2>").unwrap() < deck.find("<h2>UnexpectedConnection</h2>").unwrap());
+    }
+
+    #[test]
+    fn test_empty_trace_has_title_only() {
+        let trace = SerendipityTrace::new("researcher1", "backend", "Nothing yet");
+        let deck = to_reveal_js(&trace);
+        assert_eq!(deck.matches("<section").count(), 1);
+        assert!(deck.contains("Reveal.initialize"));
+    }
+}
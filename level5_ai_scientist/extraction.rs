@@ -0,0 +1,427 @@
+// -*- coding: utf-8 -*-
+//! Named-Entity and Domain-Term Extraction
+//!
+//! Rule-based extraction from event text, with no model to ship: named
+//! entities are quoted phrases ('Journavx'), acronyms (QAOA), and runs of
+//! capitalized words other than a lone sentence-opening word; domain terms are
+//! phrases of a `TermLexicon`, by default the names and aliases of the
+//! built-in domain taxonomy, reported under the domain's name. Text in
+//! scripts without letter case (Han, Javanese) only yields quoted phrases and
+//! lexicon terms.
+//!
+//! The pipeline fills each event's `LanguageMetadata::domain_terms` from its
+//! output, and `fold_memory` groups key discoveries whose outputs share an
+//! entity into `entity_clusters`.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use crate::domain_taxonomy::{DomainNode, DomainTaxonomy};
+use crate::serendipity_trace::SerendipityEvent;
+
+/// Lowercase words allowed inside a capitalized run ("Sultanate of Mataram")
+const NAME_CONNECTORS: [&str; 8] = ["of", "de", "van", "von", "la", "del", "bin", "binti"];
+
+/// Longest quoted phrase, in words, taken as an entity
+const MAX_QUOTED_WORDS: usize = 8;
+
+/// How an entity was recognized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    /// Phrase in quotes
+    Quoted,
+    /// All-capital abbreviation
+    Acronym,
+    /// Run of capitalized words
+    Name,
+}
+
+/// Named entity found in a text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entity {
+    pub text: String,
+    pub kind: EntityKind,
+}
+
+/// Entities and domain terms of a text
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Extraction {
+    pub entities: Vec<Entity>,
+    pub domain_terms: Vec<String>,
+}
+
+/// Key discoveries linked by shared entities
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntityCluster {
+    /// Entities appearing in at least two of the discoveries
+    pub entities: Vec<String>,
+    /// Indices into the fold's `key_discoveries`
+    pub discoveries: Vec<usize>,
+}
+
+/// Piece of text seen by the entity rules
+enum Token<'a> {
+    Word(&'a str),
+    Quoted(&'a str),
+    /// Punctuation; `sentence_end` if the next word opens a sentence
+    Break { sentence_end: bool },
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric()
+}
+
+/// Closing quote for an opening one
+fn closing_quote(c: char) -> Option<char> {
+    match c {
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        '“' => Some('”'),
+        '‘' => Some('’'),
+        '«' => Some('»'),
+        '「' => Some('」'),
+        _ => None,
+    }
+}
+
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let end_of = |i: usize| chars.get(i).map_or(text.len(), |&(offset, _)| offset);
+    let word_char_at = |i: usize| chars.get(i).is_some_and(|&(_, c)| is_word_char(c));
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        if is_word_char(c) {
+            let mut j = i + 1;
+            // Apostrophes and hyphens between letters stay in the word
+            let joiner_at = |j: usize| chars.get(j).is_some_and(|&(_, c)| matches!(c, '\'' | '’' | '-'));
+            while word_char_at(j) || (joiner_at(j) && word_char_at(j + 1)) {
+                j += 1;
+            }
+            tokens.push(Token::Word(&text[chars[i].0..end_of(j)]));
+            i = j;
+            continue;
+        }
+        let opens_quote = closing_quote(c).filter(|_| i == 0 || !word_char_at(i - 1));
+        let close = opens_quote.and_then(|close| {
+            (i + 1..chars.len()).find(|&k| chars[k].1 == close && word_char_at(k - 1) && !word_char_at(k + 1))
+        });
+        if let Some(k) = close {
+            tokens.push(Token::Quoted(text[end_of(i + 1)..chars[k].0].trim()));
+            i = k + 1;
+            continue;
+        }
+        if !c.is_whitespace() {
+            tokens.push(Token::Break { sentence_end: matches!(c, '.' | '!' | '?' | ':' | ';' | '。' | '！' | '？') });
+        }
+        i += 1;
+    }
+    tokens
+}
+
+fn is_acronym(word: &str) -> bool {
+    word.chars().count() >= 2
+        && word.chars().all(|c| c.is_uppercase() || c.is_ascii_digit())
+        && word.chars().filter(|c| c.is_uppercase()).count() >= 2
+}
+
+fn is_capitalized(word: &str) -> bool {
+    word.chars().next().is_some_and(char::is_uppercase)
+}
+
+/// Emit a run of capitalized words as a name, unless it is a lone word
+/// opening a sentence; trailing connectors are dropped
+fn close_run(run: &mut Vec<&str>, opens_sentence: bool, push: &mut impl FnMut(String, EntityKind)) {
+    while run.last().is_some_and(|word| !is_capitalized(word)) {
+        run.pop();
+    }
+    if run.len() > 1 || (run.len() == 1 && !opens_sentence) {
+        push(run.join(" "), EntityKind::Name);
+    }
+    run.clear();
+}
+
+/// Named entities of `text`, each once (compared case-insensitively), in order of appearance
+pub fn extract_entities(text: &str) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    let mut seen = HashSet::new();
+    let mut push = |text: String, kind: EntityKind| {
+        if seen.insert(text.to_lowercase()) {
+            entities.push(Entity { text, kind });
+        }
+    };
+
+    // Open run of capitalized words, and whether it opens a sentence
+    let mut run: Vec<&str> = Vec::new();
+    let mut run_opens_sentence = false;
+    let mut sentence_start = true;
+    for token in tokens(text) {
+        let next_sentence_start = match token {
+            Token::Break { sentence_end } => sentence_end || sentence_start,
+            _ => false,
+        };
+        match token {
+            Token::Word(word) if is_acronym(word) => {
+                close_run(&mut run, run_opens_sentence, &mut push);
+                push(word.to_string(), EntityKind::Acronym);
+            }
+            Token::Word(word) if is_capitalized(word) => {
+                if run.is_empty() {
+                    run_opens_sentence = sentence_start;
+                }
+                run.push(word);
+            }
+            Token::Word(word) if !run.is_empty() && NAME_CONNECTORS.contains(&word) => run.push(word),
+            Token::Word(_) | Token::Break { .. } => close_run(&mut run, run_opens_sentence, &mut push),
+            Token::Quoted(phrase) => {
+                close_run(&mut run, run_opens_sentence, &mut push);
+                if (1..=MAX_QUOTED_WORDS).contains(&phrase.split_whitespace().count()) {
+                    push(phrase.to_string(), EntityKind::Quoted);
+                }
+            }
+        }
+        sentence_start = next_sentence_start;
+    }
+    close_run(&mut run, run_opens_sentence, &mut push);
+    entities
+}
+
+/// Word as compared against lexicon phrases: lowercased, plural "s" dropped
+fn term_word(word: &str) -> String {
+    let word = word.to_lowercase();
+    match word.strip_suffix('s') {
+        Some(stem) if stem.chars().count() >= 3 && !stem.ends_with('s') => stem.to_string(),
+        _ => word,
+    }
+}
+
+fn term_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !is_word_char(c))
+        .filter(|word| !word.is_empty())
+        .map(term_word)
+        .collect()
+}
+
+/// Phrases recognized as domain terms, each reported under a term name
+#[derive(Debug, Clone, Default)]
+pub struct TermLexicon {
+    /// First phrase word -> (phrase words, term), longest phrase first
+    by_first_word: HashMap<String, Vec<(Vec<String>, String)>>,
+    len: usize,
+}
+
+impl TermLexicon {
+    /// Lexicon with no phrases
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Domain names and aliases of `taxonomy`, each reported as the domain's name
+    pub fn from_taxonomy(taxonomy: &DomainTaxonomy) -> Self {
+        fn add(lexicon: &mut TermLexicon, node: &DomainNode) {
+            lexicon.insert(&node.name, &node.name);
+            for alias in &node.aliases {
+                lexicon.insert(alias, &node.name);
+            }
+            for child in &node.children {
+                add(lexicon, child);
+            }
+        }
+        let mut lexicon = Self::new();
+        for root in taxonomy.roots() {
+            add(&mut lexicon, root);
+        }
+        lexicon
+    }
+
+    /// Lexicon of the built-in domain taxonomy
+    pub fn builtin() -> Self {
+        Self::from_taxonomy(&DomainTaxonomy::builtin())
+    }
+
+    /// Shared built-in lexicon used by the pipeline
+    pub fn global() -> &'static TermLexicon {
+        static GLOBAL: OnceLock<TermLexicon> = OnceLock::new();
+        GLOBAL.get_or_init(Self::builtin)
+    }
+
+    /// Recognize `phrase` as the domain term `term`
+    pub fn term(mut self, phrase: &str, term: &str) -> Self {
+        self.insert(phrase, term);
+        self
+    }
+
+    fn insert(&mut self, phrase: &str, term: &str) {
+        let words = term_words(phrase);
+        let Some(first) = words.first().cloned() else {
+            return;
+        };
+        let phrases = self.by_first_word.entry(first).or_default();
+        if let Some(existing) = phrases.iter_mut().find(|(existing, _)| *existing == words) {
+            existing.1 = term.to_string();
+            return;
+        }
+        let at = phrases.partition_point(|(existing, _)| existing.len() >= words.len());
+        phrases.insert(at, (words, term.to_string()));
+        self.len += 1;
+    }
+
+    /// Number of phrases
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the lexicon has no phrases
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Domain terms of `text`, each once, in order of appearance; overlapping
+    /// phrases resolve to the longest
+    pub fn find(&self, text: &str) -> Vec<String> {
+        let words = term_words(text);
+        let mut terms: Vec<String> = Vec::new();
+        let mut i = 0;
+        while i < words.len() {
+            let matched = self.by_first_word
+                .get(&words[i])
+                .and_then(|phrases| phrases.iter().find(|(phrase, _)| words[i..].starts_with(phrase)));
+            match matched {
+                Some((phrase, term)) => {
+                    if !terms.contains(term) {
+                        terms.push(term.clone());
+                    }
+                    i += phrase.len();
+                }
+                None => i += 1,
+            }
+        }
+        terms
+    }
+}
+
+/// Entities and `lexicon` domain terms of `text`
+pub fn extract(text: &str, lexicon: &TermLexicon) -> Extraction {
+    Extraction { entities: extract_entities(text), domain_terms: lexicon.find(text) }
+}
+
+impl SerendipityEvent {
+    /// Entities and built-in domain terms of the event's input and output
+    pub fn extraction(&self) -> Extraction {
+        let mut extraction = extract(&self.input, TermLexicon::global());
+        let output = extract(&self.output, TermLexicon::global());
+        for entity in output.entities {
+            if !extraction.entities.iter().any(|e| e.text.to_lowercase() == entity.text.to_lowercase()) {
+                extraction.entities.push(entity);
+            }
+        }
+        for term in output.domain_terms {
+            if !extraction.domain_terms.contains(&term) {
+                extraction.domain_terms.push(term);
+            }
+        }
+        extraction
+    }
+}
+
+/// Entities a key discovery is clustered by: those of the event's output
+/// (inputs tend to repeat the task). Redacted outputs have none.
+pub(crate) fn discovery_entities(event: &SerendipityEvent) -> Vec<String> {
+    if event.is_redacted() {
+        return Vec::new();
+    }
+    extract_entities(&event.output).into_iter().map(|entity| entity.text).collect()
+}
+
+/// Group discoveries (by their entities, in fold order) into clusters of
+/// two or more connected through shared entities
+pub(crate) fn entity_clusters(discovery_entities: &[Vec<String>]) -> Vec<EntityCluster> {
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let mut parents: Vec<usize> = (0..discovery_entities.len()).collect();
+    // Lowercased entity -> (spelling first seen, first discovery, discoveries)
+    let mut by_entity: HashMap<String, (String, usize, usize)> = HashMap::new();
+    for (index, entities) in discovery_entities.iter().enumerate() {
+        for entity in entities {
+            let (_, first, count) = by_entity.entry(entity.to_lowercase()).or_insert((entity.clone(), index, 0));
+            *count += 1;
+            let (a, b) = (root(&mut parents, *first), root(&mut parents, index));
+            parents[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut clusters: Vec<EntityCluster> = Vec::new();
+    let mut cluster_of_root: HashMap<usize, usize> = HashMap::new();
+    for index in 0..discovery_entities.len() {
+        let group = root(&mut parents, index);
+        let cluster = *cluster_of_root.entry(group).or_insert_with(|| {
+            clusters.push(EntityCluster { entities: Vec::new(), discoveries: Vec::new() });
+            clusters.len() - 1
+        });
+        clusters[cluster].discoveries.push(index);
+    }
+    clusters.retain(|cluster| cluster.discoveries.len() > 1);
+    for cluster in &mut clusters {
+        let shared: BTreeSet<&String> = cluster.discoveries
+            .iter()
+            .flat_map(|&index| &discovery_entities[index])
+            .filter_map(|entity| by_entity.get(&entity.to_lowercase()))
+            .filter(|(_, _, count)| *count > 1)
+            .map(|(spelling, _, _)| spelling)
+            .collect();
+        cluster.entities = shared.into_iter().cloned().collect();
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entities_and_domain_terms() {
+        let text = "Konfirmasi: Prinsip 'ngelmu titen' dalam navigasi Jawa cocok dengan QAOA dan Quantum Walks";
+        let entities = extract_entities(text);
+        let found: Vec<(&str, EntityKind)> = entities.iter().map(|e| (e.text.as_str(), e.kind)).collect();
+        assert_eq!(found, vec![
+            ("ngelmu titen", EntityKind::Quoted),
+            ("Jawa", EntityKind::Name),
+            ("QAOA", EntityKind::Acronym),
+            ("Quantum Walks", EntityKind::Name),
+        ]);
+        assert_eq!(extract_entities("Bank of Indonesia data, from the Sultanate of"), vec![
+            Entity { text: "Bank of Indonesia".to_string(), kind: EntityKind::Name },
+            Entity { text: "Sultanate".to_string(), kind: EntityKind::Name },
+        ]);
+
+        let lexicon = TermLexicon::builtin().term("ngelmu titen", "Traditional Wayfinding");
+        assert_eq!(lexicon.find(text), vec!["Traditional Wayfinding", "Quantum Algorithms"]);
+        assert_eq!(lexicon.find("navigation for quantum sensing"), vec!["Navigation Systems", "Quantum Sensing"]);
+    }
+
+    #[test]
+    fn test_entity_clusters() {
+        let entities = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        let clusters = entity_clusters(&[
+            entities(&["Jawa", "QAOA"]),
+            entities(&["Borobudur"]),
+            entities(&["jawa"]),
+            entities(&["QAOA", "Journavx"]),
+            entities(&[]),
+            entities(&["Borobudur"]),
+        ]);
+        assert_eq!(clusters, vec![
+            EntityCluster { entities: entities(&["Jawa", "QAOA"]), discoveries: vec![0, 2, 3] },
+            EntityCluster { entities: entities(&["Borobudur"]), discoveries: vec![1, 5] },
+        ]);
+    }
+}
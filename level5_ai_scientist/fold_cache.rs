@@ -0,0 +1,288 @@
+// -*- coding: utf-8 -*-
+//! Cached and Incremental Memory Folding
+//!
+//! `fold_memory` walks every event on each call. A trace keeps its last fold
+//! together with what it covered (event and transition counts, last event):
+//! `fold_memory_cached` returns it until new events arrive, and
+//! `fold_memory_incremental` brings it up to date from just the new tail
+//! events and transitions. Key discoveries, language transitions, knowledge
+//! credits, redaction counts, uniqueness, and duplicate groups are extended
+//! from the tail (entity clusters and serendipity peaks are recomputed from
+//! the kept discovery entities and score jumps); a key-discovery threshold that moves (adaptive insight
+//! policies) refolds the key discoveries. The result always equals
+//! `fold_memory`.
+//!
+//! The trace's own in-place edits (redaction, merged retries) drop the cache;
+//! direct edits of logged events must be followed by `fold_memory`.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use crate::serendipity_trace::{
+    key_discovery, language_transition_label, FoldedSerendipityTrace, SerendipityAgent, SerendipityStage,
+    SerendipityTrace, UniquenessBreakdown, AGENT_TYPE_COUNT,
+};
+use crate::stage_taxonomy::stage_type_count;
+use crate::knowledge_source::credit_event;
+use crate::dedup::{DedupReport, MergedRetries};
+use crate::extraction::{discovery_entities, entity_clusters};
+use crate::anomaly::AnomalyDetector;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Where a fingerprint's events sit in the duplicate groups
+#[derive(Debug, Clone)]
+enum Seen {
+    /// Logged once so far, at `first`
+    Once { first: usize, event_id: String },
+    /// Duplicate group of the events from `first` on
+    Repeated { first: usize },
+}
+
+/// Fold of a trace's first `events` events, with the state to extend it
+#[derive(Debug, Clone)]
+struct CachedFold {
+    folded: FoldedSerendipityTrace,
+    events: usize,
+    last_event_id: String,
+    transitions: usize,
+    /// Entities of each key discovery
+    discovery_entities: Vec<Vec<String>>,
+    /// Serendipity change into each event after the first
+    jumps: Vec<f64>,
+    agents: HashSet<SerendipityAgent>,
+    stages: HashSet<SerendipityStage>,
+    fingerprints: HashMap<String, Seen>,
+    /// First event index of each duplicate group, in group order
+    group_firsts: Vec<usize>,
+}
+
+impl CachedFold {
+    /// Whether the trace still starts with the events this fold covers
+    fn is_prefix_of(&self, trace: &SerendipityTrace) -> bool {
+        self.events.checked_sub(1).and_then(|i| trace.events.get(i)).is_some_and(|e| e.event_id == self.last_event_id)
+            && trace.transitions.len() >= self.transitions
+    }
+
+    /// Whether the fold covers the trace exactly
+    fn covers(&self, trace: &SerendipityTrace) -> bool {
+        self.is_prefix_of(trace)
+            && trace.events.len() == self.events
+            && trace.transitions.len() == self.transitions
+            && self.folded.key_discovery_threshold == trace.key_discovery_threshold()
+            && self.folded.overall_serendipity == trace.overall_serendipity
+    }
+}
+
+/// Last fold of a trace (not serialized; empty until the first cached fold)
+#[derive(Debug, Clone, Default)]
+pub struct FoldCache {
+    cached: Option<CachedFold>,
+}
+
+impl SerendipityTrace {
+    /// Fold memory, reusing the last cached fold while no events were added
+    pub fn fold_memory_cached(&mut self) -> SerenQaResult<&FoldedSerendipityTrace> {
+        if !self.fold_cache.cached.as_ref().is_some_and(|cached| cached.covers(self)) {
+            self.fold_cache.cached = None;
+            return self.fold_memory_incremental();
+        }
+        // Children are logged independently, so they are folded fresh
+        let subtraces = self.fold_subtraces();
+        let cached = self.fold_cache.cached.as_mut().expect("cache was just checked");
+        cached.folded.subtraces = subtraces;
+        Ok(&cached.folded)
+    }
+
+    /// Fold memory, extending the cached fold with the events logged since it
+    /// was made (a full fold if there is no usable cache)
+    pub fn fold_memory_incremental(&mut self) -> SerenQaResult<&FoldedSerendipityTrace> {
+        if self.events.is_empty() {
+            return Err(SerenQaError::EmptyTrace(self.trace_id.clone()));
+        }
+        let threshold = self.key_discovery_threshold();
+        let mut cached = match self.fold_cache.cached.take() {
+            Some(cached) if cached.is_prefix_of(self) => cached,
+            _ => self.empty_fold(threshold),
+        };
+
+        if threshold != cached.folded.key_discovery_threshold {
+            cached.folded.key_discovery_threshold = threshold;
+            (cached.folded.key_discoveries, cached.discovery_entities) = self.events[..cached.events]
+                .iter()
+                .filter_map(|e| key_discovery(e, threshold).map(|d| (d, discovery_entities(e))))
+                .unzip();
+        }
+
+        let folded = &mut cached.folded;
+        for (index, event) in self.events.iter().enumerate().skip(cached.events) {
+            if let Some(discovery) = key_discovery(event, threshold) {
+                folded.key_discoveries.push(discovery);
+                cached.discovery_entities.push(discovery_entities(event));
+            }
+            credit_event(&mut folded.knowledge_sources, event);
+            if event.is_redacted() {
+                folded.redacted_events += 1;
+            }
+            if let Some(&retries) = self.merged_retries.get(&event.event_id) {
+                folded.dedup.merged.push(MergedRetries { event_id: event.event_id.clone(), retries });
+            }
+            if let Some(previous) = index.checked_sub(1).map(|i| &self.events[i]) {
+                cached.jumps.push(event.serendipity_score - previous.serendipity_score);
+            }
+            cached.agents.insert(event.agent.clone());
+            cached.stages.insert(event.stage.clone());
+
+            match cached.fingerprints.entry(event.fingerprint()) {
+                Entry::Vacant(slot) => {
+                    slot.insert(Seen::Once { first: index, event_id: event.event_id.clone() });
+                }
+                Entry::Occupied(mut slot) => match slot.get().clone() {
+                    Seen::Repeated { first } => {
+                        let group = cached.group_firsts.binary_search(&first).expect("duplicate group is recorded");
+                        folded.dedup.duplicate_groups[group].push(event.event_id.clone());
+                    }
+                    Seen::Once { first, event_id } => {
+                        slot.insert(Seen::Repeated { first });
+                        // Groups are ordered by their first event, as in `dedup_report`
+                        let group = cached.group_firsts.binary_search(&first).unwrap_err();
+                        cached.group_firsts.insert(group, first);
+                        folded.dedup.duplicate_groups.insert(group, vec![event_id, event.event_id.clone()]);
+                    }
+                },
+            }
+        }
+        folded.language_transitions.extend(
+            self.transitions[cached.transitions..].iter().filter_map(language_transition_label),
+        );
+        folded.trace_id = self.trace_id.clone();
+        folded.discovery_name = self.discovery_name.clone();
+        folded.total_events = self.events.len();
+        folded.overall_serendipity = self.overall_serendipity;
+        folded.compression_ratio = folded.key_discoveries.len() as f64 / self.events.len() as f64;
+        folded.languages = self.languages.clone();
+        folded.partially_redacted = folded.redacted_events > 0;
+        folded.entity_clusters = entity_clusters(&cached.discovery_entities);
+        // Children are logged independently, so they are folded fresh
+        folded.subtraces = self.fold_subtraces();
+        folded.serendipity_peaks = AnomalyDetector::default().peaks_from_jumps(&self.events, &cached.jumps);
+        folded.uniqueness = UniquenessBreakdown::with_type_counts(
+            cached.agents.len(),
+            AGENT_TYPE_COUNT + cached.agents.iter().filter(|agent| agent.is_custom()).count(),
+            self.languages.len(),
+            cached.stages.len(),
+            stage_type_count(self.stage_taxonomy.as_ref(), &cached.stages),
+        );
+        cached.events = self.events.len();
+        cached.last_event_id = self.events[self.events.len() - 1].event_id.clone();
+        cached.transitions = self.transitions.len();
+
+        Ok(&self.fold_cache.cached.insert(cached).folded)
+    }
+
+    /// Drop the cached fold after changing a logged event in place
+    pub(crate) fn invalidate_fold_cache(&mut self) {
+        self.fold_cache.cached = None;
+    }
+
+    /// Fold state covering no events yet
+    fn empty_fold(&self, key_discovery_threshold: f64) -> CachedFold {
+        CachedFold {
+            folded: FoldedSerendipityTrace {
+                trace_id: self.trace_id.clone(),
+                discovery_name: self.discovery_name.clone(),
+                total_events: 0,
+                key_discoveries: Vec::new(),
+                key_discovery_threshold,
+                language_transitions: Vec::new(),
+                overall_serendipity: 0.0,
+                compression_ratio: 0.0,
+                languages: Vec::new(),
+                partially_redacted: false,
+                redacted_events: 0,
+                uniqueness: UniquenessBreakdown::default(),
+                knowledge_sources: Vec::new(),
+                dedup: DedupReport::default(),
+                entity_clusters: Vec::new(),
+                serendipity_peaks: Vec::new(),
+                subtraces: Vec::new(),
+            },
+            events: 0,
+            last_event_id: String::new(),
+            transitions: 0,
+            discovery_entities: Vec::new(),
+            jumps: Vec::new(),
+            agents: HashSet::new(),
+            stages: HashSet::new(),
+            fingerprints: HashMap::new(),
+            group_firsts: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityEventBuilder, SerendipityStage, SerendipityAgent};
+    use crate::knowledge_source::{KnowledgeSource, KnowledgeSourceKind};
+    use crate::insight_policy::InsightPolicy;
+    use crate::redaction::RedactionRequest;
+
+    /// Events repeat every four steps, so later ones join duplicate groups
+    fn log(trace: &mut SerendipityTrace, i: usize) {
+        let (stage, agent, language) = if i.is_multiple_of(2) {
+            (SerendipityStage::Exploration, SerendipityAgent::Explorer, "en")
+        } else {
+            (SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "id")
+        };
+        let mut builder = SerendipityEventBuilder::new(stage, agent, "in", &format!("note {}", i % 4), language)
+            .serendipity((i * 37 % 100) as f64 / 100.0)
+            .confidence(0.8);
+        if i.is_multiple_of(5) {
+            builder = builder.knowledge_source(KnowledgeSource::new(KnowledgeSourceKind::OralTradition, "Javanese wayfinding", "jv"));
+        }
+        trace.log(builder).unwrap();
+    }
+
+    #[test]
+    fn test_incremental_fold_equals_full_fold() {
+        for policy in [InsightPolicy::default(), InsightPolicy::Percentile { percentile: 0.8 }] {
+            let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+            trace.set_insight_policy(policy);
+            assert!(matches!(trace.fold_memory_incremental(), Err(SerenQaError::EmptyTrace(_))));
+            for i in 0..30 {
+                log(&mut trace, i);
+                if i % 3 != 1 {
+                    let full = trace.fold_memory().unwrap();
+                    assert_eq!(trace.fold_memory_incremental().unwrap(), &full);
+                }
+            }
+            assert!(!trace.fold_memory().unwrap().dedup.duplicate_groups.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_cached_fold_until_events_arrive() {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        for i in 0..4 {
+            log(&mut trace, i);
+        }
+        let first = trace.fold_memory_cached().unwrap().clone();
+
+        // Direct edits are not seen until the cache is dropped
+        trace.events[0].output = "edited".to_string();
+        assert_eq!(trace.fold_memory_cached().unwrap(), &first);
+        let request = RedactionRequest {
+            event_id: trace.events[1].event_id.clone(),
+            requested_by: "legal".to_string(),
+            reason: "ip".to_string(),
+        };
+        trace.redact_event(&request, b"salt").unwrap();
+        let full = trace.fold_memory().unwrap();
+        assert_eq!(trace.fold_memory_cached().unwrap(), &full);
+        assert_eq!(full.redacted_events, 1);
+
+        log(&mut trace, 4);
+        let full = trace.fold_memory().unwrap();
+        assert_eq!(trace.fold_memory_cached().unwrap(), &full);
+        assert_eq!(full.total_events, 5);
+    }
+}
@@ -3,11 +3,18 @@
 //! 
 //! Extends MetaAgent memory folding with language awareness,
 //! cross-language pattern detection, and multilingual insight extraction.
+//! With `with_topic_drift`, consecutive events whose outputs fall in
+//! different topics are reported as `TopicDrift` patterns.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use crate::AgentEvent::LanguageAwareAgentEvent;
-use crate::alignment::{MultilingualAligner, AlignmentResult};
+use crate::alignment_cache::{AlignmentCacheStats, CachedAligner};
+use crate::translation::TranslationBackend;
+use crate::script::{detect_script, romanize};
+use crate::topics::TopicModel;
+use crate::error::{SerenQaError, SerenQaResult};
 
 /// Multilingual memory fold with language-aware compression
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,27 +61,68 @@ pub struct TranslationSummary {
     pub language_pairs: Vec<String>,
     /// Problematic translations (quality < 0.7)
     pub problematic_translations: usize,
+    /// Translation backend that estimated the qualities
+    /// (`None` when they are alignment scores)
+    #[serde(default)]
+    pub quality_backend: Option<String>,
 }
 
 /// Multilingual memory folder
 pub struct MultilingualMemoryFolder {
-    aligner: MultilingualAligner,
+    aligner: CachedAligner,
+    translation: Option<Arc<dyn TranslationBackend>>,
+    /// Topics fitted to detect topic drift (`None` skips detection)
+    topic_count: Option<usize>,
 }
 
 impl MultilingualMemoryFolder {
     /// Create a new multilingual memory folder
     pub fn new() -> Self {
         Self {
-            aligner: MultilingualAligner::new(),
+            aligner: CachedAligner::new(),
+            translation: None,
+            topic_count: None,
         }
     }
 
-    /// Fold multilingual memory trace
+    /// Folder caching up to `capacity` alignment results (0 disables caching)
+    pub fn with_alignment_cache(capacity: usize) -> Self {
+        Self {
+            aligner: CachedAligner::with_capacity(capacity),
+            translation: None,
+            topic_count: None,
+        }
+    }
+
+    /// Score translations with `backend`'s quality estimates instead of
+    /// alignment heuristics
+    pub fn with_translation_backend<B: TranslationBackend + 'static>(mut self, backend: B) -> Self {
+        self.translation = Some(Arc::new(backend));
+        self
+    }
+
+    /// Fit up to `topic_count` topics to the event outputs and report changes
+    /// of topic between consecutive events as `TopicDrift` patterns
+    pub fn with_topic_drift(mut self, topic_count: usize) -> Self {
+        self.topic_count = Some(topic_count);
+        self
+    }
+
+    /// Hit/miss statistics of the folder's alignment cache
+    pub fn alignment_cache_stats(&self) -> AlignmentCacheStats {
+        self.aligner.cache_stats()
+    }
+
+    /// Fold multilingual memory trace.
+    /// Fails on an empty event list, which has no compression ratio.
     pub fn fold_memory(
         &mut self,
         trace_id: &str,
         events: &[LanguageAwareAgentEvent],
-    ) -> MultilingualMemoryFold {
+    ) -> SerenQaResult<MultilingualMemoryFold> {
+        if events.is_empty() {
+            return Err(SerenQaError::EmptyTrace(trace_id.to_string()));
+        }
         let total_events = events.len();
         
         // Extract key insights (high-confidence, multilingual events)
@@ -87,7 +135,7 @@ impl MultilingualMemoryFolder {
         let cross_language_patterns = self.detect_cross_language_patterns(events);
         
         // Compute translation summary
-        let translation_summary = self.compute_translation_summary(events);
+        let translation_summary = self.compute_translation_summary(events)?;
         
         // Calculate compression ratio
         let compression_ratio = (key_insights.len() as f64) / (total_events as f64);
@@ -95,7 +143,7 @@ impl MultilingualMemoryFolder {
         // Calculate overall alignment
         let overall_alignment = self.calculate_overall_alignment(events);
         
-        MultilingualMemoryFold {
+        Ok(MultilingualMemoryFold {
             trace_id: trace_id.to_string(),
             total_events,
             key_insights,
@@ -104,7 +152,7 @@ impl MultilingualMemoryFolder {
             translation_summary,
             compression_ratio,
             overall_alignment,
-        }
+        })
     }
 
     /// Extract key insights from events
@@ -197,6 +245,30 @@ impl MultilingualMemoryFolder {
             });
         }
         
+        // Pattern 3: Topic drift
+        if let Some(topic_count) = self.topic_count {
+            let outputs: Vec<&str> = events.iter().map(|e| e.output.as_str()).collect();
+            let model = TopicModel::fit(&outputs, topic_count);
+            for (i, window) in events.windows(2).enumerate() {
+                let (Some(from), Some(to)) = (model.topic_of(i), model.topic_of(i + 1)) else {
+                    continue;
+                };
+                if from.id == to.id {
+                    continue;
+                }
+                let mut languages = vec![window[0].primary_language.clone()];
+                if window[1].primary_language != window[0].primary_language {
+                    languages.push(window[1].primary_language.clone());
+                }
+                patterns.push(CrossLanguagePattern {
+                    pattern_type: "TopicDrift".to_string(),
+                    languages,
+                    description: format!("Drift from topic '{}' to '{}'", from.label, to.label),
+                    confidence: (window[0].confidence + window[1].confidence) / 2.0,
+                });
+            }
+        }
+        
         patterns
     }
 
@@ -204,7 +276,7 @@ impl MultilingualMemoryFolder {
     fn compute_translation_summary(
         &mut self,
         events: &[LanguageAwareAgentEvent],
-    ) -> TranslationSummary {
+    ) -> SerenQaResult<TranslationSummary> {
         let mut total_translations = 0;
         let mut quality_sum = 0.0;
         let mut language_pairs = Vec::new();
@@ -214,22 +286,39 @@ impl MultilingualMemoryFolder {
             if window[0].primary_language != window[1].primary_language {
                 total_translations += 1;
                 
-                // Compute alignment
-                let alignment = self.aligner.align(
-                    &window[0].output,
-                    &window[1].input,
-                    &window[0].primary_language,
-                    &window[1].primary_language,
-                );
+                // MT quality estimate if a backend is attached, else alignment
+                let quality = match &self.translation {
+                    Some(backend) => backend.estimate_quality(
+                        &window[0].output,
+                        &window[1].input,
+                        &window[0].primary_language,
+                        &window[1].primary_language,
+                    )?,
+                    None => {
+                        // Heuristics compare letters, so align across scripts in Latin
+                        let (source, target) = (&window[0].output, &window[1].input);
+                        let (source, target) = if detect_script(source) != detect_script(target) {
+                            (romanize(source), romanize(target))
+                        } else {
+                            (source.clone(), target.clone())
+                        };
+                        self.aligner.align(
+                            &source,
+                            &target,
+                            &window[0].primary_language,
+                            &window[1].primary_language,
+                        ).overall_score
+                    }
+                };
                 
-                quality_sum += alignment.overall_score;
+                quality_sum += quality;
                 
                 let pair = format!("{}-{}", window[0].primary_language, window[1].primary_language);
                 if !language_pairs.contains(&pair) {
                     language_pairs.push(pair);
                 }
                 
-                if alignment.overall_score < 0.7 {
+                if quality < 0.7 {
                     problematic_translations += 1;
                 }
             }
@@ -241,12 +330,13 @@ impl MultilingualMemoryFolder {
             1.0
         };
         
-        TranslationSummary {
+        Ok(TranslationSummary {
             total_translations,
             average_quality,
             language_pairs,
             problematic_translations,
-        }
+            quality_backend: self.translation.as_ref().map(|backend| backend.name().to_string()),
+        })
     }
 
     /// Calculate overall alignment score
@@ -283,7 +373,7 @@ mod tests {
         event2.add_secondary_language("en");
         
         let events = vec![event1, event2];
-        let fold = folder.fold_memory("trace1", &events);
+        let fold = folder.fold_memory("trace1", &events).unwrap();
         
         assert_eq!(fold.total_events, 2);
         assert!(fold.compression_ratio > 0.0);
@@ -316,4 +406,42 @@ mod tests {
         
         assert!(!patterns.is_empty());
     }
+
+    #[test]
+    fn test_translation_backend_scores_summary() {
+        let backend = crate::translation::MockBackend::new(0.9).pair_quality("id", "en", 0.4);
+        let mut folder = MultilingualMemoryFolder::new().with_translation_backend(backend);
+        
+        let events = vec![
+            LanguageAwareAgentEvent::new("Explorer", "input1", "output1", "en", 0.9),
+            LanguageAwareAgentEvent::new("Translator", "input2", "output2", "id", 0.85),
+            LanguageAwareAgentEvent::new("Validator", "input3", "output3", "en", 0.8),
+        ];
+        let summary = folder.fold_memory("trace1", &events).unwrap().translation_summary;
+        
+        assert_eq!(summary.quality_backend.as_deref(), Some("mock"));
+        assert!((summary.average_quality - 0.65).abs() < 1e-12);
+        assert_eq!(summary.problematic_translations, 1);
+    }
+
+    #[test]
+    fn test_topic_drift_patterns() {
+        let events = vec![
+            LanguageAwareAgentEvent::new("Explorer", "survey", "Batik motif encodes routes", "en", 0.9),
+            LanguageAwareAgentEvent::new("Translator", "survey", "Motif batik menyimpan rute", "id", 0.7),
+            LanguageAwareAgentEvent::new("Validator", "survey", "Quantum walk lattice", "en", 0.8),
+        ];
+        let drifts = |folder: &MultilingualMemoryFolder| -> Vec<CrossLanguagePattern> {
+            folder.detect_cross_language_patterns(&events)
+                .into_iter()
+                .filter(|p| p.pattern_type == "TopicDrift")
+                .collect()
+        };
+        
+        assert!(drifts(&MultilingualMemoryFolder::new()).is_empty());
+        let drifts = drifts(&MultilingualMemoryFolder::new().with_topic_drift(2));
+        assert_eq!(drifts.len(), 1);
+        assert_eq!(drifts[0].languages, vec!["id", "en"]);
+        assert!((drifts[0].confidence - 0.75).abs() < 1e-12);
+    }
 }
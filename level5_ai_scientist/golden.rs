@@ -0,0 +1,265 @@
+// -*- coding: utf-8 -*-
+//! Golden-File Regression Framework for Scoring Outputs
+//!
+//! Records folded outputs, scores, and hashes for a set of canonical traces
+//! (including Journavx) and compares them against checked-in golden files, so
+//! scoring-affecting changes cannot alter benchmark results unnoticed.
+//! Intentional changes are accepted by re-blessing the files
+//! (`seren golden --bless` or `SEREN_BLESS=1 cargo test`).
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration};
+use std::path::Path;
+use crate::serendipity_trace::{
+    SerendipityTrace, SerendipityTransition, SerendipityStage, SerendipityAgent, FoldedSerendipityTrace,
+};
+use crate::ContributorStats::LanguageAwareContributorStats;
+use crate::Journavx_Discovery::simulate_journavx_discovery;
+use crate::error::SerenQaResult;
+
+/// Fixed creation time (2024-01-01T00:00:00Z) for canonical traces
+pub const GOLDEN_EPOCH_SECS: i64 = 1_704_067_200;
+
+/// Alignment and translation quality fed to contributor scoring
+pub const GOLDEN_ALIGNMENT_SCORE: f64 = 0.9;
+
+/// Environment variable that blesses golden files from `cargo test`
+pub const BLESS_ENV_VAR: &str = "SEREN_BLESS";
+
+/// Scoring outputs recorded for one canonical trace
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GoldenRecord {
+    /// Canonical trace name
+    pub name: String,
+    /// Overall serendipity
+    pub overall_serendipity: f64,
+    /// Uniqueness score
+    pub uniqueness_score: f64,
+    /// Contributor overall score for a single-trace contributor
+    pub contributor_score: f64,
+    /// Provenance hash
+    pub provenance_hash: String,
+    /// Merkle root over events
+    pub merkle_root: String,
+    /// Folded trace
+    pub folded: FoldedSerendipityTrace,
+}
+
+/// Result of comparing one record with its golden file
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoldenOutcome {
+    /// Output matches the golden file
+    Matched,
+    /// Output differs; names the top-level fields that changed
+    Mismatch(Vec<String>),
+    /// No golden file exists yet
+    Missing,
+    /// Golden file was (re)written
+    Blessed,
+}
+
+/// Comparison results for every canonical trace
+#[derive(Debug, Clone, Default)]
+pub struct GoldenReport {
+    /// (trace name, outcome) in canonical order
+    pub outcomes: Vec<(String, GoldenOutcome)>,
+}
+
+impl GoldenReport {
+    /// Check if every golden file matched or was blessed
+    pub fn is_ok(&self) -> bool {
+        self.outcomes
+            .iter()
+            .all(|(_, o)| matches!(o, GoldenOutcome::Matched | GoldenOutcome::Blessed))
+    }
+
+    /// Human-readable failure summary
+    pub fn failures(&self) -> Vec<String> {
+        self.outcomes
+            .iter()
+            .filter_map(|(name, outcome)| match outcome {
+                GoldenOutcome::Mismatch(fields) => Some(format!("{}: changed {}", name, fields.join(", "))),
+                GoldenOutcome::Missing => Some(format!("{}: no golden file (run with --bless)", name)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Make a trace deterministic: fixed trace ID, timestamps, and event IDs
+pub fn pin_trace(trace: &mut SerendipityTrace, name: &str) {
+    let epoch = DateTime::from_timestamp(GOLDEN_EPOCH_SECS, 0).unwrap();
+    trace.trace_id = format!("golden_{}", name);
+    trace.created_at = epoch;
+    trace.signature = None;
+    trace.invalidate_provenance();
+    for (i, event) in trace.events.iter_mut().enumerate() {
+        event.event_id = format!("event_{}", i);
+        event.timestamp = epoch + Duration::seconds(i as i64 + 1);
+    }
+    trace.transitions = trace.events
+        .windows(2)
+        .map(|pair| SerendipityTransition::between(&pair[0], &pair[1]))
+        .collect();
+}
+
+/// Build a pinned trace from (stage, agent, language, serendipity, confidence) steps
+fn scripted_trace(
+    name: &str,
+    steps: &[(SerendipityStage, SerendipityAgent, &str, f64, f64)],
+) -> SerenQaResult<SerendipityTrace> {
+    let mut trace = SerendipityTrace::new("golden_contributor", "golden_backend", name);
+    for (i, (stage, agent, language, serendipity, confidence)) in steps.iter().enumerate() {
+        trace.log_event(
+            stage.clone(),
+            agent.clone(),
+            &format!("{} input {}", name, i),
+            &format!("{} output {}", name, i),
+            language,
+            *serendipity,
+            *confidence,
+        )?;
+    }
+    pin_trace(&mut trace, name);
+    Ok(trace)
+}
+
+/// Canonical traces covered by the golden files
+pub fn canonical_traces() -> SerenQaResult<Vec<(String, SerendipityTrace)>> {
+    use SerendipityAgent::*;
+    use SerendipityStage::*;
+
+    let mut journavx = simulate_journavx_discovery()?;
+    pin_trace(&mut journavx, "journavx");
+
+    Ok(vec![
+        ("journavx".to_string(), journavx),
+        (
+            "monolingual_steady".to_string(),
+            scripted_trace("monolingual_steady", &[
+                (Exploration, Explorer, "en", 0.5, 0.8),
+                (HypothesisFormation, HypothesisGenerator, "en", 0.55, 0.8),
+                (Validation, Validator, "en", 0.5, 0.9),
+            ])?,
+        ),
+        (
+            "single_breakthrough".to_string(),
+            scripted_trace("single_breakthrough", &[
+                (Exploration, Explorer, "en", 0.2, 0.7),
+                (Exploration, Explorer, "en", 0.25, 0.7),
+                (UnexpectedConnection, PatternRecognizer, "id", 0.98, 0.6),
+                (Validation, Validator, "en", 0.3, 0.9),
+            ])?,
+        ),
+        (
+            "multilingual_relay".to_string(),
+            scripted_trace("multilingual_relay", &[
+                (Exploration, Explorer, "en", 0.6, 0.85),
+                (UnexpectedConnection, Translator, "id", 0.85, 0.8),
+                (HypothesisFormation, HypothesisGenerator, "jv", 0.9, 0.75),
+                (Validation, Validator, "es", 0.7, 0.9),
+                (Integration, Synthesizer, "en", 0.8, 0.88),
+                (Publication, MetaOrchestrator, "id", 0.75, 0.92),
+            ])?,
+        ),
+    ])
+}
+
+/// Record scoring outputs for a pinned trace
+pub fn record(name: &str, trace: &SerendipityTrace) -> SerenQaResult<GoldenRecord> {
+    let mut stats = LanguageAwareContributorStats::new(&trace.contributor_id);
+    stats.add_trace(
+        trace.depth(),
+        trace.uniqueness_score(),
+        trace.overall_serendipity,
+        trace.languages.clone(),
+        GOLDEN_ALIGNMENT_SCORE,
+        GOLDEN_ALIGNMENT_SCORE,
+    )?;
+    stats.add_discovery(&trace.discovery_name);
+
+    Ok(GoldenRecord {
+        name: name.to_string(),
+        overall_serendipity: trace.overall_serendipity,
+        uniqueness_score: trace.uniqueness_score(),
+        contributor_score: stats.overall_score(),
+        provenance_hash: trace.compute_provenance_hash(),
+        merkle_root: trace.merkle_root(),
+        folded: trace.fold_memory()?,
+    })
+}
+
+/// Top-level fields that differ between two serialized records
+fn changed_fields(expected: &serde_json::Value, actual: &serde_json::Value) -> Vec<String> {
+    let (Some(expected), Some(actual)) = (expected.as_object(), actual.as_object()) else {
+        return vec!["<record>".to_string()];
+    };
+    let mut fields: Vec<String> = expected
+        .keys()
+        .chain(actual.keys())
+        .filter(|key| expected.get(*key) != actual.get(*key))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
+/// Compare (or, with `bless`, rewrite) golden files in `dir`
+pub fn check_golden(dir: &Path, bless: bool) -> SerenQaResult<GoldenReport> {
+    let mut report = GoldenReport::default();
+    for (name, trace) in canonical_traces()? {
+        let actual = serde_json::to_value(record(&name, &trace)?)?;
+        let path = dir.join(format!("{}.json", name));
+
+        let outcome = if bless {
+            std::fs::create_dir_all(dir)?;
+            std::fs::write(&path, serde_json::to_string_pretty(&actual)? + "\n")?;
+            GoldenOutcome::Blessed
+        } else if !path.exists() {
+            GoldenOutcome::Missing
+        } else {
+            let expected: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            let fields = changed_fields(&expected, &actual);
+            if fields.is_empty() {
+                GoldenOutcome::Matched
+            } else {
+                GoldenOutcome::Mismatch(fields)
+            }
+        };
+        report.outcomes.push((name, outcome));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_traces_are_deterministic() {
+        let first = canonical_traces().unwrap();
+        let second = canonical_traces().unwrap();
+        for ((name, a), (_, b)) in first.iter().zip(&second) {
+            assert_eq!(record(name, a).unwrap(), record(name, b).unwrap(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_bless_then_detect_change() {
+        let dir = std::env::temp_dir().join(format!("seren_golden_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(!check_golden(&dir, false).unwrap().is_ok());
+        assert!(check_golden(&dir, true).unwrap().is_ok());
+        assert!(check_golden(&dir, false).unwrap().is_ok());
+
+        let path = dir.join("journavx.json");
+        let tampered = std::fs::read_to_string(&path).unwrap().replace("\"overall_serendipity\": 0.", "\"overall_serendipity\": 1.");
+        std::fs::write(&path, tampered).unwrap();
+        let report = check_golden(&dir, false).unwrap();
+        assert!(!report.is_ok());
+        assert!(report.failures()[0].starts_with("journavx: changed"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+// -*- coding: utf-8 -*-
+//! Trace Graph Export (GraphViz DOT / GraphML)
+//!
+//! Renders a trace's events as nodes (filled by stage, outlined by agent,
+//! sized by serendipity) and its transitions as edges weighted by
+//! transition score, for viewing in Graphviz or Gephi.
+
+use std::fmt::Write;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, SerendipityAgent};
+
+/// Smallest node diameter (inches) for a zero-serendipity event
+const MIN_NODE_SIZE: f64 = 0.5;
+
+/// Extra node diameter (inches) for a fully serendipitous event
+const NODE_SIZE_RANGE: f64 = 1.0;
+
+/// Fill color for a stage
+pub fn stage_color(stage: &SerendipityStage) -> &'static str {
+    match stage {
+        SerendipityStage::Exploration => "#8dd3c7",
+        SerendipityStage::UnexpectedConnection => "#fb8072",
+        SerendipityStage::HypothesisFormation => "#bebada",
+        SerendipityStage::Validation => "#80b1d3",
+        SerendipityStage::Integration => "#fdb462",
+        SerendipityStage::Publication => "#b3de69",
+        SerendipityStage::Custom(_) => "#d9d9d9",
+    }
+}
+
+/// Outline color for an agent
+pub fn agent_color(agent: &SerendipityAgent) -> &'static str {
+    match agent {
+        SerendipityAgent::Explorer => "#1b9e77",
+        SerendipityAgent::PatternRecognizer => "#d95f02",
+        SerendipityAgent::HypothesisGenerator => "#7570b3",
+        SerendipityAgent::Validator => "#e7298a",
+        SerendipityAgent::Synthesizer => "#66a61e",
+        SerendipityAgent::Translator => "#e6ab02",
+        SerendipityAgent::MetaOrchestrator => "#a6761d",
+        SerendipityAgent::Custom(_) => "#666666",
+    }
+}
+
+/// Node diameter for a serendipity score
+fn node_size(serendipity: f64) -> f64 {
+    MIN_NODE_SIZE + NODE_SIZE_RANGE * serendipity.clamp(0.0, 1.0)
+}
+
+/// Escape a string for a double-quoted DOT identifier
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Escape a string for XML text and attributes
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl SerendipityTrace {
+    /// Render as a GraphViz DOT digraph
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph \"{}\" {{", escape_dot(&self.trace_id));
+        let _ = writeln!(dot, "    label=\"{}\";", escape_dot(&self.discovery_name));
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=circle, style=\"filled,bold\", fixedsize=true];\n");
+
+        for event in &self.events {
+            let size = node_size(event.serendipity_score);
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{:?}\\n{:?}\\n{} {:.2}\", fillcolor=\"{}\", color=\"{}\", width={:.2}, height={:.2}];",
+                escape_dot(&event.event_id),
+                event.stage,
+                event.agent,
+                escape_dot(&event.language),
+                event.serendipity_score,
+                stage_color(&event.stage),
+                agent_color(&event.agent),
+                size,
+                size,
+            );
+        }
+
+        for transition in &self.transitions {
+            let label = match &transition.language_shift {
+                Some((from, to)) => format!("{:.2} ({} -> {})", transition.transition_score, from, to),
+                None => format!("{:.2}", transition.transition_score),
+            };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{}\", weight={:.3}, penwidth={:.2}];",
+                escape_dot(&transition.from_event),
+                escape_dot(&transition.to_event),
+                escape_dot(&label),
+                transition.transition_score,
+                1.0 + 3.0 * transition.transition_score.clamp(0.0, 1.0),
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render as a GraphML document with node and edge attributes
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, target, name, kind) in [
+            ("stage", "node", "stage", "string"),
+            ("agent", "node", "agent", "string"),
+            ("language", "node", "language", "string"),
+            ("serendipity", "node", "serendipity", "double"),
+            ("confidence", "node", "confidence", "double"),
+            ("color", "node", "color", "string"),
+            ("size", "node", "size", "double"),
+            ("weight", "edge", "weight", "double"),
+            ("reason", "edge", "reason", "string"),
+            ("language_shift", "edge", "language_shift", "string"),
+        ] {
+            let _ = writeln!(
+                xml,
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                id, target, name, kind
+            );
+        }
+        let _ = writeln!(xml, "  <graph id=\"{}\" edgedefault=\"directed\">", escape_xml(&self.trace_id));
+
+        for event in &self.events {
+            let _ = writeln!(xml, "    <node id=\"{}\">", escape_xml(&event.event_id));
+            for (key, value) in [
+                ("stage", event.stage.name()),
+                ("agent", escape_xml(&event.agent.name())),
+                ("language", escape_xml(&event.language)),
+                ("serendipity", event.serendipity_score.to_string()),
+                ("confidence", event.confidence.to_string()),
+                ("color", stage_color(&event.stage).to_string()),
+                ("size", format!("{:.2}", node_size(event.serendipity_score))),
+            ] {
+                let _ = writeln!(xml, "      <data key=\"{}\">{}</data>", key, value);
+            }
+            xml.push_str("    </node>\n");
+        }
+
+        for (i, transition) in self.transitions.iter().enumerate() {
+            let _ = writeln!(
+                xml,
+                "    <edge id=\"t{}\" source=\"{}\" target=\"{}\">",
+                i,
+                escape_xml(&transition.from_event),
+                escape_xml(&transition.to_event)
+            );
+            let _ = writeln!(xml, "      <data key=\"weight\">{}</data>", transition.transition_score);
+            let _ = writeln!(xml, "      <data key=\"reason\">{}</data>", escape_xml(&transition.reason));
+            if let Some((from, to)) = &transition.language_shift {
+                let _ = writeln!(
+                    xml,
+                    "      <data key=\"language_shift\">{}</data>",
+                    escape_xml(&format!("{} -> {}", from, to))
+                );
+            }
+            xml.push_str("    </edge>\n");
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx \"v1\"");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.2, 0.8).unwrap();
+        trace.log_event(
+            SerendipityStage::UnexpectedConnection,
+            SerendipityAgent::PatternRecognizer,
+            "in",
+            "out",
+            "id",
+            1.0,
+            0.6,
+        ).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let trace = sample_trace();
+        let dot = trace.to_dot();
+        assert!(dot.starts_with("digraph"));
+        assert!(dot.contains("label=\"Journavx \\\"v1\\\"\""));
+        assert_eq!(dot.matches(" -> \"").count(), 1);
+        assert!(dot.contains("fillcolor=\"#fb8072\", color=\"#d95f02\", width=1.50"));
+        assert!(dot.contains("weight=0.700"));
+        assert!(dot.contains("(en -> id)"));
+    }
+
+    #[test]
+    fn test_to_graphml() {
+        let trace = sample_trace();
+        let xml = trace.to_graphml();
+        assert_eq!(xml.matches("<node ").count(), 2);
+        assert_eq!(xml.matches("<edge ").count(), 1);
+        assert!(xml.contains(&format!("source=\"{}\"", trace.events[0].event_id)));
+        assert!(xml.contains("<data key=\"weight\">0.7</data>"));
+        assert!(xml.contains("<data key=\"reason\">Exploration -&gt; UnexpectedConnection</data>"));
+        assert!(xml.trim_end().ends_with("</graphml>"));
+    }
+}
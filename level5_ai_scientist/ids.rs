@@ -0,0 +1,192 @@
+// -*- coding: utf-8 -*-
+//! Collision-Free Trace and Event IDs
+//!
+//! IDs built from a timestamp alone collide when two events land in the same
+//! millisecond or a contributor starts two traces in the same second. Traces
+//! take their IDs from an `IdGenerator`; the default `UuidV7Ids` embeds a
+//! UUIDv7 (millisecond timestamp followed by random bits), so IDs still sort
+//! by time. `UuidV7Ids::seeded` draws the random bits from a seed instead,
+//! making IDs reproducible in tests.
+
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use crate::serendipity_trace::SerendipityTrace;
+
+/// Source of trace and event IDs
+pub trait IdGenerator: Send + Sync {
+    /// ID of a trace started by `contributor_id` at `at`
+    fn trace_id(&self, contributor_id: &str, at: DateTime<Utc>) -> String;
+    /// ID of the event logged at position `index` at `at`
+    fn event_id(&self, index: usize, at: DateTime<Utc>) -> String;
+}
+
+/// Where `UuidV7Ids` gets its random bits
+#[derive(Debug)]
+enum RandomSource {
+    /// Per-process random keys from the standard library
+    Entropy(RandomState),
+    /// Fixed seed, for reproducible IDs
+    Seeded(u64),
+}
+
+/// Mix a 64-bit value (SplitMix64 finalizer)
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// UUIDv7-based IDs: `seren_{contributor}_{uuid}` and `event_{index}_{uuid}`
+#[derive(Debug)]
+pub struct UuidV7Ids {
+    source: RandomSource,
+    counter: AtomicU64,
+}
+
+impl UuidV7Ids {
+    /// Random IDs
+    pub fn new() -> Self {
+        Self { source: RandomSource::Entropy(RandomState::new()), counter: AtomicU64::new(0) }
+    }
+
+    /// Reproducible IDs: generators with the same seed yield the same
+    /// sequence for the same timestamps
+    pub fn seeded(seed: u64) -> Self {
+        Self { source: RandomSource::Seeded(seed), counter: AtomicU64::new(0) }
+    }
+
+    fn random(&self) -> u64 {
+        let n = self.counter.fetch_add(1, Ordering::Relaxed);
+        match &self.source {
+            RandomSource::Entropy(state) => {
+                let mut hasher = state.build_hasher();
+                hasher.write_u64(n);
+                mix(hasher.finish())
+            }
+            RandomSource::Seeded(seed) => mix(seed ^ mix(n)),
+        }
+    }
+
+    /// New UUIDv7 for a timestamp
+    pub fn uuid(&self, at: DateTime<Utc>) -> String {
+        let millis = at.timestamp_millis().max(0) as u64 & 0xffff_ffff_ffff;
+        let (high, low) = (self.random(), self.random());
+        let rand_a = high & 0x0fff;
+        let rand_b = low & 0x3fff_ffff_ffff_ffff;
+        format!(
+            "{:08x}-{:04x}-7{:03x}-{:04x}-{:012x}",
+            millis >> 16,
+            millis & 0xffff,
+            rand_a,
+            0x8000 | (rand_b >> 48),
+            rand_b & 0xffff_ffff_ffff
+        )
+    }
+}
+
+impl Default for UuidV7Ids {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for UuidV7Ids {
+    fn trace_id(&self, contributor_id: &str, at: DateTime<Utc>) -> String {
+        format!("seren_{}_{}", contributor_id, self.uuid(at))
+    }
+
+    fn event_id(&self, index: usize, at: DateTime<Utc>) -> String {
+        format!("event_{}_{}", index, self.uuid(at))
+    }
+}
+
+/// ID generator attached to a trace (not serialized; random UUIDv7 by default)
+#[derive(Clone)]
+pub struct TraceIds(Arc<dyn IdGenerator>);
+
+impl TraceIds {
+    /// Wrap a generator
+    pub fn new<G: IdGenerator + 'static>(generator: G) -> Self {
+        Self(Arc::new(generator))
+    }
+
+    /// ID of a trace started by `contributor_id` at `at`
+    pub fn trace_id(&self, contributor_id: &str, at: DateTime<Utc>) -> String {
+        self.0.trace_id(contributor_id, at)
+    }
+
+    /// ID of the event logged at position `index` at `at`
+    pub fn event_id(&self, index: usize, at: DateTime<Utc>) -> String {
+        self.0.event_id(index, at)
+    }
+}
+
+impl Default for TraceIds {
+    fn default() -> Self {
+        Self::new(UuidV7Ids::new())
+    }
+}
+
+impl std::fmt::Debug for TraceIds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TraceIds")
+    }
+}
+
+impl SerendipityTrace {
+    /// Take IDs from `generator`, reassigning the trace ID.
+    /// Call before logging events.
+    pub fn with_ids<G: IdGenerator + 'static>(mut self, generator: G) -> Self {
+        self.ids = TraceIds::new(generator);
+        self.trace_id = self.ids.trace_id(&self.contributor_id, self.created_at);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::HashSet;
+    use crate::clock::FixedClock;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    #[test]
+    fn test_uuid_v7_layout_and_uniqueness() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+        let ids = UuidV7Ids::new();
+        let uuid = ids.uuid(at);
+        let hex: String = uuid.chars().filter(|c| *c != '-').collect();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(u64::from_str_radix(&hex[..12], 16).unwrap(), at.timestamp_millis() as u64);
+        assert_eq!(&hex[12..13], "7");
+        assert!(matches!(&hex[16..17], "8" | "9" | "a" | "b"));
+
+        // Same millisecond, same contributor: no collisions
+        let generated: HashSet<String> = (0..1000).map(|i| ids.event_id(i % 2, at)).collect();
+        assert_eq!(generated.len(), 1000);
+        assert_ne!(UuidV7Ids::new().trace_id("sari", at), UuidV7Ids::new().trace_id("sari", at));
+    }
+
+    #[test]
+    fn test_seeded_ids_are_reproducible() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+        let build = |seed| {
+            let mut trace = SerendipityTrace::with_clock("sari", "backend", "Journavx", FixedClock(at)).with_ids(UuidV7Ids::seeded(seed));
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+            trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "in", "out", "en", 0.9, 0.9).unwrap();
+            trace
+        };
+        let (first, second) = (build(7), build(7));
+        assert_eq!(first.trace_id, second.trace_id);
+        assert!(first.trace_id.starts_with("seren_sari_"));
+        assert_eq!(first.events[1].event_id, second.events[1].event_id);
+        assert_ne!(first.events[0].event_id, first.events[1].event_id);
+        assert_eq!(first.compute_provenance_hash(), second.compute_provenance_hash());
+        assert_ne!(build(8).trace_id, first.trace_id);
+    }
+}
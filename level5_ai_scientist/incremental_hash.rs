@@ -0,0 +1,228 @@
+// -*- coding: utf-8 -*-
+//! Incremental Provenance Hashing
+//!
+//! Hashing the whole trace after every logged event costs O(n²) over a
+//! session. The canonical encoding hashes each event right after its incoming
+//! transition, so a trace keeps a `ProvenanceHasher` fed as events are logged,
+//! and `compute_provenance_hash` only finishes a copy of it: O(1) per query.
+//! The running state is used only while it still describes the trace (same
+//! identity and chain link, same event and transition counts, same last
+//! event); otherwise, e.g. for a deserialized trace or a reassigned ID, the
+//! trace is rehashed in full. Child traces are logged independently, so their
+//! hashes are taken afresh.
+//!
+//! Events are public fields, so editing an already-logged event in place is
+//! not seen by the running state. The crate's own in-place edits (merges,
+//! redactions, merged retries, attachments) drop it. Signing and
+//! verification always rehash in full, so such edits cannot slip past them.
+
+use crate::serendipity_trace::SerendipityTrace;
+use crate::canonical::{HashVersion, ProvenanceHasher};
+
+/// Trace fields hashed ahead of the events
+#[derive(Debug, Clone, PartialEq)]
+struct Identity {
+    trace_id: String,
+    contributor_id: String,
+    backend: String,
+    discovery_name: String,
+    prev_trace_hash: Option<String>,
+}
+
+impl Identity {
+    fn of(trace: &SerendipityTrace) -> Self {
+        Self {
+            trace_id: trace.trace_id.clone(),
+            contributor_id: trace.contributor_id.clone(),
+            backend: trace.backend.clone(),
+            discovery_name: trace.discovery_name.clone(),
+            prev_trace_hash: trace.prev_trace_hash.clone(),
+        }
+    }
+
+    fn matches(&self, trace: &SerendipityTrace) -> bool {
+        self.trace_id == trace.trace_id
+            && self.contributor_id == trace.contributor_id
+            && self.backend == trace.backend
+            && self.discovery_name == trace.discovery_name
+            && self.prev_trace_hash == trace.prev_trace_hash
+    }
+}
+
+/// Hasher state after the header and the first `events` events
+#[derive(Debug, Clone)]
+struct RunningHash {
+    identity: Identity,
+    hasher: ProvenanceHasher,
+    events: usize,
+    last_event_id: Option<String>,
+}
+
+impl RunningHash {
+    /// Whether the state covers exactly the trace's first `events` events
+    fn covers(&self, trace: &SerendipityTrace, events: usize) -> bool {
+        self.identity.matches(trace)
+            && self.events == events
+            && self.last_event_id.as_deref() == events.checked_sub(1).map(|i| trace.events[i].event_id.as_str())
+    }
+}
+
+/// Provenance hasher kept in step with a trace while it is logged
+/// (not serialized; empty until the first event)
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalProvenance {
+    running: Option<RunningHash>,
+}
+
+impl SerendipityTrace {
+    /// Feed the newest event, and its incoming transition, into the running
+    /// hasher, starting over from the whole trace if the state is stale
+    pub(crate) fn advance_provenance(&mut self) {
+        let len = self.events.len();
+        let fresh = self.incremental_provenance.running.as_ref().is_some_and(|running| running.covers(self, len - 1));
+        if !fresh {
+            let mut hasher = ProvenanceHasher::new(HashVersion::CURRENT);
+            hasher.header(&self.trace_id, &self.contributor_id, &self.backend, &self.discovery_name);
+            if let Some(prev_trace_hash) = &self.prev_trace_hash {
+                hasher.chain_link(prev_trace_hash);
+            }
+            self.incremental_provenance.running = Some(RunningHash {
+                identity: Identity::of(self),
+                hasher,
+                events: 0,
+                last_event_id: None,
+            });
+        }
+
+        let running = self.incremental_provenance.running.as_mut().expect("running hash was just set");
+        for i in running.events..len {
+            if i > 0 {
+                if let Some(transition) = self.transitions.get(i - 1) {
+                    running.hasher.transition(transition);
+                }
+            }
+            running.hasher.event(&self.events[i]);
+        }
+        running.events = len;
+        running.last_event_id = Some(self.events[len - 1].event_id.clone());
+    }
+
+    /// Drop the running state after changing a logged event in place; the
+    /// next logged event rebuilds it
+    pub(crate) fn invalidate_provenance(&mut self) {
+        self.incremental_provenance.running = None;
+    }
+
+    /// Provenance hash of the trace as logged; same as `compute_provenance_hash`
+    pub fn provenance_hash(&self) -> String {
+        self.compute_provenance_hash()
+    }
+
+    /// Current-version hash from the running hasher, if it still covers the trace
+    pub(crate) fn running_provenance_hash(&self) -> Option<String> {
+        let running = self.incremental_provenance.running.as_ref()?;
+        // Every event but the first has exactly one incoming transition
+        if !running.covers(self, self.events.len()) || self.transitions.len() + 1 != self.events.len() {
+            return None;
+        }
+        let mut hasher = running.hasher.clone();
+        hasher.co_contributors(&self.co_contributors);
+        for verdict in &self.review.verdicts {
+            hasher.verdict(verdict);
+        }
+        // Children are logged independently, so their hashes are taken fresh
+        for subtrace in &self.subtraces {
+            hasher.subtrace(&subtrace.parent_event_id, &subtrace.trace.provenance_hash());
+        }
+        Some(hasher.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent, SerendipityEventBuilder};
+    use crate::review::{ReviewDecision, ReviewVerdict};
+    use crate::redaction::RedactionRequest;
+
+    fn log(trace: &mut SerendipityTrace, i: usize) {
+        let language = if i.is_multiple_of(2) { "en" } else { "id" };
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", &format!("out {}", i), language, 0.5, 0.8).unwrap();
+    }
+
+    fn rehash(trace: &SerendipityTrace) -> String {
+        trace.compute_provenance_hash_with(HashVersion::CURRENT)
+    }
+
+    #[test]
+    fn test_running_hash_equals_batch_hash() {
+        let mut trace = SerendipityTrace::new_chained("sari", "backend", "Journavx", "v1:prev");
+        assert!(trace.running_provenance_hash().is_none());
+        for i in 0..50 {
+            log(&mut trace, i);
+            assert_eq!(trace.running_provenance_hash().unwrap(), rehash(&trace));
+        }
+        trace.review.verdicts.push(ReviewVerdict::on_trace("reviewer", ReviewDecision::Approve));
+        trace.add_co_contributor("budi");
+        assert_eq!(trace.compute_provenance_hash(), rehash(&trace));
+    }
+
+    #[test]
+    fn test_stale_state_falls_back_to_rehash() {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        log(&mut trace, 0);
+        log(&mut trace, 1);
+
+        // A reassigned ID invalidates the running state
+        trace.trace_id = "renamed".to_string();
+        assert!(trace.running_provenance_hash().is_none());
+        assert_eq!(trace.compute_provenance_hash(), rehash(&trace));
+        log(&mut trace, 2);
+        assert!(trace.running_provenance_hash().is_some());
+
+        // Deserialized traces start without state and rebuild on the next event
+        let mut restored: SerendipityTrace = serde_json::from_str(&trace.to_json().unwrap()).unwrap();
+        assert!(restored.running_provenance_hash().is_none());
+        assert_eq!(restored.compute_provenance_hash(), trace.compute_provenance_hash());
+        log(&mut restored, 3);
+        assert_eq!(restored.running_provenance_hash().unwrap(), rehash(&restored));
+
+        // A merged retry raises a logged event's confidence
+        let retry = SerendipityEventBuilder::new(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out 3", "id")
+            .serendipity(0.5)
+            .confidence(0.95);
+        restored.log_event_idempotent(retry).unwrap();
+        assert_eq!(restored.compute_provenance_hash(), rehash(&restored));
+    }
+
+    #[test]
+    fn test_running_hash_survives_merge_redaction_and_restore() {
+        let mut ours = SerendipityTrace::new("sari", "backend", "Journavx");
+        log(&mut ours, 0);
+        log(&mut ours, 1);
+        let mut theirs = ours.clone();
+        log(&mut theirs, 2);
+        log(&mut theirs, 3);
+
+        // The merge keeps our running state, which covers only our events
+        let mut merged = ours.merge(&theirs).unwrap();
+        assert_eq!(merged.compute_provenance_hash(), rehash(&merged));
+        log(&mut merged, 4);
+        assert_eq!(merged.compute_provenance_hash(), rehash(&merged));
+
+        let request = RedactionRequest {
+            event_id: merged.events[1].event_id.clone(),
+            requested_by: "sari".to_string(),
+            reason: "ip".to_string(),
+        };
+        merged.redact_event(&request, b"salt").unwrap();
+        log(&mut merged, 5);
+        assert_eq!(merged.compute_provenance_hash(), rehash(&merged));
+
+        let mut restored: SerendipityTrace = serde_json::from_str(&merged.to_json().unwrap()).unwrap();
+        assert_eq!(restored.compute_provenance_hash(), merged.compute_provenance_hash());
+        log(&mut restored, 6);
+        assert!(restored.running_provenance_hash().is_some());
+        assert_eq!(restored.compute_provenance_hash(), rehash(&restored));
+    }
+}
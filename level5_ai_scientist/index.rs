@@ -0,0 +1,305 @@
+// -*- coding: utf-8 -*-
+//! Secondary Indexes for Trace Stores
+//!
+//! Without an index, a `TraceQuery` loads every stored trace.
+//! `IndexedTraceStore` wraps a store (typically an `FsTraceStore`) and keeps
+//! an in-memory `TraceIndex` by contributor, discovery name, event language,
+//! event stage, creation time, and peak event serendipity. It is built when
+//! the store is opened, updated on every save and delete through the wrapper,
+//! and rebuilt with `reindex()` after the directory was changed behind its
+//! back. The index only narrows candidates; `TraceQuery::run` still checks
+//! each candidate, so a stale index can cost a miss but never a false match.
+
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::RwLock;
+use crate::serendipity_trace::SerendipityTrace;
+use crate::language_registry::LanguageRegistry;
+use crate::query::{wildcard_match, TraceQuery};
+use crate::store::TraceStore;
+use crate::error::SerenQaResult;
+
+/// Index keys of one trace, kept so the trace can be unindexed
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    contributor_id: String,
+    discovery_name: String,
+    languages: BTreeSet<String>,
+    stages: BTreeSet<String>,
+    created_at: DateTime<Utc>,
+    max_serendipity: f64,
+}
+
+type Postings = BTreeMap<String, BTreeSet<String>>;
+
+fn post(postings: &mut Postings, key: &str, trace_id: &str) {
+    postings.entry(key.to_string()).or_default().insert(trace_id.to_string());
+}
+
+fn unpost(postings: &mut Postings, key: &str, trace_id: &str) {
+    if let Some(ids) = postings.get_mut(key) {
+        ids.remove(trace_id);
+        if ids.is_empty() {
+            postings.remove(key);
+        }
+    }
+}
+
+/// Trace IDs under any key matching one of `patterns`
+fn matching_patterns(postings: &Postings, patterns: &[String]) -> BTreeSet<String> {
+    postings
+        .iter()
+        .filter(|(key, _)| patterns.iter().any(|pattern| wildcard_match(pattern, key)))
+        .flat_map(|(_, ids)| ids.iter().cloned())
+        .collect()
+}
+
+/// Trace IDs under any of `keys`
+fn matching_keys<'a>(postings: &Postings, keys: impl Iterator<Item = &'a String>) -> BTreeSet<String> {
+    keys.filter_map(|key| postings.get(key)).flatten().cloned().collect()
+}
+
+/// In-memory secondary indexes over a set of traces
+#[derive(Debug, Clone, Default)]
+pub struct TraceIndex {
+    entries: HashMap<String, IndexEntry>,
+    by_contributor: Postings,
+    by_discovery: Postings,
+    by_language: Postings,
+    by_stage: Postings,
+    by_created: BTreeMap<DateTime<Utc>, BTreeSet<String>>,
+}
+
+impl TraceIndex {
+    /// Empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of indexed traces
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no trace is indexed
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Index `trace`, replacing its previous entry
+    pub fn insert(&mut self, trace: &SerendipityTrace) {
+        self.remove(&trace.trace_id);
+        let registry = LanguageRegistry::global();
+        let entry = IndexEntry {
+            contributor_id: trace.contributor_id.clone(),
+            discovery_name: trace.discovery_name.clone(),
+            languages: trace.events.iter().map(|event| registry.canonical(&event.language)).collect(),
+            stages: trace.events.iter().map(|event| event.stage.name()).collect(),
+            created_at: trace.created_at,
+            max_serendipity: trace.events.iter().map(|event| event.serendipity_score).fold(f64::NEG_INFINITY, f64::max),
+        };
+        let id = trace.trace_id.as_str();
+        post(&mut self.by_contributor, &entry.contributor_id, id);
+        post(&mut self.by_discovery, &entry.discovery_name, id);
+        for language in &entry.languages {
+            post(&mut self.by_language, language, id);
+        }
+        for stage in &entry.stages {
+            post(&mut self.by_stage, stage, id);
+        }
+        self.by_created.entry(entry.created_at).or_default().insert(id.to_string());
+        self.entries.insert(id.to_string(), entry);
+    }
+
+    /// Unindex a trace; returns whether it was indexed
+    pub fn remove(&mut self, trace_id: &str) -> bool {
+        let Some(entry) = self.entries.remove(trace_id) else { return false };
+        unpost(&mut self.by_contributor, &entry.contributor_id, trace_id);
+        unpost(&mut self.by_discovery, &entry.discovery_name, trace_id);
+        for language in &entry.languages {
+            unpost(&mut self.by_language, language, trace_id);
+        }
+        for stage in &entry.stages {
+            unpost(&mut self.by_stage, stage, trace_id);
+        }
+        if let Some(ids) = self.by_created.get_mut(&entry.created_at) {
+            ids.remove(trace_id);
+            if ids.is_empty() {
+                self.by_created.remove(&entry.created_at);
+            }
+        }
+        true
+    }
+
+    /// Drop every entry
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// IDs of the indexed traces that may match `query`, sorted
+    pub fn candidates(&self, query: &TraceQuery) -> Vec<String> {
+        let mut narrowed: Option<BTreeSet<String>> = None;
+        let mut narrow = |ids: BTreeSet<String>| {
+            narrowed = Some(match narrowed.take() {
+                Some(current) => current.intersection(&ids).cloned().collect(),
+                None => ids,
+            });
+        };
+
+        if !query.contributors.is_empty() {
+            narrow(matching_patterns(&self.by_contributor, &query.contributors));
+        }
+        if !query.discoveries.is_empty() {
+            narrow(matching_patterns(&self.by_discovery, &query.discoveries));
+        }
+        if !query.languages.is_empty() {
+            let registry = LanguageRegistry::global();
+            let languages: Vec<String> = query.languages.iter().map(|l| registry.canonical(l)).collect();
+            narrow(matching_keys(&self.by_language, languages.iter()));
+        }
+        if !query.stages.is_empty() {
+            let stages: Vec<String> = query.stages.iter().map(|stage| stage.name()).collect();
+            narrow(matching_keys(&self.by_stage, stages.iter()));
+        }
+        if query.created_after.is_some() || query.created_before.is_some() {
+            let ids = self
+                .by_created
+                .iter()
+                .filter(|(at, _)| query.created_after.is_none_or(|after| **at >= after))
+                .filter(|(at, _)| query.created_before.is_none_or(|before| **at < before))
+                .flat_map(|(_, ids)| ids.iter().cloned())
+                .collect();
+            narrow(ids);
+        }
+
+        let mut ids: Vec<String> = match narrowed {
+            Some(ids) => ids.into_iter().collect(),
+            None => self.entries.keys().cloned().collect(),
+        };
+        if let Some(min) = query.min_serendipity {
+            ids.retain(|id| self.entries[id].max_serendipity >= min);
+        }
+        ids.sort();
+        ids
+    }
+}
+
+/// Store wrapper keeping a `TraceIndex` over its traces
+pub struct IndexedTraceStore<S: TraceStore> {
+    inner: S,
+    index: RwLock<TraceIndex>,
+}
+
+impl<S: TraceStore> IndexedTraceStore<S> {
+    /// Wrap `inner`, indexing the traces it already holds
+    pub fn new(inner: S) -> SerenQaResult<Self> {
+        let store = Self { inner, index: RwLock::new(TraceIndex::new()) };
+        store.reindex()?;
+        Ok(store)
+    }
+
+    /// Wrapped store
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Rebuild the index from the wrapped store, e.g. after files were
+    /// added or edited outside it; returns the number of traces indexed
+    pub fn reindex(&self) -> SerenQaResult<usize> {
+        let mut index = TraceIndex::new();
+        for trace_id in self.inner.list()? {
+            if let Some(trace) = self.inner.load(&trace_id)? {
+                index.insert(&trace);
+            }
+        }
+        let count = index.len();
+        *self.index.write().unwrap_or_else(|e| e.into_inner()) = index;
+        Ok(count)
+    }
+
+    /// Number of indexed traces
+    pub fn indexed(&self) -> usize {
+        self.index.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl<S: TraceStore> TraceStore for IndexedTraceStore<S> {
+    fn save(&self, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        self.inner.save(trace)?;
+        self.index.write().unwrap_or_else(|e| e.into_inner()).insert(trace);
+        Ok(())
+    }
+
+    fn load(&self, trace_id: &str) -> SerenQaResult<Option<SerendipityTrace>> {
+        self.inner.load(trace_id)
+    }
+
+    fn delete(&self, trace_id: &str) -> SerenQaResult<bool> {
+        let existed = self.inner.delete(trace_id)?;
+        self.index.write().unwrap_or_else(|e| e.into_inner()).remove(trace_id);
+        Ok(existed)
+    }
+
+    fn list(&self) -> SerenQaResult<Vec<String>> {
+        self.inner.list()
+    }
+
+    fn query_candidates(&self, query: &TraceQuery) -> SerenQaResult<Option<Vec<String>>> {
+        Ok(Some(self.index.read().unwrap_or_else(|e| e.into_inner()).candidates(query)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+    use crate::store::{FsTraceStore, InMemoryTraceStore};
+    use chrono::TimeZone;
+
+    fn trace(contributor_id: &str, trace_id: &str, day: u32, language: &str, score: f64) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor_id, "backend", "Journavx");
+        trace.trace_id = trace_id.to_string();
+        trace.created_at = Utc.with_ymd_and_hms(2026, 3, day, 9, 0, 0).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "out", language, score, 0.8).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_index_narrows_and_tracks_updates() {
+        let store = IndexedTraceStore::new(InMemoryTraceStore::new()).unwrap();
+        store.save(&trace("dr_sari", "t1", 1, "id", 0.95)).unwrap();
+        store.save(&trace("dr_budi", "t2", 2, "en", 0.95)).unwrap();
+        store.save(&trace("ayu", "t3", 3, "id", 0.5)).unwrap();
+
+        let query = TraceQuery::new().contributor("dr_*").language("id");
+        assert_eq!(store.query_candidates(&query).unwrap(), Some(vec!["t1".to_string()]));
+        let since = TraceQuery::new().created_after(Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap()).min_serendipity(0.9);
+        assert_eq!(store.query_candidates(&since).unwrap(), Some(vec!["t2".to_string()]));
+        assert_eq!(query.run(&store).unwrap().len(), 1);
+
+        // Saves replace index entries and deletes drop them
+        store.save(&trace("dr_sari", "t1", 1, "en", 0.95)).unwrap();
+        assert_eq!(store.query_candidates(&query).unwrap(), Some(Vec::new()));
+        store.delete("t2").unwrap();
+        assert_eq!(store.query_candidates(&TraceQuery::new().language("en")).unwrap(), Some(vec!["t1".to_string()]));
+        assert_eq!(store.indexed(), 2);
+    }
+
+    #[test]
+    fn test_reindex_picks_up_outside_changes() {
+        let dir = std::env::temp_dir().join(format!("seren_index_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let fs = FsTraceStore::open(&dir).unwrap();
+        fs.save(&trace("sari", "t1", 1, "id", 0.9)).unwrap();
+
+        let store = IndexedTraceStore::new(FsTraceStore::open(&dir).unwrap()).unwrap();
+        assert_eq!(store.indexed(), 1);
+        // Written behind the index's back
+        fs.save(&trace("budi", "t2", 2, "id", 0.9)).unwrap();
+        let query = TraceQuery::new().contributor("budi");
+        assert!(query.run(&store).unwrap().is_empty());
+        assert_eq!(store.reindex().unwrap(), 2);
+        assert_eq!(query.run(&store).unwrap()[0].trace_id, "t2");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
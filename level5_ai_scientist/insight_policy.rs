@@ -0,0 +1,124 @@
+// -*- coding: utf-8 -*-
+//! Key-Discovery Threshold Policies
+//!
+//! `fold_memory` keeps events whose serendipity exceeds a threshold as key
+//! discoveries. A fixed 0.7 misbehaves on traces whose scores cluster high
+//! (everything is "key") or low (nothing is). Each trace carries an
+//! `InsightPolicy`; the adaptive modes derive the threshold from the trace's
+//! own score distribution, and the chosen threshold is recorded in the fold.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::SerendipityEvent;
+
+/// Fixed key-discovery threshold used by default
+pub const DEFAULT_KEY_DISCOVERY_THRESHOLD: f64 = 0.7;
+
+/// How the key-discovery threshold is chosen
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum InsightPolicy {
+    /// Events scoring above `threshold` are key discoveries
+    Fixed { threshold: f64 },
+    /// Events above the `percentile` (0-1) of the trace's scores are key
+    /// discoveries, so `percentile: 0.8` keeps roughly the top 20%
+    Percentile { percentile: f64 },
+    /// Otsu split: the threshold maximizing between-class variance of the
+    /// trace's scores separates key discoveries from the rest
+    Otsu,
+}
+
+impl Default for InsightPolicy {
+    fn default() -> Self {
+        InsightPolicy::Fixed { threshold: DEFAULT_KEY_DISCOVERY_THRESHOLD }
+    }
+}
+
+impl InsightPolicy {
+    /// Threshold for `events`. Adaptive modes fall back to the default
+    /// threshold when the scores cannot be split (fewer than two distinct values).
+    pub fn threshold(&self, events: &[SerendipityEvent]) -> f64 {
+        // Fixed thresholds are applied on every incremental fold; skip the sort
+        if let InsightPolicy::Fixed { threshold } = *self {
+            return threshold;
+        }
+        let mut scores: Vec<f64> = events.iter().map(|e| e.serendipity_score).collect();
+        scores.sort_by(f64::total_cmp);
+        let splittable = scores.first() != scores.last();
+
+        match *self {
+            InsightPolicy::Fixed { threshold } => threshold,
+            _ if !splittable => DEFAULT_KEY_DISCOVERY_THRESHOLD,
+            InsightPolicy::Percentile { percentile } => {
+                let n = scores.len();
+                let kept = ((1.0 - percentile.clamp(0.0, 1.0)) * n as f64).ceil() as usize;
+                // Keep at least one event and leave at least one below the threshold
+                scores[n - kept.clamp(1, n - 1) - 1]
+            }
+            InsightPolicy::Otsu => otsu_threshold(&scores),
+        }
+    }
+}
+
+/// Otsu threshold over sorted scores: the largest score of the low class
+fn otsu_threshold(sorted: &[f64]) -> f64 {
+    let n = sorted.len() as f64;
+    let total: f64 = sorted.iter().sum();
+    let mut best = (f64::MIN, sorted[0]);
+    let mut low_sum = 0.0;
+    for (i, score) in sorted.iter().enumerate().take(sorted.len() - 1) {
+        low_sum += score;
+        if *score == sorted[i + 1] {
+            continue;
+        }
+        let low_n = (i + 1) as f64;
+        let high_n = n - low_n;
+        let low_mean = low_sum / low_n;
+        let high_mean = (total - low_sum) / high_n;
+        let between = low_n * high_n * (low_mean - high_mean).powi(2);
+        if between > best.0 {
+            best = (between, *score);
+        }
+    }
+    best.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, SerendipityAgent};
+
+    fn trace_with(scores: &[f64], policy: InsightPolicy) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.set_insight_policy(policy);
+        for (i, score) in scores.iter().enumerate() {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", &format!("step {}", i), "en", *score, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_adaptive_thresholds_on_clustered_scores() {
+        let high = [0.82, 0.85, 0.84, 0.97, 0.83];
+        let fixed = trace_with(&high, InsightPolicy::default()).fold_memory().unwrap();
+        assert_eq!(fixed.key_discoveries.len(), 5);
+        assert_eq!(fixed.key_discovery_threshold, DEFAULT_KEY_DISCOVERY_THRESHOLD);
+
+        let otsu = trace_with(&high, InsightPolicy::Otsu).fold_memory().unwrap();
+        assert_eq!(otsu.key_discoveries, vec!["Exploration: step 3".to_string()]);
+        assert_eq!(otsu.key_discovery_threshold, 0.85);
+
+        let low = [0.1, 0.2, 0.15, 0.45, 0.5];
+        let percentile = trace_with(&low, InsightPolicy::Percentile { percentile: 0.6 }).fold_memory().unwrap();
+        assert_eq!(percentile.key_discoveries.len(), 2);
+        assert_eq!(percentile.key_discovery_threshold, 0.2);
+    }
+
+    #[test]
+    fn test_unsplittable_scores_fall_back() {
+        let flat = trace_with(&[0.9, 0.9], InsightPolicy::Otsu);
+        assert_eq!(InsightPolicy::Otsu.threshold(&flat.events), DEFAULT_KEY_DISCOVERY_THRESHOLD);
+        assert_eq!(InsightPolicy::Percentile { percentile: 1.0 }.threshold(&[]), DEFAULT_KEY_DISCOVERY_THRESHOLD);
+        let policy: InsightPolicy = serde_json::from_str(r#"{"mode":"percentile","percentile":0.9}"#).unwrap();
+        assert_eq!(policy, InsightPolicy::Percentile { percentile: 0.9 });
+    }
+}
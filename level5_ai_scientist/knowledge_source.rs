@@ -0,0 +1,185 @@
+// -*- coding: utf-8 -*-
+//! Cultural Knowledge Source Citations
+//!
+//! Structured references from events to the knowledge they draw on (oral
+//! traditions, local experts, texts, datasets), with the source language and
+//! cultural origin. Folds aggregate them into credits so discoveries like
+//! Journavx formally acknowledge cultural knowledge instead of burying it in
+//! free text.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::SerendipityEvent;
+use crate::language_tag::is_valid_bcp47;
+
+/// Kind of knowledge source
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum KnowledgeSourceKind {
+    /// Orally transmitted tradition
+    OralTradition,
+    /// Community or local expert
+    LocalExpert,
+    /// Written text (book, manuscript, article)
+    Text,
+    /// Dataset
+    Dataset,
+}
+
+/// Reference to a knowledge source used by an event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct KnowledgeSource {
+    /// Kind of source
+    pub kind: KnowledgeSourceKind,
+    /// Name or title of the source
+    pub title: String,
+    /// Language the knowledge is held in (BCP-47)
+    pub language: String,
+    /// Culture or community the knowledge originates from
+    #[serde(default)]
+    pub cultural_origin: Option<String>,
+    /// Citation, URI, or archive reference
+    #[serde(default)]
+    pub reference: Option<String>,
+}
+
+impl KnowledgeSource {
+    /// Create a source reference
+    pub fn new(kind: KnowledgeSourceKind, title: &str, language: &str) -> Self {
+        Self {
+            kind,
+            title: title.to_string(),
+            language: language.to_string(),
+            cultural_origin: None,
+            reference: None,
+        }
+    }
+
+    /// Set the cultural origin
+    pub fn cultural_origin(mut self, origin: &str) -> Self {
+        self.cultural_origin = Some(origin.to_string());
+        self
+    }
+
+    /// Set the citation or URI
+    pub fn reference(mut self, reference: &str) -> Self {
+        self.reference = Some(reference.to_string());
+        self
+    }
+
+    /// Describe why the source is malformed, if it is
+    pub fn problem(&self) -> Option<String> {
+        if self.title.trim().is_empty() {
+            return Some("knowledge source title is empty".to_string());
+        }
+        if !is_valid_bcp47(&self.language) {
+            return Some(format!("knowledge source {:?} has invalid language tag {:?}", self.title, self.language));
+        }
+        None
+    }
+
+    /// Bytes fed to the provenance hash
+    pub(crate) fn hash_bytes(&self) -> String {
+        format!(
+            "{:?}|{}|{}|{}|{}",
+            self.kind,
+            self.title,
+            self.language,
+            self.cultural_origin.as_deref().unwrap_or_default(),
+            self.reference.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// A knowledge source and the events that drew on it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KnowledgeCredit {
+    /// Source credited
+    pub source: KnowledgeSource,
+    /// Events citing the source, in trace order
+    pub event_ids: Vec<String>,
+}
+
+/// Add an event's sources to running credits, merging repeated citations
+pub(crate) fn credit_event(credits: &mut Vec<KnowledgeCredit>, event: &SerendipityEvent) {
+    for source in &event.knowledge_sources {
+        match credits.iter_mut().find(|c| &c.source == source) {
+            Some(credit) => {
+                if !credit.event_ids.contains(&event.event_id) {
+                    credit.event_ids.push(event.event_id.clone());
+                }
+            }
+            None => credits.push(KnowledgeCredit {
+                source: source.clone(),
+                event_ids: vec![event.event_id.clone()],
+            }),
+        }
+    }
+}
+
+/// Credits for every source cited by `events`, in order of first citation
+pub fn knowledge_credits(events: &[SerendipityEvent]) -> Vec<KnowledgeCredit> {
+    let mut credits = Vec::new();
+    for event in events {
+        credit_event(&mut credits, event);
+    }
+    credits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{
+        SerendipityTrace, SerendipityEventBuilder, SerendipityStage, SerendipityAgent,
+    };
+
+    fn wayfinding() -> KnowledgeSource {
+        KnowledgeSource::new(KnowledgeSourceKind::OralTradition, "Javanese wayfinding", "jv")
+            .cultural_origin("Javanese")
+    }
+
+    #[test]
+    fn test_fold_aggregates_credits() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        for (language, sources) in [("id", vec![wayfinding()]), ("en", vec![]), ("id", vec![wayfinding(), KnowledgeSource::new(KnowledgeSourceKind::LocalExpert, "Navigation elders", "id")])] {
+            let mut builder = SerendipityEventBuilder::new(
+                SerendipityStage::Validation,
+                SerendipityAgent::Validator,
+                "input",
+                "output",
+                language,
+            ).serendipity(0.8).confidence(0.9);
+            for source in sources {
+                builder = builder.knowledge_source(source);
+            }
+            trace.log(builder).unwrap();
+        }
+
+        let folded = trace.fold_memory().unwrap();
+        assert_eq!(folded.knowledge_sources.len(), 2);
+        assert_eq!(folded.knowledge_sources[0].source, wayfinding());
+        assert_eq!(folded.knowledge_sources[0].event_ids, vec![trace.events[0].event_id.clone(), trace.events[2].event_id.clone()]);
+        assert_eq!(folded.knowledge_sources[1].source.kind, KnowledgeSourceKind::LocalExpert);
+    }
+
+    #[test]
+    fn test_sources_are_validated_and_hashed() {
+        let bad = KnowledgeSource::new(KnowledgeSourceKind::Text, "Serat Centhini", "not a tag");
+        let builder = SerendipityEventBuilder::new(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input",
+            "output",
+            "en",
+        );
+        assert!(builder.knowledge_source(bad).build().is_err());
+
+        let mut cited = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        cited.log(
+            SerendipityEventBuilder::new(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en")
+                .knowledge_source(wayfinding()),
+        ).unwrap();
+        let hash = cited.compute_provenance_hash();
+        cited.events[0].knowledge_sources.clear();
+        assert!(!cited.verify_provenance_hash(&hash));
+    }
+}
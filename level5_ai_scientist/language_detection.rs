@@ -0,0 +1,245 @@
+// -*- coding: utf-8 -*-
+//! Language Detection for Logged Events
+//!
+//! `log_event` otherwise trusts the caller's language tag. With a detector
+//! attached, every logged event's text is checked against its tag: in `Flag`
+//! mode a disagreement is recorded as a `LanguageMismatch` annotation; in
+//! `Correct` mode the detected tag also replaces the declared one before the
+//! event is appended, so language diversity, transitions, and distribution
+//! metrics use the corrected tag. Tags are compared by primary language
+//! subtag ("en-US" matches "en"). The `whatlang` feature provides
+//! `WhatlangDetector`; any other model can implement `LanguageDetector`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEvent};
+use crate::annotation::AnnotationKind;
+
+/// Detections below this confidence are ignored by default
+pub const DEFAULT_MIN_DETECTION_CONFIDENCE: f64 = 0.5;
+
+/// Text shorter than this (in characters) is not checked by default
+pub const DEFAULT_MIN_DETECTION_CHARS: usize = 20;
+
+/// Language detected in a piece of text
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedLanguage {
+    /// BCP-47 tag (e.g., "id")
+    pub language: String,
+    /// Detector confidence in [0, 1]
+    pub confidence: f64,
+}
+
+/// Model detecting the language of text
+pub trait LanguageDetector: Send + Sync {
+    /// Detected language, or `None` if the text is inconclusive
+    fn detect(&self, text: &str) -> Option<DetectedLanguage>;
+}
+
+impl<F> LanguageDetector for F
+where
+    F: Fn(&str) -> Option<DetectedLanguage> + Send + Sync,
+{
+    fn detect(&self, text: &str) -> Option<DetectedLanguage> {
+        self(text)
+    }
+}
+
+/// What to do when the detected language disagrees with the tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LanguageCheckMode {
+    /// Keep the declared tag and annotate the mismatch
+    #[default]
+    Flag,
+    /// Replace the declared tag with the detected one and annotate it
+    Correct,
+}
+
+/// Declared language tag that disagrees with the event text
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LanguageMismatch {
+    /// Tag supplied by the caller
+    pub declared: String,
+    /// Tag detected from the event text
+    pub detected: String,
+    /// Detector confidence
+    pub confidence: f64,
+    /// Whether the event's tag was replaced with the detected one
+    pub corrected: bool,
+}
+
+/// Detector attached to a trace with its settings
+#[derive(Clone)]
+pub struct LanguageCheck {
+    detector: Arc<dyn LanguageDetector>,
+    /// Handling of mismatches
+    pub mode: LanguageCheckMode,
+    /// Detections below this confidence are ignored
+    pub min_confidence: f64,
+    /// Text shorter than this (in characters) is not checked
+    pub min_chars: usize,
+}
+
+impl std::fmt::Debug for LanguageCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LanguageCheck")
+            .field("mode", &self.mode)
+            .field("min_confidence", &self.min_confidence)
+            .field("min_chars", &self.min_chars)
+            .finish()
+    }
+}
+
+/// Primary language subtag, lowercased
+fn primary_subtag(tag: &str) -> String {
+    tag.split('-').next().unwrap_or_default().to_ascii_lowercase()
+}
+
+impl LanguageCheck {
+    /// Check events with `detector` using the default thresholds
+    pub fn new<D: LanguageDetector + 'static>(detector: D, mode: LanguageCheckMode) -> Self {
+        Self {
+            detector: Arc::new(detector),
+            mode,
+            min_confidence: DEFAULT_MIN_DETECTION_CONFIDENCE,
+            min_chars: DEFAULT_MIN_DETECTION_CHARS,
+        }
+    }
+
+    /// Ignore detections below `confidence`
+    pub fn min_confidence(mut self, confidence: f64) -> Self {
+        self.min_confidence = confidence;
+        self
+    }
+
+    /// Skip text shorter than `chars` characters
+    pub fn min_chars(mut self, chars: usize) -> Self {
+        self.min_chars = chars;
+        self
+    }
+
+    /// Detect the language of the event's output (or its input, if the output
+    /// is too short). Returns the mismatch, after correcting the tag in
+    /// `Correct` mode; `None` if the text agrees or is inconclusive.
+    pub fn apply(&self, event: &mut SerendipityEvent) -> Option<LanguageMismatch> {
+        let text = [&event.output, &event.input]
+            .into_iter()
+            .find(|text| text.chars().count() >= self.min_chars)?;
+        let detected = self.detector
+            .detect(text)
+            .filter(|d| d.confidence >= self.min_confidence)?;
+        if primary_subtag(&detected.language) == primary_subtag(&event.language) {
+            return None;
+        }
+
+        let corrected = self.mode == LanguageCheckMode::Correct;
+        let mismatch = LanguageMismatch {
+            declared: event.language.clone(),
+            detected: detected.language.clone(),
+            confidence: detected.confidence,
+            corrected,
+        };
+        if corrected {
+            event.language = detected.language;
+        }
+        Some(mismatch)
+    }
+}
+
+impl SerendipityTrace {
+    /// Check the language of events logged from now on
+    pub fn set_language_check(&mut self, check: LanguageCheck) {
+        self.language_check = Some(check);
+    }
+
+    /// Language mismatches found while logging, with the event IDs
+    pub fn language_mismatches(&self) -> impl Iterator<Item = (&str, &LanguageMismatch)> {
+        self.annotations.iter().filter_map(|note| match &note.kind {
+            AnnotationKind::LanguageMismatch(mismatch) => Some((note.event_id.as_str(), mismatch)),
+            _ => None,
+        })
+    }
+}
+
+/// Detector backed by the `whatlang` crate
+#[cfg(feature = "whatlang")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhatlangDetector;
+
+#[cfg(feature = "whatlang")]
+impl LanguageDetector for WhatlangDetector {
+    fn detect(&self, text: &str) -> Option<DetectedLanguage> {
+        let info = whatlang::detect(text)?;
+        let code = info.lang().code();
+        // whatlang reports ISO 639-3; BCP-47 prefers the two-letter code when one exists
+        let language = match code {
+            "eng" => "en",
+            "ind" => "id",
+            "jav" => "jv",
+            "spa" => "es",
+            "fra" => "fr",
+            "deu" => "de",
+            "por" => "pt",
+            "ita" => "it",
+            "nld" => "nl",
+            "rus" => "ru",
+            "cmn" => "zh",
+            "jpn" => "ja",
+            "kor" => "ko",
+            "arb" => "ar",
+            "hin" => "hi",
+            "tur" => "tr",
+            "vie" => "vi",
+            "tha" => "th",
+            other => other,
+        };
+        Some(DetectedLanguage { language: language.to_string(), confidence: info.confidence() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    /// Stub detector: Indonesian if the text mentions "dengan", else English
+    fn stub(text: &str) -> Option<DetectedLanguage> {
+        let language = if text.contains("dengan") { "id" } else { "en" };
+        Some(DetectedLanguage { language: language.to_string(), confidence: 0.9 })
+    }
+
+    fn log(trace: &mut SerendipityTrace, output: &str, language: &str) {
+        trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "check", output, language, 0.8, 0.9).unwrap();
+    }
+
+    #[test]
+    fn test_flag_mode_annotates_without_changing_tags() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.set_language_check(LanguageCheck::new(stub, LanguageCheckMode::Flag));
+        log(&mut trace, "Rute dikonfirmasi dengan para ahli", "en");
+        log(&mut trace, "Route confirmed with the local experts", "en-US");
+        log(&mut trace, "ok dengan", "en");
+
+        let mismatches: Vec<_> = trace.language_mismatches().collect();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].0, trace.events[0].event_id);
+        assert_eq!(mismatches[0].1.detected, "id");
+        assert!(!mismatches[0].1.corrected);
+        assert_eq!(trace.events[0].language, "en");
+    }
+
+    #[test]
+    fn test_correct_mode_feeds_corrected_tags_into_languages() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.set_language_check(LanguageCheck::new(stub, LanguageCheckMode::Correct).min_chars(10));
+        log(&mut trace, "Route confirmed with the local experts", "en");
+        log(&mut trace, "Rute dikonfirmasi dengan para ahli", "en");
+
+        assert_eq!(trace.events[1].language, "id");
+        assert_eq!(trace.languages, vec!["en".to_string(), "id".to_string()]);
+        assert_eq!(trace.transitions[0].language_shift, Some(("en".to_string(), "id".to_string())));
+        let (_, mismatch) = trace.language_mismatches().next().unwrap();
+        assert_eq!(mismatch.declared, "en");
+        assert!(mismatch.corrected);
+    }
+}
@@ -0,0 +1,175 @@
+// -*- coding: utf-8 -*-
+//! Language Registry and Tag Canonicalization
+//!
+//! Language tags arrive as free-form strings ("en", "EN", "english"), which
+//! splits language counts across spellings. The registry, loaded from a data
+//! file, canonicalizes tags (casing, aliases and deprecated codes, individual
+//! languages folded into their macrolanguage) and stores script and family
+//! information centrally. Traces canonicalize event languages when logging,
+//! contributor statistics canonicalize the languages they record, and the
+//! pipeline takes script and family from the registry.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+use crate::language_tag::{is_valid_bcp47, normalize_case};
+
+/// Language entry as stored in the data file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LanguageInfo {
+    /// Canonical primary language subtag (e.g., "id")
+    pub tag: String,
+    /// English name
+    pub name: String,
+    /// Usual script (e.g., "Latin")
+    pub script: String,
+    /// Language family (e.g., "Austronesian")
+    pub family: String,
+    /// Alternative codes and names resolving to this language
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Individual-language codes folded into this macrolanguage
+    #[serde(default)]
+    pub includes: Vec<String>,
+}
+
+/// Canonical language tags with script and family info
+#[derive(Debug, Clone, Default)]
+pub struct LanguageRegistry {
+    /// Canonical tag -> entry
+    languages: BTreeMap<String, LanguageInfo>,
+    /// Lowercased tag, alias, or included code -> canonical tag
+    lookup: HashMap<String, String>,
+}
+
+impl LanguageRegistry {
+    /// Create a registry from language entries
+    pub fn new(languages: Vec<LanguageInfo>) -> Self {
+        let mut registry = Self::default();
+        for info in languages {
+            registry.register(info);
+        }
+        registry
+    }
+
+    /// Load a registry from a JSON array of entries
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let languages: Vec<LanguageInfo> = serde_json::from_str(json)?;
+        Ok(Self::new(languages))
+    }
+
+    /// Load a registry from a JSON data file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Built-in registry shipped in `data/languages.json`
+    pub fn builtin() -> Self {
+        Self::from_json(include_str!("data/languages.json")).expect("built-in language registry is valid JSON")
+    }
+
+    /// Shared built-in registry used by traces, statistics, and the pipeline
+    pub fn global() -> &'static LanguageRegistry {
+        static GLOBAL: OnceLock<LanguageRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(Self::builtin)
+    }
+
+    /// Add or replace a language entry
+    pub fn register(&mut self, info: LanguageInfo) {
+        let tag = info.tag.to_lowercase();
+        for code in std::iter::once(&tag).chain(&info.aliases).chain(&info.includes) {
+            self.lookup.insert(code.to_lowercase(), tag.clone());
+        }
+        self.languages.insert(tag, info);
+    }
+
+    /// Registered languages ordered by tag
+    pub fn languages(&self) -> impl Iterator<Item = &LanguageInfo> {
+        self.languages.values()
+    }
+
+    /// Canonical form of a tag, or `None` if it is not well-formed BCP-47.
+    /// Unregistered languages are only re-cased ("XX-yy" -> "xx-YY").
+    pub fn canonicalize(&self, tag: &str) -> Option<String> {
+        let tag = tag.trim().replace('_', "-");
+        if let Some(canonical) = self.lookup.get(&tag.to_lowercase()) {
+            return Some(canonical.clone());
+        }
+        let (primary, rest) = match tag.split_once('-') {
+            Some((primary, rest)) => (primary, Some(rest)),
+            None => (tag.as_str(), None),
+        };
+        let primary = self.lookup.get(&primary.to_lowercase()).map_or(primary, String::as_str);
+        let canonical = normalize_case(&match rest {
+            Some(rest) => format!("{}-{}", primary, rest),
+            None => primary.to_string(),
+        });
+        is_valid_bcp47(&canonical).then_some(canonical)
+    }
+
+    /// Canonical form of a tag, or the tag unchanged if it is malformed
+    /// (so validation still reports the caller's spelling)
+    pub fn canonical(&self, tag: &str) -> String {
+        self.canonicalize(tag).unwrap_or_else(|| tag.to_string())
+    }
+
+    /// Entry for a tag's primary language
+    pub fn info(&self, tag: &str) -> Option<&LanguageInfo> {
+        let canonical = self.canonicalize(tag)?;
+        let primary = canonical.split('-').next().unwrap_or_default();
+        self.languages.get(primary)
+    }
+
+    /// Usual script of a tag's language
+    pub fn script(&self, tag: &str) -> Option<&str> {
+        self.info(tag).map(|info| info.script.as_str())
+    }
+
+    /// Family of a tag's language
+    pub fn family(&self, tag: &str) -> Option<&str> {
+        self.info(tag).map(|info| info.family.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, SerendipityAgent};
+    use crate::ContributorStats::LanguageAwareContributorStats;
+
+    #[test]
+    fn test_canonicalize_spellings() {
+        let registry = LanguageRegistry::builtin();
+        for (tag, canonical) in [
+            ("en", "en"), ("EN", "en"), ("english", "en"), (" Eng ", "en"),
+            ("in", "id"), ("IND-id", "id-ID"), ("cmn_hant_tw", "zh-Hant-TW"), ("zsm", "ms"),
+            ("xx-yy", "xx-YY"),
+        ] {
+            assert_eq!(registry.canonicalize(tag).as_deref(), Some(canonical), "{}", tag);
+        }
+        assert_eq!(registry.canonicalize("english!"), None);
+        assert_eq!(registry.canonical("english!"), "english!");
+        assert_eq!(registry.family("jv-ID"), Some("Austronesian"));
+        assert_eq!(registry.script("Chinese"), Some("Han"));
+        assert_eq!(registry.info("xx"), None);
+    }
+
+    #[test]
+    fn test_counts_are_not_split_across_spellings() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        for language in ["en", "EN", "english", "id", "Indonesian"] {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", language, 0.5, 0.8).unwrap();
+        }
+        assert_eq!(trace.languages, vec!["en".to_string(), "id".to_string()]);
+        assert_eq!(trace.transitions.iter().filter(|t| t.language_shift.is_some()).count(), 1);
+
+        let mut stats = LanguageAwareContributorStats::new("researcher1");
+        let languages = vec!["EN".to_string(), "english".to_string(), "in".to_string()];
+        stats.add_trace(5, 0.7, 0.8, languages, 0.8, 0.8).unwrap();
+        assert_eq!(stats.languages_used, vec!["en".to_string(), "id".to_string()]);
+        assert_eq!(stats.multilingual_traces, 1);
+    }
+}
@@ -0,0 +1,130 @@
+// -*- coding: utf-8 -*-
+//! BCP-47 Language Tag Validation
+//!
+//! Well-formedness check for language tags (RFC 5646 syntax: language,
+//! optional script, region, variants, extensions, and private use).
+
+/// Conventional casing of a tag: trim, '_' to '-', lowercase language,
+/// titlecase script, uppercase region (e.g., " zh_hant_tw" -> "zh-Hant-TW")
+pub fn normalize_case(tag: &str) -> String {
+    let subtags: Vec<String> = tag
+        .trim()
+        .replace('_', "-")
+        .split('-')
+        .enumerate()
+        .map(|(i, subtag)| match (i, subtag.len()) {
+            (0, _) => subtag.to_lowercase(),
+            (_, 2) => subtag.to_uppercase(),
+            (_, 4) => {
+                let mut chars = subtag.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+                    None => String::new(),
+                }
+            }
+            _ => subtag.to_lowercase(),
+        })
+        .collect();
+    subtags.join("-")
+}
+
+/// Check if a subtag is ASCII alphanumerics of the given length range
+fn is_alnum(subtag: &str, min: usize, max: usize) -> bool {
+    (min..=max).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Check if a subtag is ASCII letters of the given length range
+fn is_alpha(subtag: &str, min: usize, max: usize) -> bool {
+    (min..=max).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Check if a private-use sequence ("x-" followed by 1-8 alphanumerics) is well formed
+fn is_private_use(subtags: &[&str]) -> bool {
+    !subtags.is_empty() && subtags.iter().all(|s| is_alnum(s, 1, 8))
+}
+
+/// Check if a language tag is a well-formed BCP-47 tag (e.g., "en", "id", "zh-Hant-TW")
+pub fn is_valid_bcp47(tag: &str) -> bool {
+    let subtags: Vec<&str> = tag.split('-').collect();
+    if subtags.iter().any(|s| s.is_empty()) {
+        return false;
+    }
+
+    // Entirely private-use tag
+    if subtags[0].eq_ignore_ascii_case("x") {
+        return is_private_use(&subtags[1..]);
+    }
+
+    // Primary language: 2-3 letters (optionally extlang) or 4-8 letters
+    if !is_alpha(subtags[0], 2, 3) && !is_alpha(subtags[0], 4, 8) {
+        return false;
+    }
+
+    let mut i = 1;
+    // Extended language subtags (up to 3, only after a 2-3 letter language)
+    let mut extlangs = 0;
+    while subtags[0].len() <= 3 && i < subtags.len() && extlangs < 3 && is_alpha(subtags[i], 3, 3) {
+        i += 1;
+        extlangs += 1;
+    }
+    // Script
+    if i < subtags.len() && is_alpha(subtags[i], 4, 4) {
+        i += 1;
+    }
+    // Region
+    if i < subtags.len()
+        && (is_alpha(subtags[i], 2, 2)
+            || (subtags[i].len() == 3 && subtags[i].chars().all(|c| c.is_ascii_digit())))
+    {
+        i += 1;
+    }
+    // Variants
+    while i < subtags.len() {
+        let s = subtags[i];
+        let is_variant = is_alnum(s, 5, 8)
+            || (s.len() == 4 && s.starts_with(|c: char| c.is_ascii_digit()) && is_alnum(s, 4, 4));
+        if !is_variant {
+            break;
+        }
+        i += 1;
+    }
+    // Extensions and private use
+    while i < subtags.len() {
+        let singleton = subtags[i];
+        if singleton.eq_ignore_ascii_case("x") {
+            return is_private_use(&subtags[i + 1..]);
+        }
+        if singleton.len() != 1 || !is_alnum(singleton, 1, 1) {
+            return false;
+        }
+        i += 1;
+        let start = i;
+        while i < subtags.len() && is_alnum(subtags[i], 2, 8) {
+            i += 1;
+        }
+        if i == start {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_tags() {
+        for tag in ["en", "id", "jv", "en-US", "zh-Hant-TW", "sr-Latn", "es-419", "de-CH-1996", "en-x-private", "x-klingon"] {
+            assert!(is_valid_bcp47(tag), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        for tag in ["", "e", "english!", "en-", "en--US", "123", "en-a", "toolonglanguage"] {
+            assert!(!is_valid_bcp47(tag), "{}", tag);
+        }
+    }
+}
@@ -0,0 +1,395 @@
+// -*- coding: utf-8 -*-
+//! Leaderboard Event Hooks
+//!
+//! Services that send notifications (Slack, email) when the leaderboard
+//! changes register a `LeaderboardObserver` instead of polling snapshots.
+//! Whenever the leaderboard changes scores or ranks (credited traces, added
+//! or updated contributors, a new scoring config, recorded outcomes, a
+//! finalized season), it compares the standings under every watched
+//! criterion (`Overall`, plus any added with `watch_ranking`) and the
+//! credited discoveries before and after the change, then calls
+//! `on_rank_change` for every contributor whose position moved,
+//! `on_new_top3` when a top three changed, and `on_new_discovery` for every
+//! discovery credited for the first time. Observers run synchronously while
+//! the leaderboard is being changed, so slow work belongs on a queue. They
+//! are not serialized.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use crate::ContributorStats::{best_first, LanguageAwareLeaderboard, LanguageAwareRankingCriteria};
+
+/// Number of contributors watched by `on_new_top3`
+pub const TOP_PLACES: usize = 3;
+
+/// Contributor whose position under a watched criterion changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankChange {
+    pub criteria: LanguageAwareRankingCriteria,
+    pub contributor_id: String,
+    /// 1-based position before the change (`None` for a new contributor)
+    pub previous: Option<usize>,
+    /// 1-based position after the change
+    pub current: usize,
+}
+
+impl RankChange {
+    /// Check if the contributor moved up (or entered the leaderboard)
+    pub fn is_promotion(&self) -> bool {
+        self.previous.is_none_or(|previous| self.current < previous)
+    }
+}
+
+/// Discovery credited on the leaderboard for the first time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewDiscovery {
+    pub discovery_name: String,
+    /// Contributors credited with it, sorted
+    pub contributor_ids: Vec<String>,
+}
+
+/// Callbacks fired after the leaderboard changes
+pub trait LeaderboardObserver: Send + Sync {
+    /// Name used in debug output
+    fn name(&self) -> &str;
+
+    /// A contributor's position under a watched criterion changed
+    fn on_rank_change(&self, _change: &RankChange) {}
+
+    /// The top three under a watched criterion changed; both lists are
+    /// contributor IDs, best first
+    fn on_new_top3(&self, _criteria: LanguageAwareRankingCriteria, _previous: &[String], _current: &[String]) {}
+
+    /// A discovery was credited for the first time
+    fn on_new_discovery(&self, _discovery: &NewDiscovery) {}
+}
+
+/// Observers registered on a leaderboard, and the criteria they watch
+#[derive(Clone, Default)]
+pub struct LeaderboardObservers {
+    observers: Vec<Arc<dyn LeaderboardObserver>>,
+    /// Criteria watched besides `Overall`
+    watched: Vec<LanguageAwareRankingCriteria>,
+}
+
+impl std::fmt::Debug for LeaderboardObservers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.observers.iter().map(|o| o.name()))
+            .finish()
+    }
+}
+
+impl LeaderboardObservers {
+    /// Number of observers
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    /// Check if no observer is registered
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// Criteria whose standings are compared, `Overall` first
+    pub fn criteria(&self) -> impl Iterator<Item = LanguageAwareRankingCriteria> + '_ {
+        std::iter::once(LanguageAwareRankingCriteria::Overall).chain(self.watched.iter().copied())
+    }
+}
+
+/// What observers compare across a change
+struct Standings {
+    /// Contributor IDs under each watched criterion, best first
+    orders: Vec<(LanguageAwareRankingCriteria, Vec<String>)>,
+    discoveries: HashSet<String>,
+}
+
+impl Standings {
+    fn of(leaderboard: &LanguageAwareLeaderboard) -> Self {
+        let orders = leaderboard.observers
+            .criteria()
+            .map(|criteria| (criteria, leaderboard.standings(criteria)))
+            .collect();
+        let discoveries = leaderboard.contributors
+            .values()
+            .flat_map(|stats| stats.discoveries.iter().cloned())
+            .collect();
+        Self { orders, discoveries }
+    }
+}
+
+fn top(order: &[String]) -> &[String] {
+    &order[..order.len().min(TOP_PLACES)]
+}
+
+impl LanguageAwareLeaderboard {
+    /// Register an observer notified of changes from now on
+    pub fn add_observer<O: LeaderboardObserver + 'static>(&mut self, observer: O) {
+        self.observers.observers.push(Arc::new(observer));
+    }
+
+    /// Observers registered on the leaderboard
+    pub fn observers(&self) -> &LeaderboardObservers {
+        &self.observers
+    }
+
+    /// Also report rank and top-three changes under `criteria`
+    /// (`Overall` is always watched)
+    pub fn watch_ranking(&mut self, criteria: LanguageAwareRankingCriteria) {
+        if !self.observers.criteria().any(|watched| watched == criteria) {
+            self.observers.watched.push(criteria);
+        }
+    }
+
+    /// Copy without observers, for what-if changes that must not notify
+    pub(crate) fn detached(&self) -> Self {
+        let mut copy = self.clone();
+        copy.observers = LeaderboardObservers::default();
+        copy
+    }
+
+    /// Contributor IDs by `criteria`, best first (ties by ID, NaN scores last)
+    pub fn standings(&self, criteria: LanguageAwareRankingCriteria) -> Vec<String> {
+        let mut scored: Vec<(f64, &str)> = self.contributors
+            .values()
+            .map(|stats| (self.get_score(stats, criteria), stats.contributor_id.as_str()))
+            .collect();
+        scored.sort_by(|(a, a_id), (b, b_id)| best_first(*a, *b).then_with(|| a_id.cmp(b_id)));
+        scored.into_iter().map(|(_, id)| id.to_string()).collect()
+    }
+
+    /// Run `change`, then tell observers what it changed
+    pub(crate) fn observed<T>(&mut self, change: impl FnOnce(&mut Self) -> T) -> T {
+        if self.observers.is_empty() {
+            return change(self);
+        }
+        let before = Standings::of(self);
+        let result = change(self);
+        self.notify(&before);
+        result
+    }
+
+    fn notify(&self, before: &Standings) {
+        let after = Standings::of(self);
+        let observers = &self.observers.observers;
+
+        for ((criteria, before), (_, after)) in before.orders.iter().zip(&after.orders) {
+            let previous: HashMap<&str, usize> = before.iter().enumerate().map(|(i, id)| (id.as_str(), i + 1)).collect();
+            for (i, contributor_id) in after.iter().enumerate() {
+                let previous = previous.get(contributor_id.as_str()).copied();
+                if previous != Some(i + 1) {
+                    let change = RankChange { criteria: *criteria, contributor_id: contributor_id.clone(), previous, current: i + 1 };
+                    observers.iter().for_each(|o| o.on_rank_change(&change));
+                }
+            }
+            if top(before) != top(after) {
+                observers.iter().for_each(|o| o.on_new_top3(*criteria, top(before), top(after)));
+            }
+        }
+
+        let mut discoveries: Vec<&String> = after.discoveries.difference(&before.discoveries).collect();
+        discoveries.sort();
+        for discovery_name in discoveries {
+            let mut contributor_ids: Vec<String> = self.contributors
+                .values()
+                .filter(|stats| stats.discoveries.contains(discovery_name))
+                .map(|stats| stats.contributor_id.clone())
+                .collect();
+            contributor_ids.sort();
+            let discovery = NewDiscovery { discovery_name: discovery_name.clone(), contributor_ids };
+            observers.iter().for_each(|o| o.on_new_discovery(&discovery));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::ContributorStats::{LanguageAwareContributorStats, ScoringConfig};
+    use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, SerendipityAgent};
+    use crate::outcome::{Outcome, OutcomeKind};
+    use crate::season::LeaderboardSeason;
+    use chrono::{TimeZone, Utc};
+
+    #[derive(Default)]
+    struct Recorded {
+        ranks: Vec<RankChange>,
+        tops: Vec<(LanguageAwareRankingCriteria, Vec<String>, Vec<String>)>,
+        discoveries: Vec<NewDiscovery>,
+    }
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Recorded>>);
+
+    impl Recorder {
+        fn take(&self) -> Recorded {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    impl LeaderboardObserver for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn on_rank_change(&self, change: &RankChange) {
+            self.0.lock().unwrap().ranks.push(change.clone());
+        }
+
+        fn on_new_top3(&self, criteria: LanguageAwareRankingCriteria, previous: &[String], current: &[String]) {
+            self.0.lock().unwrap().tops.push((criteria, previous.to_vec(), current.to_vec()));
+        }
+
+        fn on_new_discovery(&self, discovery: &NewDiscovery) {
+            self.0.lock().unwrap().discoveries.push(discovery.clone());
+        }
+    }
+
+    fn trace(contributor: &str, discovery: &str, outputs: &[&str], serendipity: f64) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor, "backend", discovery);
+        for output in outputs {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", output, "en", serendipity, 0.8).unwrap();
+        }
+        trace
+    }
+
+    fn overall(contributor: &str, previous: Option<usize>, current: usize) -> RankChange {
+        RankChange { criteria: LanguageAwareRankingCriteria::Overall, contributor_id: contributor.to_string(), previous, current }
+    }
+
+    fn stats(contributor: &str, serendipity: f64) -> LanguageAwareContributorStats {
+        let mut stats = LanguageAwareContributorStats::new(contributor);
+        stats.add_trace(10, 0.5, serendipity, vec!["en".to_string()], 0.5, 0.5).unwrap();
+        stats
+    }
+
+    #[test]
+    fn test_credit_fires_rank_top3_and_discovery_hooks() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        let recorder = Recorder::default();
+        leaderboard.add_observer(recorder.clone());
+        assert_eq!(format!("{:?}", leaderboard.observers()), "[\"recorder\"]");
+
+        leaderboard.credit_trace(&trace("sari", "Journavx", &["walk"], 0.5), 0.8, 0.8).unwrap();
+        let recorded = recorder.take();
+        assert_eq!(recorded.ranks, vec![overall("sari", None, 1)]);
+        assert_eq!(recorded.tops, vec![(LanguageAwareRankingCriteria::Overall, vec![], vec!["sari".to_string()])]);
+        assert_eq!(recorded.discoveries, vec![NewDiscovery { discovery_name: "Journavx".to_string(), contributor_ids: vec!["sari".to_string()] }]);
+
+        // A stronger newcomer takes first place and pushes sari down
+        leaderboard.credit_trace(&trace("budi", "Star Compass", &["a", "b", "c"], 0.95), 0.9, 0.9).unwrap();
+        let recorded = recorder.take();
+        assert_eq!(recorded.ranks, vec![
+            overall("budi", None, 1),
+            overall("sari", Some(1), 2),
+        ]);
+        assert!(recorded.ranks[0].is_promotion() && !recorded.ranks[1].is_promotion());
+        assert_eq!(recorded.tops[0].2, vec!["budi".to_string(), "sari".to_string()]);
+        assert_eq!(recorded.discoveries[0].discovery_name, "Star Compass");
+
+        // Crediting a known discovery again, without moving anyone, fires nothing
+        leaderboard.credit_trace(&trace("budi", "Star Compass", &["a", "b", "c"], 0.95), 0.9, 0.9).unwrap();
+        let recorded = recorder.take();
+        assert!(recorded.ranks.is_empty() && recorded.tops.is_empty() && recorded.discoveries.is_empty());
+
+        // A refused credit changes nothing
+        assert!(leaderboard.credit_trace(&trace("rini", "Batik", &["x"], 0.5), 1.5, 0.5).is_err());
+        assert!(recorder.take().discoveries.is_empty());
+
+        // Previews credit a detached copy
+        leaderboard.detached().credit_trace(&trace("rini", "Batik", &["x"], 0.99), 0.9, 0.9).unwrap();
+        assert!(recorder.take().ranks.is_empty());
+    }
+
+    #[test]
+    fn test_re_ranking_fires_hooks() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for (id, serendipity) in [("a", 0.9), ("b", 0.8), ("c", 0.7), ("d", 0.6), ("e", 0.5)] {
+            leaderboard.add_contributor(stats(id, serendipity));
+        }
+        let recorder = Recorder::default();
+        leaderboard.add_observer(recorder.clone());
+
+        // Fourth place moving to third changes the top three
+        leaderboard.add_contributor(stats("d", 0.75));
+        let recorded = recorder.take();
+        assert_eq!(recorded.ranks, vec![
+            overall("d", Some(4), 3),
+            overall("c", Some(3), 4),
+        ]);
+        assert_eq!(recorded.tops, vec![
+            (LanguageAwareRankingCriteria::Overall, vec!["a".to_string(), "b".to_string(), "c".to_string()], vec!["a".to_string(), "b".to_string(), "d".to_string()]),
+        ]);
+
+        // Swapping the bottom two leaves the top three alone
+        leaderboard.update_contributors(|stats| {
+            if stats.contributor_id == "e" {
+                stats.avg_serendipity = 0.72;
+            }
+        });
+        let recorded = recorder.take();
+        assert_eq!(recorded.ranks, vec![
+            overall("e", Some(5), 4),
+            overall("c", Some(4), 5),
+        ]);
+        assert!(recorded.tops.is_empty());
+
+        // A new scoring config re-ranks every contributor: here only discoveries count
+        let mut finder = stats("finder", 0.1);
+        finder.add_discovery("Journavx");
+        leaderboard.add_contributor(finder);
+        assert_eq!(recorder.take().discoveries[0].contributor_ids, vec!["finder".to_string()]);
+        let discovery_only = ScoringConfig::from_json(r#"{"depth_weight": 0.0, "uniqueness_weight": 0.0,
+            "serendipity_weight": 0.0, "language_weight": 0.0, "quality_weight": 0.0, "discovery_weight": 1.0}"#).unwrap();
+        leaderboard.set_scoring_config(discovery_only).unwrap();
+        let recorded = recorder.take();
+        assert_eq!(recorded.ranks[0], overall("finder", Some(6), 1));
+        assert_eq!(recorded.tops[0].2[0], "finder");
+    }
+
+    #[test]
+    fn test_outcomes_and_seasons_fire_watched_rank_changes() {
+        let day = |month, day| Utc.with_ymd_and_hms(2026, month, day, 12, 0, 0).unwrap();
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for (id, score) in [("budi", 0.4), ("sari", 0.9)] {
+            let mut stats = LanguageAwareContributorStats::new(id);
+            stats.add_trace_at(day(1, 10), 5, score, score, vec!["en".to_string()], 0.8, 0.8).unwrap();
+            stats.add_trace_at(day(1, 20), 5, 0.9, 0.9, vec!["en".to_string()], 0.8, 0.8).unwrap();
+            leaderboard.add_contributor(stats);
+        }
+        leaderboard.add_season(LeaderboardSeason::new("Q1", day(1, 1), day(1, 15))).unwrap();
+        let recorder = Recorder::default();
+        leaderboard.add_observer(recorder.clone());
+        leaderboard.watch_ranking(LanguageAwareRankingCriteria::VerifiedImpact);
+        leaderboard.watch_ranking(LanguageAwareRankingCriteria::Trophies);
+        leaderboard.watch_ranking(LanguageAwareRankingCriteria::Overall);
+        assert_eq!(leaderboard.observers().criteria().count(), 3);
+
+        // A published discovery lifts sari on verified impact; Overall is unchanged
+        let mut published = SerendipityTrace::new("sari", "backend", "Journavx");
+        published.record_outcome(Outcome::new(OutcomeKind::Published, published.created_at)).unwrap();
+        assert_eq!(leaderboard.record_outcomes(&published), 1);
+        let impact = |id: &str, previous, current| RankChange {
+            criteria: LanguageAwareRankingCriteria::VerifiedImpact,
+            contributor_id: id.to_string(),
+            previous: Some(previous),
+            current,
+        };
+        let recorded = recorder.take();
+        assert_eq!(recorded.ranks, vec![impact("sari", 2, 1), impact("budi", 1, 2)]);
+        assert_eq!(recorded.tops.len(), 1);
+        assert_eq!(recorded.tops[0].0, LanguageAwareRankingCriteria::VerifiedImpact);
+
+        // Finalizing a season awards trophies: sari wins Q1 and leads on trophy points
+        leaderboard.finalize_season("Q1").unwrap();
+        let trophies = |id: &str, previous, current| RankChange {
+            criteria: LanguageAwareRankingCriteria::Trophies,
+            contributor_id: id.to_string(),
+            previous: Some(previous),
+            current,
+        };
+        let recorded = recorder.take();
+        assert_eq!(recorded.ranks, vec![trophies("sari", 2, 1), trophies("budi", 1, 2)]);
+        assert_eq!(recorded.tops[0].2, vec!["sari".to_string(), "budi".to_string()]);
+    }
+}
@@ -0,0 +1,361 @@
+// -*- coding: utf-8 -*-
+//! Live Trace Streaming
+//!
+//! Dashboards watching an agent run subscribe to a `LiveBroadcaster`, a
+//! middleware that publishes every appended event and transition on a tokio
+//! broadcast channel. `SharedTraceRecorder::live` attaches one to a recorder.
+//! Each subscription carries a `LiveFilter` on stage, agent, or minimum
+//! serendipity (a transition is judged by the event it leads to). With the
+//! `server` feature, `live_router` serves the stream over WebSocket as JSON
+//! messages. Requires the `live` feature (`tokio` crate).
+#![cfg(feature = "live")]
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError, error::TryRecvError};
+use crate::serendipity_trace::{
+    SerendipityAgent, SerendipityEvent, SerendipityStage, SerendipityTrace, SerendipityTransition,
+};
+use crate::middleware::TraceMiddleware;
+use crate::shared_recorder::SharedTraceRecorder;
+
+/// Updates buffered per subscriber before slow subscribers start missing some
+pub const DEFAULT_LIVE_CAPACITY: usize = 256;
+
+/// Change published while a trace is recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveUpdate {
+    /// Event appended at position `sequence`
+    Event {
+        trace_id: String,
+        sequence: usize,
+        event: SerendipityEvent,
+    },
+    /// Transition into the event just appended
+    Transition {
+        trace_id: String,
+        transition: SerendipityTransition,
+        /// Stage of the target event
+        stage: SerendipityStage,
+        /// Serendipity of the target event
+        serendipity: f64,
+    },
+}
+
+/// Which updates a subscriber receives (everything by default)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LiveFilter {
+    /// Only these stages (any if empty)
+    #[serde(default)]
+    pub stages: Vec<SerendipityStage>,
+    /// Only these agents (any if empty)
+    #[serde(default)]
+    pub agents: Vec<SerendipityAgent>,
+    /// Only events scoring at least this
+    #[serde(default)]
+    pub min_serendipity: Option<f64>,
+}
+
+impl LiveFilter {
+    /// Filter passing every update
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also accept `stage`
+    pub fn stage(mut self, stage: SerendipityStage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Also accept `agent`
+    pub fn agent(mut self, agent: SerendipityAgent) -> Self {
+        self.agents.push(agent);
+        self
+    }
+
+    /// Require a minimum serendipity score
+    pub fn min_serendipity(mut self, min: f64) -> Self {
+        self.min_serendipity = Some(min);
+        self
+    }
+
+    /// Filter on comma-separated stage and agent names, as sent in a query
+    /// string. Names that are not built-in select custom stages and roles.
+    pub fn from_names(stages: &str, agents: &str) -> Self {
+        fn split(list: &str) -> impl Iterator<Item = &str> {
+            list.split(',').map(str::trim).filter(|name| !name.is_empty())
+        }
+        Self {
+            stages: split(stages).map(SerendipityStage::from_name).collect(),
+            agents: split(agents)
+                .map(|name| {
+                    serde_json::from_value(serde_json::Value::String(name.to_string()))
+                        .unwrap_or_else(|_| SerendipityAgent::custom(name))
+                })
+                .collect(),
+            min_serendipity: None,
+        }
+    }
+
+    /// Whether the subscriber wants `update`
+    pub fn matches(&self, update: &LiveUpdate) -> bool {
+        let (stage, agent, serendipity) = match update {
+            LiveUpdate::Event { event, .. } => (&event.stage, &event.agent, event.serendipity_score),
+            LiveUpdate::Transition { transition, stage, serendipity, .. } => (stage, &transition.to_agent, *serendipity),
+        };
+        (self.stages.is_empty() || self.stages.contains(stage))
+            && (self.agents.is_empty() || self.agents.contains(agent))
+            && self.min_serendipity.is_none_or(|min| serendipity >= min)
+    }
+}
+
+/// Middleware broadcasting appended events and transitions
+#[derive(Debug, Clone)]
+pub struct LiveBroadcaster {
+    sender: broadcast::Sender<LiveUpdate>,
+}
+
+impl LiveBroadcaster {
+    /// Broadcaster buffering `capacity` updates per subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Receive updates passing `filter` from now on
+    pub fn subscribe(&self, filter: LiveFilter) -> LiveSubscription {
+        LiveSubscription { receiver: self.sender.subscribe(), filter }
+    }
+
+    /// Number of active subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for LiveBroadcaster {
+    fn default() -> Self {
+        Self::new(DEFAULT_LIVE_CAPACITY)
+    }
+}
+
+impl TraceMiddleware for LiveBroadcaster {
+    fn name(&self) -> &str {
+        "live_broadcaster"
+    }
+
+    fn after_log(&self, event: &SerendipityEvent, trace: &SerendipityTrace) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(LiveUpdate::Event {
+            trace_id: trace.trace_id.clone(),
+            sequence: trace.events.len() - 1,
+            event: event.clone(),
+        });
+        if let Some(transition) = trace.transitions.last().filter(|t| t.to_event == event.event_id) {
+            let _ = self.sender.send(LiveUpdate::Transition {
+                trace_id: trace.trace_id.clone(),
+                transition: transition.clone(),
+                stage: event.stage.clone(),
+                serendipity: event.serendipity_score,
+            });
+        }
+    }
+}
+
+/// Filtered stream of live updates
+#[derive(Debug)]
+pub struct LiveSubscription {
+    receiver: broadcast::Receiver<LiveUpdate>,
+    filter: LiveFilter,
+}
+
+impl LiveSubscription {
+    /// Next matching update; `None` once the broadcaster is gone.
+    /// Updates missed by a lagging subscriber are skipped.
+    pub async fn next(&mut self) -> Option<LiveUpdate> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(update) if self.filter.matches(&update) => return Some(update),
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Next matching update already buffered, without waiting
+    pub fn try_next(&mut self) -> Option<LiveUpdate> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(update) if self.filter.matches(&update) => return Some(update),
+                Ok(_) | Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl SharedTraceRecorder {
+    /// Recorder that broadcasts every event it appends
+    pub fn live(mut trace: SerendipityTrace, capacity: usize) -> (Self, LiveBroadcaster) {
+        let broadcaster = LiveBroadcaster::new(capacity);
+        trace.add_middleware(broadcaster.clone());
+        (Self::new(trace), broadcaster)
+    }
+}
+
+/// WebSocket endpoint: `GET /live?stages=Validation,Integration&agents=Explorer&min_serendipity=0.8`
+#[cfg(feature = "server")]
+pub fn live_router(broadcaster: LiveBroadcaster) -> axum::Router {
+    use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+    use axum::extract::{Query, State};
+    use axum::response::Response;
+
+    /// Comma-separated filter lists, as sent in a query string
+    #[derive(Deserialize)]
+    struct LiveParams {
+        #[serde(default)]
+        stages: Option<String>,
+        #[serde(default)]
+        agents: Option<String>,
+        #[serde(default)]
+        min_serendipity: Option<f64>,
+    }
+
+    async fn stream(mut socket: WebSocket, mut subscription: LiveSubscription) {
+        while let Some(update) = subscription.next().await {
+            let Ok(json) = serde_json::to_string(&update) else { continue };
+            if socket.send(Message::Text(json.into())).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    async fn upgrade(
+        State(broadcaster): State<LiveBroadcaster>,
+        Query(params): Query<LiveParams>,
+        ws: WebSocketUpgrade,
+    ) -> Response {
+        let filter = LiveFilter {
+            min_serendipity: params.min_serendipity,
+            ..LiveFilter::from_names(params.stages.as_deref().unwrap_or_default(), params.agents.as_deref().unwrap_or_default())
+        };
+        let subscription = broadcaster.subscribe(filter);
+        ws.on_upgrade(move |socket| stream(socket, subscription))
+    }
+
+    axum::Router::new()
+        .route("/live", axum::routing::get(upgrade))
+        .with_state(broadcaster)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityEventBuilder;
+
+    fn log(trace: &mut SerendipityTrace, stage: SerendipityStage, agent: SerendipityAgent, score: f64) {
+        trace.log_event(stage, agent, "in", "out", "en", score, 0.8).unwrap();
+    }
+
+    #[test]
+    fn test_filters_select_updates() {
+        let broadcaster = LiveBroadcaster::default();
+        let mut everything = broadcaster.subscribe(LiveFilter::new());
+        let mut breakthroughs = broadcaster.subscribe(LiveFilter::new().min_serendipity(0.8));
+        let mut validators = broadcaster.subscribe(LiveFilter::new().agent(SerendipityAgent::Validator));
+
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.add_middleware(broadcaster.clone());
+        log(&mut trace, SerendipityStage::Exploration, SerendipityAgent::Explorer, 0.3);
+        log(&mut trace, SerendipityStage::Validation, SerendipityAgent::Validator, 0.9);
+
+        let all: Vec<LiveUpdate> = std::iter::from_fn(|| everything.try_next()).collect();
+        assert_eq!(all.len(), 3);
+        assert!(matches!(&all[0], LiveUpdate::Event { sequence: 0, .. }));
+        assert!(matches!(&all[2], LiveUpdate::Transition { transition, .. } if transition.to_event == trace.events[1].event_id));
+        // The validation event and the transition into it
+        assert_eq!(std::iter::from_fn(|| breakthroughs.try_next()).count(), 2);
+        assert_eq!(std::iter::from_fn(|| validators.try_next()).count(), 2);
+        assert!(serde_json::to_string(&all[0]).unwrap().starts_with("{\"type\":\"event\""));
+    }
+
+    #[test]
+    fn test_filter_names_include_custom_stages_and_roles() {
+        let filter = LiveFilter::from_names("Validation, HitScreening,", "Crystallographer");
+        assert_eq!(filter.stages, vec![SerendipityStage::Validation, SerendipityStage::custom("HitScreening")]);
+        assert_eq!(filter.agents, vec![SerendipityAgent::custom("Crystallographer")]);
+        assert_eq!(LiveFilter::from_names("", ""), LiveFilter::new());
+
+        let broadcaster = LiveBroadcaster::default();
+        let mut screening = broadcaster.subscribe(LiveFilter::from_names("HitScreening", ""));
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.add_middleware(broadcaster.clone());
+        log(&mut trace, SerendipityStage::Exploration, SerendipityAgent::Explorer, 0.3);
+        log(&mut trace, SerendipityStage::custom("HitScreening"), SerendipityAgent::Explorer, 0.6);
+
+        // The custom-stage event and the transition into it, not everything
+        let updates: Vec<LiveUpdate> = std::iter::from_fn(|| screening.try_next()).collect();
+        assert_eq!(updates.len(), 2);
+        assert!(matches!(&updates[0], LiveUpdate::Event { sequence: 1, .. }));
+    }
+
+    #[test]
+    fn test_unsubscribe_by_dropping() {
+        let broadcaster = LiveBroadcaster::default();
+        let first = broadcaster.subscribe(LiveFilter::new());
+        let mut second = broadcaster.subscribe(LiveFilter::new());
+        assert_eq!(broadcaster.subscriber_count(), 2);
+        drop(first);
+        assert_eq!(broadcaster.subscriber_count(), 1);
+
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.add_middleware(broadcaster.clone());
+        log(&mut trace, SerendipityStage::Exploration, SerendipityAgent::Explorer, 0.3);
+        assert!(second.try_next().is_some());
+        drop(second);
+        assert_eq!(broadcaster.subscriber_count(), 0);
+        // Publishing with nobody listening is not an error
+        log(&mut trace, SerendipityStage::Validation, SerendipityAgent::Validator, 0.9);
+        assert_eq!(trace.events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscribers_skip_missed_updates() {
+        let broadcaster = LiveBroadcaster::new(2);
+        let mut polled = broadcaster.subscribe(LiveFilter::new());
+        let mut awaited = broadcaster.subscribe(LiveFilter::new());
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.add_middleware(broadcaster.clone());
+        for score in [0.3, 0.5, 0.9] {
+            log(&mut trace, SerendipityStage::Exploration, SerendipityAgent::Explorer, score);
+        }
+
+        // Five updates overflowed a buffer of two: only the last event and
+        // the transition into it are left, whether polled or awaited
+        assert!(matches!(polled.try_next(), Some(LiveUpdate::Event { sequence: 2, .. })));
+        assert!(matches!(awaited.next().await, Some(LiveUpdate::Event { sequence: 2, .. })));
+        for subscription in [&mut polled, &mut awaited] {
+            assert!(matches!(subscription.try_next(), Some(LiveUpdate::Transition { .. })));
+            assert!(subscription.try_next().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recorder_streams_events() {
+        let (recorder, broadcaster) = SharedTraceRecorder::live(SerendipityTrace::new("sari", "backend", "Journavx"), 16);
+        let mut subscription = broadcaster.subscribe(LiveFilter::new().stage(SerendipityStage::UnexpectedConnection));
+        let event = SerendipityEventBuilder::new(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "out", "id")
+            .serendipity(0.9)
+            .confidence(0.8);
+        let recorded = recorder.record(event).await.unwrap();
+
+        match subscription.next().await {
+            Some(LiveUpdate::Event { event, .. }) => assert_eq!(event.event_id, recorded.event_id),
+            other => panic!("expected event, got {:?}", other),
+        }
+        drop(recorder.finish().unwrap());
+        drop(broadcaster);
+        assert!(subscription.next().await.is_none());
+    }
+}
@@ -0,0 +1,154 @@
+// -*- coding: utf-8 -*-
+//! Localized Stage and Agent Names
+//!
+//! Translation table for `SerendipityStage` and `SerendipityAgent` display
+//! names used when rendering narratives and reports, so localized output does
+//! not mix English enum identifiers into translated text. Serialized traces
+//! and hashes keep using the enum identifiers.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::{SerendipityTrace, SerendipityStage, SerendipityAgent, key_discovery};
+
+/// Report locale
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    /// English
+    #[default]
+    En,
+    /// Indonesian (Bahasa Indonesia)
+    Id,
+}
+
+impl Locale {
+    /// All supported locales
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Id];
+
+    /// Locale for a BCP-47 language tag, matched on its primary subtag
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next().unwrap_or_default();
+        match primary.to_ascii_lowercase().as_str() {
+            "en" => Some(Locale::En),
+            "id" | "in" => Some(Locale::Id),
+            _ => None,
+        }
+    }
+
+    /// Language tag of this locale
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Id => "id",
+        }
+    }
+}
+
+/// Types with a human-readable name per locale
+pub trait Localized {
+    /// Display name in `locale`
+    fn localized_name(&self, locale: Locale) -> &str;
+}
+
+impl Localized for SerendipityStage {
+    fn localized_name(&self, locale: Locale) -> &str {
+        use SerendipityStage::*;
+        match (self, locale) {
+            (Exploration, Locale::En) => "Exploration",
+            (Exploration, Locale::Id) => "Eksplorasi",
+            (UnexpectedConnection, Locale::En) => "Unexpected Connection",
+            (UnexpectedConnection, Locale::Id) => "Koneksi Tak Terduga",
+            (HypothesisFormation, Locale::En) => "Hypothesis Formation",
+            (HypothesisFormation, Locale::Id) => "Pembentukan Hipotesis",
+            (Validation, Locale::En) => "Validation",
+            (Validation, Locale::Id) => "Validasi",
+            (Integration, Locale::En) => "Integration",
+            (Integration, Locale::Id) => "Integrasi",
+            (Publication, Locale::En) => "Publication",
+            (Publication, Locale::Id) => "Publikasi",
+            // Custom stages are named by their taxonomy and not translated
+            (Custom(name), _) => name,
+        }
+    }
+}
+
+impl Localized for SerendipityAgent {
+    fn localized_name(&self, locale: Locale) -> &str {
+        use SerendipityAgent::*;
+        match (self, locale) {
+            (Explorer, Locale::En) => "Explorer",
+            (Explorer, Locale::Id) => "Penjelajah",
+            (PatternRecognizer, Locale::En) => "Pattern Recognizer",
+            (PatternRecognizer, Locale::Id) => "Pengenal Pola",
+            (HypothesisGenerator, Locale::En) => "Hypothesis Generator",
+            (HypothesisGenerator, Locale::Id) => "Pembangkit Hipotesis",
+            (Validator, Locale::En) => "Validator",
+            (Validator, Locale::Id) => "Validator",
+            (Synthesizer, Locale::En) => "Synthesizer",
+            (Synthesizer, Locale::Id) => "Penyintesis",
+            (Translator, Locale::En) => "Translator",
+            (Translator, Locale::Id) => "Penerjemah",
+            (MetaOrchestrator, Locale::En) => "Meta-Orchestrator",
+            (MetaOrchestrator, Locale::Id) => "Meta-Orkestrator",
+            // Custom roles are named by the pipeline and not translated
+            (Custom(name), _) => name,
+        }
+    }
+}
+
+impl SerendipityTrace {
+    /// Key discoveries labelled with localized stage names
+    pub fn localized_key_discoveries(&self, locale: Locale) -> Vec<String> {
+        let threshold = self.key_discovery_threshold();
+        self.events
+            .iter()
+            .filter(|e| key_discovery(e, threshold).is_some())
+            .map(|e| format!("{}: {}", e.stage.localized_name(locale), e.output))
+            .collect()
+    }
+
+    /// One line per event: "<stage> (<agent>, <language>): <serendipity>"
+    pub fn localized_event_summary(&self, locale: Locale) -> Vec<String> {
+        self.events
+            .iter()
+            .map(|e| format!(
+                "{} ({}, {}): {:.3}",
+                e.stage.localized_name(locale),
+                e.agent.localized_name(locale),
+                e.language,
+                e.serendipity_score
+            ))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!(Locale::from_tag("id-ID"), Some(Locale::Id));
+        assert_eq!(Locale::from_tag("en_US"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("jv"), None);
+        assert_eq!(Locale::default().tag(), "en");
+    }
+
+    #[test]
+    fn test_localized_report_has_no_enum_identifiers() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.log_event(
+            SerendipityStage::UnexpectedConnection,
+            SerendipityAgent::PatternRecognizer,
+            "input",
+            "temuan",
+            "id",
+            0.9,
+            0.8,
+        ).unwrap();
+
+        assert_eq!(trace.localized_key_discoveries(Locale::Id), vec!["Koneksi Tak Terduga: temuan"]);
+        let summary = trace.localized_event_summary(Locale::Id).join("\n");
+        assert!(summary.contains("Pengenal Pola"));
+        assert!(!summary.contains("UnexpectedConnection") && !summary.contains("PatternRecognizer"));
+        assert_eq!(trace.localized_event_summary(Locale::En)[0], "Unexpected Connection (Pattern Recognizer, id): 0.900");
+    }
+}
@@ -0,0 +1,159 @@
+// -*- coding: utf-8 -*-
+//! Markdown Summaries of Traces
+//!
+//! GitHub-flavored Markdown write-ups for pasting a trace into a pull request
+//! or lab notebook. `FoldedSerendipityTrace::to_markdown` summarizes a fold's
+//! scores, key discoveries, and languages; `to_markdown_summary` adds the top
+//! discoveries and a table of every event for a full trace.
+
+use std::fmt::Write;
+use crate::serendipity_trace::{FoldedSerendipityTrace, SerendipityTrace};
+
+/// Events listed under "Top Discoveries" in a trace summary
+pub const TOP_DISCOVERIES: usize = 5;
+
+/// Characters of event output shown in the events table
+const OUTPUT_PREVIEW_CHARS: usize = 80;
+
+/// Make text safe for a table cell: escape pipes and flatten line breaks
+pub fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace("\r\n", " ").replace(['\n', '\r'], " ")
+}
+
+/// First `max` characters of `text`, with an ellipsis if cut
+fn preview(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// Bulleted list under a heading (nothing when `items` is empty)
+fn list_section(md: &mut String, title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    let _ = writeln!(md, "## {}\n", title);
+    for item in items {
+        let _ = writeln!(md, "- {}", item);
+    }
+    md.push('\n');
+}
+
+impl FoldedSerendipityTrace {
+    /// Render the fold as a Markdown write-up
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# {}\n", self.discovery_name);
+        let _ = writeln!(md, "Trace `{}`\n", self.trace_id);
+
+        let _ = writeln!(md, "| Metric | Value |\n| --- | ---: |");
+        let _ = writeln!(md, "| Events | {} |", self.total_events);
+        let _ = writeln!(md, "| Overall serendipity | {:.3} |", self.overall_serendipity);
+        let _ = writeln!(md, "| Uniqueness | {:.3} |", self.uniqueness.score);
+        let _ = writeln!(md, "| Compression ratio | {:.3} |", self.compression_ratio);
+        if self.partially_redacted {
+            let _ = writeln!(md, "| Redacted events | {} |", self.redacted_events);
+        }
+        md.push('\n');
+
+        list_section(&mut md, "Key Discoveries", &self.key_discoveries);
+        let languages: Vec<String> = self.languages.iter().map(|l| format!("`{}`", l)).collect();
+        list_section(&mut md, "Languages", &languages);
+        list_section(&mut md, "Language Transitions", &self.language_transitions);
+        let sources: Vec<String> = self.knowledge_sources
+            .iter()
+            .map(|credit| format!("{} ({}, cited {}×)", credit.source.title, credit.source.language, credit.event_ids.len()))
+            .collect();
+        list_section(&mut md, "Knowledge Sources", &sources);
+        md
+    }
+}
+
+impl SerendipityTrace {
+    /// Render the trace as a Markdown write-up: scores, top discoveries,
+    /// languages, and a table of events
+    pub fn to_markdown_summary(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# {}\n", self.discovery_name);
+        let _ = writeln!(
+            md,
+            "**Contributor:** {}  \n**Backend:** {}  \n**Trace:** `{}`\n",
+            self.contributor_id, self.backend, self.trace_id
+        );
+
+        let _ = writeln!(md, "| Metric | Value |\n| --- | ---: |");
+        let _ = writeln!(md, "| Events | {} |", self.events.len());
+        let _ = writeln!(md, "| Overall serendipity | {:.3} |", self.overall_serendipity);
+        let _ = writeln!(md, "| Uniqueness | {:.3} |", self.uniqueness_score());
+        md.push('\n');
+
+        let mut top: Vec<_> = self.events.iter().collect();
+        top.sort_by(|a, b| b.serendipity_score.total_cmp(&a.serendipity_score));
+        let top: Vec<String> = top
+            .into_iter()
+            .take(TOP_DISCOVERIES)
+            .map(|e| format!("**{:.3}** {} (`{}`): {}", e.serendipity_score, e.stage.name(), e.language, escape_cell(&e.output)))
+            .collect();
+        list_section(&mut md, "Top Discoveries", &top);
+
+        let languages: Vec<String> = self.languages.iter().map(|l| format!("`{}`", l)).collect();
+        list_section(&mut md, "Languages", &languages);
+
+        if !self.events.is_empty() {
+            let _ = writeln!(md, "## Events\n");
+            let _ = writeln!(md, "| # | Stage | Agent | Language | Serendipity | Confidence | Output |");
+            let _ = writeln!(md, "| ---: | --- | --- | --- | ---: | ---: | --- |");
+            for (i, event) in self.events.iter().enumerate() {
+                let _ = writeln!(
+                    md,
+                    "| {} | {:?} | {:?} | `{}` | {:.3} | {:.3} | {} |",
+                    i + 1,
+                    event.stage,
+                    event.agent,
+                    event.language,
+                    event.serendipity_score,
+                    event.confidence,
+                    escape_cell(&preview(&event.output, OUTPUT_PREVIEW_CHARS))
+                );
+            }
+            md.push('\n');
+        }
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "Survey | tables", "en", 0.3, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "Batik motifs\nmatch gates", "id", 0.95, 0.9).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_fold_to_markdown() {
+        let md = trace().fold_memory().unwrap().to_markdown();
+        assert!(md.starts_with("# Journavx\n"));
+        assert!(md.contains("| Events | 2 |"));
+        assert!(md.contains("## Key Discoveries\n\n- UnexpectedConnection: Batik motifs"));
+        assert!(md.contains("## Language Transitions\n\n- en -> id"));
+        assert!(!md.contains("Redacted events"));
+    }
+
+    #[test]
+    fn test_trace_summary_tables() {
+        let md = trace().to_markdown_summary();
+        assert!(md.contains("**Contributor:** sari"));
+        // Highest score first, line breaks flattened
+        let top = md.split("## Top Discoveries\n\n").nth(1).unwrap();
+        assert!(top.starts_with("- **0.950** UnexpectedConnection (`id`): Batik motifs match gates"));
+        assert!(md.contains("| 1 | Exploration | Explorer | `en` | 0.300 | 0.800 | Survey \\| tables |"));
+        assert_eq!(preview("abcdef", 3), "abc…");
+        assert!(!SerendipityTrace::new("sari", "backend", "Journavx").to_markdown_summary().contains("## Events"));
+    }
+}
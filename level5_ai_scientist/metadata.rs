@@ -0,0 +1,254 @@
+// -*- coding: utf-8 -*-
+//! Typed Event Metadata and Metadata Queries
+//!
+//! Event metadata values are typed (string, number, bool, or arbitrary JSON)
+//! instead of plain strings. Traces written before typed metadata still load:
+//! their values deserialize as strings. `find_events` filters a trace's events
+//! by key existence, equality, and numeric ranges.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEvent, SerendipityEventBuilder, SerendipityStage, SerendipityAgent};
+use crate::error::SerenQaResult;
+
+/// Typed metadata value.
+/// Serialized as the bare JSON value, so `{"run": 3}` holds a number.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum MetadataValue {
+    /// Boolean flag
+    Bool(bool),
+    /// Numeric value
+    Number(f64),
+    /// Text value
+    String(String),
+    /// Any other JSON (arrays, objects, null)
+    Json(serde_json::Value),
+}
+
+impl MetadataValue {
+    /// Text value, if this is a string
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MetadataValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Numeric value, if this is a number
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MetadataValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Boolean value, if this is a bool
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            MetadataValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MetadataValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataValue::Bool(b) => write!(f, "{}", b),
+            MetadataValue::Number(n) => write!(f, "{}", n),
+            MetadataValue::String(s) => write!(f, "{}", s),
+            MetadataValue::Json(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<&str> for MetadataValue {
+    fn from(value: &str) -> Self {
+        MetadataValue::String(value.to_string())
+    }
+}
+
+impl From<String> for MetadataValue {
+    fn from(value: String) -> Self {
+        MetadataValue::String(value)
+    }
+}
+
+impl From<f64> for MetadataValue {
+    fn from(value: f64) -> Self {
+        MetadataValue::Number(value)
+    }
+}
+
+impl From<i64> for MetadataValue {
+    fn from(value: i64) -> Self {
+        MetadataValue::Number(value as f64)
+    }
+}
+
+impl From<bool> for MetadataValue {
+    fn from(value: bool) -> Self {
+        MetadataValue::Bool(value)
+    }
+}
+
+impl From<serde_json::Value> for MetadataValue {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(b) => MetadataValue::Bool(b),
+            serde_json::Value::String(s) => MetadataValue::String(s),
+            serde_json::Value::Number(n) if n.as_f64().is_some() => MetadataValue::Number(n.as_f64().unwrap()),
+            other => MetadataValue::Json(other),
+        }
+    }
+}
+
+/// Condition on one metadata key
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataFilter {
+    /// Key is present
+    Exists(String),
+    /// Key holds exactly this value
+    Equals(String, MetadataValue),
+    /// Key holds a number within the bounds (inclusive, either optional)
+    Range { key: String, min: Option<f64>, max: Option<f64> },
+}
+
+impl MetadataFilter {
+    /// Check if an event satisfies the condition
+    pub fn matches(&self, event: &SerendipityEvent) -> bool {
+        match self {
+            MetadataFilter::Exists(key) => event.metadata.contains_key(key),
+            MetadataFilter::Equals(key, value) => event.metadata.get(key) == Some(value),
+            MetadataFilter::Range { key, min, max } => event.metadata
+                .get(key)
+                .and_then(MetadataValue::as_f64)
+                .is_some_and(|n| min.is_none_or(|min| n >= min) && max.is_none_or(|max| n <= max)),
+        }
+    }
+}
+
+/// Conjunction of metadata conditions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataQuery {
+    /// Conditions every matching event satisfies
+    pub filters: Vec<MetadataFilter>,
+}
+
+impl MetadataQuery {
+    /// Query matching every event
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require a key to be present
+    pub fn exists(mut self, key: &str) -> Self {
+        self.filters.push(MetadataFilter::Exists(key.to_string()));
+        self
+    }
+
+    /// Require a key to hold a value
+    pub fn equals(mut self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        self.filters.push(MetadataFilter::Equals(key.to_string(), value.into()));
+        self
+    }
+
+    /// Require a key to hold a number within [min, max] (either bound optional)
+    pub fn range(mut self, key: &str, min: Option<f64>, max: Option<f64>) -> Self {
+        self.filters.push(MetadataFilter::Range { key: key.to_string(), min, max });
+        self
+    }
+
+    /// Check if an event satisfies every condition
+    pub fn matches(&self, event: &SerendipityEvent) -> bool {
+        self.filters.iter().all(|filter| filter.matches(event))
+    }
+}
+
+impl SerendipityTrace {
+    /// Log a serendipity event carrying metadata.
+    /// Fails like `log_event`, or if a metadata key is empty.
+    #[allow(clippy::too_many_arguments)]
+    pub fn log_event_with_metadata(
+        &mut self,
+        stage: SerendipityStage,
+        agent: SerendipityAgent,
+        input: &str,
+        output: &str,
+        language: &str,
+        serendipity_score: f64,
+        confidence: f64,
+        metadata: HashMap<String, MetadataValue>,
+    ) -> SerenQaResult<&SerendipityEvent> {
+        let builder = metadata.into_iter().fold(
+            SerendipityEventBuilder::new(stage, agent, input, output, language)
+                .serendipity(serendipity_score)
+                .confidence(confidence),
+            |builder, (key, value)| builder.metadata(&key, value),
+        );
+        self.log(builder)
+    }
+
+    /// Events matching a metadata query, in trace order
+    pub fn find_events(&self, query: &MetadataQuery) -> Vec<&SerendipityEvent> {
+        self.events.iter().filter(|e| query.matches(e)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        for (run, simulator) in [(1, true), (2, false), (3, true)] {
+            let metadata = HashMap::from([
+                ("run".to_string(), MetadataValue::from(run as i64)),
+                ("simulator".to_string(), MetadataValue::from(simulator)),
+                ("source".to_string(), MetadataValue::from("field_notes")),
+            ]);
+            trace.log_event_with_metadata(
+                SerendipityStage::Validation,
+                SerendipityAgent::Validator,
+                "test",
+                &format!("run {}", run),
+                "en",
+                0.8,
+                0.9,
+                metadata,
+            ).unwrap();
+        }
+        trace.log_event(SerendipityStage::Publication, SerendipityAgent::Synthesizer, "write", "paper", "en", 0.6, 0.9).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_find_events_by_metadata() {
+        let trace = sample_trace();
+        assert_eq!(trace.find_events(&MetadataQuery::new()).len(), 4);
+        assert_eq!(trace.find_events(&MetadataQuery::new().exists("run")).len(), 3);
+
+        let query = MetadataQuery::new().equals("simulator", true).range("run", Some(2.0), None);
+        let found = trace.find_events(&query);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].output, "run 3");
+
+        // Range filters only match numbers
+        assert!(trace.find_events(&MetadataQuery::new().range("source", None, None)).is_empty());
+    }
+
+    #[test]
+    fn test_typed_values_round_trip_and_legacy_strings_load() {
+        let trace = sample_trace();
+        let json = serde_json::to_string(&trace.events[0]).unwrap();
+        assert!(json.contains("\"run\":1.0") && json.contains("\"simulator\":true"));
+        let event: SerendipityEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(event.metadata, trace.events[0].metadata);
+
+        let legacy: HashMap<String, MetadataValue> = serde_json::from_str(r#"{"run": "3", "tags": ["a", "b"]}"#).unwrap();
+        assert_eq!(legacy["run"].as_str(), Some("3"));
+        assert_eq!(legacy["tags"], MetadataValue::Json(serde_json::json!(["a", "b"])));
+        assert_eq!(legacy["tags"].to_string(), r#"["a","b"]"#);
+    }
+}
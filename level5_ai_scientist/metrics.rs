@@ -0,0 +1,158 @@
+// -*- coding: utf-8 -*-
+//! Custom Trace Metrics
+//!
+//! Research groups can register named metrics computed by their own callbacks
+//! when a trace is finalized. Values are stored next to the built-in scores,
+//! serialized with the trace, and can be aggregated per contributor and used
+//! to rank the leaderboard, without changing the scoring core.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use crate::serendipity_trace::SerendipityTrace;
+
+/// Callback computing a metric from a finalized trace
+pub type MetricFn = dyn Fn(&SerendipityTrace) -> f64 + Send + Sync;
+
+/// Named metric callbacks attached to a trace
+#[derive(Clone, Default)]
+pub struct MetricRegistry {
+    metrics: Vec<(String, Arc<MetricFn>)>,
+}
+
+impl std::fmt::Debug for MetricRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl MetricRegistry {
+    /// Register a metric, replacing any metric with the same name
+    pub fn register<F>(&mut self, name: &str, metric: F)
+    where
+        F: Fn(&SerendipityTrace) -> f64 + Send + Sync + 'static,
+    {
+        self.metrics.retain(|(existing, _)| existing != name);
+        self.metrics.push((name.to_string(), Arc::new(metric)));
+    }
+
+    /// Registered metric names in registration order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.metrics.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Number of registered metrics
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// Check if no metrics are registered
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    /// Compute every metric; non-finite values are dropped
+    pub fn compute(&self, trace: &SerendipityTrace) -> BTreeMap<String, f64> {
+        self.metrics
+            .iter()
+            .map(|(name, metric)| (name.clone(), metric(trace)))
+            .filter(|(_, value)| value.is_finite())
+            .collect()
+    }
+}
+
+/// Running mean of a custom metric across a contributor's traces
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct MetricSummary {
+    /// Mean value
+    pub mean: f64,
+    /// Number of traces that reported the metric
+    pub samples: usize,
+}
+
+impl MetricSummary {
+    /// Fold one more value into the mean
+    pub fn record(&mut self, value: f64) {
+        self.samples += 1;
+        self.mean += (value - self.mean) / self.samples as f64;
+    }
+}
+
+impl SerendipityTrace {
+    /// Register a custom metric computed on `finalize`
+    pub fn register_metric<F>(&mut self, name: &str, metric: F)
+    where
+        F: Fn(&SerendipityTrace) -> f64 + Send + Sync + 'static,
+    {
+        self.metric_registry.register(name, metric);
+    }
+
+    /// Compute registered custom metrics and store them on the trace.
+    /// Previously stored values for metrics that are no longer registered are kept.
+    pub fn finalize(&mut self) -> &BTreeMap<String, f64> {
+        let computed = self.metric_registry.compute(self);
+        self.custom_metrics.extend(computed);
+        &self.custom_metrics
+    }
+
+    /// Stored value of a custom metric
+    pub fn custom_metric(&self, name: &str) -> Option<f64> {
+        self.custom_metrics.get(name).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        for (language, score) in [("en", 0.4), ("id", 0.9), ("en", 0.8)] {
+            trace.log_event(
+                SerendipityStage::Exploration,
+                SerendipityAgent::Explorer,
+                "input",
+                "output",
+                language,
+                score,
+                0.9,
+            ).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_finalize_stores_metrics() {
+        let mut trace = sample_trace();
+        trace.register_metric("peak", |t| t.events.iter().map(|e| e.serendipity_score).fold(0.0, f64::max));
+        trace.register_metric("language_shifts", |t| {
+            t.transitions.iter().filter(|tr| tr.language_shift.is_some()).count() as f64
+        });
+        trace.register_metric("broken", |_| f64::NAN);
+        assert!(trace.custom_metric("peak").is_none());
+
+        let metrics = trace.finalize();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(trace.custom_metric("peak"), Some(0.9));
+        assert_eq!(trace.custom_metric("language_shifts"), Some(2.0));
+
+        let restored: SerendipityTrace = serde_json::from_str(&trace.to_json().unwrap()).unwrap();
+        assert_eq!(restored.custom_metrics, trace.custom_metrics);
+        assert!(restored.metric_registry.is_empty());
+    }
+
+    #[test]
+    fn test_register_replaces_same_name() {
+        let mut registry = MetricRegistry::default();
+        registry.register("m", |_| 1.0);
+        registry.register("m", |_| 2.0);
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.compute(&sample_trace())["m"], 2.0);
+
+        let mut summary = MetricSummary::default();
+        summary.record(1.0);
+        summary.record(2.0);
+        assert_eq!(summary, MetricSummary { mean: 1.5, samples: 2 });
+    }
+}
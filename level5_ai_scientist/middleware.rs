@@ -0,0 +1,216 @@
+// -*- coding: utf-8 -*-
+//! Event Pipeline Middleware
+//!
+//! Interceptors registered on a trace run before and after every logged
+//! event. They can normalize languages, clamp scores, attach metadata, or
+//! veto events, so cross-cutting concerns live in one place instead of being
+//! copy-pasted into every agent integration.
+
+use std::sync::Arc;
+use crate::serendipity_trace::{SerendipityEvent, SerendipityTrace};
+use crate::language_tag::normalize_case;
+
+/// Outcome of a `before_log` interceptor
+#[derive(Debug, Clone, PartialEq)]
+pub enum MiddlewareDecision {
+    /// Keep the (possibly modified) event
+    Continue,
+    /// Drop the event with a reason
+    Veto(String),
+}
+
+/// Interceptor run around `log_event`
+pub trait TraceMiddleware: Send + Sync {
+    /// Name used in veto reports
+    fn name(&self) -> &str;
+
+    /// Inspect or modify an event before it is logged
+    fn before_log(&self, _event: &mut SerendipityEvent, _trace: &SerendipityTrace) -> MiddlewareDecision {
+        MiddlewareDecision::Continue
+    }
+
+    /// Observe an event after it has been logged
+    fn after_log(&self, _event: &SerendipityEvent, _trace: &SerendipityTrace) {}
+}
+
+/// Ordered chain of interceptors attached to a trace
+#[derive(Clone, Default)]
+pub struct MiddlewareChain {
+    interceptors: Vec<Arc<dyn TraceMiddleware>>,
+}
+
+impl std::fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.interceptors.iter().map(|m| m.name()))
+            .finish()
+    }
+}
+
+impl MiddlewareChain {
+    /// Append an interceptor
+    pub fn push<M: TraceMiddleware + 'static>(&mut self, middleware: M) {
+        self.interceptors.push(Arc::new(middleware));
+    }
+
+    /// Number of interceptors
+    pub fn len(&self) -> usize {
+        self.interceptors.len()
+    }
+
+    /// Check if the chain has no interceptors
+    pub fn is_empty(&self) -> bool {
+        self.interceptors.is_empty()
+    }
+
+    /// Run `before_log` on every interceptor in order.
+    /// Returns the vetoing interceptor's name and reason, if any.
+    pub fn run_before(&self, event: &mut SerendipityEvent, trace: &SerendipityTrace) -> Result<(), (String, String)> {
+        for middleware in &self.interceptors {
+            if let MiddlewareDecision::Veto(reason) = middleware.before_log(event, trace) {
+                return Err((middleware.name().to_string(), reason));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `after_log` on every interceptor in order
+    pub fn run_after(&self, event: &SerendipityEvent, trace: &SerendipityTrace) {
+        for middleware in &self.interceptors {
+            middleware.after_log(event, trace);
+        }
+    }
+}
+
+/// Normalize language tags: trim, '_' to '-', lowercase language, uppercase region
+pub struct NormalizeLanguage;
+
+impl TraceMiddleware for NormalizeLanguage {
+    fn name(&self) -> &str {
+        "normalize_language"
+    }
+
+    fn before_log(&self, event: &mut SerendipityEvent, _trace: &SerendipityTrace) -> MiddlewareDecision {
+        event.language = normalize_case(&event.language);
+        MiddlewareDecision::Continue
+    }
+}
+
+/// Clamp serendipity and confidence into [0, 1]
+pub struct ClampScores;
+
+impl TraceMiddleware for ClampScores {
+    fn name(&self) -> &str {
+        "clamp_scores"
+    }
+
+    fn before_log(&self, event: &mut SerendipityEvent, _trace: &SerendipityTrace) -> MiddlewareDecision {
+        event.serendipity_score = event.serendipity_score.clamp(0.0, 1.0);
+        event.confidence = event.confidence.clamp(0.0, 1.0);
+        MiddlewareDecision::Continue
+    }
+}
+
+/// Attach a fixed metadata entry to every event (without overwriting)
+pub struct AttachMetadata {
+    key: String,
+    value: String,
+}
+
+impl AttachMetadata {
+    /// Create an interceptor attaching `key = value`
+    pub fn new(key: &str, value: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl TraceMiddleware for AttachMetadata {
+    fn name(&self) -> &str {
+        "attach_metadata"
+    }
+
+    fn before_log(&self, event: &mut SerendipityEvent, _trace: &SerendipityTrace) -> MiddlewareDecision {
+        event.metadata.entry(self.key.clone()).or_insert_with(|| self.value.as_str().into());
+        MiddlewareDecision::Continue
+    }
+}
+
+/// Veto events with empty input or output text
+pub struct RejectEmptyText;
+
+impl TraceMiddleware for RejectEmptyText {
+    fn name(&self) -> &str {
+        "reject_empty_text"
+    }
+
+    fn before_log(&self, event: &mut SerendipityEvent, _trace: &SerendipityTrace) -> MiddlewareDecision {
+        if event.input.trim().is_empty() || event.output.trim().is_empty() {
+            MiddlewareDecision::Veto("empty input or output".to_string())
+        } else {
+            MiddlewareDecision::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+    use crate::error::SerenQaError;
+
+    struct CountAfter(Arc<AtomicUsize>);
+
+    impl TraceMiddleware for CountAfter {
+        fn name(&self) -> &str {
+            "count_after"
+        }
+
+        fn after_log(&self, _event: &SerendipityEvent, _trace: &SerendipityTrace) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_normalize_and_clamp() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.add_middleware(NormalizeLanguage);
+        trace.add_middleware(ClampScores);
+        trace.add_middleware(AttachMetadata::new("pipeline", "v1"));
+
+        trace.log_event(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input",
+            "output",
+            " EN_us ",
+            1.4,
+            -0.2,
+        ).unwrap();
+
+        let event = &trace.events[0];
+        assert_eq!(event.language, "en-US");
+        assert_eq!(event.serendipity_score, 1.0);
+        assert_eq!(event.confidence, 0.0);
+        assert_eq!(event.metadata.get("pipeline"), Some(&"v1".into()));
+        assert_eq!(trace.languages, vec!["en-US".to_string()]);
+    }
+
+    #[test]
+    fn test_veto_and_after_hooks() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.add_middleware(RejectEmptyText);
+        trace.add_middleware(CountAfter(counter.clone()));
+
+        let vetoed = trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "", "output", "en", 0.5, 0.5);
+        assert!(matches!(vetoed, Err(SerenQaError::Vetoed { .. })));
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "input", "output", "en", 0.5, 0.5).unwrap();
+
+        assert_eq!(trace.events.len(), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,96 @@
+// -*- coding: utf-8 -*-
+//! Schema Migrations for Serialized Traces
+//!
+//! Traces carry a `schema_version`. Loading goes through the JSON value
+//! first: each migration upgrades a trace from one version to the next until
+//! it reaches `TRACE_SCHEMA_VERSION`, and only then is it deserialized into
+//! the current struct. Traces written by a newer build are refused rather
+//! than silently losing fields. Migrations never touch hashed content, so a
+//! migrated trace keeps its provenance hash and signature.
+
+use serde_json::Value;
+use crate::serendipity_trace::{SerendipityTrace, TRACE_SCHEMA_VERSION, legacy_trace_schema_version};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Upgrade of a trace's JSON by one schema version
+pub type Migration = fn(&mut Value) -> SerenQaResult<()>;
+
+/// `MIGRATIONS[i]` upgrades version `i + 1` to version `i + 2`
+const MIGRATIONS: [Migration; (TRACE_SCHEMA_VERSION - 1) as usize] = [v1_to_v2];
+
+/// Version 1 traces predate the version field; their layout is otherwise
+/// current, as later fields all have defaults. Only checks the shape.
+fn v1_to_v2(trace: &mut Value) -> SerenQaResult<()> {
+    if !trace.is_object() {
+        return Err(SerenQaError::Json(serde::de::Error::custom("trace JSON is not an object")));
+    }
+    Ok(())
+}
+
+/// Schema version recorded in a trace's JSON (1 if absent)
+pub fn schema_version_of(trace: &Value) -> u32 {
+    trace.get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or_else(legacy_trace_schema_version, |v| v as u32)
+}
+
+/// Upgrade a trace's JSON to `TRACE_SCHEMA_VERSION`
+pub fn migrate(mut trace: Value) -> SerenQaResult<Value> {
+    let found = schema_version_of(&trace);
+    if found > TRACE_SCHEMA_VERSION {
+        return Err(SerenQaError::UnsupportedSchemaVersion { found, supported: TRACE_SCHEMA_VERSION });
+    }
+    for migration in MIGRATIONS.iter().skip(found.saturating_sub(1) as usize) {
+        migration(&mut trace)?;
+    }
+    if let Some(fields) = trace.as_object_mut() {
+        fields.insert("schema_version".to_string(), Value::from(TRACE_SCHEMA_VERSION));
+    }
+    Ok(trace)
+}
+
+impl SerendipityTrace {
+    /// Parse a trace written by this or any older schema version
+    pub fn from_json_any_version(json: &str) -> SerenQaResult<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        Ok(serde_json::from_value(migrate(value)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_upgrades_unversioned_traces() {
+        let trace = trace();
+        let mut legacy = serde_json::to_value(&trace).unwrap();
+        legacy.as_object_mut().unwrap().remove("schema_version");
+        // Fields added after version 1 may be missing entirely
+        legacy.as_object_mut().unwrap().remove("review");
+        assert_eq!(schema_version_of(&legacy), 1);
+
+        let loaded = SerendipityTrace::from_json_any_version(&legacy.to_string()).unwrap();
+        assert_eq!(loaded.schema_version, TRACE_SCHEMA_VERSION);
+        assert_eq!(loaded.compute_provenance_hash(), trace.compute_provenance_hash());
+        assert_eq!(schema_version_of(&serde_json::to_value(&loaded).unwrap()), TRACE_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_refuses_newer_and_malformed() {
+        let mut future = serde_json::to_value(trace()).unwrap();
+        future["schema_version"] = Value::from(99);
+        assert!(matches!(
+            SerendipityTrace::from_json_any_version(&future.to_string()),
+            Err(SerenQaError::UnsupportedSchemaVersion { found: 99, .. })
+        ));
+        assert!(SerendipityTrace::from_json_any_version("[1, 2]").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+// -*- coding: utf-8 -*-
+//! OpenTelemetry Span Export
+//!
+//! Maps a trace to OpenTelemetry spans so agent pipelines already running
+//! tracing infrastructure can inspect discovery journeys in Jaeger or Tempo.
+//! The trace becomes a root span, each event a child span (stage, agent, and
+//! language as attributes), and each transition a span link from the previous
+//! event. Requires the `otel` feature (`opentelemetry`, `opentelemetry_sdk`,
+//! and `opentelemetry-otlp` crates).
+#![cfg(feature = "otel")]
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+use opentelemetry::trace::{Link, Span, SpanContext, SpanKind, TraceContextExt, Tracer, TracerProvider as _};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::TracerProvider;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEvent, SerendipityTransition};
+use crate::error::SerenQaResult;
+
+/// Instrumentation scope of exported spans
+pub const OTEL_SCOPE: &str = "serenqa";
+
+/// Attributes of the root span for a trace
+pub fn trace_attributes(trace: &SerendipityTrace) -> Vec<KeyValue> {
+    vec![
+        KeyValue::new("seren.trace_id", trace.trace_id.clone()),
+        KeyValue::new("seren.contributor_id", trace.contributor_id.clone()),
+        KeyValue::new("seren.backend", trace.backend.clone()),
+        KeyValue::new("seren.discovery_name", trace.discovery_name.clone()),
+        KeyValue::new("seren.overall_serendipity", trace.overall_serendipity),
+        KeyValue::new("seren.languages", trace.languages.join(",")),
+    ]
+}
+
+/// Attributes of the span for one event
+pub fn event_attributes(event: &SerendipityEvent) -> Vec<KeyValue> {
+    vec![
+        KeyValue::new("seren.event_id", event.event_id.clone()),
+        KeyValue::new("seren.stage", event.stage.name()),
+        KeyValue::new("seren.agent", event.agent.name()),
+        KeyValue::new("seren.language", event.language.clone()),
+        KeyValue::new("seren.serendipity", event.serendipity_score),
+        KeyValue::new("seren.confidence", event.confidence),
+        KeyValue::new("seren.redacted", event.is_redacted()),
+    ]
+}
+
+/// Attributes of the link recording one transition
+pub fn transition_attributes(transition: &SerendipityTransition) -> Vec<KeyValue> {
+    let mut attributes = vec![
+        KeyValue::new("seren.transition_score", transition.transition_score),
+        KeyValue::new("seren.transition_reason", transition.reason.clone()),
+    ];
+    if let Some((from, to)) = &transition.language_shift {
+        attributes.push(KeyValue::new("seren.language_shift", format!("{} -> {}", from, to)));
+    }
+    attributes
+}
+
+/// Emit the trace as spans through `tracer`, returning the span context of
+/// each event by event ID. Event spans end when the next event starts; the
+/// last one ends at its own timestamp.
+pub fn export_spans<T: Tracer>(trace: &SerendipityTrace, tracer: &T) -> HashMap<String, SpanContext> {
+    let end = trace.events
+        .last()
+        .map(|e| e.timestamp)
+        .unwrap_or(trace.created_at);
+    let root = tracer
+        .span_builder(trace.discovery_name.clone())
+        .with_kind(SpanKind::Internal)
+        .with_start_time(SystemTime::from(trace.created_at))
+        .with_attributes(trace_attributes(trace))
+        .start(tracer);
+    let root_cx = Context::current_with_span(root);
+
+    let mut contexts: HashMap<String, SpanContext> = HashMap::new();
+    for (index, event) in trace.events.iter().enumerate() {
+        let links: Vec<Link> = trace.transitions
+            .iter()
+            .filter(|tr| tr.to_event == event.event_id)
+            .filter_map(|tr| {
+                contexts
+                    .get(&tr.from_event)
+                    .map(|cx| Link::new(cx.clone(), transition_attributes(tr), 0))
+            })
+            .collect();
+        let event_end = trace.events
+            .get(index + 1)
+            .map(|next| next.timestamp)
+            .unwrap_or(event.timestamp);
+
+        let mut span = tracer
+            .span_builder(event.stage.name())
+            .with_kind(SpanKind::Internal)
+            .with_start_time(SystemTime::from(event.timestamp))
+            .with_attributes(event_attributes(event))
+            .with_links(links)
+            .start_with_context(tracer, &root_cx);
+        contexts.insert(event.event_id.clone(), span.span_context().clone());
+        span.end_with_timestamp(SystemTime::from(event_end));
+    }
+
+    root_cx.span().end_with_timestamp(SystemTime::from(end));
+    contexts
+}
+
+/// Tracer provider exporting synchronously to an OTLP/HTTP collector
+/// (e.g., `http://localhost:4318/v1/traces`)
+pub fn otlp_provider(endpoint: &str) -> SerenQaResult<TracerProvider> {
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().http().with_endpoint(endpoint))
+        .install_simple()?;
+    Ok(provider)
+}
+
+/// Export `traces` to an OTLP collector and flush before returning
+pub fn export_otlp(traces: &[SerendipityTrace], endpoint: &str) -> SerenQaResult<()> {
+    let provider = otlp_provider(endpoint)?;
+    let tracer = provider.tracer(OTEL_SCOPE);
+    for trace in traces {
+        export_spans(trace, &tracer);
+    }
+    for result in provider.force_flush() {
+        result?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    #[test]
+    fn test_events_become_linked_child_spans() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.4, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "out", "id", 0.9, 0.8).unwrap();
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        let contexts = export_spans(&trace, &provider.tracer(OTEL_SCOPE));
+        assert_eq!(contexts.len(), 2);
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 3);
+        let root = spans.iter().find(|s| s.name == "Journavx").unwrap();
+        let connection = spans.iter().find(|s| s.name == "UnexpectedConnection").unwrap();
+        assert_eq!(connection.parent_span_id, root.span_context.span_id());
+        assert!(connection.attributes.contains(&KeyValue::new("seren.language", "id")));
+
+        let link = &connection.links.links[0];
+        assert_eq!(&link.span_context, &contexts[&trace.events[0].event_id]);
+        assert!(link.attributes.contains(&KeyValue::new("seren.language_shift", "en -> id")));
+    }
+
+    #[test]
+    fn test_empty_trace_exports_root_only() {
+        let trace = SerendipityTrace::new("researcher1", "backend", "Nothing yet");
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        assert!(export_spans(&trace, &provider.tracer(OTEL_SCOPE)).is_empty());
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 1);
+        // With no events the root span ends as it starts
+        assert_eq!(spans[0].start_time, spans[0].end_time);
+        assert!(spans[0].attributes.contains(&KeyValue::new("seren.languages", "")));
+    }
+
+    #[test]
+    fn test_non_ascii_names_and_no_language_shift() {
+        let mut trace = SerendipityTrace::new("peneliti", "backend", "Batik — 蝋纈染め");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "motif parang", "id", 0.4, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "フラクタル次元", "id", 0.9, 0.8).unwrap();
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        export_spans(&trace, &provider.tracer(OTEL_SCOPE));
+        let spans = exporter.get_finished_spans().unwrap();
+        let root = spans.iter().find(|s| s.name == "Batik — 蝋纈染め").unwrap();
+        assert!(root.attributes.contains(&KeyValue::new("seren.discovery_name", "Batik — 蝋纈染め")));
+
+        // A same-language transition links without a language shift attribute
+        let connection = spans.iter().find(|s| s.name == "UnexpectedConnection").unwrap();
+        let link = &connection.links.links[0];
+        assert!(link.attributes.iter().all(|kv| kv.key.as_str() != "seren.language_shift"));
+        assert!(link.attributes.contains(&KeyValue::new("seren.transition_reason", "Exploration -> UnexpectedConnection")));
+    }
+}
@@ -0,0 +1,191 @@
+// -*- coding: utf-8 -*-
+//! Ground-Truth Outcomes of Discoveries
+//!
+//! A serendipity score reflects how a discovery looked when it was logged.
+//! An `Outcome` records what happened to it afterwards: it was published,
+//! reproduced, failed to replicate, or was retracted. Each outcome has a date
+//! and evidence links such as a DOI or a replication report. A trace's current
+//! outcome is its latest one, except that a retraction is final. Outcomes
+//! arrive long after a trace is signed, so they are not part of the provenance
+//! hash. `record_outcomes` copies a trace's current outcome onto its
+//! contributors' statistics. `LanguageAwareRankingCriteria::VerifiedImpact`
+//! then ranks contributors by those outcomes, and retractions count against
+//! them.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::ContributorStats::{LanguageAwareContributorStats, LanguageAwareLeaderboard};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// What happened to a discovery after it was logged
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeKind {
+    /// Published in a venue
+    Published,
+    /// Independently reproduced
+    Reproduced,
+    /// An independent replication attempt failed
+    FailedReplication,
+    /// Withdrawn after publication
+    Retracted,
+}
+
+impl OutcomeKind {
+    /// Contribution of a discovery with this outcome to verified impact
+    pub fn impact(&self) -> f64 {
+        match self {
+            OutcomeKind::Published => 0.5,
+            OutcomeKind::Reproduced => 1.0,
+            OutcomeKind::FailedReplication => -0.5,
+            OutcomeKind::Retracted => -1.0,
+        }
+    }
+}
+
+/// Dated outcome of a trace's discovery, with its evidence
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Outcome {
+    pub kind: OutcomeKind,
+    /// When the outcome happened (e.g. the publication date)
+    pub at: DateTime<Utc>,
+    /// Links backing the outcome, e.g. a DOI or a replication report
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+}
+
+impl Outcome {
+    /// Outcome without evidence
+    pub fn new(kind: OutcomeKind, at: DateTime<Utc>) -> Self {
+        Self { kind, at, evidence: Vec::new(), note: String::new() }
+    }
+
+    /// Add an evidence link
+    pub fn with_evidence(mut self, link: &str) -> Self {
+        self.evidence.push(link.to_string());
+        self
+    }
+
+    /// Add a free-text note
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.note = note.to_string();
+        self
+    }
+}
+
+impl SerendipityTrace {
+    /// Record an outcome of the trace's discovery. It may not predate the
+    /// trace, and evidence links may not be blank.
+    pub fn record_outcome(&mut self, outcome: Outcome) -> SerenQaResult<()> {
+        if outcome.at < self.created_at {
+            return Err(SerenQaError::InvalidOutcome(format!("{:?} outcome predates trace {}", outcome.kind, self.trace_id)));
+        }
+        if outcome.evidence.iter().any(|link| link.trim().is_empty()) {
+            return Err(SerenQaError::InvalidOutcome("blank evidence link".to_string()));
+        }
+        self.outcomes.push(outcome);
+        self.outcomes.sort_by_key(|outcome| outcome.at);
+        Ok(())
+    }
+
+    /// Current outcome: a retraction if there is one, otherwise the latest outcome
+    pub fn current_outcome(&self) -> Option<&Outcome> {
+        self.outcomes
+            .iter()
+            .rfind(|outcome| outcome.kind == OutcomeKind::Retracted)
+            .or_else(|| self.outcomes.last())
+    }
+}
+
+impl LanguageAwareContributorStats {
+    /// Set the current outcome of one of the contributor's discoveries
+    pub fn record_outcome(&mut self, discovery_name: &str, kind: OutcomeKind) {
+        self.discovery_outcomes.insert(discovery_name.to_string(), kind);
+    }
+
+    /// Sum of the outcome impacts of the contributor's discoveries; discoveries
+    /// without an outcome add nothing, and failed or retracted ones subtract
+    pub fn verified_impact(&self) -> f64 {
+        self.discovery_outcomes.values().map(OutcomeKind::impact).sum()
+    }
+
+    /// Number of discoveries whose current outcome is a retraction
+    pub fn retracted_discoveries(&self) -> usize {
+        self.discovery_outcomes.values().filter(|kind| **kind == OutcomeKind::Retracted).count()
+    }
+}
+
+impl LanguageAwareLeaderboard {
+    /// Copy `trace`'s current outcome onto every credited contributor on the
+    /// leaderboard; returns how many were updated
+    pub fn record_outcomes(&mut self, trace: &SerendipityTrace) -> usize {
+        let Some(outcome) = trace.current_outcome() else { return 0 };
+        self.observed(|leaderboard| {
+            let mut updated = 0;
+            for contributor in trace.contributors() {
+                if let Some(stats) = leaderboard.contributors.get_mut(contributor) {
+                    stats.record_outcome(&trace.discovery_name, outcome.kind);
+                    updated += 1;
+                }
+            }
+            updated
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContributorStats::LanguageAwareRankingCriteria;
+    use chrono::{Duration, TimeZone};
+
+    fn trace(contributor_id: &str, discovery_name: &str) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor_id, "backend", discovery_name);
+        trace.created_at = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_current_outcome() {
+        let mut trace = trace("sari", "Journavx");
+        let day = |n: i64| trace.created_at + Duration::days(n);
+        let (published, retracted, reproduced) = (day(30), day(90), day(120));
+        assert!(trace.current_outcome().is_none());
+        trace.record_outcome(Outcome::new(OutcomeKind::Published, published).with_evidence("https://doi.org/10.1000/jvx")).unwrap();
+        assert_eq!(trace.current_outcome().unwrap().kind, OutcomeKind::Published);
+
+        // A retraction stands even when a later outcome arrives
+        trace.record_outcome(Outcome::new(OutcomeKind::Retracted, retracted).with_note("image duplication")).unwrap();
+        trace.record_outcome(Outcome::new(OutcomeKind::Reproduced, reproduced)).unwrap();
+        assert_eq!(trace.current_outcome().unwrap().kind, OutcomeKind::Retracted);
+
+        let before = trace.created_at - Duration::days(1);
+        assert!(trace.record_outcome(Outcome::new(OutcomeKind::Published, before)).is_err());
+        assert!(trace.record_outcome(Outcome::new(OutcomeKind::Published, published).with_evidence(" ")).is_err());
+        let json = serde_json::to_string(&trace).unwrap();
+        assert_eq!(serde_json::from_str::<SerendipityTrace>(&json).unwrap().outcomes, trace.outcomes);
+    }
+
+    #[test]
+    fn test_ranking_by_verified_impact() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        let mut sari = trace("sari", "Journavx");
+        let mut budi = trace("budi", "Star Compass");
+        for trace in [&sari, &budi] {
+            leaderboard.credit_trace(trace, 0.8, 0.8).unwrap();
+        }
+        let later = sari.created_at + Duration::days(60);
+        sari.record_outcome(Outcome::new(OutcomeKind::Retracted, later)).unwrap();
+        budi.record_outcome(Outcome::new(OutcomeKind::Reproduced, later)).unwrap();
+        assert_eq!(leaderboard.record_outcomes(&sari), 1);
+        assert_eq!(leaderboard.record_outcomes(&budi), 1);
+
+        let top = leaderboard.get_top_n(2, LanguageAwareRankingCriteria::VerifiedImpact);
+        assert_eq!(top[0].contributor_id, "budi");
+        assert_eq!(top[1].verified_impact(), -1.0);
+        assert_eq!(top[1].retracted_discoveries(), 1);
+    }
+}
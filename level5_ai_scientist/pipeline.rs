@@ -0,0 +1,395 @@
+// -*- coding: utf-8 -*-
+//! Composable End-to-End Analysis Pipeline
+//!
+//! `Pipeline` chains the SerenQA analysis steps (validate → lint → fold →
+//! patterns → score → report). Each step produces a typed artifact in
+//! `PipelineRun` and is timed; corpora run in parallel on worker threads.
+//! This is the supported way to run a full analysis; the Journavx demo is
+//! built on it.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::serendipity_trace::{SerendipityTrace, FoldedSerendipityTrace};
+use crate::submission::{SubmissionPipeline, RejectionCategory, RejectionIssue};
+use crate::AgentEvent::{LanguageAwareAgentEvent, LanguageMetadata};
+use crate::script::text_script;
+use crate::extraction::TermLexicon;
+use crate::fold_multilingual_memory::{MultilingualMemoryFolder, MultilingualMemoryFold};
+use crate::ContributorStats::LanguageAwareContributorStats;
+use crate::language_registry::LanguageRegistry;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Default number of worker threads for `run_corpus`
+pub const DEFAULT_PIPELINE_THREADS: usize = 4;
+
+/// Analysis step, in execution order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PipelineStep {
+    /// Check every event's scores, language tag, and metadata
+    Validate,
+    /// Structural lint checks (IDs, transition chain, stored scores)
+    Lint,
+    /// Fold the trace into its key discoveries
+    Fold,
+    /// Detect cross-language patterns
+    Patterns,
+    /// Score the contributor from the trace
+    Score,
+    /// Render a text report of every artifact
+    Report,
+}
+
+impl PipelineStep {
+    /// Every step in execution order
+    pub const ALL: [PipelineStep; 6] = [
+        PipelineStep::Validate,
+        PipelineStep::Lint,
+        PipelineStep::Fold,
+        PipelineStep::Patterns,
+        PipelineStep::Score,
+        PipelineStep::Report,
+    ];
+
+    /// Steps whose artifacts this step consumes
+    pub fn requires(&self) -> &'static [PipelineStep] {
+        match self {
+            PipelineStep::Validate | PipelineStep::Lint | PipelineStep::Fold | PipelineStep::Patterns => &[],
+            PipelineStep::Score => &[PipelineStep::Patterns],
+            PipelineStep::Report => &[PipelineStep::Fold],
+        }
+    }
+}
+
+/// Wall-clock time spent in one step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepTiming {
+    /// Step measured
+    pub step: PipelineStep,
+    /// Time spent
+    pub duration: Duration,
+}
+
+/// Artifacts produced by one pipeline run
+#[derive(Debug, Clone)]
+pub struct PipelineRun {
+    /// Trace analyzed
+    pub trace_id: String,
+    /// Number of events that passed validation
+    pub validated_events: Option<usize>,
+    /// Lint issues found (not fatal)
+    pub lint_issues: Option<Vec<RejectionIssue>>,
+    /// Folded trace
+    pub folded: Option<FoldedSerendipityTrace>,
+    /// Cross-language patterns and alignment
+    pub patterns: Option<MultilingualMemoryFold>,
+    /// Contributor statistics for the trace
+    pub stats: Option<LanguageAwareContributorStats>,
+    /// Rendered text report
+    pub report: Option<String>,
+    /// Time spent in each step that ran, in order
+    pub timings: Vec<StepTiming>,
+}
+
+impl PipelineRun {
+    fn new(trace_id: &str) -> Self {
+        Self {
+            trace_id: trace_id.to_string(),
+            validated_events: None,
+            lint_issues: None,
+            folded: None,
+            patterns: None,
+            stats: None,
+            report: None,
+            timings: Vec::new(),
+        }
+    }
+
+    /// Time spent in `step`, if it ran
+    pub fn timing(&self, step: PipelineStep) -> Option<Duration> {
+        self.timings.iter().find(|t| t.step == step).map(|t| t.duration)
+    }
+
+    /// Total time across all steps
+    pub fn total_duration(&self) -> Duration {
+        self.timings.iter().map(|t| t.duration).sum()
+    }
+}
+
+/// Language-aware view of a trace's events for multilingual folding
+pub fn language_events(trace: &SerendipityTrace) -> Vec<LanguageAwareAgentEvent> {
+    let registry = LanguageRegistry::global();
+    trace.events
+        .iter()
+        .map(|event| {
+            let mut lang_event = LanguageAwareAgentEvent::new(
+                &event.agent.name(),
+                &event.input,
+                &event.output,
+                &event.language,
+                event.confidence,
+            );
+            let mut metadata = LanguageMetadata::new(
+                &event.language,
+                &event.output,
+                &text_script(&event.output, &event.language),
+                registry.family(&event.language).unwrap_or("Unknown"),
+            );
+            for term in TermLexicon::global().find(&event.output) {
+                metadata.add_domain_term(&term);
+            }
+            lang_event.add_language_metadata(metadata);
+            lang_event
+        })
+        .collect()
+}
+
+/// Configurable chain of analysis steps
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    steps: Vec<PipelineStep>,
+    submission: SubmissionPipeline,
+    threads: usize,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pipeline {
+    /// Create a pipeline with no steps
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            submission: SubmissionPipeline::new(),
+            threads: DEFAULT_PIPELINE_THREADS,
+        }
+    }
+
+    /// Pipeline running every step
+    pub fn full() -> Self {
+        PipelineStep::ALL.iter().fold(Self::new(), |pipeline, step| pipeline.step(*step))
+    }
+
+    /// Add a step and the steps it requires
+    pub fn step(mut self, step: PipelineStep) -> Self {
+        for required in step.requires() {
+            self = self.step(*required);
+        }
+        if !self.steps.contains(&step) {
+            self.steps.push(step);
+            self.steps.sort();
+        }
+        self
+    }
+
+    /// Add the validation step
+    pub fn validate(self) -> Self {
+        self.step(PipelineStep::Validate)
+    }
+
+    /// Add the lint step
+    pub fn lint(self) -> Self {
+        self.step(PipelineStep::Lint)
+    }
+
+    /// Add the fold step
+    pub fn fold(self) -> Self {
+        self.step(PipelineStep::Fold)
+    }
+
+    /// Add the cross-language pattern step
+    pub fn patterns(self) -> Self {
+        self.step(PipelineStep::Patterns)
+    }
+
+    /// Add the scoring step (and pattern detection it needs)
+    pub fn score(self) -> Self {
+        self.step(PipelineStep::Score)
+    }
+
+    /// Add the report step (and folding it needs)
+    pub fn report(self) -> Self {
+        self.step(PipelineStep::Report)
+    }
+
+    /// Use these submission checks for linting
+    pub fn with_submission_checks(mut self, submission: SubmissionPipeline) -> Self {
+        self.submission = submission;
+        self
+    }
+
+    /// Set the number of worker threads for `run_corpus`
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Steps that will run, in order
+    pub fn steps(&self) -> &[PipelineStep] {
+        &self.steps
+    }
+
+    /// Run every configured step over one trace.
+    /// Validation failures and empty traces stop the run with an error.
+    pub fn run(&self, trace: &SerendipityTrace) -> SerenQaResult<PipelineRun> {
+        let mut run = PipelineRun::new(&trace.trace_id);
+        for step in &self.steps {
+            let started = Instant::now();
+            self.run_step(*step, trace, &mut run)?;
+            run.timings.push(StepTiming { step: *step, duration: started.elapsed() });
+        }
+        Ok(run)
+    }
+
+    fn run_step(&self, step: PipelineStep, trace: &SerendipityTrace, run: &mut PipelineRun) -> SerenQaResult<()> {
+        match step {
+            PipelineStep::Validate => {
+                if trace.events.is_empty() {
+                    return Err(SerenQaError::EmptyTrace(trace.trace_id.clone()));
+                }
+                for event in &trace.events {
+                    event.validate()?;
+                }
+                run.validated_events = Some(trace.events.len());
+            }
+            PipelineStep::Lint => {
+                let issues = self.submission
+                    .check(trace)
+                    .issues
+                    .into_iter()
+                    .filter(|issue| issue.category == RejectionCategory::Lint)
+                    .collect();
+                run.lint_issues = Some(issues);
+            }
+            PipelineStep::Fold => {
+                run.folded = Some(trace.fold_memory()?);
+            }
+            PipelineStep::Patterns => {
+                let mut folder = MultilingualMemoryFolder::new();
+                run.patterns = Some(folder.fold_memory(&trace.trace_id, &language_events(trace))?);
+            }
+            PipelineStep::Score => {
+                let patterns = run.patterns.as_ref().expect("patterns step runs before score");
+                let mut stats = LanguageAwareContributorStats::new(&trace.contributor_id);
+                stats.add_trace(
+                    trace.depth(),
+                    trace.uniqueness_score(),
+                    trace.overall_serendipity,
+                    trace.languages.clone(),
+                    patterns.overall_alignment,
+                    patterns.translation_summary.average_quality,
+                )?;
+                stats.add_discovery(&trace.discovery_name);
+                run.stats = Some(stats);
+            }
+            PipelineStep::Report => {
+                run.report = Some(render_report(trace, run));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the pipeline over every trace in parallel.
+    /// Results are returned in input order.
+    pub fn run_corpus(&self, traces: &[SerendipityTrace]) -> Vec<SerenQaResult<PipelineRun>> {
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<(usize, SerenQaResult<PipelineRun>)>> = Mutex::new(Vec::with_capacity(traces.len()));
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads.clamp(1, traces.len().max(1)) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(trace) = traces.get(index) else { break };
+                    let result = self.run(trace);
+                    results.lock().unwrap().push((index, result));
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+/// Text report of the artifacts produced so far
+fn render_report(trace: &SerendipityTrace, run: &PipelineRun) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "Trace {} ({})", trace.trace_id, trace.discovery_name);
+    let _ = writeln!(report, "Contributor: {}", trace.contributor_id);
+    let _ = writeln!(report, "Overall Serendipity: {:.3}", trace.overall_serendipity);
+    if let Some(issues) = &run.lint_issues {
+        let _ = writeln!(report, "Lint Issues: {}", issues.len());
+        for issue in issues {
+            let _ = writeln!(report, "  • {:?}: {}", issue.code, issue.message);
+        }
+    }
+    if let Some(folded) = &run.folded {
+        let _ = writeln!(report, "Compression Ratio: {:.1}%", folded.compression_ratio * 100.0);
+        let _ = writeln!(report, "Key Discoveries ({}):", folded.key_discoveries.len());
+        for (i, discovery) in folded.key_discoveries.iter().enumerate() {
+            let _ = writeln!(report, "  {}. {}", i + 1, discovery);
+        }
+    }
+    if let Some(patterns) = &run.patterns {
+        let _ = writeln!(report, "Overall Alignment: {:.3}", patterns.overall_alignment);
+        let _ = writeln!(report, "Cross-Language Patterns: {}", patterns.cross_language_patterns.len());
+    }
+    if let Some(stats) = &run.stats {
+        let _ = writeln!(report, "Contributor Score: {:.3}", stats.overall_score());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn sample_trace(contributor: &str) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor, "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "routes", "en", 0.5, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "wayfinding", "id", 0.9, 0.8).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_steps_pull_in_requirements() {
+        let pipeline = Pipeline::new().report().score();
+        assert_eq!(
+            pipeline.steps(),
+            &[PipelineStep::Fold, PipelineStep::Patterns, PipelineStep::Score, PipelineStep::Report]
+        );
+        assert_eq!(Pipeline::full().steps(), &PipelineStep::ALL);
+    }
+
+    #[test]
+    fn test_full_run_produces_every_artifact() {
+        let run = Pipeline::full().run(&sample_trace("researcher1")).unwrap();
+        assert_eq!(run.validated_events, Some(2));
+        assert!(run.lint_issues.as_ref().unwrap().is_empty());
+        assert_eq!(run.folded.as_ref().unwrap().key_discoveries.len(), 1);
+        assert!(run.stats.as_ref().unwrap().overall_score() > 0.0);
+        assert!(run.report.as_ref().unwrap().contains("Key Discoveries (1):"));
+        assert_eq!(run.timings.len(), PipelineStep::ALL.len());
+        assert!(run.timing(PipelineStep::Fold).is_some());
+    }
+
+    #[test]
+    fn test_corpus_runs_in_order_and_reports_failures() {
+        let mut traces: Vec<SerendipityTrace> = (0..5).map(|i| sample_trace(&format!("researcher{}", i))).collect();
+        traces[2].events[0].serendipity_score = 1.5;
+        traces.push(SerendipityTrace::new("empty", "backend", "Nothing"));
+
+        let results = Pipeline::new().validate().fold().with_threads(3).run_corpus(&traces);
+        assert_eq!(results.len(), 6);
+        assert_eq!(results[1].as_ref().unwrap().trace_id, traces[1].trace_id);
+        assert!(matches!(results[2], Err(SerenQaError::ScoreOutOfRange { .. })));
+        assert!(matches!(results[5], Err(SerenQaError::EmptyTrace(_))));
+        assert!(results[4].as_ref().unwrap().stats.is_none());
+    }
+}
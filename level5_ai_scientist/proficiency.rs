@@ -0,0 +1,193 @@
+// -*- coding: utf-8 -*-
+//! Language Proficiency Model
+//!
+//! A contributor's proficiency in a language is estimated from the alignment
+//! and translation quality of their traces in it. Each quality is an
+//! exponentially weighted moving average (recent traces count most; the
+//! weight of older ones decays by `1 − alpha` per trace) with its weighted
+//! variance, and proficiency is the mean of the two. The effective sample
+//! size of the weights gives a 95% confidence interval, so a single excellent
+//! trace reads as promising rather than proven. Until a language has two
+//! traces the interval is the whole [0, 1] range.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Weight of the newest trace in the moving averages
+pub const DEFAULT_PROFICIENCY_ALPHA: f64 = 0.3;
+
+/// Normal quantile of the 95% confidence interval
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// Exponentially weighted mean and variance of one quality
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct QualityEwma {
+    pub mean: f64,
+    /// Weighted sum of squared deviations from the mean
+    sum_squares: f64,
+    /// Sum of the decayed sample weights
+    weight: f64,
+    /// Sum of the squared decayed sample weights
+    weight_squares: f64,
+}
+
+impl QualityEwma {
+    /// Add a sample weighted 1, decaying earlier samples by `1 − alpha`
+    pub fn update(&mut self, value: f64, alpha: f64) {
+        let decay = 1.0 - alpha;
+        self.weight = decay * self.weight + 1.0;
+        self.weight_squares = decay * decay * self.weight_squares + 1.0;
+        // Weighted Welford update, with the old state decayed first
+        let delta = value - self.mean;
+        self.mean += delta / self.weight;
+        self.sum_squares = decay * self.sum_squares + delta * (value - self.mean);
+    }
+
+    /// Weighted variance of the samples
+    pub fn variance(&self) -> f64 {
+        if self.weight > 0.0 { self.sum_squares / self.weight } else { 0.0 }
+    }
+
+    /// Kish effective sample size of the decayed weights
+    pub fn effective_samples(&self) -> f64 {
+        if self.weight_squares > 0.0 { self.weight * self.weight / self.weight_squares } else { 0.0 }
+    }
+}
+
+/// Proficiency model state for one language
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LanguageProficiency {
+    /// Traces seen in the language
+    pub samples: usize,
+    pub alignment: QualityEwma,
+    pub translation: QualityEwma,
+}
+
+impl LanguageProficiency {
+    /// Add a trace's alignment and translation quality
+    pub fn update(&mut self, alignment_score: f64, translation_quality: f64, alpha: f64) {
+        self.samples += 1;
+        self.alignment.update(alignment_score, alpha);
+        self.translation.update(translation_quality, alpha);
+    }
+
+    /// Mean of the alignment and translation averages
+    pub fn proficiency(&self) -> f64 {
+        (self.alignment.mean + self.translation.mean) / 2.0
+    }
+
+    /// 95% confidence interval of the proficiency, within [0, 1]
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        if self.samples < 2 {
+            return (0.0, 1.0);
+        }
+        // Variance of the mean of two averages, treated as independent
+        let variance = (self.alignment.variance() + self.translation.variance()) / 4.0;
+        let margin = CONFIDENCE_Z * (variance / self.alignment.effective_samples()).sqrt();
+        let proficiency = self.proficiency();
+        ((proficiency - margin).max(0.0), (proficiency + margin).min(1.0))
+    }
+
+    /// Estimate for reporting
+    pub fn estimate(&self, language: &str) -> ProficiencyEstimate {
+        let (ci_low, ci_high) = self.confidence_interval();
+        ProficiencyEstimate {
+            language: language.to_string(),
+            proficiency: self.proficiency(),
+            alignment: self.alignment.mean,
+            translation: self.translation.mean,
+            samples: self.samples,
+            effective_samples: self.alignment.effective_samples(),
+            ci_low,
+            ci_high,
+        }
+    }
+}
+
+/// Proficiency in one language with its uncertainty
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProficiencyEstimate {
+    pub language: String,
+    pub proficiency: f64,
+    /// Moving average of alignment quality
+    pub alignment: f64,
+    /// Moving average of translation quality
+    pub translation: f64,
+    /// Traces seen in the language
+    pub samples: usize,
+    /// Effective sample size of the moving-average weights
+    pub effective_samples: f64,
+    /// Lower bound of the 95% confidence interval
+    pub ci_low: f64,
+    /// Upper bound of the 95% confidence interval
+    pub ci_high: f64,
+}
+
+/// Contributor's proficiency in every language they have used
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProficiencyReport {
+    pub contributor_id: String,
+    /// Estimates, most proficient first (ties by language tag)
+    pub languages: Vec<ProficiencyEstimate>,
+}
+
+impl ProficiencyReport {
+    /// Report over the per-language model states
+    pub fn new(contributor_id: &str, models: &BTreeMap<String, LanguageProficiency>) -> Self {
+        let mut languages: Vec<ProficiencyEstimate> =
+            models.iter().map(|(language, model)| model.estimate(language)).collect();
+        // Sorting is stable, so ties keep the tag order
+        languages.sort_by(|a, b| b.proficiency.total_cmp(&a.proficiency));
+        Self { contributor_id: contributor_id.to_string(), languages }
+    }
+
+    /// Estimate for `language`, if the contributor has used it
+    pub fn language(&self, language: &str) -> Option<&ProficiencyEstimate> {
+        self.languages.iter().find(|estimate| estimate.language == language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ewma_tracks_recent_quality() {
+        let mut quality = QualityEwma::default();
+        quality.update(0.2, 0.5);
+        assert_eq!(quality.mean, 0.2);
+        quality.update(0.8, 0.5);
+        // Weights 0.5 and 1: (0.5 · 0.2 + 0.8) / 1.5
+        assert!((quality.mean - 0.6).abs() < 1e-12);
+        assert!((quality.variance() - 0.08).abs() < 1e-12);
+        assert!((quality.effective_samples() - 1.8).abs() < 1e-12);
+
+        // Many samples: effective size saturates at (2 − alpha) / alpha
+        let mut steady = QualityEwma::default();
+        for _ in 0..200 {
+            steady.update(0.7, 0.5);
+        }
+        assert!((steady.mean - 0.7).abs() < 1e-12);
+        assert!((steady.effective_samples() - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_confidence_narrows_with_samples() {
+        let mut model = LanguageProficiency::default();
+        model.update(0.9, 0.8, DEFAULT_PROFICIENCY_ALPHA);
+        assert_eq!(model.confidence_interval(), (0.0, 1.0));
+        let mut widths = Vec::new();
+        for (alignment, translation) in [(0.7, 0.9), (0.8, 0.7), (0.9, 0.8), (0.8, 0.9)] {
+            model.update(alignment, translation, DEFAULT_PROFICIENCY_ALPHA);
+            let (low, high) = model.confidence_interval();
+            assert!(low <= model.proficiency() && model.proficiency() <= high);
+            widths.push(high - low);
+        }
+        assert!(widths[3] < widths[0]);
+
+        let models = BTreeMap::from([("en".to_string(), model), ("id".to_string(), LanguageProficiency::default())]);
+        let report = ProficiencyReport::new("sari", &models);
+        assert_eq!(report.languages[0].language, "en");
+        assert_eq!(report.language("en").unwrap().samples, 5);
+    }
+}
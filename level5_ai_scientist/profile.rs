@@ -0,0 +1,291 @@
+// -*- coding: utf-8 -*-
+//! Contributor Profiles with Opt-In Public Export
+//!
+//! A `ContributorProfile` pairs contributor statistics with the contributor's
+//! consent flags. `export_public` produces the public-facing profile the
+//! leaderboard site publishes (JSON or Markdown): only sections that both the
+//! site policy requests and the contributor consented to are included, and
+//! private fields (raw proficiency scores, activity timestamps, custom
+//! metrics) are never exported.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use crate::ContributorStats::LanguageAwareContributorStats;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Achievement shown on a public profile
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Badge {
+    /// At least one credited discovery
+    Discoverer,
+    /// Worked in three or more languages
+    Polyglot,
+    /// Cross-language expertise of at least 0.7
+    LanguageBridge,
+    /// Average serendipity of at least 0.8
+    SerendipitySeeker,
+    /// Ten or more traces submitted
+    Prolific,
+}
+
+impl Badge {
+    /// Display label
+    pub fn label(&self) -> &'static str {
+        match self {
+            Badge::Discoverer => "Discoverer",
+            Badge::Polyglot => "Polyglot",
+            Badge::LanguageBridge => "Language Bridge",
+            Badge::SerendipitySeeker => "Serendipity Seeker",
+            Badge::Prolific => "Prolific",
+        }
+    }
+
+    /// Badges earned by a contributor
+    pub fn earned(stats: &LanguageAwareContributorStats) -> Vec<Badge> {
+        let rules = [
+            (Badge::Discoverer, !stats.discoveries.is_empty()),
+            (Badge::Polyglot, stats.languages_used.len() >= 3),
+            (Badge::LanguageBridge, stats.cross_language_expertise >= 0.7),
+            (Badge::SerendipitySeeker, stats.avg_serendipity >= 0.8),
+            (Badge::Prolific, stats.total_traces >= 10),
+        ];
+        rules.iter().filter(|(_, earned)| *earned).map(|(badge, _)| *badge).collect()
+    }
+}
+
+/// What the contributor agreed to publish. Everything defaults to private.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ProfileConsent {
+    /// Contributor opted in to a public profile at all
+    #[serde(default)]
+    pub public: bool,
+    /// Scores may be shown
+    #[serde(default)]
+    pub scores: bool,
+    /// Discoveries may be listed
+    #[serde(default)]
+    pub discoveries: bool,
+    /// Languages and expertise domains may be listed
+    #[serde(default)]
+    pub languages: bool,
+}
+
+impl ProfileConsent {
+    /// Consent to publish every section
+    pub fn all() -> Self {
+        Self { public: true, scores: true, discoveries: true, languages: true }
+    }
+}
+
+/// Sections the publishing site wants in public profiles
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PublicProfilePolicy {
+    /// Include overall score, average serendipity, and trace count
+    pub include_scores: bool,
+    /// Include earned badges
+    pub include_badges: bool,
+    /// Include discoveries
+    pub include_discoveries: bool,
+    /// Include languages and expertise domains
+    pub include_languages: bool,
+}
+
+impl Default for PublicProfilePolicy {
+    fn default() -> Self {
+        Self {
+            include_scores: true,
+            include_badges: true,
+            include_discoveries: true,
+            include_languages: true,
+        }
+    }
+}
+
+/// Contributor statistics with consent and display settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContributorProfile {
+    /// Underlying statistics (private)
+    pub stats: LanguageAwareContributorStats,
+    /// Name shown publicly instead of the contributor ID
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Consent flags
+    #[serde(default)]
+    pub consent: ProfileConsent,
+}
+
+/// Public-facing profile with private fields stripped
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicProfile {
+    /// Display name, or the contributor ID if none is set
+    pub name: String,
+    /// Overall contributor score
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overall_score: Option<f64>,
+    /// Average serendipity across traces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_serendipity: Option<f64>,
+    /// Number of traces submitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_traces: Option<usize>,
+    /// Earned badges
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub badges: Vec<Badge>,
+    /// Credited discoveries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub discoveries: Vec<String>,
+    /// Languages worked in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    /// Expertise domains
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expertise_domains: Vec<String>,
+}
+
+impl ContributorProfile {
+    /// Create a private profile (no consent given)
+    pub fn new(stats: LanguageAwareContributorStats) -> Self {
+        Self {
+            stats,
+            display_name: None,
+            consent: ProfileConsent::default(),
+        }
+    }
+
+    /// Set the public display name
+    pub fn display_name(mut self, name: &str) -> Self {
+        self.display_name = Some(name.to_string());
+        self
+    }
+
+    /// Set the consent flags
+    pub fn consent(mut self, consent: ProfileConsent) -> Self {
+        self.consent = consent;
+        self
+    }
+
+    /// Public profile restricted to what `policy` requests and the contributor
+    /// consented to. Fails with `ConsentRequired` unless the contributor opted
+    /// in to a public profile.
+    pub fn export_public(&self, policy: &PublicProfilePolicy) -> SerenQaResult<PublicProfile> {
+        if !self.consent.public {
+            return Err(SerenQaError::ConsentRequired(self.stats.contributor_id.clone()));
+        }
+        let stats = &self.stats;
+        let scores = policy.include_scores && self.consent.scores;
+        let languages = policy.include_languages && self.consent.languages;
+        let discoveries = policy.include_discoveries && self.consent.discoveries;
+
+        Ok(PublicProfile {
+            name: self.display_name.clone().unwrap_or_else(|| stats.contributor_id.clone()),
+            overall_score: scores.then(|| stats.overall_score()),
+            avg_serendipity: scores.then_some(stats.avg_serendipity),
+            total_traces: scores.then_some(stats.total_traces),
+            // Badges reveal thresholds on scores, languages, and discoveries
+            badges: if policy.include_badges && scores && languages && discoveries {
+                Badge::earned(stats)
+            } else {
+                Vec::new()
+            },
+            discoveries: if discoveries { stats.discoveries.clone() } else { Vec::new() },
+            languages: if languages { stats.languages_used.clone() } else { Vec::new() },
+            expertise_domains: if languages { stats.expertise_domains.clone() } else { Vec::new() },
+        })
+    }
+}
+
+impl PublicProfile {
+    /// Serialize as pretty JSON
+    pub fn to_json(&self) -> SerenQaResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Render as a Markdown profile page
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# {}\n", self.name);
+        if let Some(score) = self.overall_score {
+            let _ = writeln!(md, "**Overall score:** {:.3}  ", score);
+        }
+        if let Some(serendipity) = self.avg_serendipity {
+            let _ = writeln!(md, "**Average serendipity:** {:.3}  ", serendipity);
+        }
+        if let Some(traces) = self.total_traces {
+            let _ = writeln!(md, "**Traces:** {}\n", traces);
+        }
+        if !self.badges.is_empty() {
+            let labels: Vec<&str> = self.badges.iter().map(Badge::label).collect();
+            let _ = writeln!(md, "**Badges:** {}\n", labels.join(" · "));
+        }
+        let sections = [
+            ("Discoveries", &self.discoveries),
+            ("Languages", &self.languages),
+            ("Expertise", &self.expertise_domains),
+        ];
+        for (title, items) in sections {
+            if items.is_empty() {
+                continue;
+            }
+            let _ = writeln!(md, "## {}\n", title);
+            for item in items {
+                let _ = writeln!(md, "- {}", item);
+            }
+            md.push('\n');
+        }
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> LanguageAwareContributorStats {
+        let mut stats = LanguageAwareContributorStats::new("sari@example.org");
+        let languages = vec!["en".to_string(), "id".to_string(), "jv".to_string()];
+        stats.add_trace(9, 0.8, 0.85, languages, 0.9, 0.9).unwrap();
+        stats.add_discovery("Journavx");
+        stats.add_expertise_domain("Quantum Computing");
+        stats
+    }
+
+    #[test]
+    fn test_private_by_default() {
+        let profile = ContributorProfile::new(stats());
+        assert!(matches!(
+            profile.export_public(&PublicProfilePolicy::default()),
+            Err(SerenQaError::ConsentRequired(_))
+        ));
+    }
+
+    #[test]
+    fn test_consent_limits_sections() {
+        let consent = ProfileConsent { public: true, discoveries: true, ..Default::default() };
+        let profile = ContributorProfile::new(stats()).display_name("Dr. Sari Wijaya").consent(consent);
+        let public = profile.export_public(&PublicProfilePolicy::default()).unwrap();
+
+        assert_eq!(public.name, "Dr. Sari Wijaya");
+        assert_eq!(public.discoveries, vec!["Journavx".to_string()]);
+        assert!(public.overall_score.is_none() && public.languages.is_empty() && public.badges.is_empty());
+        let json = public.to_json().unwrap();
+        assert!(!json.contains("sari@example.org"));
+        assert!(!json.contains("overall_score"));
+    }
+
+    #[test]
+    fn test_full_export_with_badges() {
+        let profile = ContributorProfile::new(stats()).consent(ProfileConsent::all());
+        let policy = PublicProfilePolicy { include_discoveries: false, ..Default::default() };
+        let public = profile.export_public(&policy).unwrap();
+        assert!(public.discoveries.is_empty());
+        assert!(public.badges.is_empty());
+
+        let public = profile.export_public(&PublicProfilePolicy::default()).unwrap();
+        assert_eq!(public.badges, vec![Badge::Discoverer, Badge::Polyglot, Badge::SerendipitySeeker]);
+        let md = public.to_markdown();
+        assert!(md.starts_with("# sari@example.org\n"));
+        assert!(md.contains("**Badges:** Discoverer · Polyglot · Serendipity Seeker"));
+        assert!(md.contains("## Languages\n\n- en\n- id\n- jv\n"));
+    }
+}
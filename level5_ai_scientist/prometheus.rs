@@ -0,0 +1,178 @@
+// -*- coding: utf-8 -*-
+//! Prometheus Metrics
+//!
+//! Operators running the trace recorder as a service need to see it working.
+//! With the `metrics` feature, the process keeps global counters of events
+//! logged (and their mean serendipity), traces folded, and alignment cache
+//! lookups, plus a gauge of the leaderboard size, and renders them in the
+//! Prometheus text exposition format. With the `server` feature as well, the
+//! API serves them at `GET /metrics`. The format is plain text, so no
+//! metrics crate is needed.
+#![cfg(feature = "metrics")]
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use crate::alignment_cache::AlignmentCacheStats;
+use crate::ContributorStats::LanguageAwareLeaderboard;
+
+/// Prefix of every exported metric name
+pub const METRIC_PREFIX: &str = "serenqa";
+
+/// Process-wide counters and gauges
+#[derive(Debug, Default)]
+pub struct RecorderMetrics {
+    events_logged: AtomicU64,
+    /// Sum of logged serendipity scores, as `f64` bits
+    serendipity_sum: AtomicU64,
+    traces_folded: AtomicU64,
+    alignment_cache_hits: AtomicU64,
+    alignment_cache_misses: AtomicU64,
+    leaderboard_size: AtomicU64,
+}
+
+impl RecorderMetrics {
+    /// Counters starting at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counters fed by the trace recorder in this process
+    pub fn global() -> &'static RecorderMetrics {
+        static GLOBAL: OnceLock<RecorderMetrics> = OnceLock::new();
+        GLOBAL.get_or_init(Self::new)
+    }
+
+    /// Count a logged event
+    pub fn record_event(&self, serendipity: f64) {
+        self.events_logged.fetch_add(1, Ordering::Relaxed);
+        let _ = self.serendipity_sum.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + serendipity).to_bits())
+        });
+    }
+
+    /// Count a folded trace
+    pub fn record_fold(&self) {
+        self.traces_folded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an alignment cache lookup
+    pub fn record_alignment_lookup(&self, hit: bool) {
+        let counter = if hit { &self.alignment_cache_hits } else { &self.alignment_cache_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Set the leaderboard-size gauge from `leaderboard`
+    pub fn observe_leaderboard(&self, leaderboard: &LanguageAwareLeaderboard) {
+        self.leaderboard_size.store(leaderboard.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Current values
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let events_logged = self.events_logged.load(Ordering::Relaxed);
+        let serendipity_sum = f64::from_bits(self.serendipity_sum.load(Ordering::Relaxed));
+        let alignment_cache = AlignmentCacheStats {
+            hits: self.alignment_cache_hits.load(Ordering::Relaxed),
+            misses: self.alignment_cache_misses.load(Ordering::Relaxed),
+            ..AlignmentCacheStats::default()
+        };
+        MetricsSnapshot {
+            events_logged,
+            average_serendipity: if events_logged > 0 { serendipity_sum / events_logged as f64 } else { 0.0 },
+            traces_folded: self.traces_folded.load(Ordering::Relaxed),
+            alignment_cache_hits: alignment_cache.hits,
+            alignment_cache_misses: alignment_cache.misses,
+            alignment_cache_hit_rate: alignment_cache.hit_rate(),
+            leaderboard_size: self.leaderboard_size.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Current values in the Prometheus text format
+    pub fn to_prometheus(&self) -> String {
+        self.snapshot().to_prometheus()
+    }
+}
+
+/// Metric values at one moment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub events_logged: u64,
+    /// Mean serendipity of the logged events (0 before any)
+    pub average_serendipity: f64,
+    pub traces_folded: u64,
+    pub alignment_cache_hits: u64,
+    pub alignment_cache_misses: u64,
+    /// Hits over lookups (0 before any)
+    pub alignment_cache_hit_rate: f64,
+    pub leaderboard_size: u64,
+}
+
+/// One metric with its help line and type
+fn metric(text: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(text, "# HELP {}_{} {}", METRIC_PREFIX, name, help);
+    let _ = writeln!(text, "# TYPE {}_{} {}", METRIC_PREFIX, name, kind);
+    let _ = writeln!(text, "{}_{} {}", METRIC_PREFIX, name, value);
+}
+
+impl MetricsSnapshot {
+    /// Prometheus text exposition format (version 0.0.4)
+    pub fn to_prometheus(&self) -> String {
+        let mut text = String::new();
+        metric(&mut text, "events_logged_total", "counter", "Events logged into traces.", self.events_logged);
+        metric(&mut text, "average_serendipity", "gauge", "Mean serendipity of logged events.", self.average_serendipity);
+        metric(&mut text, "traces_folded_total", "counter", "Traces folded into memory summaries.", self.traces_folded);
+        metric(&mut text, "alignment_cache_hits_total", "counter", "Alignment cache hits.", self.alignment_cache_hits);
+        metric(&mut text, "alignment_cache_misses_total", "counter", "Alignment cache misses.", self.alignment_cache_misses);
+        metric(&mut text, "alignment_cache_hit_rate", "gauge", "Alignment cache hits over lookups.", self.alignment_cache_hit_rate);
+        metric(&mut text, "leaderboard_size", "gauge", "Contributors on the leaderboard.", self.leaderboard_size);
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage, SerendipityTrace};
+    use crate::ContributorStats::LanguageAwareContributorStats;
+
+    #[test]
+    fn test_prometheus_text() {
+        let metrics = RecorderMetrics::new();
+        metrics.record_event(0.2);
+        metrics.record_event(0.6);
+        metrics.record_fold();
+        metrics.record_alignment_lookup(true);
+        metrics.record_alignment_lookup(false);
+        metrics.record_alignment_lookup(false);
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        leaderboard.add_contributor(LanguageAwareContributorStats::new("sari"));
+        metrics.observe_leaderboard(&leaderboard);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!((snapshot.events_logged, snapshot.traces_folded, snapshot.leaderboard_size), (2, 1, 1));
+        assert!((snapshot.average_serendipity - 0.4).abs() < 1e-12);
+        assert!((snapshot.alignment_cache_hit_rate - 1.0 / 3.0).abs() < 1e-12);
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains(
+            "# HELP serenqa_events_logged_total Events logged into traces.\n\
+             # TYPE serenqa_events_logged_total counter\n\
+             serenqa_events_logged_total 2\n"
+        ));
+        assert!(text.contains("serenqa_leaderboard_size 1\n"));
+        assert_eq!(text.lines().filter(|line| !line.starts_with('#')).count(), 7);
+    }
+
+    #[test]
+    fn test_recorder_feeds_global_metrics() {
+        let before = RecorderMetrics::global().snapshot();
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+        trace.fold_memory().unwrap();
+        // Other tests log concurrently, so only lower bounds hold
+        let after = RecorderMetrics::global().snapshot();
+        assert!(after.events_logged > before.events_logged);
+        assert!(after.traces_folded > before.traces_folded);
+    }
+}
@@ -0,0 +1,359 @@
+// -*- coding: utf-8 -*-
+//! Merkle-Tree Provenance for Serendipity Traces
+//!
+//! Builds a Merkle tree over per-event leaf hashes so a contributor can prove
+//! that a specific discovery step belongs to a trace (via an inclusion proof
+//! against the published root) without revealing the rest of the trace.
+//! Traces can also be signed with Ed25519 so a contributor can claim a
+//! discovery and maintainers can reject tampered or impersonated traces.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use crate::serendipity_trace::{SerendipityEvent, SerendipityTrace};
+use crate::canonical::HashVersion;
+
+/// Domain-separation prefix for leaf hashes
+const LEAF_PREFIX: u8 = 0x00;
+/// Domain-separation prefix for internal node hashes
+const NODE_PREFIX: u8 = 0x01;
+
+/// 32-byte SHA-256 digest
+pub type Hash32 = [u8; 32];
+
+/// Hex-encode a digest
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode a hex string into bytes
+pub fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Decode a 64-character hex digest
+pub fn from_hex(hex: &str) -> Option<Hash32> {
+    hex_decode(hex)?.try_into().ok()
+}
+
+/// Compute the leaf hash of a single event
+pub fn event_leaf_hash(event: &SerendipityEvent) -> Hash32 {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    for field in [
+        event.event_id.as_str(),
+        &format!("{:?}", event.stage),
+        &format!("{:?}", event.agent),
+        &event.content_hash(),
+        &event.language,
+    ] {
+        // Length-prefix each field so boundaries are unambiguous
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.update(event.serendipity_score.to_le_bytes());
+    hasher.update(event.confidence.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Hash two child nodes into their parent
+fn node_hash(left: &Hash32, right: &Hash32) -> Hash32 {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Sibling on the path from a leaf to the root
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProofStep {
+    /// Sibling hash (hex)
+    pub sibling: String,
+    /// Whether the sibling is the left child
+    pub sibling_is_left: bool,
+}
+
+/// Inclusion proof for a single event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// Event being proven
+    pub event_id: String,
+    /// Position of the event in the trace
+    pub leaf_index: usize,
+    /// Total number of leaves in the tree
+    pub leaf_count: usize,
+    /// Leaf hash (hex)
+    pub leaf_hash: String,
+    /// Sibling hashes from leaf to root
+    pub path: Vec<ProofStep>,
+    /// Merkle root the proof was generated against (hex)
+    pub root: String,
+}
+
+/// Merkle tree over the events of a trace
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    /// Tree levels, leaves first and root last
+    levels: Vec<Vec<Hash32>>,
+    /// Event IDs in leaf order
+    event_ids: Vec<String>,
+}
+
+impl MerkleTree {
+    /// Build a tree from the events of a trace
+    pub fn from_events(events: &[SerendipityEvent]) -> Self {
+        let leaves: Vec<Hash32> = events.iter().map(event_leaf_hash).collect();
+        let event_ids = events.iter().map(|e| e.event_id.clone()).collect();
+
+        let mut levels = vec![leaves];
+        while levels.last().map(|l| l.len() > 1).unwrap_or(false) {
+            let level = levels.last().unwrap();
+            let next: Vec<Hash32> = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    // Odd node is promoted unchanged
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        Self { levels, event_ids }
+    }
+
+    /// Number of leaves
+    pub fn leaf_count(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Root hash (hex); the hash of empty input for an empty trace
+    pub fn root(&self) -> String {
+        match self.levels.last().and_then(|l| l.first()) {
+            Some(root) => to_hex(root),
+            None => to_hex(&Sha256::digest(b"")),
+        }
+    }
+
+    /// Generate an inclusion proof for an event
+    pub fn prove(&self, event_id: &str) -> Option<MerkleProof> {
+        let leaf_index = self.event_ids.iter().position(|id| id == event_id)?;
+
+        let mut path = Vec::new();
+        let mut index = leaf_index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling_index = index ^ 1;
+            if let Some(sibling) = level.get(sibling_index) {
+                path.push(ProofStep {
+                    sibling: to_hex(sibling),
+                    sibling_is_left: sibling_index < index,
+                });
+            }
+            index /= 2;
+        }
+
+        Some(MerkleProof {
+            event_id: event_id.to_string(),
+            leaf_index,
+            leaf_count: self.leaf_count(),
+            leaf_hash: to_hex(&self.levels[0][leaf_index]),
+            path,
+            root: self.root(),
+        })
+    }
+}
+
+/// Verify that `event` is included under `root` according to `proof`
+pub fn verify_event_proof(event: &SerendipityEvent, proof: &MerkleProof, root: &str) -> bool {
+    if event.event_id != proof.event_id {
+        return false;
+    }
+
+    let leaf = event_leaf_hash(event);
+    if to_hex(&leaf) != proof.leaf_hash {
+        return false;
+    }
+
+    let mut current = leaf;
+    for step in &proof.path {
+        let sibling = match from_hex(&step.sibling) {
+            Some(sibling) => sibling,
+            None => return false,
+        };
+        current = if step.sibling_is_left {
+            node_hash(&sibling, &current)
+        } else {
+            node_hash(&current, &sibling)
+        };
+    }
+
+    to_hex(&current) == root && proof.root == root
+}
+
+/// Ed25519 signature over a trace's provenance hash
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceSignature {
+    /// Signer public key (hex)
+    pub public_key: String,
+    /// Provenance hash that was signed
+    pub signed_hash: String,
+    /// Signature bytes (hex)
+    pub signature: String,
+}
+
+impl SerendipityTrace {
+    /// Sign the trace's provenance hash with the contributor's keypair. The
+    /// hash covers each event's stage, agent, contributor, timestamp, and
+    /// confidence along with its content, so none can change after signing.
+    pub fn sign(&mut self, keypair: &SigningKey) {
+        // Rehash in full, so events edited in place are signed as they are
+        let signed_hash = self.compute_provenance_hash_with(HashVersion::CURRENT);
+        let signature = keypair.sign(signed_hash.as_bytes());
+        self.signature = Some(TraceSignature {
+            public_key: to_hex(keypair.verifying_key().as_bytes()),
+            signed_hash,
+            signature: to_hex(&signature.to_bytes()),
+        });
+    }
+
+    /// Verify the trace is signed by `pubkey` and unchanged since signing
+    pub fn verify_signature(&self, pubkey: &VerifyingKey) -> bool {
+        let stored = match &self.signature {
+            Some(stored) => stored,
+            None => return false,
+        };
+
+        if stored.public_key != to_hex(pubkey.as_bytes()) {
+            return false;
+        }
+
+        // Recompute in the signed hash's encoding so older signatures still verify
+        if !self.verify_provenance_hash(&stored.signed_hash) {
+            return false;
+        }
+        let signed_hash = &stored.signed_hash;
+
+        let signature = match hex_decode(&stored.signature)
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        {
+            Some(signature) => signature,
+            None => return false,
+        };
+
+        pubkey.verify(signed_hash.as_bytes(), &signature).is_ok()
+    }
+
+    /// Build the Merkle tree over this trace's events
+    pub fn merkle_tree(&self) -> MerkleTree {
+        MerkleTree::from_events(&self.events)
+    }
+
+    /// Merkle root over this trace's events
+    pub fn merkle_root(&self) -> String {
+        self.merkle_tree().root()
+    }
+
+    /// Generate an inclusion proof for a single event
+    pub fn prove_event(&self, event_id: &str) -> Option<MerkleProof> {
+        self.merkle_tree().prove(event_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn sample_trace(n: usize) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        for i in 0..n {
+            trace.log_event(
+                SerendipityStage::Exploration,
+                SerendipityAgent::Explorer,
+                &format!("input{}", i),
+                &format!("output{}", i),
+                "en",
+                0.8,
+                0.9,
+            ).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_prove_and_verify_every_event() {
+        for n in 1..=7 {
+            let trace = sample_trace(n);
+            let root = trace.merkle_root();
+            for event in &trace.events {
+                let proof = trace.prove_event(&event.event_id).unwrap();
+                assert!(verify_event_proof(event, &proof, &root));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_event_fails() {
+        let trace = sample_trace(5);
+        let root = trace.merkle_root();
+        let proof = trace.prove_event(&trace.events[2].event_id).unwrap();
+
+        let mut tampered = trace.events[2].clone();
+        tampered.output = "forged output".to_string();
+        assert!(!verify_event_proof(&tampered, &proof, &root));
+        assert!(!verify_event_proof(&trace.events[2], &proof, &"0".repeat(64)));
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keypair = SigningKey::from_bytes(&[7u8; 32]);
+        let impostor = SigningKey::from_bytes(&[9u8; 32]);
+
+        let mut trace = sample_trace(3);
+        assert!(!trace.verify_signature(&keypair.verifying_key()));
+
+        trace.sign(&keypair);
+        assert!(trace.verify_signature(&keypair.verifying_key()));
+        assert!(!trace.verify_signature(&impostor.verifying_key()));
+
+        trace.events[1].output = "tampered".to_string();
+        assert!(!trace.verify_signature(&keypair.verifying_key()));
+    }
+
+    #[test]
+    fn test_signature_covers_every_event_field() {
+        let keypair = SigningKey::from_bytes(&[7u8; 32]);
+        let mut trace = sample_trace(3);
+        trace.events[1].contributor = Some("researcher1".to_string());
+        trace.sign(&keypair);
+
+        type Edit = fn(&mut SerendipityEvent);
+        let edits: [(&str, Edit); 5] = [
+            ("stage", |e| e.stage = SerendipityStage::Validation),
+            ("agent", |e| e.agent = SerendipityAgent::Validator),
+            ("confidence", |e| e.confidence = 0.5),
+            ("timestamp", |e| e.timestamp += chrono::Duration::seconds(1)),
+            ("contributor", |e| e.contributor = Some("impostor".to_string())),
+        ];
+        for (field, edit) in edits {
+            let mut tampered = trace.clone();
+            edit(&mut tampered.events[1]);
+            assert!(!tampered.verify_signature(&keypair.verifying_key()), "{} change kept the signature valid", field);
+        }
+    }
+
+    #[test]
+    fn test_unknown_event() {
+        let trace = sample_trace(3);
+        assert!(trace.prove_event("missing").is_none());
+        assert_eq!(trace.merkle_root().len(), 64);
+    }
+}
@@ -0,0 +1,262 @@
+// -*- coding: utf-8 -*-
+//! Queries over Trace Corpora
+//!
+//! `TraceQuery` filters stored traces with a builder:
+//! `TraceQuery::new().language("id").min_serendipity(0.9).stage(UnexpectedConnection).contributor("dr_*")`.
+//! Contributor, discovery, and creation-time filters apply to the trace; the
+//! language, stage, agent, and serendipity filters apply to its events, and a
+//! trace matches when at least one event passes all of them. Contributor and
+//! discovery patterns may use `*` (any run of characters) and `?` (one
+//! character); languages compare by canonical tag. A match names the trace and
+//! the matching events. `run` asks the store for index candidates first and
+//! only loads those traces, falling back to a full scan.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::serendipity_trace::{SerendipityAgent, SerendipityEvent, SerendipityStage, SerendipityTrace};
+use crate::language_registry::LanguageRegistry;
+use crate::store::TraceStore;
+use crate::error::SerenQaResult;
+
+/// Whether `text` matches a pattern with `*` and `?` wildcards
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` absorb one more character
+                Some((star, tried)) => {
+                    p = star + 1;
+                    t = tried + 1;
+                    backtrack = Some((star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Filter over stored traces; an empty query matches every trace
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TraceQuery {
+    /// Contributor ID patterns, any of which may match
+    #[serde(default)]
+    pub contributors: Vec<String>,
+    /// Discovery name patterns, any of which may match
+    #[serde(default)]
+    pub discoveries: Vec<String>,
+    /// Earliest creation time (inclusive)
+    #[serde(default)]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Latest creation time (exclusive)
+    #[serde(default)]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Event languages, any of which may match
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Event stages, any of which may match
+    #[serde(default)]
+    pub stages: Vec<SerendipityStage>,
+    /// Event agents, any of which may match
+    #[serde(default)]
+    pub agents: Vec<SerendipityAgent>,
+    /// Lowest event serendipity score (inclusive)
+    #[serde(default)]
+    pub min_serendipity: Option<f64>,
+}
+
+/// Trace matching a query, with the events that matched
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QueryMatch {
+    pub trace_id: String,
+    /// Matching events in trace order (all events if the query has no event filters)
+    pub event_ids: Vec<String>,
+}
+
+impl TraceQuery {
+    /// Query matching every trace
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match contributors against a pattern such as `dr_*`
+    pub fn contributor(mut self, pattern: &str) -> Self {
+        self.contributors.push(pattern.to_string());
+        self
+    }
+
+    /// Match discovery names against a pattern
+    pub fn discovery(mut self, pattern: &str) -> Self {
+        self.discoveries.push(pattern.to_string());
+        self
+    }
+
+    /// Only traces created at or after `at`
+    pub fn created_after(mut self, at: DateTime<Utc>) -> Self {
+        self.created_after = Some(at);
+        self
+    }
+
+    /// Only traces created before `at`
+    pub fn created_before(mut self, at: DateTime<Utc>) -> Self {
+        self.created_before = Some(at);
+        self
+    }
+
+    /// Match events in a language
+    pub fn language(mut self, language: &str) -> Self {
+        self.languages.push(LanguageRegistry::global().canonical(language));
+        self
+    }
+
+    /// Match events in a stage
+    pub fn stage(mut self, stage: SerendipityStage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Match events by an agent
+    pub fn agent(mut self, agent: SerendipityAgent) -> Self {
+        self.agents.push(agent);
+        self
+    }
+
+    /// Match events scoring at least `score`
+    pub fn min_serendipity(mut self, score: f64) -> Self {
+        self.min_serendipity = Some(score);
+        self
+    }
+
+    /// Whether the query filters events, not just traces
+    pub fn has_event_filters(&self) -> bool {
+        !self.languages.is_empty() || !self.stages.is_empty() || !self.agents.is_empty() || self.min_serendipity.is_some()
+    }
+
+    /// Whether the trace-level filters accept `trace`
+    pub fn matches_trace(&self, trace: &SerendipityTrace) -> bool {
+        let any = |patterns: &[String], text: &str| patterns.is_empty() || patterns.iter().any(|p| wildcard_match(p, text));
+        any(&self.contributors, &trace.contributor_id)
+            && any(&self.discoveries, &trace.discovery_name)
+            && self.created_after.is_none_or(|after| trace.created_at >= after)
+            && self.created_before.is_none_or(|before| trace.created_at < before)
+    }
+
+    /// Whether the event-level filters accept `event`
+    pub fn matches_event(&self, event: &SerendipityEvent) -> bool {
+        let languages_match = self.languages.is_empty() || {
+            // Queries loaded from JSON may carry uncanonical tags
+            let registry = LanguageRegistry::global();
+            let language = registry.canonical(&event.language);
+            self.languages.iter().any(|l| registry.canonical(l) == language)
+        };
+        languages_match
+            && (self.stages.is_empty() || self.stages.contains(&event.stage))
+            && (self.agents.is_empty() || self.agents.contains(&event.agent))
+            && self.min_serendipity.is_none_or(|min| event.serendipity_score >= min)
+    }
+
+    /// Match of `trace`, if it satisfies the query
+    pub fn evaluate(&self, trace: &SerendipityTrace) -> Option<QueryMatch> {
+        if !self.matches_trace(trace) {
+            return None;
+        }
+        let event_ids: Vec<String> = trace
+            .events
+            .iter()
+            .filter(|event| self.matches_event(event))
+            .map(|event| event.event_id.clone())
+            .collect();
+        if self.has_event_filters() && event_ids.is_empty() {
+            return None;
+        }
+        Some(QueryMatch { trace_id: trace.trace_id.clone(), event_ids })
+    }
+
+    /// Matches among the traces in `store`, in trace ID order. Only the
+    /// store's index candidates are loaded when it has an index.
+    pub fn run(&self, store: &dyn TraceStore) -> SerenQaResult<Vec<QueryMatch>> {
+        let trace_ids = match store.query_candidates(self)? {
+            Some(mut candidates) => {
+                candidates.sort();
+                candidates.dedup();
+                candidates
+            }
+            None => store.list()?,
+        };
+        let mut matches = Vec::new();
+        for trace_id in trace_ids {
+            if let Some(found) = store.load(&trace_id)?.and_then(|trace| self.evaluate(&trace)) {
+                matches.push(found);
+            }
+        }
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryTraceStore;
+    use chrono::TimeZone;
+
+    fn trace(contributor_id: &str, trace_id: &str, day: u32, events: &[(&str, f64)]) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor_id, "backend", "Journavx");
+        trace.trace_id = trace_id.to_string();
+        trace.created_at = Utc.with_ymd_and_hms(2026, 3, day, 9, 0, 0).unwrap();
+        for (language, score) in events {
+            trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "out", language, *score, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(wildcard_match("dr_*", "dr_sari"));
+        assert!(wildcard_match("dr_*", "dr_"));
+        assert!(!wildcard_match("dr_*", "mr_budi"));
+        assert!(wildcard_match("*_lab_?", "quantum_lab_a"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("a*b*c", "axxbyy"));
+        assert!(wildcard_match("sari", "sari") && !wildcard_match("sari", "sarii"));
+    }
+
+    #[test]
+    fn test_run_against_store() {
+        let store = InMemoryTraceStore::new();
+        store.save(&trace("dr_sari", "t1", 1, &[("en", 0.4), ("id", 0.95)])).unwrap();
+        store.save(&trace("dr_budi", "t2", 2, &[("id", 0.5)])).unwrap();
+        store.save(&trace("ayu", "t3", 3, &[("id", 0.99)])).unwrap();
+
+        let query = TraceQuery::new()
+            .language("id")
+            .min_serendipity(0.9)
+            .stage(SerendipityStage::UnexpectedConnection)
+            .contributor("dr_*");
+        let matches = query.run(&store).unwrap();
+        assert_eq!(matches.len(), 1);
+        let t1 = store.load("t1").unwrap().unwrap();
+        assert_eq!(matches[0], QueryMatch { trace_id: "t1".to_string(), event_ids: vec![t1.events[1].event_id.clone()] });
+
+        // Trace filters alone return every event
+        let since = TraceQuery::new().created_after(Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap());
+        let ids: Vec<String> = since.run(&store).unwrap().into_iter().map(|m| m.trace_id).collect();
+        assert_eq!(ids, vec!["t2".to_string(), "t3".to_string()]);
+        assert_eq!(TraceQuery::new().run(&store).unwrap().len(), 3);
+        // Queries are serializable, e.g. for saved searches
+        let json = serde_json::to_string(&query).unwrap();
+        assert_eq!(serde_json::from_str::<TraceQuery>(&json).unwrap(), query);
+    }
+}
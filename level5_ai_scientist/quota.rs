@@ -0,0 +1,291 @@
+// -*- coding: utf-8 -*-
+//! Submission Quotas
+//!
+//! A hosted benchmark has to bound what one contributor can submit. A
+//! `QuotaPolicy` limits traces per day (a rolling 24-hour window), events per
+//! trace, and payload size (the trace's JSON encoding); unset limits are
+//! unbounded. A `QuotaManager` applies a default policy, with per-contributor
+//! overrides, and remembers when each contributor's accepted submissions were
+//! made. A refused submission yields a structured `QuotaExceeded` naming the
+//! limit, so the HTTP server can answer 429 or 413 and the CLI can report it.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, VecDeque};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::clock::{Clock, TraceClock};
+use crate::error::SerenQaResult;
+
+/// Length of the window `traces_per_day` counts over
+pub fn quota_window() -> Duration {
+    Duration::hours(24)
+}
+
+/// Submission limits; `None` means unlimited
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QuotaPolicy {
+    /// Accepted traces in any 24-hour window
+    #[serde(default)]
+    pub traces_per_day: Option<usize>,
+    /// Events in one trace
+    #[serde(default)]
+    pub max_events_per_trace: Option<usize>,
+    /// Bytes of the trace's JSON encoding
+    #[serde(default)]
+    pub max_payload_bytes: Option<usize>,
+}
+
+impl QuotaPolicy {
+    /// Policy without limits
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Load a policy from JSON; missing limits are unlimited
+    pub fn from_json(json: &str) -> SerenQaResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Limit accepted traces per 24 hours
+    pub fn with_traces_per_day(mut self, traces: usize) -> Self {
+        self.traces_per_day = Some(traces);
+        self
+    }
+
+    /// Limit events per trace
+    pub fn with_max_events_per_trace(mut self, events: usize) -> Self {
+        self.max_events_per_trace = Some(events);
+        self
+    }
+
+    /// Limit the trace's JSON size
+    pub fn with_max_payload_bytes(mut self, bytes: usize) -> Self {
+        self.max_payload_bytes = Some(bytes);
+        self
+    }
+}
+
+/// Limit a submission ran into
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaLimit {
+    TracesPerDay,
+    EventsPerTrace,
+    PayloadBytes,
+}
+
+impl std::fmt::Display for QuotaLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            QuotaLimit::TracesPerDay => "traces per day",
+            QuotaLimit::EventsPerTrace => "events per trace",
+            QuotaLimit::PayloadBytes => "payload bytes",
+        })
+    }
+}
+
+/// Submission refused by a quota
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuotaExceeded {
+    pub contributor_id: String,
+    pub limit: QuotaLimit,
+    /// Value the policy allows
+    pub allowed: usize,
+    /// Value of the submission (for the daily limit, traces already accepted)
+    pub actual: usize,
+    /// When the daily limit frees a slot (only for `TracesPerDay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<DateTime<Utc>>,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "contributor {} exceeded the {} quota ", self.contributor_id, self.limit)?;
+        match self.limit {
+            QuotaLimit::TracesPerDay => write!(f, "({} of {} used)", self.actual, self.allowed)?,
+            _ => write!(f, "({} > {})", self.actual, self.allowed)?,
+        }
+        if let Some(retry_after) = self.retry_after {
+            write!(f, "; retry after {}", retry_after.to_rfc3339())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// Quota policies and submission history of every contributor
+#[derive(Debug, Clone, Default)]
+pub struct QuotaManager {
+    /// Policy of contributors without an override
+    pub default_policy: QuotaPolicy,
+    overrides: HashMap<String, QuotaPolicy>,
+    /// Accepted submission times per contributor, oldest first
+    usage: HashMap<String, VecDeque<DateTime<Utc>>>,
+    clock: TraceClock,
+}
+
+impl QuotaManager {
+    /// Manager applying `policy` to every contributor
+    pub fn new(policy: QuotaPolicy) -> Self {
+        Self { default_policy: policy, ..Self::default() }
+    }
+
+    /// Apply `policy` to `contributor_id` instead of the default
+    pub fn with_override(mut self, contributor_id: &str, policy: QuotaPolicy) -> Self {
+        self.overrides.insert(contributor_id.to_string(), policy);
+        self
+    }
+
+    /// Read submission times from `clock`
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = TraceClock::new(clock);
+        self
+    }
+
+    /// Policy that applies to `contributor_id`
+    pub fn policy_for(&self, contributor_id: &str) -> &QuotaPolicy {
+        self.overrides.get(contributor_id).unwrap_or(&self.default_policy)
+    }
+
+    /// Accepted submissions of `contributor_id` in the window ending at `at`
+    fn used_at(&self, contributor_id: &str, at: DateTime<Utc>) -> impl Iterator<Item = &DateTime<Utc>> {
+        let since = at - quota_window();
+        self.usage
+            .get(contributor_id)
+            .into_iter()
+            .flatten()
+            .filter(move |time| since < **time && **time <= at)
+    }
+
+    /// Submissions `contributor_id` may still make now (`None` if unlimited)
+    pub fn remaining_today(&self, contributor_id: &str) -> Option<usize> {
+        let limit = self.policy_for(contributor_id).traces_per_day?;
+        Some(limit.saturating_sub(self.used_at(contributor_id, self.clock.now()).count()))
+    }
+
+    /// Check a submission made now against its contributor's quota
+    pub fn check(&self, trace: &SerendipityTrace) -> Result<(), QuotaExceeded> {
+        self.check_at(trace, self.clock.now())
+    }
+
+    /// Check a submission made at `at` against its contributor's quota
+    pub fn check_at(&self, trace: &SerendipityTrace, at: DateTime<Utc>) -> Result<(), QuotaExceeded> {
+        let contributor_id = &trace.contributor_id;
+        let policy = self.policy_for(contributor_id);
+        let exceeded = |limit, allowed, actual, retry_after| QuotaExceeded {
+            contributor_id: contributor_id.clone(),
+            limit,
+            allowed,
+            actual,
+            retry_after,
+        };
+
+        if let Some(max) = policy.max_events_per_trace {
+            if trace.events.len() > max {
+                return Err(exceeded(QuotaLimit::EventsPerTrace, max, trace.events.len(), None));
+            }
+        }
+        if let Some(max) = policy.max_payload_bytes {
+            // Serializing a trace cannot fail; an unencodable one counts as oversized
+            let bytes = serde_json::to_vec(trace).map_or(usize::MAX, |json| json.len());
+            if bytes > max {
+                return Err(exceeded(QuotaLimit::PayloadBytes, max, bytes, None));
+            }
+        }
+        if let Some(max) = policy.traces_per_day {
+            let used: Vec<&DateTime<Utc>> = self.used_at(contributor_id, at).collect();
+            if used.len() >= max {
+                // The slot frees when the oldest submission still counted leaves the window
+                let retry_after = used.first().map(|oldest| **oldest + quota_window()).unwrap_or(at);
+                return Err(exceeded(QuotaLimit::TracesPerDay, max, used.len(), Some(retry_after)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Count an accepted submission of `contributor_id` made now
+    pub fn record(&mut self, contributor_id: &str) {
+        self.record_at(contributor_id, self.clock.now());
+    }
+
+    /// Count an accepted submission of `contributor_id` made at `at`,
+    /// forgetting submissions older than the window
+    pub fn record_at(&mut self, contributor_id: &str, at: DateTime<Utc>) {
+        let times = self.usage.entry(contributor_id.to_string()).or_default();
+        let position = times.partition_point(|time| *time <= at);
+        times.insert(position, at);
+        let since = at - quota_window();
+        while times.front().is_some_and(|oldest| *oldest <= since) {
+            times.pop_front();
+        }
+    }
+
+    /// Check a submission made now and count it if it is within quota
+    pub fn admit(&mut self, trace: &SerendipityTrace) -> Result<(), QuotaExceeded> {
+        let at = self.clock.now();
+        self.check_at(trace, at)?;
+        self.record_at(&trace.contributor_id, at);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+    use chrono::TimeZone;
+
+    fn trace(contributor_id: &str, events: usize) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor_id, "backend", "Journavx");
+        for _ in 0..events {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_daily_quota_uses_a_rolling_window() {
+        let start = Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+        let mut quota = QuotaManager::new(QuotaPolicy::unlimited().with_traces_per_day(2))
+            .with_override("maintainer", QuotaPolicy::unlimited())
+            .with_clock(clock.clone());
+
+        quota.admit(&trace("sari", 1)).unwrap();
+        clock.advance(Duration::hours(6));
+        quota.admit(&trace("sari", 1)).unwrap();
+        assert_eq!(quota.remaining_today("sari"), Some(0));
+        let refused = quota.admit(&trace("sari", 1)).unwrap_err();
+        assert_eq!((refused.limit, refused.allowed, refused.actual), (QuotaLimit::TracesPerDay, 2, 2));
+        assert_eq!(refused.retry_after, Some(start + Duration::hours(24)));
+
+        // Other contributors and overrides are unaffected
+        quota.admit(&trace("budi", 1)).unwrap();
+        assert_eq!(quota.remaining_today("maintainer"), None);
+
+        clock.advance(Duration::hours(18) + Duration::seconds(1));
+        assert_eq!(quota.remaining_today("sari"), Some(1));
+        quota.admit(&trace("sari", 1)).unwrap();
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let policy = QuotaPolicy::from_json(r#"{"max_events_per_trace": 3, "max_payload_bytes": 100000}"#).unwrap();
+        assert_eq!(policy.traces_per_day, None);
+        let quota = QuotaManager::new(policy);
+        quota.check(&trace("sari", 3)).unwrap();
+        let refused = quota.check(&trace("sari", 4)).unwrap_err();
+        assert_eq!((refused.limit, refused.actual), (QuotaLimit::EventsPerTrace, 4));
+        assert!(refused.to_string().contains("events per trace quota (4 > 3)"));
+
+        let tiny = QuotaManager::new(QuotaPolicy::unlimited().with_max_payload_bytes(64));
+        let refused = tiny.check(&trace("sari", 1)).unwrap_err();
+        assert_eq!(refused.limit, QuotaLimit::PayloadBytes);
+        assert!(refused.actual > 64 && refused.retry_after.is_none());
+        // Round-trips as the structured body the server returns
+        let json = serde_json::to_string(&refused).unwrap();
+        assert_eq!(serde_json::from_str::<QuotaExceeded>(&json).unwrap(), refused);
+    }
+}
@@ -0,0 +1,222 @@
+// -*- coding: utf-8 -*-
+//! Event Redaction with Hash-Preserving Tombstones
+//!
+//! Redacts a specific event's text (legal/IP reasons) by replacing it with a
+//! salted hash tombstone. The original content hash is kept in the tombstone,
+//! so the provenance hash and Merkle proofs remain verifiable, and the holder
+//! of the salt can later prove what the redacted text was.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use chrono::{DateTime, Utc};
+use crate::serendipity_trace::{SerendipityEvent, SerendipityTrace};
+
+/// Request to redact an event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRequest {
+    /// Event to redact
+    pub event_id: String,
+    /// Who requested the redaction
+    pub requested_by: String,
+    /// Reason for redaction (e.g., "legal", "ip")
+    pub reason: String,
+}
+
+/// Tombstone left in place of redacted event text
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RedactionTombstone {
+    /// Who requested the redaction
+    pub requested_by: String,
+    /// Reason for redaction
+    pub reason: String,
+    /// Timestamp of redaction
+    pub redacted_at: DateTime<Utc>,
+    /// Unsalted hash of the original content, used by provenance
+    pub content_hash: String,
+    /// Salted hash of the original input
+    pub salted_input_hash: String,
+    /// Salted hash of the original output
+    pub salted_output_hash: String,
+}
+
+/// Redaction failure
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedactionError {
+    /// No event with this ID in the trace
+    UnknownEvent(String),
+    /// Event has already been redacted
+    AlreadyRedacted(String),
+}
+
+impl std::fmt::Display for RedactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedactionError::UnknownEvent(id) => write!(f, "unknown event: {}", id),
+            RedactionError::AlreadyRedacted(id) => write!(f, "event already redacted: {}", id),
+        }
+    }
+}
+
+impl std::error::Error for RedactionError {}
+
+/// Salted hash of a piece of text
+pub fn salted_hash(salt: &[u8], text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update((salt.len() as u64).to_le_bytes());
+    hasher.update(salt);
+    hasher.update(text.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Placeholder text written in place of redacted content
+fn tombstone_text(salted: &str) -> String {
+    format!("[REDACTED sha256:{}]", salted)
+}
+
+impl SerendipityTrace {
+    /// Redact an event's input/output, keeping its provenance verifiable.
+    /// The salt is not stored; keep it to later prove the original content.
+    pub fn redact_event(
+        &mut self,
+        request: &RedactionRequest,
+        salt: &[u8],
+    ) -> Result<&RedactionTombstone, RedactionError> {
+        let index = self.events
+            .iter()
+            .position(|e| e.event_id == request.event_id)
+            .ok_or_else(|| RedactionError::UnknownEvent(request.event_id.clone()))?;
+
+        if self.events[index].is_redacted() {
+            return Err(RedactionError::AlreadyRedacted(request.event_id.clone()));
+        }
+        self.invalidate_provenance();
+        self.invalidate_fold_cache();
+        let event = &mut self.events[index];
+
+        let tombstone = RedactionTombstone {
+            requested_by: request.requested_by.clone(),
+            reason: request.reason.clone(),
+            redacted_at: Utc::now(),
+            content_hash: event.content_hash(),
+            salted_input_hash: salted_hash(salt, &event.input),
+            salted_output_hash: salted_hash(salt, &event.output),
+        };
+
+        event.input = tombstone_text(&tombstone.salted_input_hash);
+        event.output = tombstone_text(&tombstone.salted_output_hash);
+        Ok(event.redaction.insert(tombstone))
+    }
+
+    /// Number of redacted events
+    pub fn redacted_event_count(&self) -> usize {
+        self.events.iter().filter(|e| e.is_redacted()).count()
+    }
+
+    /// Check if any event has been redacted
+    pub fn is_partially_redacted(&self) -> bool {
+        self.events.iter().any(|e| e.is_redacted())
+    }
+}
+
+/// Verify that `input`/`output` is the original text of a redacted event
+pub fn verify_redacted_content(
+    event: &SerendipityEvent,
+    input: &str,
+    output: &str,
+    salt: &[u8],
+) -> bool {
+    match &event.redaction {
+        Some(tombstone) => {
+            tombstone.salted_input_hash == salted_hash(salt, input)
+                && tombstone.salted_output_hash == salted_hash(salt, output)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.log_event(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "public input",
+            "public output",
+            "en",
+            0.8,
+            0.9,
+        ).unwrap();
+        trace.log_event(
+            SerendipityStage::Validation,
+            SerendipityAgent::Validator,
+            "proprietary prompt",
+            "proprietary result",
+            "en",
+            0.9,
+            0.9,
+        ).unwrap();
+        trace
+    }
+
+    fn request(event_id: &str) -> RedactionRequest {
+        RedactionRequest {
+            event_id: event_id.to_string(),
+            requested_by: "legal".to_string(),
+            reason: "ip".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_redaction_preserves_provenance() {
+        let mut trace = sample_trace();
+        let hash_before = trace.compute_provenance_hash();
+        let root_before = trace.merkle_root();
+
+        let event_id = trace.events[1].event_id.clone();
+        trace.redact_event(&request(&event_id), b"secret-salt").unwrap();
+
+        assert!(!trace.events[1].output.contains("proprietary"));
+        assert_eq!(trace.compute_provenance_hash(), hash_before);
+        assert_eq!(trace.merkle_root(), root_before);
+        assert!(verify_redacted_content(
+            &trace.events[1],
+            "proprietary prompt",
+            "proprietary result",
+            b"secret-salt",
+        ));
+        assert!(!verify_redacted_content(&trace.events[1], "guess", "guess", b"secret-salt"));
+    }
+
+    #[test]
+    fn test_redaction_flagged_in_fold() {
+        let mut trace = sample_trace();
+        assert!(!trace.fold_memory().unwrap().partially_redacted);
+
+        let event_id = trace.events[1].event_id.clone();
+        trace.redact_event(&request(&event_id), b"salt").unwrap();
+
+        let folded = trace.fold_memory().unwrap();
+        assert!(folded.partially_redacted);
+        assert_eq!(folded.redacted_events, 1);
+    }
+
+    #[test]
+    fn test_redaction_errors() {
+        let mut trace = sample_trace();
+        assert_eq!(
+            trace.redact_event(&request("missing"), b"salt").unwrap_err(),
+            RedactionError::UnknownEvent("missing".to_string())
+        );
+
+        let event_id = trace.events[0].event_id.clone();
+        trace.redact_event(&request(&event_id), b"salt").unwrap();
+        assert!(matches!(
+            trace.redact_event(&request(&event_id), b"salt"),
+            Err(RedactionError::AlreadyRedacted(_))
+        ));
+    }
+}
@@ -0,0 +1,385 @@
+// -*- coding: utf-8 -*-
+//! Rule-Based Content Redaction
+//!
+//! `redact_event` removes one event's text on request. A `RedactionRules`
+//! engine instead strips matching spans (secrets, personal data, proprietary
+//! prompts) from every event's input and output, by keyword lists (whole
+//! words, ASCII case-insensitive) or, with the `regex` feature, regular
+//! expressions. Each match becomes the rule's replacement, `[REDACTED:<rule>]`
+//! by default.
+//!
+//! The two ways to apply the rules differ in what the provenance hash covers,
+//! so each records what it did:
+//!
+//! - At log time (`set_redaction_rules`), the original text never enters the
+//!   trace; the hash covers the redacted text, and a `Redacted` annotation
+//!   names the rules that fired.
+//! - At export time (`redact_with_rules`), each affected event also gets a
+//!   tombstone holding the original content hash, so the trace keeps its
+//!   provenance hash, as with `redact_event`.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::{SerendipityEvent, SerendipityTrace};
+use crate::redaction::{salted_hash, RedactionTombstone};
+use crate::annotation::{AnnotationKind, TraceAnnotation};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// `requested_by` of tombstones left by `redact_with_rules`
+pub const RULE_REDACTION_REQUESTER: &str = "redaction-rules";
+
+/// Event text a rule applies to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionField {
+    Input,
+    Output,
+}
+
+/// How a rule finds the spans to redact
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMatcher {
+    /// Any of the keywords as a whole word, ignoring ASCII case
+    Keywords(Vec<String>),
+    /// Regular expression (requires the `regex` feature)
+    #[cfg(feature = "regex")]
+    Regex(String),
+}
+
+/// Named rule redacting matches in some fields
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RedactionRule {
+    pub name: String,
+    pub fields: Vec<RedactionField>,
+    pub matcher: RuleMatcher,
+    /// Text written in place of each match (`[REDACTED:<name>]` when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+}
+
+impl RedactionRule {
+    /// Rule redacting `keywords` in `fields`
+    pub fn keywords(name: &str, fields: &[RedactionField], keywords: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            fields: fields.to_vec(),
+            matcher: RuleMatcher::Keywords(keywords.iter().map(|k| k.to_string()).collect()),
+            replacement: None,
+        }
+    }
+
+    /// Rule redacting matches of `pattern` in `fields`
+    #[cfg(feature = "regex")]
+    pub fn regex(name: &str, fields: &[RedactionField], pattern: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            fields: fields.to_vec(),
+            matcher: RuleMatcher::Regex(pattern.to_string()),
+            replacement: None,
+        }
+    }
+
+    /// Write `replacement` in place of each match
+    pub fn replace_with(mut self, replacement: &str) -> Self {
+        self.replacement = Some(replacement.to_string());
+        self
+    }
+
+    /// Text written in place of each match
+    pub fn replacement_text(&self) -> String {
+        self.replacement.clone().unwrap_or_else(|| format!("[REDACTED:{}]", self.name))
+    }
+}
+
+/// Rule with its matcher prepared
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    rule: RedactionRule,
+    replacement: String,
+    #[cfg(feature = "regex")]
+    regex: Option<regex::Regex>,
+}
+
+/// Whether `c` continues a word
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Byte ranges of whole-word, ASCII case-insensitive matches of `keyword`
+fn keyword_matches(text: &str, keyword: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    while start + keyword.len() <= text.len() {
+        let end = start + keyword.len();
+        let found = text.is_char_boundary(start)
+            && text.is_char_boundary(end)
+            && text.as_bytes()[start..end].eq_ignore_ascii_case(keyword.as_bytes())
+            && !text[..start].chars().next_back().is_some_and(is_word_char)
+            && !text[end..].chars().next().is_some_and(is_word_char);
+        if found {
+            spans.push((start, end));
+            start = end;
+        } else {
+            start += 1;
+        }
+    }
+    spans
+}
+
+/// Non-overlapping matches of any keyword, preferring the longest at each position
+fn keyword_spans(text: &str, keywords: &[String]) -> Vec<(usize, usize)> {
+    let mut spans: Vec<(usize, usize)> = keywords.iter().flat_map(|keyword| keyword_matches(text, keyword)).collect();
+    spans.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    let mut kept: Vec<(usize, usize)> = Vec::new();
+    for span in spans {
+        if kept.last().is_none_or(|last| last.1 <= span.0) {
+            kept.push(span);
+        }
+    }
+    kept
+}
+
+impl CompiledRule {
+    fn new(rule: RedactionRule) -> SerenQaResult<Self> {
+        let invalid = |reason: &str| SerenQaError::InvalidRedactionRule(format!("{}: {}", rule.name, reason));
+        if rule.name.trim().is_empty() {
+            return Err(SerenQaError::InvalidRedactionRule("rule without a name".to_string()));
+        }
+        if rule.fields.is_empty() {
+            return Err(invalid("no fields"));
+        }
+        match &rule.matcher {
+            RuleMatcher::Keywords(keywords) if keywords.is_empty() || keywords.iter().any(|k| k.trim().is_empty()) => {
+                return Err(invalid("empty keyword list or blank keyword"));
+            }
+            _ => {}
+        }
+        #[cfg(feature = "regex")]
+        let regex = match &rule.matcher {
+            RuleMatcher::Regex(pattern) => Some(regex::Regex::new(pattern).map_err(|e| invalid(&e.to_string()))?),
+            RuleMatcher::Keywords(_) => None,
+        };
+        Ok(Self {
+            replacement: rule.replacement_text(),
+            rule,
+            #[cfg(feature = "regex")]
+            regex,
+        })
+    }
+
+    /// Non-overlapping byte ranges to replace, in order
+    fn spans(&self, text: &str) -> Vec<(usize, usize)> {
+        match &self.rule.matcher {
+            RuleMatcher::Keywords(keywords) => keyword_spans(text, keywords),
+            #[cfg(feature = "regex")]
+            RuleMatcher::Regex(_) => self.regex
+                .iter()
+                .flat_map(|regex| regex.find_iter(text))
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+        }
+    }
+
+    /// Replace this rule's matches; returns the match count
+    fn apply(&self, text: &mut String) -> usize {
+        let spans = self.spans(text);
+        for &(start, end) in spans.iter().rev() {
+            text.replace_range(start..end, &self.replacement);
+        }
+        spans.len()
+    }
+}
+
+/// What the rules redacted in one event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RuleRedaction {
+    /// Rules that matched, in rule order
+    pub rules: Vec<String>,
+    /// Fields that changed
+    pub fields: Vec<RedactionField>,
+    /// Spans replaced
+    pub matches: usize,
+}
+
+/// Ordered redaction rules, loadable from a JSON list
+#[derive(Debug, Clone)]
+pub struct RedactionRules {
+    compiled: Vec<CompiledRule>,
+}
+
+impl RedactionRules {
+    /// Rules applied in order; fails on an unnamed rule, a rule without
+    /// fields or keywords, a repeated name, or an invalid regex
+    pub fn new(rules: Vec<RedactionRule>) -> SerenQaResult<Self> {
+        let mut compiled: Vec<CompiledRule> = Vec::with_capacity(rules.len());
+        for rule in rules {
+            if compiled.iter().any(|c| c.rule.name == rule.name) {
+                return Err(SerenQaError::InvalidRedactionRule(format!("{}: listed twice", rule.name)));
+            }
+            compiled.push(CompiledRule::new(rule)?);
+        }
+        Ok(Self { compiled })
+    }
+
+    /// Load rules from a JSON list
+    pub fn from_json(json: &str) -> SerenQaResult<Self> {
+        Self::new(serde_json::from_str(json)?)
+    }
+
+    /// The rules, in order
+    pub fn rules(&self) -> impl Iterator<Item = &RedactionRule> {
+        self.compiled.iter().map(|c| &c.rule)
+    }
+
+    /// Redact `text` as the field `field`; returns the rules that matched and the match count
+    pub fn redact_text(&self, field: RedactionField, text: &mut String) -> (Vec<String>, usize) {
+        let mut fired = Vec::new();
+        let mut matches = 0;
+        for compiled in self.compiled.iter().filter(|c| c.rule.fields.contains(&field)) {
+            let count = compiled.apply(text);
+            if count > 0 {
+                fired.push(compiled.rule.name.clone());
+                matches += count;
+            }
+        }
+        (fired, matches)
+    }
+
+    /// Redact an event's input and output, if anything matches
+    pub fn apply(&self, event: &mut SerendipityEvent) -> Option<RuleRedaction> {
+        let mut redaction = RuleRedaction { rules: Vec::new(), fields: Vec::new(), matches: 0 };
+        for (field, text) in [(RedactionField::Input, &mut event.input), (RedactionField::Output, &mut event.output)] {
+            let (fired, matches) = self.redact_text(field, text);
+            if matches > 0 {
+                redaction.fields.push(field);
+                redaction.matches += matches;
+                for rule in fired {
+                    if !redaction.rules.contains(&rule) {
+                        redaction.rules.push(rule);
+                    }
+                }
+            }
+        }
+        // Keep the rule order of the engine, whichever field matched first
+        let order = |name: &String| self.compiled.iter().position(|c| &c.rule.name == name);
+        redaction.rules.sort_by_key(order);
+        (redaction.matches > 0).then_some(redaction)
+    }
+}
+
+impl SerendipityTrace {
+    /// Redact events logged from now on, before they are hashed
+    pub fn set_redaction_rules(&mut self, rules: RedactionRules) {
+        self.redaction_rules = Some(rules);
+    }
+
+    /// Rule redactions recorded on the trace, with the event IDs
+    pub fn rule_redactions(&self) -> impl Iterator<Item = (&str, &RuleRedaction)> {
+        self.annotations.iter().filter_map(|note| match &note.kind {
+            AnnotationKind::Redacted(redaction) => Some((note.event_id.as_str(), redaction)),
+            _ => None,
+        })
+    }
+
+    /// Redact already-logged events (and those of sub-traces) with `rules`,
+    /// e.g. on a copy for export. Affected events get a tombstone with the
+    /// original content hash and salted hashes, so the provenance hash is
+    /// unchanged. Events redacted before are skipped. Returns the number of
+    /// redacted events.
+    pub fn redact_with_rules(&mut self, rules: &RedactionRules, salt: &[u8]) -> usize {
+        let mut redacted = 0;
+        for index in 0..self.events.len() {
+            if self.events[index].is_redacted() {
+                continue;
+            }
+            let mut event = self.events[index].clone();
+            let Some(redaction) = rules.apply(&mut event) else { continue };
+            let original = &self.events[index];
+            event.redaction = Some(RedactionTombstone {
+                requested_by: RULE_REDACTION_REQUESTER.to_string(),
+                reason: redaction.rules.join(", "),
+                redacted_at: self.clock.now(),
+                content_hash: original.content_hash(),
+                salted_input_hash: salted_hash(salt, &original.input),
+                salted_output_hash: salted_hash(salt, &original.output),
+            });
+            self.annotations.push(TraceAnnotation {
+                event_id: event.event_id.clone(),
+                kind: AnnotationKind::Redacted(redaction),
+            });
+            self.events[index] = event;
+            redacted += 1;
+        }
+        for subtrace in &mut self.subtraces {
+            redacted += subtrace.trace.redact_with_rules(rules, salt);
+        }
+        if redacted > 0 {
+            self.invalidate_fold_cache();
+        }
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redaction::verify_redacted_content;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+
+    fn rules() -> RedactionRules {
+        RedactionRules::from_json(r#"[
+            {"name": "codename", "fields": ["input", "output"], "matcher": {"keywords": ["Project Falcon", "falcon"]}},
+            {"name": "people", "fields": ["output"], "matcher": {"keywords": ["Dr. Sari"]}, "replacement": "[PERSON]"}
+        ]"#).unwrap()
+    }
+
+    fn log(trace: &mut SerendipityTrace, input: &str, output: &str) {
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, input, output, "en", 0.6, 0.8).unwrap();
+    }
+
+    #[test]
+    fn test_log_time_rules_strip_text_before_hashing() {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.set_redaction_rules(rules());
+        log(&mut trace, "brief for PROJECT FALCON", "Dr. Sari saw falconry and falcon routes");
+        log(&mut trace, "public input", "public output");
+
+        let event = &trace.events[0];
+        assert_eq!(event.input, "brief for [REDACTED:codename]");
+        // "falconry" is not the whole word
+        assert_eq!(event.output, "[PERSON] saw falconry and [REDACTED:codename] routes");
+        // The hash covers the redacted text; nothing was tombstoned
+        assert!(!event.is_redacted());
+        assert!(trace.verify_provenance_hash(&trace.compute_provenance_hash()));
+
+        let recorded: Vec<(&str, &RuleRedaction)> = trace.rule_redactions().collect();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].0, trace.events[0].event_id);
+        assert_eq!(recorded[0].1.rules, vec!["codename", "people"]);
+        assert_eq!((recorded[0].1.fields.len(), recorded[0].1.matches), (2, 3));
+
+        let invalid = RedactionRules::new(vec![RedactionRule::keywords("empty", &[RedactionField::Input], &[])]);
+        assert!(matches!(invalid, Err(SerenQaError::InvalidRedactionRule(_))));
+    }
+
+    #[test]
+    fn test_export_time_rules_keep_the_provenance_hash() {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        log(&mut trace, "brief for Project Falcon", "routes match");
+        log(&mut trace, "public input", "public output");
+        let hash = trace.compute_provenance_hash();
+
+        let mut export = trace.clone();
+        assert_eq!(export.redact_with_rules(&rules(), b"salt"), 1);
+        assert_eq!(export.events[0].input, "brief for [REDACTED:codename]");
+        assert_eq!(export.compute_provenance_hash(), hash);
+        assert_eq!(export.events[0].redaction.as_ref().unwrap().requested_by, RULE_REDACTION_REQUESTER);
+        assert!(verify_redacted_content(&export.events[0], "brief for Project Falcon", "routes match", b"salt"));
+        assert!(export.fold_memory().unwrap().partially_redacted);
+
+        // Running the rules again finds nothing new
+        assert_eq!(export.redact_with_rules(&rules(), b"salt"), 0);
+        assert_eq!(export.rule_redactions().count(), 1);
+    }
+}
@@ -0,0 +1,247 @@
+// -*- coding: utf-8 -*-
+//! Leaderboard Renderers
+//!
+//! `LanguageAwareLeaderboard::display` used to print straight to stdout. The
+//! top of the ranking is now collected into a `LeaderboardView` and written by
+//! a `Renderer` to any `io::Write`: `UnicodeBox` (the pretty CLI output, used
+//! by `display`), `PlainText`, `Json`, and `MarkdownTable`. Web apps embed
+//! whichever format suits them, and tests can assert on the output.
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use crate::ContributorStats::{LanguageAwareLeaderboard, LanguageAwareRankingCriteria, LeaderboardQuery};
+use crate::markdown_export::escape_cell;
+
+/// Contributors shown by `render` and `display`
+pub const RENDERED_CONTRIBUTORS: usize = 10;
+
+/// Contributor row of a rendered leaderboard
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LeaderboardRow {
+    /// 1-based rank
+    pub rank: usize,
+    pub contributor_id: String,
+    /// Score under the ranking criteria
+    pub score: f64,
+    pub total_traces: usize,
+    pub languages: Vec<String>,
+    pub avg_serendipity: f64,
+    pub cross_language_expertise: f64,
+    pub discoveries: usize,
+    /// Badge names, in award order
+    pub badges: Vec<String>,
+    /// Trophy labels, in award order
+    pub trophies: Vec<String>,
+}
+
+/// Top of a leaderboard, ready to render
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LeaderboardView {
+    pub criteria: LanguageAwareRankingCriteria,
+    pub rows: Vec<LeaderboardRow>,
+}
+
+/// Output format for leaderboard views
+pub trait Renderer {
+    /// Write `view` to `out`
+    fn render(&self, view: &LeaderboardView, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Boxed title with medals for the top three (the CLI format)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeBox;
+
+/// One line per contributor, ASCII only
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainText;
+
+/// The view as pretty-printed JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+/// GitHub-flavored Markdown table
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownTable;
+
+impl Renderer for UnicodeBox {
+    fn render(&self, view: &LeaderboardView, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "\n╔════════════════════════════════════════════════════════════════╗")?;
+        writeln!(out, "║     Language-Aware Serendipity Discovery Leaderboard          ║")?;
+        writeln!(out, "║     Ranking by: {:?}                                    ║", view.criteria)?;
+        writeln!(out, "╚════════════════════════════════════════════════════════════════╝\n")?;
+
+        for row in &view.rows {
+            let medal = match row.rank {
+                1 => "🥇",
+                2 => "🥈",
+                3 => "🥉",
+                _ => "  ",
+            };
+
+            writeln!(out, "{} #{} {}", medal, row.rank, row.contributor_id)?;
+            writeln!(out, "   Score: {:.3} | Traces: {} | Languages: {}",
+                row.score,
+                row.total_traces,
+                row.languages.join(", "))?;
+            writeln!(out, "   Serendipity: {:.3} | Cross-Lang: {:.3} | Discoveries: {}",
+                row.avg_serendipity,
+                row.cross_language_expertise,
+                row.discoveries)?;
+            if !row.badges.is_empty() {
+                writeln!(out, "   Badges: {}", row.badges.join(", "))?;
+            }
+            if !row.trophies.is_empty() {
+                writeln!(out, "   Trophies: {}", row.trophies.join(", "))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for PlainText {
+    fn render(&self, view: &LeaderboardView, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "Leaderboard by {:?}", view.criteria)?;
+        for row in &view.rows {
+            write!(
+                out,
+                "{:>3}. {} score={:.3} traces={} languages={} serendipity={:.3} discoveries={}",
+                row.rank,
+                row.contributor_id,
+                row.score,
+                row.total_traces,
+                row.languages.join(","),
+                row.avg_serendipity,
+                row.discoveries,
+            )?;
+            if !row.badges.is_empty() {
+                write!(out, " badges={}", row.badges.join(","))?;
+            }
+            if !row.trophies.is_empty() {
+                write!(out, " trophies={}", row.trophies.join(","))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Json {
+    fn render(&self, view: &LeaderboardView, out: &mut dyn Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, view)?;
+        writeln!(out)
+    }
+}
+
+impl Renderer for MarkdownTable {
+    fn render(&self, view: &LeaderboardView, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "| Rank | Contributor | Score ({:?}) | Traces | Languages | Serendipity | Discoveries | Awards |", view.criteria)?;
+        writeln!(out, "| ---: | --- | ---: | ---: | --- | ---: | ---: | --- |")?;
+        for row in &view.rows {
+            let awards: Vec<&str> = row.badges.iter().chain(&row.trophies).map(String::as_str).collect();
+            writeln!(
+                out,
+                "| {} | {} | {:.3} | {} | {} | {:.3} | {} | {} |",
+                row.rank,
+                escape_cell(&row.contributor_id),
+                row.score,
+                row.total_traces,
+                escape_cell(&row.languages.join(", ")),
+                row.avg_serendipity,
+                row.discoveries,
+                escape_cell(&awards.join(", ")),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl LanguageAwareLeaderboard {
+    /// Top contributors by `criteria`, ready to render
+    pub fn view(&self, criteria: LanguageAwareRankingCriteria) -> LeaderboardView {
+        let page = self.query(&LeaderboardQuery::new(criteria).page(0, RENDERED_CONTRIBUTORS));
+        let rows = page
+            .entries
+            .into_iter()
+            .filter_map(|entry| {
+                let stats = self.get(&entry.contributor_id)?;
+                Some(LeaderboardRow {
+                    rank: entry.rank,
+                    score: entry.score,
+                    total_traces: stats.total_traces,
+                    languages: stats.languages_used.clone(),
+                    avg_serendipity: stats.avg_serendipity,
+                    cross_language_expertise: stats.cross_language_expertise,
+                    discoveries: stats.discoveries.len(),
+                    badges: stats.badge_names(),
+                    trophies: stats.trophy_labels(),
+                    contributor_id: entry.contributor_id,
+                })
+            })
+            .collect();
+        LeaderboardView { criteria, rows }
+    }
+
+    /// Write the top contributors by `criteria` to `out` in the CLI format
+    pub fn render<W: Write>(&self, criteria: LanguageAwareRankingCriteria, out: &mut W) -> io::Result<()> {
+        self.render_with(&UnicodeBox, criteria, out)
+    }
+
+    /// Write the top contributors by `criteria` to `out` with `renderer`
+    pub fn render_with<W: Write>(
+        &self,
+        renderer: &dyn Renderer,
+        criteria: LanguageAwareRankingCriteria,
+        out: &mut W,
+    ) -> io::Result<()> {
+        renderer.render(&self.view(criteria), out)
+    }
+
+    /// Rendered output as a string
+    pub fn render_to_string(&self, renderer: &dyn Renderer, criteria: LanguageAwareRankingCriteria) -> String {
+        let mut out = Vec::new();
+        self.render_with(renderer, criteria, &mut out).expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("renderers write UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContributorStats::LanguageAwareContributorStats;
+
+    fn leaderboard() -> LanguageAwareLeaderboard {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for (id, serendipity) in [("ayu", 0.9), ("budi|b", 0.4)] {
+            let mut stats = LanguageAwareContributorStats::new(id);
+            stats.add_trace(8, 0.7, serendipity, vec!["en".to_string(), "id".to_string()], 0.8, 0.8).unwrap();
+            leaderboard.add_contributor(stats);
+        }
+        leaderboard
+    }
+
+    #[test]
+    fn test_text_renderers() {
+        let leaderboard = leaderboard();
+        let boxed = leaderboard.render_to_string(&UnicodeBox, LanguageAwareRankingCriteria::Serendipity);
+        assert!(boxed.contains("Ranking by: Serendipity"));
+        assert!(boxed.contains("🥇 #1 ayu\n   Score: 0.900 | Traces: 1 | Languages: en, id\n"));
+
+        let plain = leaderboard.render_to_string(&PlainText, LanguageAwareRankingCriteria::Serendipity);
+        assert_eq!(plain.lines().nth(2).unwrap(), "  2. budi|b score=0.400 traces=1 languages=en,id serendipity=0.400 discoveries=0");
+
+        let markdown = leaderboard.render_to_string(&MarkdownTable, LanguageAwareRankingCriteria::Serendipity);
+        assert_eq!(markdown.lines().count(), 4);
+        assert!(markdown.contains("| 2 | budi\\|b | 0.400 |"));
+    }
+
+    #[test]
+    fn test_json_renderer_round_trips_the_view() {
+        let leaderboard = leaderboard();
+        let mut out = Vec::new();
+        leaderboard.render_with(&Json, LanguageAwareRankingCriteria::Overall, &mut out).unwrap();
+        let view: LeaderboardView = serde_json::from_slice(&out).unwrap();
+        assert_eq!(view, leaderboard.view(LanguageAwareRankingCriteria::Overall));
+        assert_eq!(view.rows[0].contributor_id, "ayu");
+    }
+}
@@ -0,0 +1,294 @@
+// -*- coding: utf-8 -*-
+//! Replay Engine for Reproducibility Checks
+//!
+//! The provenance hash proves a trace has not been altered, but not that it
+//! can be reproduced. `TraceReplayer` re-executes a trace step by step against
+//! pluggable agent callbacks, compares the recomputed serendipity and
+//! confidence scores with the recorded ones, and reports any divergence.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::serendipity_trace::{SerendipityAgent, SerendipityEvent, SerendipityTrace};
+
+/// Default absolute tolerance for score comparisons
+pub const DEFAULT_REPLAY_TOLERANCE: f64 = 1e-6;
+
+/// Input handed to an agent callback for one step
+pub struct ReplayContext<'a> {
+    /// Recorded event being replayed
+    pub event: &'a SerendipityEvent,
+    /// Recorded events preceding this one
+    pub history: &'a [SerendipityEvent],
+    /// Outputs produced by the replay so far
+    pub replayed_outputs: &'a [String],
+}
+
+/// Result produced by an agent callback
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOutput {
+    /// Output text
+    pub output: String,
+    /// Recomputed serendipity score
+    pub serendipity_score: f64,
+    /// Recomputed confidence
+    pub confidence: f64,
+}
+
+/// Pluggable agent used to re-execute trace steps
+pub trait ReplayAgent {
+    /// Re-execute a single step
+    fn replay(&mut self, context: &ReplayContext<'_>) -> ReplayOutput;
+}
+
+impl<F> ReplayAgent for F
+where
+    F: FnMut(&ReplayContext<'_>) -> ReplayOutput,
+{
+    fn replay(&mut self, context: &ReplayContext<'_>) -> ReplayOutput {
+        self(context)
+    }
+}
+
+/// Comparison of one replayed step with its recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayStep {
+    /// Event ID
+    pub event_id: String,
+    /// Agent that handled the step
+    pub agent: SerendipityAgent,
+    /// Whether no callback was registered for the agent
+    pub skipped: bool,
+    /// Recorded serendipity score
+    pub recorded_serendipity: f64,
+    /// Replayed serendipity score
+    pub replayed_serendipity: Option<f64>,
+    /// Recorded confidence
+    pub recorded_confidence: f64,
+    /// Replayed confidence
+    pub replayed_confidence: Option<f64>,
+    /// Whether the replayed output text matches the recording
+    pub output_matches: bool,
+    /// Whether any score diverged beyond tolerance
+    pub diverged: bool,
+}
+
+impl ReplayStep {
+    /// Absolute serendipity divergence
+    pub fn serendipity_delta(&self) -> Option<f64> {
+        self.replayed_serendipity.map(|s| (s - self.recorded_serendipity).abs())
+    }
+
+    /// Absolute confidence divergence
+    pub fn confidence_delta(&self) -> Option<f64> {
+        self.replayed_confidence.map(|c| (c - self.recorded_confidence).abs())
+    }
+}
+
+/// Report of a replay run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    /// Trace that was replayed
+    pub trace_id: String,
+    /// Provenance hash of the trace at replay time
+    pub provenance_hash: String,
+    /// Per-step comparison
+    pub steps: Vec<ReplayStep>,
+    /// Event IDs whose scores diverged
+    pub diverged_events: Vec<String>,
+    /// Number of steps without a registered agent
+    pub skipped_steps: usize,
+    /// Number of steps whose output text differed
+    pub output_mismatches: usize,
+    /// Largest serendipity divergence
+    pub max_serendipity_delta: f64,
+    /// Largest confidence divergence
+    pub max_confidence_delta: f64,
+    /// Tolerance used for comparisons
+    pub tolerance: f64,
+}
+
+impl ReplayReport {
+    /// Trace reproduced: every step replayed within tolerance
+    pub fn is_reproducible(&self) -> bool {
+        self.diverged_events.is_empty() && self.skipped_steps == 0
+    }
+}
+
+/// Re-executes traces against registered agent callbacks
+pub struct TraceReplayer {
+    agents: HashMap<SerendipityAgent, Box<dyn ReplayAgent>>,
+    tolerance: f64,
+}
+
+impl TraceReplayer {
+    /// Create a replayer with no agents registered
+    pub fn new() -> Self {
+        Self {
+            agents: HashMap::new(),
+            tolerance: DEFAULT_REPLAY_TOLERANCE,
+        }
+    }
+
+    /// Set the absolute tolerance for score comparisons
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Register the callback that replays steps for an agent
+    pub fn register<A: ReplayAgent + 'static>(&mut self, agent: SerendipityAgent, callback: A) {
+        self.agents.insert(agent, Box::new(callback));
+    }
+
+    /// Replay a trace and compare against its recorded scores
+    pub fn replay(&mut self, trace: &SerendipityTrace) -> ReplayReport {
+        let mut steps = Vec::with_capacity(trace.events.len());
+        let mut replayed_outputs: Vec<String> = Vec::with_capacity(trace.events.len());
+
+        for (i, event) in trace.events.iter().enumerate() {
+            let replayed = self.agents.get_mut(&event.agent).map(|callback| {
+                callback.replay(&ReplayContext {
+                    event,
+                    history: &trace.events[..i],
+                    replayed_outputs: &replayed_outputs,
+                })
+            });
+
+            let step = match replayed {
+                Some(output) => {
+                    let diverged = (output.serendipity_score - event.serendipity_score).abs() > self.tolerance
+                        || (output.confidence - event.confidence).abs() > self.tolerance;
+                    let step = ReplayStep {
+                        event_id: event.event_id.clone(),
+                        agent: event.agent.clone(),
+                        skipped: false,
+                        recorded_serendipity: event.serendipity_score,
+                        replayed_serendipity: Some(output.serendipity_score),
+                        recorded_confidence: event.confidence,
+                        replayed_confidence: Some(output.confidence),
+                        output_matches: output.output == event.output,
+                        diverged,
+                    };
+                    replayed_outputs.push(output.output);
+                    step
+                }
+                None => {
+                    // Without a callback, carry the recorded output forward
+                    replayed_outputs.push(event.output.clone());
+                    ReplayStep {
+                        event_id: event.event_id.clone(),
+                        agent: event.agent.clone(),
+                        skipped: true,
+                        recorded_serendipity: event.serendipity_score,
+                        replayed_serendipity: None,
+                        recorded_confidence: event.confidence,
+                        replayed_confidence: None,
+                        output_matches: false,
+                        diverged: false,
+                    }
+                }
+            };
+            steps.push(step);
+        }
+
+        let diverged_events = steps
+            .iter()
+            .filter(|s| s.diverged)
+            .map(|s| s.event_id.clone())
+            .collect();
+        let skipped_steps = steps.iter().filter(|s| s.skipped).count();
+        let output_mismatches = steps.iter().filter(|s| !s.skipped && !s.output_matches).count();
+        let max_serendipity_delta = steps
+            .iter()
+            .filter_map(|s| s.serendipity_delta())
+            .fold(0.0, f64::max);
+        let max_confidence_delta = steps
+            .iter()
+            .filter_map(|s| s.confidence_delta())
+            .fold(0.0, f64::max);
+
+        ReplayReport {
+            trace_id: trace.trace_id.clone(),
+            provenance_hash: trace.compute_provenance_hash(),
+            steps,
+            diverged_events,
+            skipped_steps,
+            output_mismatches,
+            max_serendipity_delta,
+            max_confidence_delta,
+            tolerance: self.tolerance,
+        }
+    }
+}
+
+impl Default for TraceReplayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityStage;
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.log_event(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input1",
+            "output1",
+            "en",
+            0.7,
+            0.9,
+        ).unwrap();
+        trace.log_event(
+            SerendipityStage::UnexpectedConnection,
+            SerendipityAgent::PatternRecognizer,
+            "input2",
+            "output2",
+            "id",
+            0.92,
+            0.85,
+        ).unwrap();
+        trace
+    }
+
+    fn echo(context: &ReplayContext<'_>) -> ReplayOutput {
+        ReplayOutput {
+            output: context.event.output.clone(),
+            serendipity_score: context.event.serendipity_score,
+            confidence: context.event.confidence,
+        }
+    }
+
+    #[test]
+    fn test_reproducible_replay() {
+        let mut replayer = TraceReplayer::new();
+        replayer.register(SerendipityAgent::Explorer, echo);
+        replayer.register(SerendipityAgent::PatternRecognizer, echo);
+
+        let report = replayer.replay(&sample_trace());
+        assert!(report.is_reproducible());
+        assert_eq!(report.output_mismatches, 0);
+        assert_eq!(report.max_serendipity_delta, 0.0);
+    }
+
+    #[test]
+    fn test_divergent_and_skipped_steps() {
+        let mut replayer = TraceReplayer::new().with_tolerance(0.01);
+        replayer.register(SerendipityAgent::Explorer, |context: &ReplayContext<'_>| ReplayOutput {
+            output: "different".to_string(),
+            serendipity_score: context.event.serendipity_score + 0.2,
+            confidence: context.event.confidence,
+        });
+
+        let report = replayer.replay(&sample_trace());
+        assert!(!report.is_reproducible());
+        assert_eq!(report.diverged_events.len(), 1);
+        assert_eq!(report.skipped_steps, 1);
+        assert_eq!(report.output_mismatches, 1);
+        assert!((report.max_serendipity_delta - 0.2).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,234 @@
+// -*- coding: utf-8 -*-
+//! Reviewer Workflow for Traces
+//!
+//! A trace submitted for review collects `ReviewVerdict`s from reviewers,
+//! each approving, rejecting, or requesting changes to the whole trace or to
+//! a single event, with an optional comment. Verdicts are hashed into the
+//! provenance hash, so a signed trace also commits to its reviews. A
+//! reviewer's latest verdict on a target supersedes earlier ones. Leaderboards
+//! can require approval before a trace's discovery counts.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::ContributorStats::LanguageAwareLeaderboard;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Reviewer's decision
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    /// Accept as is
+    Approve,
+    /// Refuse outright
+    Reject,
+    /// Acceptable after changes
+    NeedsWork,
+}
+
+/// Verdict on a trace or one of its events
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReviewVerdict {
+    /// Reviewer ID
+    pub reviewer: String,
+    /// Decision
+    pub decision: ReviewDecision,
+    /// Event the verdict is about (`None` for the whole trace)
+    #[serde(default)]
+    pub event_id: Option<String>,
+    /// Reviewer's comment
+    #[serde(default)]
+    pub comment: String,
+    /// Time the verdict was given
+    pub at: DateTime<Utc>,
+}
+
+impl ReviewVerdict {
+    /// Verdict on the whole trace
+    pub fn on_trace(reviewer: &str, decision: ReviewDecision) -> Self {
+        Self {
+            reviewer: reviewer.to_string(),
+            decision,
+            event_id: None,
+            comment: String::new(),
+            at: Utc::now(),
+        }
+    }
+
+    /// Verdict on a single event
+    pub fn on_event(reviewer: &str, event_id: &str, decision: ReviewDecision) -> Self {
+        Self { event_id: Some(event_id.to_string()), ..Self::on_trace(reviewer, decision) }
+    }
+
+    /// Attach a comment
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = comment.to_string();
+        self
+    }
+
+    /// Canonical bytes hashed into the trace's provenance hash
+    pub(crate) fn hash_bytes(&self) -> String {
+        format!(
+            "review|{}|{:?}|{}|{}|{}",
+            self.reviewer,
+            self.decision,
+            self.event_id.as_deref().unwrap_or(""),
+            self.comment,
+            self.at.to_rfc3339()
+        )
+    }
+}
+
+/// Review state stored on a trace
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TraceReview {
+    /// When the trace was submitted for review
+    #[serde(default)]
+    pub submitted_at: Option<DateTime<Utc>>,
+    /// Verdicts in the order given
+    #[serde(default)]
+    pub verdicts: Vec<ReviewVerdict>,
+}
+
+/// Outcome of the review so far
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    /// Not submitted for review
+    NotSubmitted,
+    /// Submitted, awaiting an approving or blocking verdict
+    Pending,
+    /// A reviewer currently rejects the trace or one of its events
+    Rejected,
+    /// A reviewer currently requests changes
+    ChangesRequested,
+    /// Approved for the whole trace, with no standing objections
+    Approved,
+}
+
+impl SerendipityTrace {
+    /// Submit the trace for review
+    pub fn submit_for_review(&mut self) {
+        self.review.submitted_at.get_or_insert_with(Utc::now);
+    }
+
+    /// Record a reviewer's verdict. Fails if the trace was not submitted
+    /// or the verdict names an event not in the trace.
+    pub fn add_review(&mut self, verdict: ReviewVerdict) -> SerenQaResult<()> {
+        if self.review.submitted_at.is_none() {
+            return Err(SerenQaError::NotSubmittedForReview(self.trace_id.clone()));
+        }
+        if let Some(event_id) = &verdict.event_id {
+            if !self.events.iter().any(|e| &e.event_id == event_id) {
+                return Err(SerenQaError::UnknownEvent(event_id.clone()));
+            }
+        }
+        self.review.verdicts.push(verdict);
+        Ok(())
+    }
+
+    /// Each reviewer's latest verdict per target, in the order given
+    pub fn current_verdicts(&self) -> Vec<&ReviewVerdict> {
+        let verdicts = &self.review.verdicts;
+        verdicts
+            .iter()
+            .enumerate()
+            .filter(|(i, v)| {
+                !verdicts[i + 1..].iter().any(|later| later.reviewer == v.reviewer && later.event_id == v.event_id)
+            })
+            .map(|(_, v)| v)
+            .collect()
+    }
+
+    /// Review outcome from the current verdicts
+    pub fn review_status(&self) -> ReviewStatus {
+        if self.review.submitted_at.is_none() {
+            return ReviewStatus::NotSubmitted;
+        }
+        let current = self.current_verdicts();
+        let any = |decision| current.iter().any(|v| v.decision == decision);
+        if any(ReviewDecision::Reject) {
+            ReviewStatus::Rejected
+        } else if any(ReviewDecision::NeedsWork) {
+            ReviewStatus::ChangesRequested
+        } else if current.iter().any(|v| v.event_id.is_none() && v.decision == ReviewDecision::Approve) {
+            ReviewStatus::Approved
+        } else {
+            ReviewStatus::Pending
+        }
+    }
+
+    /// Whether the review approved the trace
+    pub fn is_approved(&self) -> bool {
+        self.review_status() == ReviewStatus::Approved
+    }
+}
+
+impl LanguageAwareLeaderboard {
+    /// Only count discoveries of approved traces in `credit_trace`
+    /// (traces are still credited to contributor statistics)
+    pub fn require_review_approval(&mut self, required: bool) {
+        self.approval_required = required;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+        trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "in", "out", "en", 0.9, 0.9).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_review_status_and_provenance() {
+        let mut trace = trace();
+        let verdict = ReviewVerdict::on_trace("reviewer1", ReviewDecision::Approve);
+        assert!(matches!(trace.add_review(verdict.clone()), Err(SerenQaError::NotSubmittedForReview(_))));
+        assert_eq!(trace.review_status(), ReviewStatus::NotSubmitted);
+
+        trace.submit_for_review();
+        let unreviewed_hash = trace.compute_provenance_hash();
+        assert_eq!(trace.review_status(), ReviewStatus::Pending);
+        let validation = trace.events[1].event_id.clone();
+        trace.add_review(ReviewVerdict::on_event("reviewer2", &validation, ReviewDecision::NeedsWork).comment("add baseline runs")).unwrap();
+        trace.add_review(verdict).unwrap();
+        assert_eq!(trace.review_status(), ReviewStatus::ChangesRequested);
+
+        // A later verdict from the same reviewer on the same event supersedes the earlier one
+        trace.add_review(ReviewVerdict::on_event("reviewer2", &validation, ReviewDecision::Approve)).unwrap();
+        assert_eq!(trace.current_verdicts().len(), 2);
+        assert!(trace.is_approved());
+        assert!(matches!(
+            trace.add_review(ReviewVerdict::on_event("reviewer2", "missing", ReviewDecision::Reject)),
+            Err(SerenQaError::UnknownEvent(_))
+        ));
+
+        let reviewed_hash = trace.compute_provenance_hash();
+        assert_ne!(reviewed_hash, unreviewed_hash);
+        trace.review.verdicts[0].comment = "looks fine".to_string();
+        assert_ne!(trace.compute_provenance_hash(), reviewed_hash);
+    }
+
+    #[test]
+    fn test_only_approved_discoveries_count() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        leaderboard.require_review_approval(true);
+
+        let mut pending = trace();
+        pending.submit_for_review();
+        leaderboard.credit_trace(&pending, 0.8, 0.8).unwrap();
+        let stats = leaderboard.get("sari").unwrap();
+        assert_eq!((stats.total_traces, stats.discoveries.len()), (1, 0));
+
+        let mut approved = trace();
+        approved.submit_for_review();
+        approved.add_review(ReviewVerdict::on_trace("reviewer1", ReviewDecision::Approve)).unwrap();
+        leaderboard.credit_trace(&approved, 0.8, 0.8).unwrap();
+        assert_eq!(leaderboard.get("sari").unwrap().discoveries, vec!["Journavx".to_string()]);
+    }
+}
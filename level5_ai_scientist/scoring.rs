@@ -0,0 +1,283 @@
+// -*- coding: utf-8 -*-
+//! Serendipity Scoring Models
+//!
+//! Callers otherwise pick serendipity scores by hand. A `SerendipityScorer`
+//! computes the score from an event's content and the trace history, and
+//! `log_event_scored` logs the event with that score. Built-in models score
+//! keyword novelty, embedding distance to prior events, and stage surprise;
+//! `CompositeScorer` blends several models. Scores are clamped to [0, 1].
+
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEvent, SerendipityEventBuilder, SerendipityStage};
+use crate::error::SerenQaResult;
+
+/// Model computing a serendipity score for a candidate event
+pub trait SerendipityScorer: Send + Sync {
+    /// Name used in diagnostics
+    fn name(&self) -> &str;
+
+    /// Raw score for `event` given the events already in the trace
+    fn score(&self, event: &SerendipityEvent, history: &[SerendipityEvent]) -> f64;
+}
+
+/// Clamp a raw score into [0, 1], treating NaN as 0
+fn clamp_score(score: f64) -> f64 {
+    if score.is_nan() { 0.0 } else { score.clamp(0.0, 1.0) }
+}
+
+/// Lowercased words of at least three characters
+fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn event_text(event: &SerendipityEvent) -> String {
+    format!("{} {}", event.input, event.output)
+}
+
+/// Fraction of the event's keywords never seen earlier in the trace
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeywordNovelty;
+
+impl SerendipityScorer for KeywordNovelty {
+    fn name(&self) -> &str {
+        "keyword_novelty"
+    }
+
+    fn score(&self, event: &SerendipityEvent, history: &[SerendipityEvent]) -> f64 {
+        let words = keywords(&event_text(event));
+        if words.is_empty() {
+            return 0.0;
+        }
+        let seen: HashSet<String> = history.iter().flat_map(|e| keywords(&event_text(e))).collect();
+        words.iter().filter(|w| !seen.contains(*w)).count() as f64 / words.len() as f64
+    }
+}
+
+/// Text embedding model used by `EmbeddingDistance`
+pub trait Embedder: Send + Sync {
+    /// Embed a piece of text
+    fn embed(&self, text: &str) -> Vec<f64>;
+}
+
+impl<F> Embedder for F
+where
+    F: Fn(&str) -> Vec<f64> + Send + Sync,
+{
+    fn embed(&self, text: &str) -> Vec<f64> {
+        self(text)
+    }
+}
+
+/// Dependency-free embedder hashing keywords into a fixed number of buckets
+#[derive(Debug, Clone, Copy)]
+pub struct HashedBagOfWords {
+    /// Embedding dimensions
+    pub dims: usize,
+}
+
+impl Default for HashedBagOfWords {
+    fn default() -> Self {
+        Self { dims: 256 }
+    }
+}
+
+impl Embedder for HashedBagOfWords {
+    fn embed(&self, text: &str) -> Vec<f64> {
+        let mut vector = vec![0.0; self.dims.max(1)];
+        for word in keywords(text) {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            word.hash(&mut hasher);
+            let bucket = (hasher.finish() % vector.len() as u64) as usize;
+            vector[bucket] += 1.0;
+        }
+        vector
+    }
+}
+
+fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f64]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 { 0.0 } else { dot / denominator }
+}
+
+/// One minus the highest cosine similarity to any earlier event
+/// (1 for the first event)
+pub struct EmbeddingDistance<E: Embedder> {
+    embedder: E,
+}
+
+impl<E: Embedder> EmbeddingDistance<E> {
+    /// Score with the given embedding model
+    pub fn new(embedder: E) -> Self {
+        Self { embedder }
+    }
+}
+
+impl Default for EmbeddingDistance<HashedBagOfWords> {
+    fn default() -> Self {
+        Self::new(HashedBagOfWords::default())
+    }
+}
+
+impl<E: Embedder> SerendipityScorer for EmbeddingDistance<E> {
+    fn name(&self) -> &str {
+        "embedding_distance"
+    }
+
+    fn score(&self, event: &SerendipityEvent, history: &[SerendipityEvent]) -> f64 {
+        let embedding = self.embedder.embed(&event_text(event));
+        let closest = history
+            .iter()
+            .map(|e| cosine_similarity(&embedding, &self.embedder.embed(&event_text(e))))
+            .fold(0.0, f64::max);
+        1.0 - closest
+    }
+}
+
+/// Heuristic surprise from the stage itself and how the event departs from
+/// the previous one: a new language adds 0.15, an agent not seen before adds
+/// 0.1, and moving back to an earlier stage adds 0.1
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageSurprise;
+
+impl StageSurprise {
+    /// Base surprise of a stage
+    pub fn base(stage: &SerendipityStage) -> f64 {
+        match stage {
+            SerendipityStage::Exploration => 0.3,
+            SerendipityStage::UnexpectedConnection => 0.8,
+            SerendipityStage::HypothesisFormation => 0.5,
+            SerendipityStage::Validation => 0.4,
+            SerendipityStage::Integration => 0.3,
+            SerendipityStage::Publication => 0.2,
+            // Nothing is known about a custom stage; take the midpoint
+            SerendipityStage::Custom(_) => 0.5,
+        }
+    }
+}
+
+impl SerendipityScorer for StageSurprise {
+    fn name(&self) -> &str {
+        "stage_surprise"
+    }
+
+    fn score(&self, event: &SerendipityEvent, history: &[SerendipityEvent]) -> f64 {
+        let mut score = Self::base(&event.stage);
+        if let Some(previous) = history.last() {
+            if previous.language != event.language {
+                score += 0.15;
+            }
+            if event.stage.ordinal() < previous.stage.ordinal() {
+                score += 0.1;
+            }
+            if !history.iter().any(|e| e.agent == event.agent) {
+                score += 0.1;
+            }
+        }
+        score
+    }
+}
+
+/// Weighted mean of several scorers
+#[derive(Default)]
+pub struct CompositeScorer {
+    scorers: Vec<(Box<dyn SerendipityScorer>, f64)>,
+}
+
+impl CompositeScorer {
+    /// Composite with no scorers (scores 0)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a scorer with a (non-negative) weight
+    pub fn with<S: SerendipityScorer + 'static>(mut self, scorer: S, weight: f64) -> Self {
+        self.scorers.push((Box::new(scorer), weight.max(0.0)));
+        self
+    }
+}
+
+impl SerendipityScorer for CompositeScorer {
+    fn name(&self) -> &str {
+        "composite"
+    }
+
+    fn score(&self, event: &SerendipityEvent, history: &[SerendipityEvent]) -> f64 {
+        let total: f64 = self.scorers.iter().map(|(_, weight)| weight).sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+        self.scorers
+            .iter()
+            .map(|(scorer, weight)| weight * clamp_score(scorer.score(event, history)))
+            .sum::<f64>()
+            / total
+    }
+}
+
+impl SerendipityTrace {
+    /// Log an event whose serendipity score is computed by `scorer` from the
+    /// event content and the trace history (any score on the builder is replaced)
+    pub fn log_event_scored<S: SerendipityScorer + ?Sized>(
+        &mut self,
+        builder: SerendipityEventBuilder,
+        scorer: &S,
+    ) -> SerenQaResult<&SerendipityEvent> {
+        let mut event = builder.serendipity(0.0).build()?;
+        event.serendipity_score = clamp_score(scorer.score(&event, &self.events));
+        self.push_event(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityAgent;
+
+    fn event(stage: SerendipityStage, agent: SerendipityAgent, output: &str, language: &str) -> SerendipityEventBuilder {
+        SerendipityEventBuilder::new(stage, agent, "", output, language).confidence(0.8)
+    }
+
+    #[test]
+    fn test_content_scorers() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        let first = trace.log_event_scored(event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "quantum walk navigation", "en"), &KeywordNovelty).unwrap();
+        assert_eq!(first.serendipity_score, 1.0);
+
+        let repeat = event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "quantum walk navigation", "en");
+        assert_eq!(trace.log_event_scored(repeat, &KeywordNovelty).unwrap().serendipity_score, 0.0);
+
+        let half_new = event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "quantum wayfinding", "en");
+        assert_eq!(trace.log_event_scored(half_new, &KeywordNovelty).unwrap().serendipity_score, 0.5);
+
+        let near = event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "navigation quantum walk", "en");
+        assert!(trace.log_event_scored(near, &EmbeddingDistance::default()).unwrap().serendipity_score < 1e-9);
+        let topics = EmbeddingDistance::new(|text: &str| {
+            vec![text.contains("quantum") as u8 as f64, text.contains("Javanese") as u8 as f64]
+        });
+        let far = event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "Javanese star compass", "id");
+        assert_eq!(trace.log_event_scored(far, &topics).unwrap().serendipity_score, 1.0);
+    }
+
+    #[test]
+    fn test_stage_surprise_and_composite() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.log_event_scored(event(SerendipityStage::Validation, SerendipityAgent::Validator, "checked", "en"), &StageSurprise).unwrap();
+        assert_eq!(trace.events[0].serendipity_score, 0.4);
+
+        // Connection after validation, in a new language, from a new agent
+        let connection = event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "mirip", "id");
+        assert_eq!(trace.log_event_scored(connection, &StageSurprise).unwrap().serendipity_score, 1.0);
+
+        let composite = CompositeScorer::new().with(StageSurprise, 1.0).with(KeywordNovelty, 3.0);
+        let publication = event(SerendipityStage::Publication, SerendipityAgent::PatternRecognizer, "mirip", "id");
+        let score = trace.log_event_scored(publication, &composite).unwrap().serendipity_score;
+        assert!((score - 0.05).abs() < 1e-9);
+        assert_eq!(CompositeScorer::new().score(&trace.events[0], &[]), 0.0);
+    }
+}
@@ -0,0 +1,261 @@
+// -*- coding: utf-8 -*-
+//! Writing Script Detection and Romanization
+//!
+//! The language registry knows each language's usual script, but text does
+//! not always follow it: Javanese may be written in aksara Jawa, Malay in
+//! Jawi (Arabic script). Scripts are detected from the text itself by Unicode
+//! block, by majority of letters; Japanese kana pull co-occurring Han into
+//! "Japanese". `romanize` transliterates Greek, Cyrillic, Arabic, Devanagari,
+//! and Javanese letters to Latin so texts written in different scripts can be
+//! aligned (other scripts pass through). The pipeline records the detected
+//! script in language metadata, translation summaries align romanized text
+//! across scripts, and trace statistics count scripts and script switches.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::language_registry::LanguageRegistry;
+
+/// Writing script of a letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Bengali,
+    Tamil,
+    Thai,
+    Javanese,
+    Balinese,
+    Sundanese,
+    Hangul,
+    /// Hiragana and katakana (and Han mixed with them)
+    Japanese,
+    Han,
+}
+
+impl Script {
+    /// Script of a letter; `None` for digits, punctuation, and unlisted scripts
+    pub fn of(c: char) -> Option<Script> {
+        let script = match c as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F | 0x1E00..=0x1EFF => Script::Latin,
+            0x370..=0x3FF | 0x1F00..=0x1FFF => Script::Greek,
+            0x400..=0x4FF => Script::Cyrillic,
+            0x590..=0x5FF => Script::Hebrew,
+            0x600..=0x6FF | 0x750..=0x77F => Script::Arabic,
+            0x900..=0x97F => Script::Devanagari,
+            0x980..=0x9FF => Script::Bengali,
+            0xB80..=0xBFF => Script::Tamil,
+            0xE00..=0xE7F => Script::Thai,
+            0xA980..=0xA9DF => Script::Javanese,
+            0x1B00..=0x1B7F => Script::Balinese,
+            0x1B80..=0x1BBF => Script::Sundanese,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+            0x3040..=0x30FF => Script::Japanese,
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF => Script::Han,
+            _ => return None,
+        };
+        // Block ranges also hold digits and punctuation
+        c.is_alphabetic().then_some(script)
+    }
+
+    /// Name as used in the language registry (e.g., "Latin")
+    pub fn name(&self) -> &'static str {
+        match self {
+            Script::Latin => "Latin",
+            Script::Greek => "Greek",
+            Script::Cyrillic => "Cyrillic",
+            Script::Hebrew => "Hebrew",
+            Script::Arabic => "Arabic",
+            Script::Devanagari => "Devanagari",
+            Script::Bengali => "Bengali",
+            Script::Tamil => "Tamil",
+            Script::Thai => "Thai",
+            Script::Javanese => "Javanese",
+            Script::Balinese => "Balinese",
+            Script::Sundanese => "Sundanese",
+            Script::Hangul => "Hangul",
+            Script::Japanese => "Japanese",
+            Script::Han => "Han",
+        }
+    }
+}
+
+/// Letters per script in `text`
+pub fn script_counts(text: &str) -> BTreeMap<Script, usize> {
+    let mut counts = BTreeMap::new();
+    for script in text.chars().filter_map(Script::of) {
+        *counts.entry(script).or_insert(0) += 1;
+    }
+    if let Some(han) = counts.get(&Script::Han).copied().filter(|_| counts.contains_key(&Script::Japanese)) {
+        counts.remove(&Script::Han);
+        *counts.entry(Script::Japanese).or_insert(0) += han;
+    }
+    counts
+}
+
+/// Script of most of the letters in `text`; `None` if it has none
+pub fn detect_script(text: &str) -> Option<Script> {
+    script_counts(text)
+        .into_iter()
+        .max_by_key(|&(script, count)| (count, std::cmp::Reverse(script)))
+        .map(|(script, _)| script)
+}
+
+/// Script name of a text in `language`: detected from the text, else the
+/// language's usual script from the registry, else "Unknown"
+pub fn text_script(text: &str, language: &str) -> String {
+    detect_script(text)
+        .map(|script| script.name().to_string())
+        .or_else(|| LanguageRegistry::global().script(language).map(str::to_string))
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Latin letters for an alphabet letter (Greek, Cyrillic, Arabic)
+fn alphabet_letter(c: char) -> Option<&'static str> {
+    let latin = match c {
+        // Greek
+        'α' | 'ά' => "a", 'β' => "v", 'γ' => "g", 'δ' => "d", 'ε' | 'έ' => "e", 'ζ' => "z",
+        'η' | 'ή' => "i", 'θ' => "th", 'ι' | 'ί' | 'ϊ' => "i", 'κ' => "k", 'λ' => "l", 'μ' => "m",
+        'ν' => "n", 'ξ' => "x", 'ο' | 'ό' => "o", 'π' => "p", 'ρ' => "r", 'σ' | 'ς' => "s",
+        'τ' => "t", 'υ' | 'ύ' | 'ϋ' => "y", 'φ' => "f", 'χ' => "ch", 'ψ' => "ps", 'ω' | 'ώ' => "o",
+        // Cyrillic
+        'а' => "a", 'б' => "b", 'в' => "v", 'г' => "g", 'д' => "d", 'е' => "e", 'ё' => "yo",
+        'ж' => "zh", 'з' => "z", 'и' => "i", 'й' => "y", 'к' => "k", 'л' => "l", 'м' => "m",
+        'н' => "n", 'о' => "o", 'п' => "p", 'р' => "r", 'с' => "s", 'т' => "t", 'у' => "u",
+        'ф' => "f", 'х' => "kh", 'ц' => "ts", 'ч' => "ch", 'ш' => "sh", 'щ' => "shch", 'ъ' | 'ь' => "",
+        'ы' => "y", 'э' => "e", 'ю' => "yu", 'я' => "ya", 'і' => "i", 'ї' => "yi", 'є' => "ye",
+        // Arabic (consonantal; short vowels only where marked)
+        'ا' | 'أ' | 'آ' | 'ى' => "a", 'إ' => "i", 'ب' => "b", 'ت' => "t", 'ث' => "th", 'ج' => "j",
+        'ح' | 'ه' | 'ة' => "h", 'خ' => "kh", 'د' => "d", 'ذ' => "dh", 'ر' => "r", 'ز' => "z",
+        'س' => "s", 'ش' => "sh", 'ص' => "s", 'ض' => "d", 'ط' => "t", 'ظ' => "z", 'ع' | 'ء' => "'",
+        'غ' => "gh", 'ف' => "f", 'ق' => "q", 'ك' => "k", 'ل' => "l", 'م' => "m", 'ن' => "n",
+        'و' => "w", 'ي' => "y", '\u{064E}' => "a", '\u{0650}' => "i", '\u{064F}' => "u",
+        '\u{0651}' | '\u{0652}' => "",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+/// Abugida sign (Devanagari, Javanese): consonants carry an inherent "a"
+/// that vowel signs replace and the virama removes
+enum Abugida {
+    /// Consonant with inherent vowel
+    Consonant(&'static str),
+    /// Independent vowel or final sign
+    Letter(&'static str),
+    /// Dependent vowel sign
+    VowelSign(&'static str),
+    /// Virama (Devanagari halant, Javanese pangkon)
+    Virama,
+    /// Javanese tarung: turns a preceding "e" sign into "o"
+    Tarung,
+}
+
+fn abugida_sign(c: char) -> Option<Abugida> {
+    use Abugida::*;
+    let sign = match c {
+        // Devanagari
+        'क' => Consonant("k"), 'ख' => Consonant("kh"), 'ग' => Consonant("g"), 'घ' => Consonant("gh"),
+        'ङ' => Consonant("ng"), 'च' => Consonant("c"), 'छ' => Consonant("ch"), 'ज' => Consonant("j"),
+        'झ' => Consonant("jh"), 'ञ' => Consonant("ny"), 'ट' => Consonant("t"), 'ठ' => Consonant("th"),
+        'ड' => Consonant("d"), 'ढ' => Consonant("dh"), 'ण' => Consonant("n"), 'त' => Consonant("t"),
+        'थ' => Consonant("th"), 'द' => Consonant("d"), 'ध' => Consonant("dh"), 'न' => Consonant("n"),
+        'प' => Consonant("p"), 'फ' => Consonant("ph"), 'ब' => Consonant("b"), 'भ' => Consonant("bh"),
+        'म' => Consonant("m"), 'य' => Consonant("y"), 'र' => Consonant("r"), 'ल' => Consonant("l"),
+        'व' => Consonant("v"), 'श' | 'ष' => Consonant("sh"), 'स' => Consonant("s"), 'ह' => Consonant("h"),
+        'अ' => Letter("a"), 'आ' => Letter("aa"), 'इ' => Letter("i"), 'ई' => Letter("ii"),
+        'उ' => Letter("u"), 'ऊ' => Letter("uu"), 'ए' => Letter("e"), 'ऐ' => Letter("ai"),
+        'ओ' => Letter("o"), 'औ' => Letter("au"), 'ं' => Letter("m"), 'ः' => Letter("h"),
+        'ा' => VowelSign("aa"), 'ि' => VowelSign("i"), 'ी' => VowelSign("ii"), 'ु' => VowelSign("u"),
+        'ू' => VowelSign("uu"), 'े' => VowelSign("e"), 'ै' => VowelSign("ai"), 'ो' => VowelSign("o"),
+        'ौ' => VowelSign("au"), '्' => Virama,
+        // Javanese
+        'ꦲ' => Consonant("h"), 'ꦤ' => Consonant("n"), 'ꦕ' => Consonant("c"), 'ꦫ' => Consonant("r"),
+        'ꦏ' => Consonant("k"), 'ꦢ' => Consonant("d"), 'ꦠ' => Consonant("t"), 'ꦱ' => Consonant("s"),
+        'ꦮ' => Consonant("w"), 'ꦭ' => Consonant("l"), 'ꦥ' => Consonant("p"), 'ꦝ' => Consonant("dh"),
+        'ꦗ' => Consonant("j"), 'ꦪ' => Consonant("y"), 'ꦚ' => Consonant("ny"), 'ꦩ' => Consonant("m"),
+        'ꦒ' => Consonant("g"), 'ꦧ' => Consonant("b"), 'ꦛ' => Consonant("th"), 'ꦔ' => Consonant("ng"),
+        'ꦄ' => Letter("a"), 'ꦆ' => Letter("i"), 'ꦈ' => Letter("u"), 'ꦌ' => Letter("e"), 'ꦎ' => Letter("o"),
+        'ꦁ' => Letter("ng"), 'ꦂ' => Letter("r"), 'ꦃ' => Letter("h"), '꧈' => Letter(","), '꧉' => Letter("."),
+        'ꦶ' => VowelSign("i"), 'ꦸ' => VowelSign("u"), 'ꦺ' => VowelSign("e"), 'ꦼ' => VowelSign("e"),
+        'ꦴ' => Tarung, '꧀' => Virama,
+        _ => return None,
+    };
+    Some(sign)
+}
+
+/// Transliterate Greek, Cyrillic, Arabic, Devanagari, and Javanese letters to
+/// lowercase Latin; everything else is kept
+pub fn romanize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    // Whether `out` ends with a consonant's inherent "a"
+    let mut inherent = false;
+    for c in text.chars() {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        if let Some(latin) = alphabet_letter(lower) {
+            out.push_str(latin);
+            inherent = false;
+            continue;
+        }
+        match abugida_sign(c) {
+            Some(Abugida::Consonant(latin)) => {
+                out.push_str(latin);
+                out.push('a');
+                inherent = true;
+                continue;
+            }
+            Some(Abugida::VowelSign(latin)) => {
+                if inherent {
+                    out.pop();
+                }
+                out.push_str(latin);
+            }
+            Some(Abugida::Virama) => {
+                if inherent {
+                    out.pop();
+                }
+            }
+            Some(Abugida::Tarung) => {
+                if out.ends_with('e') {
+                    out.pop();
+                    out.push('o');
+                }
+            }
+            Some(Abugida::Letter(latin)) => out.push_str(latin),
+            None => out.push(c),
+        }
+        inherent = false;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_script_of_text() {
+        assert_eq!(detect_script("Pola batik"), Some(Script::Latin));
+        assert_eq!(detect_script("ꦗꦮ ꦧꦠꦶꦏ꧀"), Some(Script::Javanese));
+        assert_eq!(detect_script("طريق الملاحة"), Some(Script::Arabic));
+        assert_eq!(detect_script("東京へ行きます"), Some(Script::Japanese));
+        assert_eq!(detect_script("航海路线"), Some(Script::Han));
+        assert_eq!(detect_script("2024 -- 17"), None);
+        assert_eq!(text_script("2024", "id"), "Latin");
+        assert_eq!(text_script("नक्शा", "id"), "Devanagari");
+    }
+
+    #[test]
+    fn test_romanizes_across_scripts() {
+        assert_eq!(romanize("ꦗꦮ"), "jawa");
+        assert_eq!(romanize("ꦧꦠꦶꦏ꧀"), "batik");
+        assert_eq!(romanize("ꦠꦺꦴꦏ"), "toka");
+        assert_eq!(romanize("नक्शा"), "nakshaa");
+        assert_eq!(romanize("Москва"), "moskva");
+        assert_eq!(romanize("كتاب"), "ktab");
+        assert_eq!(romanize("Batik 2024"), "Batik 2024");
+    }
+}
@@ -0,0 +1,349 @@
+// -*- coding: utf-8 -*-
+//! Full-Text Search over Event Text
+//!
+//! `TraceQuery` filters on structured fields. Finding the traces that mention
+//! "quantum walk" needs a text index. `FullTextIndex` keeps a Tantivy index
+//! with one document per event over its input and output, and ranks hits by
+//! BM25. Text is analyzed for the event's language: languages with a Snowball
+//! stemmer (English, French, German, Spanish, Russian, Arabic, and others)
+//! use their own stemmed field, and all other languages such as Indonesian
+//! use a lowercased, unstemmed field. A search runs against every field.
+//! `search_matching` limits hits to the events a `TraceQuery` accepts.
+//! Requires the `search` feature (`tantivy` crate).
+#![cfg(feature = "search")]
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, STORED, STRING};
+use tantivy::tokenizer::{Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer, TextAnalyzer};
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::language_registry::LanguageRegistry;
+use crate::query::TraceQuery;
+use crate::store::TraceStore;
+use crate::error::SerenQaResult;
+
+/// Memory budget of the index writer
+const WRITER_MEMORY: usize = 50_000_000;
+
+/// Tokens longer than this (base64 blobs, hashes) are not indexed
+const MAX_TOKEN_LEN: usize = 40;
+
+/// Primary language subtags with a stemmed field
+const STEMMED_LANGUAGES: &[(&str, Language)] = &[
+    ("ar", Language::Arabic),
+    ("da", Language::Danish),
+    ("de", Language::German),
+    ("el", Language::Greek),
+    ("en", Language::English),
+    ("es", Language::Spanish),
+    ("fi", Language::Finnish),
+    ("fr", Language::French),
+    ("hu", Language::Hungarian),
+    ("it", Language::Italian),
+    ("nl", Language::Dutch),
+    ("no", Language::Norwegian),
+    ("pt", Language::Portuguese),
+    ("ro", Language::Romanian),
+    ("ru", Language::Russian),
+    ("sv", Language::Swedish),
+    ("ta", Language::Tamil),
+    ("tr", Language::Turkish),
+];
+
+/// Subtag of the unstemmed field for all other languages
+const DEFAULT_ANALYZER: &str = "default";
+
+/// Ranked event matching a text search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub trace_id: String,
+    pub event_id: String,
+    /// Canonical language of the event
+    pub language: String,
+    /// BM25 relevance; higher is better
+    pub score: f32,
+}
+
+/// Tantivy index over the text of logged events
+pub struct FullTextIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    trace_id: Field,
+    event_id: Field,
+    language: Field,
+    /// Text field per analyzer subtag, the default analyzer last
+    text_fields: Vec<(&'static str, Field)>,
+}
+
+fn analyzer_name(subtag: &str) -> String {
+    format!("seren_{}", subtag)
+}
+
+fn schema() -> Schema {
+    let mut builder = Schema::builder();
+    builder.add_text_field("trace_id", STRING | STORED);
+    builder.add_text_field("event_id", STRING | STORED);
+    builder.add_text_field("language", STRING | STORED);
+    let subtags = STEMMED_LANGUAGES.iter().map(|(subtag, _)| *subtag).chain([DEFAULT_ANALYZER]);
+    for subtag in subtags {
+        let indexing = TextFieldIndexing::default()
+            .set_tokenizer(&analyzer_name(subtag))
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+        builder.add_text_field(&format!("text_{}", subtag), TextOptions::default().set_indexing_options(indexing));
+    }
+    builder.build()
+}
+
+impl FullTextIndex {
+    /// Index held in memory, e.g. for tests or one-off searches
+    pub fn in_memory() -> SerenQaResult<Self> {
+        Self::from_index(Index::create_in_ram(schema()))
+    }
+
+    /// Index in `dir`, created if it does not exist yet
+    pub fn open(dir: &Path) -> SerenQaResult<Self> {
+        std::fs::create_dir_all(dir)?;
+        let directory = MmapDirectory::open(dir).map_err(tantivy::TantivyError::from)?;
+        Self::from_index(Index::open_or_create(directory, schema())?)
+    }
+
+    fn from_index(index: Index) -> SerenQaResult<Self> {
+        for (subtag, language) in STEMMED_LANGUAGES {
+            let analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+                .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+                .filter(LowerCaser)
+                .filter(Stemmer::new(*language))
+                .build();
+            index.tokenizers().register(&analyzer_name(subtag), analyzer);
+        }
+        let analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+            .filter(LowerCaser)
+            .build();
+        index.tokenizers().register(&analyzer_name(DEFAULT_ANALYZER), analyzer);
+
+        let schema = index.schema();
+        let text_fields = STEMMED_LANGUAGES
+            .iter()
+            .map(|(subtag, _)| *subtag)
+            .chain([DEFAULT_ANALYZER])
+            .map(|subtag| Ok((subtag, schema.get_field(&format!("text_{}", subtag))?)))
+            .collect::<SerenQaResult<Vec<_>>>()?;
+        let reader = index.reader_builder().reload_policy(ReloadPolicy::Manual).try_into()?;
+        let writer = index.writer(WRITER_MEMORY)?;
+        Ok(Self {
+            trace_id: schema.get_field("trace_id")?,
+            event_id: schema.get_field("event_id")?,
+            language: schema.get_field("language")?,
+            text_fields,
+            reader,
+            writer: Mutex::new(writer),
+            index,
+        })
+    }
+
+    /// Text field for a canonical language tag
+    fn text_field(&self, language: &str) -> Field {
+        let subtag = language.split('-').next().unwrap_or_default().to_ascii_lowercase();
+        let (_, field) = self
+            .text_fields
+            .iter()
+            .find(|(candidate, _)| *candidate == subtag)
+            .unwrap_or_else(|| self.text_fields.last().expect("default text field"));
+        *field
+    }
+
+    /// Index the events of `trace`, replacing any earlier version of it;
+    /// visible to searches after `commit`
+    pub fn index_trace(&self, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        let registry = LanguageRegistry::global();
+        let writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.delete_term(Term::from_field_text(self.trace_id, &trace.trace_id));
+        for event in &trace.events {
+            let language = registry.canonical(&event.language);
+            let text = self.text_field(&language);
+            let mut document = TantivyDocument::default();
+            document.add_text(self.trace_id, &trace.trace_id);
+            document.add_text(self.event_id, &event.event_id);
+            document.add_text(self.language, &language);
+            document.add_text(text, &event.input);
+            document.add_text(text, &event.output);
+            writer.add_document(document)?;
+        }
+        Ok(())
+    }
+
+    /// Remove a trace's events; takes effect on `commit`
+    pub fn remove_trace(&self, trace_id: &str) {
+        let writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.delete_term(Term::from_field_text(self.trace_id, trace_id));
+    }
+
+    /// Index every trace in `store` and commit, dropping traces indexed
+    /// before that are no longer stored; returns the number indexed
+    pub fn index_store(&self, store: &dyn TraceStore) -> SerenQaResult<usize> {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner()).delete_all_documents()?;
+        let mut count = 0;
+        for trace_id in store.list()? {
+            if let Some(trace) = store.load(&trace_id)? {
+                self.index_trace(&trace)?;
+                count += 1;
+            }
+        }
+        self.commit()?;
+        Ok(count)
+    }
+
+    /// Persist pending changes and make them visible to searches
+    pub fn commit(&self) -> SerenQaResult<()> {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner()).commit()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Best `limit` events for `text`, best first. `text` uses Tantivy's
+    /// query syntax: `quantum walk` ranks either term, `"quantum walk"`
+    /// requires the phrase, and `+quantum -classical` requires or excludes terms.
+    pub fn search(&self, text: &str, limit: usize) -> SerenQaResult<Vec<SearchHit>> {
+        self.ranked(text, limit, |_| true)
+    }
+
+    /// Best `limit` events for `text` among those `query` matches in `store`
+    pub fn search_matching(&self, text: &str, query: &TraceQuery, store: &dyn TraceStore, limit: usize) -> SerenQaResult<Vec<SearchHit>> {
+        let accepted: HashSet<(String, String)> = query
+            .run(store)?
+            .into_iter()
+            .flat_map(|found| {
+                let trace_id = found.trace_id;
+                found.event_ids.into_iter().map(move |event_id| (trace_id.clone(), event_id))
+            })
+            .collect();
+        self.ranked(text, limit, |hit| accepted.contains(&(hit.trace_id.clone(), hit.event_id.clone())))
+    }
+
+    fn ranked(&self, text: &str, limit: usize, keep: impl Fn(&SearchHit) -> bool) -> SerenQaResult<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+        let indexed = searcher.num_docs() as usize;
+        if limit == 0 || indexed == 0 {
+            return Ok(Vec::new());
+        }
+        let fields = self.text_fields.iter().map(|(_, field)| *field).collect();
+        let parsed = QueryParser::for_index(&self.index, fields).parse_query(text)?;
+        // Rank every event so filtering cannot push matches out of the top `limit`
+        let ranked = searcher.search(&*parsed, &TopDocs::with_limit(indexed))?;
+
+        let stored = |document: &TantivyDocument, field: Field| {
+            document.get_first(field).and_then(|value| value.as_str()).unwrap_or_default().to_string()
+        };
+        let mut hits = Vec::new();
+        for (score, address) in ranked {
+            let document: TantivyDocument = searcher.doc(address)?;
+            let hit = SearchHit {
+                trace_id: stored(&document, self.trace_id),
+                event_id: stored(&document, self.event_id),
+                language: stored(&document, self.language),
+                score,
+            };
+            if keep(&hit) {
+                hits.push(hit);
+                if hits.len() == limit {
+                    break;
+                }
+            }
+        }
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+    use crate::store::InMemoryTraceStore;
+
+    fn trace(trace_id: &str, language: &str, output: &str, score: f64) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.trace_id = trace_id.to_string();
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "survey", output, language, score, 0.8).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_ranked_search_across_languages() {
+        let index = FullTextIndex::in_memory().unwrap();
+        index.index_trace(&trace("t1", "en", "quantum walks speed up graph search", 0.9)).unwrap();
+        index.index_trace(&trace("t2", "id", "quantum walk pada graf", 0.7)).unwrap();
+        index.index_trace(&trace("t3", "en", "classical random walk baseline", 0.4)).unwrap();
+        index.commit().unwrap();
+
+        // English stemming matches "walks"; Indonesian text is matched unstemmed
+        let hits = index.search("\"quantum walk\"", 10).unwrap();
+        let ids: HashSet<&str> = hits.iter().map(|hit| hit.trace_id.as_str()).collect();
+        assert_eq!(ids, HashSet::from(["t1", "t2"]));
+        assert_eq!(index.search("walk", 10).unwrap().len(), 3);
+        assert_eq!(index.search("walk", 1).unwrap().len(), 1);
+
+        // Re-indexing replaces the trace's events
+        index.index_trace(&trace("t1", "en", "tensor network contraction", 0.9)).unwrap();
+        index.commit().unwrap();
+        assert!(index.search("quantum", 10).unwrap().iter().all(|hit| hit.trace_id == "t2"));
+    }
+
+    #[test]
+    fn test_search_matching_query() {
+        let store = InMemoryTraceStore::new();
+        store.save(&trace("t1", "en", "quantum walk on hypercubes", 0.95)).unwrap();
+        store.save(&trace("t2", "en", "quantum walk on lines", 0.3)).unwrap();
+        let index = FullTextIndex::in_memory().unwrap();
+        assert_eq!(index.index_store(&store).unwrap(), 2);
+
+        let query = TraceQuery::new().min_serendipity(0.9);
+        let hits = index.search_matching("quantum walk", &query, &store, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].trace_id, "t1");
+        assert_eq!(hits[0].language, "en");
+    }
+
+    #[test]
+    fn test_reindex_after_update_and_delete() {
+        let store = InMemoryTraceStore::new();
+        store.save(&trace("t1", "en", "quantum walk on hypercubes", 0.9)).unwrap();
+        store.save(&trace("t2", "id", "batik fraktal", 0.7)).unwrap();
+        let index = FullTextIndex::in_memory().unwrap();
+        index.index_store(&store).unwrap();
+
+        // Updated and deleted traces are picked up by the next reindex
+        store.save(&trace("t1", "en", "tensor network contraction", 0.9)).unwrap();
+        store.delete("t2").unwrap();
+        assert_eq!(index.search("batik", 10).unwrap().len(), 1);
+        assert_eq!(index.index_store(&store).unwrap(), 1);
+        assert!(index.search("quantum", 10).unwrap().is_empty());
+        assert!(index.search("batik", 10).unwrap().is_empty());
+        assert_eq!(index.search("tensor", 10).unwrap()[0].trace_id, "t1");
+
+        // A removal is invisible until committed
+        index.remove_trace("t1");
+        assert_eq!(index.search("tensor", 10).unwrap().len(), 1);
+        index.commit().unwrap();
+        assert!(index.search("tensor", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_query_without_matches() {
+        let index = FullTextIndex::in_memory().unwrap();
+        assert!(index.search("quantum", 10).unwrap().is_empty());
+        index.index_trace(&trace("t1", "en", "quantum walk", 0.9)).unwrap();
+        index.commit().unwrap();
+
+        assert!(index.search("topology", 10).unwrap().is_empty());
+        assert!(index.search("+quantum -walk", 10).unwrap().is_empty());
+        assert!(index.search("\"walk quantum\"", 10).unwrap().is_empty());
+        assert!(index.search("quantum", 0).unwrap().is_empty());
+    }
+}
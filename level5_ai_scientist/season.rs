@@ -0,0 +1,253 @@
+// -*- coding: utf-8 -*-
+//! Leaderboard Seasons
+//!
+//! Recurring SerenQA challenges rank contributors per season rather than
+//! over their lifetime. A `LeaderboardSeason` is a named, non-overlapping
+//! date range; traces are bucketed into seasons by the time they were made,
+//! and a contributor's season score is the sum of their season traces'
+//! scores (mean of uniqueness and serendipity). Standings are live until the
+//! season is finalized, which freezes them and awards trophies to the top
+//! places. Trophies are kept on the contributor statistics and rank
+//! contributors across seasons by `LanguageAwareRankingCriteria::Trophies`.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::ContributorStats::{best_first, LanguageAwareContributorStats, LanguageAwareLeaderboard};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Places awarded a trophy when a season is finalized
+pub const TROPHY_PLACES: usize = 3;
+
+/// Contributor's place in a season
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SeasonStanding {
+    /// 1-based rank
+    pub rank: usize,
+    pub contributor_id: String,
+    /// Traces made during the season
+    pub traces: usize,
+    /// Sum of the season traces' scores
+    pub score: f64,
+}
+
+/// Named date range with its own ranking
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LeaderboardSeason {
+    pub name: String,
+    /// First instant of the season
+    pub start: DateTime<Utc>,
+    /// First instant after the season
+    pub end: DateTime<Utc>,
+    /// Standings frozen by `finalize_season` (`None` while the season is open)
+    #[serde(default)]
+    pub final_standings: Option<Vec<SeasonStanding>>,
+}
+
+impl LeaderboardSeason {
+    /// Open season covering [start, end)
+    pub fn new(name: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self { name: name.to_string(), start, end, final_standings: None }
+    }
+
+    /// Whether `at` falls in the season
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.start <= at && at < self.end
+    }
+
+    /// Whether the standings are frozen
+    pub fn is_final(&self) -> bool {
+        self.final_standings.is_some()
+    }
+
+    /// Season traces and score of a contributor
+    fn tally(&self, stats: &LanguageAwareContributorStats) -> (usize, f64) {
+        stats
+            .trace_history
+            .iter()
+            .filter(|trace| self.contains(trace.at))
+            .fold((0, 0.0), |(traces, score), trace| (traces + 1, score + trace.score()))
+    }
+
+    /// Standings of contributors with season traces, best first (ties by ID)
+    fn standings<'a, I>(&self, contributors: I) -> Vec<SeasonStanding>
+    where
+        I: IntoIterator<Item = &'a LanguageAwareContributorStats>,
+    {
+        let mut tallies: Vec<(&str, usize, f64)> = contributors
+            .into_iter()
+            .filter_map(|stats| {
+                let (traces, score) = self.tally(stats);
+                (traces > 0).then_some((stats.contributor_id.as_str(), traces, score))
+            })
+            .collect();
+        tallies.sort_by(|a, b| best_first(a.2, b.2).then_with(|| a.0.cmp(b.0)));
+        tallies
+            .into_iter()
+            .enumerate()
+            .map(|(i, (contributor_id, traces, score))| SeasonStanding {
+                rank: i + 1,
+                contributor_id: contributor_id.to_string(),
+                traces,
+                score,
+            })
+            .collect()
+    }
+}
+
+/// Award for a top place in a finalized season
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SeasonTrophy {
+    pub season: String,
+    /// 1-based final rank
+    pub rank: usize,
+}
+
+impl std::fmt::Display for SeasonTrophy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} #{}", self.season, self.rank)
+    }
+}
+
+impl LanguageAwareContributorStats {
+    /// Trophy labels, in award order
+    pub fn trophy_labels(&self) -> Vec<String> {
+        self.trophies.iter().map(SeasonTrophy::to_string).collect()
+    }
+
+    /// Trophy points over all seasons: `TROPHY_PLACES` for a first place,
+    /// down to 1 for the last trophy place
+    pub fn trophy_points(&self) -> f64 {
+        self.trophies.iter().map(|trophy| (TROPHY_PLACES + 1).saturating_sub(trophy.rank) as f64).sum()
+    }
+}
+
+impl LanguageAwareLeaderboard {
+    /// Add a season; fails if the name is taken, the range is empty, or it
+    /// overlaps another season
+    pub fn add_season(&mut self, season: LeaderboardSeason) -> SerenQaResult<()> {
+        if season.end <= season.start {
+            return Err(SerenQaError::InvalidSeason(format!("season {} ends before it starts", season.name)));
+        }
+        if let Some(other) = self.seasons.iter().find(|s| s.name == season.name) {
+            return Err(SerenQaError::InvalidSeason(format!("season {} already exists", other.name)));
+        }
+        if let Some(other) = self.seasons.iter().find(|s| s.start < season.end && season.start < s.end) {
+            return Err(SerenQaError::InvalidSeason(format!("season {} overlaps {}", season.name, other.name)));
+        }
+        self.seasons.push(season);
+        self.seasons.sort_by_key(|s| s.start);
+        Ok(())
+    }
+
+    /// Seasons, in date order
+    pub fn seasons(&self) -> &[LeaderboardSeason] {
+        &self.seasons
+    }
+
+    /// Season named `name`
+    pub fn season(&self, name: &str) -> Option<&LeaderboardSeason> {
+        self.seasons.iter().find(|s| s.name == name)
+    }
+
+    /// Season a trace made at `at` belongs to
+    pub fn season_of(&self, at: DateTime<Utc>) -> Option<&LeaderboardSeason> {
+        self.seasons.iter().find(|s| s.contains(at))
+    }
+
+    /// Standings of a season: frozen once finalized, otherwise live
+    pub fn season_standings(&self, name: &str) -> SerenQaResult<Vec<SeasonStanding>> {
+        let season = self.season(name).ok_or_else(|| SerenQaError::UnknownSeason(name.to_string()))?;
+        Ok(match &season.final_standings {
+            Some(standings) => standings.clone(),
+            None => season.standings(self.contributors.values()),
+        })
+    }
+
+    /// Freeze a season's standings and award trophies to the top places
+    pub fn finalize_season(&mut self, name: &str) -> SerenQaResult<&[SeasonStanding]> {
+        let index = self.seasons
+            .iter()
+            .position(|s| s.name == name)
+            .ok_or_else(|| SerenQaError::UnknownSeason(name.to_string()))?;
+        if self.seasons[index].is_final() {
+            return Err(SerenQaError::InvalidSeason(format!("season {} is already final", name)));
+        }
+        self.observed(|leaderboard| {
+            let standings = leaderboard.seasons[index].standings(leaderboard.contributors.values());
+            for standing in standings.iter().take(TROPHY_PLACES) {
+                if let Some(stats) = leaderboard.contributors.get_mut(&standing.contributor_id) {
+                    stats.trophies.push(SeasonTrophy { season: name.to_string(), rank: standing.rank });
+                }
+            }
+            leaderboard.seasons[index].final_standings = Some(standings);
+        });
+        Ok(self.seasons[index].final_standings.as_deref().expect("season was just finalized"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, month, day, 12, 0, 0).unwrap()
+    }
+
+    fn leaderboard() -> LanguageAwareLeaderboard {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        for (id, traces) in [
+            ("ayu", vec![(day(1, 10), 0.9), (day(4, 2), 0.2)]),
+            ("budi", vec![(day(1, 12), 0.5), (day(2, 1), 0.5)]),
+            ("citra", vec![(day(4, 5), 0.8)]),
+        ] {
+            let mut stats = LanguageAwareContributorStats::new(id);
+            for (at, score) in traces {
+                stats.add_trace_at(at, 5, score, score, vec!["en".to_string()], 0.8, 0.8).unwrap();
+            }
+            leaderboard.add_contributor(stats);
+        }
+        leaderboard.add_season(LeaderboardSeason::new("Q1", day(1, 1), day(4, 1))).unwrap();
+        leaderboard.add_season(LeaderboardSeason::new("Q2", day(4, 1), day(7, 1))).unwrap();
+        leaderboard
+    }
+
+    #[test]
+    fn test_seasons_bucket_traces_by_date() {
+        let leaderboard = leaderboard();
+        assert_eq!(leaderboard.season_of(day(2, 14)).unwrap().name, "Q1");
+        assert!(leaderboard.season_of(day(8, 1)).is_none());
+
+        let q1 = leaderboard.season_standings("Q1").unwrap();
+        let order: Vec<(&str, usize)> = q1.iter().map(|s| (s.contributor_id.as_str(), s.traces)).collect();
+        assert_eq!(order, vec![("budi", 2), ("ayu", 1)]);
+        assert_eq!(leaderboard.season_standings("Q2").unwrap()[0].contributor_id, "citra");
+
+        let mut leaderboard = leaderboard;
+        let overlapping = LeaderboardSeason::new("Spring", day(3, 1), day(5, 1));
+        assert!(matches!(leaderboard.add_season(overlapping), Err(SerenQaError::InvalidSeason(_))));
+        assert!(matches!(leaderboard.season_standings("Q9"), Err(SerenQaError::UnknownSeason(_))));
+    }
+
+    #[test]
+    fn test_finalized_standings_are_frozen() {
+        let mut leaderboard = leaderboard();
+        let frozen = leaderboard.finalize_season("Q1").unwrap().to_vec();
+        assert_eq!(leaderboard.get("budi").unwrap().trophy_labels(), vec!["Q1 #1"]);
+        assert_eq!(leaderboard.get("ayu").unwrap().trophies[0].rank, 2);
+        assert!(leaderboard.get("citra").unwrap().trophies.is_empty());
+        assert!(leaderboard.finalize_season("Q1").is_err());
+
+        // Late traces dated in a finalized season do not change its standings
+        leaderboard.update_contributors(|stats| {
+            if stats.contributor_id == "ayu" {
+                stats.add_trace_at(day(3, 30), 5, 1.0, 1.0, vec!["en".to_string()], 0.8, 0.8).unwrap();
+            }
+        });
+        assert_eq!(leaderboard.season_standings("Q1").unwrap(), frozen);
+
+        let restored = LanguageAwareLeaderboard::from_json(&leaderboard.to_json().unwrap()).unwrap();
+        assert_eq!(restored.season("Q1").unwrap().final_standings.as_ref(), Some(&frozen));
+        assert_eq!(restored.get("budi").unwrap().trophies, leaderboard.get("budi").unwrap().trophies);
+    }
+}
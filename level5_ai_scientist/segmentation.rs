@@ -0,0 +1,200 @@
+// -*- coding: utf-8 -*-
+//! Segmentation of Traces into Discovery Episodes
+//!
+//! A long trace often holds several mini-discoveries. `segment` splits it
+//! into episodes at serendipity peaks, at stage resets (the research process
+//! starting over at an earlier stage), or at long gaps between events. Each
+//! `TraceSegment` carries its own fold and scores, computed on a sub-trace
+//! that keeps the parent's aggregation strategy and insight policy.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use std::ops::Range;
+use crate::serendipity_trace::{SerendipityTrace, FoldedSerendipityTrace};
+use crate::error::SerenQaResult;
+
+/// Where a trace is split into episodes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentationStrategy {
+    /// End an episode at each local serendipity peak of at least `min_peak`
+    SerendipityPeaks { min_peak: f64 },
+    /// Start an episode whenever an event returns to an earlier stage
+    StageResets,
+    /// Start an episode after a gap between events longer than the duration
+    TimeGaps(Duration),
+}
+
+impl SegmentationStrategy {
+    /// Whether a new episode starts at event `i` (for `i >= 1`)
+    fn starts_episode(&self, trace: &SerendipityTrace, i: usize) -> bool {
+        let events = &trace.events;
+        match self {
+            SegmentationStrategy::SerendipityPeaks { min_peak } => {
+                let peak = events[i - 1].serendipity_score;
+                let rising = i < 2 || events[i - 2].serendipity_score <= peak;
+                peak >= *min_peak && rising && events[i].serendipity_score < peak
+            }
+            SegmentationStrategy::StageResets => {
+                trace.stage_ordinal(&events[i].stage) < trace.stage_ordinal(&events[i - 1].stage)
+            }
+            SegmentationStrategy::TimeGaps(max_gap) => events[i].timestamp - events[i - 1].timestamp > *max_gap,
+        }
+    }
+}
+
+/// One episode of a trace with its own summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceSegment {
+    /// Zero-based episode number
+    pub index: usize,
+    /// Index of the first event in the parent trace
+    pub start: usize,
+    /// Index one past the last event in the parent trace
+    pub end: usize,
+    /// Time of the first event
+    pub started_at: DateTime<Utc>,
+    /// Time of the last event
+    pub ended_at: DateTime<Utc>,
+    /// Overall serendipity of the episode
+    pub overall_serendipity: f64,
+    /// Uniqueness score of the episode
+    pub uniqueness_score: f64,
+    /// Event with the highest serendipity score
+    pub peak_event_id: String,
+    /// Fold of the episode (trace ID `{parent}/episode_{index}`)
+    pub folded: FoldedSerendipityTrace,
+}
+
+impl TraceSegment {
+    /// Number of events in the episode
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Check if the episode has no events
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl SerendipityTrace {
+    /// Trace containing only the events in `range` and the transitions
+    /// between them, with this trace's ID, aggregation, and insight policy
+    pub fn sub_trace(&self, range: Range<usize>) -> SerendipityTrace {
+        let mut sub = SerendipityTrace::new(&self.contributor_id, &self.backend, &self.discovery_name);
+        sub.trace_id = self.trace_id.clone();
+        sub.created_at = self.created_at;
+        sub.aggregation = self.aggregation;
+        sub.insight_policy = self.insight_policy;
+        sub.transitions = self.transitions
+            .iter()
+            .skip(range.start)
+            .take(range.len().saturating_sub(1))
+            .cloned()
+            .collect();
+        sub.events = self.events[range].to_vec();
+        sub.invalidate_provenance();
+        for event in &sub.events {
+            if !sub.languages.contains(&event.language) {
+                sub.languages.push(event.language.clone());
+            }
+        }
+        sub.update_overall_serendipity();
+        sub
+    }
+
+    /// Split the trace into discovery episodes (none for an empty trace)
+    pub fn segment(&self, strategy: SegmentationStrategy) -> SerenQaResult<Vec<TraceSegment>> {
+        let mut boundaries: Vec<usize> = vec![0];
+        boundaries.extend((1..self.events.len()).filter(|&i| strategy.starts_episode(self, i)));
+        boundaries.push(self.events.len());
+
+        boundaries
+            .windows(2)
+            .filter(|pair| pair[0] < pair[1])
+            .enumerate()
+            .map(|(index, pair)| {
+                let mut episode = self.sub_trace(pair[0]..pair[1]);
+                episode.trace_id = format!("{}/episode_{}", self.trace_id, index);
+                let peak = episode.events
+                    .iter()
+                    .max_by(|a, b| a.serendipity_score.total_cmp(&b.serendipity_score))
+                    .expect("episodes are non-empty");
+                Ok(TraceSegment {
+                    index,
+                    start: pair[0],
+                    end: pair[1],
+                    started_at: episode.events[0].timestamp,
+                    ended_at: episode.events[episode.events.len() - 1].timestamp,
+                    overall_serendipity: episode.overall_serendipity,
+                    uniqueness_score: episode.uniqueness_score(),
+                    peak_event_id: peak.event_id.clone(),
+                    folded: episode.fold_memory()?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::clock::MockClock;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+    use SerendipityStage::*;
+
+    /// Two discoveries separated by a return to exploration after a day off
+    fn two_episode_trace() -> SerendipityTrace {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap());
+        let mut trace = SerendipityTrace::with_clock("sari", "backend", "Journavx", clock.clone());
+        let steps = [
+            (Exploration, "en", 0.3),
+            (UnexpectedConnection, "id", 0.9),
+            (Validation, "en", 0.5),
+            (Exploration, "jv", 0.2),
+            (UnexpectedConnection, "jv", 0.95),
+            (Integration, "en", 0.4),
+        ];
+        for (i, (stage, language, score)) in steps.into_iter().enumerate() {
+            clock.advance(if i == 3 { Duration::days(1) } else { Duration::minutes(10) });
+            trace.log_event(stage, SerendipityAgent::Explorer, &format!("in {}", i), &format!("out {}", i), language, score, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_strategies_find_the_same_episodes() {
+        let trace = two_episode_trace();
+        let strategies = [
+            SegmentationStrategy::StageResets,
+            SegmentationStrategy::TimeGaps(Duration::hours(1)),
+        ];
+        for strategy in strategies {
+            let segments = trace.segment(strategy).unwrap();
+            assert_eq!(segments.iter().map(|s| (s.start, s.end)).collect::<Vec<_>>(), vec![(0, 3), (3, 6)]);
+        }
+
+        // Peaks end an episode right after the peak event
+        let peaks = trace.segment(SegmentationStrategy::SerendipityPeaks { min_peak: 0.8 }).unwrap();
+        assert_eq!(peaks.iter().map(|s| s.end).collect::<Vec<_>>(), vec![2, 5, 6]);
+        assert_eq!(trace.segment(SegmentationStrategy::SerendipityPeaks { min_peak: 0.99 }).unwrap().len(), 1);
+        assert!(SerendipityTrace::new("sari", "backend", "Journavx").segment(SegmentationStrategy::StageResets).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_segments_have_their_own_summaries() {
+        let trace = two_episode_trace();
+        let segments = trace.segment(SegmentationStrategy::StageResets).unwrap();
+        let second = &segments[1];
+        assert_eq!(second.len(), 3);
+        assert_eq!(second.peak_event_id, trace.events[4].event_id);
+        assert_eq!(second.folded.trace_id, format!("{}/episode_1", trace.trace_id));
+        assert_eq!(second.folded.total_events, 3);
+        assert_eq!(second.folded.languages, vec!["jv".to_string(), "en".to_string()]);
+        assert_eq!(second.folded.language_transitions, vec!["jv -> en".to_string()]);
+        assert!((second.overall_serendipity - trace.sub_trace(3..6).overall_serendipity).abs() < 1e-12);
+        assert_eq!(second.started_at, trace.events[3].timestamp);
+        assert!(serde_json::to_string(&segments).unwrap().contains("\"peak_event_id\""));
+    }
+}
@@ -8,10 +8,41 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::provenance::TraceSignature;
+use crate::redaction::RedactionTombstone;
+use crate::language_tag::is_valid_bcp47;
+use crate::language_registry::LanguageRegistry;
+use crate::middleware::{MiddlewareChain, TraceMiddleware};
+use crate::aggregation::AggregationStrategy;
+use crate::insight_policy::{InsightPolicy, DEFAULT_KEY_DISCOVERY_THRESHOLD};
+use crate::metrics::MetricRegistry;
+use crate::knowledge_source::{KnowledgeSource, KnowledgeCredit, knowledge_credits};
+use crate::circuit::{CircuitArtifact, EventAttachment};
+use crate::attachment::Attachment;
+use crate::benchmark::BenchmarkResult;
+use crate::dedup::DedupReport;
+use crate::extraction::{discovery_entities, entity_clusters, EntityCluster};
+use crate::anomaly::SerendipityPeak;
+use crate::metadata::MetadataValue;
+use crate::stage_policy::{StagePolicy, PolicyMode};
+use crate::stage_taxonomy::{stage_type_count, StageTaxonomy};
+use crate::subtrace::{FoldedSubtrace, Subtrace};
+use crate::annotation::{TraceAnnotation, AnnotationKind};
+use crate::language_detection::LanguageCheck;
+use crate::redaction_rules::RedactionRules;
+use crate::acl::Visibility;
+use crate::review::TraceReview;
+use crate::outcome::Outcome;
+use crate::clock::TraceClock;
+use crate::ids::TraceIds;
+use crate::incremental_hash::IncrementalProvenance;
+use crate::fold_cache::FoldCache;
+use crate::canonical::{HashVersion, ProvenanceHasher};
+use crate::error::{SerenQaError, SerenQaResult};
 
 /// Serendipity discovery stage in the research process
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SerendipityStage {
     /// Initial exploration phase
     Exploration,
@@ -25,10 +56,65 @@ pub enum SerendipityStage {
     Integration,
     /// Publication/sharing of discovery
     Publication,
+    /// Field-specific phase named by a stage taxonomy (e.g., "LeadOptimization")
+    Custom(String),
+}
+
+impl SerendipityStage {
+    /// Built-in stages in research-process order
+    pub const BUILTIN: [SerendipityStage; STAGE_COUNT] = [
+        SerendipityStage::Exploration,
+        SerendipityStage::UnexpectedConnection,
+        SerendipityStage::HypothesisFormation,
+        SerendipityStage::Validation,
+        SerendipityStage::Integration,
+        SerendipityStage::Publication,
+    ];
+
+    /// Field-specific phase
+    pub fn custom(name: &str) -> Self {
+        SerendipityStage::Custom(name.to_string())
+    }
+
+    /// Built-in stage with this variant name, or else a custom stage
+    pub fn from_name(name: &str) -> Self {
+        Self::BUILTIN
+            .into_iter()
+            .find(|stage| stage.name() == name)
+            .unwrap_or_else(|| Self::custom(name))
+    }
+
+    /// Whether this is a field-specific phase rather than a built-in one
+    pub fn is_custom(&self) -> bool {
+        matches!(self, SerendipityStage::Custom(_))
+    }
+
+    /// Display name: the variant name, or a custom stage's own name
+    pub fn name(&self) -> String {
+        match self {
+            SerendipityStage::Custom(name) => name.clone(),
+            stage => format!("{:?}", stage),
+        }
+    }
+
+    /// Position in the research process, from exploration (0) to publication.
+    /// Custom stages come after the built-in ones; a trace with a stage
+    /// taxonomy orders them by `SerendipityTrace::stage_ordinal`.
+    pub fn ordinal(&self) -> usize {
+        match self {
+            SerendipityStage::Exploration => 0,
+            SerendipityStage::UnexpectedConnection => 1,
+            SerendipityStage::HypothesisFormation => 2,
+            SerendipityStage::Validation => 3,
+            SerendipityStage::Integration => 4,
+            SerendipityStage::Publication => 5,
+            SerendipityStage::Custom(_) => STAGE_COUNT,
+        }
+    }
 }
 
 /// Agent type involved in serendipity discovery
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SerendipityAgent {
     /// Explores diverse information sources
     Explorer,
@@ -44,6 +130,28 @@ pub enum SerendipityAgent {
     Translator,
     /// Meta-level orchestration
     MetaOrchestrator,
+    /// Domain-specific role named by the pipeline (e.g., "Crystallographer")
+    Custom(String),
+}
+
+impl SerendipityAgent {
+    /// Domain-specific role
+    pub fn custom(name: &str) -> Self {
+        SerendipityAgent::Custom(name.to_string())
+    }
+
+    /// Whether this is a domain-specific role rather than a built-in one
+    pub fn is_custom(&self) -> bool {
+        matches!(self, SerendipityAgent::Custom(_))
+    }
+
+    /// Display name: the variant name, or a custom role's own name
+    pub fn name(&self) -> String {
+        match self {
+            SerendipityAgent::Custom(name) => name.clone(),
+            agent => format!("{:?}", agent),
+        }
+    }
 }
 
 /// Serendipity event capturing a discovery moment
@@ -67,8 +175,248 @@ pub struct SerendipityEvent {
     pub serendipity_score: f64,
     /// Confidence in the discovery
     pub confidence: f64,
-    /// Additional metadata
-    pub metadata: HashMap<String, String>,
+    /// Additional typed metadata
+    pub metadata: HashMap<String, MetadataValue>,
+    /// Tombstone if the event text has been redacted
+    #[serde(default)]
+    pub redaction: Option<RedactionTombstone>,
+    /// Cultural and other knowledge sources the event draws on
+    #[serde(default)]
+    pub knowledge_sources: Vec<KnowledgeSource>,
+    /// Typed artifacts (e.g., quantum circuits) backing the event
+    #[serde(default)]
+    pub attachments: Vec<EventAttachment>,
+    /// Structured benchmark outcome (Validation events only)
+    #[serde(default)]
+    pub benchmark: Option<Box<BenchmarkResult>>,
+    /// Contributor who carried out the event, if not the trace's contributor
+    #[serde(default)]
+    pub contributor: Option<String>,
+}
+
+impl SerendipityEvent {
+    /// Hash of the event's original input/output text.
+    /// Redacted events return the hash stored in their tombstone.
+    pub fn content_hash(&self) -> String {
+        if let Some(tombstone) = &self.redaction {
+            return tombstone.content_hash.clone();
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update((self.input.len() as u64).to_le_bytes());
+        hasher.update(self.input.as_bytes());
+        hasher.update((self.output.len() as u64).to_le_bytes());
+        hasher.update(self.output.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Check if the event text has been redacted
+    pub fn is_redacted(&self) -> bool {
+        self.redaction.is_some()
+    }
+
+    /// Check scores are in [0, 1], the language is well-formed BCP-47,
+    /// metadata keys are non-empty, knowledge sources and artifact references
+    /// are well-formed, and any benchmark is well-formed and attached to a Validation event
+    pub fn validate(&self) -> Result<(), EventValidationError> {
+        if !(0.0..=1.0).contains(&self.serendipity_score) {
+            return Err(EventValidationError::SerendipityOutOfRange(self.serendipity_score));
+        }
+        if !(0.0..=1.0).contains(&self.confidence) {
+            return Err(EventValidationError::ConfidenceOutOfRange(self.confidence));
+        }
+        if !is_valid_bcp47(&self.language) {
+            return Err(EventValidationError::InvalidLanguageTag(self.language.clone()));
+        }
+        if self.metadata.keys().any(|k| k.is_empty()) {
+            return Err(EventValidationError::EmptyMetadataKey);
+        }
+        if let Some(problem) = self.knowledge_sources.iter().find_map(|s| s.problem()) {
+            return Err(EventValidationError::InvalidKnowledgeSource(problem));
+        }
+        let artifact_problem = self.attachments.iter().find_map(|attachment| match attachment {
+            EventAttachment::Artifact(artifact) => artifact.problem(),
+            _ => None,
+        });
+        if let Some(problem) = artifact_problem {
+            return Err(EventValidationError::InvalidAttachment(problem));
+        }
+        if let Some(benchmark) = &self.benchmark {
+            if self.stage != SerendipityStage::Validation {
+                return Err(EventValidationError::InvalidBenchmark(format!(
+                    "benchmark {:?} attached to a {} event",
+                    benchmark.metric, self.stage.name()
+                )));
+            }
+            if let Some(problem) = benchmark.problem() {
+                return Err(EventValidationError::InvalidBenchmark(problem));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reason a built event failed validation
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventValidationError {
+    /// Serendipity score outside [0, 1]
+    SerendipityOutOfRange(f64),
+    /// Confidence outside [0, 1]
+    ConfidenceOutOfRange(f64),
+    /// Language is not a well-formed BCP-47 tag
+    InvalidLanguageTag(String),
+    /// Metadata key is empty
+    EmptyMetadataKey,
+    /// Knowledge source has an empty title or invalid language
+    InvalidKnowledgeSource(String),
+    /// Artifact reference has a malformed hash or media type
+    InvalidAttachment(String),
+    /// Benchmark is malformed or not on a Validation event
+    InvalidBenchmark(String),
+}
+
+impl std::fmt::Display for EventValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventValidationError::SerendipityOutOfRange(v) => {
+                write!(f, "serendipity score {} is outside [0, 1]", v)
+            }
+            EventValidationError::ConfidenceOutOfRange(v) => {
+                write!(f, "confidence {} is outside [0, 1]", v)
+            }
+            EventValidationError::InvalidLanguageTag(tag) => {
+                write!(f, "invalid BCP-47 language tag: {:?}", tag)
+            }
+            EventValidationError::EmptyMetadataKey => write!(f, "metadata key is empty"),
+            EventValidationError::InvalidKnowledgeSource(problem)
+            | EventValidationError::InvalidAttachment(problem)
+            | EventValidationError::InvalidBenchmark(problem) => write!(f, "{}", problem),
+        }
+    }
+}
+
+impl std::error::Error for EventValidationError {}
+
+/// Builder for validated serendipity events
+pub struct SerendipityEventBuilder {
+    stage: SerendipityStage,
+    agent: SerendipityAgent,
+    input: String,
+    output: String,
+    language: String,
+    serendipity_score: f64,
+    confidence: f64,
+    metadata: HashMap<String, MetadataValue>,
+    knowledge_sources: Vec<KnowledgeSource>,
+    attachments: Vec<EventAttachment>,
+    benchmark: Option<Box<BenchmarkResult>>,
+    contributor: Option<String>,
+    pub(crate) timestamp: Option<DateTime<Utc>>,
+}
+
+impl SerendipityEventBuilder {
+    /// Create a new builder
+    pub fn new(
+        stage: SerendipityStage,
+        agent: SerendipityAgent,
+        input: &str,
+        output: &str,
+        language: &str,
+    ) -> Self {
+        Self {
+            stage,
+            agent,
+            input: input.to_string(),
+            output: output.to_string(),
+            language: language.to_string(),
+            serendipity_score: 0.0,
+            confidence: 0.0,
+            metadata: HashMap::new(),
+            knowledge_sources: Vec::new(),
+            attachments: Vec::new(),
+            benchmark: None,
+            contributor: None,
+            timestamp: None,
+        }
+    }
+
+    /// Set serendipity score
+    pub fn serendipity(mut self, score: f64) -> Self {
+        self.serendipity_score = score;
+        self
+    }
+
+    /// Set confidence
+    pub fn confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Add metadata entry
+    pub fn metadata(mut self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        self.metadata.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Cite a knowledge source
+    pub fn knowledge_source(mut self, source: KnowledgeSource) -> Self {
+        self.knowledge_sources.push(source);
+        self
+    }
+
+    /// Attach a validated quantum circuit
+    pub fn circuit(mut self, circuit: CircuitArtifact) -> Self {
+        self.attachments.push(EventAttachment::Circuit(circuit));
+        self
+    }
+
+    /// Reference an external artifact by content hash
+    pub fn attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(EventAttachment::Artifact(attachment));
+        self
+    }
+
+    /// Record a structured benchmark result (Validation events only)
+    pub fn benchmark(mut self, benchmark: BenchmarkResult) -> Self {
+        self.benchmark = Some(Box::new(benchmark));
+        self
+    }
+
+    /// Attribute the event to a contributor other than the trace's
+    pub fn contributor(mut self, contributor_id: &str) -> Self {
+        self.contributor = Some(contributor_id.to_string());
+        self
+    }
+
+    /// Time the event happened (the trace's clock, or now, if not set)
+    pub fn timestamp(mut self, at: DateTime<Utc>) -> Self {
+        self.timestamp = Some(at);
+        self
+    }
+
+    /// Validate and build the event.
+    /// The event ID is assigned when the event is logged into a trace.
+    pub fn build(self) -> Result<SerendipityEvent, EventValidationError> {
+        let event = SerendipityEvent {
+            event_id: String::new(),
+            timestamp: self.timestamp.unwrap_or_else(Utc::now),
+            stage: self.stage,
+            agent: self.agent,
+            input: self.input,
+            output: self.output,
+            language: LanguageRegistry::global().canonical(&self.language),
+            serendipity_score: self.serendipity_score,
+            confidence: self.confidence,
+            metadata: self.metadata,
+            redaction: None,
+            knowledge_sources: self.knowledge_sources,
+            attachments: self.attachments,
+            benchmark: self.benchmark,
+            contributor: self.contributor,
+        };
+        event.validate()?;
+        Ok(event)
+    }
 }
 
 /// Transition between serendipity events
@@ -90,9 +438,103 @@ pub struct SerendipityTransition {
     pub language_shift: Option<(String, String)>,
 }
 
+impl SerendipityTransition {
+    /// Build the transition from `prev` to `next`
+    pub fn between(prev: &SerendipityEvent, next: &SerendipityEvent) -> Self {
+        let language_shift = if prev.language != next.language {
+            Some((prev.language.clone(), next.language.clone()))
+        } else {
+            None
+        };
+
+        Self {
+            from_event: prev.event_id.clone(),
+            to_event: next.event_id.clone(),
+            from_agent: prev.agent.clone(),
+            to_agent: next.agent.clone(),
+            transition_score: (prev.confidence + next.confidence) / 2.0,
+            reason: format!("{} -> {}", prev.stage.name(), next.stage.name()),
+            language_shift,
+        }
+    }
+}
+
+/// Checks and rewrites every new event goes through, shared by in-memory
+/// and streaming traces
+pub(crate) struct EventChecks<'a> {
+    pub(crate) stage_taxonomy: Option<&'a StageTaxonomy>,
+    pub(crate) stage_policy: Option<&'a StagePolicy>,
+    pub(crate) redaction_rules: Option<&'a RedactionRules>,
+    pub(crate) language_check: Option<&'a LanguageCheck>,
+}
+
+impl EventChecks<'_> {
+    /// Canonicalize the event's language, validate it, and check its stage
+    /// against the taxonomy and the policy; `logged` tells whether a stage
+    /// was logged before and `last_stage` is the previous event's stage.
+    /// Redaction rules and the language check then run on a valid event.
+    /// Returns the notes to attach to the trace once the event has its ID.
+    pub(crate) fn apply(
+        &self,
+        event: &mut SerendipityEvent,
+        logged: impl Fn(&SerendipityStage) -> bool,
+        last_stage: Option<&SerendipityStage>,
+    ) -> SerenQaResult<Vec<AnnotationKind>> {
+        event.language = LanguageRegistry::global().canonical(&event.language);
+        event.validate()?;
+        if self.stage_taxonomy.is_some_and(|taxonomy| !taxonomy.contains(&event.stage)) {
+            return Err(SerenQaError::UnknownStage(event.stage.name()));
+        }
+        let violation = self.stage_policy
+            .and_then(|policy| policy.check_stages(logged, last_stage, &event.stage).map(|v| (policy.mode, v)));
+        if let Some((PolicyMode::Strict, violation)) = violation {
+            return Err(SerenQaError::StagePolicy(violation));
+        }
+
+        let mut notes = Vec::new();
+        if let Some((_, violation)) = violation {
+            notes.push(AnnotationKind::StagePolicy(violation));
+        }
+        if let Some(redaction) = self.redaction_rules.and_then(|rules| rules.apply(event)) {
+            notes.push(AnnotationKind::Redacted(redaction));
+        }
+        if let Some(mismatch) = self.language_check.and_then(|check| check.apply(event)) {
+            notes.push(AnnotationKind::LanguageMismatch(mismatch));
+        }
+        Ok(notes)
+    }
+}
+
+/// Key-discovery summary for an event scoring above `threshold`
+pub(crate) fn key_discovery(event: &SerendipityEvent, threshold: f64) -> Option<String> {
+    if event.serendipity_score > threshold {
+        Some(format!("{}: {}", event.stage.name(), event.output))
+    } else {
+        None
+    }
+}
+
+/// Label for a transition that shifts language
+pub(crate) fn language_transition_label(transition: &SerendipityTransition) -> Option<String> {
+    transition.language_shift
+        .as_ref()
+        .map(|(from, to)| format!("{} -> {}", from, to))
+}
+
+/// Current trace schema version.
+/// Traces without a version are treated as version 1.
+pub const TRACE_SCHEMA_VERSION: u32 = 2;
+
+pub(crate) fn legacy_trace_schema_version() -> u32 {
+    1
+}
+
 /// Complete serendipity trace for a discovery
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerendipityTrace {
+    /// Layout version the trace was written with (see `migrations`)
+    #[serde(default = "legacy_trace_schema_version")]
+    pub schema_version: u32,
     /// Unique trace identifier
     pub trace_id: String,
     /// Contributor who made the discovery
@@ -109,8 +551,74 @@ pub struct SerendipityTrace {
     pub languages: Vec<String>,
     /// Overall serendipity score
     pub overall_serendipity: f64,
+    /// Strategy combining event scores into the overall score
+    #[serde(default)]
+    pub aggregation: AggregationStrategy,
+    /// Policy choosing the key-discovery threshold
+    #[serde(default)]
+    pub insight_policy: InsightPolicy,
     /// Timestamp of trace creation
     pub created_at: DateTime<Utc>,
+    /// Contributor signature over the provenance hash
+    #[serde(default)]
+    pub signature: Option<TraceSignature>,
+    /// Interceptors run around every logged event (not serialized)
+    #[serde(skip)]
+    pub middleware: MiddlewareChain,
+    /// Custom metric values computed on finalization
+    #[serde(default)]
+    pub custom_metrics: BTreeMap<String, f64>,
+    /// Custom metric callbacks (not serialized)
+    #[serde(skip)]
+    pub metric_registry: MetricRegistry,
+    /// Retries merged into each event by idempotent logging (event ID -> count)
+    #[serde(default)]
+    pub merged_retries: BTreeMap<String, usize>,
+    /// Stage-order policy checked on every logged event
+    #[serde(default)]
+    pub stage_policy: Option<StagePolicy>,
+    /// Field-specific stages events are restricted to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stage_taxonomy: Option<StageTaxonomy>,
+    /// Audit notes such as lenient stage-policy violations and language mismatches
+    #[serde(default)]
+    pub annotations: Vec<TraceAnnotation>,
+    /// Language detection run on every logged event (not serialized)
+    #[serde(skip)]
+    pub language_check: Option<LanguageCheck>,
+    /// Redaction rules run on every logged event (not serialized)
+    #[serde(skip)]
+    pub redaction_rules: Option<RedactionRules>,
+    /// Contributors sharing credit with `contributor_id`
+    #[serde(default)]
+    pub co_contributors: Vec<String>,
+    /// Who besides the owners may read the trace
+    #[serde(default, skip_serializing_if = "Visibility::is_private")]
+    pub visibility: Visibility,
+    /// Review submission and reviewer verdicts
+    #[serde(default)]
+    pub review: TraceReview,
+    /// What happened to the discovery afterwards, oldest first (not hashed)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outcomes: Vec<Outcome>,
+    /// Provenance hash of the contributor's previous trace in a hash chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_trace_hash: Option<String>,
+    /// Child traces for work delegated from this trace's events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtraces: Vec<Subtrace>,
+    /// Clock for event timestamps (not serialized)
+    #[serde(skip)]
+    pub clock: TraceClock,
+    /// Generator for event IDs (not serialized)
+    #[serde(skip)]
+    pub ids: TraceIds,
+    /// Provenance hasher fed while logging (not serialized)
+    #[serde(skip)]
+    pub(crate) incremental_provenance: IncrementalProvenance,
+    /// Last memory fold, extended as events are logged (not serialized)
+    #[serde(skip)]
+    pub(crate) fold_cache: FoldCache,
 }
 
 impl SerendipityTrace {
@@ -120,8 +628,11 @@ impl SerendipityTrace {
         backend: &str,
         discovery_name: &str,
     ) -> Self {
+        let ids = TraceIds::default();
+        let created_at = Utc::now();
         Self {
-            trace_id: format!("seren_{}_{}", contributor_id, Utc::now().timestamp()),
+            schema_version: TRACE_SCHEMA_VERSION,
+            trace_id: ids.trace_id(contributor_id, created_at),
             contributor_id: contributor_id.to_string(),
             backend: backend.to_string(),
             discovery_name: discovery_name.to_string(),
@@ -129,11 +640,55 @@ impl SerendipityTrace {
             transitions: Vec::new(),
             languages: Vec::new(),
             overall_serendipity: 0.0,
-            created_at: Utc::now(),
+            aggregation: AggregationStrategy::default(),
+            insight_policy: InsightPolicy::default(),
+            created_at,
+            signature: None,
+            middleware: MiddlewareChain::default(),
+            custom_metrics: BTreeMap::new(),
+            metric_registry: MetricRegistry::default(),
+            merged_retries: BTreeMap::new(),
+            stage_policy: None,
+            stage_taxonomy: None,
+            annotations: Vec::new(),
+            language_check: None,
+            redaction_rules: None,
+            co_contributors: Vec::new(),
+            visibility: Visibility::Private,
+            review: TraceReview::default(),
+            outcomes: Vec::new(),
+            prev_trace_hash: None,
+            subtraces: Vec::new(),
+            clock: TraceClock::default(),
+            ids,
+            incremental_provenance: IncrementalProvenance::default(),
+            fold_cache: FoldCache::default(),
         }
     }
 
-    /// Log a serendipity event
+    /// Change the aggregation strategy and recompute the overall score
+    pub fn set_aggregation(&mut self, strategy: AggregationStrategy) {
+        self.aggregation = strategy;
+        self.update_overall_serendipity();
+    }
+
+    /// Change the policy choosing the key-discovery threshold
+    pub fn set_insight_policy(&mut self, policy: InsightPolicy) {
+        self.insight_policy = policy;
+    }
+
+    /// Key-discovery threshold the insight policy picks for this trace
+    pub fn key_discovery_threshold(&self) -> f64 {
+        self.insight_policy.threshold(&self.events)
+    }
+
+    /// Register an interceptor run before and after every logged event
+    pub fn add_middleware<M: TraceMiddleware + 'static>(&mut self, middleware: M) {
+        self.middleware.push(middleware);
+    }
+
+    /// Log a serendipity event.
+    /// Fails if middleware vetoes the event or the (intercepted) event is invalid.
     pub fn log_event(
         &mut self,
         stage: SerendipityStage,
@@ -143,37 +698,10 @@ impl SerendipityTrace {
         language: &str,
         serendipity_score: f64,
         confidence: f64,
-    ) {
-        let event_id = format!("event_{}_{}", self.events.len(), Utc::now().timestamp_millis());
-        
-        // Track language if new
-        if !self.languages.contains(&language.to_string()) {
-            self.languages.push(language.to_string());
-        }
-
-        // Detect transition from previous event
-        if let Some(prev_event) = self.events.last() {
-            let language_shift = if prev_event.language != language {
-                Some((prev_event.language.clone(), language.to_string()))
-            } else {
-                None
-            };
-
-            let transition = SerendipityTransition {
-                from_event: prev_event.event_id.clone(),
-                to_event: event_id.clone(),
-                from_agent: prev_event.agent.clone(),
-                to_agent: agent.clone(),
-                transition_score: (prev_event.confidence + confidence) / 2.0,
-                reason: format!("{:?} -> {:?}", prev_event.stage, stage),
-                language_shift,
-            };
-            self.transitions.push(transition);
-        }
-
+    ) -> SerenQaResult<&SerendipityEvent> {
         let event = SerendipityEvent {
-            event_id: event_id.clone(),
-            timestamp: Utc::now(),
+            event_id: String::new(),
+            timestamp: self.clock.now(),
             stage,
             agent,
             input: input.to_string(),
@@ -182,85 +710,184 @@ impl SerendipityTrace {
             serendipity_score,
             confidence,
             metadata: HashMap::new(),
+            redaction: None,
+            knowledge_sources: Vec::new(),
+            attachments: Vec::new(),
+            benchmark: None,
+            contributor: None,
         };
+        self.push_event(event)
+    }
 
+    /// Validate and log an event built with `SerendipityEventBuilder`
+    pub fn log(&mut self, mut builder: SerendipityEventBuilder) -> SerenQaResult<&SerendipityEvent> {
+        builder.timestamp.get_or_insert_with(|| self.clock.now());
+        let event = builder.build()?;
+        self.push_event(event)
+    }
+
+    /// Checks configured for events logged from now on
+    pub(crate) fn event_checks(&self) -> EventChecks<'_> {
+        EventChecks {
+            stage_taxonomy: self.stage_taxonomy.as_ref(),
+            stage_policy: self.stage_policy.as_ref(),
+            redaction_rules: self.redaction_rules.as_ref(),
+            language_check: self.language_check.as_ref(),
+        }
+    }
+
+    /// Run middleware, validate, assign an ID to an event and append it with its incoming transition
+    pub(crate) fn push_event(&mut self, event: SerendipityEvent) -> SerenQaResult<&SerendipityEvent> {
+        let (event, notes) = self.check_event(event)?;
+        self.append_checked(event, notes)
+    }
+
+    /// Run middleware and the event checks on a new event, leaving its ID unset
+    pub(crate) fn check_event(&self, mut event: SerendipityEvent) -> SerenQaResult<(SerendipityEvent, Vec<AnnotationKind>)> {
+        self.middleware
+            .run_before(&mut event, self)
+            .map_err(|(middleware, reason)| SerenQaError::Vetoed { middleware, reason })?;
+        let history = &self.events;
+        let notes = self.event_checks().apply(
+            &mut event,
+            |stage| history.iter().any(|e| &e.stage == stage),
+            history.last().map(|e| &e.stage),
+        )?;
+        Ok((event, notes))
+    }
+
+    /// Assign an ID to an event from `check_event` and append it with its notes
+    /// and incoming transition
+    pub(crate) fn append_checked(&mut self, mut event: SerendipityEvent, notes: Vec<AnnotationKind>) -> SerenQaResult<&SerendipityEvent> {
+        event.event_id = self.ids.event_id(self.events.len(), event.timestamp);
+        self.annotations.extend(notes.into_iter().map(|kind| TraceAnnotation { event_id: event.event_id.clone(), kind }));
+
+        // Track language if new
+        if !self.languages.contains(&event.language) {
+            self.languages.push(event.language.clone());
+        }
+
+        // Detect transition from previous event
+        if let Some(prev_event) = self.events.last() {
+            self.transitions.push(SerendipityTransition::between(prev_event, &event));
+        }
+
+        #[cfg(feature = "metrics")]
+        crate::prometheus::RecorderMetrics::global().record_event(event.serendipity_score);
         self.events.push(event);
         self.update_overall_serendipity();
-    }
+        self.advance_provenance();
 
-    /// Update overall serendipity score
-    fn update_overall_serendipity(&mut self) {
-        if self.events.is_empty() {
-            self.overall_serendipity = 0.0;
-            return;
+        if !self.middleware.is_empty() {
+            let chain = self.middleware.clone();
+            chain.run_after(self.events.last().unwrap(), self);
         }
+        Ok(self.events.last().unwrap())
+    }
 
-        let sum: f64 = self.events.iter().map(|e| e.serendipity_score).sum();
-        self.overall_serendipity = sum / self.events.len() as f64;
+    /// Update overall serendipity score
+    pub(crate) fn update_overall_serendipity(&mut self) {
+        self.overall_serendipity = self.aggregation.aggregate(&self.events);
     }
 
-    /// Compute provenance hash for reproducibility
+    /// Compute provenance hash for reproducibility, from the running hasher
+    /// while it still covers the trace and by rehashing in full otherwise
     pub fn compute_provenance_hash(&self) -> String {
-        let mut hasher = Sha256::new();
+        self.running_provenance_hash()
+            .unwrap_or_else(|| self.compute_provenance_hash_with(HashVersion::CURRENT))
+    }
+
+    /// Compute provenance hash in a specific encoding
+    pub fn compute_provenance_hash_with(&self, version: HashVersion) -> String {
+        let mut hasher = ProvenanceHasher::new(version);
         
         // Hash trace metadata
-        hasher.update(self.trace_id.as_bytes());
-        hasher.update(self.contributor_id.as_bytes());
-        hasher.update(self.backend.as_bytes());
-        hasher.update(self.discovery_name.as_bytes());
+        hasher.header(&self.trace_id, &self.contributor_id, &self.backend, &self.discovery_name);
         
-        // Hash all events
-        for event in &self.events {
-            hasher.update(event.event_id.as_bytes());
-            hasher.update(event.input.as_bytes());
-            hasher.update(event.output.as_bytes());
-            hasher.update(event.language.as_bytes());
-            hasher.update(format!("{}", event.serendipity_score).as_bytes());
+        // The chain link is only hashed when present, keeping older hashes stable
+        if let Some(prev_trace_hash) = &self.prev_trace_hash {
+            hasher.chain_link(prev_trace_hash);
         }
         
-        // Hash all transitions
-        for transition in &self.transitions {
-            hasher.update(transition.from_event.as_bytes());
-            hasher.update(transition.to_event.as_bytes());
-            hasher.update(format!("{}", transition.transition_score).as_bytes());
+        // Hash events in log order, each preceded by its incoming transition,
+        // so the hash can also be maintained incrementally while logging
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                if let Some(transition) = self.transitions.get(i - 1) {
+                    hasher.transition(transition);
+                }
+            }
+            hasher.event(event);
         }
         
-        format!("{:x}", hasher.finalize())
+        // Hash any transitions not paired with an event
+        for transition in self.transitions.iter().skip(self.events.len().saturating_sub(1)) {
+            hasher.transition(transition);
+        }
+        
+        hasher.co_contributors(&self.co_contributors);
+        
+        // Review verdicts are only hashed when present, keeping older hashes stable
+        for verdict in &self.review.verdicts {
+            hasher.verdict(verdict);
+        }
+        
+        // Child hashes are only hashed when present, keeping older hashes stable
+        for subtrace in &self.subtraces {
+            hasher.subtrace(&subtrace.parent_event_id, &subtrace.trace.compute_provenance_hash_with(version));
+        }
+        
+        hasher.finish()
+    }
+
+    /// Check a provenance hash against the trace, recomputing it in the
+    /// encoding the hash was made with
+    pub fn verify_provenance_hash(&self, hash: &str) -> bool {
+        self.compute_provenance_hash_with(HashVersion::of(hash)) == hash
     }
 
-    /// Fold memory trace for leaderboard integration
-    pub fn fold_memory(&self) -> FoldedSerendipityTrace {
-        let key_discoveries: Vec<String> = self.events
+    /// Fold memory trace for leaderboard integration.
+    /// Fails on an empty trace, which has no compression ratio.
+    pub fn fold_memory(&self) -> SerenQaResult<FoldedSerendipityTrace> {
+        if self.events.is_empty() {
+            return Err(SerenQaError::EmptyTrace(self.trace_id.clone()));
+        }
+        #[cfg(feature = "metrics")]
+        crate::prometheus::RecorderMetrics::global().record_fold();
+
+        let key_discovery_threshold = self.key_discovery_threshold();
+        let (key_discoveries, discovery_entities): (Vec<String>, Vec<Vec<String>>) = self.events
             .iter()
-            .filter(|e| e.serendipity_score > 0.7)
-            .map(|e| format!("{:?}: {}", e.stage, e.output))
-            .collect();
+            .filter_map(|e| key_discovery(e, key_discovery_threshold).map(|d| (d, discovery_entities(e))))
+            .unzip();
 
         let language_transitions: Vec<String> = self.transitions
             .iter()
-            .filter_map(|t| {
-                t.language_shift.as_ref().map(|(from, to)| {
-                    format!("{} -> {}", from, to)
-                })
-            })
+            .filter_map(language_transition_label)
             .collect();
 
-        let compression_ratio = if self.events.is_empty() {
-            0.0
-        } else {
-            (key_discoveries.len() as f64) / (self.events.len() as f64)
-        };
+        let compression_ratio = (key_discoveries.len() as f64) / (self.events.len() as f64);
+        let redacted_events = self.redacted_event_count();
 
-        FoldedSerendipityTrace {
+        Ok(FoldedSerendipityTrace {
             trace_id: self.trace_id.clone(),
             discovery_name: self.discovery_name.clone(),
             total_events: self.events.len(),
             key_discoveries,
+            key_discovery_threshold,
             language_transitions,
             overall_serendipity: self.overall_serendipity,
             compression_ratio,
             languages: self.languages.clone(),
-        }
+            partially_redacted: redacted_events > 0,
+            redacted_events,
+            uniqueness: self.uniqueness_breakdown(),
+            knowledge_sources: knowledge_credits(&self.events),
+            dedup: self.dedup_report(),
+            entity_clusters: entity_clusters(&discovery_entities),
+            serendipity_peaks: self.serendipity_peaks(),
+            subtraces: self.fold_subtraces(),
+        })
     }
 
     /// Get trace depth (number of events)
@@ -270,50 +897,173 @@ impl SerendipityTrace {
 
     /// Get uniqueness score based on diversity
     pub fn uniqueness_score(&self) -> f64 {
-        let agent_diversity = self.agent_diversity();
-        let language_diversity = self.language_diversity();
-        let stage_diversity = self.stage_diversity();
+        self.uniqueness_breakdown().score
+    }
+
+    /// Get uniqueness score with its agent/language/stage components
+    pub fn uniqueness_breakdown(&self) -> UniquenessBreakdown {
+        let unique_agents: HashSet<&SerendipityAgent> =
+            self.events.iter().map(|e| &e.agent).collect();
+        let unique_stages: HashSet<&SerendipityStage> =
+            self.events.iter().map(|e| &e.stage).collect();
         
-        // Weighted combination
-        0.4 * agent_diversity + 0.3 * language_diversity + 0.3 * stage_diversity
+        UniquenessBreakdown::with_type_counts(
+            unique_agents.len(),
+            AGENT_TYPE_COUNT + unique_agents.iter().filter(|agent| agent.is_custom()).count(),
+            self.languages.len(),
+            unique_stages.len(),
+            stage_type_count(self.stage_taxonomy.as_ref(), unique_stages.iter().copied()),
+        )
+    }
+
+    /// Export to JSON
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
     }
+}
 
-    /// Calculate agent diversity
-    fn agent_diversity(&self) -> f64 {
-        let unique_agents: std::collections::HashSet<_> = 
-            self.events.iter().map(|e| format!("{:?}", e.agent)).collect();
-        (unique_agents.len() as f64) / 7.0 // 7 possible agent types
+/// Number of built-in agent types (custom roles add to it per trace)
+pub const AGENT_TYPE_COUNT: usize = 7;
+/// Number of built-in discovery stages (custom stages or a stage taxonomy
+/// replace it per trace)
+pub const STAGE_COUNT: usize = 6;
+/// Language count at which language diversity saturates
+pub const LANGUAGE_DIVERSITY_CAP: usize = 5;
+
+/// Weight of agent diversity in the uniqueness score
+pub const AGENT_DIVERSITY_WEIGHT: f64 = 0.4;
+/// Weight of language diversity in the uniqueness score
+pub const LANGUAGE_DIVERSITY_WEIGHT: f64 = 0.3;
+/// Weight of stage diversity in the uniqueness score
+pub const STAGE_DIVERSITY_WEIGHT: f64 = 0.3;
+
+/// Single diversity component of the uniqueness score
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DiversityComponent {
+    /// Distinct values observed in the trace
+    pub observed: usize,
+    /// Denominator the observed count is divided by
+    pub denominator: usize,
+    /// Diversity (observed / denominator, capped at 1.0)
+    pub diversity: f64,
+    /// Weight in the uniqueness score
+    pub weight: f64,
+    /// Weighted contribution to the uniqueness score
+    pub contribution: f64,
+}
+
+impl DiversityComponent {
+    fn new(observed: usize, denominator: usize, weight: f64) -> Self {
+        let diversity = (observed.min(denominator) as f64) / denominator as f64;
+        Self {
+            observed,
+            denominator,
+            diversity,
+            weight,
+            contribution: weight * diversity,
+        }
     }
+}
+
+/// Uniqueness score broken down into its components
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UniquenessBreakdown {
+    /// Agent diversity component
+    pub agent: DiversityComponent,
+    /// Language diversity component
+    pub language: DiversityComponent,
+    /// Stage diversity component
+    pub stage: DiversityComponent,
+    /// Combined uniqueness score
+    pub score: f64,
+    /// Human-readable explanation of each component
+    pub explanations: Vec<String>,
+}
 
-    /// Calculate language diversity
-    fn language_diversity(&self) -> f64 {
-        (self.languages.len() as f64).min(5.0) / 5.0 // Cap at 5 languages
+impl UniquenessBreakdown {
+    /// Build the breakdown from distinct agent, language, and stage counts
+    pub fn from_counts(unique_agents: usize, languages: usize, unique_stages: usize) -> Self {
+        Self::with_custom_agents(unique_agents, 0, languages, unique_stages)
     }
 
-    /// Calculate stage diversity
-    fn stage_diversity(&self) -> f64 {
-        let unique_stages: std::collections::HashSet<_> = 
-            self.events.iter().map(|e| format!("{:?}", e.stage)).collect();
-        (unique_stages.len() as f64) / 6.0 // 6 possible stages
+    /// Build the breakdown when `custom_agents` of the distinct agents are
+    /// custom roles. Each custom role widens the set of agent types, so using
+    /// one never lowers agent diversity.
+    pub fn with_custom_agents(unique_agents: usize, custom_agents: usize, languages: usize, unique_stages: usize) -> Self {
+        Self::with_type_counts(unique_agents, AGENT_TYPE_COUNT + custom_agents, languages, unique_stages, STAGE_COUNT)
     }
 
-    /// Export to JSON
-    pub fn to_json(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string_pretty(self)
+    /// Build the breakdown against `agent_types` agent types and
+    /// `stage_types` stages (e.g., the size of the trace's stage taxonomy)
+    pub fn with_type_counts(
+        unique_agents: usize,
+        agent_types: usize,
+        languages: usize,
+        unique_stages: usize,
+        stage_types: usize,
+    ) -> Self {
+        let agent = DiversityComponent::new(unique_agents, agent_types, AGENT_DIVERSITY_WEIGHT);
+        let language = DiversityComponent::new(languages, LANGUAGE_DIVERSITY_CAP, LANGUAGE_DIVERSITY_WEIGHT);
+        let stage = DiversityComponent::new(unique_stages, stage_types, STAGE_DIVERSITY_WEIGHT);
+        
+        // Weighted combination
+        let score = agent.contribution + language.contribution + stage.contribution;
+        
+        let explanations = vec![
+            format!(
+                "Agent diversity: {} of {} agent types used ({:.3} x {:.1} = {:.3})",
+                agent.observed, agent.denominator, agent.diversity, agent.weight, agent.contribution
+            ),
+            format!(
+                "Language diversity: {} languages, capped at {} ({:.3} x {:.1} = {:.3})",
+                language.observed, language.denominator, language.diversity, language.weight, language.contribution
+            ),
+            format!(
+                "Stage diversity: {} of {} stages reached ({:.3} x {:.1} = {:.3})",
+                stage.observed, stage.denominator, stage.diversity, stage.weight, stage.contribution
+            ),
+        ];
+        
+        Self { agent, language, stage, score, explanations }
     }
 }
 
+fn default_key_discovery_threshold() -> f64 {
+    DEFAULT_KEY_DISCOVERY_THRESHOLD
+}
+
 /// Folded/compressed serendipity trace
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FoldedSerendipityTrace {
     pub trace_id: String,
     pub discovery_name: String,
     pub total_events: usize,
     pub key_discoveries: Vec<String>,
+    #[serde(default = "default_key_discovery_threshold")]
+    pub key_discovery_threshold: f64,
     pub language_transitions: Vec<String>,
     pub overall_serendipity: f64,
     pub compression_ratio: f64,
     pub languages: Vec<String>,
+    #[serde(default)]
+    pub partially_redacted: bool,
+    #[serde(default)]
+    pub redacted_events: usize,
+    #[serde(default)]
+    pub uniqueness: UniquenessBreakdown,
+    #[serde(default)]
+    pub knowledge_sources: Vec<KnowledgeCredit>,
+    #[serde(default)]
+    pub dedup: DedupReport,
+    /// Key discoveries sharing named entities
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entity_clusters: Vec<EntityCluster>,
+    /// Events whose rise in serendipity is anomalous for the trace
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub serendipity_peaks: Vec<SerendipityPeak>,
+    /// Folds of child traces, nested under their parent events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtraces: Vec<FoldedSubtrace>,
 }
 
 #[cfg(test)]
@@ -339,7 +1089,7 @@ mod tests {
             "en",
             0.85,
             0.9,
-        );
+        ).unwrap();
         assert_eq!(trace.events.len(), 1);
         assert_eq!(trace.languages.len(), 1);
     }
@@ -355,9 +1105,10 @@ mod tests {
             "en",
             0.8,
             0.9,
-        );
+        ).unwrap();
         let hash = trace.compute_provenance_hash();
-        assert_eq!(hash.len(), 64); // SHA-256 produces 64 hex characters
+        assert!(hash.starts_with("v1:"));
+        assert_eq!(hash.len(), 67); // Version prefix and 64 hex characters of SHA-256
     }
 
     #[test]
@@ -371,7 +1122,7 @@ mod tests {
             "en",
             0.9,
             0.85,
-        );
+        ).unwrap();
         trace.log_event(
             SerendipityStage::UnexpectedConnection,
             SerendipityAgent::PatternRecognizer,
@@ -380,9 +1131,9 @@ mod tests {
             "id",
             0.95,
             0.9,
-        );
+        ).unwrap();
         
-        let folded = trace.fold_memory();
+        let folded = trace.fold_memory().unwrap();
         assert_eq!(folded.total_events, 2);
         assert!(folded.compression_ratio > 0.0);
     }
@@ -398,8 +1149,131 @@ mod tests {
             "en",
             0.8,
             0.9,
-        );
+        ).unwrap();
         let score = trace.uniqueness_score();
         assert!(score >= 0.0 && score <= 1.0);
     }
+
+    #[test]
+    fn test_event_builder() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        let event = trace.log(
+            SerendipityEventBuilder::new(
+                SerendipityStage::UnexpectedConnection,
+                SerendipityAgent::PatternRecognizer,
+                "input",
+                "output",
+                "id",
+            )
+            .serendipity(0.92)
+            .confidence(0.85)
+            .metadata("source", "field_notes"),
+        ).unwrap();
+        assert_eq!(event.metadata.get("source"), Some(&MetadataValue::from("field_notes")));
+        assert!(!event.event_id.is_empty());
+        assert_eq!(trace.languages, vec!["id".to_string()]);
+        
+        let builder = |language: &str| SerendipityEventBuilder::new(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input",
+            "output",
+            language,
+        );
+        assert_eq!(
+            builder("en").serendipity(1.5).build().unwrap_err(),
+            EventValidationError::SerendipityOutOfRange(1.5)
+        );
+        assert_eq!(
+            builder("en").confidence(-0.1).build().unwrap_err(),
+            EventValidationError::ConfidenceOutOfRange(-0.1)
+        );
+        assert!(matches!(
+            builder("english!").build(),
+            Err(EventValidationError::InvalidLanguageTag(_))
+        ));
+        assert!(trace.log(builder("en").metadata("", "value")).is_err());
+        assert_eq!(trace.events.len(), 1);
+    }
+
+    #[test]
+    fn test_structured_errors() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        assert!(matches!(trace.fold_memory(), Err(SerenQaError::EmptyTrace(_))));
+
+        let result = trace.log_event(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input",
+            "output",
+            "en",
+            1.2,
+            0.9,
+        );
+        assert!(matches!(result, Err(SerenQaError::ScoreOutOfRange { field: "serendipity_score", .. })));
+
+        let result = trace.log_event(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input",
+            "output",
+            "not a language",
+            0.5,
+            0.9,
+        );
+        assert!(matches!(result, Err(SerenQaError::UnknownLanguage(_))));
+        assert!(trace.events.is_empty());
+        assert!(trace.languages.is_empty());
+    }
+
+    #[test]
+    fn test_uniqueness_breakdown() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.log_event(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input1",
+            "output1",
+            "en",
+            0.8,
+            0.9,
+        ).unwrap();
+        trace.log_event(
+            SerendipityStage::Validation,
+            SerendipityAgent::Validator,
+            "input2",
+            "output2",
+            "id",
+            0.8,
+            0.9,
+        ).unwrap();
+        
+        let breakdown = trace.uniqueness_breakdown();
+        assert_eq!(breakdown.agent.observed, 2);
+        assert_eq!(breakdown.agent.denominator, AGENT_TYPE_COUNT);
+        assert_eq!(breakdown.language.observed, 2);
+        assert_eq!(breakdown.stage.denominator, STAGE_COUNT);
+        assert_eq!(breakdown.explanations.len(), 3);
+        
+        let expected = 0.4 * (2.0 / 7.0) + 0.3 * (2.0 / 5.0) + 0.3 * (2.0 / 6.0);
+        assert!((breakdown.score - expected).abs() < 1e-12);
+        assert_eq!(trace.fold_memory().unwrap().uniqueness, breakdown);
+    }
+
+    #[test]
+    fn test_custom_agents() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        for agent in [SerendipityAgent::Explorer, SerendipityAgent::custom("Crystallographer")] {
+            trace.log_event(SerendipityStage::Exploration, agent, "input", "output", "en", 0.8, 0.9).unwrap();
+        }
+        assert_eq!(trace.events[1].agent.name(), "Crystallographer");
+        assert_eq!(trace.uniqueness_breakdown().agent.denominator, AGENT_TYPE_COUNT + 1);
+        assert_eq!(trace.fold_memory().unwrap().uniqueness, trace.uniqueness_breakdown());
+
+        // Built-in agents keep their plain serialized form
+        let json = serde_json::to_string(&trace.events.iter().map(|e| &e.agent).collect::<Vec<_>>()).unwrap();
+        assert_eq!(json, r#"["Explorer",{"Custom":"Crystallographer"}]"#);
+        let restored: SerendipityTrace = serde_json::from_str(&serde_json::to_string(&trace).unwrap()).unwrap();
+        assert_eq!(restored.events[1].agent, SerendipityAgent::custom("Crystallographer"));
+    }
 }
@@ -0,0 +1,613 @@
+// -*- coding: utf-8 -*-
+//! HTTP API Server
+//!
+//! Runs the SerenQA benchmark as a hosted service. Submitted traces go through
+//! the `SubmissionPipeline` (plus a check against the provenance hash the
+//! client declares), are saved to a `TraceStore`, and credited on the
+//! leaderboard; a trace ID already in the store is refused with 409. With
+//! `with_quota`, submissions over a contributor's quota are refused with 429
+//! (daily limit) or 413 (trace too large). A submission is checked against
+//! the quota once it passes its checks and counted only after it has been
+//! stored and credited, all while holding the leaderboard, so parallel
+//! submissions cannot overrun the quota and failed ones use no slot. With
+//! `with_access_control`, callers
+//! authenticate with `Authorization: Bearer <token>`: submitting requires
+//! write access to the trace (401 without a known token, 403 otherwise), and
+//! traces or contributor statistics the caller may not read answer 404 so
+//! their existence does not leak. With `with_audit_log`, every accepted submission is recorded as a
+//! trace save and a leaderboard recomputation. Requires the `server` feature
+//! (`axum` and `tokio` crates).
+//!
+//! ```text
+//! POST /traces                      submit {"trace": ..., "provenance_hash": "v1:..."}
+//! GET  /traces/{id}                 stored trace
+//! GET  /leaderboard?criteria=Elo    ranked page (`page`, `page_size` optional)
+//! GET  /contributors/{id}/stats     contributor statistics
+//! GET  /metrics                     Prometheus metrics (`metrics` feature)
+//! ```
+#![cfg(feature = "server")]
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::submission::{RejectionCode, RejectionIssue, RejectionReport, SubmissionPipeline, SubmissionReceipt};
+use crate::store::TraceStore;
+use crate::quota::{QuotaExceeded, QuotaLimit, QuotaManager};
+use crate::acl::{AccessControlledStore, Principal, PrincipalDirectory};
+use crate::audit::{AuditAction, AuditLog, AuditTarget};
+use crate::ContributorStats::{
+    LanguageAwareContributorStats, LanguageAwareLeaderboard, LanguageAwareRankingCriteria, LeaderboardPage,
+    LeaderboardQuery, DEFAULT_PAGE_SIZE,
+};
+use crate::fold_multilingual_memory::MultilingualMemoryFolder;
+use crate::pipeline::language_events;
+use crate::error::SerenQaError;
+
+/// Shared state behind every request
+pub struct ServerState {
+    store: Arc<dyn TraceStore>,
+    leaderboard: RwLock<LanguageAwareLeaderboard>,
+    pipeline: SubmissionPipeline,
+    quota: Option<Mutex<QuotaManager>>,
+    principals: Option<PrincipalDirectory>,
+    audit: Option<Mutex<AuditLog>>,
+}
+
+impl ServerState {
+    /// State with an empty leaderboard
+    pub fn new(store: Arc<dyn TraceStore>, pipeline: SubmissionPipeline) -> Self {
+        Self { store, leaderboard: RwLock::new(LanguageAwareLeaderboard::new()), pipeline, quota: None, principals: None, audit: None }
+    }
+
+    /// Enforce submission quotas
+    pub fn with_quota(mut self, quota: QuotaManager) -> Self {
+        self.quota = Some(Mutex::new(quota));
+        self
+    }
+
+    /// Record accepted submissions in `audit`
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(Mutex::new(audit));
+        self
+    }
+
+    /// Copy of the audit log, if one is kept
+    pub fn audit_log(&self) -> Option<AuditLog> {
+        self.audit.as_ref().map(|audit| audit.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    /// Authenticate callers against `principals` and enforce trace access control
+    pub fn with_access_control(mut self, principals: PrincipalDirectory) -> Self {
+        self.principals = Some(principals);
+        self
+    }
+
+    /// Caller named by the bearer token (`None` without access control);
+    /// requests without a token act as the anonymous principal
+    fn principal(&self, headers: &HeaderMap) -> Result<Option<Principal>, ApiError> {
+        let Some(principals) = &self.principals else { return Ok(None) };
+        let Some(value) = headers.get(header::AUTHORIZATION) else { return Ok(Some(Principal::anonymous())) };
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| principals.authenticate(token.trim()))
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| ApiError::Unauthorized("unknown API token".to_string()))
+    }
+
+    /// Start from an existing leaderboard (e.g., a loaded snapshot)
+    pub fn with_leaderboard(mut self, leaderboard: LanguageAwareLeaderboard) -> Self {
+        self.leaderboard = RwLock::new(leaderboard);
+        self
+    }
+
+    /// Copy of the current leaderboard, e.g. for saving a snapshot
+    pub fn leaderboard(&self) -> LanguageAwareLeaderboard {
+        self.leaderboard.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Body of `POST /traces`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceSubmission {
+    /// Submitted trace
+    pub trace: SerendipityTrace,
+    /// Provenance hash the contributor computed; the trace must match it
+    #[serde(default)]
+    pub provenance_hash: Option<String>,
+}
+
+/// Query string of `GET /leaderboard`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeaderboardParams {
+    /// Ranking criteria (`Overall` by default)
+    #[serde(default)]
+    pub criteria: Option<LanguageAwareRankingCriteria>,
+    /// Zero-based page index
+    #[serde(default)]
+    pub page: usize,
+    /// Entries per page
+    #[serde(default)]
+    pub page_size: Option<usize>,
+}
+
+/// Error returned by a handler, rendered as `{"error": ...}`
+#[derive(Debug)]
+pub enum ApiError {
+    /// Submission failed one or more checks (422, body is the report)
+    Rejected(RejectionReport),
+    /// No such trace or contributor (404)
+    NotFound(String),
+    /// Submission duplicates an earlier one (409)
+    Conflict(String),
+    /// Missing or unknown API token where one is required (401)
+    Unauthorized(String),
+    /// Caller lacks the permission (403)
+    Forbidden(String),
+    /// Submission exceeds a quota (429 for the daily limit, else 413)
+    QuotaExceeded(QuotaExceeded),
+    /// Storage or scoring failure (500)
+    Internal(String),
+}
+
+impl From<SerenQaError> for ApiError {
+    fn from(err: SerenQaError) -> Self {
+        match err {
+            SerenQaError::DuplicateSubmission { .. } => ApiError::Conflict(err.to_string()),
+            SerenQaError::Quota(exceeded) => ApiError::QuotaExceeded(exceeded),
+            SerenQaError::AccessDenied { .. } => ApiError::Forbidden(err.to_string()),
+            other => ApiError::Internal(other.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Rejected(report) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(report)).into_response(),
+            ApiError::QuotaExceeded(exceeded) => return quota_response(exceeded),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::Conflict(message) => (StatusCode::CONFLICT, message),
+            ApiError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, message),
+            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
+            ApiError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+/// Quota refusal with the structured quota error, and `Retry-After` for the daily limit
+fn quota_response(exceeded: QuotaExceeded) -> Response {
+    let body = Json(serde_json::json!({ "error": exceeded.to_string(), "quota": exceeded }));
+    match (exceeded.limit, exceeded.retry_after) {
+        (QuotaLimit::TracesPerDay, Some(retry_after)) => {
+            let seconds = (retry_after - chrono::Utc::now()).num_seconds().max(1);
+            (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, seconds.to_string())], body).into_response()
+        }
+        (QuotaLimit::TracesPerDay, None) => (StatusCode::TOO_MANY_REQUESTS, body).into_response(),
+        _ => (StatusCode::PAYLOAD_TOO_LARGE, body).into_response(),
+    }
+}
+
+/// Routes of the API over `state`
+pub fn router(state: Arc<ServerState>) -> Router {
+    let router = Router::new()
+        .route("/traces", post(submit_trace))
+        .route("/traces/{id}", get(get_trace))
+        .route("/leaderboard", get(get_leaderboard))
+        .route("/contributors/{id}/stats", get(get_contributor_stats));
+    #[cfg(feature = "metrics")]
+    let router = router.route("/metrics", get(get_metrics));
+    router.with_state(state)
+}
+
+/// Serve the API on `addr` until the listener fails
+pub async fn serve(addr: SocketAddr, state: Arc<ServerState>) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(state)).await
+}
+
+/// `POST /traces`: check, store, and credit a trace
+pub async fn submit_trace(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(submission): Json<TraceSubmission>,
+) -> Result<(StatusCode, Json<SubmissionReceipt>), ApiError> {
+    let trace = submission.trace;
+    let principal = state.principal(&headers)?;
+    if let Some(principal) = &principal {
+        if principal.id.is_empty() {
+            return Err(ApiError::Unauthorized("submitting requires an API token".to_string()));
+        }
+        AccessControlledStore::new(state.store.clone()).check_save(principal, &trace)?;
+    }
+    let mut report = state.pipeline.check(&trace);
+    if let Some(hash) = &submission.provenance_hash {
+        if !trace.verify_provenance_hash(hash) && !report.codes().contains(&RejectionCode::ProvenanceHashMismatch) {
+            report.issues.push(RejectionIssue::new(
+                RejectionCode::ProvenanceHashMismatch,
+                Vec::new(),
+                "trace does not match the declared provenance hash".to_string(),
+            ));
+        }
+    }
+    if !report.is_empty() {
+        return Err(ApiError::Rejected(report));
+    }
+
+    let patterns = MultilingualMemoryFolder::new().fold_memory(&trace.trace_id, &language_events(&trace))?;
+    // Hold the leaderboard from the duplicate check to the credit, so two
+    // submissions of one trace ID cannot both be stored
+    let mut leaderboard = state.leaderboard.write().unwrap_or_else(|e| e.into_inner());
+    if state.store.load(&trace.trace_id)?.is_some() {
+        return Err(ApiError::Conflict(format!("trace {} was already submitted", trace.trace_id)));
+    }
+    // Parallel submissions wait on the leaderboard, so the quota cannot
+    // change between this check and the count below
+    if let Some(quota) = &state.quota {
+        quota.lock().unwrap_or_else(|e| e.into_inner()).check(&trace).map_err(ApiError::QuotaExceeded)?;
+    }
+    // Store before crediting, so no credited trace is missing from the store
+    state.store.save(&trace)?;
+    if let Err(err) = leaderboard.credit_trace(&trace, patterns.overall_alignment, patterns.translation_summary.average_quality) {
+        state.store.delete(&trace.trace_id)?;
+        return Err(err.into());
+    }
+    // Count only accepted submissions against the quota
+    if let Some(quota) = &state.quota {
+        quota.lock().unwrap_or_else(|e| e.into_inner()).record(&trace.contributor_id);
+    }
+    drop(leaderboard);
+
+    let receipt = SubmissionReceipt {
+        trace_id: trace.trace_id.clone(),
+        provenance_hash: trace.compute_provenance_hash(),
+    };
+    if let Some(audit) = &state.audit {
+        let actor = principal.map_or_else(|| trace.contributor_id.clone(), |principal| principal.id);
+        let mut audit = audit.lock().unwrap_or_else(|e| e.into_inner());
+        audit.record(
+            &actor,
+            AuditTarget::Trace(trace.trace_id.clone()),
+            AuditAction::TraceSaved { provenance_hash: receipt.provenance_hash.clone() },
+        )?;
+        audit.record(
+            &actor,
+            AuditTarget::LeaderboardEntry(trace.contributor_id.clone()),
+            AuditAction::StatsRecomputed { trace_id: Some(trace.trace_id.clone()) },
+        )?;
+    }
+    Ok((StatusCode::CREATED, Json(receipt)))
+}
+
+/// `GET /traces/{id}`: a stored trace
+pub async fn get_trace(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path(trace_id): Path<String>,
+) -> Result<Json<SerendipityTrace>, ApiError> {
+    let trace = match state.principal(&headers)? {
+        Some(principal) => match AccessControlledStore::new(state.store.clone()).load(&principal, &trace_id) {
+            Err(SerenQaError::AccessDenied { .. }) => None,
+            loaded => loaded?,
+        },
+        None => state.store.load(&trace_id)?,
+    };
+    trace.map(Json).ok_or_else(|| ApiError::NotFound(format!("no trace {}", trace_id)))
+}
+
+/// `GET /leaderboard`: one page of rankings
+pub async fn get_leaderboard(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<LeaderboardParams>,
+) -> Json<LeaderboardPage> {
+    let criteria = params.criteria.unwrap_or(LanguageAwareRankingCriteria::Overall);
+    let query = LeaderboardQuery::new(criteria).page(params.page, params.page_size.unwrap_or(DEFAULT_PAGE_SIZE));
+    Json(state.leaderboard.read().unwrap_or_else(|e| e.into_inner()).query(&query))
+}
+
+/// `GET /contributors/{id}/stats`: statistics of one contributor
+pub async fn get_contributor_stats(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path(contributor_id): Path<String>,
+) -> Result<Json<LanguageAwareContributorStats>, ApiError> {
+    let readable = state.principal(&headers)?.is_none_or(|principal| principal.can_read_stats(&contributor_id));
+    let stats = match readable {
+        true => state.leaderboard.read().unwrap_or_else(|e| e.into_inner()).get(&contributor_id).cloned(),
+        false => None,
+    };
+    stats.map(Json).ok_or_else(|| ApiError::NotFound(format!("no contributor {}", contributor_id)))
+}
+
+/// `GET /metrics`: recorder metrics in the Prometheus text format
+#[cfg(feature = "metrics")]
+pub async fn get_metrics(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let metrics = crate::prometheus::RecorderMetrics::global();
+    metrics.observe_leaderboard(&state.leaderboard.read().unwrap_or_else(|e| e.into_inner()));
+    ([(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")], metrics.to_prometheus())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryTraceStore;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+    use crate::error::SerenQaResult;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn state() -> Arc<ServerState> {
+        Arc::new(ServerState::new(Arc::new(InMemoryTraceStore::new()), SubmissionPipeline::new()))
+    }
+
+    fn submission() -> TraceSubmission {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.4, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "batik", "id", 0.9, 0.8).unwrap();
+        let provenance_hash = Some(trace.compute_provenance_hash());
+        TraceSubmission { trace, provenance_hash }
+    }
+
+    #[tokio::test]
+    async fn test_submit_then_read_back() {
+        let state = state();
+        let submission = submission();
+        let trace_id = submission.trace.trace_id.clone();
+        let (status, receipt) = submit_trace(State(state.clone()), HeaderMap::new(), Json(submission)).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(receipt.trace_id, trace_id);
+
+        let Json(stored) = get_trace(State(state.clone()), HeaderMap::new(), Path(trace_id)).await.unwrap();
+        assert_eq!(stored.events.len(), 2);
+        let Json(stats) = get_contributor_stats(State(state.clone()), HeaderMap::new(), Path("sari".to_string())).await.unwrap();
+        assert_eq!(stats.total_traces, 1);
+
+        let params = LeaderboardParams { criteria: Some(LanguageAwareRankingCriteria::Serendipity), page: 0, page_size: None };
+        let Json(page) = get_leaderboard(State(state), Query(params)).await;
+        assert_eq!(page.entries[0].contributor_id, "sari");
+    }
+
+    #[tokio::test]
+    async fn test_router_matches_path_parameters() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let state = state();
+        let submission = submission();
+        let trace_id = submission.trace.trace_id.clone();
+        let request = Request::post("/traces")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&submission).unwrap()))
+            .unwrap();
+        assert_eq!(router(state.clone()).oneshot(request).await.unwrap().status(), StatusCode::CREATED);
+
+        let get = |uri: String| Request::get(uri).body(Body::empty()).unwrap();
+        let response = router(state.clone()).oneshot(get(format!("/traces/{}", trace_id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let stored: SerendipityTrace = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(stored.trace_id, trace_id);
+
+        let response = router(state.clone()).oneshot(get("/contributors/sari/stats".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router(state).oneshot(get("/contributors/budi/stats".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_tampered_and_unknown() {
+        let state = state();
+        let mut submission = submission();
+        submission.trace.events[1].serendipity_score = 0.1;
+        match submit_trace(State(state.clone()), HeaderMap::new(), Json(submission)).await {
+            Err(ApiError::Rejected(report)) => assert!(report.codes().contains(&RejectionCode::ProvenanceHashMismatch)),
+            other => panic!("expected rejection, got {:?}", other.map(|(status, _)| status)),
+        }
+        assert!(state.leaderboard().is_empty());
+        assert!(matches!(get_trace(State(state.clone()), HeaderMap::new(), Path("missing".to_string())).await, Err(ApiError::NotFound(_))));
+        assert!(matches!(get_contributor_stats(State(state), HeaderMap::new(), Path("sari".to_string())).await, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_error_responses() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        async fn send(state: &Arc<ServerState>, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+            let response = router(state.clone()).oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+        }
+        let post = |body: Vec<u8>| {
+            Request::post("/traces").header(header::CONTENT_TYPE, "application/json").body(Body::from(body)).unwrap()
+        };
+
+        let state = state();
+        // Bodies that are not a submission never reach the handler
+        assert_eq!(send(&state, post(b"{ not json".to_vec())).await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(send(&state, post(b"{\"trace\": 7}".to_vec())).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+        let untyped = Request::post("/traces").body(Body::from(serde_json::to_vec(&submission()).unwrap())).unwrap();
+        assert_eq!(send(&state, untyped).await.0, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        // A rejected trace answers with the report, other errors with {"error": ...}
+        let mut tampered = submission();
+        tampered.trace.events[1].serendipity_score = 0.1;
+        let (status, body) = send(&state, post(serde_json::to_vec(&tampered).unwrap())).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["trace_id"], tampered.trace.trace_id.as_str());
+        assert!(!body["issues"].as_array().unwrap().is_empty());
+
+        let submission = serde_json::to_vec(&submission()).unwrap();
+        assert_eq!(send(&state, post(submission.clone())).await.0, StatusCode::CREATED);
+        let (status, body) = send(&state, post(submission)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(body["error"].as_str().unwrap().contains("already submitted"));
+        let (status, body) = send(&state, Request::get("/traces/missing").body(Body::empty()).unwrap()).await;
+        assert_eq!((status, body["error"].as_str()), (StatusCode::NOT_FOUND, Some("no trace missing")));
+
+        // Only bearer tokens authenticate
+        let guarded = Arc::new(
+            ServerState::new(Arc::new(InMemoryTraceStore::new()), SubmissionPipeline::new())
+                .with_access_control(PrincipalDirectory::new().with_token("sari-token", Principal::new("sari"))),
+        );
+        let basic = Request::get("/traces/missing").header(header::AUTHORIZATION, "Basic sari-token").body(Body::empty()).unwrap();
+        let (status, body) = send(&guarded, basic).await;
+        assert_eq!((status, body["error"].as_str()), (StatusCode::UNAUTHORIZED, Some("unknown API token")));
+
+        // Library errors map onto statuses; size limits are 413
+        let denied = SerenQaError::AccessDenied { principal: "budi".to_string(), permission: crate::acl::Permission::Read, trace_id: "t".to_string() };
+        assert!(matches!(ApiError::from(denied), ApiError::Forbidden(_)));
+        assert!(matches!(ApiError::from(SerenQaError::Io(std::io::Error::other("disk full"))), ApiError::Internal(ref m) if m.contains("disk full")));
+        let oversized = QuotaExceeded { contributor_id: "sari".to_string(), limit: QuotaLimit::PayloadBytes, allowed: 10, actual: 11, retry_after: None };
+        assert_eq!(ApiError::QuotaExceeded(oversized).into_response().status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    /// In-memory store whose first `failures` saves fail
+    struct FailingStore {
+        failures: AtomicUsize,
+        inner: InMemoryTraceStore,
+    }
+
+    impl FailingStore {
+        fn new(failures: usize) -> Self {
+            Self { failures: failures.into(), inner: InMemoryTraceStore::new() }
+        }
+    }
+
+    impl TraceStore for FailingStore {
+        fn save(&self, trace: &SerendipityTrace) -> SerenQaResult<()> {
+            if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| failures.checked_sub(1)).is_ok() {
+                return Err(SerenQaError::Io(std::io::Error::other("disk full")));
+            }
+            self.inner.save(trace)
+        }
+
+        fn load(&self, trace_id: &str) -> SerenQaResult<Option<SerendipityTrace>> {
+            self.inner.load(trace_id)
+        }
+
+        fn delete(&self, trace_id: &str) -> SerenQaResult<bool> {
+            self.inner.delete(trace_id)
+        }
+
+        fn list(&self) -> SerenQaResult<Vec<String>> {
+            self.inner.list()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_ids_and_failed_saves_are_not_credited() {
+        let state = state();
+        let submission = submission();
+        submit_trace(State(state.clone()), HeaderMap::new(), Json(submission.clone())).await.unwrap();
+        assert!(matches!(submit_trace(State(state.clone()), HeaderMap::new(), Json(submission)).await, Err(ApiError::Conflict(_))));
+        assert_eq!(state.leaderboard().get("sari").unwrap().total_traces, 1);
+
+        let failing = Arc::new(ServerState::new(Arc::new(FailingStore::new(usize::MAX)), SubmissionPipeline::new()));
+        assert!(matches!(submit_trace(State(failing.clone()), HeaderMap::new(), Json(submission())).await, Err(ApiError::Internal(_))));
+        assert!(failing.leaderboard().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quota_refuses_extra_submissions() {
+        let quota = QuotaManager::new(crate::quota::QuotaPolicy::unlimited().with_traces_per_day(1));
+        let state = Arc::new(ServerState::new(Arc::new(InMemoryTraceStore::new()), SubmissionPipeline::new()).with_quota(quota));
+        submit_trace(State(state.clone()), HeaderMap::new(), Json(submission())).await.unwrap();
+        match submit_trace(State(state.clone()), HeaderMap::new(), Json(submission())).await {
+            Err(ApiError::QuotaExceeded(exceeded)) => {
+                assert_eq!(exceeded.limit, QuotaLimit::TracesPerDay);
+                let response = quota_response(exceeded);
+                assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+                assert!(response.headers().contains_key(header::RETRY_AFTER));
+            }
+            other => panic!("expected quota refusal, got {:?}", other.map(|(status, _)| status)),
+        }
+        assert_eq!(state.leaderboard().get("sari").unwrap().total_traces, 1);
+        assert!(state.audit_log().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_submissions_use_no_quota() {
+        let quota = QuotaManager::new(crate::quota::QuotaPolicy::unlimited().with_traces_per_day(1));
+        let state = Arc::new(ServerState::new(Arc::new(FailingStore::new(1)), SubmissionPipeline::new()).with_quota(quota));
+        assert!(matches!(submit_trace(State(state.clone()), HeaderMap::new(), Json(submission())).await, Err(ApiError::Internal(_))));
+        assert_eq!(state.quota.as_ref().unwrap().lock().unwrap().remaining_today("sari"), Some(1));
+
+        // The save failed, so the daily slot is still free for the next submission
+        submit_trace(State(state.clone()), HeaderMap::new(), Json(submission())).await.unwrap();
+        assert_eq!(state.leaderboard().get("sari").unwrap().total_traces, 1);
+        assert!(matches!(submit_trace(State(state), HeaderMap::new(), Json(submission())).await, Err(ApiError::QuotaExceeded(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_parallel_submissions_respect_quota() {
+        let quota = QuotaManager::new(crate::quota::QuotaPolicy::unlimited().with_traces_per_day(3));
+        let store = Arc::new(InMemoryTraceStore::new());
+        let state = Arc::new(ServerState::new(store.clone(), SubmissionPipeline::new()).with_quota(quota));
+        let handles: Vec<_> = (0..12)
+            .map(|_| tokio::spawn(submit_trace(State(state.clone()), HeaderMap::new(), Json(submission()))))
+            .collect();
+        let mut accepted = 0;
+        for handle in handles {
+            match handle.await.unwrap() {
+                Ok(_) => accepted += 1,
+                Err(ApiError::QuotaExceeded(exceeded)) => assert_eq!(exceeded.limit, QuotaLimit::TracesPerDay),
+                Err(other) => panic!("unexpected error {:?}", other),
+            }
+        }
+        assert_eq!(accepted, 3);
+        assert_eq!(store.list().unwrap().len(), 3);
+        assert_eq!(state.leaderboard().get("sari").unwrap().total_traces, 3);
+    }
+
+    #[tokio::test]
+    async fn test_access_control() {
+        let principals = PrincipalDirectory::new()
+            .with_token("sari-token", Principal::new("sari"))
+            .with_token("budi-token", Principal::new("budi"))
+            .with_token("admin-token", Principal::new("admin").with_role(crate::acl::Role::BenchmarkAdmin));
+        let state = Arc::new(
+            ServerState::new(Arc::new(InMemoryTraceStore::new()), SubmissionPipeline::new())
+                .with_access_control(principals)
+                .with_audit_log(AuditLog::new()),
+        );
+        let bearer = |token: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+            headers
+        };
+
+        assert!(matches!(submit_trace(State(state.clone()), HeaderMap::new(), Json(submission())).await, Err(ApiError::Unauthorized(_))));
+        assert!(matches!(submit_trace(State(state.clone()), bearer("forged"), Json(submission())).await, Err(ApiError::Unauthorized(_))));
+        // Budi cannot submit a trace in Sari's name
+        assert!(matches!(submit_trace(State(state.clone()), bearer("budi-token"), Json(submission())).await, Err(ApiError::Forbidden(_))));
+        assert!(state.leaderboard().is_empty());
+
+        let (_, receipt) = submit_trace(State(state.clone()), bearer("sari-token"), Json(submission())).await.unwrap();
+        // Only the accepted submission is audited
+        let audit = state.audit_log().unwrap();
+        assert_eq!(audit.by_actor("sari").count(), 2);
+        assert!(audit.entries().iter().all(|entry| entry.actor == "sari"));
+        get_trace(State(state.clone()), bearer("sari-token"), Path(receipt.trace_id.clone())).await.unwrap();
+        // A private trace looks missing to everyone else
+        assert!(matches!(get_trace(State(state.clone()), bearer("budi-token"), Path(receipt.trace_id.clone())).await, Err(ApiError::NotFound(_))));
+        assert!(matches!(get_trace(State(state.clone()), HeaderMap::new(), Path(receipt.trace_id)).await, Err(ApiError::NotFound(_))));
+
+        // So do Sari's full statistics, to all but Sari and admins
+        let stats = |headers| get_contributor_stats(State(state.clone()), headers, Path("sari".to_string()));
+        assert_eq!(stats(bearer("sari-token")).await.unwrap().0.total_traces, 1);
+        assert!(matches!(stats(bearer("budi-token")).await, Err(ApiError::NotFound(_))));
+        assert!(matches!(stats(HeaderMap::new()).await, Err(ApiError::NotFound(_))));
+        assert!(stats(bearer("admin-token")).await.is_ok());
+    }
+}
@@ -0,0 +1,230 @@
+// -*- coding: utf-8 -*-
+//! Thread-Safe Async Trace Recorder
+//!
+//! `SerendipityTrace` needs `&mut self` to log, which forces concurrent agents
+//! to serialize behind one owner. `SharedTraceRecorder` moves the trace onto a
+//! dedicated writer thread fed by a channel; `record(builder).await` resolves
+//! once the event is appended. It is runtime-agnostic, so tokio tasks (or
+//! plain threads) can share it behind an `Arc`.
+//!
+//! Ordering: events are appended in the order `record` is *called* (the
+//! event is enqueued immediately, before the future is polled), so a task
+//! that awaits each record sees its events in program order.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEventBuilder};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// One-shot reply slot shared by the writer thread and a waiting future
+struct Slot<T> {
+    value: Option<T>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+/// Writer-side half of a reply slot; closes the slot if dropped unanswered
+struct Reply<T>(Arc<Mutex<Slot<T>>>);
+
+impl<T> Reply<T> {
+    fn send(self, value: T) {
+        self.0.lock().unwrap().value = Some(value);
+    }
+}
+
+impl<T> Drop for Reply<T> {
+    fn drop(&mut self) {
+        let mut slot = self.0.lock().unwrap();
+        slot.closed = true;
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Future resolving to the writer thread's reply
+pub struct Pending<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> Future for Pending<T> {
+    type Output = SerenQaResult<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().unwrap();
+        if let Some(value) = slot.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+        if slot.closed {
+            return Poll::Ready(Err(SerenQaError::RecorderStopped));
+        }
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+fn reply_pair<T>() -> (Reply<T>, Pending<T>) {
+    let slot = Arc::new(Mutex::new(Slot { value: None, closed: false, waker: None }));
+    (Reply(Arc::clone(&slot)), Pending { slot })
+}
+
+/// Event appended by the recorder
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    /// ID assigned to the event
+    pub event_id: String,
+    /// Position of the event in the trace
+    pub sequence: usize,
+}
+
+enum Command {
+    Record(Box<SerendipityEventBuilder>, Reply<SerenQaResult<RecordedEvent>>),
+    Snapshot(Reply<SerendipityTrace>),
+}
+
+/// Trace shared by concurrent agents through a writer thread
+pub struct SharedTraceRecorder {
+    sender: Option<Sender<Command>>,
+    writer: Option<JoinHandle<SerendipityTrace>>,
+}
+
+impl SharedTraceRecorder {
+    /// Start a writer thread owning `trace`
+    pub fn new(mut trace: SerendipityTrace) -> Self {
+        let (sender, commands) = mpsc::channel::<Command>();
+        let writer = std::thread::spawn(move || {
+            for command in commands {
+                match command {
+                    Command::Record(builder, reply) => {
+                        let result = trace
+                            .log(*builder)
+                            .map(|event| event.event_id.clone())
+                            .map(|event_id| RecordedEvent { event_id, sequence: trace.events.len() - 1 });
+                        reply.send(result);
+                    }
+                    Command::Snapshot(reply) => reply.send(trace.clone()),
+                }
+            }
+            trace
+        });
+        Self {
+            sender: Some(sender),
+            writer: Some(writer),
+        }
+    }
+
+    fn send<T>(&self, command: impl FnOnce(Reply<T>) -> Command) -> Pending<T> {
+        let (reply, pending) = reply_pair();
+        if let Some(sender) = &self.sender {
+            // A send error drops the reply, which closes the pending future
+            let _ = sender.send(command(reply));
+        }
+        pending
+    }
+
+    /// Enqueue an event; resolves with its ID once appended, or with the
+    /// validation/veto error that rejected it
+    pub fn record(&self, builder: SerendipityEventBuilder) -> impl Future<Output = SerenQaResult<RecordedEvent>> {
+        let pending = self.send(|reply| Command::Record(Box::new(builder), reply));
+        async move { pending.await? }
+    }
+
+    /// Copy of the trace after every event enqueued so far
+    pub fn snapshot(&self) -> Pending<SerendipityTrace> {
+        self.send(Command::Snapshot)
+    }
+
+    /// Stop accepting events and return the trace once the queue drains
+    pub fn finish(mut self) -> SerenQaResult<SerendipityTrace> {
+        self.sender.take();
+        self.writer
+            .take()
+            .and_then(|writer| writer.join().ok())
+            .ok_or(SerenQaError::RecorderStopped)
+    }
+}
+
+impl Drop for SharedTraceRecorder {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            std::thread::park();
+        }
+    }
+
+    fn event(language: &str, score: f64) -> SerendipityEventBuilder {
+        SerendipityEventBuilder::new(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", language)
+            .serendipity(score)
+            .confidence(0.8)
+    }
+
+    #[test]
+    fn test_concurrent_agents_share_one_trace() {
+        let recorder = Arc::new(SharedTraceRecorder::new(SerendipityTrace::new("researcher1", "backend", "Journavx")));
+        let agents: Vec<_> = (0..4)
+            .map(|agent| {
+                let recorder = Arc::clone(&recorder);
+                std::thread::spawn(move || {
+                    let language = if agent % 2 == 0 { "en" } else { "id" };
+                    (0..5)
+                        .map(|_| block_on(recorder.record(event(language, 0.5))).unwrap().sequence)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for agent in agents {
+            let sequences = agent.join().unwrap();
+            assert!(sequences.windows(2).all(|w| w[0] < w[1]));
+        }
+
+        let trace = Arc::try_unwrap(recorder).ok().unwrap().finish().unwrap();
+        assert_eq!(trace.events.len(), 20);
+        assert_eq!(trace.transitions.len(), 19);
+    }
+
+    #[test]
+    fn test_rejections_and_snapshots() {
+        let recorder = SharedTraceRecorder::new(SerendipityTrace::new("researcher1", "backend", "Journavx"));
+        let first = recorder.record(event("en", 0.4));
+        let rejected = recorder.record(event("en", 1.5));
+        let second = recorder.record(event("id", 0.9));
+        assert_eq!(block_on(first).unwrap().sequence, 0);
+        assert!(matches!(block_on(rejected), Err(SerenQaError::ScoreOutOfRange { .. })));
+        assert_eq!(block_on(second).unwrap().sequence, 1);
+
+        let snapshot = block_on(recorder.snapshot()).unwrap();
+        assert_eq!(snapshot.events.len(), 2);
+        assert_eq!(snapshot.languages, vec!["en".to_string(), "id".to_string()]);
+    }
+}
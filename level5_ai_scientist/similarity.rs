@@ -0,0 +1,212 @@
+// -*- coding: utf-8 -*-
+//! Trace Similarity
+//!
+//! Compares two traces along three axes: structure (edit distance between
+//! their stage sequences), agent usage (weighted overlap of how often each
+//! agent acted), and content (keyword overlap of event inputs and outputs).
+//! Benchmark maintainers use it to spot near-duplicate submissions and to
+//! cluster related discovery trajectories.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::serendipity_trace::{SerendipityTrace, SerendipityAgent};
+
+/// Overall similarity at or above which two traces count as near-duplicates
+pub const DEFAULT_NEAR_DUPLICATE_THRESHOLD: f64 = 0.9;
+
+/// Similarity of two traces, each component in [0, 1]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TraceSimilarity {
+    /// One minus the normalized edit distance between stage sequences
+    pub structural: f64,
+    /// Sum of per-agent minimum event counts over the sum of maximums
+    pub agent_overlap: f64,
+    /// Jaccard similarity of the traces' keyword sets
+    pub content: f64,
+}
+
+impl TraceSimilarity {
+    /// Mean of the three components
+    pub fn overall(&self) -> f64 {
+        (self.structural + self.agent_overlap + self.content) / 3.0
+    }
+
+    /// Whether the overall similarity reaches `threshold`
+    pub fn is_near_duplicate(&self, threshold: f64) -> bool {
+        self.overall() >= threshold
+    }
+}
+
+/// Levenshtein distance over arbitrary sequences
+fn sequence_edit_distance<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Lowercased words of at least three characters in a trace's events
+fn trace_keywords(trace: &SerendipityTrace) -> HashSet<String> {
+    trace.events
+        .iter()
+        .flat_map(|e| [e.input.as_str(), e.output.as_str()])
+        .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn agent_counts(trace: &SerendipityTrace) -> HashMap<&SerendipityAgent, usize> {
+    let mut counts = HashMap::new();
+    for event in &trace.events {
+        *counts.entry(&event.agent).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Jaccard similarity, 1 when both sets are empty
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+impl SerendipityTrace {
+    /// Structural, agent-usage, and content similarity to another trace
+    pub fn similarity(&self, other: &SerendipityTrace) -> TraceSimilarity {
+        let stages: Vec<_> = self.events.iter().map(|e| &e.stage).collect();
+        let other_stages: Vec<_> = other.events.iter().map(|e| &e.stage).collect();
+        let longest = stages.len().max(other_stages.len());
+        let structural = if longest == 0 {
+            1.0
+        } else {
+            1.0 - sequence_edit_distance(&stages, &other_stages) as f64 / longest as f64
+        };
+
+        let counts = agent_counts(self);
+        let other_counts = agent_counts(other);
+        let agents: HashSet<_> = counts.keys().chain(other_counts.keys()).collect();
+        let (shared, total) = agents.iter().fold((0, 0), |(shared, total), agent| {
+            let a = counts.get(**agent).copied().unwrap_or(0);
+            let b = other_counts.get(**agent).copied().unwrap_or(0);
+            (shared + a.min(b), total + a.max(b))
+        });
+        let agent_overlap = if total == 0 { 1.0 } else { shared as f64 / total as f64 };
+
+        TraceSimilarity {
+            structural,
+            agent_overlap,
+            content: jaccard(&trace_keywords(self), &trace_keywords(other)),
+        }
+    }
+}
+
+/// Pairs of traces (by index) whose overall similarity reaches `threshold`,
+/// most similar first
+pub fn near_duplicates(traces: &[SerendipityTrace], threshold: f64) -> Vec<(usize, usize, TraceSimilarity)> {
+    let mut pairs = Vec::new();
+    for i in 0..traces.len() {
+        for j in i + 1..traces.len() {
+            let similarity = traces[i].similarity(&traces[j]);
+            if similarity.is_near_duplicate(threshold) {
+                pairs.push((i, j, similarity));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.2.overall().total_cmp(&a.2.overall()));
+    pairs
+}
+
+/// Group traces (by index) whose overall similarity reaches `threshold`,
+/// transitively (single linkage); clusters are ordered by their first trace
+pub fn cluster_traces(traces: &[SerendipityTrace], threshold: f64) -> Vec<Vec<usize>> {
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut parent: Vec<usize> = (0..traces.len()).collect();
+    for (i, j, _) in near_duplicates(traces, threshold) {
+        let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+        parent[a.max(b)] = a.min(b);
+    }
+
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut cluster_of: HashMap<usize, usize> = HashMap::new();
+    for i in 0..traces.len() {
+        let r = root(&mut parent, i);
+        let index = *cluster_of.entry(r).or_insert_with(|| {
+            clusters.push(Vec::new());
+            clusters.len() - 1
+        });
+        clusters[index].push(i);
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityStage;
+
+    fn trace(steps: &[(SerendipityStage, SerendipityAgent, &str)]) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        for (stage, agent, output) in steps {
+            trace.log_event(stage.clone(), agent.clone(), "in", output, "en", 0.5, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_similarity_components() {
+        use SerendipityAgent::*;
+        use SerendipityStage::*;
+        let a = trace(&[(Exploration, Explorer, "quantum walk"), (Validation, Validator, "benchmark passed")]);
+        let b = trace(&[(Exploration, Explorer, "quantum walk"), (Publication, Validator, "paper drafted")]);
+
+        let same = a.similarity(&a);
+        assert_eq!((same.structural, same.agent_overlap, same.content), (1.0, 1.0, 1.0));
+
+        let sim = a.similarity(&b);
+        assert_eq!(sim.structural, 0.5);
+        assert_eq!(sim.agent_overlap, 1.0);
+        // {quantum, walk} shared out of {quantum, walk, benchmark, passed, paper, drafted}
+        assert!((sim.content - 2.0 / 6.0).abs() < 1e-9);
+        assert_eq!(sim, b.similarity(&a));
+
+        let empty = SerendipityTrace::new("researcher2", "backend", "Empty");
+        assert_eq!(empty.similarity(&empty).overall(), 1.0);
+        assert_eq!(a.similarity(&empty).agent_overlap, 0.0);
+    }
+
+    #[test]
+    fn test_near_duplicates_and_clusters() {
+        use SerendipityAgent::*;
+        use SerendipityStage::*;
+        let original = trace(&[(Exploration, Explorer, "quantum walk navigation"), (Validation, Validator, "benchmark passed")]);
+        let mut resubmitted = original.clone();
+        resubmitted.events[1].output = "benchmark passed again".to_string();
+        let unrelated = trace(&[(UnexpectedConnection, PatternRecognizer, "tidal model"), (Integration, Synthesizer, "merged")]);
+        let traces = vec![original, unrelated, resubmitted];
+
+        let pairs = near_duplicates(&traces, DEFAULT_NEAR_DUPLICATE_THRESHOLD);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].0, pairs[0].1), (0, 2));
+        assert_eq!(cluster_traces(&traces, DEFAULT_NEAR_DUPLICATE_THRESHOLD), vec![vec![0, 2], vec![1]]);
+        assert_eq!(cluster_traces(&traces, 0.0), vec![vec![0, 1, 2]]);
+    }
+}
@@ -0,0 +1,188 @@
+// -*- coding: utf-8 -*-
+//! Stage Transition Policies
+//!
+//! By default any stage can follow any stage. A `StagePolicy` attached to a
+//! trace constrains the order: a stage can require an earlier prerequisite
+//! (Publication only after Validation) or forbid a direct transition. In
+//! `Strict` mode a violating event is rejected; in `Lenient` mode it is logged
+//! and the violation is recorded as a trace annotation for later auditing.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::{SerendipityStage, SerendipityEvent, SerendipityTrace};
+use crate::annotation::AnnotationKind;
+
+/// How violations are handled
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyMode {
+    /// Reject violating events
+    #[default]
+    Strict,
+    /// Log violating events and annotate the trace
+    Lenient,
+}
+
+/// Constraint on stage order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum StageRule {
+    /// `stage` may only be logged after some `prerequisite` event
+    Requires { stage: SerendipityStage, prerequisite: SerendipityStage },
+    /// `to` may not directly follow `from`
+    Forbids { from: SerendipityStage, to: SerendipityStage },
+}
+
+impl std::fmt::Display for StageRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StageRule::Requires { stage, prerequisite } => {
+                write!(f, "{} requires an earlier {}", stage.name(), prerequisite.name())
+            }
+            StageRule::Forbids { from, to } => write!(f, "{} may not directly follow {}", to.name(), from.name()),
+        }
+    }
+}
+
+/// Event stage that broke a policy rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StageViolation {
+    /// Stage of the offending event
+    pub stage: SerendipityStage,
+    /// Rule that was broken
+    pub rule: StageRule,
+}
+
+impl std::fmt::Display for StageViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stage policy violation: {}", self.rule)
+    }
+}
+
+impl std::error::Error for StageViolation {}
+
+/// Rules constraining stage order, with the enforcement mode
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct StagePolicy {
+    /// Enforcement mode
+    #[serde(default)]
+    pub mode: PolicyMode,
+    /// Rules checked against every logged event
+    #[serde(default)]
+    pub rules: Vec<StageRule>,
+}
+
+impl StagePolicy {
+    /// Empty policy with the given mode
+    pub fn new(mode: PolicyMode) -> Self {
+        Self { mode, rules: Vec::new() }
+    }
+
+    /// Research-process order: hypotheses follow exploration, validation
+    /// follows a hypothesis, and integration and publication follow validation
+    pub fn research_process(mode: PolicyMode) -> Self {
+        use SerendipityStage::*;
+        Self::new(mode)
+            .requires(HypothesisFormation, Exploration)
+            .requires(Validation, HypothesisFormation)
+            .requires(Integration, Validation)
+            .requires(Publication, Validation)
+    }
+
+    /// Add a prerequisite rule
+    pub fn requires(mut self, stage: SerendipityStage, prerequisite: SerendipityStage) -> Self {
+        self.rules.push(StageRule::Requires { stage, prerequisite });
+        self
+    }
+
+    /// Add a forbidden direct transition
+    pub fn forbids(mut self, from: SerendipityStage, to: SerendipityStage) -> Self {
+        self.rules.push(StageRule::Forbids { from, to });
+        self
+    }
+
+    /// First rule broken by logging `stage` after `history`
+    pub fn check(&self, history: &[SerendipityEvent], stage: &SerendipityStage) -> Option<StageViolation> {
+        self.check_stages(|s| history.iter().any(|e| &e.stage == s), history.last().map(|e| &e.stage), stage)
+    }
+
+    /// First rule broken by logging `stage`, given whether each stage was
+    /// logged before and the stage of the previous event
+    pub fn check_stages(
+        &self,
+        logged: impl Fn(&SerendipityStage) -> bool,
+        last_stage: Option<&SerendipityStage>,
+        stage: &SerendipityStage,
+    ) -> Option<StageViolation> {
+        self.rules
+            .iter()
+            .find(|rule| match rule {
+                StageRule::Requires { stage: s, prerequisite } => s == stage && !logged(prerequisite),
+                StageRule::Forbids { from, to } => to == stage && last_stage == Some(from),
+            })
+            .map(|rule| StageViolation { stage: stage.clone(), rule: rule.clone() })
+    }
+}
+
+impl SerendipityTrace {
+    /// Enforce `policy` on events logged from now on
+    pub fn set_stage_policy(&mut self, policy: StagePolicy) {
+        self.stage_policy = Some(policy);
+    }
+
+    /// Violations recorded under a lenient policy, with the offending event IDs
+    pub fn policy_violations(&self) -> impl Iterator<Item = (&str, &StageViolation)> {
+        self.annotations.iter().filter_map(|note| match &note.kind {
+            AnnotationKind::StagePolicy(violation) => Some((note.event_id.as_str(), violation)),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SerenQaError;
+    use crate::serendipity_trace::SerendipityAgent;
+
+    fn log(trace: &mut SerendipityTrace, stage: SerendipityStage) -> Result<String, SerenQaError> {
+        trace
+            .log_event(stage, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8)
+            .map(|e| e.event_id.clone())
+    }
+
+    #[test]
+    fn test_strict_policy_rejects_out_of_order_stage() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.set_stage_policy(StagePolicy::research_process(PolicyMode::Strict));
+        log(&mut trace, SerendipityStage::Exploration).unwrap();
+
+        let err = log(&mut trace, SerendipityStage::Publication).unwrap_err();
+        assert!(matches!(err, SerenQaError::StagePolicy(ref v) if v.stage == SerendipityStage::Publication));
+        assert_eq!(err.to_string(), "stage policy violation: Publication requires an earlier Validation");
+        assert_eq!(trace.events.len(), 1);
+        assert!(trace.annotations.is_empty());
+    }
+
+    #[test]
+    fn test_lenient_policy_annotates_violations() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        let policy = StagePolicy::new(PolicyMode::Lenient)
+            .forbids(SerendipityStage::Exploration, SerendipityStage::Publication);
+        trace.set_stage_policy(policy);
+        log(&mut trace, SerendipityStage::Exploration).unwrap();
+        let publication = log(&mut trace, SerendipityStage::Publication).unwrap();
+        log(&mut trace, SerendipityStage::Publication).unwrap();
+
+        let violations: Vec<_> = trace.policy_violations().collect();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, publication);
+        assert_eq!(
+            violations[0].1.rule,
+            StageRule::Forbids { from: SerendipityStage::Exploration, to: SerendipityStage::Publication }
+        );
+
+        let restored: SerendipityTrace = serde_json::from_str(&serde_json::to_string(&trace).unwrap()).unwrap();
+        assert_eq!(restored.annotations, trace.annotations);
+        assert_eq!(restored.stage_policy, trace.stage_policy);
+    }
+}
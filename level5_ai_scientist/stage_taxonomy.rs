@@ -0,0 +1,236 @@
+// -*- coding: utf-8 -*-
+//! Stage Taxonomies
+//!
+//! The six built-in stages follow a generic research process. Fields such as
+//! drug discovery or materials science have phases of their own, so a trace
+//! can carry a `StageTaxonomy`: an ordered list of stages with descriptions,
+//! loaded from a config file. Names of built-in stages resolve to the
+//! built-in variants and any other name is a `SerendipityStage::Custom`.
+//! With a taxonomy attached, events in unlisted stages are rejected, stage
+//! diversity is measured against the taxonomy's size, stage resets follow
+//! its order, and `StagePolicy::sequential` derives an order policy from it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use crate::serendipity_trace::{SerendipityStage, SerendipityTrace, STAGE_COUNT};
+use crate::stage_policy::{PolicyMode, StagePolicy};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Stage as stored in the config file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StageDefinition {
+    /// Stage name (a built-in variant name or a custom one)
+    pub name: String,
+    /// What happens in this stage
+    #[serde(default)]
+    pub description: String,
+}
+
+impl StageDefinition {
+    /// Stage the definition names
+    pub fn stage(&self) -> SerendipityStage {
+        SerendipityStage::from_name(&self.name)
+    }
+}
+
+/// Ordered, non-empty list of distinct stages
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "Vec<StageDefinition>", into = "Vec<StageDefinition>")]
+pub struct StageTaxonomy {
+    definitions: Vec<StageDefinition>,
+    stages: Vec<SerendipityStage>,
+}
+
+impl TryFrom<Vec<StageDefinition>> for StageTaxonomy {
+    type Error = SerenQaError;
+
+    fn try_from(definitions: Vec<StageDefinition>) -> SerenQaResult<Self> {
+        Self::new(definitions)
+    }
+}
+
+impl From<StageTaxonomy> for Vec<StageDefinition> {
+    fn from(taxonomy: StageTaxonomy) -> Self {
+        taxonomy.definitions
+    }
+}
+
+impl StageTaxonomy {
+    /// Taxonomy of `definitions`, in order; fails on an empty list, a blank
+    /// name, or a repeated name
+    pub fn new(definitions: Vec<StageDefinition>) -> SerenQaResult<Self> {
+        if definitions.is_empty() {
+            return Err(SerenQaError::InvalidStageTaxonomy("no stages".to_string()));
+        }
+        let mut seen = HashSet::new();
+        for definition in &definitions {
+            if definition.name.trim().is_empty() {
+                return Err(SerenQaError::InvalidStageTaxonomy("blank stage name".to_string()));
+            }
+            if !seen.insert(definition.name.as_str()) {
+                return Err(SerenQaError::InvalidStageTaxonomy(format!("stage {:?} is listed twice", definition.name)));
+            }
+        }
+        let stages = definitions.iter().map(StageDefinition::stage).collect();
+        Ok(Self { definitions, stages })
+    }
+
+    /// Load a taxonomy from a JSON array of stage definitions
+    pub fn from_json(json: &str) -> SerenQaResult<Self> {
+        Self::new(serde_json::from_str(json)?)
+    }
+
+    /// Load a taxonomy from a JSON config file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> SerenQaResult<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Built-in research-process stages shipped in `data/stage_taxonomy.json`
+    pub fn builtin() -> Self {
+        Self::from_json(include_str!("data/stage_taxonomy.json")).expect("built-in stage taxonomy is valid")
+    }
+
+    /// Stage definitions, in order
+    pub fn definitions(&self) -> &[StageDefinition] {
+        &self.definitions
+    }
+
+    /// Stages, in order
+    pub fn stages(&self) -> &[SerendipityStage] {
+        &self.stages
+    }
+
+    /// Number of stages
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Always false: taxonomies have at least one stage
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Position of `stage` in the taxonomy
+    pub fn ordinal(&self, stage: &SerendipityStage) -> Option<usize> {
+        self.stages.iter().position(|s| s == stage)
+    }
+
+    /// Whether `stage` is listed
+    pub fn contains(&self, stage: &SerendipityStage) -> bool {
+        self.ordinal(stage).is_some()
+    }
+
+    /// Description of `stage`, if listed
+    pub fn description(&self, stage: &SerendipityStage) -> Option<&str> {
+        self.ordinal(stage).map(|i| self.definitions[i].description.as_str())
+    }
+}
+
+/// Stage types that stage diversity is measured against: the taxonomy's
+/// stages when there is one, otherwise the built-in stages; stages seen
+/// outside either widen the count, so they never lower diversity
+pub(crate) fn stage_type_count<'a, I>(taxonomy: Option<&StageTaxonomy>, stages: I) -> usize
+where
+    I: IntoIterator<Item = &'a SerendipityStage>,
+{
+    match taxonomy {
+        Some(taxonomy) => taxonomy.len() + stages.into_iter().filter(|s| !taxonomy.contains(s)).count(),
+        None => STAGE_COUNT + stages.into_iter().filter(|s| s.is_custom()).count(),
+    }
+}
+
+impl StagePolicy {
+    /// Taxonomy order: each stage after the first requires an earlier event
+    /// in the stage listed before it
+    pub fn sequential(taxonomy: &StageTaxonomy, mode: PolicyMode) -> Self {
+        taxonomy
+            .stages()
+            .windows(2)
+            .fold(Self::new(mode), |policy, pair| policy.requires(pair[1].clone(), pair[0].clone()))
+    }
+}
+
+impl SerendipityTrace {
+    /// Restrict events logged from now on to the stages of `taxonomy`
+    pub fn set_stage_taxonomy(&mut self, taxonomy: StageTaxonomy) {
+        self.stage_taxonomy = Some(taxonomy);
+        self.invalidate_fold_cache();
+    }
+
+    /// Position of `stage` in the trace's taxonomy, or its built-in ordinal
+    /// when the trace has none (or does not list it)
+    pub fn stage_ordinal(&self, stage: &SerendipityStage) -> usize {
+        self.stage_taxonomy
+            .as_ref()
+            .and_then(|taxonomy| taxonomy.ordinal(stage))
+            .unwrap_or_else(|| stage.ordinal())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::SerendipityAgent;
+
+    const DRUG_DISCOVERY: &str = r#"[
+        {"name": "TargetIdentification", "description": "Pick a biological target"},
+        {"name": "HitScreening", "description": "Screen compound libraries"},
+        {"name": "LeadOptimization", "description": "Tune hits into leads"},
+        {"name": "Validation", "description": "Preclinical validation"}
+    ]"#;
+
+    fn log(trace: &mut SerendipityTrace, stage: &str) -> SerenQaResult<()> {
+        trace
+            .log_event(SerendipityStage::from_name(stage), SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8)
+            .map(|_| ())
+    }
+
+    #[test]
+    fn test_taxonomy_from_config() {
+        let taxonomy = StageTaxonomy::from_json(DRUG_DISCOVERY).unwrap();
+        assert_eq!(taxonomy.len(), 4);
+        assert_eq!(taxonomy.stages()[1], SerendipityStage::custom("HitScreening"));
+        assert_eq!(taxonomy.stages()[3], SerendipityStage::Validation);
+        assert_eq!(taxonomy.ordinal(&SerendipityStage::custom("LeadOptimization")), Some(2));
+        assert_eq!(taxonomy.description(&SerendipityStage::Validation), Some("Preclinical validation"));
+        assert!(!taxonomy.contains(&SerendipityStage::Publication));
+        assert_eq!(StageTaxonomy::builtin().stages(), SerendipityStage::BUILTIN);
+
+        let restored = StageTaxonomy::from_json(&serde_json::to_string(&taxonomy).unwrap()).unwrap();
+        assert_eq!(restored, taxonomy);
+        assert!(matches!(StageTaxonomy::from_json("[]"), Err(SerenQaError::InvalidStageTaxonomy(_))));
+        assert!(StageTaxonomy::from_json(r#"[{"name": "Assay"}, {"name": "Assay"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_trace_adapts_to_taxonomy() {
+        let taxonomy = StageTaxonomy::from_json(DRUG_DISCOVERY).unwrap();
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.set_stage_taxonomy(taxonomy.clone());
+        trace.set_stage_policy(StagePolicy::sequential(&taxonomy, PolicyMode::Strict));
+        log(&mut trace, "TargetIdentification").unwrap();
+        log(&mut trace, "HitScreening").unwrap();
+
+        // Unlisted stages and out-of-order stages are rejected
+        assert!(matches!(log(&mut trace, "Publication"), Err(SerenQaError::UnknownStage(ref s)) if s == "Publication"));
+        let err = log(&mut trace, "Validation").unwrap_err();
+        assert_eq!(err.to_string(), "stage policy violation: Validation requires an earlier LeadOptimization");
+
+        let breakdown = trace.uniqueness_breakdown();
+        assert_eq!((breakdown.stage.observed, breakdown.stage.denominator), (2, 4));
+        assert_eq!(trace.fold_memory().unwrap().uniqueness, breakdown);
+        assert_eq!(trace.stage_ordinal(&SerendipityStage::custom("HitScreening")), 1);
+
+        // Summaries and transition reasons name custom stages as configured
+        trace.log_event(SerendipityStage::custom("LeadOptimization"), SerendipityAgent::Explorer, "in", "lead found", "en", 0.95, 0.8).unwrap();
+        assert_eq!(trace.transitions.last().unwrap().reason, "HitScreening -> LeadOptimization");
+        assert!(trace.fold_memory().unwrap().key_discoveries.contains(&"LeadOptimization: lead found".to_string()));
+
+        // Without a taxonomy, custom stages widen the built-in stage count
+        let mut open = SerendipityTrace::new("sari", "backend", "Journavx");
+        log(&mut open, "Exploration").unwrap();
+        log(&mut open, "HitScreening").unwrap();
+        assert_eq!(open.uniqueness_breakdown().stage.denominator, STAGE_COUNT + 1);
+    }
+}
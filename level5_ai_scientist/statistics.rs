@@ -0,0 +1,203 @@
+// -*- coding: utf-8 -*-
+//! Statistical Summaries of Traces
+//!
+//! `statistics` summarizes a trace for dashboards: the distribution of
+//! serendipity and confidence per stage and per agent, the time between
+//! successive events, and how often the trace switches language and writing
+//! script. Stages and agents are keyed by name so the summary serializes to
+//! plain JSON objects.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::serendipity_trace::SerendipityTrace;
+use crate::script::text_script;
+
+/// Count, center, and spread of a set of values (all zero when empty)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Distribution {
+    /// Number of values
+    pub count: usize,
+    /// Arithmetic mean
+    pub mean: f64,
+    /// Middle value (mean of the two middle values for even counts)
+    pub median: f64,
+    /// Population standard deviation
+    pub std_dev: f64,
+    /// Smallest value
+    pub min: f64,
+    /// Largest value
+    pub max: f64,
+}
+
+impl Distribution {
+    /// Summarize `values`
+    pub fn of(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let median = if count.is_multiple_of(2) {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+        } else {
+            sorted[count / 2]
+        };
+        let std_dev = (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64).sqrt();
+        Self { count, mean, median, std_dev, min: sorted[0], max: sorted[count - 1] }
+    }
+}
+
+/// Serendipity and confidence of the events sharing a stage or agent
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GroupStatistics {
+    /// Number of events
+    pub count: usize,
+    /// Serendipity scores of the events
+    pub serendipity: Distribution,
+    /// Confidence of the events
+    pub confidence: Distribution,
+}
+
+/// Statistical summary of a trace
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceStatistics {
+    /// Trace ID
+    pub trace_id: String,
+    /// Number of events
+    pub total_events: usize,
+    /// Serendipity scores of all events
+    pub serendipity: Distribution,
+    /// Confidence of all events
+    pub confidence: Distribution,
+    /// Events per stage, keyed by stage name
+    pub by_stage: BTreeMap<String, GroupStatistics>,
+    /// Events per agent, keyed by agent name
+    pub by_agent: BTreeMap<String, GroupStatistics>,
+    /// Seconds between successive events
+    pub inter_event_seconds: Distribution,
+    /// Successive events in different languages
+    pub language_switches: usize,
+    /// Language switches per successive pair of events (0 with fewer than two events)
+    pub language_switch_rate: f64,
+    /// Events per writing script of their output (see `script::text_script`)
+    pub scripts: BTreeMap<String, usize>,
+    /// Successive events written in different scripts
+    pub script_switches: usize,
+}
+
+/// Group `(key, serendipity, confidence)` triples by key
+fn group<I>(rows: I) -> BTreeMap<String, GroupStatistics>
+where
+    I: IntoIterator<Item = (String, f64, f64)>,
+{
+    let mut values: BTreeMap<String, (Vec<f64>, Vec<f64>)> = BTreeMap::new();
+    for (key, serendipity, confidence) in rows {
+        let entry = values.entry(key).or_default();
+        entry.0.push(serendipity);
+        entry.1.push(confidence);
+    }
+    values
+        .into_iter()
+        .map(|(key, (serendipity, confidence))| {
+            let stats = GroupStatistics {
+                count: serendipity.len(),
+                serendipity: Distribution::of(&serendipity),
+                confidence: Distribution::of(&confidence),
+            };
+            (key, stats)
+        })
+        .collect()
+}
+
+impl SerendipityTrace {
+    /// Per-stage and per-agent distributions, inter-event timing, and
+    /// language- and script-switch frequency
+    pub fn statistics(&self) -> TraceStatistics {
+        let serendipity: Vec<f64> = self.events.iter().map(|e| e.serendipity_score).collect();
+        let confidence: Vec<f64> = self.events.iter().map(|e| e.confidence).collect();
+        let by_stage = group(self.events.iter().map(|e| (e.stage.name(), e.serendipity_score, e.confidence)));
+        let by_agent = group(self.events.iter().map(|e| (e.agent.name(), e.serendipity_score, e.confidence)));
+
+        let gaps: Vec<f64> = self.events
+            .windows(2)
+            .map(|pair| (pair[1].timestamp - pair[0].timestamp).num_milliseconds() as f64 / 1000.0)
+            .collect();
+        let language_switches = self.events.windows(2).filter(|pair| pair[0].language != pair[1].language).count();
+        let language_switch_rate = if gaps.is_empty() { 0.0 } else { language_switches as f64 / gaps.len() as f64 };
+        let event_scripts: Vec<String> = self.events.iter().map(|e| text_script(&e.output, &e.language)).collect();
+        let mut scripts = BTreeMap::new();
+        for script in &event_scripts {
+            *scripts.entry(script.clone()).or_insert(0) += 1;
+        }
+        let script_switches = event_scripts.windows(2).filter(|pair| pair[0] != pair[1]).count();
+
+        TraceStatistics {
+            trace_id: self.trace_id.clone(),
+            total_events: self.events.len(),
+            serendipity: Distribution::of(&serendipity),
+            confidence: Distribution::of(&confidence),
+            by_stage,
+            by_agent,
+            inter_event_seconds: Distribution::of(&gaps),
+            language_switches,
+            language_switch_rate,
+            scripts,
+            script_switches,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use crate::clock::MockClock;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    #[test]
+    fn test_distribution() {
+        let odd = Distribution::of(&[0.9, 0.1, 0.5]);
+        assert_eq!((odd.count, odd.median, odd.min, odd.max), (3, 0.5, 0.1, 0.9));
+        assert!((odd.mean - 0.5).abs() < 1e-12);
+
+        let even = Distribution::of(&[1.0, 3.0, 2.0, 4.0]);
+        assert_eq!(even.median, 2.5);
+        assert!((even.std_dev - 1.25f64.sqrt()).abs() < 1e-12);
+        assert_eq!(Distribution::of(&[]), Distribution::default());
+    }
+
+    #[test]
+    fn test_trace_statistics() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap());
+        let mut trace = SerendipityTrace::with_clock("sari", "backend", "Journavx", clock.clone());
+        let steps = [
+            (SerendipityStage::Exploration, SerendipityAgent::Explorer, "en", 0.2, 0.6, 0),
+            (SerendipityStage::Exploration, SerendipityAgent::Explorer, "id", 0.4, 0.8, 60),
+            (SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "id", 0.9, 0.7, 180),
+            (SerendipityStage::Validation, SerendipityAgent::Validator, "en", 0.6, 0.9, 120),
+        ];
+        for (i, (stage, agent, language, score, confidence, wait)) in steps.into_iter().enumerate() {
+            clock.advance(Duration::seconds(wait));
+            trace.log_event(stage, agent, "in", &format!("out {}", i), language, score, confidence).unwrap();
+        }
+
+        let stats = trace.statistics();
+        assert_eq!(stats.total_events, 4);
+        let exploration = &stats.by_stage["Exploration"];
+        assert_eq!(exploration.count, 2);
+        assert!((exploration.serendipity.mean - 0.3).abs() < 1e-12);
+        assert!((exploration.confidence.median - 0.7).abs() < 1e-12);
+        assert_eq!(stats.by_agent["PatternRecognizer"].count, 1);
+        assert_eq!(stats.inter_event_seconds.median, 120.0);
+        assert_eq!(stats.inter_event_seconds.max, 180.0);
+        assert_eq!(stats.language_switches, 2);
+        assert!((stats.language_switch_rate - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!((stats.scripts["Latin"], stats.script_switches), (4, 0));
+
+        let json = serde_json::to_string(&stats).unwrap();
+        assert!(json.contains("\"by_stage\":{\"Exploration\""));
+        assert_eq!(SerendipityTrace::new("sari", "backend", "Journavx").statistics().language_switch_rate, 0.0);
+    }
+}
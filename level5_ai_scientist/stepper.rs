@@ -0,0 +1,201 @@
+// -*- coding: utf-8 -*-
+//! Interactive Trace Stepping for Debuggers
+//!
+//! `TraceStepper` walks a recorded trace event by event with forward and
+//! backward navigation, exposing the cumulative state at each step (running
+//! serendipity, languages seen, fold preview) so IDE/TUI debuggers can show
+//! how the final scores came to be.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::{
+    SerendipityTrace, SerendipityEvent, SerendipityTransition, FoldedSerendipityTrace,
+};
+
+/// Cumulative trace state after a step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepState {
+    /// Zero-based index of the current event
+    pub index: usize,
+    /// Total number of events in the trace
+    pub total: usize,
+    /// Current event
+    pub event: SerendipityEvent,
+    /// Transition into the current event
+    pub incoming_transition: Option<SerendipityTransition>,
+    /// Overall serendipity over events so far, using the trace's strategy
+    pub running_serendipity: f64,
+    /// Change in running serendipity caused by this event
+    pub serendipity_delta: f64,
+    /// Languages seen so far, in order of first use
+    pub languages_seen: Vec<String>,
+    /// Whether this event introduced a new language
+    pub new_language: bool,
+    /// Fold of the trace prefix ending at this event
+    pub fold_preview: FoldedSerendipityTrace,
+}
+
+/// Cursor over a trace's events with cumulative state
+pub struct TraceStepper<'a> {
+    trace: &'a SerendipityTrace,
+    /// Index of the current event; `None` before the first step
+    position: Option<usize>,
+}
+
+impl<'a> TraceStepper<'a> {
+    /// Create a stepper positioned before the first event
+    pub fn new(trace: &'a SerendipityTrace) -> Self {
+        Self { trace, position: None }
+    }
+
+    /// Number of events
+    pub fn len(&self) -> usize {
+        self.trace.events.len()
+    }
+
+    /// Check if the trace has no events
+    pub fn is_empty(&self) -> bool {
+        self.trace.events.is_empty()
+    }
+
+    /// Index of the current event
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Check if positioned on the last event
+    pub fn at_end(&self) -> bool {
+        self.position.map(|p| p + 1 >= self.len()).unwrap_or(self.is_empty())
+    }
+
+    /// Move to the next event
+    pub fn step_forward(&mut self) -> Option<StepState> {
+        let next = self.position.map_or(0, |p| p + 1);
+        self.seek(next)
+    }
+
+    /// Move to the previous event; stepping back from the first event
+    /// returns to the start position
+    pub fn step_back(&mut self) -> Option<StepState> {
+        match self.position {
+            Some(0) | None => {
+                self.position = None;
+                None
+            }
+            Some(p) => self.seek(p - 1),
+        }
+    }
+
+    /// Jump to an event index (position is unchanged if out of range)
+    pub fn seek(&mut self, index: usize) -> Option<StepState> {
+        if index >= self.len() {
+            return None;
+        }
+        self.position = Some(index);
+        self.current()
+    }
+
+    /// Return to the start position
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    /// State at the current position
+    pub fn current(&self) -> Option<StepState> {
+        let index = self.position?;
+        let prefix = self.prefix(index + 1);
+        let previous_serendipity = if index == 0 {
+            0.0
+        } else {
+            self.trace.aggregation.aggregate(&self.trace.events[..index])
+        };
+        let event = self.trace.events[index].clone();
+        let new_language = !self.trace.events[..index].iter().any(|e| e.language == event.language);
+
+        Some(StepState {
+            index,
+            total: self.len(),
+            incoming_transition: index.checked_sub(1).and_then(|i| self.trace.transitions.get(i).cloned()),
+            running_serendipity: prefix.overall_serendipity,
+            serendipity_delta: prefix.overall_serendipity - previous_serendipity,
+            languages_seen: prefix.languages.clone(),
+            new_language,
+            fold_preview: prefix.fold_memory().ok()?,
+            event,
+        })
+    }
+
+    /// Trace containing only the first `len` events and their transitions
+    fn prefix(&self, len: usize) -> SerendipityTrace {
+        self.trace.sub_trace(0..len)
+    }
+}
+
+impl<'a> Iterator for TraceStepper<'a> {
+    type Item = StepState;
+
+    fn next(&mut self) -> Option<StepState> {
+        self.step_forward()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        for (language, score) in [("en", 0.4), ("id", 0.9), ("en", 0.5)] {
+            trace.log_event(
+                SerendipityStage::Exploration,
+                SerendipityAgent::Explorer,
+                "input",
+                "output",
+                language,
+                score,
+                0.9,
+            ).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_forward_and_backward() {
+        let trace = sample_trace();
+        let mut stepper = TraceStepper::new(&trace);
+        assert!(stepper.current().is_none());
+
+        let first = stepper.step_forward().unwrap();
+        assert_eq!(first.index, 0);
+        assert!(first.new_language);
+        assert!(first.incoming_transition.is_none());
+        assert!((first.running_serendipity - 0.4).abs() < 1e-9);
+
+        let second = stepper.step_forward().unwrap();
+        assert_eq!(second.languages_seen, vec!["en".to_string(), "id".to_string()]);
+        assert!((second.serendipity_delta - 0.25).abs() < 1e-9);
+        assert_eq!(second.fold_preview.key_discoveries.len(), 1);
+
+        let last = stepper.step_forward().unwrap();
+        assert!(!last.new_language);
+        assert!(stepper.at_end());
+        assert!(stepper.step_forward().is_none());
+        assert!((last.running_serendipity - trace.overall_serendipity).abs() < 1e-9);
+
+        assert_eq!(stepper.step_back().unwrap().index, 1);
+        assert_eq!(stepper.step_back().unwrap().index, 0);
+        assert!(stepper.step_back().is_none());
+        assert_eq!(stepper.position(), None);
+    }
+
+    #[test]
+    fn test_iterate_and_seek() {
+        let trace = sample_trace();
+        assert_eq!(TraceStepper::new(&trace).count(), 3);
+
+        let mut stepper = TraceStepper::new(&trace);
+        assert_eq!(stepper.seek(2).unwrap().fold_preview.total_events, 3);
+        assert!(stepper.seek(5).is_none());
+        assert_eq!(stepper.position(), Some(2));
+    }
+}
@@ -0,0 +1,501 @@
+// -*- coding: utf-8 -*-
+//! Trace Storage and Lifecycle Management
+//!
+//! `TraceStore` abstracts where traces live (in memory or a directory of JSON
+//! files). `ArchivingStore` layers a hot/cold split on top: traces idle past
+//! the policy's thresholds become Stale and are then folded and moved to cold
+//! storage, either on demand or by a background archiver thread, so the hot
+//! store stays small in live deployments.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::JoinHandle;
+use crate::serendipity_trace::{SerendipityTrace, FoldedSerendipityTrace};
+use crate::corpus::{load_trace, TRACE_EXTENSION, FOLD_SUFFIX};
+use crate::query::TraceQuery;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Persistent storage for traces, keyed by trace ID
+pub trait TraceStore: Send + Sync {
+    /// Insert or replace a trace
+    fn save(&self, trace: &SerendipityTrace) -> SerenQaResult<()>;
+
+    /// Load a trace
+    fn load(&self, trace_id: &str) -> SerenQaResult<Option<SerendipityTrace>>;
+
+    /// Remove a trace; returns whether it existed
+    fn delete(&self, trace_id: &str) -> SerenQaResult<bool>;
+
+    /// IDs of all stored traces, sorted
+    fn list(&self) -> SerenQaResult<Vec<String>>;
+
+    /// IDs of the traces that may match `query`, narrowed with an index, or
+    /// `None` if the store has no index and every trace must be checked
+    fn query_candidates(&self, _query: &TraceQuery) -> SerenQaResult<Option<Vec<String>>> {
+        Ok(None)
+    }
+}
+
+/// Store keeping traces in a map
+#[derive(Debug, Default)]
+pub struct InMemoryTraceStore {
+    traces: RwLock<HashMap<String, SerendipityTrace>>,
+}
+
+impl InMemoryTraceStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TraceStore for InMemoryTraceStore {
+    fn save(&self, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        self.traces.write().unwrap().insert(trace.trace_id.clone(), trace.clone());
+        Ok(())
+    }
+
+    fn load(&self, trace_id: &str) -> SerenQaResult<Option<SerendipityTrace>> {
+        Ok(self.traces.read().unwrap().get(trace_id).cloned())
+    }
+
+    fn delete(&self, trace_id: &str) -> SerenQaResult<bool> {
+        Ok(self.traces.write().unwrap().remove(trace_id).is_some())
+    }
+
+    fn list(&self) -> SerenQaResult<Vec<String>> {
+        let mut ids: Vec<String> = self.traces.read().unwrap().keys().cloned().collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+/// Encoding of trace files in an `FsTraceStore`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFileFormat {
+    /// Pretty JSON (`<trace_id>.json`)
+    Json,
+    /// Compressed binary (`<trace_id>.seren`)
+    #[cfg(feature = "binary")]
+    Binary,
+}
+
+impl TraceFileFormat {
+    /// File extension of traces in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TraceFileFormat::Json => TRACE_EXTENSION,
+            #[cfg(feature = "binary")]
+            TraceFileFormat::Binary => crate::binary_format::BINARY_EXTENSION,
+        }
+    }
+
+    fn encode(&self, trace: &SerendipityTrace) -> SerenQaResult<Vec<u8>> {
+        match self {
+            TraceFileFormat::Json => Ok(trace.to_json()?.into_bytes()),
+            #[cfg(feature = "binary")]
+            TraceFileFormat::Binary => trace.to_bytes(),
+        }
+    }
+
+    fn load(&self, path: &Path) -> SerenQaResult<SerendipityTrace> {
+        match self {
+            TraceFileFormat::Json => load_trace(path),
+            #[cfg(feature = "binary")]
+            TraceFileFormat::Binary => SerendipityTrace::from_bytes(&std::fs::read(path)?),
+        }
+    }
+}
+
+/// File name stem for a trace ID; characters unsafe in file names become '_'
+pub(crate) fn file_stem(trace_id: &str) -> String {
+    trace_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Store keeping one `<trace_id>.json` (or `.seren`) file per trace in a directory
+#[derive(Debug, Clone)]
+pub struct FsTraceStore {
+    dir: PathBuf,
+    format: TraceFileFormat,
+}
+
+impl FsTraceStore {
+    /// Open (and create if needed) a store directory of JSON traces
+    pub fn open(dir: &Path) -> SerenQaResult<Self> {
+        Self::open_with_format(dir, TraceFileFormat::Json)
+    }
+
+    /// Open (and create if needed) a store directory of binary traces
+    #[cfg(feature = "binary")]
+    pub fn open_binary(dir: &Path) -> SerenQaResult<Self> {
+        Self::open_with_format(dir, TraceFileFormat::Binary)
+    }
+
+    /// Open (and create if needed) a store directory of traces in `format`
+    pub fn open_with_format(dir: &Path, format: TraceFileFormat) -> SerenQaResult<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self { dir: dir.to_path_buf(), format })
+    }
+
+    /// Encoding of the store's trace files
+    pub fn format(&self) -> TraceFileFormat {
+        self.format
+    }
+
+    /// Directory backing the store
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File path for a trace ID
+    fn path_for(&self, trace_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", file_stem(trace_id), self.format.extension()))
+    }
+}
+
+impl TraceStore for FsTraceStore {
+    fn save(&self, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        let path = self.path_for(&trace.trace_id);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, self.format.encode(trace)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn load(&self, trace_id: &str) -> SerenQaResult<Option<SerendipityTrace>> {
+        let path = self.path_for(trace_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(self.format.load(&path)?))
+    }
+
+    fn delete(&self, trace_id: &str) -> SerenQaResult<bool> {
+        match std::fs::remove_file(self.path_for(trace_id)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list(&self) -> SerenQaResult<Vec<String>> {
+        let mut ids = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if path.extension().and_then(|e| e.to_str()) == Some(self.format.extension()) && !name.ends_with(FOLD_SUFFIX) {
+                ids.push(self.format.load(&path)?.trace_id);
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+/// Lifecycle state of a stored trace
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceLifecycle {
+    /// Recently active, kept in the hot store
+    Active,
+    /// Idle past the stale threshold, awaiting archival
+    Stale,
+    /// Folded and moved to cold storage
+    Archived,
+}
+
+/// Inactivity thresholds driving lifecycle transitions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LifecyclePolicy {
+    /// Idle time after which a trace is Stale
+    pub stale_after: Duration,
+    /// Idle time after which a trace is archived (at least `stale_after`)
+    pub archive_after: Duration,
+}
+
+impl LifecyclePolicy {
+    /// Create a policy; `archive_after` is raised to `stale_after` if shorter
+    pub fn new(stale_after: Duration, archive_after: Duration) -> Self {
+        Self {
+            stale_after,
+            archive_after: archive_after.max(stale_after),
+        }
+    }
+
+    /// Lifecycle of a hot-store trace at `now`
+    pub fn classify(&self, trace: &SerendipityTrace, now: DateTime<Utc>) -> TraceLifecycle {
+        if now - trace.last_activity() >= self.stale_after {
+            TraceLifecycle::Stale
+        } else {
+            TraceLifecycle::Active
+        }
+    }
+
+    /// Check if a hot-store trace is due for archival at `now`
+    pub fn should_archive(&self, trace: &SerendipityTrace, now: DateTime<Utc>) -> bool {
+        now - trace.last_activity() >= self.archive_after
+    }
+}
+
+impl Default for LifecyclePolicy {
+    /// Stale after 30 days idle, archived after 90
+    fn default() -> Self {
+        Self::new(Duration::days(30), Duration::days(90))
+    }
+}
+
+impl SerendipityTrace {
+    /// Time of the most recent event, or creation time for an empty trace
+    pub fn last_activity(&self) -> DateTime<Utc> {
+        self.events
+            .iter()
+            .map(|e| e.timestamp)
+            .max()
+            .unwrap_or(self.created_at)
+            .max(self.created_at)
+    }
+}
+
+/// Result of one archival sweep
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ArchivalReport {
+    /// Traces moved to cold storage
+    pub archived: Vec<String>,
+    /// Traces now Stale but not yet due for archival
+    pub stale: Vec<String>,
+}
+
+/// Hot/cold store with lifecycle-driven archival
+pub struct ArchivingStore {
+    hot: Arc<dyn TraceStore>,
+    cold: Arc<dyn TraceStore>,
+    policy: LifecyclePolicy,
+    /// Folds of archived traces, filled on archival or first lookup
+    folds: Mutex<HashMap<String, FoldedSerendipityTrace>>,
+}
+
+impl ArchivingStore {
+    /// Combine a hot and a cold store under a policy
+    pub fn new(hot: Arc<dyn TraceStore>, cold: Arc<dyn TraceStore>, policy: LifecyclePolicy) -> Self {
+        Self {
+            hot,
+            cold,
+            policy,
+            folds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Lifecycle policy in use
+    pub fn policy(&self) -> LifecyclePolicy {
+        self.policy
+    }
+
+    /// Save a trace to the hot store (reviving it if it was archived)
+    pub fn save(&self, trace: &SerendipityTrace) -> SerenQaResult<()> {
+        self.hot.save(trace)?;
+        if self.cold.delete(&trace.trace_id)? {
+            self.folds.lock().unwrap().remove(&trace.trace_id);
+        }
+        Ok(())
+    }
+
+    /// Load a trace from the hot store, falling back to cold storage
+    pub fn load(&self, trace_id: &str) -> SerenQaResult<Option<SerendipityTrace>> {
+        match self.hot.load(trace_id)? {
+            Some(trace) => Ok(Some(trace)),
+            None => self.cold.load(trace_id),
+        }
+    }
+
+    /// IDs of traces in the hot store
+    pub fn hot_ids(&self) -> SerenQaResult<Vec<String>> {
+        self.hot.list()
+    }
+
+    /// IDs of archived traces
+    pub fn archived_ids(&self) -> SerenQaResult<Vec<String>> {
+        self.cold.list()
+    }
+
+    /// Lifecycle of a stored trace at `now`
+    pub fn lifecycle(&self, trace_id: &str, now: DateTime<Utc>) -> SerenQaResult<Option<TraceLifecycle>> {
+        if let Some(trace) = self.hot.load(trace_id)? {
+            return Ok(Some(self.policy.classify(&trace, now)));
+        }
+        Ok(self.cold.load(trace_id)?.map(|_| TraceLifecycle::Archived))
+    }
+
+    /// Fold of an archived trace, without loading it into the hot store
+    pub fn archived_fold(&self, trace_id: &str) -> SerenQaResult<Option<FoldedSerendipityTrace>> {
+        if let Some(fold) = self.folds.lock().unwrap().get(trace_id) {
+            return Ok(Some(fold.clone()));
+        }
+        let Some(trace) = self.cold.load(trace_id)? else {
+            return Ok(None);
+        };
+        let fold = trace.fold_memory()?;
+        self.folds.lock().unwrap().insert(trace_id.to_string(), fold.clone());
+        Ok(Some(fold))
+    }
+
+    /// Fold and move every trace due for archival at `now` to cold storage
+    pub fn archive_stale(&self, now: DateTime<Utc>) -> SerenQaResult<ArchivalReport> {
+        let mut report = ArchivalReport::default();
+        for trace_id in self.hot.list()? {
+            let Some(trace) = self.hot.load(&trace_id)? else {
+                continue;
+            };
+            if self.policy.should_archive(&trace, now) {
+                let fold = if trace.events.is_empty() { None } else { Some(trace.fold_memory()?) };
+                self.cold.save(&trace)?;
+                self.hot.delete(&trace_id)?;
+                if let Some(fold) = fold {
+                    self.folds.lock().unwrap().insert(trace_id.clone(), fold);
+                }
+                report.archived.push(trace_id);
+            } else if self.policy.classify(&trace, now) == TraceLifecycle::Stale {
+                report.stale.push(trace_id);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Run `archive_stale` every `interval` on a background thread until the
+    /// handle is stopped or dropped. Sweep errors are passed to `on_error`.
+    pub fn spawn_archiver<F>(self: &Arc<Self>, interval: std::time::Duration, on_error: F) -> ArchiverHandle
+    where
+        F: Fn(SerenQaError) + Send + 'static,
+    {
+        let store = Arc::clone(self);
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || loop {
+            if let Err(error) = store.archive_stale(Utc::now()) {
+                on_error(error);
+            }
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                _ => break,
+            }
+        });
+        ArchiverHandle {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Handle to a background archiver; stops it when dropped
+pub struct ArchiverHandle {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ArchiverHandle {
+    /// Stop the archiver and wait for its current sweep to finish
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ArchiverHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent, SerendipityEventBuilder};
+
+    fn sample_trace(id: &str, idle_days: i64) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.trace_id = id.to_string();
+        let shifted = Utc::now() - Duration::days(idle_days);
+        trace.created_at = shifted;
+        trace.log(
+            SerendipityEventBuilder::new(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en")
+                .serendipity(0.9)
+                .confidence(0.8)
+                .timestamp(shifted),
+        ).unwrap();
+        trace
+    }
+
+    fn archiving_store() -> ArchivingStore {
+        ArchivingStore::new(
+            Arc::new(InMemoryTraceStore::new()),
+            Arc::new(InMemoryTraceStore::new()),
+            LifecyclePolicy::default(),
+        )
+    }
+
+    #[test]
+    fn test_lifecycle_and_archival() {
+        let store = archiving_store();
+        for (id, idle_days) in [("fresh", 1), ("idle", 45), ("old", 120)] {
+            store.save(&sample_trace(id, idle_days)).unwrap();
+        }
+        let now = Utc::now();
+        assert_eq!(store.lifecycle("fresh", now).unwrap(), Some(TraceLifecycle::Active));
+        assert_eq!(store.lifecycle("idle", now).unwrap(), Some(TraceLifecycle::Stale));
+
+        let report = store.archive_stale(now).unwrap();
+        assert_eq!(report.archived, vec!["old".to_string()]);
+        assert_eq!(report.stale, vec!["idle".to_string()]);
+        assert_eq!(store.hot_ids().unwrap(), vec!["fresh".to_string(), "idle".to_string()]);
+        assert_eq!(store.lifecycle("old", now).unwrap(), Some(TraceLifecycle::Archived));
+        assert_eq!(store.archived_fold("old").unwrap().unwrap().total_events, 1);
+        assert!(store.load("old").unwrap().is_some());
+
+        // Saving again revives the trace
+        store.save(&sample_trace("old", 0)).unwrap();
+        assert_eq!(store.lifecycle("old", now).unwrap(), Some(TraceLifecycle::Active));
+        assert!(store.archived_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fs_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("seren_store_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = FsTraceStore::open(&dir).unwrap();
+        let trace = sample_trace("seren/with:odd chars", 0);
+
+        store.save(&trace).unwrap();
+        assert_eq!(store.list().unwrap(), vec![trace.trace_id.clone()]);
+        assert_eq!(store.load(&trace.trace_id).unwrap().unwrap().compute_provenance_hash(), trace.compute_provenance_hash());
+        assert!(store.delete(&trace.trace_id).unwrap());
+        assert!(!store.delete(&trace.trace_id).unwrap());
+        assert!(store.load(&trace.trace_id).unwrap().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_background_archiver() {
+        let store = Arc::new(archiving_store());
+        store.save(&sample_trace("old", 120)).unwrap();
+        let handle = store.spawn_archiver(std::time::Duration::from_millis(10), |e| panic!("{}", e));
+        for _ in 0..200 {
+            if store.hot_ids().unwrap().is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        handle.stop();
+        assert_eq!(store.archived_ids().unwrap(), vec!["old".to_string()]);
+    }
+}
@@ -0,0 +1,646 @@
+// -*- coding: utf-8 -*-
+//! Streaming Serendipity Trace with Bounded Memory
+//!
+//! For very long agent runs (100k+ events) holding every event in memory is
+//! not an option. `StreamingSerendipityTrace` keeps only running aggregates
+//! (overall serendipity, rolling provenance hasher, fold summary) and spills
+//! raw events and transitions to a sink, while producing the same
+//! `fold_memory()` and `compute_provenance_hash()` results as `SerendipityTrace`.
+//! Events go through the same checks (stage taxonomy and policy, redaction
+//! rules, language check) and are scored with the same aggregation strategy.
+//! Only fixed key-discovery thresholds are supported, since adaptive ones
+//! need every score.
+//! The same line-oriented records back `SerendipityTrace::to_jsonl` and
+//! `from_jsonl` for log pipelines (Fluentd, Vector).
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufRead, Write};
+use crate::serendipity_trace::{
+    SerendipityTrace, SerendipityEvent, SerendipityTransition, SerendipityStage,
+    SerendipityAgent, FoldedSerendipityTrace, UniquenessBreakdown, EventChecks, key_discovery,
+    language_transition_label, AGENT_TYPE_COUNT,
+};
+use crate::stage_taxonomy::{stage_type_count, StageTaxonomy};
+use crate::aggregation::{AggregationStrategy, RunningAggregate};
+use crate::provenance::TraceSignature;
+use crate::knowledge_source::{KnowledgeCredit, credit_event};
+use crate::dedup::DedupReport;
+use crate::extraction::{discovery_entities, entity_clusters};
+use crate::insight_policy::{InsightPolicy, DEFAULT_KEY_DISCOVERY_THRESHOLD};
+use crate::stage_policy::StagePolicy;
+use crate::redaction_rules::RedactionRules;
+use crate::language_detection::LanguageCheck;
+use crate::annotation::TraceAnnotation;
+use crate::canonical::{HashVersion, ProvenanceHasher};
+use crate::ids::TraceIds;
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Trace-level fields written ahead of events in a JSONL export
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceHeader {
+    /// Unique trace identifier
+    pub trace_id: String,
+    /// Contributor who made the discovery
+    pub contributor_id: String,
+    /// Backend/system used
+    pub backend: String,
+    /// Discovery name
+    pub discovery_name: String,
+    /// Timestamp of trace creation
+    pub created_at: DateTime<Utc>,
+    /// Strategy combining event scores into the overall score
+    #[serde(default)]
+    pub aggregation: AggregationStrategy,
+    /// Contributor signature over the provenance hash
+    #[serde(default)]
+    pub signature: Option<TraceSignature>,
+    /// Custom metric values
+    #[serde(default)]
+    pub custom_metrics: BTreeMap<String, f64>,
+    /// Provenance hash of the previous trace in a hash chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_trace_hash: Option<String>,
+}
+
+impl TraceHeader {
+    /// Header for an in-memory trace
+    pub fn of(trace: &SerendipityTrace) -> Self {
+        Self {
+            trace_id: trace.trace_id.clone(),
+            contributor_id: trace.contributor_id.clone(),
+            backend: trace.backend.clone(),
+            discovery_name: trace.discovery_name.clone(),
+            created_at: trace.created_at,
+            aggregation: trace.aggregation,
+            signature: trace.signature.clone(),
+            custom_metrics: trace.custom_metrics.clone(),
+            prev_trace_hash: trace.prev_trace_hash.clone(),
+        }
+    }
+
+    /// Copy header fields onto a trace
+    fn apply(self, trace: &mut SerendipityTrace) {
+        trace.trace_id = self.trace_id;
+        trace.contributor_id = self.contributor_id;
+        trace.backend = self.backend;
+        trace.discovery_name = self.discovery_name;
+        trace.created_at = self.created_at;
+        trace.aggregation = self.aggregation;
+        trace.signature = self.signature;
+        trace.custom_metrics = self.custom_metrics;
+        trace.prev_trace_hash = self.prev_trace_hash;
+    }
+}
+
+/// Record spilled to a sink, one per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceRecord {
+    /// Trace-level fields (JSONL exports only)
+    Header(TraceHeader),
+    /// Logged event
+    Event(SerendipityEvent),
+    /// Transition into the following event
+    Transition(SerendipityTransition),
+}
+
+/// Destination for spilled trace records
+pub trait EventSink {
+    /// Write a single record
+    fn write_record(&mut self, record: &TraceRecord) -> std::io::Result<()>;
+
+    /// Flush buffered records
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink writing one JSON record per line to any writer (file, socket, buffer)
+pub struct JsonLinesSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    /// Create a sink over a writer
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Recover the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> EventSink for JsonLinesSink<W> {
+    fn write_record(&mut self, record: &TraceRecord) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Sink that discards records, keeping only the running aggregates
+pub struct NullSink;
+
+impl EventSink for NullSink {
+    fn write_record(&mut self, _record: &TraceRecord) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serendipity trace that spills raw events to a sink
+pub struct StreamingSerendipityTrace<S: EventSink> {
+    /// Unique trace identifier
+    pub trace_id: String,
+    /// Contributor who made the discovery
+    pub contributor_id: String,
+    /// Backend/system used
+    pub backend: String,
+    /// Discovery name
+    pub discovery_name: String,
+    /// Languages involved
+    pub languages: Vec<String>,
+    /// Overall serendipity score
+    pub overall_serendipity: f64,
+    /// Timestamp of trace creation
+    pub created_at: DateTime<Utc>,
+    /// Number of events logged
+    event_count: usize,
+    /// Running overall score under the trace's aggregation strategy
+    aggregate: RunningAggregate,
+    /// Events scoring above this are key discoveries
+    key_discovery_threshold: f64,
+    /// Most recent event, needed for the next transition
+    last_event: Option<SerendipityEvent>,
+    /// Rolling provenance hasher
+    hasher: ProvenanceHasher,
+    /// Running fold: key discoveries
+    key_discoveries: Vec<String>,
+    /// Running fold: entities of each key discovery
+    discovery_entities: Vec<Vec<String>>,
+    /// Running fold: language transitions
+    language_transitions: Vec<String>,
+    /// Distinct agents seen, for the uniqueness breakdown
+    agents_seen: HashSet<SerendipityAgent>,
+    /// Distinct stages seen, for the uniqueness breakdown
+    stages_seen: HashSet<SerendipityStage>,
+    /// Field-specific stages events are restricted to
+    stage_taxonomy: Option<StageTaxonomy>,
+    /// Stage order enforced on logged events
+    stage_policy: Option<StagePolicy>,
+    /// Redaction rules run on every logged event
+    redaction_rules: Option<RedactionRules>,
+    /// Language check run on every logged event
+    language_check: Option<LanguageCheck>,
+    /// Notes on logged events (lenient policy violations, redactions, language mismatches)
+    annotations: Vec<TraceAnnotation>,
+    /// Running fold: knowledge source credits
+    knowledge_credits: Vec<KnowledgeCredit>,
+    /// Generator for event IDs
+    ids: TraceIds,
+    /// Destination for raw records
+    sink: S,
+}
+
+impl<S: EventSink> StreamingSerendipityTrace<S> {
+    /// Create a new streaming trace writing records to `sink`
+    pub fn new(contributor_id: &str, backend: &str, discovery_name: &str, sink: S) -> Self {
+        let ids = TraceIds::default();
+        let created_at = Utc::now();
+        let trace_id = ids.trace_id(contributor_id, created_at);
+        let mut hasher = ProvenanceHasher::new(HashVersion::CURRENT);
+        hasher.header(&trace_id, contributor_id, backend, discovery_name);
+
+        Self {
+            trace_id,
+            contributor_id: contributor_id.to_string(),
+            backend: backend.to_string(),
+            discovery_name: discovery_name.to_string(),
+            languages: Vec::new(),
+            overall_serendipity: 0.0,
+            created_at,
+            event_count: 0,
+            aggregate: RunningAggregate::new(AggregationStrategy::default()),
+            key_discovery_threshold: DEFAULT_KEY_DISCOVERY_THRESHOLD,
+            last_event: None,
+            hasher,
+            key_discoveries: Vec::new(),
+            discovery_entities: Vec::new(),
+            language_transitions: Vec::new(),
+            agents_seen: HashSet::new(),
+            stages_seen: HashSet::new(),
+            stage_taxonomy: None,
+            stage_policy: None,
+            redaction_rules: None,
+            language_check: None,
+            annotations: Vec::new(),
+            knowledge_credits: Vec::new(),
+            ids,
+            sink,
+        }
+    }
+
+    /// Restrict events logged from now on to the stages of `taxonomy`
+    pub fn set_stage_taxonomy(&mut self, taxonomy: StageTaxonomy) {
+        self.stage_taxonomy = Some(taxonomy);
+    }
+
+    /// Enforce `policy` on events logged from now on
+    pub fn set_stage_policy(&mut self, policy: StagePolicy) {
+        self.stage_policy = Some(policy);
+    }
+
+    /// Apply `rules` to events logged from now on
+    pub fn set_redaction_rules(&mut self, rules: RedactionRules) {
+        self.redaction_rules = Some(rules);
+    }
+
+    /// Check the language of events logged from now on
+    pub fn set_language_check(&mut self, check: LanguageCheck) {
+        self.language_check = Some(check);
+    }
+
+    /// Combine event scores with `strategy`. Scores are not kept, so the
+    /// strategy must be chosen before the first event.
+    pub fn set_aggregation(&mut self, strategy: AggregationStrategy) -> SerenQaResult<()> {
+        if self.event_count > 0 {
+            return Err(SerenQaError::StreamingUnsupported("changing the aggregation after the first event".to_string()));
+        }
+        self.aggregate = RunningAggregate::new(strategy);
+        Ok(())
+    }
+
+    /// Choose the key-discovery threshold before the first event. Only fixed
+    /// thresholds are supported; adaptive ones need every score.
+    pub fn set_insight_policy(&mut self, policy: InsightPolicy) -> SerenQaResult<()> {
+        let InsightPolicy::Fixed { threshold } = policy else {
+            return Err(SerenQaError::StreamingUnsupported(format!("adaptive insight policy {:?}", policy)));
+        };
+        if self.event_count > 0 {
+            return Err(SerenQaError::StreamingUnsupported("changing the insight policy after the first event".to_string()));
+        }
+        self.key_discovery_threshold = threshold;
+        Ok(())
+    }
+
+    /// Aggregation strategy of the overall score
+    pub fn aggregation(&self) -> AggregationStrategy {
+        self.aggregate.strategy()
+    }
+
+    /// Notes on logged events: lenient stage-policy violations, rule
+    /// redactions, and language mismatches
+    pub fn annotations(&self) -> &[TraceAnnotation] {
+        &self.annotations
+    }
+
+    /// Log a serendipity event, spilling it to the sink.
+    /// Invalid events are rejected before touching the running aggregates.
+    #[allow(clippy::too_many_arguments)]
+    pub fn log_event(
+        &mut self,
+        stage: SerendipityStage,
+        agent: SerendipityAgent,
+        input: &str,
+        output: &str,
+        language: &str,
+        serendipity_score: f64,
+        confidence: f64,
+    ) -> SerenQaResult<()> {
+        let now = Utc::now();
+        let mut event = SerendipityEvent {
+            event_id: String::new(),
+            timestamp: now,
+            stage,
+            agent,
+            input: input.to_string(),
+            output: output.to_string(),
+            language: language.to_string(),
+            serendipity_score,
+            confidence,
+            metadata: HashMap::new(),
+            redaction: None,
+            knowledge_sources: Vec::new(),
+            attachments: Vec::new(),
+            benchmark: None,
+            contributor: None,
+        };
+        let checks = EventChecks {
+            stage_taxonomy: self.stage_taxonomy.as_ref(),
+            stage_policy: self.stage_policy.as_ref(),
+            redaction_rules: self.redaction_rules.as_ref(),
+            language_check: self.language_check.as_ref(),
+        };
+        let (ids, event_count) = (&self.ids, self.event_count);
+        let notes = checks.apply(
+            &mut event,
+            |stage| self.stages_seen.contains(stage),
+            self.last_event.as_ref().map(|e| &e.stage),
+        )?;
+        event.event_id = ids.event_id(event_count, event.timestamp);
+        self.annotations.extend(notes.into_iter().map(|kind| TraceAnnotation { event_id: event.event_id.clone(), kind }));
+
+        if !self.languages.contains(&event.language) {
+            self.languages.push(event.language.clone());
+        }
+
+        if let Some(prev_event) = &self.last_event {
+            let transition = SerendipityTransition::between(prev_event, &event);
+            self.hasher.transition(&transition);
+            if let Some(label) = language_transition_label(&transition) {
+                self.language_transitions.push(label);
+            }
+            self.sink.write_record(&TraceRecord::Transition(transition))?;
+        }
+
+        self.hasher.event(&event);
+        self.agents_seen.insert(event.agent.clone());
+        self.stages_seen.insert(event.stage.clone());
+        if let Some(discovery) = key_discovery(&event, self.key_discovery_threshold) {
+            self.key_discoveries.push(discovery);
+            self.discovery_entities.push(discovery_entities(&event));
+        }
+        credit_event(&mut self.knowledge_credits, &event);
+
+        self.event_count += 1;
+        self.aggregate.push(&event);
+        self.overall_serendipity = self.aggregate.value();
+
+        self.sink.write_record(&TraceRecord::Event(event.clone()))?;
+        #[cfg(feature = "metrics")]
+        crate::prometheus::RecorderMetrics::global().record_event(serendipity_score);
+        self.last_event = Some(event);
+        Ok(())
+    }
+
+    /// Compute provenance hash from the rolling hasher
+    pub fn compute_provenance_hash(&self) -> String {
+        // Streaming traces have no co-contributors
+        let mut hasher = self.hasher.clone();
+        hasher.co_contributors(&[]);
+        hasher.finish()
+    }
+
+    /// Fold memory trace from the running summaries.
+    /// Fails on an empty trace, which has no compression ratio.
+    pub fn fold_memory(&self) -> SerenQaResult<FoldedSerendipityTrace> {
+        if self.event_count == 0 {
+            return Err(SerenQaError::EmptyTrace(self.trace_id.clone()));
+        }
+        #[cfg(feature = "metrics")]
+        crate::prometheus::RecorderMetrics::global().record_fold();
+        let compression_ratio = (self.key_discoveries.len() as f64) / (self.event_count as f64);
+
+        Ok(FoldedSerendipityTrace {
+            trace_id: self.trace_id.clone(),
+            discovery_name: self.discovery_name.clone(),
+            total_events: self.event_count,
+            key_discoveries: self.key_discoveries.clone(),
+            key_discovery_threshold: self.key_discovery_threshold,
+            language_transitions: self.language_transitions.clone(),
+            overall_serendipity: self.overall_serendipity,
+            compression_ratio,
+            languages: self.languages.clone(),
+            partially_redacted: false,
+            redacted_events: 0,
+            uniqueness: self.uniqueness_breakdown(),
+            knowledge_sources: self.knowledge_credits.clone(),
+            // Duplicate detection needs every event; not tracked when streaming
+            dedup: DedupReport::default(),
+            entity_clusters: entity_clusters(&self.discovery_entities),
+            // Peaks are judged against every jump; not tracked when streaming
+            serendipity_peaks: Vec::new(),
+            subtraces: Vec::new(),
+        })
+    }
+
+    /// Get uniqueness score with its components
+    pub fn uniqueness_breakdown(&self) -> UniquenessBreakdown {
+        UniquenessBreakdown::with_type_counts(
+            self.agents_seen.len(),
+            AGENT_TYPE_COUNT + self.agents_seen.iter().filter(|agent| agent.is_custom()).count(),
+            self.languages.len(),
+            self.stages_seen.len(),
+            stage_type_count(self.stage_taxonomy.as_ref(), &self.stages_seen),
+        )
+    }
+
+    /// Get trace depth (number of events)
+    pub fn depth(&self) -> usize {
+        self.event_count
+    }
+
+    /// Flush the sink and return it
+    pub fn finish(mut self) -> std::io::Result<S> {
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+}
+
+impl SerendipityTrace {
+    /// Rebuild a trace from spilled records and the streaming trace header
+    pub fn from_records<I>(
+        trace_id: &str,
+        contributor_id: &str,
+        backend: &str,
+        discovery_name: &str,
+        records: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = TraceRecord>,
+    {
+        let mut trace = SerendipityTrace::new(contributor_id, backend, discovery_name);
+        trace.trace_id = trace_id.to_string();
+
+        for record in records {
+            match record {
+                TraceRecord::Event(event) => {
+                    if !trace.languages.contains(&event.language) {
+                        trace.languages.push(event.language.clone());
+                    }
+                    trace.events.push(event);
+                }
+                TraceRecord::Transition(transition) => trace.transitions.push(transition),
+                TraceRecord::Header(header) => header.apply(&mut trace),
+            }
+        }
+
+        trace.update_overall_serendipity();
+        trace
+    }
+
+    /// Write the trace as JSON lines: a header, then each event preceded by
+    /// its incoming transition
+    pub fn to_jsonl<W: Write>(&self, writer: W) -> SerenQaResult<()> {
+        let mut sink = JsonLinesSink::new(writer);
+        sink.write_record(&TraceRecord::Header(TraceHeader::of(self)))?;
+        for (i, event) in self.events.iter().enumerate() {
+            if let Some(transition) = i.checked_sub(1).and_then(|t| self.transitions.get(t)) {
+                sink.write_record(&TraceRecord::Transition(transition.clone()))?;
+            }
+            sink.write_record(&TraceRecord::Event(event.clone()))?;
+        }
+        sink.flush()?;
+        Ok(())
+    }
+
+    /// Rebuild a trace from JSON lines written by `to_jsonl` or a `JsonLinesSink`.
+    /// Without a header line (raw sink output), trace identifiers are left empty.
+    pub fn from_jsonl<R: BufRead>(reader: R) -> SerenQaResult<Self> {
+        let records = read_jsonl_records(reader).collect::<SerenQaResult<Vec<_>>>()?;
+        Ok(SerendipityTrace::from_records("", "", "", "", records))
+    }
+}
+
+/// Parse JSON-lines records one at a time, skipping blank lines
+pub fn read_jsonl_records<R: BufRead>(reader: R) -> impl Iterator<Item = SerenQaResult<TraceRecord>> {
+    reader
+        .lines()
+        .filter(|line| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream_sample<S: EventSink>(trace: &mut StreamingSerendipityTrace<S>) {
+        let steps = [
+            (SerendipityStage::Exploration, SerendipityAgent::Explorer, "en", 0.65),
+            (SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "id", 0.92),
+            (SerendipityStage::HypothesisFormation, SerendipityAgent::Translator, "en", 0.88),
+            (SerendipityStage::Validation, SerendipityAgent::Validator, "id", 0.5),
+        ];
+        for (i, (stage, agent, language, score)) in steps.into_iter().enumerate() {
+            trace
+                .log_event(stage, agent, &format!("input{}", i), &format!("output{}", i), language, score, 0.9)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_in_memory_trace() {
+        let mut streaming = StreamingSerendipityTrace::new(
+            "researcher1", "backend", "Discovery", JsonLinesSink::new(Vec::new()),
+        );
+        stream_sample(&mut streaming);
+
+        let hash = streaming.compute_provenance_hash();
+        let folded = streaming.fold_memory().unwrap();
+        let trace_id = streaming.trace_id.clone();
+        let bytes = streaming.finish().unwrap().into_inner();
+
+        let records = String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<TraceRecord>(line).unwrap())
+            .collect::<Vec<_>>();
+        let trace = SerendipityTrace::from_records(&trace_id, "researcher1", "backend", "Discovery", records);
+
+        assert_eq!(trace.events.len(), 4);
+        assert_eq!(trace.transitions.len(), 3);
+        assert_eq!(trace.compute_provenance_hash(), hash);
+
+        let expected = trace.fold_memory().unwrap();
+        assert_eq!(folded.key_discoveries, expected.key_discoveries);
+        assert_eq!(folded.language_transitions, expected.language_transitions);
+        assert_eq!(folded.languages, expected.languages);
+        assert_eq!(folded.uniqueness, expected.uniqueness);
+        assert!((folded.overall_serendipity - expected.overall_serendipity).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_jsonl_round_trip() {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        for (language, score) in [("en", 0.6), ("id", 0.9), ("en", 0.7)] {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", language, score, 0.8).unwrap();
+        }
+        trace.custom_metrics.insert("novelty".to_string(), 0.4);
+
+        let mut bytes = Vec::new();
+        trace.to_jsonl(&mut bytes).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        assert_eq!(text.lines().count(), 6);
+        assert!(text.lines().next().unwrap().contains("\"kind\":\"header\""));
+
+        let restored = SerendipityTrace::from_jsonl(text.as_bytes()).unwrap();
+        assert_eq!(restored.trace_id, trace.trace_id);
+        assert_eq!(restored.created_at, trace.created_at);
+        assert_eq!(restored.events.len(), 3);
+        assert_eq!(restored.custom_metrics, trace.custom_metrics);
+        assert_eq!(restored.compute_provenance_hash(), trace.compute_provenance_hash());
+
+        let truncated = format!("{}\n{{\"kind\":", text.lines().take(2).collect::<Vec<_>>().join("\n\n"));
+        assert!(matches!(SerendipityTrace::from_jsonl(truncated.as_bytes()), Err(SerenQaError::Json(_))));
+    }
+
+    #[test]
+    fn test_null_sink() {
+        let mut streaming = StreamingSerendipityTrace::new("researcher1", "backend", "Discovery", NullSink);
+        stream_sample(&mut streaming);
+        assert_eq!(streaming.depth(), 4);
+        assert_eq!(streaming.fold_memory().unwrap().key_discoveries.len(), 2);
+        assert_eq!(streaming.compute_provenance_hash().len(), 67);
+    }
+
+    #[test]
+    fn test_streaming_shares_event_checks() {
+        use crate::stage_policy::PolicyMode;
+        let rules = || RedactionRules::from_json(r#"[{"name": "codename", "fields": ["output"], "matcher": {"keywords": ["falcon"]}}]"#).unwrap();
+        let mut streaming = StreamingSerendipityTrace::new("researcher1", "backend", "Discovery", NullSink);
+        streaming.set_aggregation(AggregationStrategy::TopKMean { k: 2 }).unwrap();
+        streaming.set_insight_policy(InsightPolicy::Fixed { threshold: 0.6 }).unwrap();
+        streaming.set_stage_policy(StagePolicy::research_process(PolicyMode::Lenient));
+        streaming.set_redaction_rules(rules());
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.set_aggregation(AggregationStrategy::TopKMean { k: 2 });
+        trace.set_insight_policy(InsightPolicy::Fixed { threshold: 0.6 });
+        trace.set_stage_policy(StagePolicy::research_process(PolicyMode::Lenient));
+        trace.set_redaction_rules(rules());
+
+        let steps = [
+            (SerendipityStage::Exploration, "falcon route", 0.65),
+            (SerendipityStage::Publication, "preprint", 0.9),
+            (SerendipityStage::Validation, "checked", 0.5),
+        ];
+        for (stage, output, score) in steps {
+            streaming.log_event(stage.clone(), SerendipityAgent::Explorer, "in", output, "en", score, 0.8).unwrap();
+            trace.log_event(stage, SerendipityAgent::Explorer, "in", output, "en", score, 0.8).unwrap();
+        }
+        // One redaction and two out-of-order stages, noted the same way
+        let kinds = |notes: &[TraceAnnotation]| notes.iter().map(|note| note.kind.clone()).collect::<Vec<_>>();
+        assert_eq!(streaming.annotations().len(), 3);
+        assert_eq!(kinds(streaming.annotations()), kinds(&trace.annotations));
+        let (streamed, expected) = (streaming.fold_memory().unwrap(), trace.fold_memory().unwrap());
+        assert_eq!(streamed.overall_serendipity, expected.overall_serendipity);
+        assert_eq!(streamed.key_discoveries, expected.key_discoveries);
+        assert_eq!(streamed.key_discovery_threshold, 0.6);
+
+        // Settings that would need the scores already streamed are refused
+        assert!(matches!(streaming.set_aggregation(AggregationStrategy::Max), Err(SerenQaError::StreamingUnsupported(_))));
+        let mut strict = StreamingSerendipityTrace::new("researcher1", "backend", "Discovery", NullSink);
+        assert!(strict.set_insight_policy(InsightPolicy::Otsu).is_err());
+        strict.set_stage_policy(StagePolicy::research_process(PolicyMode::Strict));
+        let publication = strict.log_event(SerendipityStage::Publication, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8);
+        assert!(matches!(publication, Err(SerenQaError::StagePolicy(_))));
+        assert_eq!(strict.depth(), 0);
+    }
+
+    #[test]
+    fn test_language_tags_are_canonicalized_like_in_memory_traces() {
+        let mut streaming = StreamingSerendipityTrace::new("researcher1", "backend", "Discovery", NullSink);
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        for language in ["EN", "en"] {
+            streaming.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", language, 0.5, 0.8).unwrap();
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", language, 0.5, 0.8).unwrap();
+        }
+        assert_eq!(streaming.languages, vec!["en".to_string()]);
+        assert_eq!(streaming.languages, trace.languages);
+    }
+}
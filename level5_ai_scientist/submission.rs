@@ -0,0 +1,491 @@
+// -*- coding: utf-8 -*-
+//! Submission Pipeline with Structured Rejection Feedback
+//!
+//! Runs validation, lint, fraud, and eligibility checks over a submitted
+//! trace. A rejected submission yields a machine-readable `RejectionReport`
+//! with error codes, offending event IDs, and remediation hints, so
+//! automated pipelines can self-correct and resubmit.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use ed25519_dalek::VerifyingKey;
+use crate::serendipity_trace::SerendipityTrace;
+use crate::language_tag::is_valid_bcp47;
+use crate::trace_validator::TraceValidator;
+
+/// Stage of the pipeline that raised an issue
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionCategory {
+    /// Malformed event data
+    Validation,
+    /// Internally inconsistent trace structure
+    Lint,
+    /// Signs of tampering or score inflation
+    Fraud,
+    /// Trace does not meet benchmark requirements
+    Eligibility,
+}
+
+/// Machine-readable rejection code
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RejectionCode {
+    /// Trace has no events
+    EmptyTrace,
+    /// Serendipity or confidence outside [0, 1]
+    ScoreOutOfRange,
+    /// Language is not a well-formed BCP-47 tag
+    InvalidLanguageTag,
+    /// Two events share an ID
+    DuplicateEventId,
+    /// Transitions do not link consecutive events
+    BrokenTransitionChain,
+    /// Stored overall score disagrees with the events
+    OverallScoreMismatch,
+    /// Stored language list disagrees with the events
+    LanguageListMismatch,
+    /// Trace changed after it was signed
+    SignedHashMismatch,
+    /// Signature missing or invalid for the contributor key
+    InvalidSignature,
+    /// Identical content logged repeatedly
+    DuplicateContent,
+    /// Fewer events than required
+    TooFewEvents,
+    /// Fewer languages than required
+    TooFewLanguages,
+    /// Event timestamps go backwards
+    NonMonotonicTimestamps,
+    /// Event input or output is empty
+    EmptyEventContent,
+    /// Provenance hash differs from the one declared for the trace
+    ProvenanceHashMismatch,
+    /// Trace lacks stages the benchmark requires
+    MissingStages,
+}
+
+impl RejectionCode {
+    /// Pipeline stage the code belongs to
+    pub fn category(&self) -> RejectionCategory {
+        match self {
+            RejectionCode::EmptyTrace
+            | RejectionCode::ScoreOutOfRange
+            | RejectionCode::InvalidLanguageTag
+            | RejectionCode::NonMonotonicTimestamps
+            | RejectionCode::EmptyEventContent => RejectionCategory::Validation,
+            RejectionCode::DuplicateEventId
+            | RejectionCode::BrokenTransitionChain
+            | RejectionCode::OverallScoreMismatch
+            | RejectionCode::LanguageListMismatch => RejectionCategory::Lint,
+            RejectionCode::SignedHashMismatch
+            | RejectionCode::InvalidSignature
+            | RejectionCode::DuplicateContent
+            | RejectionCode::ProvenanceHashMismatch => RejectionCategory::Fraud,
+            RejectionCode::TooFewEvents
+            | RejectionCode::TooFewLanguages
+            | RejectionCode::MissingStages => RejectionCategory::Eligibility,
+        }
+    }
+
+    /// Suggested fix for the code
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            RejectionCode::EmptyTrace => "log at least one event before submitting",
+            RejectionCode::ScoreOutOfRange => "clamp serendipity and confidence into [0, 1]",
+            RejectionCode::InvalidLanguageTag => "use BCP-47 language tags such as \"en\" or \"zh-Hant\"",
+            RejectionCode::DuplicateEventId => "let the trace assign event IDs instead of setting them manually",
+            RejectionCode::BrokenTransitionChain => "rebuild transitions from consecutive events",
+            RejectionCode::OverallScoreMismatch => "recompute the overall score with the trace's aggregation strategy",
+            RejectionCode::LanguageListMismatch => "rebuild the language list from the logged events",
+            RejectionCode::SignedHashMismatch => "re-sign the trace after the final edit",
+            RejectionCode::InvalidSignature => "sign the trace with the contributor's registered key",
+            RejectionCode::DuplicateContent => "remove repeated events with identical input and output",
+            RejectionCode::TooFewEvents => "extend the trace with more discovery steps",
+            RejectionCode::TooFewLanguages => "include events in additional languages",
+            RejectionCode::NonMonotonicTimestamps => "log events in the order they happened",
+            RejectionCode::EmptyEventContent => "record the input and output of every event",
+            RejectionCode::ProvenanceHashMismatch => "resubmit the trace exactly as hashed in the manifest",
+            RejectionCode::MissingStages => "log events for every stage the benchmark requires",
+        }
+    }
+}
+
+/// Single problem found in a submission
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RejectionIssue {
+    /// Error code
+    pub code: RejectionCode,
+    /// Pipeline stage
+    pub category: RejectionCategory,
+    /// Events responsible for the issue (empty for trace-level issues)
+    pub event_ids: Vec<String>,
+    /// Human-readable description
+    pub message: String,
+    /// Suggested fix
+    pub remediation: String,
+}
+
+impl RejectionIssue {
+    pub(crate) fn new(code: RejectionCode, event_ids: Vec<String>, message: String) -> Self {
+        Self {
+            code,
+            category: code.category(),
+            event_ids,
+            message,
+            remediation: code.remediation().to_string(),
+        }
+    }
+}
+
+/// Machine-readable reasons a submission was rejected
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct RejectionReport {
+    /// Rejected trace
+    pub trace_id: String,
+    /// Problems found, in pipeline order
+    pub issues: Vec<RejectionIssue>,
+}
+
+impl RejectionReport {
+    /// Check if no issues were found
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Distinct error codes, in order of first appearance
+    pub fn codes(&self) -> Vec<RejectionCode> {
+        let mut codes = Vec::new();
+        for issue in &self.issues {
+            if !codes.contains(&issue.code) {
+                codes.push(issue.code);
+            }
+        }
+        codes
+    }
+
+    /// Check if any issue belongs to a category
+    pub fn has_category(&self, category: RejectionCategory) -> bool {
+        self.issues.iter().any(|i| i.category == category)
+    }
+
+    /// All offending event IDs, deduplicated
+    pub fn offending_event_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        for id in self.issues.iter().flat_map(|i| &i.event_ids) {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+        ids
+    }
+}
+
+impl std::fmt::Display for RejectionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "submission {} rejected with {} issues", self.trace_id, self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  [{:?}] {}", issue.code, issue.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RejectionReport {}
+
+/// Proof of an accepted submission
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubmissionReceipt {
+    /// Accepted trace
+    pub trace_id: String,
+    /// Provenance hash at acceptance time
+    pub provenance_hash: String,
+}
+
+/// Checks run on every submitted trace
+#[derive(Debug, Clone)]
+pub struct SubmissionPipeline {
+    /// Minimum number of events
+    pub min_events: usize,
+    /// Minimum number of distinct languages
+    pub min_languages: usize,
+    /// Contributor key required to have signed the trace
+    pub verifying_key: Option<VerifyingKey>,
+    /// Tolerance for the overall score consistency check
+    pub score_tolerance: f64,
+    /// Quality gate run after the built-in checks
+    pub validator: Option<TraceValidator>,
+}
+
+impl SubmissionPipeline {
+    /// Create a pipeline with no eligibility requirements
+    pub fn new() -> Self {
+        Self {
+            min_events: 1,
+            min_languages: 1,
+            verifying_key: None,
+            score_tolerance: 1e-9,
+            validator: None,
+        }
+    }
+
+    /// Require a minimum trace depth
+    pub fn with_min_events(mut self, min_events: usize) -> Self {
+        self.min_events = min_events;
+        self
+    }
+
+    /// Require a minimum number of languages
+    pub fn with_min_languages(mut self, min_languages: usize) -> Self {
+        self.min_languages = min_languages;
+        self
+    }
+
+    /// Require a valid signature from the contributor key
+    pub fn with_verifying_key(mut self, key: VerifyingKey) -> Self {
+        self.verifying_key = Some(key);
+        self
+    }
+
+    /// Also run a quality gate; its issues are added unless the built-in
+    /// checks already reported the same code
+    pub fn with_validator(mut self, validator: TraceValidator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Run every check and collect the issues found
+    pub fn check(&self, trace: &SerendipityTrace) -> RejectionReport {
+        let mut issues = Vec::new();
+        self.check_validation(trace, &mut issues);
+        self.check_lint(trace, &mut issues);
+        self.check_fraud(trace, &mut issues);
+        self.check_eligibility(trace, &mut issues);
+        if let Some(validator) = &self.validator {
+            for issue in validator.issues(trace, None) {
+                if !issues.iter().any(|i| i.code == issue.code) {
+                    issues.push(issue);
+                }
+            }
+        }
+        RejectionReport {
+            trace_id: trace.trace_id.clone(),
+            issues,
+        }
+    }
+
+    /// Accept the trace or return why it was rejected
+    pub fn submit(&self, trace: &SerendipityTrace) -> Result<SubmissionReceipt, RejectionReport> {
+        let report = self.check(trace);
+        if !report.is_empty() {
+            return Err(report);
+        }
+        Ok(SubmissionReceipt {
+            trace_id: trace.trace_id.clone(),
+            provenance_hash: trace.compute_provenance_hash(),
+        })
+    }
+
+    fn check_validation(&self, trace: &SerendipityTrace, issues: &mut Vec<RejectionIssue>) {
+        if trace.events.is_empty() {
+            issues.push(RejectionIssue::new(RejectionCode::EmptyTrace, Vec::new(), "trace has no events".to_string()));
+        }
+
+        let out_of_range: Vec<String> = trace.events
+            .iter()
+            .filter(|e| !(0.0..=1.0).contains(&e.serendipity_score) || !(0.0..=1.0).contains(&e.confidence))
+            .map(|e| e.event_id.clone())
+            .collect();
+        if !out_of_range.is_empty() {
+            issues.push(RejectionIssue::new(
+                RejectionCode::ScoreOutOfRange,
+                out_of_range,
+                "event scores outside [0, 1]".to_string(),
+            ));
+        }
+
+        let bad_tags: Vec<String> = trace.events
+            .iter()
+            .filter(|e| !is_valid_bcp47(&e.language))
+            .map(|e| e.event_id.clone())
+            .collect();
+        if !bad_tags.is_empty() {
+            issues.push(RejectionIssue::new(
+                RejectionCode::InvalidLanguageTag,
+                bad_tags,
+                "events with malformed language tags".to_string(),
+            ));
+        }
+    }
+
+    fn check_lint(&self, trace: &SerendipityTrace, issues: &mut Vec<RejectionIssue>) {
+        let mut seen = HashSet::new();
+        let duplicates: Vec<String> = trace.events
+            .iter()
+            .filter(|e| !seen.insert(e.event_id.as_str()))
+            .map(|e| e.event_id.clone())
+            .collect();
+        if !duplicates.is_empty() {
+            issues.push(RejectionIssue::new(
+                RejectionCode::DuplicateEventId,
+                duplicates,
+                "event IDs are not unique".to_string(),
+            ));
+        }
+
+        let expected_links = trace.events.len().saturating_sub(1);
+        let broken: Vec<String> = trace.events
+            .windows(2)
+            .zip(trace.transitions.iter().map(Some).chain(std::iter::repeat(None)))
+            .filter(|(pair, t)| {
+                !matches!(t, Some(t) if t.from_event == pair[0].event_id && t.to_event == pair[1].event_id)
+            })
+            .map(|(pair, _)| pair[1].event_id.clone())
+            .collect();
+        if !broken.is_empty() || trace.transitions.len() != expected_links {
+            issues.push(RejectionIssue::new(
+                RejectionCode::BrokenTransitionChain,
+                broken,
+                format!(
+                    "{} transitions for {} events; expected {}",
+                    trace.transitions.len(),
+                    trace.events.len(),
+                    expected_links
+                ),
+            ));
+        }
+
+        let expected_score = trace.aggregation.aggregate(&trace.events);
+        if (trace.overall_serendipity - expected_score).abs() > self.score_tolerance {
+            issues.push(RejectionIssue::new(
+                RejectionCode::OverallScoreMismatch,
+                Vec::new(),
+                format!("overall score {} but events aggregate to {}", trace.overall_serendipity, expected_score),
+            ));
+        }
+
+        let event_languages: HashSet<&str> = trace.events.iter().map(|e| e.language.as_str()).collect();
+        let listed_languages: HashSet<&str> = trace.languages.iter().map(|l| l.as_str()).collect();
+        if event_languages != listed_languages {
+            issues.push(RejectionIssue::new(
+                RejectionCode::LanguageListMismatch,
+                Vec::new(),
+                "language list does not match event languages".to_string(),
+            ));
+        }
+    }
+
+    fn check_fraud(&self, trace: &SerendipityTrace, issues: &mut Vec<RejectionIssue>) {
+        if let Some(signature) = &trace.signature {
+            if !trace.verify_provenance_hash(&signature.signed_hash) {
+                issues.push(RejectionIssue::new(
+                    RejectionCode::SignedHashMismatch,
+                    Vec::new(),
+                    "provenance hash changed after signing".to_string(),
+                ));
+            }
+        }
+        if let Some(key) = &self.verifying_key {
+            if !trace.verify_signature(key) {
+                issues.push(RejectionIssue::new(
+                    RejectionCode::InvalidSignature,
+                    Vec::new(),
+                    "trace is not signed by the contributor key".to_string(),
+                ));
+            }
+        }
+
+        let mut seen_content = HashSet::new();
+        let repeated: Vec<String> = trace.events
+            .iter()
+            .filter(|e| !seen_content.insert(e.content_hash()))
+            .map(|e| e.event_id.clone())
+            .collect();
+        if !repeated.is_empty() {
+            issues.push(RejectionIssue::new(
+                RejectionCode::DuplicateContent,
+                repeated,
+                "events repeat earlier input and output".to_string(),
+            ));
+        }
+    }
+
+    fn check_eligibility(&self, trace: &SerendipityTrace, issues: &mut Vec<RejectionIssue>) {
+        if !trace.events.is_empty() && trace.events.len() < self.min_events {
+            issues.push(RejectionIssue::new(
+                RejectionCode::TooFewEvents,
+                Vec::new(),
+                format!("{} events; at least {} required", trace.events.len(), self.min_events),
+            ));
+        }
+        if trace.languages.len() < self.min_languages {
+            issues.push(RejectionIssue::new(
+                RejectionCode::TooFewLanguages,
+                Vec::new(),
+                format!("{} languages; at least {} required", trace.languages.len(), self.min_languages),
+            ));
+        }
+    }
+}
+
+impl Default for SubmissionPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Discovery");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "input1", "output1", "en", 0.7, 0.9).unwrap();
+        trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "input2", "output2", "id", 0.9, 0.8).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_accepts_valid_trace() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut trace = sample_trace();
+        trace.sign(&key);
+
+        let pipeline = SubmissionPipeline::new()
+            .with_min_events(2)
+            .with_min_languages(2)
+            .with_verifying_key(key.verifying_key());
+        let receipt = pipeline.submit(&trace).unwrap();
+        assert_eq!(receipt.provenance_hash, trace.compute_provenance_hash());
+    }
+
+    #[test]
+    fn test_rejection_report() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut trace = sample_trace();
+        trace.sign(&key);
+        trace.events[1].serendipity_score = 1.5;
+        trace.events[1].input = "input1".to_string();
+        trace.events[1].output = "output1".to_string();
+
+        let report = SubmissionPipeline::new().with_min_events(3).submit(&trace).unwrap_err();
+        assert_eq!(
+            report.codes(),
+            vec![
+                RejectionCode::ScoreOutOfRange,
+                RejectionCode::OverallScoreMismatch,
+                RejectionCode::SignedHashMismatch,
+                RejectionCode::DuplicateContent,
+                RejectionCode::TooFewEvents,
+            ]
+        );
+        assert!(report.has_category(RejectionCategory::Fraud));
+        assert_eq!(report.offending_event_ids(), vec![trace.events[1].event_id.clone()]);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"SCORE_OUT_OF_RANGE\""));
+        assert!(json.contains("\"remediation\""));
+    }
+}
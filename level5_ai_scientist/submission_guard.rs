@@ -0,0 +1,367 @@
+// -*- coding: utf-8 -*-
+//! Duplicate-Submission Guard for the Leaderboard
+//!
+//! A contributor can resubmit someone else's trace (or their own) with minor
+//! edits and collect credit twice. A `SubmissionGuard` attached to a
+//! `LanguageAwareLeaderboard` keeps a MinHash signature of every credited
+//! trace's shingled event outputs. Each new trace is compared against them;
+//! one whose estimated content similarity reaches the threshold is flagged
+//! for review or rejected, depending on the guard's action. Signatures are
+//! kept in leaderboard snapshots, so the history survives restarts.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::ContributorStats::{LanguageAwareLeaderboard, LanguageAwareContributorStats};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Estimated similarity at or above which a submission is suspicious
+pub const DEFAULT_DUPLICATE_THRESHOLD: f64 = 0.8;
+
+/// Hash functions per MinHash signature
+pub const DEFAULT_NUM_HASHES: usize = 128;
+
+/// Words per shingle
+pub const DEFAULT_SHINGLE_SIZE: usize = 3;
+
+/// What the guard does with a suspiciously similar submission
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardAction {
+    /// Credit the trace and record the match for review
+    #[default]
+    Flag,
+    /// Refuse to credit the trace
+    Reject,
+}
+
+/// MinHash signature of a trace's shingled event outputs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MinHashSignature(Vec<u64>);
+
+/// SplitMix64 finalizer, deriving independent hashes from one base hash
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+impl MinHashSignature {
+    /// Signature over `num_hashes` hash functions of a set of shingles
+    /// (empty if there are no shingles)
+    pub fn from_shingles(shingles: &HashSet<String>, num_hashes: usize) -> Self {
+        if shingles.is_empty() {
+            return Self(Vec::new());
+        }
+        let mut minimums = vec![u64::MAX; num_hashes];
+        for shingle in shingles {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            shingle.hash(&mut hasher);
+            let base = hasher.finish();
+            for (i, minimum) in minimums.iter_mut().enumerate() {
+                *minimum = (*minimum).min(mix(base ^ mix(i as u64)));
+            }
+        }
+        Self(minimums)
+    }
+
+    /// Estimated Jaccard similarity of the underlying shingle sets
+    /// (0 if either is empty or the signatures have different lengths)
+    pub fn similarity(&self, other: &MinHashSignature) -> f64 {
+        if self.0.is_empty() || self.0.len() != other.0.len() {
+            return 0.0;
+        }
+        let equal = self.0.iter().zip(&other.0).filter(|(a, b)| a == b).count();
+        equal as f64 / self.0.len() as f64
+    }
+}
+
+/// Earlier submission remembered by the guard
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubmittedTrace {
+    /// Trace ID
+    pub trace_id: String,
+    /// Contributor credited with the trace
+    pub contributor_id: String,
+    /// Signature of the trace's outputs
+    pub signature: MinHashSignature,
+}
+
+/// New submission resembling an earlier one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DuplicateMatch {
+    /// Submitted trace
+    pub trace_id: String,
+    /// Contributor submitting it
+    pub contributor_id: String,
+    /// Earlier trace it resembles
+    pub matched_trace_id: String,
+    /// Contributor credited with the earlier trace
+    pub matched_contributor_id: String,
+    /// Estimated content similarity
+    pub similarity: f64,
+}
+
+/// Outcome of crediting a trace
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubmissionVerdict {
+    /// Credited with no similar earlier submission
+    Accepted,
+    /// Credited, but flagged as resembling an earlier submission
+    Flagged(DuplicateMatch),
+}
+
+/// Checks new submissions against earlier ones
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubmissionGuard {
+    /// Handling of suspicious submissions
+    pub action: GuardAction,
+    /// Estimated similarity at or above which a submission is suspicious
+    pub threshold: f64,
+    /// Hash functions per signature
+    pub num_hashes: usize,
+    /// Words per shingle
+    pub shingle_size: usize,
+    /// Credited submissions, in order
+    #[serde(default)]
+    submissions: Vec<SubmittedTrace>,
+    /// Matches flagged so far
+    #[serde(default)]
+    flagged: Vec<DuplicateMatch>,
+}
+
+impl SubmissionGuard {
+    /// Guard with the default threshold and signature parameters
+    pub fn new(action: GuardAction) -> Self {
+        Self {
+            action,
+            threshold: DEFAULT_DUPLICATE_THRESHOLD,
+            num_hashes: DEFAULT_NUM_HASHES,
+            shingle_size: DEFAULT_SHINGLE_SIZE,
+            submissions: Vec::new(),
+            flagged: Vec::new(),
+        }
+    }
+
+    /// Treat submissions at or above `threshold` as suspicious
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Use `num_hashes` hash functions per signature
+    pub fn num_hashes(mut self, num_hashes: usize) -> Self {
+        self.num_hashes = num_hashes.max(1);
+        self
+    }
+
+    /// Shingle outputs into runs of `words` words
+    pub fn shingle_size(mut self, words: usize) -> Self {
+        self.shingle_size = words.max(1);
+        self
+    }
+
+    /// Word shingles of each event output (an output shorter than the
+    /// shingle size is one shingle)
+    fn shingles(&self, trace: &SerendipityTrace) -> HashSet<String> {
+        let mut shingles = HashSet::new();
+        for event in &trace.events {
+            let words: Vec<String> = event.output
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+                .collect();
+            if words.is_empty() {
+                continue;
+            }
+            let size = self.shingle_size.min(words.len());
+            shingles.extend(words.windows(size).map(|window| window.join(" ")));
+        }
+        shingles
+    }
+
+    /// Signature of a trace under this guard's parameters
+    pub fn signature(&self, trace: &SerendipityTrace) -> MinHashSignature {
+        MinHashSignature::from_shingles(&self.shingles(trace), self.num_hashes)
+    }
+
+    /// Most similar earlier submission at or above the threshold
+    pub fn check(&self, trace: &SerendipityTrace) -> Option<DuplicateMatch> {
+        let signature = self.signature(trace);
+        self.submissions
+            .iter()
+            .map(|submitted| (submitted, signature.similarity(&submitted.signature)))
+            .filter(|(_, similarity)| *similarity >= self.threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(submitted, similarity)| DuplicateMatch {
+                trace_id: trace.trace_id.clone(),
+                contributor_id: trace.contributor_id.clone(),
+                matched_trace_id: submitted.trace_id.clone(),
+                matched_contributor_id: submitted.contributor_id.clone(),
+                similarity,
+            })
+    }
+
+    /// Remember a credited trace
+    pub fn record(&mut self, trace: &SerendipityTrace) {
+        let signature = self.signature(trace);
+        self.submissions.push(SubmittedTrace {
+            trace_id: trace.trace_id.clone(),
+            contributor_id: trace.contributor_id.clone(),
+            signature,
+        });
+    }
+
+    /// Credited submissions, in order
+    pub fn submissions(&self) -> &[SubmittedTrace] {
+        &self.submissions
+    }
+
+    /// Matches flagged so far, in order
+    pub fn flagged(&self) -> &[DuplicateMatch] {
+        &self.flagged
+    }
+}
+
+impl LanguageAwareLeaderboard {
+    /// Check traces credited from now on against earlier submissions
+    pub fn set_submission_guard(&mut self, guard: SubmissionGuard) {
+        self.guard = Some(guard);
+    }
+
+    /// Attached duplicate-submission guard
+    pub fn submission_guard(&self) -> Option<&SubmissionGuard> {
+        self.guard.as_ref()
+    }
+
+    /// Credit a trace to its contributors' statistics, splitting team credit
+    /// by the leaderboard's `CreditSplit`. With a guard attached, a trace
+    /// resembling an earlier submission is flagged, or rejected with
+    /// `SerenQaError::DuplicateSubmission`. Observers are notified once,
+    /// after every member has been credited.
+    pub fn credit_trace(
+        &mut self,
+        trace: &SerendipityTrace,
+        alignment_score: f64,
+        translation_quality: f64,
+    ) -> SerenQaResult<SubmissionVerdict> {
+        let duplicate = self.guard.as_ref().and_then(|guard| guard.check(trace));
+        if let (Some(duplicate), Some(GuardAction::Reject)) = (&duplicate, self.guard.as_ref().map(|g| g.action)) {
+            return Err(SerenQaError::DuplicateSubmission {
+                trace_id: duplicate.trace_id.clone(),
+                matched_trace_id: duplicate.matched_trace_id.clone(),
+                similarity: duplicate.similarity,
+            });
+        }
+
+        // Validate every member's update before changing any statistics
+        let team = trace.is_team_trace();
+        let counts_discovery = !self.approval_required || trace.is_approved();
+        let mut updates = Vec::new();
+        for (contributor, share) in trace.credit_shares(self.credit_split) {
+            let mut stats = self.get(&contributor)
+                .cloned()
+                .unwrap_or_else(|| LanguageAwareContributorStats::new(&contributor));
+            stats.add_trace_at(
+                trace.created_at,
+                trace.depth(),
+                trace.uniqueness_score(),
+                trace.overall_serendipity,
+                trace.languages.clone(),
+                alignment_score,
+                translation_quality,
+            )?;
+            if counts_discovery {
+                stats.add_discovery(&trace.discovery_name);
+            }
+            if team {
+                stats.team_traces += 1;
+                stats.team_credit += share;
+            }
+            updates.push(stats);
+        }
+        self.observed(|leaderboard| {
+            for stats in updates {
+                leaderboard.contributors.insert(stats.contributor_id.clone(), stats);
+            }
+        });
+        if team {
+            self.record_team_trace(trace);
+        }
+
+        if let Some(guard) = self.guard.as_mut() {
+            guard.record(trace);
+            if let Some(duplicate) = &duplicate {
+                guard.flagged.push(duplicate.clone());
+            }
+        }
+        Ok(duplicate.map_or(SubmissionVerdict::Accepted, SubmissionVerdict::Flagged))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn trace(contributor: &str, outputs: &[&str]) -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new(contributor, "backend", "Journavx");
+        for output in outputs {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", output, "en", 0.6, 0.8).unwrap();
+        }
+        trace
+    }
+
+    const ORIGINAL: &[&str] = &[
+        "Quantum walk on the road graph finds shorter delivery routes",
+        "Javanese star compass suggests a heuristic for the walk coin",
+        "Benchmark on Jakarta traffic shows routes improve by twelve percent",
+    ];
+
+    #[test]
+    fn test_minhash_estimates_similarity() {
+        let guard = SubmissionGuard::new(GuardAction::Flag);
+        let original = guard.signature(&trace("researcher1", ORIGINAL));
+        assert_eq!(original.similarity(&original), 1.0);
+
+        let mut edited = ORIGINAL.to_vec();
+        edited[2] = "Benchmark on Jakarta traffic shows routes improve by 12 percent";
+        let near = guard.signature(&trace("researcher2", &edited)).similarity(&original);
+        assert!(near > 0.6 && near < 1.0, "{}", near);
+
+        let unrelated = guard.signature(&trace("researcher3", &["Tidal model fitted to harbour sensor data"]));
+        assert!(unrelated.similarity(&original) < 0.1);
+        let empty = guard.signature(&trace("researcher3", &[]));
+        assert_eq!(empty.similarity(&empty), 0.0);
+    }
+
+    #[test]
+    fn test_guard_flags_or_rejects_resubmissions() {
+        let mut leaderboard = LanguageAwareLeaderboard::new();
+        leaderboard.set_submission_guard(SubmissionGuard::new(GuardAction::Flag).threshold(0.9));
+        let original = trace("researcher1", ORIGINAL);
+        assert_eq!(leaderboard.credit_trace(&original, 0.8, 0.8).unwrap(), SubmissionVerdict::Accepted);
+
+        let copy = trace("researcher2", ORIGINAL);
+        let SubmissionVerdict::Flagged(duplicate) = leaderboard.credit_trace(&copy, 0.8, 0.8).unwrap() else {
+            panic!("copy was not flagged");
+        };
+        assert_eq!(duplicate.matched_trace_id, original.trace_id);
+        assert_eq!(duplicate.matched_contributor_id, "researcher1");
+        assert_eq!(leaderboard.get("researcher2").unwrap().total_traces, 1);
+
+        let restored = LanguageAwareLeaderboard::from_json(&leaderboard.to_json().unwrap()).unwrap();
+        let mut guard = restored.submission_guard().unwrap().clone();
+        assert_eq!((guard.submissions().len(), guard.flagged().len()), (2, 1));
+
+        guard.action = GuardAction::Reject;
+        let mut strict = restored;
+        strict.set_submission_guard(guard);
+        let err = strict.credit_trace(&trace("researcher3", ORIGINAL), 0.8, 0.8).unwrap_err();
+        assert!(matches!(err, SerenQaError::DuplicateSubmission { similarity, .. } if similarity == 1.0));
+        assert!(strict.get("researcher3").is_none());
+        assert_eq!(strict.submission_guard().unwrap().submissions().len(), 2);
+    }
+}
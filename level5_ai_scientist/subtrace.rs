@@ -0,0 +1,156 @@
+// -*- coding: utf-8 -*-
+//! Nested Sub-Traces
+//!
+//! When the MetaOrchestrator delegates a sub-investigation, its events belong
+//! to their own line of work rather than the parent's linear trace.
+//! `spawn_subtrace` attaches a child trace to the parent event that started
+//! it; children can spawn children of their own. Folding is recursive: the
+//! parent's fold carries each child's fold (children with no events yet are
+//! left out), and the provenance hash covers every child's hash, so the
+//! signature over a parent vouches for the whole tree. Traces without
+//! children keep their hashes.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::{FoldedSerendipityTrace, SerendipityTrace};
+use crate::error::{SerenQaError, SerenQaResult};
+
+/// Child trace attached to the parent event that spawned it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subtrace {
+    /// Parent event the sub-investigation was spawned from
+    pub parent_event_id: String,
+    pub trace: SerendipityTrace,
+}
+
+/// Fold of a child trace, under its parent's fold
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FoldedSubtrace {
+    /// Parent event the sub-investigation was spawned from
+    pub parent_event_id: String,
+    pub folded: FoldedSerendipityTrace,
+}
+
+impl SerendipityTrace {
+    /// Start a child trace for work delegated from the event `event_id`.
+    /// The child shares the contributor, backend, discovery name, clock, ID
+    /// generator, stage taxonomy, and redaction rules.
+    pub fn spawn_subtrace(&mut self, event_id: &str) -> SerenQaResult<&mut SerendipityTrace> {
+        if !self.events.iter().any(|event| event.event_id == event_id) {
+            return Err(SerenQaError::UnknownEvent(event_id.to_string()));
+        }
+        let mut child = SerendipityTrace::new(&self.contributor_id, &self.backend, &self.discovery_name);
+        child.clock = self.clock.clone();
+        child.ids = self.ids.clone();
+        child.created_at = self.clock.now();
+        child.trace_id = self.ids.trace_id(&self.contributor_id, child.created_at);
+        child.stage_taxonomy = self.stage_taxonomy.clone();
+        child.redaction_rules = self.redaction_rules.clone();
+        child.visibility = self.visibility.clone();
+        self.subtraces.push(Subtrace { parent_event_id: event_id.to_string(), trace: child });
+        Ok(&mut self.subtraces.last_mut().expect("subtrace was just pushed").trace)
+    }
+
+    /// Child traces spawned from the event `event_id`
+    pub fn subtraces_of<'a>(&'a self, event_id: &'a str) -> impl Iterator<Item = &'a SerendipityTrace> + 'a {
+        self.subtraces
+            .iter()
+            .filter(move |subtrace| subtrace.parent_event_id == event_id)
+            .map(|subtrace| &subtrace.trace)
+    }
+
+    /// Events in the trace and all its descendants
+    pub fn tree_event_count(&self) -> usize {
+        self.events.len() + self.subtraces.iter().map(|subtrace| subtrace.trace.tree_event_count()).sum::<usize>()
+    }
+
+    /// Folds of the child traces that have events, in spawn order
+    pub(crate) fn fold_subtraces(&self) -> Vec<FoldedSubtrace> {
+        self.subtraces
+            .iter()
+            .filter_map(|subtrace| {
+                let folded = subtrace.trace.fold_memory().ok()?;
+                Some(FoldedSubtrace { parent_event_id: subtrace.parent_event_id.clone(), folded })
+            })
+            .collect()
+    }
+}
+
+impl FoldedSerendipityTrace {
+    /// Events in the folded trace and all its descendants
+    pub fn tree_events(&self) -> usize {
+        self.total_events + self.subtraces.iter().map(|subtrace| subtrace.folded.tree_events()).sum::<usize>()
+    }
+
+    /// Key discoveries of the folded trace and all its descendants, parents first
+    pub fn tree_key_discoveries(&self) -> Vec<String> {
+        let mut discoveries = self.key_discoveries.clone();
+        for subtrace in &self.subtraces {
+            discoveries.extend(subtrace.folded.tree_key_discoveries());
+        }
+        discoveries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityAgent, SerendipityStage};
+
+    fn log(trace: &mut SerendipityTrace, output: &str, serendipity: f64) -> String {
+        trace
+            .log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", output, "en", serendipity, 0.8)
+            .unwrap()
+            .event_id
+            .clone()
+    }
+
+    #[test]
+    fn test_subtraces_fold_into_parent() {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        let delegated = log(&mut trace, "delegate batik survey", 0.4);
+        log(&mut trace, "parent continues", 0.3);
+
+        let child = trace.spawn_subtrace(&delegated).unwrap();
+        log(child, "batik motif matches map", 0.9);
+        let grandchild_parent = log(child, "follow up", 0.2);
+        let grandchild = child.spawn_subtrace(&grandchild_parent).unwrap();
+        log(grandchild, "route motif confirmed", 0.85);
+        trace.spawn_subtrace(&delegated).unwrap();
+
+        assert_eq!(trace.events.len(), 2);
+        assert_eq!(trace.tree_event_count(), 5);
+        assert_eq!(trace.subtraces_of(&delegated).count(), 2);
+        assert!(matches!(trace.spawn_subtrace("missing"), Err(SerenQaError::UnknownEvent(_))));
+
+        let folded = trace.fold_memory().unwrap();
+        // The empty second child is left out
+        assert_eq!(folded.subtraces.len(), 1);
+        assert_eq!(folded.subtraces[0].parent_event_id, delegated);
+        assert_eq!(folded.tree_events(), 5);
+        assert_eq!(folded.tree_key_discoveries().len(), 2);
+        assert_eq!(trace.fold_memory_cached().unwrap(), &folded);
+        let restored: SerendipityTrace = serde_json::from_str(&serde_json::to_string(&trace).unwrap()).unwrap();
+        assert_eq!(restored.fold_memory().unwrap(), folded);
+
+        // Logging into a child after the parent was folded still shows up
+        log(&mut trace.subtraces[1].trace, "late child note", 0.5);
+        assert_eq!(trace.fold_memory_cached().unwrap().subtraces.len(), 2);
+    }
+
+    #[test]
+    fn test_provenance_hash_covers_the_tree() {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        let delegated = log(&mut trace, "delegate", 0.4);
+        let flat = trace.compute_provenance_hash();
+
+        log(trace.spawn_subtrace(&delegated).unwrap(), "child finding", 0.7);
+        let tree = trace.compute_provenance_hash();
+        assert_ne!(tree, flat);
+        assert_eq!(trace.provenance_hash(), tree);
+        assert!(trace.verify_provenance_hash(&tree));
+
+        // Editing a descendant changes the parent's hash
+        trace.subtraces[0].trace.events[0].serendipity_score = 0.1;
+        assert!(!trace.verify_provenance_hash(&tree));
+    }
+}
@@ -0,0 +1,22 @@
+// -*- coding: utf-8 -*-
+//! Golden-file regression test for scoring outputs
+//!
+//! Fails when folded outputs, scores, or hashes of the canonical traces drift
+//! from `tests/golden/`. After an intentional scoring change, re-bless with
+//! `SEREN_BLESS=1 cargo test --test test_golden` or `seren golden --bless`.
+
+use std::path::Path;
+use level5_ai_scientist::golden::{check_golden, BLESS_ENV_VAR};
+
+#[test]
+fn test_scoring_outputs_match_golden_files() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let bless = std::env::var_os(BLESS_ENV_VAR).is_some();
+
+    let report = check_golden(&dir, bless).unwrap();
+    assert!(
+        report.is_ok(),
+        "scoring outputs changed:\n  {}\nre-bless if intentional",
+        report.failures().join("\n  ")
+    );
+}
@@ -22,7 +22,7 @@ fn test_complete_serendipity_workflow() {
         "en",
         0.7,
         0.85,
-    );
+    ).unwrap();
     
     trace.log_event(
         SerendipityStage::UnexpectedConnection,
@@ -32,7 +32,7 @@ fn test_complete_serendipity_workflow() {
         "id",
         0.92,
         0.88,
-    );
+    ).unwrap();
     
     // Verify trace
     assert_eq!(trace.events.len(), 2);
@@ -41,10 +41,10 @@ fn test_complete_serendipity_workflow() {
     
     // Compute provenance
     let hash = trace.compute_provenance_hash();
-    assert_eq!(hash.len(), 64);
+    assert_eq!(hash.len(), 67);
     
     // Fold memory
-    let folded = trace.fold_memory();
+    let folded = trace.fold_memory().unwrap();
     assert!(folded.compression_ratio > 0.0);
     assert_eq!(folded.total_events, 2);
 }
@@ -100,7 +100,7 @@ fn test_multilingual_memory_folding() {
     event2.set_alignment_score(0.88);
     
     let events = vec![event1, event2];
-    let fold = folder.fold_memory("trace1", &events);
+    let fold = folder.fold_memory("trace1", &events).unwrap();
     
     assert_eq!(fold.total_events, 2);
     assert!(fold.compression_ratio > 0.0);
@@ -119,7 +119,7 @@ fn test_contributor_statistics() {
         vec!["en".to_string(), "id".to_string()],
         0.88,
         0.90,
-    );
+    ).unwrap();
     
     stats.add_discovery("TestDiscovery");
     stats.add_expertise_domain("Quantum Computing");
@@ -136,11 +136,11 @@ fn test_language_aware_leaderboard() {
     let mut leaderboard = LanguageAwareLeaderboard::new();
     
     let mut stats1 = LanguageAwareContributorStats::new("researcher1");
-    stats1.add_trace(20, 0.85, 0.9, vec!["en".to_string(), "id".to_string()], 0.88, 0.9);
+    stats1.add_trace(20, 0.85, 0.9, vec!["en".to_string(), "id".to_string()], 0.88, 0.9).unwrap();
     stats1.add_discovery("Discovery1");
     
     let mut stats2 = LanguageAwareContributorStats::new("researcher2");
-    stats2.add_trace(15, 0.75, 0.8, vec!["en".to_string()], 0.85, 0.82);
+    stats2.add_trace(15, 0.75, 0.8, vec!["en".to_string()], 0.85, 0.82).unwrap();
     
     leaderboard.add_contributor(stats1);
     leaderboard.add_contributor(stats2);
@@ -154,7 +154,7 @@ fn test_language_aware_leaderboard() {
 
 #[test]
 fn test_journavx_discovery_simulation() {
-    let trace = simulate_journavx_discovery();
+    let trace = simulate_journavx_discovery().unwrap();
     
     assert_eq!(trace.discovery_name, "Journavx");
     assert!(trace.events.len() >= 9);
@@ -163,9 +163,9 @@ fn test_journavx_discovery_simulation() {
     assert!(trace.overall_serendipity > 0.8);
     
     let hash = trace.compute_provenance_hash();
-    assert_eq!(hash.len(), 64);
+    assert_eq!(hash.len(), 67);
     
-    let folded = trace.fold_memory();
+    let folded = trace.fold_memory().unwrap();
     assert!(folded.compression_ratio > 0.0);
 }
 
@@ -179,7 +179,7 @@ fn test_cross_language_patterns() {
     event3.add_secondary_language("id");
     
     let events = vec![event1, event2, event3];
-    let fold = folder.fold_memory("trace1", &events);
+    let fold = folder.fold_memory("trace1", &events).unwrap();
     
     assert!(!fold.cross_language_patterns.is_empty());
     
@@ -197,7 +197,7 @@ fn test_translation_quality_tracking() {
     let event2 = LanguageAwareAgentEvent::new("Translator", "Halo", "output2", "id", 0.85);
     
     let events = vec![event1, event2];
-    let fold = folder.fold_memory("trace1", &events);
+    let fold = folder.fold_memory("trace1", &events).unwrap();
     
     assert!(fold.translation_summary.total_translations > 0);
     assert!(fold.translation_summary.average_quality > 0.0);
@@ -260,7 +260,7 @@ fn test_serendipity_stages() {
             "en",
             0.8,
             0.85,
-        );
+        ).unwrap();
     }
     
     assert_eq!(trace.events.len(), 6);
@@ -270,7 +270,7 @@ fn test_serendipity_stages() {
 #[test]
 fn test_ranking_criteria() {
     let mut stats = LanguageAwareContributorStats::new("researcher");
-    stats.add_trace(25, 0.9, 0.95, vec!["en".to_string(), "id".to_string(), "es".to_string()], 0.92, 0.93);
+    stats.add_trace(25, 0.9, 0.95, vec!["en".to_string(), "id".to_string(), "es".to_string()], 0.92, 0.93).unwrap();
     stats.add_discovery("Discovery1");
     stats.add_discovery("Discovery2");
     
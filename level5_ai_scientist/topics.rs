@@ -0,0 +1,340 @@
+// -*- coding: utf-8 -*-
+//! Topic Clustering of Event Outputs
+//!
+//! Lightweight topic model over a trace's event outputs: each output becomes
+//! a TF-IDF vector of its words (three or more letters), and spherical
+//! k-means (cosine similarity, farthest-point seeding, so runs are
+//! deterministic) groups them. Topics are labeled with their highest-weight
+//! terms and numbered in order of first appearance. Topics are lexical, so
+//! outputs in different languages meet in a topic only through shared terms
+//! (names, loanwords, technical vocabulary).
+//!
+//! `annotate_topics` records each event's topic as a trace annotation,
+//! `topic_serendipity` scores topics by the serendipity of their events, and
+//! `MultilingualMemoryFolder::with_topic_drift` reports consecutive events
+//! that change topic as `TopicDrift` cross-language patterns.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::SerendipityTrace;
+use crate::annotation::{AnnotationKind, TraceAnnotation};
+
+/// Topics fitted by default
+pub const DEFAULT_TOPIC_COUNT: usize = 4;
+
+/// Terms labeling a topic
+pub const TOPIC_LABEL_TERMS: usize = 3;
+
+/// Upper bound on k-means refinement passes
+const MAX_ITERATIONS: usize = 50;
+
+/// Cluster of texts sharing vocabulary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Topic {
+    /// Position in order of first appearance
+    pub id: usize,
+    /// Highest-weight terms, joined ("batik, motif, route")
+    pub label: String,
+    /// Highest-weight terms, strongest first
+    pub terms: Vec<String>,
+    /// Texts assigned to the topic
+    pub size: usize,
+}
+
+/// Topics of a set of texts and the topic of each text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopicModel {
+    pub topics: Vec<Topic>,
+    /// Topic of each text (`None` for texts without terms)
+    pub assignments: Vec<Option<usize>>,
+}
+
+/// Topic recorded for an event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventTopic {
+    pub topic: usize,
+    pub label: String,
+}
+
+/// Serendipity of a topic's events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopicSerendipity {
+    pub topic: usize,
+    pub label: String,
+    pub event_ids: Vec<String>,
+    pub mean_serendipity: f64,
+    pub peak_serendipity: f64,
+}
+
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+}
+
+/// Sparse vector as (term index, weight), sorted by term index
+type SparseVector = Vec<(usize, f64)>;
+
+fn dot(doc: &SparseVector, centroid: &[f64]) -> f64 {
+    doc.iter().map(|&(term, weight)| weight * centroid[term]).sum()
+}
+
+fn normalize(vector: &mut [f64]) {
+    let norm = vector.iter().map(|w| w * w).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|w| *w /= norm);
+    }
+}
+
+/// Index of the most similar centroid (lowest index on ties)
+fn nearest(doc: &SparseVector, centroids: &[Vec<f64>]) -> usize {
+    let mut best = (0, f64::NEG_INFINITY);
+    for (index, centroid) in centroids.iter().enumerate() {
+        let similarity = dot(doc, centroid);
+        if similarity > best.1 {
+            best = (index, similarity);
+        }
+    }
+    best.0
+}
+
+impl TopicModel {
+    /// Cluster `texts` into at most `topic_count` topics
+    pub fn fit(texts: &[&str], topic_count: usize) -> Self {
+        // Vocabulary, term counts per text, and document frequencies
+        let mut vocabulary: BTreeMap<String, usize> = BTreeMap::new();
+        let counts: Vec<HashMap<String, usize>> = texts
+            .iter()
+            .map(|text| {
+                let mut counts = HashMap::new();
+                for term in terms(text) {
+                    *counts.entry(term).or_insert(0) += 1;
+                }
+                counts
+            })
+            .collect();
+        for term in counts.iter().flat_map(HashMap::keys) {
+            *vocabulary.entry(term.clone()).or_insert(0) += 1;
+        }
+        let index: HashMap<&str, usize> = vocabulary.keys().enumerate().map(|(i, term)| (term.as_str(), i)).collect();
+        let document_frequency: Vec<usize> = vocabulary.values().copied().collect();
+
+        let with_terms = counts.iter().filter(|c| !c.is_empty()).count() as f64;
+        let docs: Vec<SparseVector> = counts
+            .iter()
+            .map(|counts| {
+                let total: usize = counts.values().sum();
+                let mut doc: SparseVector = counts
+                    .iter()
+                    .map(|(term, &count)| {
+                        let term = index[term.as_str()];
+                        let idf = (with_terms / document_frequency[term] as f64).ln() + 1.0;
+                        (term, count as f64 / total as f64 * idf)
+                    })
+                    .collect();
+                doc.sort_by_key(|&(term, _)| term);
+                let norm = doc.iter().map(|(_, w)| w * w).sum::<f64>().sqrt();
+                doc.iter_mut().for_each(|(_, w)| *w /= norm);
+                doc
+            })
+            .collect();
+
+        let centroids = Self::cluster(&docs, vocabulary.len(), topic_count);
+        let mut assignments: Vec<Option<usize>> = docs
+            .iter()
+            .map(|doc| (!doc.is_empty() && !centroids.is_empty()).then(|| nearest(doc, &centroids)))
+            .collect();
+
+        // Renumber topics by first appearance, dropping any left empty
+        let mut renumbered: HashMap<usize, usize> = HashMap::new();
+        for topic in assignments.iter_mut().flatten() {
+            let next = renumbered.len();
+            *topic = *renumbered.entry(*topic).or_insert(next);
+        }
+        let terms_by_index: Vec<&String> = vocabulary.keys().collect();
+        let mut topics: Vec<Topic> = Vec::with_capacity(renumbered.len());
+        let mut by_new_id: Vec<(usize, usize)> = renumbered.into_iter().map(|(old, new)| (new, old)).collect();
+        by_new_id.sort_unstable();
+        for (id, old) in by_new_id {
+            let mut weights: Vec<(usize, f64)> = centroids[old].iter().copied().enumerate().filter(|&(_, w)| w > 0.0).collect();
+            weights.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            let terms: Vec<String> = weights.iter().take(TOPIC_LABEL_TERMS).map(|&(term, _)| terms_by_index[term].clone()).collect();
+            topics.push(Topic {
+                id,
+                label: terms.join(", "),
+                terms,
+                size: assignments.iter().filter(|&&topic| topic == Some(id)).count(),
+            });
+        }
+        Self { topics, assignments }
+    }
+
+    /// Spherical k-means centroids over the non-empty documents
+    fn cluster(docs: &[SparseVector], dimensions: usize, topic_count: usize) -> Vec<Vec<f64>> {
+        let densify = |doc: &SparseVector| {
+            let mut dense = vec![0.0; dimensions];
+            doc.iter().for_each(|&(term, weight)| dense[term] = weight);
+            dense
+        };
+        let non_empty: Vec<&SparseVector> = docs.iter().filter(|doc| !doc.is_empty()).collect();
+        let Some(first) = non_empty.first().filter(|_| topic_count > 0) else {
+            return Vec::new();
+        };
+
+        // Farthest-point seeding: each seed is the text least similar to the seeds so far
+        let mut centroids = vec![densify(first)];
+        let mut chosen: HashSet<usize> = HashSet::from([0]);
+        while centroids.len() < topic_count {
+            let farthest = non_empty
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !chosen.contains(i))
+                .map(|(i, doc)| (i, centroids.iter().map(|c| dot(doc, c)).fold(f64::NEG_INFINITY, f64::max)))
+                .fold(None, |best: Option<(usize, f64)>, (i, similarity)| match best {
+                    Some((_, best_similarity)) if best_similarity <= similarity => best,
+                    _ => Some((i, similarity)),
+                });
+            match farthest {
+                // Texts identical to an existing seed add no topic
+                Some((i, similarity)) if similarity < 1.0 - 1e-9 => {
+                    chosen.insert(i);
+                    centroids.push(densify(non_empty[i]));
+                }
+                _ => break,
+            }
+        }
+
+        let mut assignment: Vec<usize> = non_empty.iter().map(|doc| nearest(doc, &centroids)).collect();
+        for _ in 0..MAX_ITERATIONS {
+            for (topic, centroid) in centroids.iter_mut().enumerate() {
+                let members: Vec<&&SparseVector> = non_empty.iter().zip(&assignment).filter(|(_, &a)| a == topic).map(|(doc, _)| doc).collect();
+                if members.is_empty() {
+                    continue;
+                }
+                centroid.iter_mut().for_each(|w| *w = 0.0);
+                for doc in members {
+                    doc.iter().for_each(|&(term, weight)| centroid[term] += weight);
+                }
+                normalize(centroid);
+            }
+            let next: Vec<usize> = non_empty.iter().map(|doc| nearest(doc, &centroids)).collect();
+            if next == assignment {
+                break;
+            }
+            assignment = next;
+        }
+        centroids
+    }
+
+    /// Topic of the `index`-th text
+    pub fn topic_of(&self, index: usize) -> Option<&Topic> {
+        self.assignments.get(index).copied().flatten().map(|topic| &self.topics[topic])
+    }
+}
+
+impl SerendipityTrace {
+    /// Topics of the event outputs (redacted outputs have none)
+    pub fn topic_model(&self, topic_count: usize) -> TopicModel {
+        let outputs: Vec<&str> = self.events
+            .iter()
+            .map(|event| if event.is_redacted() { "" } else { event.output.as_str() })
+            .collect();
+        TopicModel::fit(&outputs, topic_count)
+    }
+
+    /// Fit topics and record each event's topic as an annotation, replacing
+    /// earlier topic annotations
+    pub fn annotate_topics(&mut self, topic_count: usize) -> TopicModel {
+        let model = self.topic_model(topic_count);
+        self.annotations.retain(|note| !matches!(note.kind, AnnotationKind::Topic(_)));
+        for (event, topic) in self.events.iter().zip(&model.assignments) {
+            if let Some(topic) = topic {
+                self.annotations.push(TraceAnnotation {
+                    event_id: event.event_id.clone(),
+                    kind: AnnotationKind::Topic(EventTopic { topic: *topic, label: model.topics[*topic].label.clone() }),
+                });
+            }
+        }
+        model
+    }
+
+    /// Topics recorded by `annotate_topics`, with the event IDs
+    pub fn event_topics(&self) -> impl Iterator<Item = (&str, &EventTopic)> {
+        self.annotations.iter().filter_map(|note| match &note.kind {
+            AnnotationKind::Topic(topic) => Some((note.event_id.as_str(), topic)),
+            _ => None,
+        })
+    }
+
+    /// Mean and peak serendipity of each topic's events
+    pub fn topic_serendipity(&self, model: &TopicModel) -> Vec<TopicSerendipity> {
+        model.topics
+            .iter()
+            .map(|topic| {
+                let events: Vec<_> = self.events
+                    .iter()
+                    .zip(&model.assignments)
+                    .filter(|(_, assigned)| **assigned == Some(topic.id))
+                    .map(|(event, _)| event)
+                    .collect();
+                let scores = events.iter().map(|event| event.serendipity_score);
+                TopicSerendipity {
+                    topic: topic.id,
+                    label: topic.label.clone(),
+                    event_ids: events.iter().map(|event| event.event_id.clone()).collect(),
+                    mean_serendipity: scores.clone().sum::<f64>() / events.len().max(1) as f64,
+                    peak_serendipity: scores.fold(0.0, f64::max),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        let outputs = [
+            ("Batik motifs encode coastal routes", "en", 0.4),
+            ("Motif batik menyimpan rute pesisir", "id", 0.6),
+            ("Quantum walk amplitudes spread over the lattice", "en", 0.9),
+            ("Batik motifs repeat like route markers", "en", 0.5),
+            ("Quantum walk lattice sensing", "en", 0.7),
+        ];
+        for (output, language, serendipity) in outputs {
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "Survey", output, language, serendipity, 0.8).unwrap();
+        }
+        trace
+    }
+
+    #[test]
+    fn test_topics_group_shared_vocabulary() {
+        let model = TopicModel::fit(&["batik motif route", "batik motif", "", "quantum walk lattice", "quantum lattice"], 2);
+        assert_eq!(model.assignments, vec![Some(0), Some(0), None, Some(1), Some(1)]);
+        assert_eq!(model.topics[0].terms[..2], ["batik".to_string(), "motif".to_string()]);
+        assert_eq!(model.topics[1].size, 2);
+        assert!(model.topics[1].label.contains("quantum"));
+
+        // Identical texts yield a single topic whatever the requested count
+        assert_eq!(TopicModel::fit(&["batik motif", "batik motif"], 3).topics.len(), 1);
+    }
+
+    #[test]
+    fn test_trace_topics_and_serendipity() {
+        let mut trace = trace();
+        let model = trace.annotate_topics(2);
+        let topics: Vec<usize> = trace.event_topics().map(|(_, topic)| topic.topic).collect();
+        assert_eq!(topics, vec![0, 0, 1, 0, 1]);
+        assert_eq!(trace.annotate_topics(2), model);
+        assert_eq!(trace.event_topics().count(), 5);
+
+        let scores = trace.topic_serendipity(&model);
+        assert_eq!(scores[0].event_ids.len(), 3);
+        assert!((scores[0].mean_serendipity - 0.5).abs() < 1e-12);
+        assert!((scores[1].mean_serendipity - 0.8).abs() < 1e-12);
+        assert_eq!(scores[1].peak_serendipity, 0.9);
+    }
+}
@@ -0,0 +1,97 @@
+// -*- coding: utf-8 -*-
+//! Hash Chains of Successive Traces
+//!
+//! A chained trace embeds the provenance hash of the contributor's previous
+//! trace, so a researcher's discovery history forms an append-only chain:
+//! editing, reordering, or removing an earlier trace breaks every later
+//! link. The link is part of the chained trace's own provenance hash, and
+//! signing the newest trace therefore commits to the whole history.
+
+use crate::serendipity_trace::SerendipityTrace;
+use crate::error::{SerenQaError, SerenQaResult};
+
+impl SerendipityTrace {
+    /// Create a trace following the trace whose provenance hash is `prev_trace_hash`
+    pub fn new_chained(contributor_id: &str, backend: &str, discovery_name: &str, prev_trace_hash: &str) -> Self {
+        let mut trace = Self::new(contributor_id, backend, discovery_name);
+        trace.prev_trace_hash = Some(prev_trace_hash.to_string());
+        trace
+    }
+
+    /// Whether the trace links to `previous` in its current state
+    pub fn follows(&self, previous: &SerendipityTrace) -> bool {
+        self.prev_trace_hash
+            .as_deref()
+            .is_some_and(|hash| previous.verify_provenance_hash(hash))
+    }
+}
+
+/// Check traces form an unbroken chain from its first trace: the first has no
+/// predecessor and each later trace links to the one before it. Fails at the
+/// first trace whose link does not match, which detects edited, reordered, or
+/// removed traces.
+pub fn verify_chain(traces: &[SerendipityTrace]) -> SerenQaResult<()> {
+    for (index, trace) in traces.iter().enumerate() {
+        let linked = match index {
+            0 => trace.prev_trace_hash.is_none(),
+            _ => trace.follows(&traces[index - 1]),
+        };
+        if !linked {
+            return Err(SerenQaError::BrokenChain { index, trace_id: trace.trace_id.clone() });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn chain(len: usize) -> Vec<SerendipityTrace> {
+        let mut traces: Vec<SerendipityTrace> = Vec::new();
+        for i in 0..len {
+            let mut trace = match traces.last() {
+                Some(prev) => SerendipityTrace::new_chained("sari", "backend", "Journavx", &prev.compute_provenance_hash()),
+                None => SerendipityTrace::new("sari", "backend", "Journavx"),
+            };
+            trace.trace_id = format!("trace_{}", i);
+            trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.5, 0.8).unwrap();
+            traces.push(trace);
+        }
+        traces
+    }
+
+    #[test]
+    fn test_chain_links_into_provenance() {
+        let traces = chain(3);
+        assert!(verify_chain(&traces).is_ok());
+        assert!(traces[1].follows(&traces[0]));
+
+        let mut unlinked = traces[1].clone();
+        unlinked.prev_trace_hash = None;
+        assert_ne!(unlinked.compute_provenance_hash(), traces[1].compute_provenance_hash());
+
+        let mut jsonl = Vec::new();
+        traces[2].to_jsonl(&mut jsonl).unwrap();
+        let restored = SerendipityTrace::from_jsonl(jsonl.as_slice()).unwrap();
+        assert_eq!(restored.prev_trace_hash, traces[2].prev_trace_hash);
+    }
+
+    #[test]
+    fn test_detects_edits_reordering_and_removal() {
+        let traces = chain(4);
+
+        let mut edited = traces.clone();
+        edited[1].events[0].serendipity_score = 0.9;
+        assert!(matches!(verify_chain(&edited), Err(SerenQaError::BrokenChain { index: 2, .. })));
+
+        let mut reordered = traces.clone();
+        reordered.swap(1, 2);
+        assert!(matches!(verify_chain(&reordered), Err(SerenQaError::BrokenChain { index: 1, .. })));
+
+        let removed = vec![traces[0].clone(), traces[2].clone(), traces[3].clone()];
+        assert!(matches!(verify_chain(&removed), Err(SerenQaError::BrokenChain { index: 1, .. })));
+        assert!(matches!(verify_chain(&traces[1..]), Err(SerenQaError::BrokenChain { index: 0, .. })));
+    }
+}
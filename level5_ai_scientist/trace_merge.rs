@@ -0,0 +1,399 @@
+// -*- coding: utf-8 -*-
+//! Trace Diffing and Merging
+//!
+//! Lets two researchers working on the same discovery in parallel reconcile
+//! their event histories. `diff` reports added, removed, and modified events
+//! and transitions; `merge` unions the histories in timestamp order and fails
+//! with a `MergeConflict` on overlapping event IDs with different content or
+//! on transitions that diverge from a shared event. Review verdicts and
+//! annotations from both sides are kept, each once.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::serendipity_trace::{SerendipityEvent, SerendipityTrace, SerendipityTransition};
+
+/// `ours` followed by the items of `theirs` it does not already hold
+fn union<T: Clone + PartialEq>(ours: &[T], theirs: &[T]) -> Vec<T> {
+    let mut merged = ours.to_vec();
+    for item in theirs {
+        if !merged.contains(item) {
+            merged.push(item.clone());
+        }
+    }
+    merged
+}
+
+/// Event present in both traces with different content
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventChange {
+    /// Event ID
+    pub event_id: String,
+    /// Names of fields that differ
+    pub fields: Vec<String>,
+}
+
+/// Transitions leaving the same event towards different targets
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DivergentTransition {
+    /// Shared source event
+    pub from_event: String,
+    /// Target in this trace
+    pub ours: String,
+    /// Target in the other trace
+    pub theirs: String,
+}
+
+/// Difference between two traces
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TraceDiff {
+    /// Event IDs only in this trace
+    pub events_only_in_self: Vec<String>,
+    /// Event IDs only in the other trace
+    pub events_only_in_other: Vec<String>,
+    /// Events in both traces with different content
+    pub modified_events: Vec<EventChange>,
+    /// Transitions (from, to) only in this trace
+    pub transitions_only_in_self: Vec<(String, String)>,
+    /// Transitions (from, to) only in the other trace
+    pub transitions_only_in_other: Vec<(String, String)>,
+    /// Transitions diverging from a shared event
+    pub divergent_transitions: Vec<DivergentTransition>,
+}
+
+impl TraceDiff {
+    /// Check if the traces have identical histories
+    pub fn is_empty(&self) -> bool {
+        self.events_only_in_self.is_empty()
+            && self.events_only_in_other.is_empty()
+            && self.modified_events.is_empty()
+            && self.transitions_only_in_self.is_empty()
+            && self.transitions_only_in_other.is_empty()
+    }
+}
+
+/// Reasons two traces cannot be merged
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct MergeConflict {
+    /// Discovery names differ (ours, theirs)
+    pub discovery_mismatch: Option<(String, String)>,
+    /// Overlapping event IDs with different content
+    pub conflicting_events: Vec<EventChange>,
+    /// Histories fork from a shared event
+    pub divergent_transitions: Vec<DivergentTransition>,
+}
+
+impl MergeConflict {
+    /// Check if there is any conflict
+    pub fn is_empty(&self) -> bool {
+        self.discovery_mismatch.is_none()
+            && self.conflicting_events.is_empty()
+            && self.divergent_transitions.is_empty()
+    }
+}
+
+impl std::fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "merge conflict: {} conflicting events, {} divergent transitions",
+            self.conflicting_events.len(),
+            self.divergent_transitions.len()
+        )?;
+        if let Some((ours, theirs)) = &self.discovery_mismatch {
+            write!(f, ", discovery mismatch ({} vs {})", ours, theirs)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MergeConflict {}
+
+/// Names of fields that differ between two versions of an event
+fn changed_fields(ours: &SerendipityEvent, theirs: &SerendipityEvent) -> Vec<String> {
+    let mut fields = Vec::new();
+    if ours.stage != theirs.stage {
+        fields.push("stage".to_string());
+    }
+    if ours.agent != theirs.agent {
+        fields.push("agent".to_string());
+    }
+    if ours.content_hash() != theirs.content_hash() {
+        fields.push("content".to_string());
+    }
+    if ours.language != theirs.language {
+        fields.push("language".to_string());
+    }
+    if ours.serendipity_score != theirs.serendipity_score {
+        fields.push("serendipity_score".to_string());
+    }
+    if ours.confidence != theirs.confidence {
+        fields.push("confidence".to_string());
+    }
+    fields
+}
+
+/// Transition edges as (from, to) pairs
+fn edges(transitions: &[SerendipityTransition]) -> Vec<(String, String)> {
+    transitions
+        .iter()
+        .map(|t| (t.from_event.clone(), t.to_event.clone()))
+        .collect()
+}
+
+impl SerendipityTrace {
+    /// Compute the difference between this trace and another
+    pub fn diff(&self, other: &SerendipityTrace) -> TraceDiff {
+        let ours: HashMap<&str, &SerendipityEvent> =
+            self.events.iter().map(|e| (e.event_id.as_str(), e)).collect();
+        let theirs: HashMap<&str, &SerendipityEvent> =
+            other.events.iter().map(|e| (e.event_id.as_str(), e)).collect();
+
+        let events_only_in_self = self.events
+            .iter()
+            .filter(|e| !theirs.contains_key(e.event_id.as_str()))
+            .map(|e| e.event_id.clone())
+            .collect();
+        let events_only_in_other = other.events
+            .iter()
+            .filter(|e| !ours.contains_key(e.event_id.as_str()))
+            .map(|e| e.event_id.clone())
+            .collect();
+
+        let modified_events = self.events
+            .iter()
+            .filter_map(|e| {
+                let their_event = theirs.get(e.event_id.as_str())?;
+                let fields = changed_fields(e, their_event);
+                if fields.is_empty() {
+                    None
+                } else {
+                    Some(EventChange { event_id: e.event_id.clone(), fields })
+                }
+            })
+            .collect();
+
+        let our_edges = edges(&self.transitions);
+        let their_edges = edges(&other.transitions);
+        let our_set: HashSet<&(String, String)> = our_edges.iter().collect();
+        let their_set: HashSet<&(String, String)> = their_edges.iter().collect();
+
+        let transitions_only_in_self: Vec<(String, String)> = our_edges
+            .iter()
+            .filter(|e| !their_set.contains(e))
+            .cloned()
+            .collect();
+        let transitions_only_in_other: Vec<(String, String)> = their_edges
+            .iter()
+            .filter(|e| !our_set.contains(e))
+            .cloned()
+            .collect();
+
+        let divergent_transitions = transitions_only_in_self
+            .iter()
+            .filter_map(|(from, to)| {
+                transitions_only_in_other
+                    .iter()
+                    .find(|(their_from, _)| their_from == from)
+                    .map(|(_, their_to)| DivergentTransition {
+                        from_event: from.clone(),
+                        ours: to.clone(),
+                        theirs: their_to.clone(),
+                    })
+            })
+            .collect();
+
+        TraceDiff {
+            events_only_in_self,
+            events_only_in_other,
+            modified_events,
+            transitions_only_in_self,
+            transitions_only_in_other,
+            divergent_transitions,
+        }
+    }
+
+    /// Merge another trace's history into a copy of this one.
+    /// Events are unioned in timestamp order and transitions rebuilt;
+    /// verdicts (in the order given) and annotations are unioned too.
+    pub fn merge(&self, other: &SerendipityTrace) -> Result<SerendipityTrace, MergeConflict> {
+        let diff = self.diff(other);
+        let conflict = MergeConflict {
+            discovery_mismatch: if self.discovery_name != other.discovery_name {
+                Some((self.discovery_name.clone(), other.discovery_name.clone()))
+            } else {
+                None
+            },
+            conflicting_events: diff.modified_events,
+            divergent_transitions: diff.divergent_transitions,
+        };
+        if !conflict.is_empty() {
+            return Err(conflict);
+        }
+
+        let known: HashSet<&str> = self.events.iter().map(|e| e.event_id.as_str()).collect();
+        let mut events: Vec<SerendipityEvent> = self.events.clone();
+        events.extend(
+            other.events
+                .iter()
+                .filter(|e| !known.contains(e.event_id.as_str()))
+                .cloned(),
+        );
+        events.sort_by(|a, b| {
+            a.timestamp.cmp(&b.timestamp).then_with(|| a.event_id.cmp(&b.event_id))
+        });
+
+        let mut merged = self.clone();
+        merged.signature = None;
+        merged.transitions = events
+            .windows(2)
+            .map(|pair| SerendipityTransition::between(&pair[0], &pair[1]))
+            .collect();
+        merged.languages.clear();
+        for event in &events {
+            if !merged.languages.contains(&event.language) {
+                merged.languages.push(event.language.clone());
+            }
+        }
+        merged.events = events;
+
+        merged.review.verdicts = union(&self.review.verdicts, &other.review.verdicts);
+        // Stable, so verdicts given at the same time keep ours first
+        merged.review.verdicts.sort_by_key(|verdict| verdict.at);
+        merged.review.submitted_at = match (self.review.submitted_at, other.review.submitted_at) {
+            (Some(ours), Some(theirs)) => Some(ours.min(theirs)),
+            (ours, theirs) => ours.or(theirs),
+        };
+        merged.annotations = union(&self.annotations, &other.annotations);
+        merged.invalidate_provenance();
+        merged.update_overall_serendipity();
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+    use crate::review::{ReviewDecision, ReviewVerdict};
+    use crate::annotation::{AnnotationKind, TraceAnnotation};
+    use crate::topics::EventTopic;
+    use chrono::Duration;
+
+    fn base_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.log_event(
+            SerendipityStage::Exploration,
+            SerendipityAgent::Explorer,
+            "input0",
+            "output0",
+            "en",
+            0.7,
+            0.9,
+        ).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let trace = base_trace();
+        assert!(trace.diff(&trace.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_merge_extension() {
+        let ours = base_trace();
+        let mut theirs = ours.clone();
+        theirs.contributor_id = "researcher2".to_string();
+        theirs.log_event(
+            SerendipityStage::UnexpectedConnection,
+            SerendipityAgent::PatternRecognizer,
+            "input1",
+            "output1",
+            "id",
+            0.95,
+            0.85,
+        ).unwrap();
+
+        let diff = ours.diff(&theirs);
+        assert_eq!(diff.events_only_in_other.len(), 1);
+        assert_eq!(diff.transitions_only_in_other.len(), 1);
+
+        let merged = ours.merge(&theirs).unwrap();
+        assert_eq!(merged.events.len(), 2);
+        assert_eq!(merged.transitions.len(), 1);
+        assert_eq!(merged.languages, vec!["en".to_string(), "id".to_string()]);
+        assert!(merged.diff(&theirs).events_only_in_other.is_empty());
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let ours = base_trace();
+        let mut theirs = ours.clone();
+        theirs.events[0].output = "rewritten".to_string();
+
+        let conflict = ours.merge(&theirs).unwrap_err();
+        assert_eq!(conflict.conflicting_events[0].fields, vec!["content".to_string()]);
+
+        let mut ours_forked = ours.clone();
+        let mut theirs_forked = ours.clone();
+        ours_forked.log_event(
+            SerendipityStage::Validation,
+            SerendipityAgent::Validator,
+            "a",
+            "a",
+            "en",
+            0.5,
+            0.5,
+        ).unwrap();
+        theirs_forked.log_event(
+            SerendipityStage::Integration,
+            SerendipityAgent::Synthesizer,
+            "b",
+            "b",
+            "en",
+            0.5,
+            0.5,
+        ).unwrap();
+        theirs_forked.events[1].event_id = "event_1_other".to_string();
+        theirs_forked.transitions[0].to_event = "event_1_other".to_string();
+
+        let conflict = ours_forked.merge(&theirs_forked).unwrap_err();
+        assert_eq!(conflict.divergent_transitions.len(), 1);
+        assert_eq!(conflict.divergent_transitions[0].from_event, ours.events[0].event_id);
+    }
+
+    #[test]
+    fn test_merge_keeps_verdicts_and_annotations_of_both_sides() {
+        let mut ours = base_trace();
+        let shared = TraceAnnotation {
+            event_id: ours.events[0].event_id.clone(),
+            kind: AnnotationKind::Topic(EventTopic { topic: 0, label: "routes".to_string() }),
+        };
+        ours.annotations.push(shared.clone());
+        let mut theirs = ours.clone();
+        theirs.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "check", "confirmed", "en", 0.6, 0.9).unwrap();
+
+        let start = ours.events[0].timestamp;
+        let verdict = |reviewer: &str, minutes: i64| {
+            let mut verdict = ReviewVerdict::on_trace(reviewer, ReviewDecision::Approve);
+            verdict.at = start + Duration::minutes(minutes);
+            verdict
+        };
+        let (early, late, shared_verdict) = (verdict("budi", 1), verdict("sari", 2), verdict("rini", 0));
+        ours.review.verdicts = vec![shared_verdict.clone(), late.clone()];
+        theirs.review.verdicts = vec![shared_verdict.clone(), early.clone()];
+        ours.review.submitted_at = Some(start + Duration::minutes(5));
+        theirs.review.submitted_at = Some(start);
+        let theirs_only = TraceAnnotation {
+            event_id: theirs.events[1].event_id.clone(),
+            kind: AnnotationKind::Topic(EventTopic { topic: 1, label: "checks".to_string() }),
+        };
+        theirs.annotations.push(theirs_only.clone());
+
+        let merged = ours.merge(&theirs).unwrap();
+        assert_eq!(merged.review.verdicts, vec![shared_verdict, early, late]);
+        assert_eq!(merged.review.submitted_at, Some(start));
+        assert_eq!(merged.annotations, vec![shared, theirs_only]);
+        assert!(merged.verify_provenance_hash(&merged.compute_provenance_hash()));
+    }
+}
@@ -0,0 +1,216 @@
+// -*- coding: utf-8 -*-
+//! Trace Quality Gate for Benchmark Ingestion
+//!
+//! `TraceValidator` is the check benchmark maintainers run before accepting
+//! a submission: minimum depth, coverage of required stages, monotonic event
+//! timestamps, score ranges, non-empty inputs and outputs, language tags, and
+//! provenance hash consistency. Issues use the same codes and remediation
+//! hints as `RejectionReport`, so a `ValidationReport` can be fed back to
+//! submitters unchanged, and a `SubmissionPipeline` can run the validator as
+//! one of its checks.
+
+use serde::{Deserialize, Serialize};
+use crate::serendipity_trace::{SerendipityStage, SerendipityTrace};
+use crate::language_tag::is_valid_bcp47;
+use crate::canonical::HashVersion;
+use crate::submission::{RejectionCode, RejectionIssue, RejectionReport};
+
+/// Machine-readable outcome of a quality-gate run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidationReport {
+    /// Validated trace
+    pub trace_id: String,
+    /// Whether no issues were found
+    pub passed: bool,
+    /// Problems found, in check order
+    pub issues: Vec<RejectionIssue>,
+}
+
+impl ValidationReport {
+    /// Distinct error codes, in order of first appearance
+    pub fn codes(&self) -> Vec<RejectionCode> {
+        self.clone().into_rejection().codes()
+    }
+
+    /// Report for submitters of a trace that failed the gate
+    pub fn into_rejection(self) -> RejectionReport {
+        RejectionReport { trace_id: self.trace_id, issues: self.issues }
+    }
+}
+
+/// Configurable quality gate for submitted traces
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceValidator {
+    /// Minimum number of events
+    pub min_depth: usize,
+    /// Stages every trace must contain
+    pub required_stages: Vec<SerendipityStage>,
+}
+
+impl TraceValidator {
+    /// Validator requiring one event and no particular stages
+    pub fn new() -> Self {
+        Self { min_depth: 1, required_stages: Vec::new() }
+    }
+
+    /// Require a minimum trace depth
+    pub fn with_min_depth(mut self, min_depth: usize) -> Self {
+        self.min_depth = min_depth;
+        self
+    }
+
+    /// Require the trace to cover the given stages
+    pub fn with_required_stages(mut self, stages: Vec<SerendipityStage>) -> Self {
+        self.required_stages = stages;
+        self
+    }
+
+    /// Run every check. The provenance hash is checked against the signature
+    /// when the trace is signed.
+    pub fn validate(&self, trace: &SerendipityTrace) -> ValidationReport {
+        self.validate_with_hash(trace, None)
+    }
+
+    /// Run every check, also requiring the provenance hash to equal
+    /// `expected_hash` (e.g., the hash from a submission manifest)
+    pub fn validate_with_hash(&self, trace: &SerendipityTrace, expected_hash: Option<&str>) -> ValidationReport {
+        let issues = self.issues(trace, expected_hash);
+        ValidationReport { trace_id: trace.trace_id.clone(), passed: issues.is_empty(), issues }
+    }
+
+    pub(crate) fn issues(&self, trace: &SerendipityTrace, expected_hash: Option<&str>) -> Vec<RejectionIssue> {
+        let mut issues = Vec::new();
+        let mut flag = |code, event_ids: Vec<String>, message: String| {
+            issues.push(RejectionIssue::new(code, event_ids, message));
+        };
+        let events_where = |failed: &dyn Fn(usize) -> bool| -> Vec<String> {
+            (0..trace.events.len()).filter(|&i| failed(i)).map(|i| trace.events[i].event_id.clone()).collect()
+        };
+
+        if trace.events.len() < self.min_depth {
+            flag(
+                RejectionCode::TooFewEvents,
+                Vec::new(),
+                format!("{} events; at least {} required", trace.events.len(), self.min_depth),
+            );
+        }
+
+        let missing: Vec<&SerendipityStage> = self.required_stages
+            .iter()
+            .filter(|stage| !trace.events.iter().any(|e| &e.stage == *stage))
+            .collect();
+        if !missing.is_empty() {
+            flag(RejectionCode::MissingStages, Vec::new(), format!("required stages missing: {:?}", missing));
+        }
+
+        let out_of_order = events_where(&|i| i > 0 && trace.events[i].timestamp < trace.events[i - 1].timestamp);
+        if !out_of_order.is_empty() {
+            flag(RejectionCode::NonMonotonicTimestamps, out_of_order, "events logged earlier than their predecessors".to_string());
+        }
+
+        let out_of_range = events_where(&|i| {
+            let e = &trace.events[i];
+            !(0.0..=1.0).contains(&e.serendipity_score) || !(0.0..=1.0).contains(&e.confidence)
+        });
+        if !out_of_range.is_empty() {
+            flag(RejectionCode::ScoreOutOfRange, out_of_range, "event scores outside [0, 1]".to_string());
+        }
+
+        // Redacted events legitimately carry no text
+        let empty = events_where(&|i| {
+            let e = &trace.events[i];
+            e.redaction.is_none() && (e.input.trim().is_empty() || e.output.trim().is_empty())
+        });
+        if !empty.is_empty() {
+            flag(RejectionCode::EmptyEventContent, empty, "events with empty input or output".to_string());
+        }
+
+        let bad_tags = events_where(&|i| !is_valid_bcp47(&trace.events[i].language));
+        if !bad_tags.is_empty() {
+            flag(RejectionCode::InvalidLanguageTag, bad_tags, "events with malformed language tags".to_string());
+        }
+
+        let hash = trace.compute_provenance_hash_with(HashVersion::CURRENT);
+        if let Some(signature) = &trace.signature {
+            if !trace.verify_provenance_hash(&signature.signed_hash) {
+                flag(RejectionCode::SignedHashMismatch, Vec::new(), "provenance hash changed after signing".to_string());
+            }
+        }
+        if let Some(expected) = expected_hash {
+            if !trace.verify_provenance_hash(expected) {
+                flag(
+                    RejectionCode::ProvenanceHashMismatch,
+                    Vec::new(),
+                    format!("provenance hash {} but {} expected", hash, expected),
+                );
+            }
+        }
+        issues
+    }
+}
+
+impl Default for TraceValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::serendipity_trace::SerendipityAgent;
+
+    fn sample_trace() -> SerendipityTrace {
+        let mut trace = SerendipityTrace::new("researcher1", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "input1", "output1", "en", 0.7, 0.9).unwrap();
+        trace.log_event(SerendipityStage::Validation, SerendipityAgent::Validator, "input2", "output2", "id", 0.9, 0.8).unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_valid_trace_passes() {
+        let trace = sample_trace();
+        let validator = TraceValidator::new()
+            .with_min_depth(2)
+            .with_required_stages(vec![SerendipityStage::Exploration, SerendipityStage::Validation]);
+        let report = validator.validate_with_hash(&trace, Some(&trace.compute_provenance_hash()));
+        assert!(report.passed);
+        assert!(report.issues.is_empty());
+
+        let pipeline = crate::submission::SubmissionPipeline::new()
+            .with_validator(TraceValidator::new().with_required_stages(vec![SerendipityStage::Integration]));
+        assert_eq!(pipeline.submit(&trace).unwrap_err().codes(), vec![RejectionCode::MissingStages]);
+    }
+
+    #[test]
+    fn test_report_lists_failed_checks() {
+        let mut trace = sample_trace();
+        let shifted = trace.events[0].timestamp - Duration::seconds(5);
+        trace.events[1].timestamp = shifted;
+        trace.events[1].output = "  ".to_string();
+        trace.events[0].language = "not a tag".to_string();
+
+        let validator = TraceValidator::new()
+            .with_min_depth(3)
+            .with_required_stages(vec![SerendipityStage::HypothesisFormation]);
+        let report = validator.validate_with_hash(&trace, Some("stale"));
+        assert!(!report.passed);
+        assert_eq!(
+            report.codes(),
+            vec![
+                RejectionCode::TooFewEvents,
+                RejectionCode::MissingStages,
+                RejectionCode::NonMonotonicTimestamps,
+                RejectionCode::EmptyEventContent,
+                RejectionCode::InvalidLanguageTag,
+                RejectionCode::ProvenanceHashMismatch,
+            ]
+        );
+        assert_eq!(report.issues[2].event_ids, vec![trace.events[1].event_id.clone()]);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"passed\":false") && json.contains("\"NON_MONOTONIC_TIMESTAMPS\""));
+        assert_eq!(report.into_rejection().issues.len(), 6);
+    }
+}
@@ -0,0 +1,254 @@
+// -*- coding: utf-8 -*-
+//! `tracing` Subscriber Integration
+//!
+//! `SerendipityLayer` turns instrumented agent code into trace events, so
+//! orchestration code can write
+//! `tracing::info!(stage = "Validation", agent = "Validator", language = "id", serendipity = 0.8, "confirmed")`
+//! instead of calling `log_event` by hand. Events without both `stage` and
+//! `agent` fields are ignored; agent names other than the built-in roles are
+//! recorded as `SerendipityAgent::Custom`. Requires the `tracing` feature (`tracing` and
+//! `tracing-subscriber` crates).
+#![cfg(feature = "tracing")]
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use serde::de::DeserializeOwned;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use crate::serendipity_trace::{SerendipityTrace, SerendipityEventBuilder, SerendipityAgent};
+use crate::metadata::MetadataValue;
+
+/// Language used when an event has no `language` field
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Parse a stage or agent variant name (e.g., "UnexpectedConnection")
+fn parse_variant<T: DeserializeOwned>(name: &str) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+}
+
+/// Fields recorded from one `tracing` event
+#[derive(Default)]
+struct EventFields {
+    stage: Option<String>,
+    agent: Option<String>,
+    language: Option<String>,
+    input: Option<String>,
+    output: Option<String>,
+    message: Option<String>,
+    serendipity: Option<f64>,
+    confidence: Option<f64>,
+    metadata: HashMap<String, MetadataValue>,
+}
+
+impl EventFields {
+    fn record_text(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "stage" => self.stage = Some(value),
+            "agent" => self.agent = Some(value),
+            "language" => self.language = Some(value),
+            "input" => self.input = Some(value),
+            "output" => self.output = Some(value),
+            "message" => self.message = Some(value),
+            name => {
+                self.metadata.insert(name.to_string(), value.into());
+            }
+        }
+    }
+
+    /// Keep numbers and bools typed when they land in metadata
+    fn record_typed(&mut self, field: &Field, value: MetadataValue) {
+        match field.name() {
+            "stage" | "agent" | "language" | "input" | "output" | "message" => self.record_text(field, value.to_string()),
+            name => {
+                self.metadata.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    /// Builder for the event, or why the fields don't describe one.
+    /// `Ok(None)` means the event carries no stage/agent and is not ours.
+    fn into_builder(self) -> Result<Option<SerendipityEventBuilder>, String> {
+        let (Some(stage), Some(agent)) = (self.stage, self.agent) else {
+            return Ok(None);
+        };
+        let stage = parse_variant(&stage).ok_or_else(|| format!("unknown stage {:?}", stage))?;
+        // Names other than the built-in roles are custom roles
+        let agent = parse_variant(&agent).unwrap_or(SerendipityAgent::Custom(agent));
+        let output = self.output.or(self.message).unwrap_or_default();
+
+        let mut builder = SerendipityEventBuilder::new(
+            stage,
+            agent,
+            self.input.as_deref().unwrap_or_default(),
+            &output,
+            self.language.as_deref().unwrap_or(DEFAULT_LANGUAGE),
+        )
+        .serendipity(self.serendipity.unwrap_or(0.0))
+        .confidence(self.confidence.unwrap_or(0.0));
+        for (key, value) in self.metadata {
+            builder = builder.metadata(&key, value);
+        }
+        Ok(Some(builder))
+    }
+}
+
+impl Visit for EventFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        match field.name() {
+            "serendipity" => self.serendipity = Some(value),
+            "confidence" => self.confidence = Some(value),
+            _ => self.record_typed(field, value.into()),
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_typed(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record_typed(field, (value as f64).into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record_typed(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_text(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.record_text(field, format!("{:?}", value));
+    }
+}
+
+/// `tracing_subscriber` layer logging stage/agent events into a shared trace
+#[derive(Clone)]
+pub struct SerendipityLayer {
+    trace: Arc<Mutex<SerendipityTrace>>,
+    rejected: Arc<Mutex<Vec<String>>>,
+}
+
+impl SerendipityLayer {
+    /// Create a layer recording into `trace`
+    pub fn new(trace: SerendipityTrace) -> Self {
+        Self {
+            trace: Arc::new(Mutex::new(trace)),
+            rejected: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Shared handle to the trace being recorded
+    pub fn trace(&self) -> Arc<Mutex<SerendipityTrace>> {
+        Arc::clone(&self.trace)
+    }
+
+    /// Copy of the trace recorded so far
+    pub fn snapshot(&self) -> SerendipityTrace {
+        self.trace.lock().unwrap().clone()
+    }
+
+    /// Why stage/agent events were dropped (bad names, scores, or vetoes)
+    pub fn rejected(&self) -> Vec<String> {
+        self.rejected.lock().unwrap().clone()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SerendipityLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+
+        let result = fields.into_builder().and_then(|builder| match builder {
+            Some(builder) => self.trace
+                .lock()
+                .unwrap()
+                .log(builder)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            None => Ok(()),
+        });
+        if let Err(reason) = result {
+            self.rejected.lock().unwrap().push(reason);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn record(emit: impl FnOnce()) -> SerendipityLayer {
+        let layer = SerendipityLayer::new(SerendipityTrace::new("researcher1", "backend", "Journavx"));
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        tracing::subscriber::with_default(subscriber, emit);
+        layer
+    }
+
+    #[test]
+    fn test_instrumented_events_become_trace_events() {
+        let layer = record(|| {
+            tracing::info!(stage = "Exploration", agent = "Explorer", serendipity = 0.4, confidence = 0.8, "surveyed routes");
+            tracing::info!("unrelated log line");
+            tracing::info!(
+                stage = "UnexpectedConnection",
+                agent = "PatternRecognizer",
+                language = "id",
+                serendipity = 0.9,
+                confidence = 0.7,
+                run = 3,
+                "wayfinding matches"
+            );
+        });
+
+        let trace = layer.snapshot();
+        assert_eq!(trace.events.len(), 2);
+        assert_eq!(trace.events[0].output, "surveyed routes");
+        assert_eq!(trace.events[1].stage, SerendipityStage::UnexpectedConnection);
+        assert_eq!(trace.events[1].agent, SerendipityAgent::PatternRecognizer);
+        assert_eq!(trace.events[1].metadata["run"], MetadataValue::Number(3.0));
+        assert_eq!(trace.transitions[0].language_shift, Some(("en".to_string(), "id".to_string())));
+        assert!(layer.rejected().is_empty());
+
+        let layer = record(|| tracing::info!(stage = "Validation", agent = "Crystallographer", "lattice confirmed"));
+        assert_eq!(layer.snapshot().events[0].agent, SerendipityAgent::custom("Crystallographer"));
+    }
+
+    #[test]
+    fn test_invalid_events_are_reported() {
+        let layer = record(|| {
+            tracing::warn!(stage = "Daydreaming", agent = "Explorer", "nope");
+            tracing::warn!(stage = "Validation", agent = "Validator", serendipity = 1.5, "too lucky");
+        });
+        assert!(layer.snapshot().events.is_empty());
+        let rejected = layer.rejected();
+        assert_eq!(rejected.len(), 2);
+        assert!(rejected[0].contains("Daydreaming"));
+    }
+
+    #[test]
+    fn test_empty_recording_non_ascii_text_and_missing_fields() {
+        let layer = record(|| tracing::info!("nothing to trace"));
+        assert!(layer.snapshot().events.is_empty());
+        assert!(layer.rejected().is_empty());
+
+        let layer = record(|| {
+            tracing::info!(stage = "Exploration", agent = "Explorer", language = "ja", input = "蝋纈染め", "フラクタル次元 ≈ 1.58");
+            tracing::info!(stage = "Validation", agent = "Validator");
+        });
+        let trace = layer.snapshot();
+        assert_eq!((trace.events[0].input.as_str(), trace.events[0].output.as_str()), ("蝋纈染め", "フラクタル次元 ≈ 1.58"));
+        assert_eq!(trace.events[0].language, "ja");
+
+        // Missing optional fields fall back to the defaults
+        let bare = &trace.events[1];
+        assert_eq!((bare.language.as_str(), bare.input.as_str(), bare.output.as_str()), (DEFAULT_LANGUAGE, "", ""));
+        assert_eq!((bare.serendipity_score, bare.confidence), (0.0, 0.0));
+        assert!(bare.metadata.is_empty());
+        assert!(layer.rejected().is_empty());
+    }
+}
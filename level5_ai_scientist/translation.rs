@@ -0,0 +1,279 @@
+// -*- coding: utf-8 -*-
+//! Pluggable Translation Backends
+//!
+//! Translation summaries otherwise score each language switch with the
+//! aligner's heuristics. A `TranslationBackend` translates text and estimates
+//! how good a given translation is; attached to a `MultilingualMemoryFolder`
+//! (`with_translation_backend`), it supplies the quality of every translation
+//! in the summary. `DictionaryBackend` is a dependency-free word-list
+//! baseline, `MockBackend` returns canned results for tests, and the `http`
+//! feature provides `HttpTranslationBackend` for a user-supplied MT endpoint
+//! (`ureq` crate).
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+use crate::error::SerenQaResult;
+#[cfg(feature = "http")]
+use crate::error::SerenQaError;
+
+/// Machine translation with quality estimation
+pub trait TranslationBackend: Send + Sync {
+    /// Name recorded in translation summaries
+    fn name(&self) -> &str;
+
+    /// Translate `text` from `source_lang` to `target_lang`
+    fn translate(&self, text: &str, source_lang: &str, target_lang: &str) -> SerenQaResult<String>;
+
+    /// Estimated quality in [0, 1] of `translation` as a rendering of `source`
+    fn estimate_quality(
+        &self,
+        source: &str,
+        translation: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> SerenQaResult<f64>;
+}
+
+/// Lowercased words of a text
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Word-for-word translation from per-language-pair word lists. Unknown
+/// words are kept as is; quality is the word overlap (Dice coefficient)
+/// between the dictionary translation and the candidate.
+#[derive(Debug, Clone, Default)]
+pub struct DictionaryBackend {
+    /// (source language, target language) -> word -> translation
+    entries: BTreeMap<(String, String), BTreeMap<String, String>>,
+}
+
+impl DictionaryBackend {
+    /// Backend with no entries
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a translation of `word` from `source_lang` to `target_lang`
+    pub fn entry(mut self, source_lang: &str, target_lang: &str, word: &str, translation: &str) -> Self {
+        self.entries
+            .entry((source_lang.to_string(), target_lang.to_string()))
+            .or_default()
+            .insert(word.to_lowercase(), translation.to_lowercase());
+        self
+    }
+
+    /// Add `(word, translation)` pairs in both directions
+    pub fn pairs(mut self, lang_a: &str, lang_b: &str, pairs: &[(&str, &str)]) -> Self {
+        for (a, b) in pairs {
+            self = self.entry(lang_a, lang_b, a, b).entry(lang_b, lang_a, b, a);
+        }
+        self
+    }
+
+    /// Number of entries for a language pair
+    pub fn entries_for(&self, source_lang: &str, target_lang: &str) -> usize {
+        self.entries.get(&(source_lang.to_string(), target_lang.to_string())).map_or(0, BTreeMap::len)
+    }
+}
+
+impl TranslationBackend for DictionaryBackend {
+    fn name(&self) -> &str {
+        "dictionary"
+    }
+
+    fn translate(&self, text: &str, source_lang: &str, target_lang: &str) -> SerenQaResult<String> {
+        let dictionary = self.entries.get(&(source_lang.to_string(), target_lang.to_string()));
+        let translated: Vec<String> = words(text)
+            .into_iter()
+            .map(|word| dictionary.and_then(|d| d.get(&word)).cloned().unwrap_or(word))
+            .collect();
+        Ok(translated.join(" "))
+    }
+
+    fn estimate_quality(
+        &self,
+        source: &str,
+        translation: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> SerenQaResult<f64> {
+        let expected: HashSet<String> = words(&self.translate(source, source_lang, target_lang)?).into_iter().collect();
+        let actual: HashSet<String> = words(translation).into_iter().collect();
+        if expected.is_empty() && actual.is_empty() {
+            return Ok(1.0);
+        }
+        let shared = expected.intersection(&actual).count();
+        Ok(2.0 * shared as f64 / (expected.len() + actual.len()) as f64)
+    }
+}
+
+/// Backend returning canned translations and qualities, recording each call
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    quality: f64,
+    pair_qualities: BTreeMap<(String, String), f64>,
+    translations: BTreeMap<String, String>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl MockBackend {
+    /// Mock estimating `quality` for every translation
+    pub fn new(quality: f64) -> Self {
+        Self { quality, ..Self::default() }
+    }
+
+    /// Estimate `quality` for translations from `source_lang` to `target_lang`
+    pub fn pair_quality(mut self, source_lang: &str, target_lang: &str, quality: f64) -> Self {
+        self.pair_qualities.insert((source_lang.to_string(), target_lang.to_string()), quality);
+        self
+    }
+
+    /// Translate `text` as `translation` (other text is returned unchanged)
+    pub fn translation(mut self, text: &str, translation: &str) -> Self {
+        self.translations.insert(text.to_string(), translation.to_string());
+        self
+    }
+
+    /// Calls made so far, as `"translate <src>-<tgt>"` or `"quality <src>-<tgt>"`
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn record(&self, call: &str, source_lang: &str, target_lang: &str) {
+        self.calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(format!("{} {}-{}", call, source_lang, target_lang));
+    }
+}
+
+impl TranslationBackend for MockBackend {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn translate(&self, text: &str, source_lang: &str, target_lang: &str) -> SerenQaResult<String> {
+        self.record("translate", source_lang, target_lang);
+        Ok(self.translations.get(text).cloned().unwrap_or_else(|| text.to_string()))
+    }
+
+    fn estimate_quality(
+        &self,
+        _source: &str,
+        _translation: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> SerenQaResult<f64> {
+        self.record("quality", source_lang, target_lang);
+        let pair = (source_lang.to_string(), target_lang.to_string());
+        Ok(self.pair_qualities.get(&pair).copied().unwrap_or(self.quality))
+    }
+}
+
+/// Client for an MT service speaking a small JSON protocol:
+///
+/// ```text
+/// POST {endpoint}/translate  {"text", "source_lang", "target_lang"}                -> {"translation": "..."}
+/// POST {endpoint}/quality    {"source", "translation", "source_lang", "target_lang"} -> {"quality": 0.87}
+/// ```
+///
+/// An API key, if set, is sent as a bearer token.
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct HttpTranslationBackend {
+    endpoint: String,
+    api_key: Option<String>,
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "http")]
+impl HttpTranslationBackend {
+    /// Client for the service at `endpoint` (e.g., "https://mt.example.org/v1")
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_key: None,
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    /// Authenticate with `api_key`
+    pub fn api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    fn post(&self, path: &str, body: serde_json::Value) -> SerenQaResult<serde_json::Value> {
+        let mut request = self.agent.post(&format!("{}/{}", self.endpoint, path));
+        if let Some(api_key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", api_key));
+        }
+        let response = request
+            .send_json(body)
+            .map_err(|e| SerenQaError::Translation(format!("{} {}: {}", self.endpoint, path, e)))?;
+        Ok(response.into_json()?)
+    }
+}
+
+#[cfg(feature = "http")]
+impl TranslationBackend for HttpTranslationBackend {
+    fn name(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn translate(&self, text: &str, source_lang: &str, target_lang: &str) -> SerenQaResult<String> {
+        let body = serde_json::json!({ "text": text, "source_lang": source_lang, "target_lang": target_lang });
+        self.post("translate", body)?["translation"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| SerenQaError::Translation("response has no \"translation\"".to_string()))
+    }
+
+    fn estimate_quality(
+        &self,
+        source: &str,
+        translation: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> SerenQaResult<f64> {
+        let body = serde_json::json!({
+            "source": source,
+            "translation": translation,
+            "source_lang": source_lang,
+            "target_lang": target_lang,
+        });
+        let quality = self.post("quality", body)?["quality"]
+            .as_f64()
+            .ok_or_else(|| SerenQaError::Translation("response has no numeric \"quality\"".to_string()))?;
+        SerenQaError::check_unit_range("translation_quality", quality)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dictionary_translates_and_scores() {
+        let backend = DictionaryBackend::new().pairs("en", "id", &[("batik", "batik"), ("pattern", "pola"), ("route", "rute")]);
+        assert_eq!(backend.entries_for("id", "en"), 3);
+        assert_eq!(backend.translate("Batik pattern route", "en", "id").unwrap(), "batik pola rute");
+        assert_eq!(backend.estimate_quality("Batik pattern route", "Batik pola rute", "en", "id").unwrap(), 1.0);
+
+        let partial = backend.estimate_quality("Batik pattern route", "Pola kain", "en", "id").unwrap();
+        assert!((partial - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_mock_records_calls() {
+        let backend = MockBackend::new(0.5).pair_quality("en", "id", 0.9).translation("hello", "halo");
+        assert_eq!(backend.translate("hello", "en", "id").unwrap(), "halo");
+        assert_eq!(backend.estimate_quality("hello", "halo", "en", "id").unwrap(), 0.9);
+        assert_eq!(backend.estimate_quality("hello", "hola", "en", "es").unwrap(), 0.5);
+        assert_eq!(backend.calls(), vec!["translate en-id", "quality en-id", "quality en-es"]);
+    }
+}
@@ -0,0 +1,117 @@
+// -*- coding: utf-8 -*-
+//! WebAssembly Bindings
+//!
+//! wasm-bindgen wrappers so browser dashboards can check provenance hashes,
+//! fold traces, align texts, and render summaries client-side without a
+//! server round-trip. Traces and folds cross the boundary as JSON strings;
+//! failures surface as JavaScript `Error`s. Build with
+//! `wasm-pack build --target web --features wasm`. Requires the `wasm`
+//! feature (`wasm-bindgen` crate, and chrono's `wasmbind` feature for clocks).
+#![cfg(feature = "wasm")]
+
+use wasm_bindgen::prelude::*;
+use crate::serendipity_trace::{SerendipityTrace, FoldedSerendipityTrace};
+use crate::alignment::MultilingualAligner;
+use crate::error::{SerenQaError, SerenQaResult};
+
+// Bindings delegate to functions on crate errors: a `JsError` can only be
+// built with a JavaScript host, so these are what native tests exercise.
+
+fn parse_trace(trace_json: &str) -> SerenQaResult<SerendipityTrace> {
+    Ok(serde_json::from_str(trace_json)?)
+}
+
+fn fold_trace_json(trace_json: &str) -> SerenQaResult<String> {
+    let folded = parse_trace(trace_json)?.fold_memory()?;
+    Ok(serde_json::to_string(&folded)?)
+}
+
+fn render_fold_json(folded_json: &str) -> SerenQaResult<String> {
+    let folded: FoldedSerendipityTrace = serde_json::from_str(folded_json)?;
+    Ok(folded.to_markdown())
+}
+
+fn align_texts_json(source_text: &str, target_text: &str, source_lang: &str, target_lang: &str) -> SerenQaResult<String> {
+    let result = MultilingualAligner::new().align(source_text, target_text, source_lang, target_lang);
+    Ok(serde_json::to_string(&result)?)
+}
+
+fn js_error(error: SerenQaError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+/// Provenance hash of a trace, in the current encoding
+#[wasm_bindgen(js_name = computeProvenanceHash)]
+pub fn compute_provenance_hash(trace_json: &str) -> Result<String, JsError> {
+    parse_trace(trace_json).map(|trace| trace.compute_provenance_hash()).map_err(js_error)
+}
+
+/// Whether a trace matches a provenance hash of any supported version
+#[wasm_bindgen(js_name = verifyProvenanceHash)]
+pub fn verify_provenance_hash(trace_json: &str, hash: &str) -> Result<bool, JsError> {
+    parse_trace(trace_json).map(|trace| trace.verify_provenance_hash(hash)).map_err(js_error)
+}
+
+/// Fold a trace; returns the folded trace as JSON
+#[wasm_bindgen(js_name = foldTrace)]
+pub fn fold_trace(trace_json: &str) -> Result<String, JsError> {
+    fold_trace_json(trace_json).map_err(js_error)
+}
+
+/// Markdown write-up of a folded trace
+#[wasm_bindgen(js_name = renderFoldMarkdown)]
+pub fn render_fold_markdown(folded_json: &str) -> Result<String, JsError> {
+    render_fold_json(folded_json).map_err(js_error)
+}
+
+/// Markdown write-up of a full trace
+#[wasm_bindgen(js_name = renderTraceMarkdown)]
+pub fn render_trace_markdown(trace_json: &str) -> Result<String, JsError> {
+    parse_trace(trace_json).map(|trace| trace.to_markdown_summary()).map_err(js_error)
+}
+
+/// Align a text with its translation; returns the alignment result as JSON
+#[wasm_bindgen(js_name = alignTexts)]
+pub fn align_texts(source_text: &str, target_text: &str, source_lang: &str, target_lang: &str) -> Result<String, JsError> {
+    align_texts_json(source_text, target_text, source_lang, target_lang).map_err(js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serendipity_trace::{SerendipityStage, SerendipityAgent};
+
+    fn trace_json() -> (String, String) {
+        let mut trace = SerendipityTrace::new("sari", "backend", "Journavx");
+        trace.log_event(SerendipityStage::Exploration, SerendipityAgent::Explorer, "in", "out", "en", 0.4, 0.8).unwrap();
+        trace.log_event(SerendipityStage::UnexpectedConnection, SerendipityAgent::PatternRecognizer, "in", "batik", "id", 0.9, 0.8).unwrap();
+        (trace.to_json().unwrap(), trace.compute_provenance_hash())
+    }
+
+    #[test]
+    fn test_hash_round_trips_through_json() {
+        let (json, hash) = trace_json();
+        assert_eq!(compute_provenance_hash(&json).unwrap(), hash);
+        assert!(verify_provenance_hash(&json, &hash).unwrap());
+        assert!(!verify_provenance_hash(&json.replace("batik", "wayang"), &hash).unwrap());
+    }
+
+    #[test]
+    fn test_fold_and_render() {
+        let (json, _) = trace_json();
+        let folded = fold_trace(&json).unwrap();
+        assert!(render_fold_markdown(&folded).unwrap().contains("| Events | 2 |"));
+        assert!(render_trace_markdown(&json).unwrap().contains("## Events"));
+
+        let aligned: serde_json::Value = serde_json::from_str(&align_texts("Hello world", "Halo dunia", "en", "id").unwrap()).unwrap();
+        assert!(aligned["overall_score"].as_f64().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_malformed_json_is_an_error() {
+        // Checked below the bindings: the `JsError`s they return need a JavaScript host
+        assert!(matches!(parse_trace("{\"trace_id\": "), Err(SerenQaError::Json(_))));
+        assert!(matches!(fold_trace_json("[]"), Err(SerenQaError::Json(_))));
+        assert!(matches!(render_fold_json("not json"), Err(SerenQaError::Json(_))));
+    }
+}